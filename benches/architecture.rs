@@ -0,0 +1,25 @@
+#![feature(test)]
+
+extern crate test;
+
+use dll_syringe::{
+    process::{OwnedProcess, Process},
+    Syringe,
+};
+use test::Bencher;
+
+// `Process::is_x86` calls `IsWow64Process` every time, while a syringe only queries its target once.
+// Stub builds and calls of procedures returning out buffers query the architecture of the target,
+// so RPC-heavy workloads save one system call per call.
+
+#[bench]
+fn process_is_x86(b: &mut Bencher) {
+    let process = OwnedProcess::current();
+    b.iter(|| process.is_x86().unwrap());
+}
+
+#[bench]
+fn syringe_is_target_x86(b: &mut Bencher) {
+    let syringe = Syringe::for_process(OwnedProcess::current());
+    b.iter(|| syringe.is_target_x86().unwrap());
+}
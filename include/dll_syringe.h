@@ -0,0 +1,129 @@
+#ifndef DLL_SYRINGE_H
+#define DLL_SYRINGE_H
+
+/* This file is generated by cbindgen from src/capi.rs, do not edit it manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// An injector for a target process, created by `syringe_open` and freed by `syringe_free`.
+// It is bound to the thread that created it and must only be used and freed on that thread.
+typedef struct DllSyringe DllSyringe;
+
+// The status code returned by the functions of the C API.
+typedef enum SyringeStatus {
+  // The function succeeded.
+  SYRINGE_STATUS_OK = 0,
+  // An argument was null or invalid.
+  SYRINGE_STATUS_INVALID_ARGUMENT = 1,
+  // The process, module or procedure was not found.
+  SYRINGE_STATUS_NOT_FOUND = 2,
+  // The function panicked, which indicates a bug in this crate.
+  SYRINGE_STATUS_PANIC = 3,
+  // A windows api call in the current process failed with an error that does not fall into a more specific kind.
+  SYRINGE_STATUS_IO = 10,
+  // A windows api call inside the target process failed with an error that does not fall into a more specific kind.
+  SYRINGE_STATUS_REMOTE_IO = 11,
+  // An unhandled exception occurred inside the target process.
+  SYRINGE_STATUS_REMOTE_EXCEPTION = 12,
+  // The target process is inaccessible, e.g. because it crashed, was terminated or denied access.
+  SYRINGE_STATUS_PROCESS_INACCESSIBLE = 13,
+  // The target module is inaccessible, e.g. because it was ejected or unloaded.
+  SYRINGE_STATUS_MODULE_INACCESSIBLE = 14,
+  // The target process is not supported.
+  SYRINGE_STATUS_UNSUPPORTED_TARGET = 15,
+  // The payload module was compiled for a different architecture than the target process.
+  SYRINGE_STATUS_ARCHITECTURE_MISMATCH = 16,
+  // The target process is protected, its mitigation policies prevent the operation or the target module is pinned.
+  SYRINGE_STATUS_BLOCKED = 17,
+  // The payload path is invalid, too long or ambiguous.
+  SYRINGE_STATUS_INVALID_PATH = 18,
+  // A module image could not be parsed or has unexpected contents.
+  SYRINGE_STATUS_MALFORMED_IMAGE = 19,
+  // A value could not be serialized or deserialized.
+  SYRINGE_STATUS_SERIALIZATION = 20,
+  // A remote payload procedure returned an error or panicked.
+  SYRINGE_STATUS_REMOTE_PROCEDURE = 21,
+  // A function could not be hooked.
+  SYRINGE_STATUS_HOOK = 22,
+  // The operation did not complete within the given timeout.
+  SYRINGE_STATUS_TIMED_OUT = 23,
+  // A remote allocation would exceed the allocation budget of the syringe.
+  SYRINGE_STATUS_ALLOCATION_BUDGET_EXCEEDED = 24,
+  // The operation is not supported by Wine, which the target process is running under.
+  SYRINGE_STATUS_TARGET_IS_WINE = 25,
+  // Access was denied, e.g. due to missing privileges or a higher integrity level of the target.
+  SYRINGE_STATUS_ACCESS_DENIED = 26,
+  // The system or the target process ran out of memory.
+  SYRINGE_STATUS_OUT_OF_MEMORY = 27,
+  // A resource is temporarily locked or in use.
+  SYRINGE_STATUS_BUSY = 28,
+} SyringeStatus;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Opens the process with the given id and creates a new syringe for it, which is stored in `out_syringe` and has to be
+// freed using `syringe_free`.
+//
+// The syringe is bound to the calling thread, it must only be used and freed on the thread that created it.
+//
+// # Safety
+// `out_syringe` must be valid for writes.
+enum SyringeStatus syringe_open(uint32_t pid,
+                                DllSyringe **out_syringe);
+
+// Frees a syringe created by `syringe_open`. Injected modules stay loaded. Passing null does nothing.
+//
+// # Safety
+// `syringe` must be null or a syringe returned by `syringe_open` that was not freed yet.
+void syringe_free(DllSyringe *syringe);
+
+// Injects the payload at the given null-terminated UTF-16 path into the target process and stores the handle of the loaded
+// module in `out_module`, if it is not null.
+//
+// # Safety
+// `syringe` must be a valid syringe, `payload_path` a valid null-terminated UTF-16 string and `out_module` null or valid for writes.
+enum SyringeStatus syringe_inject(const DllSyringe *syringe,
+                                  const uint16_t *payload_path,
+                                  void **out_module);
+
+// Ejects the module with the given handle, as returned by `syringe_inject`, from the target process.
+//
+// # Safety
+// `syringe` must be a valid syringe.
+enum SyringeStatus syringe_eject(const DllSyringe *syringe,
+                                 void *module);
+
+// Calls the procedure with the given null-terminated name exported by the given module in the target process with the
+// given parameter and stores its return value in `out_result`, if it is not null.
+//
+// The procedure is called as `uintptr_t __stdcall procedure(uintptr_t parameter)` with the pointer width of the target
+// process, i.e. the parameter is truncated to 32 bits for a 32-bit target.
+//
+// # Safety
+// `syringe` must be a valid syringe, `name` a valid null-terminated string and `out_result` null or valid for writes.
+// The procedure must abide by the given signature.
+enum SyringeStatus syringe_call_procedure(const DllSyringe *syringe,
+                                          void *module,
+                                          const char *name,
+                                          uintptr_t parameter,
+                                          uintptr_t *out_result);
+
+// Copies the message of the last error returned by a function of the C API on the calling thread into the given buffer
+// as a null-terminated UTF-16 string, truncating it if necessary, and returns the length of the full message including the
+// terminator. Returns 0 if the last call succeeded. The buffer may be null if `buffer_len` is 0, e.g. to query the length.
+//
+// # Safety
+// `buffer` must be valid for writes of `buffer_len` UTF-16 code units.
+uintptr_t syringe_last_error_message(uint16_t *buffer,
+                                     uintptr_t buffer_len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* DLL_SYRINGE_H */
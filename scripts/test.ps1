@@ -2,7 +2,7 @@
 Set-Location "$PSScriptRoot\.."
 
 # Windows/MSVC x86
-cargo test --target i686-pc-windows-msvc -- --nocapture
+cargo test --target i686-pc-windows-msvc --features test-support -- --nocapture
 
 # Windows/MSVC x64
-cargo test --target x86_64-pc-windows-msvc -- --nocapture
\ No newline at end of file
+cargo test --target x86_64-pc-windows-msvc --features test-support -- --nocapture
\ No newline at end of file
@@ -0,0 +1,190 @@
+use std::{
+    path::Path,
+    sync::Mutex,
+    thread::{self, available_parallelism},
+};
+
+use crate::{
+    error::InjectError,
+    process::{
+        BorrowedProcess, BorrowedProcessModule, ModuleHandle, OwnedProcess, Process, ProcessIter,
+        ProcessModule, ProcessSelector,
+    },
+    CancellationToken, InjectOptions, Syringe,
+};
+
+/// The maximum number of processes injected into concurrently by [`Syringe::inject_into_all`].
+const MAX_BROADCAST_THREADS: usize = 8;
+
+/// The result of injecting into one of the processes selected by [`Syringe::inject_into_all`].
+#[derive(Debug)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub struct BroadcastInjection {
+    pid: u32,
+    process: Option<OwnedProcess>,
+    result: Result<ModuleHandle, InjectError>,
+}
+
+// SAFETY: the module handle is only an address in the target process that is never dereferenced by this process,
+// and process handles can be used from any thread.
+unsafe impl Send for BroadcastInjection {}
+unsafe impl Sync for BroadcastInjection {}
+
+impl BroadcastInjection {
+    /// Returns the id of the process.
+    #[must_use]
+    pub const fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Returns the process or [`None`] if it could not be opened.
+    #[must_use]
+    pub fn process(&self) -> Option<BorrowedProcess<'_>> {
+        self.process.as_ref().map(OwnedProcess::borrowed)
+    }
+
+    /// Returns the injected module or the error that occurred while opening or injecting into the process.
+    pub fn module(&self) -> Result<BorrowedProcessModule<'_>, &InjectError> {
+        let module = *self.result.as_ref()?;
+        // a module can only have been injected into a process that was opened.
+        let process = self.process().unwrap();
+        // SAFETY: the handle was returned by injecting into this process.
+        Ok(unsafe { ProcessModule::new_unchecked(module, process) })
+    }
+
+    /// Returns whether the module was injected successfully.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// Returns a syringe for the process if it was opened, which can be used to eject the module later on.
+    #[must_use]
+    pub fn into_syringe(self) -> Option<Syringe> {
+        self.process.map(Syringe::for_process)
+    }
+}
+
+impl Syringe {
+    /// Injects the module from the given path into every running process selected by the given selector,
+    /// see [`inject_into_all_with_options`](Self::inject_into_all_with_options).
+    pub fn inject_into_all(
+        selector: &ProcessSelector,
+        payload_path: impl AsRef<Path>,
+    ) -> Vec<BroadcastInjection> {
+        Self::inject_into_all_with_options(selector, payload_path, &InjectOptions::new())
+    }
+
+    /// Injects the module from the given path into every running process selected by the given selector using the given options.
+    ///
+    /// The processes are injected into concurrently on a small number of threads, each using its own [`Syringe`].
+    /// A result is returned for every selected process, ordered by process id, including processes that could not be opened.
+    /// The current process is never selected.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dll_syringe::{process::ProcessSelector, Syringe};
+    ///
+    /// let results = Syringe::inject_into_all(&ProcessSelector::name("target_process.exe"), "injection_payload.dll");
+    /// for result in &results {
+    ///     if let Err(err) = result.module() {
+    ///         eprintln!("failed to inject into {}: {err}", result.pid());
+    ///     }
+    /// }
+    /// ```
+    pub fn inject_into_all_with_options(
+        selector: &ProcessSelector,
+        payload_path: impl AsRef<Path>,
+        options: &InjectOptions,
+    ) -> Vec<BroadcastInjection> {
+        Self::inject_into_all_with(selector, payload_path.as_ref(), options, None)
+    }
+
+    /// Injects the module from the given path into every running process selected by the given selector using the given options
+    /// like [`inject_into_all_with_options`](Self::inject_into_all_with_options), but stops once the given token is cancelled.
+    ///
+    /// Injections that already started when the token is cancelled are completed, so no process is left half-injected.
+    /// Results are only returned for the processes that were injected into or could not be opened before that.
+    pub fn inject_into_all_cancellable(
+        selector: &ProcessSelector,
+        payload_path: impl AsRef<Path>,
+        options: &InjectOptions,
+        cancellation: &CancellationToken,
+    ) -> Vec<BroadcastInjection> {
+        Self::inject_into_all_with(selector, payload_path.as_ref(), options, Some(cancellation))
+    }
+
+    fn inject_into_all_with(
+        selector: &ProcessSelector,
+        payload_path: &Path,
+        options: &InjectOptions,
+        cancellation: Option<&CancellationToken>,
+    ) -> Vec<BroadcastInjection> {
+        let current_pid = std::process::id();
+        let mut pids = ProcessIter::new()
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry.pid() != current_pid && selector.matches(entry))
+            .map(|entry| entry.pid())
+            .collect::<Vec<_>>();
+        pids.sort_unstable();
+        pids.dedup();
+
+        let thread_count = available_parallelism()
+            .map_or(1, |count| count.get())
+            .clamp(1, MAX_BROADCAST_THREADS)
+            .min(pids.len());
+        let pending = Mutex::new(pids.into_iter());
+        let results = Mutex::new(Vec::new());
+        thread::scope(|scope| {
+            for _ in 0..thread_count {
+                scope.spawn(|| loop {
+                    if cancellation.is_some_and(CancellationToken::is_cancelled) {
+                        break;
+                    }
+                    let Some(pid) = pending.lock().unwrap().next() else {
+                        break;
+                    };
+                    let result = Self::inject_into_pid(pid, payload_path, options);
+                    results.lock().unwrap().push(result);
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap();
+        results.sort_unstable_by_key(|result| result.pid);
+        results
+    }
+
+    fn inject_into_pid(
+        pid: u32,
+        payload_path: &Path,
+        options: &InjectOptions,
+    ) -> BroadcastInjection {
+        let process = match OwnedProcess::from_pid(pid) {
+            Ok(process) => process,
+            Err(err) => {
+                return BroadcastInjection {
+                    pid,
+                    process: None,
+                    result: Err(err.into()),
+                }
+            }
+        };
+
+        // the syringe is not thread-safe, so each target gets its own syringe on the injecting thread.
+        let result = process
+            .try_clone()
+            .map_err(InjectError::from)
+            .and_then(|target| {
+                Syringe::for_process(target)
+                    .inject_with_options(payload_path, options)
+                    .map(|module| module.handle())
+            });
+        BroadcastInjection {
+            pid,
+            process: Some(process),
+            result,
+        }
+    }
+}
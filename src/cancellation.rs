@@ -0,0 +1,240 @@
+use std::{
+    fmt,
+    num::NonZeroU64,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "process-memory")]
+use std::io;
+
+/// A token that cancels long-running operations like memory scans, dumps, broadcast injections and watcher loops from
+/// another thread, e.g. from the UI thread of a GUI host, and optionally limits the rate at which they read target memory.
+///
+/// Clones of a token share their state, so one clone can be passed to the operation and another one kept to cancel it.
+/// A cancelled operation stops at its next check and returns an error of kind [`std::io::ErrorKind::Interrupted`].
+///
+/// The read rate limit applies to the operations reading target memory in bulk, i.e. the `_cancellable` scans of
+/// [`scanner`](crate::process::memory::scanner) and dumps of [`Process`](crate::process::Process) (requires the
+/// `process-memory` feature).
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::{process::ProcessSelector, CancellationToken, InjectOptions, Syringe};
+/// use std::{thread, time::Duration};
+///
+/// let token = CancellationToken::new();
+/// let canceller = token.clone();
+/// thread::spawn(move || {
+///     thread::sleep(Duration::from_secs(5));
+///     canceller.cancel();
+/// });
+/// let results = Syringe::inject_into_all_cancellable(
+///     &ProcessSelector::name("target_process.exe"),
+///     "injection_payload.dll",
+///     &InjectOptions::new(),
+///     &token,
+/// );
+/// println!("injected into {} processes before cancellation", results.len());
+/// ```
+#[derive(Clone, Default)]
+#[cfg_attr(
+    feature = "doc-cfg",
+    doc(cfg(any(feature = "syringe", feature = "process-memory")))
+)]
+pub struct CancellationToken {
+    inner: Arc<TokenState>,
+}
+
+#[derive(Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    // the maximum number of bytes read per second, or 0 if reads are not throttled.
+    read_rate_limit: AtomicU64,
+    // when the bytes read so far are within the read rate limit. reads after an idle period start from the current time,
+    // so the idle time is not credited towards a later burst.
+    throttle_until: Mutex<Option<Instant>>,
+    // signalled when the token is cancelled, so waits end early.
+    cancel_signal: (Mutex<()>, Condvar),
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .field("read_rate_limit", &self.read_rate_limit())
+            .finish()
+    }
+}
+
+impl CancellationToken {
+    /// The longest time an operation sleeps between checks for cancellation while it is throttled.
+    #[cfg(feature = "process-memory")]
+    const MAX_THROTTLE_SLEEP: Duration = Duration::from_millis(50);
+
+    /// Creates a new token that is not cancelled and does not limit the read rate.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of bytes per second read from target memory by the operations using this token,
+    /// or removes the limit if [`None`] is given.
+    #[must_use]
+    pub fn with_read_rate_limit(self, bytes_per_second: Option<NonZeroU64>) -> Self {
+        self.set_read_rate_limit(bytes_per_second);
+        self
+    }
+
+    /// Changes the maximum number of bytes per second read from target memory by the operations using this token,
+    /// or removes the limit if [`None`] is given. This also affects operations that are already running.
+    pub fn set_read_rate_limit(&self, bytes_per_second: Option<NonZeroU64>) {
+        self.inner.read_rate_limit.store(
+            bytes_per_second.map_or(0, NonZeroU64::get),
+            Ordering::Relaxed,
+        );
+        *self.inner.throttle_until.lock().unwrap() = None;
+    }
+
+    /// Returns the maximum number of bytes per second read from target memory by the operations using this token, if limited.
+    #[must_use]
+    pub fn read_rate_limit(&self) -> Option<NonZeroU64> {
+        NonZeroU64::new(self.inner.read_rate_limit.load(Ordering::Relaxed))
+    }
+
+    /// Cancels the operations using this token or any of its clones. Operations started with a cancelled token fail immediately.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Relaxed);
+        let (lock, signal) = &self.inner.cancel_signal;
+        let _guard = lock.lock().unwrap();
+        signal.notify_all();
+    }
+
+    /// Returns whether this token was cancelled.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Blocks the current thread until this token is cancelled or the given timeout elapses and returns whether it was cancelled.
+    #[must_use]
+    pub fn wait(&self, timeout: Duration) -> bool {
+        let (lock, signal) = &self.inner.cancel_signal;
+        let guard = lock.lock().unwrap();
+        let _guard = signal
+            .wait_timeout_while(guard, timeout, |()| !self.is_cancelled())
+            .unwrap();
+        self.is_cancelled()
+    }
+
+    /// Returns an error of kind [`io::ErrorKind::Interrupted`] if this token was cancelled.
+    #[cfg(feature = "process-memory")]
+    pub(crate) fn check(&self) -> Result<(), io::Error> {
+        if self.is_cancelled() {
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "the operation was cancelled",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Accounts for reading the given number of bytes and blocks until doing so does not exceed the read rate limit.
+    /// Returns an error if this token is or gets cancelled in the meantime.
+    #[cfg(feature = "process-memory")]
+    pub(crate) fn throttle(&self, bytes: usize) -> Result<(), io::Error> {
+        self.check()?;
+        let Some(limit) = self.read_rate_limit() else {
+            return Ok(());
+        };
+
+        let resume = {
+            let mut until = self.inner.throttle_until.lock().unwrap();
+            let resume = throttle_until(*until, Instant::now(), bytes as u64, limit);
+            *until = Some(resume);
+            resume
+        };
+        loop {
+            let remaining = resume.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(());
+            }
+            if self.wait(remaining.min(Self::MAX_THROTTLE_SLEEP)) {
+                return self.check();
+            }
+        }
+    }
+}
+
+/// Returns when reading the given number of bytes after the previous reads, which are within the given rate at `previous`,
+/// is within the rate. Time in which nothing was read is not credited, so a read after an idle period starts at `now`.
+#[cfg(feature = "process-memory")]
+fn throttle_until(
+    previous: Option<Instant>,
+    now: Instant,
+    bytes: u64,
+    bytes_per_second: NonZeroU64,
+) -> Instant {
+    let start = previous.map_or(now, |previous| previous.max(now));
+    start + Duration::from_secs_f64(bytes as f64 / bytes_per_second.get() as f64)
+}
+
+#[cfg(all(test, feature = "process-memory"))]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn cancelling_a_clone_cancels_the_token() {
+        let token = CancellationToken::new();
+        assert!(token.check().is_ok());
+        assert!(!token.wait(Duration::from_millis(1)));
+
+        let clone = token.clone();
+        let waiter = thread::spawn(move || clone.wait(Duration::from_secs(60)));
+        token.cancel();
+        assert!(waiter.join().unwrap());
+        assert!(token.is_cancelled());
+        assert_eq!(
+            token.throttle(1).unwrap_err().kind(),
+            io::ErrorKind::Interrupted
+        );
+    }
+
+    #[test]
+    fn throttle_until_keeps_reads_below_rate() {
+        let rate = NonZeroU64::new(1000).unwrap();
+        let now = Instant::now();
+        assert_eq!(
+            throttle_until(None, now, 500, rate),
+            now + Duration::from_millis(500)
+        );
+        assert_eq!(
+            throttle_until(
+                Some(now + Duration::from_millis(500)),
+                now + Duration::from_millis(200),
+                500,
+                rate
+            ),
+            now + Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn throttle_until_does_not_credit_idle_time() {
+        let rate = NonZeroU64::new(1000).unwrap();
+        let now = Instant::now();
+        assert_eq!(
+            throttle_until(
+                Some(now + Duration::from_millis(500)),
+                now + Duration::from_secs(10),
+                500,
+                rate
+            ),
+            now + Duration::from_millis(10_500)
+        );
+    }
+}
@@ -0,0 +1,334 @@
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_void, CStr},
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    ptr,
+};
+
+use widestring::U16CStr;
+
+use crate::{
+    error::{ErrorKind, OpenProcessError},
+    process::{BorrowedProcessModule, ProcessModule},
+    rpc::Truncate,
+    Syringe,
+};
+
+/// The status code returned by the functions of the C API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(C)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "capi")))]
+pub enum SyringeStatus {
+    /// The function succeeded.
+    Ok = 0,
+    /// An argument was null or invalid.
+    InvalidArgument = 1,
+    /// The process, module or procedure was not found.
+    NotFound = 2,
+    /// The function panicked, which indicates a bug in this crate.
+    Panic = 3,
+    /// A windows api call in the current process failed with an error that does not fall into a more specific kind.
+    Io = 10,
+    /// A windows api call inside the target process failed with an error that does not fall into a more specific kind.
+    RemoteIo = 11,
+    /// An unhandled exception occurred inside the target process.
+    RemoteException = 12,
+    /// The target process is inaccessible, e.g. because it crashed, was terminated or denied access.
+    ProcessInaccessible = 13,
+    /// The target module is inaccessible, e.g. because it was ejected or unloaded.
+    ModuleInaccessible = 14,
+    /// The target process is not supported.
+    UnsupportedTarget = 15,
+    /// The payload module was compiled for a different architecture than the target process.
+    ArchitectureMismatch = 16,
+    /// The target process is protected, its mitigation policies prevent the operation or the target module is pinned.
+    Blocked = 17,
+    /// The payload path is invalid, too long or ambiguous.
+    InvalidPath = 18,
+    /// A module image could not be parsed or has unexpected contents.
+    MalformedImage = 19,
+    /// A value could not be serialized or deserialized.
+    Serialization = 20,
+    /// A remote payload procedure returned an error or panicked.
+    RemoteProcedure = 21,
+    /// A function could not be hooked.
+    Hook = 22,
+    /// The operation did not complete within the given timeout.
+    TimedOut = 23,
+    /// A remote allocation would exceed the allocation budget of the syringe.
+    AllocationBudgetExceeded = 24,
+    /// The operation is not supported by Wine, which the target process is running under.
+    TargetIsWine = 25,
+    /// Access was denied, e.g. due to missing privileges or a higher integrity level of the target.
+    AccessDenied = 26,
+    /// The system or the target process ran out of memory.
+    OutOfMemory = 27,
+    /// A resource is temporarily locked or in use.
+    Busy = 28,
+}
+
+impl From<ErrorKind> for SyringeStatus {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::Io => Self::Io,
+            ErrorKind::RemoteIo => Self::RemoteIo,
+            ErrorKind::RemoteException => Self::RemoteException,
+            ErrorKind::ProcessInaccessible => Self::ProcessInaccessible,
+            ErrorKind::ModuleInaccessible => Self::ModuleInaccessible,
+            ErrorKind::UnsupportedTarget => Self::UnsupportedTarget,
+            ErrorKind::ArchitectureMismatch => Self::ArchitectureMismatch,
+            ErrorKind::Blocked => Self::Blocked,
+            ErrorKind::InvalidPath => Self::InvalidPath,
+            ErrorKind::MalformedImage => Self::MalformedImage,
+            ErrorKind::Serialization => Self::Serialization,
+            ErrorKind::RemoteProcedure => Self::RemoteProcedure,
+            ErrorKind::Hook => Self::Hook,
+            ErrorKind::TimedOut => Self::TimedOut,
+            ErrorKind::AllocationBudgetExceeded => Self::AllocationBudgetExceeded,
+            ErrorKind::TargetIsWine => Self::TargetIsWine,
+            ErrorKind::AccessDenied => Self::AccessDenied,
+            ErrorKind::NotFound => Self::NotFound,
+            ErrorKind::OutOfMemory => Self::OutOfMemory,
+            ErrorKind::Busy => Self::Busy,
+            ErrorKind::InvalidInput => Self::InvalidArgument,
+        }
+    }
+}
+
+thread_local! {
+    // the message of the last error returned by a function of the C API on this thread.
+    static LAST_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Runs the given function, recording the message of a returned error or a panic for [`syringe_last_error_message`].
+fn ffi_call(f: impl FnOnce() -> Result<(), (SyringeStatus, String)>) -> SyringeStatus {
+    let (status, message) = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => (SyringeStatus::Ok, None),
+        Ok(Err((status, message))) => (status, Some(message)),
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| (*s).to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "panic with a non-string payload".to_string());
+            (SyringeStatus::Panic, Some(message))
+        }
+    };
+    LAST_ERROR.set(message);
+    status
+}
+
+fn error(kind: ErrorKind, err: &dyn std::error::Error) -> (SyringeStatus, String) {
+    // C callers only get a single message, so it includes the whole source chain.
+    let mut message = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        message = format!("{message}: {err}");
+        source = err.source();
+    }
+    (kind.into(), message)
+}
+
+fn invalid_argument(name: &str) -> (SyringeStatus, String) {
+    (
+        SyringeStatus::InvalidArgument,
+        format!("argument `{name}` is null or invalid"),
+    )
+}
+
+fn not_found(message: impl Into<String>) -> (SyringeStatus, String) {
+    (SyringeStatus::NotFound, message.into())
+}
+
+/// Returns the module with the given handle in the target process of the given syringe.
+fn module_of(syringe: &Syringe, module: *mut c_void) -> BorrowedProcessModule<'_> {
+    unsafe { ProcessModule::new_unchecked(module.cast(), syringe.process()) }
+}
+
+/// Opens the process with the given id and creates a new syringe for it, which is stored in `out_syringe` and has to be
+/// freed using `syringe_free`.
+///
+/// The syringe is bound to the calling thread, it must only be used and freed on the thread that created it.
+///
+/// # Safety
+/// `out_syringe` must be valid for writes.
+#[no_mangle]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "capi")))]
+pub unsafe extern "C" fn syringe_open(pid: u32, out_syringe: *mut *mut Syringe) -> SyringeStatus {
+    ffi_call(|| {
+        if out_syringe.is_null() {
+            return Err(invalid_argument("out_syringe"));
+        }
+        let syringe = match Syringe::for_process_by_pid(pid) {
+            Ok(syringe) => syringe,
+            Err(err @ OpenProcessError::NotFound { .. }) => return Err(not_found(err.to_string())),
+            Err(err) => return Err(error(err.kind(), &err)),
+        };
+        unsafe { out_syringe.write(Box::into_raw(Box::new(syringe))) };
+        Ok(())
+    })
+}
+
+/// Frees a syringe created by `syringe_open`. Injected modules stay loaded. Passing null does nothing.
+///
+/// # Safety
+/// `syringe` must be null or a syringe returned by `syringe_open` that was not freed yet.
+#[no_mangle]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "capi")))]
+pub unsafe extern "C" fn syringe_free(syringe: *mut Syringe) {
+    if !syringe.is_null() {
+        drop(unsafe { Box::from_raw(syringe) });
+    }
+}
+
+/// Injects the payload at the given null-terminated UTF-16 path into the target process and stores the handle of the loaded
+/// module in `out_module`, if it is not null.
+///
+/// # Safety
+/// `syringe` must be a valid syringe, `payload_path` a valid null-terminated UTF-16 string and `out_module` null or valid for writes.
+#[no_mangle]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "capi")))]
+pub unsafe extern "C" fn syringe_inject(
+    syringe: *const Syringe,
+    payload_path: *const u16,
+    out_module: *mut *mut c_void,
+) -> SyringeStatus {
+    ffi_call(|| {
+        let syringe = unsafe { syringe.as_ref() }.ok_or_else(|| invalid_argument("syringe"))?;
+        if payload_path.is_null() {
+            return Err(invalid_argument("payload_path"));
+        }
+        let payload_path =
+            PathBuf::from(unsafe { U16CStr::from_ptr_str(payload_path) }.to_os_string());
+
+        let module = syringe
+            .inject(payload_path)
+            .map_err(|err| error(err.kind(), &err))?;
+        if !out_module.is_null() {
+            unsafe { out_module.write(module.handle().cast()) };
+        }
+        Ok(())
+    })
+}
+
+/// Ejects the module with the given handle, as returned by `syringe_inject`, from the target process.
+///
+/// # Safety
+/// `syringe` must be a valid syringe.
+#[no_mangle]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "capi")))]
+pub unsafe extern "C" fn syringe_eject(
+    syringe: *const Syringe,
+    module: *mut c_void,
+) -> SyringeStatus {
+    ffi_call(|| {
+        let syringe = unsafe { syringe.as_ref() }.ok_or_else(|| invalid_argument("syringe"))?;
+        if module.is_null() {
+            return Err(invalid_argument("module"));
+        }
+        syringe
+            .eject(module_of(syringe, module))
+            .map_err(|err| error(err.kind(), &err))
+    })
+}
+
+/// Calls the procedure with the given null-terminated name exported by the given module in the target process with the
+/// given parameter and stores its return value in `out_result`, if it is not null.
+///
+/// The procedure is called as `uintptr_t __stdcall procedure(uintptr_t parameter)` with the pointer width of the target
+/// process, i.e. the parameter is truncated to 32 bits for a 32-bit target.
+///
+/// # Safety
+/// `syringe` must be a valid syringe, `name` a valid null-terminated string and `out_result` null or valid for writes.
+/// The procedure must abide by the given signature.
+#[no_mangle]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "capi")))]
+pub unsafe extern "C" fn syringe_call_procedure(
+    syringe: *const Syringe,
+    module: *mut c_void,
+    name: *const c_char,
+    parameter: usize,
+    out_result: *mut usize,
+) -> SyringeStatus {
+    ffi_call(|| {
+        let syringe = unsafe { syringe.as_ref() }.ok_or_else(|| invalid_argument("syringe"))?;
+        if module.is_null() {
+            return Err(invalid_argument("module"));
+        }
+        if name.is_null() {
+            return Err(invalid_argument("name"));
+        }
+        let name = unsafe { CStr::from_ptr(name) }
+            .to_str()
+            .map_err(|_| invalid_argument("name"))?;
+
+        let procedure = unsafe {
+            syringe.get_raw_procedure::<extern "system" fn(Truncate<usize>) -> usize>(
+                module_of(syringe, module),
+                name,
+            )
+        }
+        .map_err(|err| error(err.kind(), &err))?
+        .ok_or_else(|| {
+            not_found(format!(
+                "the module does not export a procedure named `{name}`"
+            ))
+        })?;
+        let result = procedure
+            .call(Truncate(parameter))
+            .map_err(|err| error(err.kind(), &err))?;
+        if !out_result.is_null() {
+            unsafe { out_result.write(result) };
+        }
+        Ok(())
+    })
+}
+
+/// Copies the message of the last error returned by a function of the C API on the calling thread into the given buffer
+/// as a null-terminated UTF-16 string, truncating it if necessary, and returns the length of the full message including the
+/// terminator. Returns 0 if the last call succeeded. The buffer may be null if `buffer_len` is 0, e.g. to query the length.
+///
+/// # Safety
+/// `buffer` must be valid for writes of `buffer_len` UTF-16 code units.
+#[no_mangle]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "capi")))]
+pub unsafe extern "C" fn syringe_last_error_message(buffer: *mut u16, buffer_len: usize) -> usize {
+    LAST_ERROR.with_borrow(|message| {
+        let Some(message) = message else {
+            return 0;
+        };
+        let wide = message.encode_utf16().collect::<Vec<_>>();
+        if !buffer.is_null() && buffer_len > 0 {
+            let copied = wide.len().min(buffer_len - 1);
+            unsafe {
+                ptr::copy_nonoverlapping(wide.as_ptr(), buffer, copied);
+                buffer.add(copied).write(0);
+            }
+        }
+        wide.len() + 1
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_arguments_are_reported() {
+        let status = unsafe { syringe_open(std::process::id(), ptr::null_mut()) };
+        assert_eq!(status, SyringeStatus::InvalidArgument);
+
+        let len = unsafe { syringe_last_error_message(ptr::null_mut(), 0) };
+        let mut buffer = vec![0; len];
+        assert_eq!(
+            unsafe { syringe_last_error_message(buffer.as_mut_ptr(), buffer.len()) },
+            len
+        );
+        let message = String::from_utf16(&buffer[..len - 1]).unwrap();
+        assert!(message.contains("out_syringe"));
+
+        let status = unsafe { syringe_inject(ptr::null(), ptr::null(), ptr::null_mut()) };
+        assert_eq!(status, SyringeStatus::InvalidArgument);
+    }
+}
@@ -0,0 +1,134 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    error::InjectError,
+    process::{JobObject, OwnedProcess, Process, ProcessId},
+    Syringe,
+};
+
+/// An injector that follows a target process and injects a module into every child process it spawns.
+///
+/// This is useful for launchers that spawn the actual target process.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// use dll_syringe::{ChildInjector, process::OwnedProcess};
+///
+/// let launcher = OwnedProcess::find_first_by_name("launcher").unwrap();
+/// let mut injector = ChildInjector::new(launcher, "injection_payload.dll");
+/// injector.run(Duration::from_millis(100)).unwrap();
+/// ```
+#[derive(Debug)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub struct ChildInjector {
+    root: OwnedProcess,
+    payload_path: PathBuf,
+    recursive: bool,
+    job: Option<JobObject>,
+    injected: HashMap<ProcessId, Syringe>,
+}
+
+impl ChildInjector {
+    /// Creates a new injector that injects the module at the given path into the children of the given process.
+    pub fn new(root: OwnedProcess, payload_path: impl AsRef<Path>) -> Self {
+        Self {
+            root,
+            payload_path: payload_path.as_ref().to_path_buf(),
+            recursive: false,
+            job: None,
+            injected: HashMap::new(),
+        }
+    }
+
+    /// Sets whether the children of injected processes should be followed as well.
+    #[must_use]
+    pub fn recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// Sets a job object the root process and every child are assigned to before the module is injected into it.
+    ///
+    /// The root process is assigned on the next [`poll`](Self::poll), so the children it spawns afterwards (and their own
+    /// children) are part of the job from their start. Children that were already running are assigned when they are found.
+    /// With [kill on close](JobObject::set_kill_on_close) the root process and all its descendants in the job are terminated
+    /// once the job is closed, so they are not left behind if the injector exits or crashes.
+    #[must_use]
+    pub fn with_job(mut self, job: JobObject) -> Self {
+        self.job = Some(job);
+        self
+    }
+
+    /// Returns the job object children are assigned to, if any.
+    #[must_use]
+    pub fn job(&self) -> Option<&JobObject> {
+        self.job.as_ref()
+    }
+
+    /// Returns the process whose children are injected.
+    #[must_use]
+    pub fn root(&self) -> &OwnedProcess {
+        &self.root
+    }
+
+    /// Returns the syringes for the processes that were injected and are still running.
+    pub fn syringes(&self) -> impl Iterator<Item = &Syringe> {
+        self.injected.values()
+    }
+
+    /// Injects the module into all child processes that have not been injected yet and returns their ids.
+    ///
+    /// # Note
+    /// If injecting into a child fails, the error is returned and the child is retried on the next call.
+    pub fn poll(&mut self) -> Result<Vec<ProcessId>, InjectError> {
+        self.injected
+            .retain(|_, syringe| syringe.process().is_alive());
+
+        // children inherit the job of their parent, so none can escape it once the root is assigned.
+        if let Some(job) = &self.job {
+            if !job.contains(&self.root)? {
+                job.assign(&self.root)?;
+            }
+        }
+
+        let mut children = self.root.children()?;
+        if self.recursive {
+            for syringe in self.injected.values() {
+                children.extend(syringe.process().children()?);
+            }
+        }
+
+        let mut newly_injected = Vec::new();
+        for child in children {
+            let id = child.id()?;
+            if self.injected.contains_key(&id) {
+                continue;
+            }
+            if let Some(job) = &self.job {
+                if !job.contains(&child)? {
+                    job.assign(&child)?;
+                }
+            }
+            let syringe = Syringe::for_process(child);
+            syringe.inject(&self.payload_path)?;
+            self.injected.insert(id, syringe);
+            newly_injected.push(id);
+        }
+        Ok(newly_injected)
+    }
+
+    /// Repeatedly calls [`poll`](Self::poll) with the given interval until the root process exits.
+    pub fn run(&mut self, poll_interval: Duration) -> Result<(), InjectError> {
+        while self.root.is_alive() {
+            self.poll()?;
+            thread::sleep(poll_interval);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+#[cfg(feature = "assembler")]
+use iced_x86::{code_asm::*, IcedError};
+#[cfg(not(feature = "assembler"))]
+use {crate::stub_templates, std::convert::Infallible};
+
+use std::{io, mem};
+
+use crate::{
+    process::{memory::RemoteAllocation, RemoteThreadResult},
+    utils::trace_event,
+    Syringe,
+};
+
+/// The stub used by [`Syringe::run_remote_thread_with_context`], which unpacks the parameter block
+/// `[function, context, data]` passed as the thread parameter and calls the function with the two pointers.
+#[derive(Debug)]
+pub(crate) struct ContextThreadStub {
+    pub(crate) code: RemoteAllocation,
+}
+
+impl Syringe {
+    /// Starts a new thread in the target process that calls the given function with two independent pointers,
+    /// waits for it to finish and returns how it ended, which includes the value returned by the function.
+    ///
+    /// Unlike [`Process::run_remote_thread`], the arguments do not have to be packed behind a single thread parameter,
+    /// so native functions with a `(context, buffer)` signature can be called directly.
+    /// The function and both pointers have to be valid in the target process.
+    #[allow(clippy::not_unsafe_ptr_arg_deref)] // the pointers are only dereferenced in the target process.
+    pub fn run_remote_thread_with_context<C, D>(
+        &self,
+        remote_fn: unsafe extern "system" fn(*mut C, *mut D) -> u32,
+        context: *mut C,
+        data: *mut D,
+    ) -> Result<RemoteThreadResult, io::Error> {
+        let stub = self.build_context_thread_stub()?;
+        let parameter = self.remote_allocator.alloc_and_copy(&[
+            remote_fn as usize,
+            context as usize,
+            data as usize,
+        ])?;
+
+        self.remote_allocator.run_remote_thread(
+            unsafe {
+                mem::transmute::<*mut u8, extern "system" fn(*mut u8) -> u32>(
+                    stub.code.as_raw_ptr(),
+                )
+            },
+            parameter.as_raw_ptr(),
+            &self.remote_thread_options,
+        )
+    }
+
+    fn build_context_thread_stub(&self) -> Result<&ContextThreadStub, io::Error> {
+        self.context_thread_stub.get_or_try_init(|| {
+            let code = if self.remote_allocator.is_x86()? {
+                ContextThreadStub::build_code_x86()
+            } else {
+                ContextThreadStub::build_code_x64()
+            }
+            .unwrap();
+            trace_event!(trace, bytes = %crate::utils::HexBytes(code.as_slice()), "assembled context thread stub");
+            let code = self.remote_allocator.alloc_and_copy_code(code.as_slice())?;
+            trace_event!(debug, address = ?code.as_ptr(), len = code.len(), "wrote context thread stub");
+
+            Ok(ContextThreadStub { code })
+        })
+    }
+}
+
+impl ContextThreadStub {
+    #[cfg(feature = "assembler")]
+    fn build_code_x86() -> Result<Vec<u8>, IcedError> {
+        const WORD: usize = mem::size_of::<usize>();
+
+        let mut asm = CodeAssembler::new(32)?;
+
+        asm.mov(eax, dword_ptr(esp + 4))?; // CreateRemoteThread lpParameter
+        asm.push(dword_ptr(eax + 2 * WORD))?; // data
+        asm.push(dword_ptr(eax + WORD))?; // context
+        asm.call(dword_ptr(eax))?; // stdcall, so the callee cleans up the arguments
+        asm.ret_1(4)?; // return the result as the exit code, restore stack ptr. (Callee cleanup)
+
+        let code = asm.assemble(0x1234_5678)?;
+        debug_assert_eq!(
+            code,
+            asm.assemble(0x1111_2222)?,
+            "context thread x86 stub is not location independent"
+        );
+
+        Ok(code)
+    }
+
+    #[cfg(feature = "assembler")]
+    fn build_code_x64() -> Result<Vec<u8>, IcedError> {
+        let mut asm = CodeAssembler::new(64)?;
+
+        asm.sub(rsp, 40)?; // Re-align stack to 16 byte boundary +32 shadow space
+        asm.mov(rax, rcx)?; // CreateRemoteThread lpParameter
+        asm.mov(rcx, qword_ptr(rax + 8))?; // context
+        asm.mov(rdx, qword_ptr(rax + 16))?; // data
+        asm.call(qword_ptr(rax))?;
+        asm.add(rsp, 40)?; // Re-align stack to 16 byte boundary + shadow space.
+        asm.ret()?; // return the result as the exit code
+
+        let code = asm.assemble(0x1234_5678)?;
+        debug_assert_eq!(
+            code,
+            asm.assemble(0x1111_2222)?,
+            "context thread x64 stub is not location independent"
+        );
+
+        Ok(code)
+    }
+
+    #[cfg(not(feature = "assembler"))]
+    fn build_code_x86() -> Result<Vec<u8>, Infallible> {
+        Ok(stub_templates::context_thread_x86())
+    }
+
+    #[cfg(not(feature = "assembler"))]
+    fn build_code_x64() -> Result<Vec<u8>, Infallible> {
+        Ok(stub_templates::CONTEXT_THREAD_X64.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{OwnedProcess, Process};
+    use winapi::um::winbase::lstrcmpA;
+
+    #[test]
+    fn calls_function_with_both_pointers() {
+        let syringe = Syringe::for_process(OwnedProcess::current());
+        let compare = unsafe {
+            mem::transmute::<
+                unsafe extern "system" fn(*const i8, *const i8) -> i32,
+                unsafe extern "system" fn(*mut i8, *mut i8) -> u32,
+            >(lstrcmpA)
+        };
+
+        let mut a = *b"abc\0";
+        let mut b = *b"abd\0";
+        let result = syringe.run_remote_thread_with_context(
+            compare,
+            a.as_mut_ptr().cast(),
+            a.as_mut_ptr().cast(),
+        );
+        assert_eq!(result.unwrap(), RemoteThreadResult::Returned(0));
+        let result = syringe.run_remote_thread_with_context(
+            compare,
+            a.as_mut_ptr().cast(),
+            b.as_mut_ptr().cast(),
+        );
+        assert!((result.unwrap().returned().unwrap() as i32) < 0);
+        assert!(syringe
+            .stubs()
+            .unwrap()
+            .iter()
+            .any(|stub| stub.kind() == crate::StubKind::ContextThread));
+    }
+
+    #[test]
+    #[cfg(feature = "assembler")]
+    fn stub_templates_match_the_assembler() {
+        assert_eq!(
+            ContextThreadStub::build_code_x86().unwrap(),
+            crate::stub_templates::context_thread_x86()
+        );
+        assert_eq!(
+            ContextThreadStub::build_code_x64().unwrap(),
+            crate::stub_templates::CONTEXT_THREAD_X64
+        );
+    }
+}
@@ -0,0 +1,389 @@
+use std::{
+    io,
+    mem::MaybeUninit,
+    panic,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use winapi::{
+    shared::{
+        minwindef::{DWORD, FALSE},
+        ntstatus::STATUS_WX86_BREAKPOINT,
+        winerror::ERROR_SEM_TIMEOUT,
+    },
+    um::{
+        debugapi::{
+            ContinueDebugEvent, DebugActiveProcess, DebugActiveProcessStop, WaitForDebugEvent,
+        },
+        handleapi::CloseHandle,
+        minwinbase::{
+            CREATE_PROCESS_DEBUG_EVENT, DEBUG_EVENT, EXCEPTION_BREAKPOINT, EXCEPTION_DEBUG_EVENT,
+            EXIT_PROCESS_DEBUG_EVENT, LOAD_DLL_DEBUG_EVENT,
+        },
+        winbase::{DebugSetProcessKillOnExit, INFINITE},
+        winnt::{DBG_CONTINUE, DBG_EXCEPTION_NOT_HANDLED},
+    },
+};
+
+use crate::{
+    error::InjectError,
+    process::{
+        memory::{ProcessMemorySlice, RemoteImage},
+        peb::is_loader_initialized,
+        BorrowedProcess, BorrowedProcessModule, ModuleHandle, OwnedProcess, Process,
+        ProcessBuilder, ProcessModule,
+    },
+    InjectOptions, Syringe,
+};
+
+// `jmp $`, which keeps the main thread spinning at the entry point until the original bytes are restored.
+const SPIN_CODE: [u8; 2] = [0xEB, 0xFE];
+
+// how often the debugger thread checks whether the injection completed while no debug events arrive.
+const DEBUG_EVENT_POLL_INTERVAL_MS: DWORD = 50;
+
+/// An injector that debugs the target process while injecting a module, so the module is loaded before its entry point runs.
+///
+/// The injector attaches to the target (or spawns it as a debuggee) on a separate debugger thread. If the main thread of the target
+/// has not started running yet, it is parked at the entry point of the executable when the process is reported to the debugger.
+/// Once the initial breakpoint is reached, the payload is injected while the debugger thread keeps handling debug events.
+/// The entry point is only restored and the debugger only detaches after the injection completed,
+/// so no code of the executable itself runs before the payload.
+/// This works for processes that are created by a third-party launcher where spawning them suspended is not an option.
+///
+/// # Note
+/// The payload is loaded after the loader initialized the process, so the initializers of statically linked modules and TLS callbacks
+/// of the executable still run before the payload.
+/// If the target was already running when the injector attached, the module is injected without stopping it.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::{DebugInjector, process::ProcessBuilder};
+///
+/// let injector = DebugInjector::new("injection_payload.dll");
+/// let injection = injector.spawn(&ProcessBuilder::new("target_process.exe")).unwrap();
+/// let syringe = injection.into_syringe();
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub struct DebugInjector {
+    payload_path: PathBuf,
+    options: InjectOptions,
+}
+
+/// The result of an injection performed by a [`DebugInjector`].
+#[derive(Debug)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub struct DebugInjection {
+    syringe: Syringe,
+    module: ModuleHandle,
+    restore_error: Option<io::Error>,
+}
+
+impl DebugInjection {
+    /// Returns the syringe for the target process.
+    #[must_use]
+    pub fn syringe(&self) -> &Syringe {
+        &self.syringe
+    }
+
+    /// Returns the injected module.
+    #[must_use]
+    pub fn module(&self) -> BorrowedProcessModule<'_> {
+        unsafe { ProcessModule::new_unchecked(self.module, self.syringe.process()) }
+    }
+
+    /// Returns the error that occurred while restoring the entry point of the target after the module was injected, if any.
+    /// In that case the main thread of the target keeps spinning at its entry point.
+    #[must_use]
+    pub fn restore_error(&self) -> Option<&io::Error> {
+        self.restore_error.as_ref()
+    }
+
+    /// Returns the syringe for the target process, which can be used to eject the module later on.
+    #[must_use]
+    pub fn into_syringe(self) -> Syringe {
+        self.syringe
+    }
+}
+
+impl DebugInjector {
+    /// Creates a new injector for the module at the given path.
+    pub fn new(payload_path: impl AsRef<Path>) -> Self {
+        Self {
+            payload_path: payload_path.as_ref().to_path_buf(),
+            options: InjectOptions::new(),
+        }
+    }
+
+    /// Sets the options used to inject the module.
+    #[must_use]
+    pub fn with_options(mut self, options: InjectOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Spawns the process described by the given builder as a debuggee and injects the module before its entry point runs.
+    /// The process is started running regardless of [`ProcessBuilder::suspended`].
+    pub fn spawn(&self, builder: &ProcessBuilder) -> Result<DebugInjection, InjectError> {
+        let mut builder = builder.clone();
+        builder.suspended(false).debug(true);
+        // the process is debugged by the thread that spawned it.
+        self.inject(move || Ok(builder.spawn()?.into_process()))
+    }
+
+    /// Attaches to the given process as a debugger and injects the module before its entry point runs.
+    /// If the entry point already ran, the module is injected as soon as the process is attached.
+    pub fn attach(&self, process: OwnedProcess) -> Result<DebugInjection, InjectError> {
+        let pid = process.pid()?.get();
+        self.inject(move || {
+            if unsafe { DebugActiveProcess(pid) } == FALSE {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(process)
+        })
+    }
+
+    fn inject(
+        &self,
+        attach: impl FnOnce() -> Result<OwnedProcess, io::Error> + Send,
+    ) -> Result<DebugInjection, InjectError> {
+        let (stopped_sender, stopped_receiver) = mpsc::channel();
+        let injected = AtomicBool::new(false);
+        thread::scope(|scope| {
+            // debug events are only reported to the thread that attached, which has to keep handling them while the module
+            // is injected, as the target is frozen while an event is pending.
+            let debugger = scope.spawn(|| -> Result<(), io::Error> {
+                let (process, mut debuggee) = match attach()
+                    .and_then(|process| Ok((Debuggee::new(process.pid()?.get())?, process)))
+                {
+                    Ok((debuggee, process)) => (process, debuggee),
+                    Err(err) => {
+                        let _ = stopped_sender.send(Err(err.into()));
+                        return Ok(());
+                    }
+                };
+                let entry_patch = match debuggee.run_until_initial_breakpoint(process.borrowed()) {
+                    Ok(entry_patch) => entry_patch,
+                    Err(err) => {
+                        let _ = stopped_sender.send(Err(err));
+                        return Ok(());
+                    }
+                };
+                let _ = stopped_sender.send(process.try_clone().map_err(Into::into));
+                debuggee.run_while(|| !injected.load(Ordering::Acquire));
+                match entry_patch {
+                    Some(entry_patch) => entry_patch.restore(),
+                    None => Ok(()),
+                }
+            });
+
+            let result = stopped_receiver
+                .recv()
+                .map_err(|_| InjectError::ProcessInaccessible)
+                .and_then(|process| {
+                    let syringe = Syringe::for_process(process?);
+                    let module = syringe
+                        .inject_with_options(&self.payload_path, &self.options)?
+                        .handle();
+                    Ok((syringe, module))
+                });
+            injected.store(true, Ordering::Release);
+
+            // the module was injected at this point, so the handle is returned even if the target keeps spinning.
+            let restore_error = match debugger.join() {
+                Ok(result) => result.err(),
+                Err(payload) => panic::resume_unwind(payload),
+            };
+            let (syringe, module) = result?;
+            Ok(DebugInjection {
+                syringe,
+                module,
+                restore_error,
+            })
+        })
+    }
+}
+
+/// A process debugged by the current thread, which is detached on drop.
+struct Debuggee {
+    pid: u32,
+    has_exited: bool,
+}
+
+impl Debuggee {
+    fn new(pid: u32) -> Result<Self, io::Error> {
+        let debuggee = Self {
+            pid,
+            has_exited: false,
+        };
+        // the target must survive the debugger thread exiting before it detached.
+        if unsafe { DebugSetProcessKillOnExit(FALSE) } == FALSE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(debuggee)
+    }
+
+    /// Handles the debug events of the process until the initial breakpoint, parking the main thread at the entry point on the way
+    /// if it did not start running yet.
+    fn run_until_initial_breakpoint<'a>(
+        &mut self,
+        process: BorrowedProcess<'a>,
+    ) -> Result<Option<EntryPatch<'a>>, InjectError> {
+        let mut entry_patch = None;
+        loop {
+            let event = wait_for_debug_event(INFINITE)?.expect("infinite wait timed out");
+
+            let mut continue_status = DBG_CONTINUE;
+            let mut is_initial_breakpoint = false;
+            let mut result = Ok(());
+            match event.dwDebugEventCode {
+                CREATE_PROCESS_DEBUG_EVENT => {
+                    let info = unsafe { event.u.CreateProcessInfo() };
+                    close_file_handle(info.hFile);
+                    // the process is frozen while the event is pending, so the entry point can be patched safely.
+                    // a process that is already running has executed its entry point, so patching it would be pointless.
+                    result = is_loader_initialized(process).and_then(|is_running| {
+                        if !is_running {
+                            entry_patch = EntryPatch::apply(process, info.lpBaseOfImage as usize)?;
+                        }
+                        Ok(())
+                    });
+                }
+                EXCEPTION_DEBUG_EVENT => {
+                    let info = unsafe { event.u.Exception() };
+                    if info.ExceptionRecord.ExceptionCode == EXCEPTION_BREAKPOINT {
+                        is_initial_breakpoint = true;
+                    } else {
+                        continue_status = DBG_EXCEPTION_NOT_HANDLED;
+                    }
+                }
+                _ => continue_status = self.handle_event(&event),
+            }
+
+            if unsafe { ContinueDebugEvent(event.dwProcessId, event.dwThreadId, continue_status) }
+                == FALSE
+            {
+                return Err(io::Error::last_os_error().into());
+            }
+            result?;
+            if self.has_exited {
+                return Err(InjectError::ProcessInaccessible);
+            }
+            if is_initial_breakpoint {
+                return Ok(entry_patch);
+            }
+        }
+    }
+
+    /// Handles the debug events of the process while the given condition holds or until it exits.
+    fn run_while(&mut self, condition: impl Fn() -> bool) {
+        while condition() && !self.has_exited {
+            let event = match wait_for_debug_event(DEBUG_EVENT_POLL_INTERVAL_MS) {
+                Ok(Some(event)) => event,
+                Ok(None) => continue,
+                Err(_) => return,
+            };
+            let continue_status = self.handle_event(&event);
+            if unsafe { ContinueDebugEvent(event.dwProcessId, event.dwThreadId, continue_status) }
+                == FALSE
+            {
+                return;
+            }
+        }
+    }
+
+    /// Handles a debug event that is not part of the initial attach sequence.
+    fn handle_event(&mut self, event: &DEBUG_EVENT) -> DWORD {
+        match event.dwDebugEventCode {
+            LOAD_DLL_DEBUG_EVENT => close_file_handle(unsafe { event.u.LoadDll() }.hFile),
+            CREATE_PROCESS_DEBUG_EVENT => {
+                close_file_handle(unsafe { event.u.CreateProcessInfo() }.hFile);
+            }
+            // exceptions are left to the handlers of the target, breakpoints are reported by the loader of wow64 processes.
+            EXCEPTION_DEBUG_EVENT => {
+                let code = unsafe { event.u.Exception() }.ExceptionRecord.ExceptionCode;
+                if code != EXCEPTION_BREAKPOINT && code != STATUS_WX86_BREAKPOINT as DWORD {
+                    return DBG_EXCEPTION_NOT_HANDLED;
+                }
+            }
+            EXIT_PROCESS_DEBUG_EVENT => self.has_exited = true,
+            _ => {}
+        }
+        DBG_CONTINUE
+    }
+}
+
+impl Drop for Debuggee {
+    fn drop(&mut self) {
+        unsafe { DebugActiveProcessStop(self.pid) };
+    }
+}
+
+/// Waits for the next debug event of a process debugged by the current thread, returning `None` if the wait timed out.
+fn wait_for_debug_event(timeout_ms: DWORD) -> Result<Option<DEBUG_EVENT>, io::Error> {
+    let mut event = MaybeUninit::<DEBUG_EVENT>::uninit();
+    if unsafe { WaitForDebugEvent(event.as_mut_ptr(), timeout_ms) } == FALSE {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() == Some(ERROR_SEM_TIMEOUT as i32) {
+            return Ok(None);
+        }
+        return Err(err);
+    }
+    Ok(Some(unsafe { event.assume_init() }))
+}
+
+fn close_file_handle(handle: winapi::um::winnt::HANDLE) {
+    if !handle.is_null() {
+        unsafe { CloseHandle(handle) };
+    }
+}
+
+/// The entry point of an executable overwritten with [`SPIN_CODE`], which is restored on drop.
+struct EntryPatch<'a> {
+    memory: ProcessMemorySlice<'a>,
+    original: Option<[u8; SPIN_CODE.len()]>,
+}
+
+impl<'a> EntryPatch<'a> {
+    fn apply(process: BorrowedProcess<'a>, image_base: usize) -> Result<Option<Self>, io::Error> {
+        let image = RemoteImage::from_base(process, image_base)?;
+        let Some(entry_point_rva) = image.entry_point_rva()? else {
+            return Ok(None);
+        };
+
+        let memory = image.memory(entry_point_rva, SPIN_CODE.len());
+        let mut original = [0; SPIN_CODE.len()];
+        memory.read(0, &mut original)?;
+        memory.write(0, &SPIN_CODE)?;
+        memory.flush_instruction_cache()?;
+        Ok(Some(Self {
+            memory,
+            original: Some(original),
+        }))
+    }
+
+    fn restore(mut self) -> Result<(), io::Error> {
+        self.restore_original()
+    }
+
+    fn restore_original(&mut self) -> Result<(), io::Error> {
+        if let Some(original) = self.original.take() {
+            self.memory.write(0, &original)?;
+            self.memory.flush_instruction_cache()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for EntryPatch<'_> {
+    fn drop(&mut self) {
+        // the main thread would spin forever otherwise.
+        let _ = self.restore_original();
+    }
+}
@@ -0,0 +1,389 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use widestring::U16CString;
+
+use crate::{
+    error::{ManagedInjectError, MissingExportError},
+    process::{
+        memory::{PointerWidth, RemoteAllocation},
+        BorrowedProcess, BorrowedProcessModule, Process,
+    },
+    rpc::{RawRpcFunctionPtr, RemoteRawProcedure, Truncate},
+    InjectOptions, ResolveRelativeTo, Syringe,
+};
+
+// a word in the target process, pointers are truncated for x86 targets.
+type Word = Truncate<usize>;
+type ComMethod1 = extern "system" fn(Word) -> u32;
+type ComMethod4 = extern "system" fn(Word, Word, Word, Word) -> u32;
+type ComMethod6 = extern "system" fn(Word, Word, Word, Word, Word, Word) -> u32;
+type ClrCreateInstanceFn = extern "system" fn(Word, Word, Word) -> u32;
+
+// {9280188D-0E8E-4867-B30C-7FA83884E8DE}
+const CLSID_CLR_META_HOST: Guid = Guid(
+    0x9280_188D,
+    0x0E8E,
+    0x4867,
+    [0xB3, 0x0C, 0x7F, 0xA8, 0x38, 0x84, 0xE8, 0xDE],
+);
+// {D332DB9E-B9B3-4125-8207-A14884F53216}
+const IID_ICLR_META_HOST: Guid = Guid(
+    0xD332_DB9E,
+    0xB9B3,
+    0x4125,
+    [0x82, 0x07, 0xA1, 0x48, 0x84, 0xF5, 0x32, 0x16],
+);
+// {BD39D1D2-BA2F-486A-89B0-B4B0CB466891}
+const IID_ICLR_RUNTIME_INFO: Guid = Guid(
+    0xBD39_D1D2,
+    0xBA2F,
+    0x486A,
+    [0x89, 0xB0, 0xB4, 0xB0, 0xCB, 0x46, 0x68, 0x91],
+);
+// {90F1A06E-7712-4762-86B5-7A5EBA6BDB02}
+const CLSID_CLR_RUNTIME_HOST: Guid = Guid(
+    0x90F1_A06E,
+    0x7712,
+    0x4762,
+    [0x86, 0xB5, 0x7A, 0x5E, 0xBA, 0x6B, 0xDB, 0x02],
+);
+// {90F1A06C-7712-4762-86B5-7A5EBA6BDB02}
+const IID_ICLR_RUNTIME_HOST: Guid = Guid(
+    0x90F1_A06C,
+    0x7712,
+    0x4762,
+    [0x86, 0xB5, 0x7A, 0x5E, 0xBA, 0x6B, 0xDB, 0x02],
+);
+
+// vtable indices of the used interface methods.
+const IUNKNOWN_RELEASE: usize = 2;
+const ICLR_META_HOST_GET_RUNTIME: usize = 3;
+const ICLR_RUNTIME_INFO_GET_INTERFACE: usize = 9;
+const ICLR_RUNTIME_HOST_START: usize = 3;
+const ICLR_RUNTIME_HOST_EXECUTE_IN_DEFAULT_APP_DOMAIN: usize = 11;
+
+/// A managed method that can be run in a target process using [`Syringe::inject_managed`].
+///
+/// The method has to have the signature `static int Method(string argument)`.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::{Syringe, ManagedEntryPoint, process::OwnedProcess};
+///
+/// let target_process = OwnedProcess::find_first_by_name("target_process").unwrap();
+/// let syringe = Syringe::for_process(target_process);
+///
+/// let entry_point = ManagedEntryPoint::new("ManagedPayload.dll", "ManagedPayload.EntryPoint", "Run")
+///     .with_argument("hello from the injector");
+/// let return_value = syringe.inject_managed(&entry_point).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "dotnet")))]
+pub struct ManagedEntryPoint {
+    assembly_path: PathBuf,
+    type_name: String,
+    method_name: String,
+    argument: String,
+    runtime_version: String,
+}
+
+impl ManagedEntryPoint {
+    /// The runtime version used by default, which is the version of all .NET Framework 4.x runtimes.
+    pub const DEFAULT_RUNTIME_VERSION: &'static str = "v4.0.30319";
+
+    /// Creates a new entry point for the method with the given name of the type with the given fully qualified name in the given assembly.
+    pub fn new(
+        assembly_path: impl AsRef<Path>,
+        type_name: impl Into<String>,
+        method_name: impl Into<String>,
+    ) -> Self {
+        Self {
+            assembly_path: assembly_path.as_ref().to_path_buf(),
+            type_name: type_name.into(),
+            method_name: method_name.into(),
+            argument: String::new(),
+            runtime_version: Self::DEFAULT_RUNTIME_VERSION.to_string(),
+        }
+    }
+
+    /// Sets the string passed to the method.
+    #[must_use]
+    pub fn with_argument(mut self, argument: impl Into<String>) -> Self {
+        self.argument = argument.into();
+        self
+    }
+
+    /// Sets the version of the runtime to start if no runtime is loaded in the target process yet, e.g. `v2.0.50727`.
+    #[must_use]
+    pub fn with_runtime_version(mut self, runtime_version: impl Into<String>) -> Self {
+        self.runtime_version = runtime_version.into();
+        self
+    }
+
+    /// Returns the path of the assembly containing the method.
+    #[must_use]
+    pub fn assembly_path(&self) -> &Path {
+        &self.assembly_path
+    }
+
+    /// Returns the fully qualified name of the type containing the method.
+    #[must_use]
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// Returns the name of the method.
+    #[must_use]
+    pub fn method_name(&self) -> &str {
+        &self.method_name
+    }
+
+    /// Returns the string passed to the method.
+    #[must_use]
+    pub fn argument(&self) -> &str {
+        &self.argument
+    }
+
+    /// Returns the version of the runtime to start.
+    #[must_use]
+    pub fn runtime_version(&self) -> &str {
+        &self.runtime_version
+    }
+}
+
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "dotnet")))]
+impl Syringe {
+    /// Starts the .NET Framework runtime in the target process using the CLR hosting api and runs the given managed method in its default app domain.
+    /// Returns the value returned by the method.
+    ///
+    /// `mscoree.dll` is loaded into the target process if necessary and the calls into the hosting api are made from the injector,
+    /// so no native bootstrap module is needed.
+    /// If a runtime is already started in the target process, it is reused.
+    ///
+    /// # Limitations
+    /// Only the .NET Framework runtime is supported, .NET Core and .NET 5+ use a different hosting api.
+    pub fn inject_managed(
+        &self,
+        entry_point: &ManagedEntryPoint,
+    ) -> Result<u32, ManagedInjectError> {
+        let mscoree = match self.process().find_module_by_name("mscoree.dll")? {
+            Some(mscoree) => mscoree,
+            // let the target look up its own copy in the system directory.
+            None => self
+                .inject_with_options(
+                    "mscoree.dll",
+                    &InjectOptions::new().with_resolve_relative_to(ResolveRelativeTo::None),
+                )?
+                .module(),
+        };
+
+        let clr_create_instance =
+            unsafe { self.get_raw_procedure::<ClrCreateInstanceFn>(mscoree, "CLRCreateInstance")? }
+                .ok_or(MissingExportError {
+                    module: "mscoree.dll",
+                    name: "CLRCreateInstance",
+                })?;
+
+        let out = self.remote_allocator.alloc_and_copy_buf(&[0u8; 8])?;
+        let clsid = self.remote_guid(&CLSID_CLR_META_HOST)?;
+        let iid = self.remote_guid(&IID_ICLR_META_HOST)?;
+        check_hresult(
+            "CLRCreateInstance",
+            clr_create_instance.call(word(&clsid), word(&iid), word(&out))?,
+        )?;
+        let meta_host = ComObject::new(self, mscoree, read_pointer(&out)?);
+
+        let version = self.remote_wide_string(&entry_point.runtime_version)?;
+        let iid = self.remote_guid(&IID_ICLR_RUNTIME_INFO)?;
+        check_hresult(
+            "ICLRMetaHost::GetRuntime",
+            meta_host
+                .method::<ComMethod4>(ICLR_META_HOST_GET_RUNTIME)?
+                .call(meta_host.this(), word(&version), word(&iid), word(&out))?,
+        )?;
+        let runtime_info = ComObject::new(self, mscoree, read_pointer(&out)?);
+
+        let clsid = self.remote_guid(&CLSID_CLR_RUNTIME_HOST)?;
+        let iid = self.remote_guid(&IID_ICLR_RUNTIME_HOST)?;
+        check_hresult(
+            "ICLRRuntimeInfo::GetInterface",
+            runtime_info
+                .method::<ComMethod4>(ICLR_RUNTIME_INFO_GET_INTERFACE)?
+                .call(runtime_info.this(), word(&clsid), word(&iid), word(&out))?,
+        )?;
+        let runtime_host = ComObject::new(self, mscoree, read_pointer(&out)?);
+
+        // S_FALSE is returned if the runtime is already started.
+        check_hresult(
+            "ICLRRuntimeHost::Start",
+            runtime_host
+                .method::<ComMethod1>(ICLR_RUNTIME_HOST_START)?
+                .call(runtime_host.this())?,
+        )?;
+
+        let assembly_path =
+            U16CString::from_os_str(entry_point.assembly_path.as_os_str())?.into_vec_with_nul();
+        let assembly_path = self.remote_allocator.alloc_and_copy_buf(&assembly_path)?;
+        let type_name = self.remote_wide_string(&entry_point.type_name)?;
+        let method_name = self.remote_wide_string(&entry_point.method_name)?;
+        let argument = self.remote_wide_string(&entry_point.argument)?;
+        let return_value = self.remote_allocator.alloc_and_copy(&0u32)?;
+        check_hresult(
+            "ICLRRuntimeHost::ExecuteInDefaultAppDomain",
+            runtime_host
+                .method::<ComMethod6>(ICLR_RUNTIME_HOST_EXECUTE_IN_DEFAULT_APP_DOMAIN)?
+                .call(
+                    runtime_host.this(),
+                    word(&assembly_path),
+                    word(&type_name),
+                    word(&method_name),
+                    word(&argument),
+                    Truncate(return_value.as_raw_ptr() as usize),
+                )?,
+        )?;
+
+        Ok(return_value.read()?)
+    }
+
+    fn remote_guid(&self, guid: &Guid) -> Result<RemoteAllocation, io::Error> {
+        self.remote_allocator.alloc_and_copy_buf(&guid.to_bytes())
+    }
+
+    fn remote_wide_string(&self, s: &str) -> Result<RemoteAllocation, ManagedInjectError> {
+        let wide = U16CString::from_str(s)?.into_vec_with_nul();
+        Ok(self.remote_allocator.alloc_and_copy_buf(&wide)?)
+    }
+}
+
+/// A COM interface pointer in the target process, which is released on drop.
+struct ComObject<'a> {
+    syringe: &'a Syringe,
+    module: BorrowedProcessModule<'a>,
+    ptr: usize,
+}
+
+impl<'a> ComObject<'a> {
+    fn new(syringe: &'a Syringe, module: BorrowedProcessModule<'a>, ptr: usize) -> Self {
+        Self {
+            syringe,
+            module,
+            ptr,
+        }
+    }
+
+    fn this(&self) -> Word {
+        Truncate(self.ptr)
+    }
+
+    /// Returns the method with the given vtable index.
+    fn method<F: RawRpcFunctionPtr>(
+        &self,
+        index: usize,
+    ) -> Result<RemoteRawProcedure<F>, io::Error> {
+        let process = self.syringe.process();
+        let vtable = read_remote_pointer(process, self.ptr)?;
+        let method =
+            read_remote_pointer(process, vtable + index * PointerWidth::of(process)?.size())?;
+        Ok(RemoteRawProcedure::new(
+            unsafe { F::from_ptr(method as _) },
+            self.syringe.remote_allocator.clone(),
+            self.module.handle(),
+            self.syringe.remote_thread_options.clone(),
+        ))
+    }
+}
+
+impl Drop for ComObject<'_> {
+    fn drop(&mut self) {
+        if let Ok(release) = self.method::<ComMethod1>(IUNKNOWN_RELEASE) {
+            let _ = release.call(self.this());
+        }
+    }
+}
+
+/// A `GUID` as laid out in memory.
+struct Guid(u32, u16, u16, [u8; 8]);
+
+impl Guid {
+    fn to_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        bytes[0..4].copy_from_slice(&self.0.to_le_bytes());
+        bytes[4..6].copy_from_slice(&self.1.to_le_bytes());
+        bytes[6..8].copy_from_slice(&self.2.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.3);
+        bytes
+    }
+}
+
+fn word(allocation: &RemoteAllocation) -> Word {
+    Truncate(allocation.as_raw_ptr() as usize)
+}
+
+fn check_hresult(call: &'static str, hresult: u32) -> Result<(), ManagedInjectError> {
+    // failure codes have the severity bit set.
+    if hresult & 0x8000_0000 == 0 {
+        Ok(())
+    } else {
+        Err(ManagedInjectError::Hosting { call, hresult })
+    }
+}
+
+fn read_pointer(allocation: &RemoteAllocation) -> Result<usize, io::Error> {
+    read_remote_pointer(allocation.process(), allocation.as_raw_ptr() as usize)
+}
+
+fn read_remote_pointer(process: BorrowedProcess<'_>, address: usize) -> Result<usize, io::Error> {
+    Ok(PointerWidth::of(process)?.read_address(process, address)? as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guid_is_laid_out_like_windows() {
+        // {9280188D-0E8E-4867-B30C-7FA83884E8DE}
+        assert_eq!(
+            CLSID_CLR_META_HOST.to_bytes(),
+            [
+                0x8D, 0x18, 0x80, 0x92, 0x8E, 0x0E, 0x67, 0x48, 0xB3, 0x0C, 0x7F, 0xA8, 0x38, 0x84,
+                0xE8, 0xDE
+            ]
+        );
+    }
+
+    #[test]
+    fn check_hresult_accepts_success_codes() {
+        // S_OK and S_FALSE
+        assert!(check_hresult("Test", 0).is_ok());
+        assert!(check_hresult("Test", 1).is_ok());
+        // E_FAIL
+        let err = check_hresult("Test", 0x8000_4005).unwrap_err();
+        assert!(matches!(
+            err,
+            ManagedInjectError::Hosting {
+                call: "Test",
+                hresult: 0x8000_4005
+            }
+        ));
+    }
+
+    #[test]
+    fn entry_point_uses_default_runtime_version() {
+        let entry_point = ManagedEntryPoint::new("Payload.dll", "Payload.EntryPoint", "Run");
+        assert_eq!(
+            entry_point.runtime_version(),
+            ManagedEntryPoint::DEFAULT_RUNTIME_VERSION
+        );
+        assert_eq!(entry_point.argument(), "");
+
+        let entry_point = entry_point
+            .with_argument("argument")
+            .with_runtime_version("v2.0.50727");
+        assert_eq!(entry_point.argument(), "argument");
+        assert_eq!(entry_point.runtime_version(), "v2.0.50727");
+    }
+}
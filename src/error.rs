@@ -3,6 +3,9 @@ use std::{
     io,
 };
 
+#[cfg(feature = "syringe")]
+use std::path::PathBuf;
+
 use num_enum::{IntoPrimitive, TryFromPrimitive, TryFromPrimitiveError};
 use thiserror::Error;
 use winapi::um::{
@@ -19,8 +22,21 @@ use winapi::um::{
     winnt::STATUS_UNWIND_CONSOLIDATE,
 };
 
+use winapi::shared::{
+    ntdef::NTSTATUS,
+    winerror::{
+        ERROR_ACCESS_DENIED, ERROR_BAD_EXE_FORMAT, ERROR_BAD_FORMAT, ERROR_BAD_PATHNAME,
+        ERROR_BUSY, ERROR_CALL_NOT_IMPLEMENTED, ERROR_COMMITMENT_LIMIT, ERROR_ELEVATION_REQUIRED,
+        ERROR_FILE_NOT_FOUND, ERROR_INVALID_IMAGE_HASH, ERROR_INVALID_NAME, ERROR_LOCK_VIOLATION,
+        ERROR_MOD_NOT_FOUND, ERROR_MR_MID_NOT_FOUND, ERROR_NOT_ENOUGH_MEMORY, ERROR_NOT_SUPPORTED,
+        ERROR_NO_SYSTEM_RESOURCES, ERROR_OUTOFMEMORY, ERROR_PARTIAL_COPY, ERROR_PATH_NOT_FOUND,
+        ERROR_PRIVILEGE_NOT_HELD, ERROR_PROCESS_ABORTED, ERROR_PROC_NOT_FOUND,
+        ERROR_SHARING_VIOLATION, ERROR_TIMEOUT, WAIT_TIMEOUT,
+    },
+};
+
 #[cfg(feature = "syringe")]
-use winapi::shared::winerror::ERROR_PARTIAL_COPY;
+use crate::process::ProtectionLevel;
 
 #[derive(Debug, Error)]
 /// Error enum representing either a windows api error or a nul error from an invalid interior nul.
@@ -33,6 +49,334 @@ pub enum IoOrNulError {
     Io(#[from] io::Error),
 }
 
+/// Error enum for errors during [`Process::terminate`](crate::process::Process::terminate).
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum TerminateError {
+    /// Variant representing an io error.
+    #[error("io error: {}", _0)]
+    Io(#[from] io::Error),
+    /// Variant representing a process handle without the `PROCESS_TERMINATE` access right.
+    #[error("process handle lacks the PROCESS_TERMINATE access right")]
+    AccessDenied,
+    /// Variant representing a process that did not exit within the given timeout.
+    #[error("process did not exit in time")]
+    TimedOut,
+}
+
+impl TerminateError {
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(e) => ErrorKind::Io.refine(e),
+            Self::AccessDenied => ErrorKind::ProcessInaccessible,
+            Self::TimedOut => ErrorKind::TimedOut,
+        }
+    }
+}
+
+/// Error enum for errors while opening a target process, e.g. using [`Syringe::for_process_by_pid`](crate::Syringe::for_process_by_pid).
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum OpenProcessError {
+    /// Variant representing a process id that does not belong to a running process.
+    #[error("no process with id {pid}")]
+    NotFound {
+        /// The id of the process.
+        pid: u32,
+    },
+    /// Variant representing a process name that does not match any running process.
+    #[error("no process named {name}")]
+    NoMatchingProcess {
+        /// The name that was searched for.
+        name: String,
+    },
+    /// Variant representing a process that denied access.
+    #[error("access to process {pid} denied{}", if *requires_elevation { " (run the injector as administrator)" } else { "" })]
+    AccessDenied {
+        /// The id of the process.
+        pid: u32,
+        /// Whether the current process is not elevated, so running it as administrator may grant access.
+        /// Protected processes deny access regardless.
+        requires_elevation: bool,
+    },
+    /// Variant representing a process that exited while it was being opened.
+    #[error("process {pid} exited while it was opened")]
+    Exited {
+        /// The id of the process.
+        pid: u32,
+    },
+    /// Variant representing an io error.
+    #[error("io error: {}", _0)]
+    Io(#[from] io::Error),
+}
+
+impl OpenProcessError {
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(e) => ErrorKind::Io.refine(e),
+            Self::NotFound { .. }
+            | Self::NoMatchingProcess { .. }
+            | Self::AccessDenied { .. }
+            | Self::Exited { .. } => ErrorKind::ProcessInaccessible,
+        }
+    }
+}
+
+/// Error representing a remote allocation that would exceed the budget set using [`Syringe::set_allocation_budget`](crate::Syringe::set_allocation_budget).
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[cfg(feature = "syringe")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+#[error("remote allocation of {requested} bytes would exceed the budget of {budget} bytes ({committed} bytes committed)")]
+pub struct RemoteAllocationBudgetExceeded {
+    pub(crate) budget: usize,
+    pub(crate) committed: usize,
+    pub(crate) requested: usize,
+}
+
+#[cfg(feature = "syringe")]
+impl RemoteAllocationBudgetExceeded {
+    /// Returns the maximum number of bytes that may be committed in the target process.
+    #[must_use]
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Returns the number of bytes that were committed in the target process when the allocation was attempted.
+    #[must_use]
+    pub fn committed(&self) -> usize {
+        self.committed
+    }
+
+    /// Returns the number of bytes the allocation would have committed.
+    #[must_use]
+    pub fn requested(&self) -> usize {
+        self.requested
+    }
+
+    /// Wraps this error in an [`io::Error`], so it can be passed through the allocator, which only reports io errors.
+    pub(crate) fn into_io_error(self) -> io::Error {
+        io::Error::new(io::ErrorKind::OutOfMemory, self)
+    }
+
+    /// Returns the budget error wrapped in the given io error, if any.
+    pub(crate) fn from_io_error(err: &io::Error) -> Option<Self> {
+        err.get_ref()?.downcast_ref::<Self>().copied()
+    }
+}
+
+/// Error representing a handle to the target process of a [`Syringe`](crate::Syringe) that lacks access rights required by an operation.
+///
+/// Operations only require the [access rights](https://docs.microsoft.com/en-us/windows/win32/procthread/process-security-and-access-rights) they use,
+/// so a syringe for a handle with limited access can still be used for operations within that access.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[cfg(feature = "syringe")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+#[error("handle to the target process lacks the access rights {:#x}", self.missing())]
+pub struct MissingAccessError {
+    pub(crate) required: u32,
+    pub(crate) granted: u32,
+}
+
+#[cfg(feature = "syringe")]
+impl MissingAccessError {
+    /// Returns the access rights required by the operation.
+    #[must_use]
+    pub fn required(&self) -> u32 {
+        self.required
+    }
+
+    /// Returns the access rights granted to the handle of the target process.
+    #[must_use]
+    pub fn granted(&self) -> u32 {
+        self.granted
+    }
+
+    /// Returns the required access rights that were not granted.
+    #[must_use]
+    pub fn missing(&self) -> u32 {
+        self.required & !self.granted
+    }
+}
+
+/// Error representing a function that is not exported by a system module of the target process, but required by an operation.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[cfg(feature = "syringe")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+#[error("{module} does not export {name}")]
+pub struct MissingExportError {
+    pub(crate) module: &'static str,
+    pub(crate) name: &'static str,
+}
+
+/// Error representing a dependency of a payload module that the target process could not find,
+/// see [`InjectOptions::with_diagnose_missing_dependencies`](crate::InjectOptions::with_diagnose_missing_dependencies).
+#[derive(Debug, Error)]
+#[cfg(feature = "syringe")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub enum MissingDependencyError {
+    /// Variant representing a dependency that was identified as missing.
+    #[error("{name} required by {} could not be found", importer.display())]
+    NotFound {
+        /// The name of the missing module as imported by the importer.
+        name: String,
+        /// The path of the payload or dependency importing the missing module.
+        importer: PathBuf,
+    },
+    /// Variant representing a payload that failed to load due to a missing dependency, which could not be identified
+    /// because reading the imports or the dll search path failed.
+    #[error(
+        "a dependency of the payload could not be found and diagnosing it failed: {}",
+        _0
+    )]
+    DiagnosisFailed(#[source] io::Error),
+}
+
+#[cfg(feature = "syringe")]
+impl MissingExportError {
+    pub(crate) const fn kernel32(name: &'static str) -> Self {
+        Self {
+            module: "kernel32.dll",
+            name,
+        }
+    }
+
+    /// Returns the name of the module that does not export the function.
+    #[must_use]
+    pub fn module(&self) -> &'static str {
+        self.module
+    }
+
+    /// Returns the name of the missing function.
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// Error enum for errors while parsing a [`Pattern`](crate::process::memory::scanner::Pattern).
+#[cfg(feature = "process-memory")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum PatternParseError {
+    /// Variant representing an empty pattern.
+    #[error("pattern is empty")]
+    Empty,
+    /// Variant representing a token that is neither a hex byte nor a wildcard.
+    #[error("invalid pattern token: {}", _0)]
+    InvalidToken(String),
+}
+
+/// Error enum for errors during a call to [`ProcessModule::verify_text_section`].
+///
+/// [`ProcessModule::verify_text_section`]: crate::process::ProcessModule::verify_text_section
+#[cfg(feature = "process-memory")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum VerifyModuleError {
+    /// Variant representing an io error.
+    #[error("io error")]
+    Io(#[source] io::Error),
+    /// Variant representing an inaccessible target process.
+    /// This can occur if it crashed or was terminated.
+    #[error("inaccessible target process")]
+    ProcessInaccessible,
+    /// Variant representing a module file without a `.text` section.
+    #[error("module has no .text section")]
+    MissingTextSection,
+    /// Variant representing a module file with invalid or unsupported contents.
+    #[error("malformed module image")]
+    MalformedImage,
+    /// Variant representing an error while loading an pe file.
+    #[error("failed to load pe file: {}", _0)]
+    Goblin(#[from] goblin::error::Error),
+}
+
+#[cfg(feature = "process-memory")]
+impl VerifyModuleError {
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(e) => ErrorKind::Io.refine(e),
+            Self::ProcessInaccessible => ErrorKind::ProcessInaccessible,
+            Self::MissingTextSection | Self::MalformedImage | Self::Goblin(_) => {
+                ErrorKind::MalformedImage
+            }
+        }
+    }
+}
+
+#[cfg(feature = "process-memory")]
+impl From<io::Error> for VerifyModuleError {
+    fn from(err: io::Error) -> Self {
+        if err.raw_os_error() == Some(ERROR_PARTIAL_COPY as _)
+            || err.kind() == io::ErrorKind::PermissionDenied
+        {
+            Self::ProcessInaccessible
+        } else {
+            Self::Io(err)
+        }
+    }
+}
+
+/// Error enum for errors while installing a hook.
+#[cfg(feature = "process-memory")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum HookError {
+    /// Variant representing an io error.
+    #[error("io error")]
+    Io(#[source] io::Error),
+    /// Variant representing an inaccessible target process.
+    /// This can occur if it crashed or was terminated.
+    #[error("inaccessible target process")]
+    ProcessInaccessible,
+    /// Variant representing a missing import in the hooked module.
+    #[error("import not found")]
+    ImportNotFound,
+    /// Variant representing a hooked function whose first instructions cannot be relocated into a trampoline.
+    #[error("unsupported function prologue")]
+    UnsupportedPrologue,
+    /// Variant representing an error while assembling code.
+    #[cfg(feature = "assembler")]
+    #[error("failed to assemble code: {}", _0)]
+    Iced(#[from] iced_x86::IcedError),
+}
+
+#[cfg(feature = "process-memory")]
+impl HookError {
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(e) => ErrorKind::Io.refine(e),
+            Self::ProcessInaccessible => ErrorKind::ProcessInaccessible,
+            Self::ImportNotFound | Self::UnsupportedPrologue => ErrorKind::Hook,
+            #[cfg(feature = "assembler")]
+            Self::Iced(_) => ErrorKind::Hook,
+        }
+    }
+}
+
+#[cfg(feature = "process-memory")]
+impl From<io::Error> for HookError {
+    fn from(err: io::Error) -> Self {
+        if err.raw_os_error() == Some(ERROR_PARTIAL_COPY as _)
+            || err.kind() == io::ErrorKind::PermissionDenied
+        {
+            Self::ProcessInaccessible
+        } else {
+            Self::Io(err)
+        }
+    }
+}
+
 /// Error enum for errors during a call to [`ProcessModule::get_local_procedure_address`].
 ///
 /// [`ProcessModule::get_local_procedure_address`]: crate::process::ProcessModule::get_local_procedure_address
@@ -155,165 +499,104 @@ impl Display for ExceptionCode {
     }
 }
 
+impl std::error::Error for ExceptionCode {}
+
 #[derive(Debug, Error)]
 /// An error representing either an unhandled exception or an io error.
 pub enum ExceptionOrIoError {
     /// Variant representing an io error.
-    #[error("remote io error: {}", _0)]
-    Io(io::Error),
+    #[error("remote io error")]
+    Io(#[source] io::Error),
     /// Variant representing an unhandled exception.
-    #[error("remote exception: {}", _0)]
-    Exception(ExceptionCode),
+    #[error("remote exception")]
+    Exception(#[source] ExceptionCode),
 }
 
-/// Error enum for errors during [`Syringe::load_inject_help_data_for_process`](crate::Syringe::load_inject_help_data_for_process).
-#[derive(Debug, Error)]
-#[cfg(feature = "syringe")]
-#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
-pub(crate) enum LoadInjectHelpDataError {
-    /// Variant representing an io error.
-    #[error("io error: {}", _0)]
-    Io(io::Error),
-    /// Variant representing an unsupported target process.
-    #[error("unsupported target process")]
-    UnsupportedTarget,
-    /// Variant representing an inaccessible target process.
-    /// This can occur if it crashed or was terminated.
-    #[error("inaccessible target process")]
-    ProcessInaccessible,
-    /// Variant representing an error while loading an pe file.
-    #[cfg(target_arch = "x86_64")]
-    #[cfg(feature = "into-x86-from-x64")]
-    #[error("failed to load pe file: {}", _0)]
-    Goblin(#[from] goblin::error::Error),
-}
-
-#[cfg(feature = "syringe")]
-impl From<io::Error> for LoadInjectHelpDataError {
-    fn from(err: io::Error) -> Self {
-        if err.raw_os_error() == Some(ERROR_PARTIAL_COPY as _)
-            || err.kind() == io::ErrorKind::PermissionDenied
-        {
-            Self::ProcessInaccessible
-        } else {
-            Self::Io(err)
+impl ExceptionOrIoError {
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(e) => ErrorKind::RemoteIo.refine(e),
+            Self::Exception(_) => ErrorKind::RemoteException,
         }
     }
 }
 
-/// Error enum for errors during [`Syringe::inject`](crate::Syringe::inject).
-#[derive(Debug, Error)]
-#[cfg(feature = "syringe")]
-#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
-pub enum InjectError {
-    /// Variant representing an illegal interior nul value in the module path.
-    #[error("module path contains illegal interior nul")]
-    IllegalPath(#[from] widestring::error::ContainsNul<u16>),
-    /// Variant representing an io error.
-    #[error("io error: {}", _0)]
-    Io(io::Error),
-    /// Variant representing an unsupported target process.
-    #[error("unsupported target process")]
-    UnsupportedTarget,
-    /// Variant representing an io error inside the target process.
-    #[error("remote io error: {}", _0)]
-    RemoteIo(io::Error),
-    /// Variant representing an unhandled exception inside the target process.
-    #[error("remote exception: {}", _0)]
-    RemoteException(ExceptionCode),
-    /// Variant representing an inaccessible target process.
-    /// This can occur if it crashed or was terminated.
-    #[error("inaccessible target process")]
-    ProcessInaccessible,
-    /// Variant representing an incompatible payload module compiled for a different target than the target process.
-    #[error("mismatch between target and payload architecture")]
-    ArchitectureMismatch,
-    /// Variant representing an error while loading an pe file.
-    #[cfg(target_arch = "x86_64")]
-    #[cfg(feature = "into-x86-from-x64")]
-    #[error("failed to load pe file: {}", _0)]
-    Goblin(#[from] goblin::error::Error),
-}
+/// A raw [`NTSTATUS`](https://docs.microsoft.com/en-us/openspecs/windows_protocols/ms-erref/87fba13e-bf06-450e-83b1-9241dc81e781) value,
+/// as reported by native apis and used as the exit code of threads and processes that terminated due to an exception.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NtStatus(pub u32);
 
-#[cfg(feature = "syringe")]
-impl From<io::Error> for InjectError {
-    fn from(err: io::Error) -> Self {
-        if err.raw_os_error() == Some(ERROR_PARTIAL_COPY as _)
-            || err.kind() == io::ErrorKind::PermissionDenied
-        {
-            Self::ProcessInaccessible
-        } else {
-            Self::Io(err)
-        }
+impl NtStatus {
+    /// Returns whether this status represents a success.
+    #[must_use]
+    pub fn is_success(self) -> bool {
+        self.severity() == 0
     }
-}
 
-#[cfg(feature = "syringe")]
-impl From<ExceptionCode> for InjectError {
-    fn from(err: ExceptionCode) -> Self {
-        Self::RemoteException(err)
+    /// Returns whether this status represents an informational message.
+    #[must_use]
+    pub fn is_informational(self) -> bool {
+        self.severity() == 1
     }
-}
 
-#[cfg(feature = "syringe")]
-impl From<IoOrNulError> for InjectError {
-    fn from(err: IoOrNulError) -> Self {
-        match err {
-            IoOrNulError::Nul(e) => e.into(),
-            IoOrNulError::Io(e) => e.into(),
-        }
+    /// Returns whether this status represents a warning.
+    #[must_use]
+    pub fn is_warning(self) -> bool {
+        self.severity() == 2
+    }
+
+    /// Returns whether this status represents an error.
+    #[must_use]
+    pub fn is_error(self) -> bool {
+        self.severity() == 3
+    }
+
+    fn severity(self) -> u32 {
+        self.0 >> 30
+    }
+
+    /// Returns the equivalent win32 error code, if there is one.
+    #[must_use]
+    pub fn to_win32_error(self) -> Option<u32> {
+        let code = crate::process::ntdll::status_to_win32_error(self.0 as NTSTATUS);
+        (code != ERROR_MR_MID_NOT_FOUND).then_some(code)
     }
 }
 
-#[cfg(feature = "syringe")]
-impl From<ExceptionOrIoError> for InjectError {
-    fn from(err: ExceptionOrIoError) -> Self {
-        match err {
-            ExceptionOrIoError::Io(e) => Self::RemoteIo(e),
-            ExceptionOrIoError::Exception(e) => Self::RemoteException(e),
-        }
+impl Display for NtStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NTSTATUS {:#010X}", self.0)
     }
 }
 
-#[cfg(feature = "syringe")]
-impl From<LoadInjectHelpDataError> for InjectError {
-    fn from(err: LoadInjectHelpDataError) -> Self {
-        match err {
-            LoadInjectHelpDataError::Io(e) => Self::Io(e),
-            LoadInjectHelpDataError::UnsupportedTarget => Self::UnsupportedTarget,
-            LoadInjectHelpDataError::ProcessInaccessible => Self::ProcessInaccessible,
-            #[cfg(target_arch = "x86_64")]
-            #[cfg(feature = "into-x86-from-x64")]
-            LoadInjectHelpDataError::Goblin(e) => Self::Goblin(e),
-        }
+impl std::error::Error for NtStatus {}
+
+impl From<ExceptionCode> for NtStatus {
+    fn from(code: ExceptionCode) -> Self {
+        Self(code.code())
     }
 }
 
-/// Error enum for errors during [`Syringe::eject`](crate::Syringe::eject).
+/// Error enum for errors during [`Syringe::load_inject_help_data_for_process`](crate::Syringe::load_inject_help_data_for_process).
 #[derive(Debug, Error)]
 #[cfg(feature = "syringe")]
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
-pub enum EjectError {
+pub(crate) enum LoadInjectHelpDataError {
     /// Variant representing an io error.
-    #[error("io error: {}", _0)]
-    Io(io::Error),
+    #[error("io error")]
+    Io(#[source] io::Error),
     /// Variant representing an unsupported target process.
     #[error("unsupported target process")]
     UnsupportedTarget,
-    /// Variant representing an io error inside the target process.
-    #[error("remote io error: {}", _0)]
-    RemoteIo(io::Error),
-    /// Variant representing an unhandled exception inside the target process.
-    #[error("remote exception: {}", _0)]
-    RemoteException(ExceptionCode),
     /// Variant representing an inaccessible target process.
     /// This can occur if it crashed or was terminated.
     #[error("inaccessible target process")]
     ProcessInaccessible,
-    /// Variant representing an inaccessible target module.
-    /// This can occur if the target module was ejected or unloaded.
-    #[error("inaccessible target module")]
-    ModuleInaccessible,
+    /// Variant representing a function required for injection that is not exported by the `kernel32.dll` of the target process.
+    #[error(transparent)]
+    MissingExport(#[from] MissingExportError),
     /// Variant representing an error while loading an pe file.
     #[cfg(target_arch = "x86_64")]
     #[cfg(feature = "into-x86-from-x64")]
@@ -322,21 +605,7 @@ pub enum EjectError {
 }
 
 #[cfg(feature = "syringe")]
-impl From<LoadInjectHelpDataError> for EjectError {
-    fn from(err: LoadInjectHelpDataError) -> Self {
-        match err {
-            LoadInjectHelpDataError::Io(e) => Self::Io(e),
-            LoadInjectHelpDataError::UnsupportedTarget => Self::UnsupportedTarget,
-            LoadInjectHelpDataError::ProcessInaccessible => Self::ProcessInaccessible,
-            #[cfg(target_arch = "x86_64")]
-            #[cfg(feature = "into-x86-from-x64")]
-            LoadInjectHelpDataError::Goblin(e) => Self::Goblin(e),
-        }
-    }
-}
-
-#[cfg(feature = "syringe")]
-impl From<io::Error> for EjectError {
+impl From<io::Error> for LoadInjectHelpDataError {
     fn from(err: io::Error) -> Self {
         if err.raw_os_error() == Some(ERROR_PARTIAL_COPY as _)
             || err.kind() == io::ErrorKind::PermissionDenied
@@ -348,181 +617,429 @@ impl From<io::Error> for EjectError {
     }
 }
 
+/// Defines an error enum for an operation of a [`Syringe`](crate::Syringe) with the variants all of these operations share,
+/// followed by the given ones, and the conversions from the errors of the underlying apis.
 #[cfg(feature = "syringe")]
-impl From<ExceptionCode> for EjectError {
-    fn from(err: ExceptionCode) -> Self {
-        Self::RemoteException(err)
-    }
-}
+macro_rules! syringe_operation_error {
+    ($(#[$attr:meta])* pub enum $name:ident { $($variants:tt)* }) => {
+        $(#[$attr])*
+        pub enum $name {
+            /// Variant representing an io error.
+            #[error("io error")]
+            Io(#[source] io::Error),
+            /// Variant representing an unsupported target process.
+            #[error("unsupported target process")]
+            UnsupportedTarget,
+            /// Variant representing an io error inside the target process.
+            #[error("remote io error")]
+            RemoteIo(#[source] io::Error),
+            /// Variant representing an unhandled exception inside the target process.
+            #[error("remote exception")]
+            RemoteException(#[source] ExceptionCode),
+            /// Variant representing an inaccessible target process.
+            /// This can occur if it crashed or was terminated.
+            #[error("inaccessible target process")]
+            ProcessInaccessible,
+            /// Variant representing a remote allocation that would exceed the allocation budget of the syringe.
+            /// See [`Syringe::set_allocation_budget`](crate::Syringe::set_allocation_budget).
+            // thiserror does not recognize `#[error(transparent)]` in macro output, neither of these errors has a source though.
+            #[error("{0}")]
+            AllocationBudgetExceeded(RemoteAllocationBudgetExceeded),
+            /// Variant representing a function required for injection that is not exported by the `kernel32.dll` of the target process.
+            #[error("{0}")]
+            MissingExport(MissingExportError),
+            /// Variant representing an error while loading an pe file.
+            #[cfg(target_arch = "x86_64")]
+            #[cfg(feature = "into-x86-from-x64")]
+            #[error("failed to load pe file: {}", _0)]
+            Goblin(#[from] goblin::error::Error),
+            $($variants)*
+        }
 
-#[cfg(feature = "syringe")]
-impl From<ExceptionOrIoError> for EjectError {
-    fn from(err: ExceptionOrIoError) -> Self {
-        match err {
-            ExceptionOrIoError::Io(e) => Self::RemoteIo(e),
-            ExceptionOrIoError::Exception(e) => Self::RemoteException(e),
+        impl From<io::Error> for $name {
+            fn from(err: io::Error) -> Self {
+                if let Some(err) = RemoteAllocationBudgetExceeded::from_io_error(&err) {
+                    return Self::AllocationBudgetExceeded(err);
+                }
+                if err.raw_os_error() == Some(ERROR_PARTIAL_COPY as _)
+                    || err.kind() == io::ErrorKind::PermissionDenied
+                {
+                    Self::ProcessInaccessible
+                } else {
+                    Self::Io(err)
+                }
+            }
         }
-    }
+
+        impl From<MissingExportError> for $name {
+            fn from(err: MissingExportError) -> Self {
+                Self::MissingExport(err)
+            }
+        }
+
+        impl From<ExceptionCode> for $name {
+            fn from(err: ExceptionCode) -> Self {
+                Self::RemoteException(err)
+            }
+        }
+
+        impl From<ExceptionOrIoError> for $name {
+            fn from(err: ExceptionOrIoError) -> Self {
+                match err {
+                    ExceptionOrIoError::Io(e) => Self::RemoteIo(e),
+                    ExceptionOrIoError::Exception(e) => Self::RemoteException(e),
+                }
+            }
+        }
+
+        impl From<LoadInjectHelpDataError> for $name {
+            fn from(err: LoadInjectHelpDataError) -> Self {
+                match err {
+                    LoadInjectHelpDataError::Io(e) => Self::Io(e),
+                    LoadInjectHelpDataError::UnsupportedTarget => Self::UnsupportedTarget,
+                    LoadInjectHelpDataError::ProcessInaccessible => Self::ProcessInaccessible,
+                    LoadInjectHelpDataError::MissingExport(e) => Self::MissingExport(e),
+                    #[cfg(target_arch = "x86_64")]
+                    #[cfg(feature = "into-x86-from-x64")]
+                    LoadInjectHelpDataError::Goblin(e) => Self::Goblin(e),
+                }
+            }
+        }
+    };
 }
 
-/// Error enum for errors during procedure loading.
-#[derive(Debug, Error)]
 #[cfg(feature = "syringe")]
-#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
-pub enum LoadProcedureError {
-    /// Variant representing an io error.
-    #[error("io error: {}", _0)]
-    Io(io::Error),
-    /// Variant representing an unsupported target process.
-    #[error("unsupported target process")]
-    UnsupportedTarget,
-    /// Variant representing an io error inside the target process.
-    #[error("remote io error: {}", _0)]
-    RemoteIo(io::Error),
-    /// Variant representing an unhandled exception inside the target process.
-    #[error("remote exception: {}", _0)]
-    RemoteException(ExceptionCode),
-    /// Variant representing an inaccessible target process.
-    /// This can occur if it crashed or was terminated.
-    #[error("inaccessible target process")]
-    ProcessInaccessible,
-    /// Variant representing an inaccessible target module.
-    /// This can occur if the target module was ejected or unloaded.
-    #[error("inaccessible target module")]
-    ModuleInaccessible,
-    /// Variant representing an error while loading an pe file.
-    #[cfg(target_arch = "x86_64")]
-    #[cfg(feature = "into-x86-from-x64")]
-    #[error("failed to load pe file: {}", _0)]
-    Goblin(#[from] goblin::error::Error),
+syringe_operation_error! {
+    /// Error enum for errors during [`Syringe::inject`](crate::Syringe::inject).
+    #[derive(Debug, Error)]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+    #[non_exhaustive]
+    pub enum InjectError {
+        /// Variant representing an illegal interior nul value in the module path.
+        #[error("module path contains illegal interior nul")]
+        IllegalPath(#[from] widestring::error::ContainsNul<u16>),
+        /// Variant representing a handle to the target process that lacks access rights required by the operation.
+        #[error(transparent)]
+        MissingAccess(#[from] MissingAccessError),
+        /// Variant representing an incompatible payload module compiled for a different target than the target process.
+        #[error("mismatch between target and payload architecture")]
+        ArchitectureMismatch,
+        /// Variant representing a protected target process, which can not be injected into from user mode.
+        #[error("target process is protected: {level}")]
+        ProtectedProcess {
+            /// The protection level of the target process.
+            level: ProtectionLevel,
+        },
+        /// Variant representing a target process that prohibits the creation of executable memory (arbitrary code guard).
+        #[error("target process prohibits dynamic code")]
+        DynamicCodeProhibited,
+        /// Variant representing a target process that refused to load the payload module because it is not signed by Microsoft or the Microsoft Store.
+        #[error("target process only loads signed modules")]
+        UnsignedModuleRejected,
+        /// Variant representing a target process whose image load policy refuses the payload module,
+        /// because it is located on a network share or has a low mandatory label.
+        #[error(
+            "target process refuses to load the payload module due to its location or integrity label"
+        )]
+        ImageLoadRejected,
+        /// Variant representing a target process running in an AppContainer that is not allowed to read the payload module.
+        /// See [`grant_app_container_access`](crate::grant_app_container_access).
+        #[error("payload module is not accessible from the app container of the target process")]
+        AppContainerAccessDenied,
+        /// Variant representing a payload path that exceeds `MAX_PATH` and could not be loaded by the target process.
+        /// Long paths are only supported by processes that opt in and on systems with `LongPathsEnabled` set.
+        #[error("payload path is too long for the target process")]
+        PayloadPathTooLong,
+        /// Variant representing a relative payload path that refers to different files depending on the directory it is resolved against.
+        /// See [`InjectOptions::with_resolve_relative_to`](crate::InjectOptions::with_resolve_relative_to).
+        #[error("relative payload path is ambiguous ({} candidates)", candidates.len())]
+        AmbiguousPayloadPath {
+            /// The existing files the payload path could refer to.
+            candidates: Vec<PathBuf>,
+        },
+        /// Variant representing a module the injection was waiting for that was not loaded by the target process within the timeout.
+        /// See [`Syringe::inject_when_module_loaded`](crate::Syringe::inject_when_module_loaded).
+        #[error("timed out waiting for {} to be loaded", module_name.display())]
+        ModuleLoadTimedOut {
+            /// The name of the module that was waited for.
+            module_name: PathBuf,
+        },
+        /// Variant representing a dependency of the payload module that the target process could not find.
+        /// Only reported if enabled using [`InjectOptions::with_diagnose_missing_dependencies`](crate::InjectOptions::with_diagnose_missing_dependencies).
+        #[error(transparent)]
+        MissingDependency(#[from] MissingDependencyError),
+    }
 }
 
 #[cfg(feature = "syringe")]
-impl From<LoadInjectHelpDataError> for LoadProcedureError {
-    fn from(err: LoadInjectHelpDataError) -> Self {
-        match err {
-            LoadInjectHelpDataError::Io(e) => Self::Io(e),
-            LoadInjectHelpDataError::UnsupportedTarget => Self::UnsupportedTarget,
-            LoadInjectHelpDataError::ProcessInaccessible => Self::ProcessInaccessible,
+impl InjectError {
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(e) => ErrorKind::Io.refine(e),
+            Self::UnsupportedTarget => ErrorKind::UnsupportedTarget,
+            Self::RemoteIo(e) => ErrorKind::RemoteIo.refine(e),
+            Self::RemoteException(_) => ErrorKind::RemoteException,
+            Self::ProcessInaccessible | Self::MissingAccess(_) => ErrorKind::ProcessInaccessible,
+            Self::AllocationBudgetExceeded(_) => ErrorKind::AllocationBudgetExceeded,
+            Self::ArchitectureMismatch => ErrorKind::ArchitectureMismatch,
+            Self::ProtectedProcess { .. }
+            | Self::DynamicCodeProhibited
+            | Self::UnsignedModuleRejected
+            | Self::ImageLoadRejected
+            | Self::AppContainerAccessDenied => ErrorKind::Blocked,
+            Self::IllegalPath(_) | Self::PayloadPathTooLong | Self::AmbiguousPayloadPath { .. } => {
+                ErrorKind::InvalidPath
+            }
+            Self::ModuleLoadTimedOut { .. } => ErrorKind::TimedOut,
+            Self::MissingDependency(_) => ErrorKind::NotFound,
+            Self::MissingExport(_) => ErrorKind::MalformedImage,
             #[cfg(target_arch = "x86_64")]
             #[cfg(feature = "into-x86-from-x64")]
-            LoadInjectHelpDataError::Goblin(e) => Self::Goblin(e),
+            Self::Goblin(_) => ErrorKind::MalformedImage,
         }
     }
 }
 
 #[cfg(feature = "syringe")]
-impl From<io::Error> for LoadProcedureError {
-    fn from(err: io::Error) -> Self {
-        if err.raw_os_error() == Some(ERROR_PARTIAL_COPY as _)
-            || err.kind() == io::ErrorKind::PermissionDenied
-        {
-            Self::ProcessInaccessible
-        } else {
-            Self::Io(err)
+impl From<IoOrNulError> for InjectError {
+    fn from(err: IoOrNulError) -> Self {
+        match err {
+            IoOrNulError::Nul(e) => e.into(),
+            IoOrNulError::Io(e) => e.into(),
         }
     }
 }
 
 #[cfg(feature = "syringe")]
-impl From<ExceptionCode> for LoadProcedureError {
-    fn from(err: ExceptionCode) -> Self {
-        Self::RemoteException(err)
+syringe_operation_error! {
+    /// Error enum for errors during [`Syringe::eject`](crate::Syringe::eject).
+    #[derive(Debug, Error)]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+    #[non_exhaustive]
+    pub enum EjectError {
+        /// Variant representing a handle to the target process that lacks access rights required by the operation.
+        #[error(transparent)]
+        MissingAccess(#[from] MissingAccessError),
+        /// Variant representing an inaccessible target module.
+        /// This can occur if the target module was ejected or unloaded.
+        #[error("inaccessible target module")]
+        ModuleInaccessible,
+        /// Variant representing a module that stays loaded after all of its references were released,
+        /// e.g. because it was pinned using `GetModuleHandleExW`.
+        #[error("module is pinned in the target process")]
+        ModulePinned,
+        /// Variant representing a module ejected with [`EjectMode::SelfUnload`](crate::EjectMode::SelfUnload) that does not
+        /// define the export of the `payload_self_unload!` macro.
+        #[error("module does not support unloading itself")]
+        SelfUnloadUnsupported,
     }
 }
 
 #[cfg(feature = "syringe")]
-impl From<ExceptionOrIoError> for LoadProcedureError {
-    fn from(err: ExceptionOrIoError) -> Self {
-        match err {
-            ExceptionOrIoError::Io(e) => Self::RemoteIo(e),
-            ExceptionOrIoError::Exception(e) => Self::RemoteException(e),
+impl EjectError {
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(e) => ErrorKind::Io.refine(e),
+            Self::UnsupportedTarget => ErrorKind::UnsupportedTarget,
+            Self::RemoteIo(e) => ErrorKind::RemoteIo.refine(e),
+            Self::RemoteException(_) => ErrorKind::RemoteException,
+            Self::ProcessInaccessible | Self::MissingAccess(_) => ErrorKind::ProcessInaccessible,
+            Self::AllocationBudgetExceeded(_) => ErrorKind::AllocationBudgetExceeded,
+            Self::ModuleInaccessible => ErrorKind::ModuleInaccessible,
+            Self::ModulePinned => ErrorKind::Blocked,
+            Self::MissingExport(_) | Self::SelfUnloadUnsupported => ErrorKind::MalformedImage,
+            #[cfg(target_arch = "x86_64")]
+            #[cfg(feature = "into-x86-from-x64")]
+            Self::Goblin(_) => ErrorKind::MalformedImage,
         }
     }
 }
 
-/// Error enum encompassing all errors during [`Syringe`](crate::Syringe) operations.
-#[derive(Debug, Error)]
 #[cfg(feature = "syringe")]
-#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
-pub enum SyringeError {
-    /// Variant representing an illegal interior nul value in the module path.
-    #[error("module path contains illegal interior nul")]
-    IllegalPath(#[from] widestring::error::ContainsNul<u16>),
-    /// Variant representing an io error.
-    #[error("io error: {}", _0)]
-    Io(io::Error),
-    /// Variant representing an unsupported target process.
-    #[error("unsupported target process")]
-    UnsupportedTarget,
-    /// Variant representing an io error inside the target process.
-    #[error("remote io error: {}", _0)]
-    RemoteIo(io::Error),
-    /// Variant representing an unhandled exception inside the target process.
-    #[error("remote exception: {}", _0)]
-    RemoteException(ExceptionCode),
-    /// Variant representing an inaccessible target process.
-    /// This can occur if it crashed or was terminated.
-    #[error("inaccessible target process")]
-    ProcessInaccessible,
-    /// Variant representing an incompatible payload module compiled for a different target than the target process.
-    #[error("mismatch between target and payload architecture")]
-    ArchitectureMismatch,
-    /// Variant representing an inaccessible target module.
-    /// This can occur if the target module was ejected or unloaded.
-    #[error("inaccessible target module")]
-    ModuleInaccessible,
-    /// Variant representing an error while serializing or deserializing.
-    #[cfg(feature = "rpc-payload")]
-    #[error("serde error: {}", _0)]
-    Serde(Box<bincode::ErrorKind>),
-    /// Variant representing an error or panic inside a remote payload procedure.
-    #[cfg(feature = "rpc-payload")]
-    #[error("remote payload error: {}", _0)]
-    RemotePayloadProcedure(String),
-    /// Variant representing an error while loading an pe file.
-    #[cfg(target_arch = "x86_64")]
-    #[cfg(feature = "into-x86-from-x64")]
-    #[error("failed to load pe file: {}", _0)]
-    Goblin(#[from] goblin::error::Error),
+syringe_operation_error! {
+    /// Error enum for errors during procedure loading.
+    #[derive(Debug, Error)]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+    #[non_exhaustive]
+    pub enum LoadProcedureError {
+        /// Variant representing an inaccessible target module.
+        /// This can occur if the target module was ejected or unloaded.
+        #[error("inaccessible target module")]
+        ModuleInaccessible,
+        /// Variant representing a signature that can not be used to call the procedure in the target process,
+        /// see [`RemoteRawProcedure::validate_signature`](crate::rpc::RemoteRawProcedure::validate_signature).
+        #[cfg(feature = "rpc-raw")]
+        #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-raw")))]
+        #[error("invalid signature")]
+        InvalidSignature(#[source] crate::rpc::SignatureError),
+    }
 }
 
 #[cfg(feature = "syringe")]
-impl From<io::Error> for SyringeError {
-    fn from(err: io::Error) -> Self {
-        if err.raw_os_error() == Some(ERROR_PARTIAL_COPY as _)
-            || err.kind() == io::ErrorKind::PermissionDenied
-        {
-            Self::ProcessInaccessible
-        } else {
-            Self::Io(err)
+impl LoadProcedureError {
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(e) => ErrorKind::Io.refine(e),
+            Self::UnsupportedTarget => ErrorKind::UnsupportedTarget,
+            Self::RemoteIo(e) => ErrorKind::RemoteIo.refine(e),
+            Self::RemoteException(_) => ErrorKind::RemoteException,
+            Self::ProcessInaccessible => ErrorKind::ProcessInaccessible,
+            Self::AllocationBudgetExceeded(_) => ErrorKind::AllocationBudgetExceeded,
+            Self::ModuleInaccessible => ErrorKind::ModuleInaccessible,
+            Self::MissingExport(_) => ErrorKind::MalformedImage,
+            #[cfg(feature = "rpc-raw")]
+            Self::InvalidSignature(_) => ErrorKind::InvalidInput,
+            #[cfg(target_arch = "x86_64")]
+            #[cfg(feature = "into-x86-from-x64")]
+            Self::Goblin(_) => ErrorKind::MalformedImage,
         }
     }
 }
 
 #[cfg(feature = "syringe")]
-impl From<ExceptionCode> for SyringeError {
-    fn from(err: ExceptionCode) -> Self {
-        Self::RemoteException(err)
+syringe_operation_error! {
+    /// Error enum encompassing all errors during [`Syringe`](crate::Syringe) operations.
+    #[derive(Debug, Error)]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+    #[non_exhaustive]
+    pub enum SyringeError {
+        /// Variant representing an illegal interior nul value in the module path.
+        #[error("module path contains illegal interior nul")]
+        IllegalPath(#[from] widestring::error::ContainsNul<u16>),
+        /// Variant representing a handle to the target process that lacks access rights required by the operation.
+        #[error(transparent)]
+        MissingAccess(#[from] MissingAccessError),
+        /// Variant representing an incompatible payload module compiled for a different target than the target process.
+        #[error("mismatch between target and payload architecture")]
+        ArchitectureMismatch,
+        /// Variant representing a protected target process, which can not be injected into from user mode.
+        #[error("target process is protected: {level}")]
+        ProtectedProcess {
+            /// The protection level of the target process.
+            level: ProtectionLevel,
+        },
+        /// Variant representing a target process that prohibits the creation of executable memory (arbitrary code guard).
+        #[error("target process prohibits dynamic code")]
+        DynamicCodeProhibited,
+        /// Variant representing a target process that refused to load the payload module because it is not signed by Microsoft or the Microsoft Store.
+        #[error("target process only loads signed modules")]
+        UnsignedModuleRejected,
+        /// Variant representing a target process whose image load policy refuses the payload module,
+        /// because it is located on a network share or has a low mandatory label.
+        #[error(
+            "target process refuses to load the payload module due to its location or integrity label"
+        )]
+        ImageLoadRejected,
+        /// Variant representing a target process running in an AppContainer that is not allowed to read the payload module.
+        /// See [`grant_app_container_access`](crate::grant_app_container_access).
+        #[error("payload module is not accessible from the app container of the target process")]
+        AppContainerAccessDenied,
+        /// Variant representing a payload path that exceeds `MAX_PATH` and could not be loaded by the target process.
+        /// Long paths are only supported by processes that opt in and on systems with `LongPathsEnabled` set.
+        #[error("payload path is too long for the target process")]
+        PayloadPathTooLong,
+        /// Variant representing a relative payload path that refers to different files depending on the directory it is resolved against.
+        /// See [`InjectOptions::with_resolve_relative_to`](crate::InjectOptions::with_resolve_relative_to).
+        #[error("relative payload path is ambiguous ({} candidates)", candidates.len())]
+        AmbiguousPayloadPath {
+            /// The existing files the payload path could refer to.
+            candidates: Vec<PathBuf>,
+        },
+        /// Variant representing a module the injection was waiting for that was not loaded by the target process within the timeout.
+        /// See [`Syringe::inject_when_module_loaded`](crate::Syringe::inject_when_module_loaded).
+        #[error("timed out waiting for {} to be loaded", module_name.display())]
+        ModuleLoadTimedOut {
+            /// The name of the module that was waited for.
+            module_name: PathBuf,
+        },
+        /// Variant representing an inaccessible target module.
+        /// This can occur if the target module was ejected or unloaded.
+        #[error("inaccessible target module")]
+        ModuleInaccessible,
+        /// Variant representing a module that stays loaded after all of its references were released.
+        #[error("module is pinned in the target process")]
+        ModulePinned,
+        /// Variant representing a module that does not support unloading itself.
+        #[error("module does not support unloading itself")]
+        SelfUnloadUnsupported,
+        /// Variant representing an error while serializing or deserializing.
+        #[cfg(feature = "rpc-payload")]
+        #[error("serde error: {}", _0)]
+        Serde(Box<bincode::ErrorKind>),
+        /// Variant representing an error or panic inside a remote payload procedure.
+        #[cfg(feature = "rpc-payload")]
+        #[error("remote payload error: {}", _0)]
+        RemotePayloadProcedure(String),
+        /// Variant representing a panic inside a remote payload procedure.
+        #[cfg(feature = "rpc-payload")]
+        #[error(
+            "remote payload panicked{}: {message}",
+            .location.as_ref().map_or(String::new(), |location| format!(" at {location}"))
+        )]
+        RemotePayloadProcedurePanicked {
+            /// The panic message.
+            message: String,
+            /// The source location of the panic, if it could be determined.
+            location: Option<String>,
+        },
+        /// Variant representing a dependency of the payload module that the target process could not find.
+        /// Only reported if enabled using [`InjectOptions::with_diagnose_missing_dependencies`](crate::InjectOptions::with_diagnose_missing_dependencies).
+        #[error(transparent)]
+        MissingDependency(#[from] MissingDependencyError),
     }
 }
 
 #[cfg(feature = "syringe")]
-impl From<IoOrNulError> for SyringeError {
-    fn from(err: IoOrNulError) -> Self {
-        match err {
-            IoOrNulError::Nul(e) => e.into(),
-            IoOrNulError::Io(e) => e.into(),
+impl SyringeError {
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(e) => ErrorKind::Io.refine(e),
+            Self::UnsupportedTarget => ErrorKind::UnsupportedTarget,
+            Self::RemoteIo(e) => ErrorKind::RemoteIo.refine(e),
+            Self::RemoteException(_) => ErrorKind::RemoteException,
+            Self::ProcessInaccessible | Self::MissingAccess(_) => ErrorKind::ProcessInaccessible,
+            Self::AllocationBudgetExceeded(_) => ErrorKind::AllocationBudgetExceeded,
+            Self::ArchitectureMismatch => ErrorKind::ArchitectureMismatch,
+            Self::ProtectedProcess { .. }
+            | Self::DynamicCodeProhibited
+            | Self::UnsignedModuleRejected
+            | Self::ImageLoadRejected
+            | Self::AppContainerAccessDenied
+            | Self::ModulePinned => ErrorKind::Blocked,
+            Self::IllegalPath(_) | Self::PayloadPathTooLong | Self::AmbiguousPayloadPath { .. } => {
+                ErrorKind::InvalidPath
+            }
+            Self::ModuleLoadTimedOut { .. } => ErrorKind::TimedOut,
+            Self::ModuleInaccessible => ErrorKind::ModuleInaccessible,
+            Self::MissingDependency(_) => ErrorKind::NotFound,
+            Self::MissingExport(_) | Self::SelfUnloadUnsupported => ErrorKind::MalformedImage,
+            #[cfg(feature = "rpc-payload")]
+            Self::Serde(_) => ErrorKind::Serialization,
+            #[cfg(feature = "rpc-payload")]
+            Self::RemotePayloadProcedure(_) | Self::RemotePayloadProcedurePanicked { .. } => {
+                ErrorKind::RemoteProcedure
+            }
+            #[cfg(target_arch = "x86_64")]
+            #[cfg(feature = "into-x86-from-x64")]
+            Self::Goblin(_) => ErrorKind::MalformedImage,
         }
     }
 }
 
 #[cfg(feature = "syringe")]
-impl From<ExceptionOrIoError> for SyringeError {
-    fn from(err: ExceptionOrIoError) -> Self {
+impl From<IoOrNulError> for SyringeError {
+    fn from(err: IoOrNulError) -> Self {
         match err {
-            ExceptionOrIoError::Io(e) => Self::RemoteIo(e),
-            ExceptionOrIoError::Exception(e) => Self::RemoteException(e),
+            IoOrNulError::Nul(e) => e.into(),
+            IoOrNulError::Io(e) => e.into(),
         }
     }
 }
@@ -537,7 +1054,23 @@ impl From<InjectError> for SyringeError {
             InjectError::RemoteIo(e) => Self::RemoteIo(e),
             InjectError::RemoteException(e) => Self::RemoteException(e),
             InjectError::ProcessInaccessible => Self::ProcessInaccessible,
+            InjectError::MissingAccess(e) => Self::MissingAccess(e),
+            InjectError::AllocationBudgetExceeded(e) => Self::AllocationBudgetExceeded(e),
             InjectError::ArchitectureMismatch => Self::ArchitectureMismatch,
+            InjectError::ProtectedProcess { level } => Self::ProtectedProcess { level },
+            InjectError::DynamicCodeProhibited => Self::DynamicCodeProhibited,
+            InjectError::UnsignedModuleRejected => Self::UnsignedModuleRejected,
+            InjectError::ImageLoadRejected => Self::ImageLoadRejected,
+            InjectError::AppContainerAccessDenied => Self::AppContainerAccessDenied,
+            InjectError::PayloadPathTooLong => Self::PayloadPathTooLong,
+            InjectError::AmbiguousPayloadPath { candidates } => {
+                Self::AmbiguousPayloadPath { candidates }
+            }
+            InjectError::ModuleLoadTimedOut { module_name } => {
+                Self::ModuleLoadTimedOut { module_name }
+            }
+            InjectError::MissingDependency(e) => Self::MissingDependency(e),
+            InjectError::MissingExport(e) => Self::MissingExport(e),
             #[cfg(target_arch = "x86_64")]
             #[cfg(feature = "into-x86-from-x64")]
             InjectError::Goblin(e) => Self::Goblin(e),
@@ -554,7 +1087,12 @@ impl From<EjectError> for SyringeError {
             EjectError::RemoteIo(e) => Self::RemoteIo(e),
             EjectError::RemoteException(e) => Self::RemoteException(e),
             EjectError::ProcessInaccessible => Self::ProcessInaccessible,
+            EjectError::MissingAccess(e) => Self::MissingAccess(e),
+            EjectError::AllocationBudgetExceeded(e) => Self::AllocationBudgetExceeded(e),
             EjectError::ModuleInaccessible => Self::ModuleInaccessible,
+            EjectError::ModulePinned => Self::ModulePinned,
+            EjectError::SelfUnloadUnsupported => Self::SelfUnloadUnsupported,
+            EjectError::MissingExport(e) => Self::MissingExport(e),
             #[cfg(target_arch = "x86_64")]
             #[cfg(feature = "into-x86-from-x64")]
             EjectError::Goblin(e) => Self::Goblin(e),
@@ -571,7 +1109,13 @@ impl From<LoadProcedureError> for SyringeError {
             LoadProcedureError::RemoteIo(e) => Self::RemoteIo(e),
             LoadProcedureError::RemoteException(e) => Self::RemoteException(e),
             LoadProcedureError::ProcessInaccessible => Self::ProcessInaccessible,
+            LoadProcedureError::AllocationBudgetExceeded(e) => Self::AllocationBudgetExceeded(e),
             LoadProcedureError::ModuleInaccessible => Self::ModuleInaccessible,
+            LoadProcedureError::MissingExport(e) => Self::MissingExport(e),
+            #[cfg(feature = "rpc-raw")]
+            LoadProcedureError::InvalidSignature(e) => {
+                Self::Io(io::Error::new(io::ErrorKind::InvalidInput, e))
+            }
             #[cfg(target_arch = "x86_64")]
             #[cfg(feature = "into-x86-from-x64")]
             LoadProcedureError::Goblin(e) => Self::Goblin(e),
@@ -587,7 +1131,14 @@ impl From<crate::rpc::RawRpcError> for SyringeError {
             crate::rpc::RawRpcError::Io(err) => Self::Io(err),
             crate::rpc::RawRpcError::RemoteException(code) => Self::RemoteException(code),
             crate::rpc::RawRpcError::ProcessInaccessible => Self::ProcessInaccessible,
+            crate::rpc::RawRpcError::AllocationBudgetExceeded(e) => {
+                Self::AllocationBudgetExceeded(e)
+            }
             crate::rpc::RawRpcError::ModuleInaccessible => Self::ModuleInaccessible,
+            #[cfg(feature = "rpc-raw")]
+            crate::rpc::RawRpcError::InvalidSignature(e) => {
+                Self::Io(io::Error::new(io::ErrorKind::InvalidInput, e))
+            }
         }
     }
 }
@@ -600,8 +1151,14 @@ impl From<crate::rpc::PayloadRpcError> for SyringeError {
             crate::rpc::PayloadRpcError::Io(e) => Self::Io(e),
             crate::rpc::PayloadRpcError::RemoteException(e) => Self::RemoteException(e),
             crate::rpc::PayloadRpcError::ProcessInaccessible => Self::ProcessInaccessible,
+            crate::rpc::PayloadRpcError::AllocationBudgetExceeded(e) => {
+                Self::AllocationBudgetExceeded(e)
+            }
             crate::rpc::PayloadRpcError::ModuleInaccessible => Self::ModuleInaccessible,
             crate::rpc::PayloadRpcError::RemoteProcedure(e) => Self::RemotePayloadProcedure(e),
+            crate::rpc::PayloadRpcError::RemoteProcedurePanicked { message, location } => {
+                Self::RemotePayloadProcedurePanicked { message, location }
+            }
             crate::rpc::PayloadRpcError::Serde(e) => Self::Serde(e),
         }
     }
@@ -611,6 +1168,7 @@ impl From<crate::rpc::PayloadRpcError> for SyringeError {
 #[derive(Debug, Error)]
 #[cfg(feature = "syringe")]
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+#[non_exhaustive]
 pub enum SyringeOperationError {
     /// Variant representing an error while injecting a module.
     #[error("inject error: {}", _0)]
@@ -631,3 +1189,435 @@ pub enum SyringeOperationError {
     #[error("procedure load error: {}", _0)]
     ProcedureLoad(#[from] LoadProcedureError),
 }
+
+#[cfg(feature = "syringe")]
+impl SyringeOperationError {
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Inject(e) => e.kind(),
+            Self::Eject(e) => e.kind(),
+            #[cfg(feature = "rpc-payload")]
+            Self::PayloadProcedureCall(e) => e.kind(),
+            #[cfg(feature = "rpc-raw")]
+            Self::RawProcedureCall(e) => e.kind(),
+            #[cfg(feature = "rpc-core")]
+            Self::ProcedureLoad(e) => e.kind(),
+        }
+    }
+}
+
+/// Error enum for errors while accessing the configuration buffer of a payload.
+#[derive(Debug, Error)]
+#[cfg(feature = "rpc-core")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-core")))]
+#[non_exhaustive]
+pub enum PayloadConfigError {
+    /// Variant representing an error while looking up the configuration buffer.
+    #[error("procedure load error: {}", _0)]
+    ProcedureLoad(#[from] LoadProcedureError),
+    /// Variant representing an io error.
+    #[error("io error: {}", _0)]
+    Io(#[from] io::Error),
+    /// Variant representing a payload that does not define a configuration buffer.
+    #[error("payload does not export a configuration buffer")]
+    MissingExport,
+    /// Variant representing a configuration that does not fit into the configuration buffer of the payload.
+    #[error("configuration of {len} bytes exceeds the buffer capacity of {capacity} bytes")]
+    TooLarge {
+        /// The length of the configuration.
+        len: usize,
+        /// The capacity of the configuration buffer.
+        capacity: usize,
+    },
+    /// Variant representing a configuration buffer with an invalid header.
+    #[error("malformed configuration buffer")]
+    MalformedBuffer,
+}
+
+#[cfg(feature = "rpc-core")]
+impl PayloadConfigError {
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::ProcedureLoad(e) => e.kind(),
+            Self::Io(e) => ErrorKind::Io.refine(e),
+            Self::TooLarge { .. } => ErrorKind::InvalidInput,
+            Self::MissingExport | Self::MalformedBuffer => ErrorKind::MalformedImage,
+        }
+    }
+}
+
+/// Error enum for errors while attaching to the log sink of a payload.
+#[derive(Debug, Error)]
+#[cfg(feature = "rpc-core")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-core")))]
+#[non_exhaustive]
+pub enum PayloadLogError {
+    /// Variant representing an error while looking up the log sink export.
+    #[error("procedure load error: {}", _0)]
+    ProcedureLoad(#[from] LoadProcedureError),
+    /// Variant representing an io error.
+    #[error("io error: {}", _0)]
+    Io(#[from] io::Error),
+    /// Variant representing a payload that does not define a log sink.
+    #[error("payload does not export a log sink")]
+    MissingExport,
+    /// Variant representing an unhandled exception or io error while handing the sink to the payload.
+    #[error("{}", _0)]
+    Remote(#[from] ExceptionOrIoError),
+}
+
+#[cfg(feature = "rpc-core")]
+impl PayloadLogError {
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::ProcedureLoad(e) => e.kind(),
+            Self::Remote(e) => e.kind(),
+            Self::Io(e) => ErrorKind::Io.refine(e),
+            Self::MissingExport => ErrorKind::MalformedImage,
+        }
+    }
+}
+
+/// Error enum for errors while running a managed method in the target process using the CLR hosting api.
+#[derive(Debug, Error)]
+#[cfg(feature = "dotnet")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "dotnet")))]
+#[non_exhaustive]
+pub enum ManagedInjectError {
+    /// Variant representing an error while loading `mscoree.dll` into the target process.
+    #[error("inject error: {}", _0)]
+    Inject(#[from] InjectError),
+    /// Variant representing an error while loading a procedure of `mscoree.dll`.
+    #[error("procedure load error: {}", _0)]
+    ProcedureLoad(#[from] LoadProcedureError),
+    /// Variant representing an error while calling a hosting api function in the target process.
+    #[error("raw rpc error: {}", _0)]
+    ProcedureCall(#[from] crate::rpc::RawRpcError),
+    /// Variant representing an io error.
+    #[error("io error: {}", _0)]
+    Io(#[from] io::Error),
+    /// Variant representing a string argument containing an illegal interior nul.
+    #[error("argument contains illegal interior nul")]
+    IllegalString(#[from] widestring::error::ContainsNul<u16>),
+    /// Variant representing a hosting api function that is not exported by the `mscoree.dll` of the target process.
+    #[error(transparent)]
+    MissingExport(#[from] MissingExportError),
+    /// Variant representing a failed call of the CLR hosting api, e.g. because the runtime version is not installed
+    /// or the assembly, type or method could not be found.
+    #[error("{call} failed with HRESULT {hresult:#010X}")]
+    Hosting {
+        /// The name of the failed hosting api function.
+        call: &'static str,
+        /// The `HRESULT` returned by the function.
+        hresult: u32,
+    },
+}
+
+#[cfg(feature = "dotnet")]
+impl ManagedInjectError {
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Inject(e) => e.kind(),
+            Self::ProcedureLoad(e) => e.kind(),
+            Self::ProcedureCall(e) => e.kind(),
+            Self::Io(e) => ErrorKind::Io.refine(e),
+            Self::IllegalString(_) => ErrorKind::InvalidPath,
+            Self::MissingExport(_) => ErrorKind::MalformedImage,
+            Self::Hosting { .. } => ErrorKind::RemoteProcedure,
+        }
+    }
+}
+
+/// Error enum for errors during [`Syringe::apply_profile`](crate::Syringe::apply_profile).
+#[derive(Debug, Error)]
+#[cfg(all(feature = "syringe", feature = "serde"))]
+#[cfg_attr(
+    feature = "doc-cfg",
+    doc(cfg(all(feature = "syringe", feature = "serde")))
+)]
+#[non_exhaustive]
+pub enum ProfileError {
+    /// Variant representing a profile without a payload for the architecture of the target process.
+    #[error("profile has no payload for {} target processes", if *is_x86 { "x86" } else { "x64" })]
+    NoPayloadForArchitecture {
+        /// Whether the target process is an x86 process.
+        is_x86: bool,
+    },
+    /// Variant representing an error while injecting the payload.
+    #[error("inject error")]
+    Inject(#[from] InjectError),
+    /// Variant representing a procedure of a post-inject call that is not exported by the payload.
+    #[error("payload does not export {procedure}")]
+    MissingProcedure {
+        /// The name of the missing procedure.
+        procedure: String,
+    },
+    /// Variant representing a post-inject call that failed or did not finish within the call timeout of the profile.
+    #[error("post-inject call of {procedure} failed")]
+    PostInjectCall {
+        /// The name of the called procedure.
+        procedure: String,
+        /// The error that caused the call to fail.
+        #[source]
+        source: ExceptionOrIoError,
+    },
+    /// Variant representing an io error.
+    #[error("io error")]
+    Io(#[from] io::Error),
+}
+
+#[cfg(all(feature = "syringe", feature = "serde"))]
+impl ProfileError {
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Inject(e) => e.kind(),
+            Self::Io(e) => ErrorKind::Io.refine(e),
+            Self::NoPayloadForArchitecture { .. } => ErrorKind::ArchitectureMismatch,
+            Self::MissingProcedure { .. } => ErrorKind::MalformedImage,
+            Self::PostInjectCall {
+                source: ExceptionOrIoError::Io(e),
+                ..
+            } if e.kind() == io::ErrorKind::TimedOut => ErrorKind::TimedOut,
+            Self::PostInjectCall { source, .. } => source.kind(),
+        }
+    }
+}
+
+/// The category of an error of this crate, as returned by the `kind` method of the error enums.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A windows api call in the current process failed with an error that does not fall into a more specific kind.
+    Io,
+    /// A windows api call inside the target process failed with an error that does not fall into a more specific kind.
+    RemoteIo,
+    /// An unhandled exception occurred inside the target process.
+    RemoteException,
+    /// The target process is inaccessible, e.g. because it crashed, was terminated or denied access.
+    ProcessInaccessible,
+    /// The target module is inaccessible, e.g. because it was ejected or unloaded.
+    ModuleInaccessible,
+    /// The target process is not supported.
+    UnsupportedTarget,
+    /// The payload module was compiled for a different architecture than the target process.
+    ArchitectureMismatch,
+    /// The target process is protected, its mitigation policies prevent the operation or the target module is pinned.
+    Blocked,
+    /// The payload path is invalid, too long or ambiguous.
+    InvalidPath,
+    /// A module image could not be parsed or has unexpected contents.
+    MalformedImage,
+    /// A value could not be serialized or deserialized.
+    Serialization,
+    /// A remote payload procedure returned an error or panicked.
+    RemoteProcedure,
+    /// A function could not be hooked.
+    Hook,
+    /// The operation did not complete within the given timeout.
+    TimedOut,
+    /// A remote allocation would exceed the allocation budget of the syringe.
+    AllocationBudgetExceeded,
+    /// The operation is not supported by [Wine](https://www.winehq.org/), which the target process is running under.
+    TargetIsWine,
+    /// Access was denied, e.g. due to missing privileges or a higher integrity level of the target.
+    AccessDenied,
+    /// A file, module or procedure was not found.
+    NotFound,
+    /// The system or the target process ran out of memory.
+    OutOfMemory,
+    /// A resource is temporarily locked or in use.
+    Busy,
+    /// An argument is invalid, e.g. a value that does not fit into the buffer it is written to.
+    InvalidInput,
+}
+
+impl ErrorKind {
+    /// Classifies the given win32 error code, if it falls into a more specific kind than [`Io`](Self::Io).
+    #[must_use]
+    pub fn from_win32_error(code: u32) -> Option<Self> {
+        Some(match code {
+            ERROR_PROCESS_ABORTED | ERROR_PARTIAL_COPY => Self::ProcessInaccessible,
+            ERROR_ACCESS_DENIED | ERROR_PRIVILEGE_NOT_HELD | ERROR_ELEVATION_REQUIRED => {
+                Self::AccessDenied
+            }
+            ERROR_FILE_NOT_FOUND | ERROR_PATH_NOT_FOUND | ERROR_MOD_NOT_FOUND
+            | ERROR_PROC_NOT_FOUND | ERROR_INVALID_NAME | ERROR_BAD_PATHNAME => Self::NotFound,
+            ERROR_BAD_FORMAT | ERROR_BAD_EXE_FORMAT => Self::MalformedImage,
+            ERROR_INVALID_IMAGE_HASH => Self::Blocked,
+            ERROR_NOT_ENOUGH_MEMORY
+            | ERROR_OUTOFMEMORY
+            | ERROR_NO_SYSTEM_RESOURCES
+            | ERROR_COMMITMENT_LIMIT => Self::OutOfMemory,
+            ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION | ERROR_BUSY => Self::Busy,
+            WAIT_TIMEOUT | ERROR_TIMEOUT => Self::TimedOut,
+            _ => return None,
+        })
+    }
+
+    /// Returns whether an operation that failed this way may succeed if it is retried later.
+    #[must_use]
+    pub fn is_transient(self) -> bool {
+        matches!(self, Self::OutOfMemory | Self::Busy | Self::TimedOut)
+    }
+
+    /// Classifies the given io error, which falls into this kind unless its error code is more specific,
+    /// so callers can e.g. decide whether to retry.
+    pub(crate) fn refine(self, err: &io::Error) -> Self {
+        #[cfg(feature = "syringe")]
+        if RemoteAllocationBudgetExceeded::from_io_error(err).is_some() {
+            return Self::AllocationBudgetExceeded;
+        }
+        let code = err.raw_os_error().map(|code| code as u32);
+        // under wine a function that is not implemented means that the operation is not supported rather than that it failed.
+        if matches!(code, Some(ERROR_CALL_NOT_IMPLEMENTED | ERROR_NOT_SUPPORTED))
+            && crate::process::is_wine()
+        {
+            return Self::TargetIsWine;
+        }
+        code.and_then(Self::from_win32_error).unwrap_or(self)
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Io => "io error",
+            Self::RemoteIo => "remote io error",
+            Self::RemoteException => "remote exception",
+            Self::ProcessInaccessible => "inaccessible target process",
+            Self::ModuleInaccessible => "inaccessible target module",
+            Self::UnsupportedTarget => "unsupported target process",
+            Self::ArchitectureMismatch => "architecture mismatch",
+            Self::Blocked => "blocked by target process",
+            Self::InvalidPath => "invalid payload path",
+            Self::MalformedImage => "malformed module image",
+            Self::Serialization => "serialization error",
+            Self::RemoteProcedure => "remote procedure error",
+            Self::Hook => "hook error",
+            Self::TimedOut => "timed out",
+            Self::AllocationBudgetExceeded => "remote allocation budget exceeded",
+            Self::TargetIsWine => "not supported under wine",
+            Self::AccessDenied => "access denied",
+            Self::NotFound => "not found",
+            Self::OutOfMemory => "out of memory",
+            Self::Busy => "resource busy",
+            Self::InvalidInput => "invalid input",
+        })
+    }
+}
+
+/// An operation on a target process, e.g. the one a failed remote call was part of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Operation {
+    /// Injecting a module into the target process.
+    Inject,
+    /// Ejecting a module from the target process.
+    Eject,
+    /// Loading a remote procedure.
+    LoadProcedure,
+    /// Calling a remote procedure.
+    CallProcedure,
+    /// Installing a hook.
+    Hook,
+    /// Verifying a loaded module against its image on disk.
+    VerifyModule,
+    /// Accessing the configuration buffer of a payload.
+    ConfigurePayload,
+    /// Terminating a process.
+    Terminate,
+    /// Opening a process.
+    OpenProcess,
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Inject => "inject",
+            Self::Eject => "eject",
+            Self::LoadProcedure => "procedure load",
+            Self::CallProcedure => "procedure call",
+            Self::Hook => "hook",
+            Self::VerifyModule => "module verification",
+            Self::ConfigurePayload => "payload configuration",
+            Self::Terminate => "terminate",
+            Self::OpenProcess => "process open",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use winapi::shared::winerror::ERROR_INVALID_PARAMETER;
+
+    use super::*;
+
+    #[test]
+    fn access_denied_is_not_assumed_to_refer_to_the_process() {
+        let err = TerminateError::Io(io::Error::from_raw_os_error(ERROR_ACCESS_DENIED as _));
+        assert_eq!(err.kind(), ErrorKind::AccessDenied);
+    }
+
+    #[test]
+    fn io_errors_are_classified_by_error_code() {
+        assert_eq!(
+            ErrorKind::from_win32_error(ERROR_MOD_NOT_FOUND),
+            Some(ErrorKind::NotFound)
+        );
+        assert!(ErrorKind::from_win32_error(ERROR_SHARING_VIOLATION)
+            .unwrap()
+            .is_transient());
+        assert_eq!(ErrorKind::from_win32_error(ERROR_NOT_SUPPORTED), None);
+        assert!(NtStatus(0xC000_0005).is_error());
+        assert!(NtStatus(0).is_success());
+        // STATUS_INVALID_HANDLE is only classified through its win32 equivalent.
+        assert_eq!(NtStatus(0xC000_0008).to_win32_error(), Some(6));
+        assert_eq!(NtStatus(0xC0FF_FFFF).to_win32_error(), None);
+
+        let err = io::Error::from_raw_os_error(ERROR_PROCESS_ABORTED as _);
+        assert_eq!(ErrorKind::Io.refine(&err), ErrorKind::ProcessInaccessible);
+        let err = io::Error::from_raw_os_error(ERROR_INVALID_PARAMETER as _);
+        assert_eq!(ErrorKind::RemoteIo.refine(&err), ErrorKind::RemoteIo);
+    }
+
+    #[cfg(feature = "syringe")]
+    #[test]
+    fn syringe_errors_keep_error_code_and_source() {
+        let err = InjectError::from(io::Error::from_raw_os_error(ERROR_PARTIAL_COPY as _));
+        assert!(matches!(err, InjectError::ProcessInaccessible));
+
+        let err = InjectError::RemoteIo(io::Error::from_raw_os_error(126));
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert_eq!(err.to_string(), "remote io error");
+        assert_eq!(
+            err.source().unwrap().to_string(),
+            io::Error::from_raw_os_error(126).to_string()
+        );
+        assert_eq!(SyringeError::from(err).kind(), ErrorKind::NotFound);
+
+        let err = EjectError::from(ExceptionCode::AccessViolation);
+        assert_eq!(err.kind(), ErrorKind::RemoteException);
+    }
+
+    #[cfg(feature = "rpc-core")]
+    #[test]
+    fn oversized_payload_config_is_invalid_input() {
+        let err = PayloadConfigError::TooLarge {
+            len: 2,
+            capacity: 1,
+        };
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
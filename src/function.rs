@@ -178,7 +178,7 @@ macro_rules! impl_fn {
 
             unsafe fn from_ptr(ptr: crate::function::RawFunctionPtr) -> Self {
                 ::core::assert!(!ptr.is_null());
-                unsafe { ::core::mem::transmute(ptr) }
+                unsafe { ::core::mem::transmute::<crate::function::RawFunctionPtr, Self>(ptr) }
             }
 
             fn as_ptr(&self) -> crate::function::RawFunctionPtr {
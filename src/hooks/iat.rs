@@ -0,0 +1,144 @@
+use std::io;
+
+use crate::{
+    error::HookError,
+    function::RawFunctionPtr,
+    process::{
+        memory::{Patch, ProcessMemorySlice, RemoteImage},
+        BorrowedProcessModule,
+    },
+};
+
+/// A hook redirecting an entry of the import address table of a module in a (remote) process to another function.
+///
+/// The original function pointer is restored when the hook is uninstalled or dropped.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::{hooks::IatHook, process::{OwnedProcess, Process}, Syringe};
+///
+/// let process = OwnedProcess::find_first_by_name("ExampleProcess").unwrap();
+/// let syringe = Syringe::for_process(process);
+/// let payload = syringe.inject("payload.dll").unwrap();
+/// let handler = syringe.get_procedure_address(payload, "get_key_state_hook").unwrap().unwrap();
+///
+/// let target_module = syringe.process().find_module_by_name("ExampleProcess.exe").unwrap().unwrap();
+/// let hook = IatHook::install(target_module, "user32.dll", "GetKeyState", handler).unwrap();
+/// // ...
+/// hook.uninstall().unwrap();
+/// ```
+#[must_use = "the hook is uninstalled immediately if it is dropped"]
+#[derive(Debug)]
+pub struct IatHook<'a> {
+    patch: Patch<'a>,
+    original: RawFunctionPtr,
+}
+
+impl<'a> IatHook<'a> {
+    /// Redirects the import of the function with the given name from the module with the given name in the import address table
+    /// of the given module to the given replacement function.
+    /// The comparison of module names is case-insensitive.
+    ///
+    /// # Note
+    /// The replacement must be a function in the target process with the same signature and calling convention as the original.
+    pub fn install(
+        module: BorrowedProcessModule<'a>,
+        import_module_name: &str,
+        import_name: &str,
+        replacement: RawFunctionPtr,
+    ) -> Result<Self, HookError> {
+        let image = RemoteImage::new(module)?;
+        let import = image
+            .imports()?
+            .into_iter()
+            .find(|import| {
+                import.module_name.eq_ignore_ascii_case(import_module_name)
+                    && import.name.as_deref() == Some(import_name)
+            })
+            .ok_or(HookError::ImportNotFound)?;
+
+        let slot = image.memory(import.slot_rva, image.pointer_size());
+        Self::install_at(slot, replacement)
+    }
+
+    /// Redirects the import address table slot at the given memory to the given replacement function.
+    ///
+    /// # Panics
+    /// This function will panic if the given memory is not exactly pointer sized for the target process.
+    fn install_at(
+        slot: ProcessMemorySlice<'a>,
+        replacement: RawFunctionPtr,
+    ) -> Result<Self, HookError> {
+        let mut original = [0u8; 8];
+        slot.read(0, &mut original[..slot.len()])?;
+        let original = u64::from_le_bytes(original) as usize as RawFunctionPtr;
+
+        let replacement = (replacement as usize as u64).to_le_bytes();
+        #[cfg(feature = "syringe")]
+        let _kind = crate::journal::record_as(crate::ModificationKind::IatHook);
+        let patch = Patch::apply(slot, 0, &replacement[..slot.len()])?;
+        Ok(Self { patch, original })
+    }
+
+    /// Returns the function the import pointed to before the hook was installed.
+    /// This can be used by the replacement to call through to the original function.
+    #[must_use]
+    pub const fn original(&self) -> RawFunctionPtr {
+        self.original
+    }
+
+    /// Returns the address of the import address table slot that was modified.
+    ///
+    /// # Note
+    /// The returned pointer is only valid in the target process.
+    #[must_use]
+    pub const fn slot(&self) -> *mut u8 {
+        self.patch.memory().as_ptr()
+    }
+
+    /// Uninstalls the hook by restoring the original function pointer.
+    pub fn uninstall(self) -> Result<(), io::Error> {
+        self.patch.revert()
+    }
+
+    /// Consumes the hook without uninstalling it.
+    #[cfg(feature = "syringe")]
+    pub(crate) fn keep(self) {
+        self.patch.keep();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{memory::ProcessMemoryBuffer, BorrowedProcess, Process, ProcessModule};
+
+    #[test]
+    fn install_and_uninstall_restores_slot() {
+        let process = BorrowedProcess::current();
+        let slot =
+            ProcessMemoryBuffer::allocate_data(process, std::mem::size_of::<usize>()).unwrap();
+        slot.write_struct(0, &0x1234usize).unwrap();
+
+        let hook = IatHook::install_at(*slot.as_slice(), 0x5678usize as RawFunctionPtr).unwrap();
+        assert_eq!(hook.original() as usize, 0x1234);
+        assert_eq!(unsafe { slot.read_struct::<usize>(0) }.unwrap(), 0x5678);
+
+        hook.uninstall().unwrap();
+        assert_eq!(unsafe { slot.read_struct::<usize>(0) }.unwrap(), 0x1234);
+    }
+
+    #[test]
+    fn install_fails_for_missing_import() {
+        let kernel32 = ProcessModule::find_by_name("kernel32.dll", BorrowedProcess::current())
+            .unwrap()
+            .unwrap();
+        let result = IatHook::install(
+            kernel32.borrowed(),
+            "does_not_exist.dll",
+            "DoesNotExist",
+            std::ptr::null_mut(),
+        );
+        assert!(matches!(result, Err(HookError::ImportNotFound)));
+    }
+}
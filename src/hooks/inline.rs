@@ -0,0 +1,206 @@
+use std::io;
+
+use iced_x86::{
+    code_asm::{qword_ptr, CodeAssembler},
+    BlockEncoder, BlockEncoderOptions, Decoder, DecoderOptions, IcedError, Instruction,
+    InstructionBlock, Mnemonic,
+};
+
+use crate::{
+    error::HookError,
+    function::RawFunctionPtr,
+    process::{
+        memory::{Patch, ProcessMemoryBuffer, ProcessMemorySlice},
+        BorrowedProcess, Process,
+    },
+};
+
+// The maximum length of an x86 instruction.
+const MAX_INSTRUCTION_LEN: usize = 15;
+
+/// An inline hook (detour) redirecting a function in a (remote) process to a handler function.
+///
+/// The first instructions of the hooked function are overwritten with a jump to the handler.
+/// The overwritten instructions are relocated into a trampoline which can be called to invoke the original function.
+/// The original instructions are restored when the hook is uninstalled or dropped.
+#[must_use = "the hook is uninstalled immediately if it is dropped"]
+#[derive(Debug)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "assembler")))]
+pub struct InlineHook<'a> {
+    // declared before the trampoline so that the jump is removed before the trampoline is freed.
+    patch: Patch<'a>,
+    trampoline: ProcessMemoryBuffer<'a>,
+}
+
+impl<'a> InlineHook<'a> {
+    /// Installs a hook redirecting all calls of the given target function in the given process to the given handler.
+    ///
+    /// # Safety
+    /// The target must point to the start of a function in the given process and the handler must be a function in the given process
+    /// with the same signature and calling convention.
+    /// No thread may be executing the first instructions of the target while the hook is installed or uninstalled (e.g. by suspending the process),
+    /// and the function must not contain jumps back into its first instructions.
+    pub unsafe fn install(
+        process: BorrowedProcess<'a>,
+        target: RawFunctionPtr,
+        handler: RawFunctionPtr,
+    ) -> Result<Self, HookError> {
+        let bitness = if process.is_x64()? { 64 } else { 32 };
+        let target = target as u64;
+        let jump = Self::build_jump(bitness, target, handler as u64)?;
+
+        let mut prologue = [0u8; 2 * MAX_INSTRUCTION_LEN];
+        let prologue_memory = unsafe {
+            ProcessMemorySlice::from_raw_parts(target as *mut u8, prologue.len(), process)
+        };
+        prologue_memory.read(0, &mut prologue)?;
+        let stolen = Self::decode_prologue(bitness, target, &prologue, jump.len())?;
+        let stolen_len = stolen.iter().map(Instruction::len).sum::<usize>();
+
+        let _kind = crate::journal::record_as(crate::ModificationKind::InlineHook);
+        let trampoline = ProcessMemoryBuffer::allocate_code(process, 128)?;
+        let trampoline_code = Self::build_trampoline(
+            bitness,
+            &stolen,
+            trampoline.as_ptr() as u64,
+            target + stolen_len as u64,
+        )?;
+        if trampoline_code.len() > trampoline.len() {
+            return Err(HookError::UnsupportedPrologue);
+        }
+        trampoline.write(0, &trampoline_code)?;
+        trampoline.flush_instruction_cache()?;
+
+        // pad the remainder of the overwritten instructions with int3 so that no partial instructions remain.
+        let mut patch_bytes = jump;
+        patch_bytes.resize(stolen_len, 0xCC);
+        let patch = Patch::apply(prologue_memory, 0, &patch_bytes)?;
+
+        Ok(Self { patch, trampoline })
+    }
+
+    fn decode_prologue(
+        bitness: u32,
+        ip: u64,
+        code: &[u8],
+        min_len: usize,
+    ) -> Result<Vec<Instruction>, HookError> {
+        let mut decoder = Decoder::with_ip(bitness, code, ip, DecoderOptions::NONE);
+        let mut instructions = Vec::new();
+        let mut len = 0;
+        while len < min_len {
+            if !decoder.can_decode() {
+                return Err(HookError::UnsupportedPrologue);
+            }
+            let instruction = decoder.decode();
+            if instruction.is_invalid() {
+                return Err(HookError::UnsupportedPrologue);
+            }
+            len += instruction.len();
+            let ends_function = matches!(
+                instruction.mnemonic(),
+                Mnemonic::Ret | Mnemonic::Retf | Mnemonic::Int3 | Mnemonic::Jmp
+            );
+            instructions.push(instruction);
+            // the function ends before there is enough space for the jump, so code after it could be overwritten.
+            if ends_function && len < min_len {
+                return Err(HookError::UnsupportedPrologue);
+            }
+        }
+        Ok(instructions)
+    }
+
+    fn build_jump(bitness: u32, from: u64, to: u64) -> Result<Vec<u8>, IcedError> {
+        let mut asm = CodeAssembler::new(bitness)?;
+        if bitness == 64 {
+            // jmp qword ptr [rip], followed by the absolute target address, so that the handler can be anywhere in the address space.
+            let mut target = asm.create_label();
+            asm.jmp(qword_ptr(target))?;
+            asm.set_label(&mut target)?;
+            asm.dq(&[to])?;
+        } else {
+            asm.jmp(to)?;
+        }
+        asm.assemble(from)
+    }
+
+    fn build_trampoline(
+        bitness: u32,
+        stolen: &[Instruction],
+        trampoline: u64,
+        resume: u64,
+    ) -> Result<Vec<u8>, HookError> {
+        let block = InstructionBlock::new(stolen, trampoline);
+        let mut code = BlockEncoder::encode(bitness, block, BlockEncoderOptions::NONE)
+            .map_err(|_| HookError::UnsupportedPrologue)?
+            .code_buffer;
+        let jump_back = Self::build_jump(bitness, trampoline + code.len() as u64, resume)?;
+        code.extend_from_slice(&jump_back);
+        Ok(code)
+    }
+
+    /// Returns a pointer to the trampoline which executes the original function.
+    ///
+    /// # Note
+    /// The returned pointer is only valid in the target process.
+    #[must_use]
+    pub fn trampoline(&self) -> RawFunctionPtr {
+        self.trampoline.as_ptr().cast()
+    }
+
+    /// Returns a pointer to the hooked function.
+    ///
+    /// # Note
+    /// The returned pointer is only valid in the target process.
+    #[must_use]
+    pub const fn target(&self) -> RawFunctionPtr {
+        self.patch.memory().as_ptr().cast()
+    }
+
+    /// Uninstalls the hook by restoring the original instructions and freeing the trampoline.
+    ///
+    /// # Note
+    /// The trampoline must not be executing or be called afterwards.
+    pub fn uninstall(self) -> Result<(), io::Error> {
+        self.patch.revert()?;
+        self.trampoline.free().map_err(|(_, e)| e)
+    }
+
+    /// Consumes the hook without uninstalling it, so the trampoline is no longer freed.
+    pub(crate) fn keep(self) {
+        self.patch.keep();
+        self.trampoline.leak();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prologue_is_decoded_until_jump_fits() {
+        // push rbp; mov rbp, rsp; sub rsp, 0x20; mov eax, 1; ret
+        let code = [
+            0x55, 0x48, 0x89, 0xE5, 0x48, 0x83, 0xEC, 0x20, 0xB8, 0x01, 0x00, 0x00, 0x00, 0xC3,
+        ];
+        let stolen = InlineHook::decode_prologue(64, 0x1000, &code, 5).unwrap();
+        assert_eq!(stolen.len(), 3);
+
+        assert!(matches!(
+            InlineHook::decode_prologue(64, 0x1000, &code, 14),
+            Err(HookError::UnsupportedPrologue)
+        ));
+    }
+
+    #[test]
+    fn trampoline_jumps_back_after_stolen_bytes() {
+        // push rbp; mov rbp, rsp; sub rsp, 0x20
+        let code = [0x55, 0x48, 0x89, 0xE5, 0x48, 0x83, 0xEC, 0x20];
+        let stolen = InlineHook::decode_prologue(64, 0x1000, &code, 8).unwrap();
+        let trampoline = InlineHook::build_trampoline(64, &stolen, 0x2000, 0x1008).unwrap();
+
+        assert_eq!(&trampoline[..8], &code);
+        assert_eq!(&trampoline[8..14], &[0xFF, 0x25, 0, 0, 0, 0]);
+        assert_eq!(&trampoline[14..], &0x1008u64.to_le_bytes());
+    }
+}
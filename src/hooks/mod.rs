@@ -0,0 +1,7 @@
+mod iat;
+pub use iat::*;
+
+#[cfg(feature = "assembler")]
+mod inline;
+#[cfg(feature = "assembler")]
+pub use inline::*;
@@ -0,0 +1,327 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{
+    error::SyringeOperationError,
+    process::{BorrowedProcessModule, ModuleHandle, ProcessModule},
+    utils::trace_event,
+    CancellationToken, InjectOptions, InjectedModule, Syringe,
+};
+
+#[cfg(feature = "rpc-core")]
+use crate::{error::LoadProcedureError, function::RawFunctionPtr};
+
+/// An event emitted by a [`HotReloader`].
+#[derive(Debug)]
+#[non_exhaustive]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub enum HotReloadEvent<'a> {
+    /// A change of the payload file was detected, which is reloaded once the file stops changing.
+    Changed {
+        /// The watched payload path.
+        payload_path: &'a Path,
+    },
+    /// The payload is about to be reloaded.
+    Reloading {
+        /// The currently loaded build of the payload, which is ejected first, if any.
+        previous: Option<BorrowedProcessModule<'a>>,
+    },
+    /// The new build of the payload was injected and the registered procedures were resolved again.
+    Reloaded {
+        /// The newly injected module.
+        module: BorrowedProcessModule<'a>,
+    },
+    /// Reloading the payload failed. The failed build is not retried, the next change of the payload file is reloaded again.
+    Failed {
+        /// The error that caused the reload to fail.
+        error: &'a SyringeOperationError,
+    },
+}
+
+/// The length and modification time of a file, which change whenever it is rebuilt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: SystemTime,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok()?,
+        })
+    }
+}
+
+type HotReloadListener = Box<dyn FnMut(&HotReloadEvent<'_>)>;
+
+/// Watches a payload file and injects every new build of it into the target process of a [`Syringe`], ejecting the previous one.
+///
+/// The file is polled for changes of its size or modification time and reloaded once it did not change for the settle time,
+/// so a build that is still being written is not injected. By default the payload is injected as a randomly named temporary copy
+/// (see [`InjectOptions::with_copy_to_temp`]), so the original file is not locked and can be overwritten by the next build.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::{process::OwnedProcess, HotReloadEvent, HotReloader, Syringe};
+/// use std::time::Duration;
+///
+/// let syringe = Syringe::for_process(OwnedProcess::find_first_by_name("target_process").unwrap());
+/// let mut reloader = HotReloader::new(&syringe, "target/debug/injection_payload.dll");
+/// reloader.add_listener(|event| {
+///     if let HotReloadEvent::Failed { error } = event {
+///         eprintln!("reload failed: {error}");
+///     }
+/// });
+/// reloader.inject().unwrap();
+/// reloader.watch(Duration::from_millis(250), || true);
+/// ```
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub struct HotReloader<'a> {
+    syringe: &'a Syringe,
+    payload_path: PathBuf,
+    options: InjectOptions,
+    settle_time: Duration,
+    module: Option<ModuleHandle>,
+    stamp: Option<FileStamp>,
+    pending: Option<(FileStamp, Instant)>,
+    failed: Option<FileStamp>,
+    #[cfg(feature = "rpc-core")]
+    procedures: Vec<(String, Option<RawFunctionPtr>)>,
+    listeners: Vec<HotReloadListener>,
+}
+
+impl fmt::Debug for HotReloader<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HotReloader")
+            .field("syringe", &self.syringe)
+            .field("payload_path", &self.payload_path)
+            .field("options", &self.options)
+            .field("settle_time", &self.settle_time)
+            .field("module", &self.module)
+            .field("listeners", &self.listeners.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> HotReloader<'a> {
+    /// The default time the payload file has to remain unchanged before it is reloaded.
+    pub const DEFAULT_SETTLE_TIME: Duration = Duration::from_millis(500);
+
+    /// Creates a new reloader for the payload at the given path, which is not injected until [`HotReloader::inject`] or
+    /// [`HotReloader::poll`] is called.
+    #[must_use]
+    pub fn new(syringe: &'a Syringe, payload_path: impl AsRef<Path>) -> Self {
+        Self {
+            syringe,
+            payload_path: payload_path.as_ref().to_path_buf(),
+            options: InjectOptions::new()
+                .with_copy_to_temp(true)
+                .with_randomized_name(true),
+            settle_time: Self::DEFAULT_SETTLE_TIME,
+            module: None,
+            stamp: None,
+            pending: None,
+            failed: None,
+            #[cfg(feature = "rpc-core")]
+            procedures: Vec::new(),
+            listeners: Vec::new(),
+        }
+    }
+
+    /// Sets the options used to inject each build of the payload.
+    #[must_use]
+    pub fn with_inject_options(mut self, options: InjectOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets the time the payload file has to remain unchanged before it is reloaded.
+    #[must_use]
+    pub fn with_settle_time(mut self, settle_time: Duration) -> Self {
+        self.settle_time = settle_time;
+        self
+    }
+
+    /// Returns the watched payload path.
+    #[must_use]
+    pub fn payload_path(&self) -> &Path {
+        &self.payload_path
+    }
+
+    /// Returns the currently injected build of the payload, if any.
+    #[must_use]
+    pub fn module(&self) -> Option<InjectedModule<'a>> {
+        self.loaded_module()
+            .map(|module| InjectedModule::new(self.syringe, module))
+    }
+
+    /// Registers a listener that is called for every event of this reloader.
+    pub fn add_listener(&mut self, listener: impl FnMut(&HotReloadEvent<'_>) + 'static) {
+        self.listeners.push(Box::new(listener));
+    }
+
+    /// Registers the exported procedure with the given name, which is resolved again after every reload,
+    /// and returns its address in the current build, if it is loaded and exports it.
+    #[cfg(feature = "rpc-core")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-core")))]
+    pub fn register_procedure(
+        &mut self,
+        name: impl Into<String>,
+    ) -> Result<Option<RawFunctionPtr>, LoadProcedureError> {
+        let name = name.into();
+        let address = match self.loaded_module() {
+            Some(module) => self.syringe.get_procedure_address(module, &name)?,
+            None => None,
+        };
+        self.procedures
+            .retain(|(registered, _)| *registered != name);
+        self.procedures.push((name, address));
+        Ok(address)
+    }
+
+    /// Returns the address of the registered procedure with the given name in the current build of the payload.
+    /// Returns [`None`] if the procedure is not registered, no build is loaded or the current build does not export it.
+    #[cfg(feature = "rpc-core")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-core")))]
+    #[must_use]
+    pub fn procedure(&self, name: &str) -> Option<RawFunctionPtr> {
+        self.procedures
+            .iter()
+            .find(|(registered, _)| registered == name)
+            .and_then(|(_, address)| *address)
+    }
+
+    /// Injects the current build of the payload, ejecting the previously injected build if there is one.
+    ///
+    /// If this fails, [`HotReloader::poll`] does not retry the same build and waits for the next change of the payload file.
+    pub fn inject(&mut self) -> Result<InjectedModule<'a>, SyringeOperationError> {
+        self.pending = None;
+        let stamp = FileStamp::of(&self.payload_path);
+        let result = self.reload_now();
+        match &result {
+            Ok(_) => self.failed = None,
+            Err(error) => {
+                self.failed = stamp;
+                emit(&mut self.listeners, &HotReloadEvent::Failed { error });
+            }
+        }
+        result
+    }
+
+    /// Checks the payload file for changes and reloads it if it changed and did not change for the settle time since.
+    /// Returns whether the payload was reloaded.
+    ///
+    /// A missing payload file, e.g. while it is rebuilt, is not considered a change, neither is a build that already failed to reload.
+    pub fn poll(&mut self) -> Result<bool, SyringeOperationError> {
+        let Some(stamp) = FileStamp::of(&self.payload_path) else {
+            return Ok(false);
+        };
+        if self.failed == Some(stamp) || (self.module.is_some() && self.stamp == Some(stamp)) {
+            self.pending = None;
+            return Ok(false);
+        }
+
+        match self.pending {
+            Some((pending, since)) if pending == stamp => {
+                if since.elapsed() < self.settle_time {
+                    return Ok(false);
+                }
+            }
+            _ => {
+                self.pending = Some((stamp, Instant::now()));
+                emit(
+                    &mut self.listeners,
+                    &HotReloadEvent::Changed {
+                        payload_path: &self.payload_path,
+                    },
+                );
+                if !self.settle_time.is_zero() {
+                    return Ok(false);
+                }
+            }
+        }
+
+        self.inject().map(|_| true)
+    }
+
+    /// Polls the payload file with the given interval and reloads it on every change until the given function returns `false`
+    /// or the target process exits.
+    /// Failed reloads are reported through [`HotReloadEvent::Failed`] and do not stop the loop, the failed build is only
+    /// retried once the payload file changes again.
+    pub fn watch(&mut self, poll_interval: Duration, mut keep_running: impl FnMut() -> bool) {
+        while keep_running() && !self.syringe.has_target_exited() {
+            if let Err(_err) = self.poll() {
+                trace_event!(warn, payload = %self.payload_path.display(), error = %_err, "failed to reload payload");
+            }
+            thread::sleep(poll_interval);
+        }
+    }
+
+    /// Polls the payload file with the given interval and reloads it on every change until the given token is cancelled
+    /// or the target process exits. Unlike [`HotReloader::watch`], cancelling the token also ends the wait between two polls,
+    /// so the loop stops right away, e.g. when a GUI host is closed.
+    /// Failed reloads are reported through [`HotReloadEvent::Failed`] and do not stop the loop.
+    pub fn watch_cancellable(&mut self, poll_interval: Duration, cancellation: &CancellationToken) {
+        while !cancellation.is_cancelled() && !self.syringe.has_target_exited() {
+            if let Err(_err) = self.poll() {
+                trace_event!(warn, payload = %self.payload_path.display(), error = %_err, "failed to reload payload");
+            }
+            if cancellation.wait(poll_interval) {
+                break;
+            }
+        }
+    }
+
+    fn reload_now(&mut self) -> Result<InjectedModule<'a>, SyringeOperationError> {
+        let previous = self.loaded_module();
+        emit(&mut self.listeners, &HotReloadEvent::Reloading { previous });
+
+        // the stamp is taken before injecting, so a build written during the injection is picked up by the next poll.
+        let stamp = FileStamp::of(&self.payload_path);
+        if let Some(previous) = previous {
+            self.syringe.eject(previous)?;
+        }
+        self.module = None;
+        #[cfg(feature = "rpc-core")]
+        for (_, address) in &mut self.procedures {
+            *address = None;
+        }
+
+        let module = self
+            .syringe
+            .inject_with_options(&self.payload_path, &self.options)?;
+        self.module = Some(module.handle());
+        self.stamp = stamp;
+        trace_event!(info, payload = %self.payload_path.display(), module = ?module.handle(), "reloaded payload");
+
+        #[cfg(feature = "rpc-core")]
+        for (name, address) in &mut self.procedures {
+            *address = self.syringe.get_procedure_address(*module, name.as_str())?;
+        }
+
+        emit(
+            &mut self.listeners,
+            &HotReloadEvent::Reloaded { module: *module },
+        );
+        Ok(module)
+    }
+
+    /// Returns the injected build of the payload if it is still loaded.
+    fn loaded_module(&self) -> Option<BorrowedProcessModule<'a>> {
+        let module = unsafe { ProcessModule::new_unchecked(self.module?, self.syringe.process()) };
+        module.guess_is_loaded().then_some(module)
+    }
+}
+
+fn emit(listeners: &mut [HotReloadListener], event: &HotReloadEvent<'_>) {
+    for listener in listeners {
+        listener(event);
+    }
+}
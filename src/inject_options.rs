@@ -0,0 +1,427 @@
+use std::{
+    collections::hash_map::RandomState,
+    env,
+    ffi::OsString,
+    fs,
+    hash::{BuildHasher, Hasher},
+    io,
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use path_absolutize::Absolutize;
+
+use crate::{
+    error::InjectError,
+    process::{BorrowedProcess, Process, ProcessId},
+};
+
+/// The directory a relative payload path is resolved against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub enum ResolveRelativeTo {
+    /// Resolve relative paths against the current working directory of the injecting process.
+    InjectorCwd,
+    /// Resolve relative paths against the directory of the executable of the injecting process.
+    InjectorExe,
+    /// Resolve relative paths against the directory of the executable of the target process.
+    TargetExe,
+    /// Resolve relative paths against the current working directory of the target process, see [`Process::current_dir`].
+    TargetCwd,
+    /// Pass relative paths to the target process unchanged, which looks them up using its [dll search order](https://docs.microsoft.com/en-us/windows/win32/dlls/dynamic-link-library-search-order).
+    None,
+}
+
+impl ResolveRelativeTo {
+    fn base_dir(self, process: BorrowedProcess<'_>) -> Result<Option<PathBuf>, io::Error> {
+        Ok(match self {
+            Self::InjectorCwd => Some(env::current_dir()?),
+            Self::InjectorExe => env::current_exe()?.parent().map(Path::to_path_buf),
+            Self::TargetExe => process.path()?.parent().map(Path::to_path_buf),
+            Self::TargetCwd => Some(process.current_dir()?),
+            Self::None => None,
+        })
+    }
+}
+
+/// Options controlling how a payload is injected by [`Syringe::inject_with_options`](crate::Syringe::inject_with_options).
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::{InjectOptions, Syringe, process::OwnedProcess};
+///
+/// let syringe = Syringe::for_process(OwnedProcess::find_first_by_name("target_process").unwrap());
+///
+/// // inject a private copy so the payload can be rebuilt while it is loaded.
+/// let options = InjectOptions::new().with_copy_to_temp(true).with_randomized_name(true);
+/// let payload = syringe.inject_with_options("injection_payload.dll", &options).unwrap();
+///
+/// // the copy is deleted again once the payload is ejected.
+/// syringe.eject(payload).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub struct InjectOptions {
+    copy_to_temp: bool,
+    randomized_name: bool,
+    temp_dir: Option<PathBuf>,
+    resolve_relative_to: Option<ResolveRelativeTo>,
+    expand_target_env: bool,
+    diagnose_missing_dependencies: bool,
+}
+
+impl InjectOptions {
+    /// Creates a new set of options that injects the payload from its original location.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the payload is copied to a temporary directory unique to the target process before it is injected.
+    ///
+    /// This avoids locking the original file (e.g. during development rebuilds) and allows injecting different versions of a payload into different targets.
+    /// The copy is deleted when the payload is ejected using [`Syringe::eject`](crate::Syringe::eject).
+    #[must_use]
+    pub fn with_copy_to_temp(mut self, copy_to_temp: bool) -> Self {
+        self.copy_to_temp = copy_to_temp;
+        self
+    }
+
+    /// Sets whether the copy of the payload gets a random file name.
+    ///
+    /// This allows injecting multiple versions of the same payload into a single target process,
+    /// but prevents finding the payload by its original name. Only has an effect if [`copy_to_temp`](Self::copy_to_temp) is enabled.
+    #[must_use]
+    pub fn with_randomized_name(mut self, randomized_name: bool) -> Self {
+        self.randomized_name = randomized_name;
+        self
+    }
+
+    /// Sets the directory the per-target directories for the payload copies are created in.
+    /// Defaults to a `dll-syringe` directory inside of [`env::temp_dir`].
+    #[must_use]
+    pub fn with_temp_dir(mut self, temp_dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = Some(temp_dir.into());
+        self
+    }
+
+    /// Sets the directory relative payload paths are resolved against.
+    ///
+    /// By default, relative paths are resolved against the injector working directory, the injector executable directory and the target executable directory
+    /// and rejected with [`InjectError::AmbiguousPayloadPath`] if they refer to different existing files.
+    /// If the path exists in only one of these directories, that file is used. If it exists in none of them, the injector working directory is used.
+    #[must_use]
+    pub fn with_resolve_relative_to(mut self, resolve_relative_to: ResolveRelativeTo) -> Self {
+        self.resolve_relative_to = Some(resolve_relative_to);
+        self
+    }
+
+    /// Sets whether `%NAME%` references in the payload path are expanded using the environment of the target process.
+    ///
+    /// This resolves paths like `%LOCALAPPDATA%\\payload.dll` as the target would see them, e.g. in the profile of its user
+    /// when injecting into a process of another user. References to variables the target does not define are left unchanged,
+    /// like `ExpandEnvironmentStringsW` does. The path is expanded before relative paths are resolved.
+    #[must_use]
+    pub fn with_expand_target_env(mut self, expand_target_env: bool) -> Self {
+        self.expand_target_env = expand_target_env;
+        self
+    }
+
+    /// Sets whether a failed injection is diagnosed by searching for the dependency of the payload the target process could not find.
+    ///
+    /// If enabled and the target process fails to load the payload with `ERROR_MOD_NOT_FOUND`, the imports of the payload and its dependencies are searched
+    /// in the dll search path of the target process and the first missing module is reported as [`InjectError::MissingDependency`].
+    /// If the search itself fails, that error is reported as [`MissingDependencyError::DiagnosisFailed`](crate::error::MissingDependencyError::DiagnosisFailed).
+    /// Note that the directory of the payload is not searched for its dependencies by the target process.
+    #[must_use]
+    pub fn with_diagnose_missing_dependencies(
+        mut self,
+        diagnose_missing_dependencies: bool,
+    ) -> Self {
+        self.diagnose_missing_dependencies = diagnose_missing_dependencies;
+        self
+    }
+
+    /// Returns whether the payload is copied to a temporary directory before it is injected.
+    #[must_use]
+    pub fn copy_to_temp(&self) -> bool {
+        self.copy_to_temp
+    }
+
+    /// Returns whether the copy of the payload gets a random file name.
+    #[must_use]
+    pub fn randomized_name(&self) -> bool {
+        self.randomized_name
+    }
+
+    /// Returns the directory the payload copies are created in.
+    #[must_use]
+    pub fn temp_dir(&self) -> PathBuf {
+        self.temp_dir
+            .clone()
+            .unwrap_or_else(|| env::temp_dir().join("dll-syringe"))
+    }
+
+    /// Returns the directory relative payload paths are resolved against, if one was set explicitly.
+    #[must_use]
+    pub fn resolve_relative_to(&self) -> Option<ResolveRelativeTo> {
+        self.resolve_relative_to
+    }
+
+    /// Returns whether environment variables in the payload path are expanded using the environment of the target process.
+    #[must_use]
+    pub fn expand_target_env(&self) -> bool {
+        self.expand_target_env
+    }
+
+    /// Returns whether a failed injection is diagnosed by searching for a missing dependency of the payload.
+    #[must_use]
+    pub fn diagnose_missing_dependencies(&self) -> bool {
+        self.diagnose_missing_dependencies
+    }
+
+    /// Resolves the given payload path according to the environment expansion and relative path policy.
+    /// The returned path is absolute unless [`ResolveRelativeTo::None`] is used.
+    pub(crate) fn resolve_payload_path(
+        &self,
+        payload_path: &Path,
+        process: BorrowedProcess<'_>,
+    ) -> Result<PathBuf, InjectError> {
+        let expanded;
+        let payload_path = if self.expand_target_env {
+            expanded = expand_env_vars(payload_path, &process.environment()?);
+            expanded.as_path()
+        } else {
+            payload_path
+        };
+
+        if payload_path.is_absolute() {
+            return Ok(payload_path.absolutize()?.into_owned());
+        }
+
+        if let Some(resolve_relative_to) = self.resolve_relative_to {
+            return Ok(match resolve_relative_to.base_dir(process)? {
+                Some(base_dir) => payload_path.absolutize_from(&base_dir)?.into_owned(),
+                None => payload_path.to_path_buf(),
+            });
+        }
+
+        let mut candidates = Vec::<PathBuf>::new();
+        for resolve_relative_to in [
+            ResolveRelativeTo::InjectorCwd,
+            ResolveRelativeTo::InjectorExe,
+            ResolveRelativeTo::TargetExe,
+        ] {
+            let Some(base_dir) = resolve_relative_to.base_dir(process)? else {
+                continue;
+            };
+            let candidate = payload_path.absolutize_from(&base_dir)?.into_owned();
+            if candidate.is_file()
+                && !candidates
+                    .iter()
+                    .any(|other| same_file::is_same_file(other, &candidate).unwrap_or(false))
+            {
+                candidates.push(candidate);
+            }
+        }
+
+        match candidates.len() {
+            0 => Ok(payload_path.absolutize()?.into_owned()),
+            1 => Ok(candidates.remove(0)),
+            _ => Err(InjectError::AmbiguousPayloadPath { candidates }),
+        }
+    }
+
+    /// Copies the given payload into the staging directory of the given process and returns the path of the copy.
+    /// Relative paths are resolved against the working directory of the current process.
+    pub(crate) fn stage_payload(
+        &self,
+        payload_path: &Path,
+        process: BorrowedProcess<'_>,
+    ) -> Result<PathBuf, io::Error> {
+        let id = ProcessId::of(&process)?;
+        let creation_time = id
+            .creation_time()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let target_dir = self
+            .temp_dir()
+            .join(format!("{}-{:x}", id.pid(), creation_time));
+        fs::create_dir_all(&target_dir)?;
+
+        let file_name = payload_path.file_name().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "payload path has no file name")
+        })?;
+        let staged_path = if self.randomized_name {
+            let mut file_name = Path::new(file_name)
+                .file_stem()
+                .unwrap_or(file_name)
+                .to_os_string();
+            file_name.push(format!("-{:016x}", random_u64()));
+            if let Some(extension) = payload_path.extension() {
+                file_name.push(".");
+                file_name.push(extension);
+            }
+            target_dir.join(file_name)
+        } else {
+            target_dir.join(file_name)
+        };
+
+        fs::copy(payload_path, &staged_path)?;
+        Ok(staged_path)
+    }
+}
+
+/// Removes a staged payload copy and its directory if it is now empty.
+pub(crate) fn remove_staged_payload(staged_path: &Path) -> Result<(), io::Error> {
+    fs::remove_file(staged_path)?;
+    if let Some(target_dir) = staged_path.parent() {
+        // fails if other payloads are still staged for the target.
+        let _ = fs::remove_dir(target_dir);
+    }
+    Ok(())
+}
+
+/// Expands the `%NAME%` references in the given path using the given environment variables, whose names are compared
+/// case-insensitively. References to undefined variables are left unchanged.
+fn expand_env_vars(path: &Path, environment: &[(OsString, OsString)]) -> PathBuf {
+    const PERCENT: u16 = b'%' as u16;
+
+    let path = path.as_os_str().encode_wide().collect::<Vec<_>>();
+    let mut expanded = Vec::with_capacity(path.len());
+    let mut rest = path.as_slice();
+    while let Some(start) = rest.iter().position(|&c| c == PERCENT) {
+        expanded.extend_from_slice(&rest[..start]);
+        let reference = &rest[start + 1..];
+        let Some(len) = reference.iter().position(|&c| c == PERCENT) else {
+            rest = &rest[start..];
+            break;
+        };
+        let name = OsString::from_wide(&reference[..len]);
+        match environment
+            .iter()
+            .find(|(variable, _)| !name.is_empty() && variable.eq_ignore_ascii_case(&name))
+        {
+            Some((_, value)) => expanded.extend(value.encode_wide()),
+            None => expanded.extend_from_slice(&rest[start..start + len + 2]),
+        }
+        rest = &reference[len + 1..];
+    }
+    expanded.extend_from_slice(rest);
+    PathBuf::from(OsString::from_wide(&expanded))
+}
+
+fn random_u64() -> u64 {
+    // every RandomState is seeded with fresh random keys.
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::Process;
+
+    #[test]
+    fn resolve_payload_path_applies_policy() {
+        let process = BorrowedProcess::current();
+        let cwd = env::current_dir().unwrap();
+        let exe_dir = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+
+        let options = InjectOptions::new();
+        assert_eq!(
+            options
+                .resolve_payload_path(&cwd.join("payload.dll"), process)
+                .unwrap(),
+            cwd.join("payload.dll")
+        );
+        // missing files fall back to the working directory.
+        assert_eq!(
+            options
+                .resolve_payload_path(Path::new("missing.dll"), process)
+                .unwrap(),
+            cwd.join("missing.dll")
+        );
+
+        let options = options.with_resolve_relative_to(ResolveRelativeTo::InjectorExe);
+        assert_eq!(
+            options
+                .resolve_payload_path(Path::new("payload.dll"), process)
+                .unwrap(),
+            exe_dir.join("payload.dll")
+        );
+
+        let options = options.with_resolve_relative_to(ResolveRelativeTo::None);
+        assert_eq!(
+            options
+                .resolve_payload_path(Path::new("payload.dll"), process)
+                .unwrap(),
+            Path::new("payload.dll")
+        );
+    }
+
+    #[test]
+    fn expand_env_vars_uses_given_environment() {
+        let environment = [
+            (
+                OsString::from("LocalAppData"),
+                OsString::from("C:\\Users\\other\\AppData\\Local"),
+            ),
+            (OsString::from("EMPTY"), OsString::new()),
+        ];
+        assert_eq!(
+            expand_env_vars(Path::new("%LOCALAPPDATA%\\payload.dll"), &environment),
+            Path::new("C:\\Users\\other\\AppData\\Local\\payload.dll")
+        );
+        assert_eq!(
+            expand_env_vars(Path::new("%EMPTY%%MISSING%\\%%\\50%.dll"), &environment),
+            Path::new("%MISSING%\\%%\\50%.dll")
+        );
+
+        let process = BorrowedProcess::current();
+        let options = InjectOptions::new()
+            .with_expand_target_env(true)
+            .with_resolve_relative_to(ResolveRelativeTo::TargetCwd);
+        assert_eq!(
+            options
+                .resolve_payload_path(Path::new("%SystemRoot%\\payload.dll"), process)
+                .unwrap(),
+            Path::new(&env::var_os("SystemRoot").unwrap()).join("payload.dll")
+        );
+        assert_eq!(
+            options
+                .resolve_payload_path(Path::new("payload.dll"), process)
+                .unwrap(),
+            env::current_dir().unwrap().join("payload.dll")
+        );
+    }
+
+    #[test]
+    fn stage_payload_copies_to_unique_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let payload_path = temp_dir.path().join("payload.dll");
+        fs::write(&payload_path, b"payload").unwrap();
+
+        let options = InjectOptions::new()
+            .with_copy_to_temp(true)
+            .with_temp_dir(temp_dir.path().join("staged"));
+        let process = BorrowedProcess::current();
+        let staged = options.stage_payload(&payload_path, process).unwrap();
+        assert_eq!(staged.file_name().unwrap(), "payload.dll");
+        assert_eq!(fs::read(&staged).unwrap(), b"payload");
+
+        let randomized = options
+            .clone()
+            .with_randomized_name(true)
+            .stage_payload(&payload_path, process)
+            .unwrap();
+        assert_ne!(randomized, staged);
+        assert_eq!(randomized.parent(), staged.parent());
+        assert_eq!(randomized.extension().unwrap(), "dll");
+
+        remove_staged_payload(&staged).unwrap();
+        remove_staged_payload(&randomized).unwrap();
+        assert!(!staged.parent().unwrap().exists());
+    }
+}
@@ -0,0 +1,342 @@
+use std::{ops::Deref, rc::Rc};
+
+use crate::{
+    error::EjectError,
+    process::{BorrowedProcessModule, ModuleHandle, ProcessModule},
+    Syringe,
+};
+
+#[cfg(feature = "rpc-core")]
+use crate::{
+    error::{LoadProcedureError, PayloadConfigError, PayloadLogError},
+    function::RawFunctionPtr,
+    process::{memory::ProcessMemorySlice, BorrowedProcess, Process},
+    remote_worker::{close_remote_handle, duplicate_into},
+    utils::trace_event,
+    PayloadConfigHeader, PayloadLogReader, LOG_SINK_EXPORT_NAME, PAYLOAD_CONFIG_EXPORT_NAME,
+};
+#[cfg(feature = "rpc-core")]
+use std::{
+    ffi::c_void,
+    io, mem,
+    os::windows::io::{AsRawHandle, OwnedHandle},
+};
+#[cfg(feature = "rpc-core")]
+use winapi::um::winnt::PROCESS_DUP_HANDLE;
+
+#[cfg(feature = "rpc-payload")]
+use crate::rpc::{PayloadRpcFunctionPtr, RemotePayloadProcedure};
+#[cfg(feature = "rpc-raw")]
+use crate::rpc::{RawRpcFunctionPtr, RemoteRawProcedure};
+
+/// A module in the target process of a [`Syringe`], which can be managed through the syringe without passing both around.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub struct InjectedModule<'a> {
+    syringe: &'a Syringe,
+    module: BorrowedProcessModule<'a>,
+}
+
+impl<'a> InjectedModule<'a> {
+    /// Creates a new instance for the given module of the target process of the given syringe.
+    ///
+    /// # Panics
+    /// This method panics if the given module is not from the target process of the syringe.
+    #[must_use]
+    pub fn new(syringe: &'a Syringe, module: BorrowedProcessModule<'a>) -> Self {
+        assert!(
+            module.process() == &syringe.process(),
+            "trying to create an injected module from a module of a different process"
+        );
+        Self { syringe, module }
+    }
+
+    /// Returns the syringe this module belongs to.
+    #[must_use]
+    pub fn syringe(&self) -> &'a Syringe {
+        self.syringe
+    }
+
+    /// Returns the underlying module.
+    #[must_use]
+    pub fn module(&self) -> BorrowedProcessModule<'a> {
+        self.module
+    }
+
+    /// Ejects this module from the target process.
+    pub fn eject(self) -> Result<(), EjectError> {
+        self.syringe.eject(self.module)
+    }
+}
+
+/// An owned version of [`InjectedModule`], which shares its [`Syringe`] and can be stored without borrowing the syringe.
+///
+/// The module can be used through [`OwnedInjectedModule::borrowed`] in the same way as an [`InjectedModule`].
+/// The procedures loaded through it ([`RemoteRawProcedure`](crate::rpc::RemoteRawProcedure) and
+/// [`RemotePayloadProcedure`](crate::rpc::RemotePayloadProcedure)) and values allocated using [`Syringe::alloc_box`]
+/// share the process handle of the syringe as well, so none of them borrow the syringe.
+///
+/// # Threads
+/// A [`Syringe`] keeps its caches and remote allocations in single-threaded shared state, so neither the syringe nor this type
+/// are [`Send`] or [`Sync`] and they have to stay on the thread that created them. To work with the same target from another thread,
+/// send the [`OwnedProcess`](crate::process::OwnedProcess) (or a [`ModuleId`](crate::process::ModuleId) of the module) there and
+/// create a new syringe for it.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub struct OwnedInjectedModule {
+    syringe: Rc<Syringe>,
+    module: ModuleHandle,
+}
+
+impl OwnedInjectedModule {
+    /// Creates a new instance for the given module of the target process of the given syringe.
+    ///
+    /// # Panics
+    /// This method panics if the given module is not from the target process of the syringe.
+    #[must_use]
+    pub fn new<'a>(syringe: Rc<Syringe>, module: impl Into<BorrowedProcessModule<'a>>) -> Self {
+        let module = InjectedModule::new(&syringe, module.into())
+            .module()
+            .handle();
+        Self { syringe, module }
+    }
+
+    /// Returns the syringe this module belongs to.
+    #[must_use]
+    pub fn syringe(&self) -> &Rc<Syringe> {
+        &self.syringe
+    }
+
+    /// Returns the underlying module.
+    #[must_use]
+    pub fn module(&self) -> BorrowedProcessModule<'_> {
+        unsafe { ProcessModule::new_unchecked(self.module, self.syringe.process()) }
+    }
+
+    /// Returns a borrowed [`InjectedModule`] for this module.
+    #[must_use]
+    pub fn borrowed(&self) -> InjectedModule<'_> {
+        InjectedModule {
+            syringe: &self.syringe,
+            module: self.module(),
+        }
+    }
+
+    /// Ejects this module from the target process.
+    pub fn eject(self) -> Result<(), EjectError> {
+        self.borrowed().eject()
+    }
+}
+
+#[cfg(feature = "rpc-core")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-core")))]
+impl OwnedInjectedModule {
+    /// Loads the address of the given exported function or variable of this module.
+    /// See [`Syringe::get_procedure_address`].
+    pub fn get_procedure_address(
+        &self,
+        name: impl AsRef<str>,
+    ) -> Result<Option<RawFunctionPtr>, LoadProcedureError> {
+        self.borrowed().get_procedure_address(name)
+    }
+}
+
+#[cfg(feature = "rpc-raw")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-raw")))]
+impl OwnedInjectedModule {
+    /// Loads an exported function of this module. The returned procedure does not borrow this module or its syringe.
+    /// See [`Syringe::get_raw_procedure`].
+    ///
+    /// # Safety
+    /// The target function must abide by the given signature.
+    pub unsafe fn get_raw_procedure<F: RawRpcFunctionPtr>(
+        &self,
+        name: &str,
+    ) -> Result<Option<RemoteRawProcedure<F>>, LoadProcedureError> {
+        unsafe { self.borrowed().get_raw_procedure(name) }
+    }
+}
+
+#[cfg(feature = "rpc-payload")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-payload")))]
+impl OwnedInjectedModule {
+    /// Loads an exported function of this module declared using the [`payload_procedure!`](crate::payload_procedure) macro.
+    /// The returned procedure does not borrow this module or its syringe. See [`Syringe::get_payload_procedure`].
+    ///
+    /// # Safety
+    /// The target function must abide by the given signature and has to be declared using the [`payload_procedure!`](crate::payload_procedure) macro.
+    #[allow(rustdoc::broken_intra_doc_links)]
+    pub unsafe fn get_payload_procedure<F: PayloadRpcFunctionPtr>(
+        &self,
+        name: &str,
+    ) -> Result<Option<RemotePayloadProcedure<F>>, LoadProcedureError> {
+        unsafe { self.borrowed().get_payload_procedure(name) }
+    }
+}
+
+impl<'a> Deref for InjectedModule<'a> {
+    type Target = BorrowedProcessModule<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.module
+    }
+}
+
+impl<'a> From<InjectedModule<'a>> for BorrowedProcessModule<'a> {
+    fn from(module: InjectedModule<'a>) -> Self {
+        module.module
+    }
+}
+
+impl<'a> From<&'a OwnedInjectedModule> for InjectedModule<'a> {
+    fn from(module: &'a OwnedInjectedModule) -> Self {
+        module.borrowed()
+    }
+}
+
+#[cfg(feature = "rpc-core")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-core")))]
+impl<'a> InjectedModule<'a> {
+    /// Loads the address of the given exported function or variable of this module.
+    /// See [`Syringe::get_procedure_address`].
+    pub fn get_procedure_address(
+        &self,
+        name: impl AsRef<str>,
+    ) -> Result<Option<RawFunctionPtr>, LoadProcedureError> {
+        self.syringe.get_procedure_address(self.module, name)
+    }
+
+    /// Writes the given configuration into the configuration buffer of this module.
+    ///
+    /// The module has to define its configuration buffer using the `payload_config!` macro (requires the `payload-utils` feature).
+    /// The buffer is written in place without synchronization, so the payload should only read it after it has been written,
+    /// e.g. from a remote procedure called afterwards.
+    pub fn write_config(&self, config: &[u8]) -> Result<(), PayloadConfigError> {
+        let (buffer, header) = self.config_buffer()?;
+        let capacity = header.capacity as usize;
+        if config.len() > capacity {
+            return Err(PayloadConfigError::TooLarge {
+                len: config.len(),
+                capacity,
+            });
+        }
+
+        buffer.write(mem::size_of::<PayloadConfigHeader>(), config)?;
+        buffer.write_struct(
+            0,
+            &PayloadConfigHeader {
+                len: config.len() as u32,
+                ..header
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Reads the current contents of the configuration buffer of this module.
+    /// See [`InjectedModule::write_config`].
+    pub fn read_config(&self) -> Result<Vec<u8>, PayloadConfigError> {
+        let (buffer, header) = self.config_buffer()?;
+        if header.len > header.capacity {
+            return Err(PayloadConfigError::MalformedBuffer);
+        }
+
+        let mut config = vec![0; header.len as usize];
+        buffer.read(mem::size_of::<PayloadConfigHeader>(), &mut config)?;
+        Ok(config)
+    }
+
+    /// Hands the payload of this module the write end of a new pipe for its log output and returns a reader for the lines it writes.
+    ///
+    /// The module has to define its log sink using the `payload_log_sink!` macro (requires the `payload-utils` feature)
+    /// and writes lines using `payload_log!`. Attaching another reader replaces the sink of the payload.
+    pub fn attach_log_reader(&self) -> Result<PayloadLogReader, PayloadLogError> {
+        let set_log_sink = self
+            .syringe
+            .get_procedure_address(self.module, LOG_SINK_EXPORT_NAME)?
+            .ok_or(PayloadLogError::MissingExport)?;
+        let process = self.syringe.process();
+
+        let (reader, writer) = io::pipe()?;
+        // the handle of the target may lack the right to duplicate handles into it.
+        let dup_process = process.duplicate_with_access(PROCESS_DUP_HANDLE)?;
+        // the local write end is closed when this function returns, so reading ends once the payload closes its copy.
+        let remote_writer =
+            duplicate_into(&OwnedHandle::from(writer), dup_process.as_raw_handle())?;
+
+        let result = process
+            .run_remote_thread_with_options(
+                unsafe {
+                    mem::transmute::<RawFunctionPtr, extern "system" fn(*mut c_void) -> u32>(
+                        set_log_sink,
+                    )
+                },
+                remote_writer as *mut c_void,
+                &self.syringe.remote_thread_options,
+            )
+            .map_err(PayloadLogError::from)
+            .and_then(|result| result.into_result().map_err(PayloadLogError::from));
+        if let Err(err) = result {
+            close_remote_handle(dup_process.as_raw_handle(), remote_writer);
+            return Err(err);
+        }
+        trace_event!(debug, module = ?self.module.handle(), handle = remote_writer, "attached payload log reader");
+        Ok(PayloadLogReader::new(reader))
+    }
+
+    fn config_buffer(
+        &self,
+    ) -> Result<(ProcessMemorySlice<'a>, PayloadConfigHeader), PayloadConfigError> {
+        let address = self
+            .syringe
+            .get_procedure_address(self.module, PAYLOAD_CONFIG_EXPORT_NAME)?
+            .ok_or(PayloadConfigError::MissingExport)?;
+        let process: BorrowedProcess<'a> = self.syringe.process();
+
+        let header_len = mem::size_of::<PayloadConfigHeader>();
+        let header = unsafe {
+            ProcessMemorySlice::from_raw_parts(address as *mut u8, header_len, process)
+                .read_struct::<PayloadConfigHeader>(0)?
+        };
+        let buffer = unsafe {
+            ProcessMemorySlice::from_raw_parts(
+                address as *mut u8,
+                header_len + header.capacity as usize,
+                process,
+            )
+        };
+        Ok((buffer, header))
+    }
+}
+
+#[cfg(feature = "rpc-raw")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-raw")))]
+impl InjectedModule<'_> {
+    /// Loads an exported function of this module.
+    /// See [`Syringe::get_raw_procedure`].
+    ///
+    /// # Safety
+    /// The target function must abide by the given signature.
+    pub unsafe fn get_raw_procedure<F: RawRpcFunctionPtr>(
+        &self,
+        name: &str,
+    ) -> Result<Option<RemoteRawProcedure<F>>, LoadProcedureError> {
+        unsafe { self.syringe.get_raw_procedure(self.module, name) }
+    }
+}
+
+#[cfg(feature = "rpc-payload")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-payload")))]
+impl InjectedModule<'_> {
+    /// Loads an exported function of this module declared using the [`payload_procedure!`](crate::payload_procedure) macro.
+    /// See [`Syringe::get_payload_procedure`].
+    ///
+    /// # Safety
+    /// The target function must abide by the given signature and has to be declared using the [`payload_procedure!`](crate::payload_procedure) macro.
+    #[allow(rustdoc::broken_intra_doc_links)]
+    pub unsafe fn get_payload_procedure<F: PayloadRpcFunctionPtr>(
+        &self,
+        name: &str,
+    ) -> Result<Option<RemotePayloadProcedure<F>>, LoadProcedureError> {
+        unsafe { self.syringe.get_payload_procedure(self.module, name) }
+    }
+}
@@ -0,0 +1,743 @@
+use std::{
+    cell::{Cell, RefCell},
+    io,
+    ops::Range,
+    rc::{Rc, Weak},
+    time::SystemTime,
+};
+
+use crate::{
+    error::HookError,
+    function::RawFunctionPtr,
+    hooks::IatHook,
+    process::{
+        memory::{
+            write_patch_bytes, MemoryProtection, MemoryRegionIter, Patch, ProcessMemoryBuffer,
+            ProcessMemorySlice,
+        },
+        BorrowedProcess, BorrowedProcessModule, ProcessId,
+    },
+    utils::trace_event,
+    Syringe,
+};
+
+#[cfg(feature = "assembler")]
+use crate::hooks::InlineHook;
+
+thread_local! {
+    // The journals of the syringes on this thread together with the process they belong to.
+    // A syringe can not be sent to another thread, so only the modifications made on its thread are recorded.
+    static JOURNALS: RefCell<Vec<(ProcessId, Weak<RefCell<ModificationJournal>>)>> = const { RefCell::new(Vec::new()) };
+    // The memory of the remote allocators on this thread, which holds allocations like `RemoteBox`es.
+    // It is freed by its allocator, so writes to it are recorded without being undone.
+    static ALLOCATOR_MEMORY: RefCell<Vec<(ProcessId, Range<usize>)>> = const { RefCell::new(Vec::new()) };
+    // The kind of the hook that is being installed on this thread, see `record_as`.
+    static HOOK_KIND: Cell<Option<ModificationKind>> = const { Cell::new(None) };
+    // Whether this thread is undoing modifications, which must not be recorded themselves.
+    static UNDOING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The kind of a modification of the target process recorded in the journal of a [`Syringe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+#[cfg_attr(
+    feature = "doc-cfg",
+    doc(cfg(all(feature = "syringe", feature = "process-memory")))
+)]
+pub enum ModificationKind {
+    /// Bytes were written, e.g. using [`Syringe::write_memory`], a [`ProcessMemorySlice`] or a
+    /// [`RemoteBox`](crate::process::memory::RemoteBox).
+    Write,
+    /// Memory was allocated, e.g. using [`Syringe::allocate_memory`] or a [`ProcessMemoryBuffer`].
+    Allocation,
+    /// The protection of memory was changed, see [`Syringe::protect_memory`].
+    ProtectionChange {
+        /// The protection of the first page of the memory before the change.
+        previous: MemoryProtection,
+        /// The protection the memory was changed to.
+        new: MemoryProtection,
+    },
+    /// An import address table slot was redirected, see [`IatHook`].
+    IatHook,
+    /// The prologue of a function was overwritten with a jump or its trampoline was allocated and written, see
+    /// [`InlineHook`](crate::hooks::InlineHook).
+    InlineHook,
+}
+
+/// A modification of the target process recorded in the journal of a [`Syringe`], see [`Syringe::set_journaling`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "doc-cfg",
+    doc(cfg(all(feature = "syringe", feature = "process-memory")))
+)]
+pub struct JournalEntry {
+    kind: ModificationKind,
+    address: usize,
+    len: usize,
+    time: SystemTime,
+    undoable: bool,
+}
+
+impl JournalEntry {
+    /// Returns the kind of this modification.
+    #[must_use]
+    pub const fn kind(&self) -> ModificationKind {
+        self.kind
+    }
+
+    /// Returns the address of the modified memory in the target process.
+    #[must_use]
+    pub const fn address(&self) -> usize {
+        self.address
+    }
+
+    /// Returns the length of the modified memory in bytes.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether no bytes were modified.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the time the modification was made.
+    #[must_use]
+    pub const fn time(&self) -> SystemTime {
+        self.time
+    }
+
+    /// Returns whether this modification is undone by [`Syringe::undo_all`].
+    ///
+    /// Modifications of memory that is owned by someone else are not undone, e.g. writes to a
+    /// [`RemoteBox`](crate::process::memory::RemoteBox) or to a [`ProcessMemoryBuffer`] that was freed since,
+    /// and neither are writes whose overwritten bytes could not be read.
+    #[must_use]
+    pub const fn is_undoable(&self) -> bool {
+        self.undoable
+    }
+}
+
+/// How a recorded modification is undone.
+#[derive(Debug, Clone)]
+enum Undo {
+    // the memory is owned by someone else, who undoes the modification by freeing it.
+    Nothing,
+    RestoreBytes(Vec<u8>),
+    Free,
+    // the address, length and previous protection of each region of the memory.
+    RestoreProtection(Vec<(usize, usize, MemoryProtection)>),
+}
+
+/// The modifications made on the thread of a [`Syringe`] while journaling is enabled, in the order they were made.
+#[derive(Debug, Default)]
+pub(crate) struct ModificationJournal {
+    enabled: bool,
+    records: Vec<(JournalEntry, Undo)>,
+}
+
+impl ModificationJournal {
+    fn record(&mut self, kind: ModificationKind, memory: ProcessMemorySlice<'_>, undo: Undo) {
+        if !self.enabled {
+            return;
+        }
+        trace_event!(trace, kind = ?kind, address = ?memory.as_ptr(), len = memory.len(), "recorded modification");
+        self.records.push((
+            JournalEntry {
+                kind,
+                address: memory.as_ptr() as usize,
+                len: memory.len(),
+                time: SystemTime::now(),
+                undoable: !matches!(undo, Undo::Nothing),
+            },
+            undo,
+        ));
+    }
+
+    fn forget(&mut self, range: &Range<usize>) {
+        for (entry, undo) in &mut self.records {
+            if range.contains(&entry.address) {
+                entry.undoable = false;
+                *undo = Undo::Nothing;
+            }
+        }
+    }
+}
+
+/// A write to the memory of a process, which is recorded by the journals on this thread once it was made.
+pub(crate) struct PendingWrite<'a> {
+    journals: Vec<Rc<RefCell<ModificationJournal>>>,
+    memory: ProcessMemorySlice<'a>,
+    undo: Undo,
+}
+
+impl<'a> PendingWrite<'a> {
+    /// Prepares recording a write to the given memory, reading the bytes it overwrites if they have to be restored.
+    pub(crate) fn new(memory: ProcessMemorySlice<'a>) -> Self {
+        let (journals, undo) = match recording_process_id(memory) {
+            Some(id) if !memory.is_empty() => {
+                let journals = journals_of(&id, true);
+                let undo = if journals.is_empty() || is_allocator_memory(&id, memory) {
+                    Undo::Nothing
+                } else {
+                    match memory.read_vec(0, memory.len()) {
+                        Ok(bytes) => Undo::RestoreBytes(bytes),
+                        // the write itself may still succeed, it is just recorded without being undoable.
+                        Err(err) => {
+                            trace_event!(warn, address = ?memory.as_ptr(), len = memory.len(), %err, "failed to read the bytes overwritten by a recorded write");
+                            Undo::Nothing
+                        }
+                    }
+                };
+                (journals, undo)
+            }
+            _ => (Vec::new(), Undo::Nothing),
+        };
+        Self {
+            journals,
+            memory,
+            undo,
+        }
+    }
+
+    /// Records the write, which was made successfully.
+    pub(crate) fn commit(self) {
+        let kind = HOOK_KIND.get().unwrap_or(ModificationKind::Write);
+        for journal in self.journals {
+            journal
+                .borrow_mut()
+                .record(kind, self.memory, self.undo.clone());
+        }
+    }
+}
+
+/// Records the given memory, which was just allocated, in the journals on this thread.
+pub(crate) fn record_allocation(memory: ProcessMemorySlice<'_>) {
+    let Some(id) = recording_process_id(memory) else {
+        return;
+    };
+    let kind = HOOK_KIND.get().unwrap_or(ModificationKind::Allocation);
+    for journal in journals_of(&id, true) {
+        journal.borrow_mut().record(kind, memory, Undo::Free);
+    }
+}
+
+/// Stops undoing the modifications of the given memory, which is about to be freed by its owner.
+pub(crate) fn forget_memory(memory: ProcessMemorySlice<'_>) {
+    let Some(id) = journaling_process_id(memory) else {
+        return;
+    };
+    forget(&id, &address_range(memory));
+}
+
+/// Stops undoing the modifications of the given allocation of a remote allocator, which is about to be freed and may be
+/// handed out again, e.g. because it was allocated before its memory was registered.
+pub(crate) fn forget_allocation(memory: ProcessMemorySlice<'_>) {
+    let Some(id) = journaling_process_id(memory) else {
+        return;
+    };
+    for journal in journals_of(&id, false) {
+        journal.borrow_mut().forget(&address_range(memory));
+    }
+}
+
+/// Marks the given memory as owned by a remote allocator, so writes to it are not undone.
+/// Without journals on this thread there is nothing to mark, so a syringe registers the memory of its allocator once
+/// journaling is enabled.
+pub(crate) fn register_allocator_memory(memory: ProcessMemorySlice<'_>) {
+    let Some(id) = journaling_process_id(memory) else {
+        return;
+    };
+    let range = address_range(memory);
+    // the allocation of the memory itself is undone by the allocator as well.
+    forget(&id, &range);
+    ALLOCATOR_MEMORY.with_borrow_mut(|allocator_memory| allocator_memory.push((id, range)));
+}
+
+/// Records the modifications made on this thread as the given kind of hook until the returned guard is dropped.
+pub(crate) fn record_as(kind: ModificationKind) -> HookKindGuard {
+    HookKindGuard(HOOK_KIND.replace(Some(kind)))
+}
+
+/// Restores the kind modifications were recorded as before [`record_as`] was called when dropped.
+pub(crate) struct HookKindGuard(Option<ModificationKind>);
+
+impl Drop for HookKindGuard {
+    fn drop(&mut self) {
+        HOOK_KIND.set(self.0);
+    }
+}
+
+// Returns the identifier of the process of the given memory if there are journals on this thread that may record modifications.
+fn recording_process_id(memory: ProcessMemorySlice<'_>) -> Option<ProcessId> {
+    if UNDOING.get() {
+        return None;
+    }
+    journaling_process_id(memory)
+}
+
+// Returns the identifier of the process of the given memory if there are journals on this thread, which may refer to it.
+fn journaling_process_id(memory: ProcessMemorySlice<'_>) -> Option<ProcessId> {
+    if !has_journals() {
+        return None;
+    }
+    // a process that can not be identified has exited, so there is nothing to record.
+    ProcessId::of(&memory.process()).ok()
+}
+
+// Returns whether a syringe on this thread has a journal, forgetting the registered allocator memory once there is none.
+fn has_journals() -> bool {
+    let has_journals = JOURNALS.with_borrow_mut(|journals| {
+        journals.retain(|(_, journal)| journal.strong_count() != 0);
+        !journals.is_empty()
+    });
+    if !has_journals {
+        ALLOCATOR_MEMORY.with_borrow_mut(Vec::clear);
+    }
+    has_journals
+}
+
+fn journals_of(id: &ProcessId, recording: bool) -> Vec<Rc<RefCell<ModificationJournal>>> {
+    JOURNALS.with_borrow_mut(|journals| {
+        // drop the entries of dropped syringes, so the list does not grow with every journaling syringe.
+        journals.retain(|(_, journal)| journal.strong_count() != 0);
+        journals
+            .iter()
+            .filter(|(process_id, _)| process_id == id)
+            .filter_map(|(_, journal)| journal.upgrade())
+            .filter(|journal| !recording || journal.borrow().enabled)
+            .collect()
+    })
+}
+
+fn is_allocator_memory(id: &ProcessId, memory: ProcessMemorySlice<'_>) -> bool {
+    let range = address_range(memory);
+    ALLOCATOR_MEMORY.with_borrow(|allocator_memory| {
+        allocator_memory
+            .iter()
+            .any(|(process_id, allocator_range)| {
+                process_id == id
+                    && allocator_range.start <= range.start
+                    && range.end <= allocator_range.end
+            })
+    })
+}
+
+fn forget(id: &ProcessId, range: &Range<usize>) {
+    ALLOCATOR_MEMORY.with_borrow_mut(|allocator_memory| {
+        allocator_memory.retain(|(process_id, allocator_range)| {
+            process_id != id || !range.contains(&allocator_range.start)
+        });
+    });
+    for journal in journals_of(id, false) {
+        journal.borrow_mut().forget(range);
+    }
+}
+
+fn address_range(memory: ProcessMemorySlice<'_>) -> Range<usize> {
+    let start = memory.as_ptr() as usize;
+    start..start + memory.len()
+}
+
+// Returns the address, length and protection of each region the given memory spans.
+fn region_protections(
+    memory: ProcessMemorySlice<'_>,
+) -> Result<Vec<(usize, usize, MemoryProtection)>, io::Error> {
+    let regions = MemoryRegionIter::new(memory.process());
+    let range = address_range(memory);
+    let mut protections = Vec::new();
+    let mut address = range.start;
+    while address < range.end {
+        let region = regions
+            .query(address)?
+            .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+        let region_end = region.base() as usize + region.size();
+        protections.push((
+            address,
+            region_end.min(range.end) - address,
+            region.protection(),
+        ));
+        address = region_end;
+    }
+    Ok(protections)
+}
+
+impl Syringe {
+    /// Enables or disables the journal of modifications of the target process, which is disabled by default.
+    ///
+    /// While enabled, every write, allocation and hook made on the thread of this syringe is recorded, whether it was made
+    /// through this syringe (e.g. [`Syringe::write_memory`]), a [`ProcessMemorySlice`], a
+    /// [`RemoteBox`](crate::process::memory::RemoteBox), an [`IatHook`] or an [`InlineHook`](crate::hooks::InlineHook),
+    /// as well as the protection changes made using [`Syringe::protect_memory`].
+    /// The modifications can be inspected using [`Syringe::journal`] and undone using [`Syringe::undo_all`].
+    /// Modifications of memory that is owned by someone else, like the memory of remote allocations or the stubs of the
+    /// syringe, are recorded but not undone, see [`JournalEntry::is_undoable`].
+    /// Disabling the journal keeps the recorded modifications.
+    pub fn set_journaling(&mut self, enabled: bool) -> Result<(), io::Error> {
+        if enabled {
+            let id = ProcessId::of(&self.process())?;
+            JOURNALS.with_borrow_mut(|journals| {
+                if !journals
+                    .iter()
+                    .any(|(_, journal)| journal.ptr_eq(&Rc::downgrade(&self.journal)))
+                {
+                    journals.push((id, Rc::downgrade(&self.journal)));
+                }
+            });
+            // pages allocated while there was no journal on this thread were not registered.
+            for page in self.remote_allocator.0.allocator.borrow().pages() {
+                register_allocator_memory(*page.as_slice());
+            }
+        }
+        self.journal.borrow_mut().enabled = enabled;
+        Ok(())
+    }
+
+    /// Returns whether modifications made through this syringe are recorded, see [`Syringe::set_journaling`].
+    #[must_use]
+    pub fn is_journaling(&self) -> bool {
+        self.journal.borrow().enabled
+    }
+
+    /// Returns the recorded modifications in the order they were made.
+    #[must_use]
+    pub fn journal(&self) -> Vec<JournalEntry> {
+        self.journal
+            .borrow()
+            .records
+            .iter()
+            .map(|(entry, _)| *entry)
+            .collect()
+    }
+
+    /// Forgets the recorded modifications without undoing them.
+    pub fn clear_journal(&self) {
+        self.journal.borrow_mut().records.clear();
+    }
+
+    /// Writes the given bytes to the given memory at the given offset, temporarily changing the protection of read-only pages.
+    ///
+    /// # Panics
+    /// This method panics if the given memory is not from the target process or the given offset plus the length of the given
+    /// bytes exceeds the length of the given memory.
+    pub fn write_memory(
+        &self,
+        memory: ProcessMemorySlice<'_>,
+        offset: usize,
+        bytes: &[u8],
+    ) -> Result<(), io::Error> {
+        self.assert_target_memory(memory);
+        Patch::apply(memory, offset, bytes)?.keep();
+        Ok(())
+    }
+
+    /// Allocates memory of the given length with the given protection in the target process, which stays allocated until it is
+    /// freed by undoing the allocation or by the target process.
+    pub fn allocate_memory(
+        &self,
+        len: usize,
+        protection: MemoryProtection,
+    ) -> Result<ProcessMemorySlice<'_>, io::Error> {
+        Ok(ProcessMemoryBuffer::allocate_with_protection(self.process(), len, protection)?.leak())
+    }
+
+    /// Changes the protection of all pages containing the given memory and returns the previous protection of its first page.
+    /// Undoing the change restores the previous protection of each region of the memory.
+    ///
+    /// # Panics
+    /// This method panics if the given memory is not from the target process.
+    ///
+    /// # Safety
+    /// The caller must ensure that changing the protection does not break code in the target process (e.g. by making memory it is using inaccessible).
+    pub unsafe fn protect_memory(
+        &self,
+        memory: ProcessMemorySlice<'_>,
+        protection: MemoryProtection,
+    ) -> Result<MemoryProtection, io::Error> {
+        self.assert_target_memory(memory);
+        let regions = if self.is_journaling() {
+            region_protections(memory)?
+        } else {
+            Vec::new()
+        };
+        let previous = unsafe { memory.set_protection(protection) }?;
+        self.journal.borrow_mut().record(
+            ModificationKind::ProtectionChange {
+                previous,
+                new: protection,
+            },
+            memory,
+            Undo::RestoreProtection(regions),
+        );
+        Ok(previous)
+    }
+
+    /// Redirects an import of the given module to the given replacement like [`IatHook::install`] and returns the function the
+    /// import pointed to before. Unlike an [`IatHook`], the hook stays installed until it is removed by undoing it.
+    ///
+    /// # Panics
+    /// This method panics if the given module is not from the target process.
+    pub fn install_iat_hook(
+        &self,
+        module: BorrowedProcessModule<'_>,
+        import_module_name: &str,
+        import_name: &str,
+        replacement: RawFunctionPtr,
+    ) -> Result<RawFunctionPtr, HookError> {
+        assert!(
+            module.process() == &self.process(),
+            "trying to hook a module from a different process"
+        );
+        let hook = IatHook::install(module, import_module_name, import_name, replacement)?;
+        let original_function = hook.original();
+        hook.keep();
+        Ok(original_function)
+    }
+
+    /// Redirects the given target function to the given handler like [`InlineHook::install`] and returns the trampoline calling
+    /// the original function. Unlike an [`InlineHook`], the hook stays installed until it is removed by undoing it.
+    ///
+    /// # Safety
+    /// See [`InlineHook::install`].
+    #[cfg(feature = "assembler")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "assembler")))]
+    pub unsafe fn install_inline_hook(
+        &self,
+        target: RawFunctionPtr,
+        handler: RawFunctionPtr,
+    ) -> Result<RawFunctionPtr, HookError> {
+        let hook = unsafe { InlineHook::install(self.process(), target, handler) }?;
+        let trampoline = hook.trampoline();
+        hook.keep();
+        Ok(trampoline)
+    }
+
+    /// Undoes all recorded modifications in reverse order, i.e. restores overwritten bytes, frees allocations, restores
+    /// protections and removes hooks, and returns the number of undone modifications.
+    /// Modifications that are not undoable are forgotten without being counted, see [`JournalEntry::is_undoable`].
+    ///
+    /// If a modification can not be undone, the error is returned and it stays recorded together with the modifications made
+    /// before it, so undoing can be retried.
+    ///
+    /// # Note
+    /// No thread of the target process may be executing hooked code or code in freed memory while it is undone (e.g. by suspending the process).
+    pub fn undo_all(&self) -> Result<usize, io::Error> {
+        struct Undoing;
+        impl Drop for Undoing {
+            fn drop(&mut self) {
+                UNDOING.set(false);
+            }
+        }
+        UNDOING.set(true);
+        let _undoing = Undoing;
+
+        let mut undone = 0;
+        loop {
+            // the journal is not borrowed while undoing, since freeing memory updates it.
+            let Some((entry, undo)) = self.journal.borrow_mut().records.pop() else {
+                break;
+            };
+            if let Err(err) = undo_modification(self.process(), &entry, &undo) {
+                self.journal.borrow_mut().records.push((entry, undo));
+                return Err(err);
+            }
+            if entry.undoable {
+                undone += 1;
+            }
+        }
+        trace_event!(debug, undone, "undid recorded modifications");
+        Ok(undone)
+    }
+
+    fn assert_target_memory(&self, memory: ProcessMemorySlice<'_>) {
+        assert!(
+            memory.process() == self.process(),
+            "trying to modify memory of a different process"
+        );
+    }
+}
+
+fn undo_modification(
+    process: BorrowedProcess<'_>,
+    entry: &JournalEntry,
+    undo: &Undo,
+) -> Result<(), io::Error> {
+    let memory =
+        unsafe { ProcessMemorySlice::from_raw_parts(entry.address as *mut u8, entry.len, process) };
+    match undo {
+        Undo::Nothing => Ok(()),
+        Undo::RestoreBytes(original) => write_patch_bytes(memory, original),
+        Undo::Free => free(memory),
+        Undo::RestoreProtection(regions) => {
+            for &(address, len, protection) in regions.iter().rev() {
+                let region =
+                    unsafe { ProcessMemorySlice::from_raw_parts(address as *mut u8, len, process) };
+                unsafe { region.set_protection(protection) }?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn free(memory: ProcessMemorySlice<'_>) -> Result<(), io::Error> {
+    let buffer = unsafe {
+        ProcessMemoryBuffer::from_raw_parts(memory.as_ptr(), memory.len(), memory.process())
+    };
+    buffer.free().map_err(|(buffer, err)| {
+        // the memory stays recorded, so the buffer must not free it when dropped.
+        buffer.leak();
+        err
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{
+        memory::{MemoryState, RemoteBoxAllocator},
+        OwnedProcess, Process,
+    };
+    use winapi::um::winnt::{PAGE_NOACCESS, PAGE_READONLY, PAGE_READWRITE};
+
+    #[test]
+    fn undo_all_restores_target_in_reverse_order() {
+        let mut syringe = Syringe::for_current_process();
+        let buffer = ProcessMemoryBuffer::allocate_data(BorrowedProcess::current(), 4).unwrap();
+        buffer.write(0, &[1, 2, 3, 4]).unwrap();
+
+        // modifications made before journaling is enabled are not recorded.
+        syringe.write_memory(*buffer.as_slice(), 0, &[5]).unwrap();
+        syringe.set_journaling(true).unwrap();
+        syringe
+            .write_memory(*buffer.as_slice(), 1, &[6, 6])
+            .unwrap();
+        syringe
+            .write_memory(*buffer.as_slice(), 2, &[7, 7])
+            .unwrap();
+        let allocation = syringe
+            .allocate_memory(16, MemoryProtection(PAGE_READWRITE))
+            .unwrap();
+        let previous =
+            unsafe { syringe.protect_memory(*buffer.as_slice(), MemoryProtection(PAGE_READONLY)) }
+                .unwrap();
+        assert_eq!(previous, MemoryProtection(PAGE_READWRITE));
+
+        let kinds = syringe
+            .journal()
+            .iter()
+            .map(JournalEntry::kind)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                ModificationKind::Write,
+                ModificationKind::Write,
+                ModificationKind::Allocation,
+                ModificationKind::ProtectionChange {
+                    previous,
+                    new: MemoryProtection(PAGE_READONLY)
+                },
+            ]
+        );
+
+        assert_eq!(syringe.undo_all().unwrap(), 4);
+        assert!(syringe.journal().is_empty());
+        assert_eq!(buffer.read_vec(0, 4).unwrap(), [5, 2, 3, 4]);
+        let allocation_region = MemoryRegionIter::new(BorrowedProcess::current())
+            .query(allocation.as_ptr() as usize)
+            .unwrap()
+            .unwrap();
+        assert_eq!(allocation_region.state(), MemoryState::Free);
+    }
+
+    #[test]
+    fn writes_through_memory_slices_and_remote_boxes_are_recorded() {
+        let mut syringe = Syringe::for_current_process();
+        let remote_box = syringe.alloc_box(&0u32).unwrap();
+        let buffer = ProcessMemoryBuffer::allocate_data(BorrowedProcess::current(), 4).unwrap();
+        syringe.set_journaling(true).unwrap();
+
+        buffer.write(0, &[1, 2]).unwrap();
+        remote_box.write(&7).unwrap();
+
+        let journal = syringe.journal();
+        assert_eq!(journal.len(), 2);
+        assert_eq!(journal[0].kind(), ModificationKind::Write);
+        assert_eq!(journal[0].address(), buffer.as_ptr() as usize);
+        assert!(journal[0].is_undoable());
+        // the memory of the box is owned by the allocator of the syringe, which frees it.
+        assert_eq!(journal[1].address(), remote_box.as_raw_ptr() as usize);
+        assert!(!journal[1].is_undoable());
+
+        assert_eq!(syringe.undo_all().unwrap(), 1);
+        assert_eq!(buffer.read_vec(0, 2).unwrap(), [0, 0]);
+        assert_eq!(remote_box.read().unwrap(), 7);
+    }
+
+    #[test]
+    fn writes_to_freed_memory_are_not_undone() {
+        let mut syringe = Syringe::for_current_process();
+        syringe.set_journaling(true).unwrap();
+
+        let buffer = ProcessMemoryBuffer::allocate_data(BorrowedProcess::current(), 4).unwrap();
+        buffer.write(0, &[1]).unwrap();
+        drop(buffer);
+
+        let journal = syringe.journal();
+        assert_eq!(journal.len(), 2);
+        assert_eq!(journal[0].kind(), ModificationKind::Allocation);
+        assert!(journal.iter().all(|entry| !entry.is_undoable()));
+        assert_eq!(syringe.undo_all().unwrap(), 0);
+    }
+
+    #[test]
+    fn writes_to_freed_remote_boxes_of_other_allocators_are_not_undone() {
+        // the page of this allocator is allocated before there is a journal on this thread, so it is not registered.
+        let allocator = RemoteBoxAllocator::new(OwnedProcess::current());
+        let remote_box = allocator.alloc_and_copy(&0u32).unwrap();
+        let mut syringe = Syringe::for_current_process();
+        syringe.set_journaling(true).unwrap();
+
+        remote_box.write(&7).unwrap();
+        assert!(syringe.journal()[0].is_undoable());
+        drop(remote_box);
+
+        // the memory of the box may be handed out again, so the write must not be undone.
+        assert!(!syringe.journal()[0].is_undoable());
+        assert_eq!(syringe.undo_all().unwrap(), 0);
+    }
+
+    #[test]
+    fn undoing_a_protection_change_restores_each_region() {
+        let mut syringe = Syringe::for_current_process();
+        let page_size = ProcessMemoryBuffer::os_page_size();
+        let buffer =
+            ProcessMemoryBuffer::allocate_data(BorrowedProcess::current(), 2 * page_size).unwrap();
+        let second_page = buffer.as_slice().slice(page_size..);
+        unsafe { second_page.set_protection(MemoryProtection(PAGE_READONLY)) }.unwrap();
+
+        syringe.set_journaling(true).unwrap();
+        unsafe { syringe.protect_memory(*buffer.as_slice(), MemoryProtection(PAGE_NOACCESS)) }
+            .unwrap();
+        assert_eq!(syringe.undo_all().unwrap(), 1);
+
+        let regions = MemoryRegionIter::new(BorrowedProcess::current());
+        let protection_at = |address: *mut u8| {
+            regions
+                .query(address as usize)
+                .unwrap()
+                .unwrap()
+                .protection()
+        };
+        assert_eq!(
+            protection_at(buffer.as_ptr()),
+            MemoryProtection(PAGE_READWRITE)
+        );
+        assert_eq!(
+            protection_at(second_page.as_ptr()),
+            MemoryProtection(PAGE_READONLY)
+        );
+    }
+}
@@ -32,14 +32,140 @@ mod syringe;
 #[cfg(feature = "syringe")]
 pub use syringe::*;
 
+#[cfg(feature = "syringe")]
+mod inject_options;
+#[cfg(feature = "syringe")]
+pub use inject_options::*;
+
+#[cfg(feature = "syringe")]
+mod syringe_events;
+#[cfg(feature = "syringe")]
+pub use syringe_events::*;
+
+#[cfg(feature = "syringe")]
+mod child_injector;
+#[cfg(feature = "syringe")]
+pub use child_injector::*;
+
+#[cfg(feature = "syringe")]
+mod debug_injector;
+#[cfg(feature = "syringe")]
+pub use debug_injector::*;
+
+#[cfg(feature = "syringe")]
+mod injected_module;
+#[cfg(feature = "syringe")]
+pub use injected_module::*;
+
+#[cfg(feature = "syringe")]
+mod context_thread;
+
+#[cfg(feature = "syringe")]
+mod missing_dependency;
+
+#[cfg(feature = "syringe")]
+mod payload_policy;
+
+#[cfg(feature = "syringe")]
+mod remote_worker;
+
+#[cfg(feature = "syringe")]
+mod stub_info;
+#[cfg(feature = "syringe")]
+pub use stub_info::*;
+
+#[cfg(feature = "syringe")]
+mod resource_usage;
+#[cfg(feature = "syringe")]
+pub use resource_usage::*;
+
+#[cfg(all(feature = "syringe", any(not(feature = "assembler"), test)))]
+mod stub_templates;
+
+#[cfg(feature = "syringe")]
+mod syringe_set;
+#[cfg(feature = "syringe")]
+pub use syringe_set::*;
+
+#[cfg(feature = "syringe")]
+mod hot_reload;
+#[cfg(feature = "syringe")]
+pub use hot_reload::*;
+
+#[cfg(feature = "syringe")]
+mod broadcast;
+#[cfg(feature = "syringe")]
+pub use broadcast::*;
+
+#[cfg(feature = "syringe")]
+mod payload_bundle;
+#[cfg(feature = "syringe")]
+pub use payload_bundle::*;
+
+#[cfg(all(feature = "syringe", feature = "serde"))]
+mod profile;
+#[cfg(all(feature = "syringe", feature = "serde"))]
+pub use profile::*;
+
+#[cfg(feature = "rpc-core")]
+mod payload_log;
+#[cfg(feature = "rpc-core")]
+pub use payload_log::*;
+
+#[cfg(all(feature = "syringe", feature = "process-memory"))]
+mod journal;
+#[cfg(all(feature = "syringe", feature = "process-memory"))]
+pub use journal::*;
+
+#[cfg(any(feature = "syringe", feature = "process-memory"))]
+mod cancellation;
+#[cfg(any(feature = "syringe", feature = "process-memory"))]
+pub use cancellation::*;
+
+#[cfg(feature = "dotnet")]
+mod dotnet;
+#[cfg(feature = "dotnet")]
+pub use dotnet::*;
+
 /// Module containing process abstractions and utilities.
 pub mod process;
+pub use process::{enable_debug_privilege, grant_app_container_access};
 
 #[cfg(feature = "rpc-core")]
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-core")))]
 /// Module containing traits and structs regarding remote procedures.
 pub mod rpc;
 
+#[cfg(feature = "process-memory")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+/// Module containing utilities for hooking functions in another process.
+pub mod hooks;
+
+#[cfg(feature = "syringe")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+/// Module containing the builders for the stubs the syringe runs in another process, which can be placed in the target using [`Syringe::alloc_shellcode`].
+pub mod shellcode;
+
+#[cfg(feature = "capi")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "capi")))]
+/// Module containing a flat C API for hosts written in other languages.
+///
+/// The functions are exported unmangled, so a host can link against a `cdylib` build of this crate, e.g. built using
+/// `cargo rustc --release --features capi --crate-type cdylib`. The matching header `include/dll_syringe.h` is generated
+/// from this module using `cbindgen` with the `cbindgen.toml` of the repository.
+///
+/// A syringe created by [`syringe_open`](capi::syringe_open) is bound to the thread that created it.
+/// All functions return a [`SyringeStatus`](capi::SyringeStatus) and never unwind into the caller.
+/// The message of the last error on the calling thread can be retrieved using
+/// [`syringe_last_error_message`](capi::syringe_last_error_message).
+pub mod capi;
+
+#[cfg(feature = "test-support")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "test-support")))]
+/// Module containing helpers for integration tests of payloads, which build the payload and a dummy target process for every
+/// supported architecture, see [`syringe_test!`].
+pub mod test_support;
+
 pub(crate) mod utils;
 
 /// Module containing the error enums used in this crate.
@@ -59,4 +185,46 @@ pub(crate) struct ArgAndResultBufInfo {
     pub data: u64,
     pub len: u64,
     pub is_error: bool,
+    /// Set together with `is_error` if the procedure panicked, in which case the data holds the serialized message and location.
+    pub is_panic: bool,
+    /// Set if the data holds staged arguments that are passed to further calls, so the result must not be written over them.
+    pub preserve_data: bool,
+}
+
+/// The name of the export defined by the `payload_config!` macro.
+#[cfg(feature = "rpc-core")]
+pub(crate) const PAYLOAD_CONFIG_EXPORT_NAME: &str = "DLL_SYRINGE_CONFIG";
+
+/// The name of the export defined by the `payload_log_sink!` macro.
+#[cfg(feature = "rpc-core")]
+pub(crate) const LOG_SINK_EXPORT_NAME: &str = "DLL_SYRINGE_SET_LOG_SINK";
+
+/// The header preceding the data of a payload configuration buffer.
+#[cfg(any(feature = "payload-utils", feature = "rpc-core"))]
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub(crate) struct PayloadConfigHeader {
+    pub capacity: u32,
+    pub len: u32,
+}
+
+/// The name of the export defined by the `payload_marker!` macro.
+#[cfg(feature = "syringe")]
+pub(crate) const PAYLOAD_MARKER_EXPORT_NAME: &str = "DLL_SYRINGE_PAYLOAD_MARKER";
+
+/// The name of the export defined by the `payload_self_unload!` macro.
+#[cfg(feature = "syringe")]
+pub(crate) const SELF_UNLOAD_EXPORT_NAME: &str = "DLL_SYRINGE_SELF_UNLOAD";
+
+/// The maximum length of the id of a payload marker in bytes.
+#[cfg(any(feature = "payload-utils", feature = "syringe"))]
+pub(crate) const PAYLOAD_MARKER_CAPACITY: usize = 64;
+
+/// The id embedded into a payload by the `payload_marker!` macro, which identifies it independent of its file name.
+#[cfg(any(feature = "payload-utils", feature = "syringe"))]
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+pub(crate) struct PayloadMarkerData {
+    pub len: u32,
+    pub id: [u8; PAYLOAD_MARKER_CAPACITY],
 }
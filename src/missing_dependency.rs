@@ -0,0 +1,173 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    env, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    process::{
+        memory::with_image_file, system_windows_dir, ApiSetSchema, BorrowedProcess, ModuleSnapshot,
+        Process,
+    },
+    utils::redirect_system_path,
+};
+
+/// A statically imported dependency of a payload that the target process cannot find.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MissingDependency {
+    /// The name of the missing module as it appears in the import table.
+    pub name: String,
+    /// The path of the module importing the missing module.
+    pub importer: PathBuf,
+}
+
+/// Searches the imports of the given payload and, recursively, of its dependencies for a module the target process cannot find.
+///
+/// Dependencies are looked up like `LoadLibraryW` in the target process would: among its loaded modules and in the directory of its executable,
+/// its system and windows directories and the directories on its `PATH`. Note that the directory of the payload itself is not part of that search path.
+/// The working directory of the target and directories added using `AddDllDirectory` are not searched and delay-loaded imports are ignored.
+pub(crate) fn find_missing_dependency(
+    process: BorrowedProcess<'_>,
+    payload_path: &Path,
+) -> Result<Option<MissingDependency>, io::Error> {
+    let search_path = DllSearchPath::of(process)?;
+    let loaded_modules = process.module_snapshot()?;
+    let api_set_schema = ApiSetSchema::read(process)?;
+
+    let mut visited = HashSet::new();
+    let mut pending = VecDeque::from([payload_path.to_path_buf()]);
+    while let Some(importer) = pending.pop_front() {
+        let importer_name = importer
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        for module_name in imported_module_names(&importer)? {
+            let host_name = if is_api_set(&module_name) {
+                // contracts cannot be resolved without a supported schema, so assume they are hosted.
+                let Some(schema) = &api_set_schema else {
+                    continue;
+                };
+                match schema.resolve(&module_name, &importer_name) {
+                    Some(host_name) => host_name,
+                    None => {
+                        return Ok(Some(MissingDependency {
+                            name: module_name,
+                            importer,
+                        }))
+                    }
+                }
+            } else {
+                module_name.clone()
+            };
+
+            // loaded modules already have all their dependencies loaded as well.
+            if !visited.insert(host_name.to_ascii_lowercase())
+                || is_loaded(&loaded_modules, &host_name)
+            {
+                continue;
+            }
+            match search_path.find(&host_name) {
+                Some(path) => pending.push_back(path),
+                None => {
+                    return Ok(Some(MissingDependency {
+                        name: module_name,
+                        importer,
+                    }))
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the names of the modules statically imported by the module at the given path, in the order of its import directory.
+fn imported_module_names(path: &Path) -> Result<Vec<String>, io::Error> {
+    let imports = with_image_file(path, |image| image.imports())?;
+
+    let mut names = Vec::<String>::new();
+    for import in imports {
+        if !names.contains(&import.module_name) {
+            names.push(import.module_name);
+        }
+    }
+    Ok(names)
+}
+
+fn is_api_set(module_name: &str) -> bool {
+    let module_name = module_name.to_ascii_lowercase();
+    module_name.starts_with("api-") || module_name.starts_with("ext-")
+}
+
+fn is_loaded(loaded_modules: &ModuleSnapshot, module_name: &str) -> bool {
+    loaded_modules.modules().iter().any(|module| {
+        module
+            .path()
+            .file_name()
+            .is_some_and(|name| name.eq_ignore_ascii_case(module_name))
+    })
+}
+
+/// The directories the target process searches for the dependencies of a module loaded by `LoadLibraryW`, as seen by the current process.
+#[derive(Debug, Clone)]
+struct DllSearchPath {
+    dirs: Vec<PathBuf>,
+}
+
+impl DllSearchPath {
+    fn of(process: BorrowedProcess<'_>) -> Result<Self, io::Error> {
+        let windows_dir = system_windows_dir()?;
+        let from_wow64 = process.runs_under_wow64()?;
+        let to_wow64 = BorrowedProcess::current().runs_under_wow64()?;
+        // e.g. System32 of a 32-bit target is SysWOW64 for a 64-bit injector.
+        let translate = |path: PathBuf| {
+            redirect_system_path(&path, &windows_dir, from_wow64, to_wow64).unwrap_or(path)
+        };
+
+        let mut dirs = Vec::new();
+        if let Some(exe_dir) = process.path()?.parent() {
+            dirs.push(exe_dir.to_path_buf());
+        }
+        dirs.push(translate(windows_dir.join("System32")));
+        dirs.push(windows_dir.clone());
+        if let Some((_, path)) = process
+            .environment()?
+            .into_iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("PATH"))
+        {
+            dirs.extend(env::split_paths(&path).map(translate));
+        }
+        Ok(Self { dirs })
+    }
+
+    fn find(&self, module_name: &str) -> Option<PathBuf> {
+        self.dirs
+            .iter()
+            .map(|dir| dir.join(module_name))
+            .find(|path| path.is_file())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn system_modules_have_no_missing_dependencies() {
+        let process = BorrowedProcess::current();
+        let kernel32 = process
+            .find_module_by_name("kernel32.dll")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            find_missing_dependency(process, &kernel32.path().unwrap()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn api_sets_are_recognized() {
+        assert!(is_api_set("api-ms-win-core-synch-l1-2-0.dll"));
+        assert!(is_api_set("EXT-MS-WIN-NTUSER-WINDOW-L1-1-0.dll"));
+        assert!(!is_api_set("kernel32.dll"));
+    }
+}
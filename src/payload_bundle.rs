@@ -0,0 +1,220 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    error::{EjectError, InjectError},
+    process::{ModuleHandle, ProcessModule},
+    utils::trace_event,
+    InjectOptions, InjectedModule, Syringe,
+};
+
+/// A set of plugin payloads that depend on a shared runtime payload, e.g. a common hooking framework.
+///
+/// The runtime is injected once per target before the first plugin and ejected only after the last plugin of the bundle
+/// was ejected, see [`InjectedBundle`]. Plugins resolve the runtime through their imports by its file name, so it is always
+/// injected under its real name, even if the options of the bundle enable [randomized names](InjectOptions::with_randomized_name).
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::{process::OwnedProcess, PayloadBundle, Syringe};
+///
+/// let syringe = Syringe::for_process(OwnedProcess::find_first_by_name("target_process").unwrap());
+/// let bundle = PayloadBundle::new("hook_runtime.dll")
+///     .with_plugin("plugin_a.dll")
+///     .with_plugin("plugin_b.dll");
+/// let mut injected = bundle.inject(&syringe).unwrap();
+/// // the runtime stays loaded, as plugin_b.dll still depends on it.
+/// injected.eject_plugin("plugin_a.dll").unwrap();
+/// // ejects plugin_b.dll and the runtime afterwards.
+/// injected.eject().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub struct PayloadBundle {
+    runtime_path: PathBuf,
+    plugin_paths: Vec<PathBuf>,
+    options: InjectOptions,
+}
+
+impl PayloadBundle {
+    /// Creates a new bundle for the runtime payload at the given path without any plugins.
+    #[must_use]
+    pub fn new(runtime_path: impl AsRef<Path>) -> Self {
+        Self {
+            runtime_path: runtime_path.as_ref().to_path_buf(),
+            plugin_paths: Vec::new(),
+            options: InjectOptions::new(),
+        }
+    }
+
+    /// Adds the plugin payload at the given path, which depends on the runtime.
+    #[must_use]
+    pub fn with_plugin(mut self, plugin_path: impl AsRef<Path>) -> Self {
+        self.plugin_paths.push(plugin_path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets the options used to inject the runtime and the plugins, except that the runtime never gets a randomized name.
+    #[must_use]
+    pub fn with_inject_options(mut self, options: InjectOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Returns the path of the runtime payload.
+    #[must_use]
+    pub fn runtime_path(&self) -> &Path {
+        &self.runtime_path
+    }
+
+    /// Returns the paths of the plugin payloads in the order they are injected.
+    #[must_use]
+    pub fn plugin_paths(&self) -> &[PathBuf] {
+        &self.plugin_paths
+    }
+
+    /// Injects the runtime and afterwards all plugins of this bundle into the target process of the given syringe.
+    ///
+    /// If a plugin fails to inject, the plugins injected before it and the runtime are ejected again and the error is returned.
+    /// As the runtime is only loaded for its plugins, nothing is injected for a bundle without plugins.
+    pub fn inject<'a>(&self, syringe: &'a Syringe) -> Result<InjectedBundle<'a>, InjectError> {
+        let mut injected = InjectedBundle {
+            syringe,
+            runtime_path: self.runtime_path.clone(),
+            options: self.options.clone(),
+            runtime: None,
+            plugins: Vec::new(),
+        };
+        for plugin_path in &self.plugin_paths {
+            if let Err(err) = injected.add_plugin(plugin_path) {
+                // the injection error is more useful than a failure to clean up after it.
+                let _ = injected.eject();
+                return Err(err);
+            }
+        }
+        Ok(injected)
+    }
+}
+
+/// A [`PayloadBundle`] injected into the target process of a [`Syringe`].
+///
+/// The runtime is loaded while at least one plugin of the bundle is loaded, i.e. it counts the plugins depending on it.
+/// It is injected when the first plugin is added and ejected after the last plugin was ejected.
+/// Dropping the bundle leaves all of its modules loaded.
+#[derive(Debug)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub struct InjectedBundle<'a> {
+    syringe: &'a Syringe,
+    runtime_path: PathBuf,
+    options: InjectOptions,
+    runtime: Option<ModuleHandle>,
+    plugins: Vec<(PathBuf, ModuleHandle)>,
+}
+
+impl<'a> InjectedBundle<'a> {
+    /// Returns the syringe this bundle was injected with.
+    #[must_use]
+    pub fn syringe(&self) -> &'a Syringe {
+        self.syringe
+    }
+
+    /// Returns the runtime module or [`None`] if no plugin is loaded, in which case the runtime was ejected.
+    #[must_use]
+    pub fn runtime(&self) -> Option<InjectedModule<'a>> {
+        self.runtime.map(|module| self.injected(module))
+    }
+
+    /// Returns the loaded plugin modules together with the paths they were injected from.
+    pub fn plugins(&self) -> impl Iterator<Item = (&Path, InjectedModule<'a>)> + '_ {
+        self.plugins
+            .iter()
+            .map(|(path, module)| (path.as_path(), self.injected(*module)))
+    }
+
+    /// Returns the number of loaded plugins, i.e. the number of modules depending on the runtime.
+    #[must_use]
+    pub fn plugin_count(&self) -> usize {
+        self.plugins.len()
+    }
+
+    /// Injects the plugin from the given path, injecting the runtime first if no plugin is loaded.
+    /// If the plugin fails to inject and no other plugin is loaded, the runtime is ejected again.
+    pub fn add_plugin(
+        &mut self,
+        plugin_path: impl AsRef<Path>,
+    ) -> Result<InjectedModule<'a>, InjectError> {
+        let plugin_path = plugin_path.as_ref();
+        if self.runtime.is_none() {
+            // plugins import the runtime by its file name.
+            let options = self.options.clone().with_randomized_name(false);
+            let runtime = self
+                .syringe
+                .inject_with_options(&self.runtime_path, &options)?;
+            trace_event!(debug, runtime = %self.runtime_path.display(), module = ?runtime.handle(), "injected bundle runtime");
+            self.runtime = Some(runtime.handle());
+        }
+
+        match self.syringe.inject_with_options(plugin_path, &self.options) {
+            Ok(plugin) => {
+                self.plugins
+                    .push((plugin_path.to_path_buf(), plugin.handle()));
+                trace_event!(debug, plugin = %plugin_path.display(), dependents = self.plugins.len(), "injected bundle plugin");
+                Ok(self.injected(plugin.handle()))
+            }
+            Err(err) => {
+                if self.plugins.is_empty() {
+                    // the injection error is more useful than a failure to clean up after it.
+                    let _ = self.release_runtime();
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Ejects the plugin injected from the given path and afterwards the runtime if no other plugin depends on it.
+    /// Returns `false` if no plugin was injected from the given path.
+    pub fn eject_plugin(&mut self, plugin_path: impl AsRef<Path>) -> Result<bool, EjectError> {
+        let plugin_path = plugin_path.as_ref();
+        let Some(index) = self
+            .plugins
+            .iter()
+            .position(|(path, _)| path == plugin_path)
+        else {
+            return Ok(false);
+        };
+
+        let (_, module) = &self.plugins[index];
+        self.syringe.eject(self.injected(*module).module())?;
+        self.plugins.remove(index);
+        trace_event!(debug, plugin = %plugin_path.display(), dependents = self.plugins.len(), "ejected bundle plugin");
+        if self.plugins.is_empty() {
+            self.release_runtime()?;
+        }
+        Ok(true)
+    }
+
+    /// Ejects all plugins in reverse order of injection and afterwards the runtime.
+    ///
+    /// If a plugin fails to eject, the runtime is kept loaded for it and the error is returned.
+    pub fn eject(mut self) -> Result<(), EjectError> {
+        while let Some((_, module)) = self.plugins.last() {
+            self.syringe.eject(self.injected(*module).module())?;
+            self.plugins.pop();
+        }
+        self.release_runtime()
+    }
+
+    fn release_runtime(&mut self) -> Result<(), EjectError> {
+        if let Some(runtime) = self.runtime {
+            self.syringe.eject(self.injected(runtime).module())?;
+            self.runtime = None;
+            trace_event!(debug, runtime = %self.runtime_path.display(), "ejected bundle runtime");
+        }
+        Ok(())
+    }
+
+    fn injected(&self, module: ModuleHandle) -> InjectedModule<'a> {
+        InjectedModule::new(self.syringe, unsafe {
+            ProcessModule::new_unchecked(module, self.syringe.process())
+        })
+    }
+}
@@ -0,0 +1,177 @@
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, PipeReader},
+    sync::Arc,
+    thread::{self, JoinHandle},
+};
+
+use crate::{
+    error::PayloadLogError,
+    process::{BorrowedProcessModule, ModuleHandle},
+    utils::trace_event,
+    InjectedModule, Syringe,
+};
+
+type PayloadLogFn = dyn Fn(ModuleHandle, &str) + Send + Sync;
+
+/// The handler set by [`Syringe::set_payload_log_handler`].
+#[derive(Clone)]
+pub(crate) struct PayloadLogHandler(Arc<PayloadLogFn>);
+
+impl fmt::Debug for PayloadLogHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PayloadLogHandler").finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-core")))]
+impl Syringe {
+    /// Sets a handler for the log output of the payloads injected by this syringe from now on.
+    ///
+    /// After each successful injection, a [`PayloadLogReader`] is attached to the payload if it defines a log sink using the
+    /// `payload_log_sink!` macro and the lines it writes are passed to the handler on a background thread, together with the
+    /// handle of the module. Payloads without a log sink are injected as usual.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dll_syringe::{process::OwnedProcess, Syringe};
+    ///
+    /// let mut syringe = Syringe::for_process(OwnedProcess::find_first_by_name("target_process").unwrap());
+    /// syringe.set_payload_log_handler(|module, line| println!("[{module:?}] {line}"));
+    /// syringe.inject("injection_payload.dll").unwrap();
+    /// ```
+    pub fn set_payload_log_handler(
+        &mut self,
+        handler: impl Fn(ModuleHandle, &str) + Send + Sync + 'static,
+    ) {
+        self.payload_log_handler = Some(PayloadLogHandler(Arc::new(handler)));
+    }
+
+    /// Removes the handler set by [`Syringe::set_payload_log_handler`], so later injections do not attach a log reader.
+    /// Readers that are already attached keep passing lines to it.
+    pub fn clear_payload_log_handler(&mut self) {
+        self.payload_log_handler = None;
+    }
+
+    /// Attaches a log reader passing the lines of the given module to the handler of this syringe, if there is one.
+    pub(crate) fn attach_payload_log_handler(&self, module: BorrowedProcessModule<'_>) {
+        let Some(PayloadLogHandler(handler)) = self.payload_log_handler.clone() else {
+            return;
+        };
+        // the injection succeeded either way, so failing to attach the reader is only traced.
+        let reader = match InjectedModule::new(self, module).attach_log_reader() {
+            Ok(reader) => reader,
+            Err(PayloadLogError::MissingExport) => return,
+            Err(_err) => {
+                trace_event!(warn, module = ?module.handle(), error = %_err, "failed to attach payload log reader");
+                return;
+            }
+        };
+        // module handles are not `Send`, but the handle is only passed on as a value.
+        let handle = module.handle() as usize;
+        if let Err(_err) = reader.spawn_callback(move |line| handler(handle as ModuleHandle, &line))
+        {
+            trace_event!(warn, module = ?module.handle(), error = %_err, "failed to start payload log thread");
+        }
+    }
+}
+
+/// Reads the lines a payload writes to its log sink, see [`InjectedModule::attach_log_reader`](crate::InjectedModule::attach_log_reader).
+///
+/// The lines can be read one by one using [`PayloadLogReader::read_line`], as an iterator or passed to a callback on a background
+/// thread using [`PayloadLogReader::spawn_callback`]. Reading blocks until the payload writes a line and ends once every handle
+/// to the sink in the target process was closed, which at the latest happens when the target process exits.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::{process::OwnedProcess, Syringe};
+///
+/// let syringe = Syringe::for_process(OwnedProcess::find_first_by_name("target_process").unwrap());
+/// let module = syringe.inject("injection_payload.dll").unwrap();
+/// let log = module.attach_log_reader().unwrap();
+/// let _logger = log.spawn_callback(|line| println!("[payload] {line}")).unwrap();
+/// ```
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-core")))]
+pub struct PayloadLogReader {
+    reader: BufReader<PipeReader>,
+    buf: Vec<u8>,
+}
+
+impl fmt::Debug for PayloadLogReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PayloadLogReader").finish_non_exhaustive()
+    }
+}
+
+impl PayloadLogReader {
+    pub(crate) fn new(reader: PipeReader) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            buf: Vec::new(),
+        }
+    }
+
+    /// Blocks until the payload writes the next line and returns it without its line terminator.
+    /// Returns [`None`] once the sink was closed in the target process.
+    ///
+    /// Lines that are not valid UTF-8 are converted lossily.
+    pub fn read_line(&mut self) -> Result<Option<String>, io::Error> {
+        self.buf.clear();
+        match self.reader.read_until(b'\n', &mut self.buf) {
+            Ok(0) => return Ok(None),
+            Ok(_) => {}
+            // the pipe is broken once the payload side is closed.
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(None),
+            Err(err) => return Err(err),
+        }
+        if self.buf.ends_with(b"\n") {
+            self.buf.pop();
+            if self.buf.ends_with(b"\r") {
+                self.buf.pop();
+            }
+        }
+        Ok(Some(String::from_utf8_lossy(&self.buf).into_owned()))
+    }
+
+    /// Reads the lines of the payload on a new thread and passes each of them to the given callback until the sink is closed.
+    /// The thread returns the error that stopped it, if any.
+    pub fn spawn_callback(
+        mut self,
+        mut callback: impl FnMut(String) + Send + 'static,
+    ) -> Result<JoinHandle<Result<(), io::Error>>, io::Error> {
+        thread::Builder::new()
+            .name("dll-syringe payload log".to_string())
+            .spawn(move || {
+                while let Some(line) = self.read_line()? {
+                    callback(line);
+                }
+                Ok(())
+            })
+    }
+}
+
+impl Iterator for PayloadLogReader {
+    type Item = Result<String, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_line().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn reads_lines_until_the_writer_is_closed() {
+        let (reader, mut writer) = io::pipe().unwrap();
+        writer.write_all(b"first\r\nsecond\n\xFFthird").unwrap();
+        drop(writer);
+
+        let lines = PayloadLogReader::new(reader)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(lines, ["first", "second", "\u{FFFD}third"]);
+    }
+}
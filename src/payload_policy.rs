@@ -0,0 +1,135 @@
+use std::{
+    io,
+    path::{Component, Path, Prefix},
+    ptr,
+};
+
+use widestring::U16CString;
+use winapi::um::{
+    fileapi::GetDriveTypeW,
+    libloaderapi::{
+        FreeLibrary, LoadLibraryExW, LOAD_LIBRARY_AS_DATAFILE, LOAD_LIBRARY_AS_IMAGE_RESOURCE,
+    },
+    winbase::DRIVE_REMOTE,
+    winnt::IMAGE_DIRECTORY_ENTRY_SECURITY,
+};
+
+use crate::{
+    error::InjectError,
+    process::{
+        has_low_mandatory_label, memory::RemoteImage, system_windows_dir, BorrowedProcess,
+        MitigationPolicies, Process,
+    },
+};
+
+/// Checks whether the given mitigation policies of the target process make it refuse to load the given payload,
+/// so that the injection fails before anything is allocated or run in the target process.
+///
+/// Only violations that can be determined reliably are reported, the loader of the target process has the final say.
+pub(crate) fn check_mitigation_policies(
+    policies: &MitigationPolicies,
+    payload_path: &Path,
+) -> Result<(), InjectError> {
+    // the stubs used for injection need executable memory in the target process.
+    if policies.prohibits_dynamic_code() {
+        return Err(InjectError::DynamicCodeProhibited);
+    }
+    if (policies.microsoft_signed_only() || policies.store_signed_only())
+        && !may_pass_signature_policy(payload_path)?
+    {
+        return Err(InjectError::UnsignedModuleRejected);
+    }
+    if policies.no_remote_images() && is_remote_path(payload_path)? {
+        return Err(InjectError::ImageLoadRejected);
+    }
+    if policies.no_low_mandatory_label_images() && has_low_mandatory_label(payload_path)? {
+        return Err(InjectError::ImageLoadRejected);
+    }
+    Ok(())
+}
+
+/// Returns whether the module at the given path may be accepted by a signature policy.
+/// Modules without an embedded signature are rejected, except for those in the windows directory, which are signed through catalogs instead.
+fn may_pass_signature_policy(path: &Path) -> Result<bool, io::Error> {
+    if starts_with_ignore_ascii_case(path, &system_windows_dir()?) {
+        return Ok(true);
+    }
+    has_embedded_signature(path)
+}
+
+fn starts_with_ignore_ascii_case(path: &Path, base: &Path) -> bool {
+    let mut components = path.components();
+    base.components().all(|base_component| {
+        components.next().is_some_and(|component| {
+            component
+                .as_os_str()
+                .eq_ignore_ascii_case(base_component.as_os_str())
+        })
+    })
+}
+
+fn has_embedded_signature(path: &Path) -> Result<bool, io::Error> {
+    let wide_path = U16CString::from_os_str(path.as_os_str())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    // maps the module with its image layout without running it, which also works for modules of the other architecture.
+    let handle = unsafe {
+        LoadLibraryExW(
+            wide_path.as_ptr(),
+            ptr::null_mut(),
+            LOAD_LIBRARY_AS_DATAFILE | LOAD_LIBRARY_AS_IMAGE_RESOURCE,
+        )
+    };
+    if handle.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+
+    // the low bits of the handle mark the module as a data file.
+    let base = handle as usize & !0b11;
+    let security_directory = RemoteImage::from_base(BorrowedProcess::current(), base)
+        .and_then(|image| image.data_directory(IMAGE_DIRECTORY_ENTRY_SECURITY as usize));
+    unsafe { FreeLibrary(handle) };
+    Ok(security_directory?.is_some_and(|directory| directory.size != 0))
+}
+
+/// Returns whether the given path refers to a network share, either directly or through a mapped drive.
+fn is_remote_path(path: &Path) -> Result<bool, io::Error> {
+    let Some(Component::Prefix(prefix)) = path.components().next() else {
+        return Ok(false);
+    };
+    let drive = match prefix.kind() {
+        Prefix::UNC(..) | Prefix::VerbatimUNC(..) => return Ok(true),
+        Prefix::Disk(drive) | Prefix::VerbatimDisk(drive) => drive,
+        Prefix::Verbatim(_) | Prefix::DeviceNS(_) => return Ok(false),
+    };
+    let root = U16CString::from_str(format!("{}:\\", char::from(drive)))
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    Ok(unsafe { GetDriveTypeW(root.as_ptr()) } == DRIVE_REMOTE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unc_paths_are_remote() {
+        assert!(is_remote_path(Path::new(r"\\server\share\payload.dll")).unwrap());
+        assert!(is_remote_path(Path::new(r"\\?\UNC\server\share\payload.dll")).unwrap());
+        assert!(!is_remote_path(Path::new("payload.dll")).unwrap());
+    }
+
+    #[test]
+    fn unsigned_modules_have_no_embedded_signature() {
+        assert!(!has_embedded_signature(&std::env::current_exe().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn system_modules_may_pass_signature_policy() {
+        let kernel32 = system_windows_dir()
+            .unwrap()
+            .join("System32")
+            .join("kernel32.dll");
+        assert!(may_pass_signature_policy(&kernel32).unwrap());
+        let lowercase = Path::new(&kernel32.as_os_str().to_ascii_lowercase()).to_path_buf();
+        assert!(may_pass_signature_policy(&lowercase).unwrap());
+    }
+}
@@ -1,16 +1,32 @@
 use std::{
+    cell::{Cell, UnsafeCell},
     ffi::c_void,
+    fmt,
+    fs::File,
     io::{self, Write},
+    os::windows::io::FromRawHandle,
     panic::{self, AssertUnwindSafe},
-    slice,
+    ptr, slice,
+    sync::{Arc, Mutex, Once, PoisonError},
 };
 
 use serde::{de::DeserializeOwned, Serialize};
 use thiserror::Error;
+use winapi::{
+    shared::{minwindef::FALSE, winerror::ERROR_UNHANDLED_EXCEPTION},
+    um::{
+        errhandlingapi::GetLastError,
+        libloaderapi::{
+            FreeLibraryAndExitThread, GetModuleHandleExW, GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS,
+            GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+        },
+        processthreadsapi::ExitThread,
+    },
+};
 
 use crate::{
     process::{memory::ProcessMemoryBuffer, BorrowedProcess, Process},
-    ArgAndResultBufInfo,
+    ArgAndResultBufInfo, PayloadConfigHeader, PayloadMarkerData, PAYLOAD_MARKER_CAPACITY,
 };
 
 /// A macro for defining an exported function that can be used with [`RemotePayloadProcedure`](crate::rpc::RemotePayloadProcedure).
@@ -38,6 +54,293 @@ macro_rules! payload_procedure {
     };
 }
 
+/// A macro for defining the configuration buffer of a payload with the given capacity in bytes,
+/// which can be written by the injector using `InjectedModule::write_config`.
+///
+/// The buffer is defined as a static named `DLL_SYRINGE_CONFIG` and its contents can be read using [`PayloadConfig::read`].
+///
+/// # Example
+/// ```ignore
+/// dll_syringe::payload_config!(4096);
+///
+/// dll_syringe::payload_procedure! {
+///     fn configure() {
+///         let config = DLL_SYRINGE_CONFIG.read();
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! payload_config {
+    ($capacity:expr) => {
+        #[no_mangle]
+        pub static DLL_SYRINGE_CONFIG: $crate::payload_utils::PayloadConfig<{ $capacity }> =
+            $crate::payload_utils::PayloadConfig::new();
+    };
+}
+
+/// The configuration buffer of a payload, see [`payload_config!`](crate::payload_config).
+#[repr(C)]
+pub struct PayloadConfig<const N: usize> {
+    header: UnsafeCell<PayloadConfigHeader>,
+    data: UnsafeCell<[u8; N]>,
+}
+
+// the buffer is only written from outside the process.
+unsafe impl<const N: usize> Sync for PayloadConfig<N> {}
+
+impl<const N: usize> PayloadConfig<N> {
+    /// Creates a new empty configuration buffer.
+    ///
+    /// # Panics
+    /// This function panics if the capacity does not fit into a [`u32`].
+    #[must_use]
+    pub const fn new() -> Self {
+        assert!(N <= u32::MAX as usize, "payload config capacity too large");
+        Self {
+            header: UnsafeCell::new(PayloadConfigHeader {
+                capacity: N as u32,
+                len: 0,
+            }),
+            data: UnsafeCell::new([0; N]),
+        }
+    }
+
+    /// Returns the capacity of this buffer in bytes.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns a copy of the current contents of this buffer.
+    #[must_use]
+    pub fn read(&self) -> Vec<u8> {
+        let header = unsafe { self.header.get().read_volatile() };
+        let len = (header.len as usize).min(N);
+        let data = self.data.get().cast::<u8>();
+        (0..len)
+            .map(|i| unsafe { data.add(i).read_volatile() })
+            .collect()
+    }
+}
+
+impl<const N: usize> fmt::Debug for PayloadConfig<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PayloadConfig")
+            .field("capacity", &N)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<const N: usize> Default for PayloadConfig<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A macro for embedding a marker with the given id into a payload, which identifies copies of it in a target process
+/// even if they were renamed, e.g. using `Syringe::eject_stale_by_marker`.
+///
+/// The marker is defined as a static named `DLL_SYRINGE_PAYLOAD_MARKER`. The id must not be longer than 64 bytes.
+///
+/// # Example
+/// ```ignore
+/// dll_syringe::payload_marker!("my-payload");
+/// ```
+#[macro_export]
+macro_rules! payload_marker {
+    ($id:expr) => {
+        #[no_mangle]
+        pub static DLL_SYRINGE_PAYLOAD_MARKER: $crate::payload_utils::PayloadMarker =
+            $crate::payload_utils::PayloadMarker::new($id);
+    };
+}
+
+/// The marker identifying a payload, see [`payload_marker!`](crate::payload_marker).
+#[derive(Debug, Clone, Copy)]
+#[repr(transparent)]
+pub struct PayloadMarker(PayloadMarkerData);
+
+impl PayloadMarker {
+    /// Creates a new marker with the given id.
+    ///
+    /// # Panics
+    /// This function panics if the id is longer than 64 bytes.
+    #[must_use]
+    pub const fn new(id: &str) -> Self {
+        let bytes = id.as_bytes();
+        assert!(
+            bytes.len() <= PAYLOAD_MARKER_CAPACITY,
+            "payload marker id too long"
+        );
+        let mut data = [0; PAYLOAD_MARKER_CAPACITY];
+        let mut i = 0;
+        while i < bytes.len() {
+            data[i] = bytes[i];
+            i += 1;
+        }
+        Self(PayloadMarkerData {
+            len: bytes.len() as u32,
+            id: data,
+        })
+    }
+
+    /// Returns the id of this marker.
+    #[must_use]
+    pub fn id(&self) -> &str {
+        let len = (self.0.len as usize).min(PAYLOAD_MARKER_CAPACITY);
+        std::str::from_utf8(&self.0.id[..len]).unwrap_or_default()
+    }
+}
+
+/// A macro for defining the export used by `Syringe::eject_with_mode` with `EjectMode::SelfUnload`, which unloads the payload
+/// from a thread of its own using `FreeLibraryAndExitThread` after running the given cleanup code.
+///
+/// As the unloading thread never returns into the payload, the payload can stop its own threads in the cleanup code and is not
+/// unloaded while they may still execute its code. If the cleanup code panics, the payload stays loaded and ejecting it fails
+/// with `ERROR_UNHANDLED_EXCEPTION`.
+///
+/// # Example
+/// ```ignore
+/// dll_syringe::payload_self_unload!({
+///     stop_worker_threads();
+/// });
+/// ```
+#[macro_export]
+macro_rules! payload_self_unload {
+    () => {
+        $crate::payload_self_unload!({});
+    };
+    ($cleanup:block) => {
+        #[no_mangle]
+        pub unsafe extern "system" fn DLL_SYRINGE_SELF_UNLOAD(_: *mut ::core::ffi::c_void) -> u32 {
+            $crate::payload_utils::__self_unload(DLL_SYRINGE_SELF_UNLOAD as *const (), || $cleanup)
+        }
+    };
+}
+
+/// Runs the given cleanup code, releases a reference to the module containing the given address and exits the current thread.
+/// If the cleanup code panics or the module can not be determined, the thread exits with an error code instead.
+///
+/// # Safety
+/// The caller must not hold references into the module, as the thread exits without unwinding.
+#[doc(hidden)]
+pub unsafe fn __self_unload(address_in_module: *const (), cleanup: impl FnOnce()) -> ! {
+    // unwinding out of an exported function is undefined behavior.
+    if panic::catch_unwind(AssertUnwindSafe(cleanup)).is_err() {
+        unsafe { ExitThread(ERROR_UNHANDLED_EXCEPTION) };
+    }
+
+    let mut module = ptr::null_mut();
+    let found = unsafe {
+        GetModuleHandleExW(
+            GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+            address_in_module.cast(),
+            &mut module,
+        )
+    };
+    if found == FALSE {
+        unsafe { ExitThread(GetLastError()) };
+    } else {
+        unsafe { FreeLibraryAndExitThread(module, 0) };
+    }
+    unreachable!("the thread exited")
+}
+
+/// A macro for defining the export used by `InjectedModule::attach_log_reader`, through which the injector hands the payload
+/// the write end of a pipe for its log output. Lines are written to it using [`payload_log!`](crate::payload_log) or [`log`].
+///
+/// # Example
+/// ```ignore
+/// dll_syringe::payload_log_sink!();
+///
+/// dll_syringe::payload_procedure! {
+///     fn run() {
+///         dll_syringe::payload_log!("running in {}", std::process::id());
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! payload_log_sink {
+    () => {
+        #[no_mangle]
+        pub unsafe extern "system" fn DLL_SYRINGE_SET_LOG_SINK(
+            handle: *mut ::core::ffi::c_void,
+        ) -> u32 {
+            $crate::payload_utils::__set_log_sink(handle)
+        }
+    };
+}
+
+/// A macro for writing a formatted line to the log sink of the payload, see [`log`].
+#[macro_export]
+macro_rules! payload_log {
+    ($($arg:tt)*) => {
+        $crate::payload_utils::log(&::std::format!($($arg)*))
+    };
+}
+
+// the write end of the pipe handed to the payload by the injector, see `payload_log_sink!`.
+// the lock is only held to replace or clone the sink, so a host that does not read the pipe does not block replacing it.
+static LOG_SINK: Mutex<Option<Arc<File>>> = Mutex::new(None);
+
+/// Replaces the log sink of the payload with the given pipe handle, closing the previous one once pending writes finished.
+///
+/// # Safety
+/// The handle must be a valid handle to the write end of a pipe, which is owned by the sink afterwards.
+#[doc(hidden)]
+pub unsafe fn __set_log_sink(handle: *mut c_void) -> u32 {
+    let sink = unsafe { File::from_raw_handle(handle) };
+    *LOG_SINK.lock().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(sink));
+    0
+}
+
+/// Writes the given line to the log sink the injector handed to this payload, see [`payload_log_sink!`](crate::payload_log_sink).
+///
+/// Each line is written by a single write to the pipe, so lines written from multiple threads are not interleaved.
+/// The write blocks while the pipe is full, i.e. until the injector reads earlier lines.
+/// Returns whether the line was written, i.e. `false` if no sink was set or the injector stopped reading, in which case the
+/// sink is closed.
+pub fn log(line: &str) -> bool {
+    let Some(file) = LOG_SINK
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+    else {
+        return false;
+    };
+
+    let mut buf = String::with_capacity(line.len() + 1);
+    buf.push_str(line);
+    buf.push('\n');
+    if (&*file).write_all(buf.as_bytes()).is_err() {
+        let mut sink = LOG_SINK.lock().unwrap_or_else(PoisonError::into_inner);
+        // the sink may have been replaced by a working one in the meantime.
+        if sink.as_ref().is_some_and(|sink| Arc::ptr_eq(sink, &file)) {
+            *sink = None;
+        }
+        return false;
+    }
+    true
+}
+
+thread_local! {
+    // the location of the last panic on this thread, recorded by the hook installed by `install_panic_location_hook`.
+    static PANIC_LOCATION: Cell<Option<String>> = const { Cell::new(None) };
+}
+
+/// Installs a panic hook that records the location of panics, as the payload of a caught panic does not include it.
+/// The previously installed hook is still called.
+fn install_panic_location_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            PANIC_LOCATION.set(info.location().map(ToString::to_string));
+            previous_hook(info);
+        }));
+    });
+}
+
 pub fn __payload_procedure_helper<A: DeserializeOwned, R: Serialize>(
     buf_info_ptr: *mut c_void,
     f: impl FnOnce(A) -> R,
@@ -46,45 +349,55 @@ pub fn __payload_procedure_helper<A: DeserializeOwned, R: Serialize>(
     let buf_info = unsafe { &mut *buf_info_ptr };
     let buf = unsafe { slice::from_raw_parts_mut(buf_info.data as *mut u8, buf_info.len as usize) };
 
-    let result = panic::catch_unwind(AssertUnwindSafe(|| payload_procedure_helper_inner(buf, f)));
+    install_panic_location_hook();
+    PANIC_LOCATION.set(None);
+    let preserve_args = buf_info.preserve_data;
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        payload_procedure_helper_inner(buf, preserve_args, f)
+    }));
 
     match result {
         Ok(Ok(result_buf)) => unsafe {
             (*buf_info_ptr).data = result_buf.as_ptr() as u64;
             (*buf_info_ptr).len = result_buf.len() as u64;
         },
-        err => {
-            unsafe {
-                (*buf_info_ptr).is_error = true;
-            }
-
-            let message = match err {
-                Ok(Err(e)) => e.to_string(),
-                Err(e) => match e.downcast_ref::<&'static str>() {
-                    Some(s) => s.to_string(),
-                    None => match e.downcast::<String>() {
-                        Ok(s) => *s,
-                        Err(_) => "unknown panic".to_string(),
-                    },
+        Ok(Err(err)) => write_error(buf_info_ptr, err.to_string().into_bytes(), false),
+        Err(panic) => {
+            let message = match panic.downcast_ref::<&'static str>() {
+                Some(s) => s.to_string(),
+                None => match panic.downcast::<String>() {
+                    Ok(s) => *s,
+                    Err(_) => "unknown panic".to_string(),
                 },
-                _ => unreachable!(),
-            };
-
-            let mut error_buf = match allocate_local_process_memory(message.len()) {
-                Ok(buf) => buf,
-                Err(_) => return,
             };
-            unsafe {
-                (*buf_info_ptr).data = error_buf.as_ptr() as u64;
-                (*buf_info_ptr).len = error_buf.len() as u64;
+            let location = PANIC_LOCATION.take();
+            match bincode::serialize(&(&message, &location)) {
+                Ok(data) => write_error(buf_info_ptr, data, true),
+                Err(_) => write_error(buf_info_ptr, message.into_bytes(), false),
             }
-            let _ = write!(error_buf, "{message}");
         }
     }
 }
 
+fn write_error(buf_info_ptr: *mut ArgAndResultBufInfo, data: Vec<u8>, is_panic: bool) {
+    unsafe {
+        (*buf_info_ptr).is_error = true;
+        (*buf_info_ptr).is_panic = is_panic;
+    }
+
+    let Ok(error_buf) = allocate_local_process_memory(data.len()) else {
+        return;
+    };
+    error_buf[..data.len()].copy_from_slice(&data);
+    unsafe {
+        (*buf_info_ptr).data = error_buf.as_ptr() as u64;
+        (*buf_info_ptr).len = data.len() as u64;
+    }
+}
+
 fn payload_procedure_helper_inner<A: DeserializeOwned, R: Serialize>(
     buf: &mut [u8],
+    preserve_args: bool,
     f: impl FnOnce(A) -> R,
 ) -> Result<&'_ mut [u8], PayloadProcedureHelperError> {
     let args = bincode::deserialize(buf)?;
@@ -92,7 +405,9 @@ fn payload_procedure_helper_inner<A: DeserializeOwned, R: Serialize>(
     let result = f(args);
 
     let required_buf_len = bincode::serialized_size(&result)? as usize;
-    let result_buf = if required_buf_len > buf.len() {
+    let result_buf = if required_buf_len == 0 {
+        &mut buf[..0]
+    } else if preserve_args || required_buf_len > buf.len() {
         allocate_local_process_memory(required_buf_len)?
     } else {
         buf
@@ -116,3 +431,30 @@ enum PayloadProcedureHelperError {
     #[error("io error: {0}")]
     Io(#[from] io::Error),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    crate::payload_marker!("dll-syringe-unit-test");
+
+    #[test]
+    fn payload_marker_macro_embeds_id() {
+        assert_eq!(DLL_SYRINGE_PAYLOAD_MARKER.id(), "dll-syringe-unit-test");
+    }
+
+    #[test]
+    fn payload_marker_roundtrips_id() {
+        assert_eq!(PayloadMarker::new("").id(), "");
+        assert_eq!(PayloadMarker::new("my-payload").id(), "my-payload");
+
+        let longest = "a".repeat(PAYLOAD_MARKER_CAPACITY);
+        assert_eq!(PayloadMarker::new(&longest).id(), longest);
+    }
+
+    #[test]
+    #[should_panic(expected = "payload marker id too long")]
+    fn payload_marker_rejects_long_id() {
+        let _ = PayloadMarker::new(&"a".repeat(PAYLOAD_MARKER_CAPACITY + 1));
+    }
+}
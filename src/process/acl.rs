@@ -0,0 +1,223 @@
+use std::{io, mem, path::Path, ptr};
+
+use widestring::{u16cstr, U16CString};
+use winapi::{
+    shared::{minwindef::FALSE, sddl::ConvertStringSidToSidW, winerror::ERROR_SUCCESS},
+    um::{
+        accctrl::{
+            EXPLICIT_ACCESS_W, GRANT_ACCESS, NO_INHERITANCE, SE_FILE_OBJECT, TRUSTEE_IS_SID,
+            TRUSTEE_IS_WELL_KNOWN_GROUP,
+        },
+        aclapi::{GetNamedSecurityInfoW, SetEntriesInAclW, SetNamedSecurityInfoW},
+        securitybaseapi::{GetAce, GetSidSubAuthority, GetSidSubAuthorityCount},
+        winbase::LocalFree,
+        winnt::{
+            DACL_SECURITY_INFORMATION, GENERIC_EXECUTE, GENERIC_READ, LABEL_SECURITY_INFORMATION,
+            SECURITY_MANDATORY_LOW_RID, SYSTEM_MANDATORY_LABEL_ACE,
+            SYSTEM_MANDATORY_LABEL_ACE_TYPE,
+        },
+    },
+};
+
+use crate::process::{windows_version, WindowsVersion};
+
+/// The well-known sid of the `ALL APPLICATION PACKAGES` group.
+const ALL_APPLICATION_PACKAGES_SID: &widestring::U16CStr = u16cstr!("S-1-15-2-1");
+
+/// Frees memory allocated by the system using `LocalAlloc` when dropped.
+struct LocalBox<T>(*mut T);
+
+impl<T> Drop for LocalBox<T> {
+    fn drop(&mut self) {
+        if !self.0.is_null() {
+            unsafe { LocalFree(self.0.cast()) };
+        }
+    }
+}
+
+fn check_error_code(code: u32) -> Result<(), io::Error> {
+    if code == ERROR_SUCCESS {
+        Ok(())
+    } else {
+        Err(io::Error::from_raw_os_error(code as i32))
+    }
+}
+
+/// Grants the `ALL APPLICATION PACKAGES` group read and execute access to the file at the given path.
+///
+/// Sandboxed processes running in an [AppContainer](https://docs.microsoft.com/en-us/windows/win32/secauthz/appcontainer-isolation) (e.g. UWP apps)
+/// can only load modules they have been granted access to, so this is required before injecting into them
+/// (see [`Process::is_app_container`](crate::process::Process::is_app_container)).
+///
+/// # Note
+/// This permanently modifies the access control list of the file and requires the permission to change it.
+/// Before Windows 8, which introduced AppContainers, the file is left unchanged.
+pub fn grant_app_container_access(path: impl AsRef<Path>) -> Result<(), io::Error> {
+    let path = U16CString::from_os_str(path.as_ref().as_os_str())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    if !windows_version().is_at_least(WindowsVersion::WINDOWS_8) {
+        return Ok(());
+    }
+
+    let mut sid = ptr::null_mut();
+    if unsafe { ConvertStringSidToSidW(ALL_APPLICATION_PACKAGES_SID.as_ptr(), &mut sid) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let sid = LocalBox(sid);
+
+    let mut old_dacl = ptr::null_mut();
+    let mut security_descriptor = ptr::null_mut();
+    check_error_code(unsafe {
+        GetNamedSecurityInfoW(
+            path.as_ptr(),
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &mut old_dacl,
+            ptr::null_mut(),
+            &mut security_descriptor,
+        )
+    })?;
+    // the old dacl points into the security descriptor.
+    let _security_descriptor = LocalBox(security_descriptor);
+
+    let mut access: EXPLICIT_ACCESS_W = unsafe { mem::zeroed() };
+    access.grfAccessPermissions = GENERIC_READ | GENERIC_EXECUTE;
+    access.grfAccessMode = GRANT_ACCESS;
+    access.grfInheritance = NO_INHERITANCE;
+    access.Trustee.TrusteeForm = TRUSTEE_IS_SID;
+    access.Trustee.TrusteeType = TRUSTEE_IS_WELL_KNOWN_GROUP;
+    access.Trustee.ptstrName = sid.0.cast();
+
+    let mut new_dacl = ptr::null_mut();
+    check_error_code(unsafe { SetEntriesInAclW(1, &mut access, old_dacl, &mut new_dacl) })?;
+    let new_dacl = LocalBox(new_dacl);
+
+    check_error_code(unsafe {
+        SetNamedSecurityInfoW(
+            path.as_ptr().cast_mut(),
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            new_dacl.0,
+            ptr::null_mut(),
+        )
+    })
+}
+
+/// Returns whether the file at the given path has a mandatory label of low integrity or below,
+/// which processes with the corresponding image load policy refuse to load.
+#[cfg_attr(not(feature = "syringe"), allow(dead_code))]
+pub(crate) fn has_low_mandatory_label(path: &Path) -> Result<bool, io::Error> {
+    let path = U16CString::from_os_str(path.as_os_str())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let mut sacl = ptr::null_mut();
+    let mut security_descriptor = ptr::null_mut();
+    check_error_code(unsafe {
+        GetNamedSecurityInfoW(
+            path.as_ptr(),
+            SE_FILE_OBJECT,
+            LABEL_SECURITY_INFORMATION,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &mut sacl,
+            &mut security_descriptor,
+        )
+    })?;
+    // the sacl points into the security descriptor.
+    let _security_descriptor = LocalBox(security_descriptor);
+    if sacl.is_null() {
+        return Ok(false);
+    }
+
+    for index in 0..u32::from(unsafe { (*sacl).AceCount }) {
+        let mut ace = ptr::null_mut();
+        if unsafe { GetAce(sacl, index, &mut ace) } == FALSE {
+            return Err(io::Error::last_os_error());
+        }
+        let ace = ace.cast::<SYSTEM_MANDATORY_LABEL_ACE>();
+        if unsafe { (*ace).Header.AceType } != SYSTEM_MANDATORY_LABEL_ACE_TYPE {
+            continue;
+        }
+        let sid = unsafe { ptr::addr_of_mut!((*ace).SidStart) }.cast();
+        let rid = unsafe { *GetSidSubAuthority(sid, u32::from(*GetSidSubAuthorityCount(sid)) - 1) };
+        return Ok(rid <= SECURITY_MANDATORY_LOW_RID);
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use winapi::um::{
+        securitybaseapi::EqualSid,
+        winnt::{ACCESS_ALLOWED_ACE, ACCESS_ALLOWED_ACE_TYPE, FILE_READ_DATA},
+    };
+
+    use super::*;
+
+    fn app_container_access_mask(path: &Path) -> Option<u32> {
+        let path = U16CString::from_os_str(path.as_os_str()).unwrap();
+        let mut dacl = ptr::null_mut();
+        let mut security_descriptor = ptr::null_mut();
+        check_error_code(unsafe {
+            GetNamedSecurityInfoW(
+                path.as_ptr(),
+                SE_FILE_OBJECT,
+                DACL_SECURITY_INFORMATION,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                &mut dacl,
+                ptr::null_mut(),
+                &mut security_descriptor,
+            )
+        })
+        .unwrap();
+        let _security_descriptor = LocalBox(security_descriptor);
+
+        let mut sid = ptr::null_mut();
+        assert_ne!(
+            unsafe { ConvertStringSidToSidW(ALL_APPLICATION_PACKAGES_SID.as_ptr(), &mut sid) },
+            0
+        );
+        let sid = LocalBox(sid);
+
+        (0..u32::from(unsafe { (*dacl).AceCount })).find_map(|index| {
+            let mut ace = ptr::null_mut();
+            assert_ne!(unsafe { GetAce(dacl, index, &mut ace) }, FALSE);
+            let ace = ace.cast::<ACCESS_ALLOWED_ACE>();
+            let ace_sid = unsafe { ptr::addr_of_mut!((*ace).SidStart) }.cast();
+            (unsafe { (*ace).Header.AceType } == ACCESS_ALLOWED_ACE_TYPE
+                && unsafe { EqualSid(ace_sid, sid.0) } != FALSE)
+                .then(|| unsafe { (*ace).Mask })
+        })
+    }
+
+    #[test]
+    fn grant_app_container_access_adds_read_access() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("payload.dll");
+        std::fs::write(&path, b"payload").unwrap();
+        assert_eq!(app_container_access_mask(&path), None);
+
+        grant_app_container_access(&path).unwrap();
+        let mask = app_container_access_mask(&path).unwrap();
+        assert_ne!(mask & (GENERIC_READ | FILE_READ_DATA), 0);
+    }
+
+    #[test]
+    fn grant_app_container_access_fails_for_missing_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert!(grant_app_container_access(temp_dir.path().join("missing.dll")).is_err());
+    }
+
+    #[test]
+    fn new_file_has_no_low_mandatory_label() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("payload.dll");
+        std::fs::write(&path, b"payload").unwrap();
+        assert!(!has_low_mandatory_label(&path).unwrap());
+    }
+}
@@ -0,0 +1,124 @@
+use std::io;
+
+use crate::process::{memory::ProcessMemorySlice, peb::api_set_map_address, BorrowedProcess};
+
+// only the schema format used since Windows 10 is supported.
+const API_SET_SCHEMA_VERSION: u32 = 6;
+const NAMESPACE_ENTRY_SIZE: usize = 24;
+const VALUE_ENTRY_SIZE: usize = 20;
+
+/// The api set schema of a process, which maps api set contracts like `api-ms-win-core-synch-l1-2-0` to the modules hosting them.
+#[derive(Debug, Clone)]
+pub(crate) struct ApiSetSchema {
+    data: Vec<u8>,
+}
+
+impl ApiSetSchema {
+    /// Reads the api set schema mapped into the given process.
+    /// Returns [`None`] if the schema format is not supported.
+    pub fn read(process: BorrowedProcess<'_>) -> Result<Option<Self>, io::Error> {
+        let address = api_set_map_address(process)?;
+        if address == 0 {
+            return Ok(None);
+        }
+        let read = |offset: usize, buf: &mut [u8]| {
+            unsafe {
+                ProcessMemorySlice::from_raw_parts(
+                    (address + offset) as *mut u8,
+                    buf.len(),
+                    process,
+                )
+            }
+            .read(0, buf)
+        };
+
+        let mut header = [0; 8];
+        read(0, &mut header)?;
+        let version = u32::from_le_bytes(header[0..4].try_into().unwrap());
+        let size = u32::from_le_bytes(header[4..8].try_into().unwrap()) as usize;
+        if version != API_SET_SCHEMA_VERSION {
+            return Ok(None);
+        }
+
+        let mut data = vec![0; size];
+        read(0, &mut data)?;
+        Ok(Some(Self { data }))
+    }
+
+    /// Returns the name of the module hosting the given api set contract when imported by the module with the given name.
+    /// Returns [`None`] if the name is not a known contract or if it has no host.
+    pub fn resolve(&self, contract: &str, importer: &str) -> Option<String> {
+        let contract = contract.to_ascii_lowercase();
+        let contract = contract.strip_suffix(".dll").unwrap_or(&contract);
+        // the version suffix after the last hyphen is not part of the hashed name.
+        let (contract, _) = contract.rsplit_once('-')?;
+
+        let count = self.read_u32(12)? as usize;
+        let entries_offset = self.read_u32(16)? as usize;
+        for i in 0..count {
+            let entry_offset = entries_offset + i * NAMESPACE_ENTRY_SIZE;
+            let name_offset = self.read_u32(entry_offset + 4)? as usize;
+            let hashed_len = self.read_u32(entry_offset + 12)? as usize;
+            let name = self.read_string(name_offset, hashed_len)?;
+            if !name.eq_ignore_ascii_case(contract) {
+                continue;
+            }
+
+            let values_offset = self.read_u32(entry_offset + 16)? as usize;
+            let value_count = self.read_u32(entry_offset + 20)? as usize;
+            let mut host = None;
+            for j in 0..value_count {
+                let value_offset = values_offset + j * VALUE_ENTRY_SIZE;
+                let importer_name = self.read_string(
+                    self.read_u32(value_offset + 4)? as usize,
+                    self.read_u32(value_offset + 8)? as usize,
+                )?;
+                let value = self.read_string(
+                    self.read_u32(value_offset + 12)? as usize,
+                    self.read_u32(value_offset + 16)? as usize,
+                )?;
+                // the default host has no importer name, the others override it for specific importers.
+                if importer_name.is_empty() {
+                    host.get_or_insert(value);
+                } else if importer_name.eq_ignore_ascii_case(importer) {
+                    host = Some(value);
+                    break;
+                }
+            }
+            return host.filter(|host| !host.is_empty());
+        }
+        None
+    }
+
+    fn read_u32(&self, offset: usize) -> Option<u32> {
+        let bytes = self.data.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_string(&self, offset: usize, len: usize) -> Option<String> {
+        let bytes = self.data.get(offset..offset + len)?;
+        let chars = bytes
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect::<Vec<_>>();
+        Some(String::from_utf16_lossy(&chars))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::Process;
+
+    #[test]
+    fn resolve_core_contract() {
+        let Some(schema) = ApiSetSchema::read(BorrowedProcess::current()).unwrap() else {
+            return;
+        };
+        let host = schema
+            .resolve("api-ms-win-core-synch-l1-1-0.dll", "test.exe")
+            .unwrap();
+        assert!(host.to_ascii_lowercase().ends_with(".dll"), "{host}");
+        assert_eq!(schema.resolve("not-an-api-set-l1-1-0", "test.exe"), None);
+    }
+}
@@ -14,22 +14,29 @@ use std::{
 
 use winapi::{
     shared::{
-        minwindef::{FALSE, HMODULE},
+        minwindef::{DWORD, FALSE, HMODULE},
         winerror::ERROR_PARTIAL_COPY,
     },
     um::{
         handleapi::DuplicateHandle,
         processthreadsapi::GetCurrentProcess,
-        psapi::{EnumProcessModulesEx, LIST_MODULES_ALL},
+        psapi::{EnumProcessModulesEx, LIST_MODULES_32BIT, LIST_MODULES_64BIT, LIST_MODULES_ALL},
         winnt::DUPLICATE_SAME_ACCESS,
     },
 };
 
 use crate::{
-    process::{ModuleHandle, OwnedProcess, Process, ProcessModule},
-    utils::{retry_faillable_until_some_with_timeout, ArrayOrVecBuf},
+    process::{
+        check_process_handle, ModuleHandle, ModulePathMatcher, OwnedProcess, Process, ProcessModule,
+    },
+    utils::{retry_faillable_until_some_with_timeout, ArrayOrVecBuf, ArrayOrVecBufIter},
 };
 
+// the handles of up to this many modules are read without a heap allocation.
+const MODULE_HANDLE_BUF_LEN: usize = 1024;
+
+pub(crate) type ModuleHandleIter = ArrayOrVecBufIter<ModuleHandle, MODULE_HANDLE_BUF_LEN>;
+
 /// A struct representing a running process.
 /// This struct does **NOT** own the underlying process handle (see also [`OwnedProcess`] for an owned version).
 ///
@@ -40,6 +47,7 @@ use crate::{
 ///  - `PROCESS_VM_OPERATION`
 ///  - `PROCESS_VM_WRITE`
 ///  - `PROCESS_VM_READ`
+///  - `SYNCHRONIZE`
 #[repr(transparent)]
 #[derive(Debug, Clone, Copy)]
 pub struct BorrowedProcess<'a>(BorrowedHandle<'a>);
@@ -94,6 +102,25 @@ impl<'a> From<&'a OwnedProcess> for BorrowedProcess<'a> {
     }
 }
 
+impl<'a> TryFrom<BorrowedHandle<'a>> for BorrowedProcess<'a> {
+    type Error = io::Error;
+
+    /// Creates a new instance from a process handle obtained elsewhere (e.g. from a debugger loop).
+    ///
+    /// # Errors
+    /// Returns an error if the handle does not refer to a process or lacks the `PROCESS_QUERY_LIMITED_INFORMATION` access right.
+    fn try_from(handle: BorrowedHandle<'a>) -> Result<Self, Self::Error> {
+        check_process_handle(handle.as_raw_handle())?;
+        Ok(Self(handle))
+    }
+}
+
+impl<'a> From<BorrowedProcess<'a>> for BorrowedHandle<'a> {
+    fn from(process: BorrowedProcess<'a>) -> Self {
+        process.0
+    }
+}
+
 impl<'a> Process for BorrowedProcess<'a> {
     type Handle = BorrowedHandle<'a>;
 
@@ -148,34 +175,14 @@ impl<'a> Process for BorrowedProcess<'a> {
         &self,
         module_path: impl AsRef<Path>,
     ) -> Result<Option<ProcessModule<BorrowedProcess<'a>>>, io::Error> {
-        let target_module_path = module_path.as_ref();
-
-        // add default file extension if missing
-        let target_module_path = if target_module_path.extension().is_none() {
-            Cow::Owned(target_module_path.with_extension("dll").into_os_string())
-        } else {
-            Cow::Borrowed(target_module_path.as_os_str())
-        };
-
-        let target_module_handle = same_file::Handle::from_path(&target_module_path)?;
+        let target_module_path = ModulePathMatcher::new(module_path.as_ref());
 
         let modules = self.module_handles()?;
 
         for module_handle in modules {
             let module = unsafe { ProcessModule::new_unchecked(module_handle, *self) };
-            let module_path = module.path()?.into_os_string();
-
-            match same_file::Handle::from_path(&module_path) {
-                Ok(module_handle) => {
-                    if module_handle == target_module_handle {
-                        return Ok(Some(module));
-                    }
-                }
-                Err(_) => {
-                    if target_module_path.eq_ignore_ascii_case(&module_path) {
-                        return Ok(Some(module));
-                    }
-                }
+            if target_module_path.matches(&module.path()?) {
+                return Ok(Some(module));
             }
         }
 
@@ -206,6 +213,16 @@ impl<'a> Process for BorrowedProcess<'a> {
 }
 
 impl<'a> BorrowedProcess<'a> {
+    /// Creates a new instance borrowing the given raw process handle.
+    ///
+    /// # Safety
+    /// The caller must ensure that the handle is a valid process handle with the required privileges
+    /// and that it stays open for the lifetime `'a`.
+    #[must_use]
+    pub unsafe fn borrow_raw(handle: HANDLE) -> Self {
+        Self(unsafe { BorrowedHandle::borrow_raw(handle) })
+    }
+
     /// Tries to create a new [`OwnedProcess`] instance for this process.
     pub fn try_to_owned(&self) -> Result<OwnedProcess, io::Error> {
         let raw_handle = self.as_raw_handle();
@@ -229,84 +246,95 @@ impl<'a> BorrowedProcess<'a> {
     }
 
     /// Returns a snapshot of the handles of the modules currently loaded in this process.
+    /// For a WOW64 process this includes both its 32-bit and 64-bit modules, see [`BorrowedProcess::module_handles_with_filter`].
     ///
     /// # Note
     /// If the process is currently starting up and has not yet loaded all its modules, the returned list may be incomplete.
     /// This can be worked around by repeatedly calling this method.
     pub fn module_handles(&self) -> Result<impl ExactSizeIterator<Item = ModuleHandle>, io::Error> {
-        let mut module_buf = ArrayOrVecBuf::<ModuleHandle, 1024>::new_uninit_array();
+        self.module_handles_with_filter(ModuleListFilter::All)
+    }
+
+    /// Returns a snapshot of the handles of the modules of the given kind currently loaded in this process.
+    ///
+    /// The list is read again if modules are loaded or unloaded while it is read, so that it is consistent.
+    ///
+    /// # Note
+    /// If the process is currently starting up and has not yet loaded all its modules, the returned list may be incomplete.
+    /// This can be worked around by repeatedly calling this method.
+    pub fn module_handles_with_filter(
+        &self,
+        filter: ModuleListFilter,
+    ) -> Result<impl ExactSizeIterator<Item = ModuleHandle>, io::Error> {
+        self.module_handle_iter(filter)
+    }
+
+    pub(crate) fn module_handle_iter(
+        &self,
+        filter: ModuleListFilter,
+    ) -> Result<ModuleHandleIter, io::Error> {
         const HANDLE_SIZE: u32 = mem::size_of::<HMODULE>() as _;
-        let mut module_buf_byte_size = HANDLE_SIZE * module_buf.capacity() as u32;
-        let mut bytes_needed_new = MaybeUninit::uninit();
+        let mut modules = ArrayOrVecBuf::<ModuleHandle, MODULE_HANDLE_BUF_LEN>::new_uninit_array();
         loop {
+            let module_buf_byte_size = HANDLE_SIZE * modules.capacity() as u32;
+            let mut bytes_needed = MaybeUninit::uninit();
             let result = unsafe {
                 EnumProcessModulesEx(
                     self.as_raw_handle(),
-                    module_buf.as_mut_ptr(),
+                    modules.spare_capacity_mut().as_mut_ptr().cast(),
                     module_buf_byte_size,
-                    bytes_needed_new.as_mut_ptr(),
-                    LIST_MODULES_ALL,
+                    bytes_needed.as_mut_ptr(),
+                    filter.as_raw(),
                 )
             };
             if result == 0 {
                 let err = io::Error::last_os_error();
+                // the module list changed while it was read.
                 if err.raw_os_error() == Some(ERROR_PARTIAL_COPY as _) && self.is_alive() {
                     continue;
                 }
                 return Err(err);
             }
 
-            break;
-        }
-
-        let mut bytes_needed = unsafe { bytes_needed_new.assume_init() };
-
-        let modules = if bytes_needed <= module_buf_byte_size {
-            // buffer size was sufficient
-            let module_buf_len = (bytes_needed / HANDLE_SIZE) as usize;
-            unsafe { module_buf.set_len(module_buf_len) };
-            module_buf
-        } else {
-            // buffer size was not sufficient
-            let mut module_buf_vec = Vec::new();
-
-            // we loop here trying to find a buffer size that fits all handles
-            // this needs to be a loop as the returned bytes_needed is only valid for the modules loaded when
-            // the function run, if more modules have loaded in the meantime we need to resize the buffer again.
-            // This can happen often if the process is currently starting up.
-            loop {
-                module_buf_byte_size =
-                    cmp::max(bytes_needed, module_buf_byte_size.saturating_mul(2));
-                let mut module_buf_len = (module_buf_byte_size / HANDLE_SIZE) as usize;
-                if module_buf_len > module_buf_vec.capacity() {
-                    module_buf_vec.reserve(module_buf_len - module_buf_vec.capacity());
-                }
-
-                let mut bytes_needed_new = MaybeUninit::uninit();
-                let result = unsafe {
-                    EnumProcessModulesEx(
-                        self.as_raw_handle(),
-                        module_buf_vec.as_mut_ptr(),
-                        module_buf_byte_size,
-                        bytes_needed_new.as_mut_ptr(),
-                        LIST_MODULES_ALL,
-                    )
-                };
-                if result == 0 {
-                    return Err(io::Error::last_os_error());
-                }
-                bytes_needed = unsafe { bytes_needed_new.assume_init() };
-
-                if bytes_needed <= module_buf_byte_size {
-                    module_buf_len = (bytes_needed / HANDLE_SIZE) as usize;
-                    unsafe { module_buf_vec.set_len(module_buf_len) };
-                    break ArrayOrVecBuf::from_vec(module_buf_vec);
-                }
+            let bytes_needed = unsafe { bytes_needed.assume_init() };
+            if bytes_needed <= module_buf_byte_size {
+                unsafe { modules.set_len((bytes_needed / HANDLE_SIZE) as usize) };
+                break;
             }
-        };
+
+            // the returned size is only valid for the modules loaded when the function ran, if more modules have loaded in the meantime
+            // we need to resize the buffer again. This can happen often if the process is currently starting up.
+            let module_count = (bytes_needed / HANDLE_SIZE) as usize;
+            modules = ArrayOrVecBuf::with_capacity(cmp::max(
+                module_count + module_count / 4,
+                modules.capacity() * 2,
+            ));
+        }
 
         debug_assert!(modules.iter().all(|module| !module.is_null()));
 
         Ok(modules.into_iter())
     }
 }
+
+/// The kinds of modules listed by [`BorrowedProcess::module_handles_with_filter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ModuleListFilter {
+    /// All modules, which for a WOW64 process includes both its 32-bit and 64-bit modules.
+    #[default]
+    All,
+    /// Only 32-bit modules.
+    X86,
+    /// Only 64-bit modules.
+    X64,
+}
+
+impl ModuleListFilter {
+    const fn as_raw(self) -> DWORD {
+        match self {
+            Self::All => LIST_MODULES_ALL,
+            Self::X86 => LIST_MODULES_32BIT,
+            Self::X64 => LIST_MODULES_64BIT,
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use std::{fmt, io};
+
+use crate::process::{
+    windows_version, wine_version, BorrowedProcess, IntegrityLevel, MitigationPolicies, Process,
+    ProtectionLevel, RemoteThreadCreationMethod, WindowsVersion,
+};
+
+/// A reason why injecting into a process is expected to fail, see [`Capabilities::blockers`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum InjectionBlocker {
+    /// The target is a 64-bit process, which can not be injected into from a 32-bit process.
+    TargetIsX64,
+    /// The target is a 32-bit process and injecting into it from a 64-bit process requires the `into-x86-from-x64` feature.
+    CrossBitnessDisabled,
+    /// The target is a protected process, which can not be injected into from user mode.
+    ProtectedProcess(ProtectionLevel),
+    /// The target prohibits the creation of executable memory, which is required for the injection stubs.
+    DynamicCodeProhibited,
+    /// The target only loads modules signed by Microsoft or the Microsoft Store.
+    SignedModulesOnly,
+    /// The target runs at a higher integrity level than the current process, which therefore needs to be elevated.
+    ElevationRequired,
+}
+
+impl fmt::Display for InjectionBlocker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TargetIsX64 => f.write_str("a 64-bit target can not be injected into from a 32-bit process"),
+            Self::CrossBitnessDisabled => f.write_str(
+                "injecting into a 32-bit target from a 64-bit process requires the into-x86-from-x64 feature",
+            ),
+            Self::ProtectedProcess(level) => write!(f, "the target is a protected process ({level})"),
+            Self::DynamicCodeProhibited => f.write_str("the target prohibits dynamic code"),
+            Self::SignedModulesOnly => f.write_str("the target only loads signed modules"),
+            Self::ElevationRequired => {
+                f.write_str("the target runs at a higher integrity level than the current process")
+            }
+        }
+    }
+}
+
+/// A property of a target process that [`Capabilities`] could not determine, see [`Capabilities::unavailable`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CapabilityProbe {
+    /// Whether the target is a 64-bit process.
+    Bitness,
+    /// The protection level of the target.
+    ProtectionLevel,
+    /// The mitigation policies of the target.
+    MitigationPolicies,
+    /// The integrity level of the current process.
+    HostIntegrityLevel,
+    /// The integrity level of the target.
+    TargetIntegrityLevel,
+    /// Whether the target runs in the same session as the current process.
+    Session,
+}
+
+/// A report of what is supported when working with a target process from the current process,
+/// see [`Process::capabilities`] and [`Syringe::capabilities`](crate::Syringe::capabilities).
+///
+/// The report is a prediction based on the properties of both processes, so an operation may still fail for other reasons,
+/// e.g. because of security software. Properties that could not be queried, e.g. because the handle of the target lacks
+/// the required access rights, are listed by [`unavailable`](Capabilities::unavailable) instead of failing the whole report,
+/// and can not cause a [blocker](Capabilities::blockers).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    windows_version: WindowsVersion,
+    wine_version: Option<&'static str>,
+    is_host_x64: bool,
+    is_target_x64: Option<bool>,
+    thread_creation_method: RemoteThreadCreationMethod,
+    requires_debug_privilege: bool,
+    protection_level: Option<ProtectionLevel>,
+    mitigation_policies: MitigationPolicies,
+    host_integrity_level: Option<IntegrityLevel>,
+    target_integrity_level: Option<IntegrityLevel>,
+    blockers: Vec<InjectionBlocker>,
+    unavailable: Vec<CapabilityProbe>,
+}
+
+impl Capabilities {
+    pub(crate) fn probe(target: BorrowedProcess<'_>, wine_compatibility: bool) -> Self {
+        let host = BorrowedProcess::current();
+        let mut unavailable = Vec::new();
+        let is_host_x64 = cfg!(target_pointer_width = "64");
+        let is_target_x64 = available(&mut unavailable, CapabilityProbe::Bitness, target.is_x64());
+
+        // wine has neither protected processes nor mitigation policies.
+        let (protection_level, mitigation_policies) = if wine_compatibility {
+            (None, MitigationPolicies::default())
+        } else {
+            (
+                available(
+                    &mut unavailable,
+                    CapabilityProbe::ProtectionLevel,
+                    target.protection_level(),
+                )
+                .flatten(),
+                available(
+                    &mut unavailable,
+                    CapabilityProbe::MitigationPolicies,
+                    target.mitigation_policies(),
+                )
+                .unwrap_or_default(),
+            )
+        };
+        // the token of the target may not be accessible, e.g. for system processes.
+        let host_integrity_level = available(
+            &mut unavailable,
+            CapabilityProbe::HostIntegrityLevel,
+            host.integrity_level(),
+        );
+        let target_integrity_level = available(
+            &mut unavailable,
+            CapabilityProbe::TargetIntegrityLevel,
+            target.integrity_level(),
+        );
+
+        // CreateRemoteThread refuses to create threads in other sessions, e.g. in services.
+        let same_session = available(
+            &mut unavailable,
+            CapabilityProbe::Session,
+            host.session_id()
+                .and_then(|host_session| Ok(host_session == target.session_id()?)),
+        )
+        .unwrap_or(true);
+        let thread_creation_method = if same_session {
+            RemoteThreadCreationMethod::CreateRemoteThread
+        } else {
+            RemoteThreadCreationMethod::RtlCreateUserThread
+        };
+
+        let mut blockers = Vec::new();
+        if is_target_x64 == Some(true) && !is_host_x64 {
+            blockers.push(InjectionBlocker::TargetIsX64);
+        }
+        if is_target_x64 == Some(false) && is_host_x64 && !cfg!(feature = "into-x86-from-x64") {
+            blockers.push(InjectionBlocker::CrossBitnessDisabled);
+        }
+        if let Some(level) = protection_level {
+            blockers.push(InjectionBlocker::ProtectedProcess(level));
+        }
+        if mitigation_policies.prohibits_dynamic_code() {
+            blockers.push(InjectionBlocker::DynamicCodeProhibited);
+        }
+        if mitigation_policies.microsoft_signed_only() || mitigation_policies.store_signed_only() {
+            blockers.push(InjectionBlocker::SignedModulesOnly);
+        }
+        if let (Some(host_level), Some(target_level)) =
+            (host_integrity_level, target_integrity_level)
+        {
+            if target_level > host_level {
+                blockers.push(InjectionBlocker::ElevationRequired);
+            }
+        }
+
+        Self {
+            windows_version: windows_version(),
+            wine_version: wine_version(),
+            is_host_x64,
+            is_target_x64,
+            thread_creation_method,
+            requires_debug_privilege: !same_session,
+            protection_level,
+            mitigation_policies,
+            host_integrity_level,
+            target_integrity_level,
+            blockers,
+            unavailable,
+        }
+    }
+
+    /// Returns the version of the running Windows.
+    #[must_use]
+    pub fn windows_version(&self) -> WindowsVersion {
+        self.windows_version
+    }
+
+    /// Returns the version of Wine both processes are running under, if any.
+    #[must_use]
+    pub fn wine_version(&self) -> Option<&'static str> {
+        self.wine_version
+    }
+
+    /// Returns whether the current process is a 64-bit process.
+    #[must_use]
+    pub fn is_host_x64(&self) -> bool {
+        self.is_host_x64
+    }
+
+    /// Returns whether the target process is a 64-bit process, if it could be queried.
+    #[must_use]
+    pub fn is_target_x64(&self) -> Option<bool> {
+        self.is_target_x64
+    }
+
+    /// Returns whether the processes have a different bitness, which requires the `into-x86-from-x64` feature
+    /// for a 32-bit target and is not supported for a 64-bit target.
+    /// Returns [`None`] if the bitness of the target could not be queried.
+    #[must_use]
+    pub fn is_cross_bitness(&self) -> Option<bool> {
+        self.is_target_x64
+            .map(|is_target_x64| self.is_host_x64 != is_target_x64)
+    }
+
+    /// Returns the method that is expected to be used to create threads in the target process.
+    #[must_use]
+    pub fn thread_creation_method(&self) -> RemoteThreadCreationMethod {
+        self.thread_creation_method
+    }
+
+    /// Returns whether the target runs in a different session than the current process (e.g. a service),
+    /// which usually requires the debug privilege to open it (see [`enable_debug_privilege`](crate::process::enable_debug_privilege)).
+    /// If the sessions could not be queried, they are assumed to be the same.
+    #[must_use]
+    pub fn requires_debug_privilege(&self) -> bool {
+        self.requires_debug_privilege
+    }
+
+    /// Returns the protection level of the target process, if it is protected and its protection level could be queried.
+    #[must_use]
+    pub fn protection_level(&self) -> Option<ProtectionLevel> {
+        self.protection_level
+    }
+
+    /// Returns the mitigation policies of the target process, or the default (no) policies if they could not be queried.
+    #[must_use]
+    pub fn mitigation_policies(&self) -> MitigationPolicies {
+        self.mitigation_policies
+    }
+
+    /// Returns the integrity level of the current process, if it could be queried.
+    #[must_use]
+    pub fn host_integrity_level(&self) -> Option<IntegrityLevel> {
+        self.host_integrity_level
+    }
+
+    /// Returns the integrity level of the target process, if it could be queried.
+    #[must_use]
+    pub fn target_integrity_level(&self) -> Option<IntegrityLevel> {
+        self.target_integrity_level
+    }
+
+    /// Returns the reasons why injecting into the target process is expected to fail.
+    #[must_use]
+    pub fn blockers(&self) -> &[InjectionBlocker] {
+        &self.blockers
+    }
+
+    /// Returns whether injecting into the target process is expected to succeed.
+    /// Properties that could not be queried (see [`unavailable`](Capabilities::unavailable)) are not taken into account.
+    #[must_use]
+    pub fn can_inject(&self) -> bool {
+        self.blockers.is_empty()
+    }
+
+    /// Returns the properties of the target process that could not be queried, which are reported with default values.
+    #[must_use]
+    pub fn unavailable(&self) -> &[CapabilityProbe] {
+        &self.unavailable
+    }
+}
+
+/// Returns the result of the given probe or records it as unavailable if it failed.
+fn available<T>(
+    unavailable: &mut Vec<CapabilityProbe>,
+    probe: CapabilityProbe,
+    result: Result<T, io::Error>,
+) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(_) => {
+            unavailable.push(probe);
+            None
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    fmt, io,
+    os::windows::io::AsRawHandle,
+    panic::{self, AssertUnwindSafe},
+    ptr,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+use winapi::{
+    shared::{
+        minwindef::FALSE,
+        ntdef::{BOOLEAN, PVOID},
+    },
+    um::{
+        handleapi::INVALID_HANDLE_VALUE,
+        processthreadsapi::GetCurrentThreadId,
+        threadpoollegacyapiset::UnregisterWaitEx,
+        winbase::{RegisterWaitForSingleObject, INFINITE},
+        winnt::{HANDLE, WT_EXECUTEONLYONCE},
+    },
+};
+
+use crate::{process::OwnedProcess, utils::trace_event};
+
+type ExitCallback = Box<dyn FnOnce() + Send>;
+
+/// A registration for the exit of a process, see [`Process::watch_exit`] and [`Process::on_exit`].
+///
+/// The exit is waited for on the thread pool, so [`has_exited`](ProcessExitWatch::has_exited) does not need to query the process.
+/// The registration is cancelled on drop, which waits for a running callback to return unless the watch is dropped
+/// from within the callback itself. A panic in the callback is caught, as it must not unwind into the thread pool.
+pub struct ProcessExitWatch {
+    wait_handle: HANDLE,
+    state: Arc<ExitState>,
+    // the handle has to stay valid while the wait is registered.
+    _process: OwnedProcess,
+}
+
+unsafe impl Send for ProcessExitWatch {}
+unsafe impl Sync for ProcessExitWatch {}
+
+struct ExitState {
+    exited: AtomicBool,
+    callback: Mutex<Option<ExitCallback>>,
+    // the id of the thread running the callback, or 0 if it is not running.
+    callback_thread: AtomicU32,
+    // set if the watch was dropped from within the callback, which then releases the reference of the thread pool.
+    dropped_in_callback: AtomicBool,
+}
+
+impl ProcessExitWatch {
+    pub(crate) fn new(
+        process: OwnedProcess,
+        callback: Option<ExitCallback>,
+    ) -> Result<Self, io::Error> {
+        let state = Arc::new(ExitState {
+            exited: AtomicBool::new(false),
+            callback: Mutex::new(callback),
+            callback_thread: AtomicU32::new(0),
+            dropped_in_callback: AtomicBool::new(false),
+        });
+
+        // the thread pool holds its own reference, as the watch may be dropped while the callback is running.
+        let context = Arc::into_raw(Arc::clone(&state));
+        let mut wait_handle = INVALID_HANDLE_VALUE;
+        let result = unsafe {
+            RegisterWaitForSingleObject(
+                &mut wait_handle,
+                process.as_raw_handle(),
+                Some(exit_callback),
+                context as PVOID,
+                INFINITE,
+                WT_EXECUTEONLYONCE,
+            )
+        };
+        if result == FALSE {
+            let err = io::Error::last_os_error();
+            drop(unsafe { Arc::from_raw(context) });
+            return Err(err);
+        }
+
+        Ok(Self {
+            wait_handle,
+            state,
+            _process: process,
+        })
+    }
+
+    /// Returns whether the watched process has exited.
+    #[must_use]
+    pub fn has_exited(&self) -> bool {
+        self.state.exited.load(Ordering::Acquire)
+    }
+}
+
+unsafe extern "system" fn exit_callback(context: PVOID, _timed_out: BOOLEAN) {
+    let state = unsafe { &*(context as *const ExitState) };
+    state
+        .callback_thread
+        .store(unsafe { GetCurrentThreadId() }, Ordering::Release);
+    state.exited.store(true, Ordering::Release);
+    let callback = state
+        .callback
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+    if let Some(callback) = callback {
+        if panic::catch_unwind(AssertUnwindSafe(callback)).is_err() {
+            trace_event!(warn, "process exit callback panicked");
+        }
+    }
+    state.callback_thread.store(0, Ordering::Release);
+
+    if state.dropped_in_callback.load(Ordering::Acquire) {
+        drop(unsafe { Arc::from_raw(context as *const ExitState) });
+    }
+}
+
+impl Drop for ProcessExitWatch {
+    fn drop(&mut self) {
+        if self.state.callback_thread.load(Ordering::Acquire) == unsafe { GetCurrentThreadId() } {
+            // waiting for the callback would deadlock, so the callback releases the reference of the thread pool once it returns.
+            self.state
+                .dropped_in_callback
+                .store(true, Ordering::Release);
+            unsafe { UnregisterWaitEx(self.wait_handle, ptr::null_mut()) };
+            return;
+        }
+
+        // blocks until a running callback returned, so the thread pool no longer uses its reference.
+        unsafe { UnregisterWaitEx(self.wait_handle, INVALID_HANDLE_VALUE) };
+        drop(unsafe { Arc::from_raw(Arc::as_ptr(&self.state)) });
+    }
+}
+
+impl fmt::Debug for ProcessExitWatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProcessExitWatch")
+            .field("process", &self._process)
+            .field("has_exited", &self.has_exited())
+            .finish()
+    }
+}
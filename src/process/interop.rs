@@ -0,0 +1,211 @@
+//! Conversions between the handles used by this crate and the handle types of the
+//! [`windows-sys`](https://docs.rs/windows-sys) and [`windows`](https://docs.rs/windows) crates.
+//!
+//! All of these bindings represent handles as pointer sized values, so the conversions never change the handle itself.
+
+use std::os::windows::io::{AsRawHandle, FromRawHandle, IntoRawHandle};
+
+use crate::process::{BorrowedProcess, OwnedProcess, Process, ProcessModule};
+
+#[cfg(feature = "windows-sys")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "windows-sys")))]
+impl OwnedProcess {
+    /// Creates a new instance from the given [`windows-sys`](https://docs.rs/windows-sys) process handle,
+    /// taking ownership of it.
+    ///
+    /// # Safety
+    /// The caller must ensure that the handle is a valid, owned process handle with the required privileges
+    /// that is not closed by anyone else.
+    #[must_use]
+    pub unsafe fn from_windows_sys_handle(handle: windows_sys::Win32::Foundation::HANDLE) -> Self {
+        unsafe { Self::from_raw_handle(handle) }
+    }
+
+    /// Returns the underlying process handle as a [`windows-sys`](https://docs.rs/windows-sys) handle
+    /// without giving up ownership.
+    #[must_use]
+    pub fn as_windows_sys_handle(&self) -> windows_sys::Win32::Foundation::HANDLE {
+        self.as_raw_handle()
+    }
+
+    /// Consumes this instance and returns the underlying process handle as a
+    /// [`windows-sys`](https://docs.rs/windows-sys) handle. The caller becomes responsible for closing it.
+    #[must_use]
+    pub fn into_windows_sys_handle(self) -> windows_sys::Win32::Foundation::HANDLE {
+        self.into_raw_handle()
+    }
+}
+
+#[cfg(feature = "windows-sys")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "windows-sys")))]
+impl BorrowedProcess<'_> {
+    /// Creates a new instance borrowing the given [`windows-sys`](https://docs.rs/windows-sys) process handle.
+    ///
+    /// # Safety
+    /// The caller must ensure that the handle is a valid process handle with the required privileges
+    /// and that it stays open for the lifetime of the returned instance.
+    #[must_use]
+    pub unsafe fn from_windows_sys_handle(handle: windows_sys::Win32::Foundation::HANDLE) -> Self {
+        unsafe { Self::borrow_raw(handle) }
+    }
+
+    /// Returns the underlying process handle as a [`windows-sys`](https://docs.rs/windows-sys) handle.
+    #[must_use]
+    pub fn as_windows_sys_handle(&self) -> windows_sys::Win32::Foundation::HANDLE {
+        self.as_raw_handle()
+    }
+}
+
+#[cfg(feature = "windows-sys")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "windows-sys")))]
+impl<P: Process> ProcessModule<P> {
+    /// Contructs a new instance from the given [`windows-sys`](https://docs.rs/windows-sys) module handle
+    /// (which is the same type as its `HINSTANCE`) and its corresponding process.
+    ///
+    /// # Safety
+    /// The caller must guarantee that the given handle is valid and that the module is loaded into the given process.
+    /// (and stays that way while using the returned instance).
+    pub unsafe fn from_windows_sys_handle(
+        handle: windows_sys::Win32::Foundation::HMODULE,
+        process: P,
+    ) -> Self {
+        unsafe { Self::new_unchecked(handle.cast(), process) }
+    }
+
+    /// Returns the underlying module handle as a [`windows-sys`](https://docs.rs/windows-sys) handle.
+    #[must_use]
+    pub fn windows_sys_handle(&self) -> windows_sys::Win32::Foundation::HMODULE {
+        self.handle().cast()
+    }
+}
+
+#[cfg(feature = "windows")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "windows")))]
+impl OwnedProcess {
+    /// Creates a new instance from the given [`windows`](https://docs.rs/windows) process handle,
+    /// taking ownership of it.
+    ///
+    /// # Safety
+    /// The caller must ensure that the handle is a valid, owned process handle with the required privileges
+    /// that is not closed by anyone else.
+    #[must_use]
+    pub unsafe fn from_windows_handle(handle: windows::Win32::Foundation::HANDLE) -> Self {
+        unsafe { Self::from_raw_handle(handle.0) }
+    }
+
+    /// Returns the underlying process handle as a [`windows`](https://docs.rs/windows) handle
+    /// without giving up ownership.
+    #[must_use]
+    pub fn as_windows_handle(&self) -> windows::Win32::Foundation::HANDLE {
+        windows::Win32::Foundation::HANDLE(self.as_raw_handle())
+    }
+
+    /// Consumes this instance and returns the underlying process handle as a
+    /// [`windows`](https://docs.rs/windows) handle. The caller becomes responsible for closing it.
+    #[must_use]
+    pub fn into_windows_handle(self) -> windows::Win32::Foundation::HANDLE {
+        windows::Win32::Foundation::HANDLE(self.into_raw_handle())
+    }
+}
+
+#[cfg(feature = "windows")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "windows")))]
+impl BorrowedProcess<'_> {
+    /// Creates a new instance borrowing the given [`windows`](https://docs.rs/windows) process handle.
+    ///
+    /// # Safety
+    /// The caller must ensure that the handle is a valid process handle with the required privileges
+    /// and that it stays open for the lifetime of the returned instance.
+    #[must_use]
+    pub unsafe fn from_windows_handle(handle: windows::Win32::Foundation::HANDLE) -> Self {
+        unsafe { Self::borrow_raw(handle.0) }
+    }
+
+    /// Returns the underlying process handle as a [`windows`](https://docs.rs/windows) handle.
+    #[must_use]
+    pub fn as_windows_handle(&self) -> windows::Win32::Foundation::HANDLE {
+        windows::Win32::Foundation::HANDLE(self.as_raw_handle())
+    }
+}
+
+#[cfg(feature = "windows")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "windows")))]
+impl<P: Process> ProcessModule<P> {
+    /// Contructs a new instance from the given [`windows`](https://docs.rs/windows) module handle
+    /// (either a `HMODULE` or a `HINSTANCE`) and its corresponding process.
+    ///
+    /// # Safety
+    /// The caller must guarantee that the given handle is valid and that the module is loaded into the given process.
+    /// (and stays that way while using the returned instance).
+    pub unsafe fn from_windows_handle(
+        handle: impl Into<windows::Win32::Foundation::HMODULE>,
+        process: P,
+    ) -> Self {
+        unsafe { Self::new_unchecked(handle.into().0.cast(), process) }
+    }
+
+    /// Returns the underlying module handle as a [`windows`](https://docs.rs/windows) `HMODULE`.
+    #[must_use]
+    pub fn windows_handle(&self) -> windows::Win32::Foundation::HMODULE {
+        windows::Win32::Foundation::HMODULE(self.handle().cast())
+    }
+
+    /// Returns the underlying module handle as a [`windows`](https://docs.rs/windows) `HINSTANCE`.
+    #[must_use]
+    pub fn windows_instance_handle(&self) -> windows::Win32::Foundation::HINSTANCE {
+        self.windows_handle().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "windows-sys")]
+    #[test]
+    fn windows_sys_handles_round_trip() {
+        let process = OwnedProcess::current();
+        let raw = process.as_raw_handle();
+        let handle = process.into_windows_sys_handle();
+        assert_eq!(handle, raw);
+        let process = unsafe { OwnedProcess::from_windows_sys_handle(handle) };
+        assert_eq!(process.as_windows_sys_handle(), raw);
+
+        let borrowed = unsafe { BorrowedProcess::from_windows_sys_handle(handle) };
+        assert_eq!(borrowed.pid().unwrap(), process.pid().unwrap());
+
+        let module = process
+            .borrowed()
+            .find_module_by_name("kernel32.dll")
+            .unwrap()
+            .unwrap();
+        let converted = unsafe {
+            ProcessModule::from_windows_sys_handle(module.windows_sys_handle(), borrowed)
+        };
+        assert_eq!(converted.handle(), module.handle());
+    }
+
+    #[cfg(feature = "windows")]
+    #[test]
+    fn windows_handles_round_trip() {
+        let process = OwnedProcess::current();
+        let raw = process.as_raw_handle();
+        let handle = process.into_windows_handle();
+        assert_eq!(handle.0, raw);
+        let process = unsafe { OwnedProcess::from_windows_handle(handle) };
+        assert_eq!(process.as_windows_handle(), handle);
+
+        let borrowed = unsafe { BorrowedProcess::from_windows_handle(handle) };
+        assert_eq!(borrowed.pid().unwrap(), process.pid().unwrap());
+
+        let module = process
+            .borrowed()
+            .find_module_by_name("kernel32.dll")
+            .unwrap()
+            .unwrap();
+        let converted = unsafe {
+            ProcessModule::from_windows_handle(module.windows_instance_handle(), borrowed)
+        };
+        assert_eq!(converted.handle(), module.handle());
+    }
+}
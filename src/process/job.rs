@@ -0,0 +1,244 @@
+use std::{
+    io, mem,
+    os::windows::prelude::{AsHandle, AsRawHandle, BorrowedHandle, FromRawHandle, OwnedHandle},
+    ptr,
+    sync::Arc,
+};
+
+use winapi::{
+    shared::{
+        minwindef::{DWORD, FALSE},
+        winerror::{ERROR_ACCESS_DENIED, ERROR_MORE_DATA},
+    },
+    um::{
+        jobapi::IsProcessInJob,
+        jobapi2::{
+            AssignProcessToJobObject, CreateJobObjectW, QueryInformationJobObject,
+            SetInformationJobObject, TerminateJobObject,
+        },
+        winnt::{
+            JobObjectBasicProcessIdList, JobObjectExtendedLimitInformation,
+            JOBOBJECT_BASIC_PROCESS_ID_LIST, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+            JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE, PROCESS_SET_QUOTA, PROCESS_TERMINATE,
+        },
+    },
+};
+
+use crate::{
+    process::{BorrowedProcess, OwnedProcess, Process},
+    utils::trace_event,
+};
+
+/// The [privileges](https://docs.microsoft.com/en-us/windows/win32/procthread/process-security-and-access-rights) required for a process handle to be assigned to a job object.
+pub const JOB_ASSIGN_ACCESS: DWORD = PROCESS_SET_QUOTA | PROCESS_TERMINATE;
+
+/// An anonymous [job object](https://docs.microsoft.com/en-us/windows/win32/procthread/job-objects) owned by this process,
+/// used to group target processes so they can be cleaned up together.
+///
+/// Clones refer to the same job, which is closed once the last clone is dropped.
+/// If [kill on close](Self::set_kill_on_close) is enabled, all processes in the job are terminated at that point,
+/// which also happens if this process exits or crashes.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::process::{JobObject, OwnedProcess, Process};
+///
+/// let job = JobObject::new().unwrap();
+/// job.set_kill_on_close(true).unwrap();
+/// let spawned = OwnedProcess::spawn("target_process.exe").job(&job).spawn().unwrap();
+/// assert!(job.contains(spawned.process()).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct JobObject {
+    handle: Arc<OwnedHandle>,
+}
+
+impl AsRawHandle for JobObject {
+    fn as_raw_handle(&self) -> std::os::windows::raw::HANDLE {
+        self.handle.as_raw_handle()
+    }
+}
+
+impl AsHandle for JobObject {
+    fn as_handle(&self) -> BorrowedHandle<'_> {
+        self.handle.as_handle()
+    }
+}
+
+impl JobObject {
+    /// Creates a new anonymous job object without any limits.
+    pub fn new() -> Result<Self, io::Error> {
+        let handle = unsafe { CreateJobObjectW(ptr::null_mut(), ptr::null()) };
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            handle: Arc::new(unsafe { OwnedHandle::from_raw_handle(handle) }),
+        })
+    }
+
+    /// Creates a new instance from the given handle to a job object.
+    ///
+    /// # Safety
+    /// The caller must ensure that the handle is a valid handle to a job object.
+    #[must_use]
+    pub unsafe fn from_handle(handle: OwnedHandle) -> Self {
+        Self {
+            handle: Arc::new(handle),
+        }
+    }
+
+    /// Returns whether the processes in this job are terminated once the last handle to it is closed.
+    pub fn kill_on_close(&self) -> Result<bool, io::Error> {
+        let limits = self.extended_limits()?;
+        Ok(limits.BasicLimitInformation.LimitFlags & JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE != 0)
+    }
+
+    /// Sets whether the processes in this job are terminated once the last handle to it is closed,
+    /// e.g. because this process exited or crashed.
+    pub fn set_kill_on_close(&self, kill_on_close: bool) -> Result<(), io::Error> {
+        let mut limits = self.extended_limits()?;
+        if kill_on_close {
+            limits.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        } else {
+            limits.BasicLimitInformation.LimitFlags &= !JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        }
+        let result = unsafe {
+            SetInformationJobObject(
+                self.as_raw_handle(),
+                JobObjectExtendedLimitInformation,
+                ptr::addr_of_mut!(limits).cast(),
+                mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as DWORD,
+            )
+        };
+        if result == FALSE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn extended_limits(&self) -> Result<JOBOBJECT_EXTENDED_LIMIT_INFORMATION, io::Error> {
+        let mut limits: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = unsafe { mem::zeroed() };
+        let result = unsafe {
+            QueryInformationJobObject(
+                self.as_raw_handle(),
+                JobObjectExtendedLimitInformation,
+                ptr::addr_of_mut!(limits).cast(),
+                mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as DWORD,
+                ptr::null_mut(),
+            )
+        };
+        if result == FALSE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(limits)
+    }
+
+    /// Assigns the given process to this job. Child processes it creates afterwards are part of the job as well.
+    ///
+    /// If the handle of the process lacks the [`JOB_ASSIGN_ACCESS`] access rights, the process is reopened with them.
+    ///
+    /// # Note
+    /// A process can be part of multiple (nested) jobs starting with Windows 8, but can never leave a job.
+    pub fn assign(&self, process: &(impl Process + ?Sized)) -> Result<(), io::Error> {
+        let result =
+            unsafe { AssignProcessToJobObject(self.as_raw_handle(), process.as_raw_handle()) };
+        if result != FALSE {
+            return Ok(());
+        }
+
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(ERROR_ACCESS_DENIED as i32) {
+            return Err(err);
+        }
+        // the handle we hold keeps the pid from being reused, so reopening it refers to the same process.
+        trace_event!(debug, "reopening process for job assignment");
+        let process = OwnedProcess::from_pid_with_access(process.pid()?.get(), JOB_ASSIGN_ACCESS)?;
+        let result =
+            unsafe { AssignProcessToJobObject(self.as_raw_handle(), process.as_raw_handle()) };
+        if result == FALSE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Returns whether the given process is part of this job.
+    pub fn contains(&self, process: &(impl Process + ?Sized)) -> Result<bool, io::Error> {
+        let mut result = FALSE;
+        if unsafe { IsProcessInJob(process.as_raw_handle(), self.as_raw_handle(), &mut result) }
+            == FALSE
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(result != FALSE)
+    }
+
+    /// Returns the ids of the processes that are currently part of this job.
+    pub fn process_ids(&self) -> Result<Vec<u32>, io::Error> {
+        // the list starts with two counts followed by the ids, so it is read into a buffer of `usize`s to keep it aligned.
+        const HEADER_LEN: usize = 2 * mem::size_of::<DWORD>() / mem::size_of::<usize>();
+        let mut capacity = 64;
+        loop {
+            let mut buf = vec![0usize; HEADER_LEN + capacity];
+            let result = unsafe {
+                QueryInformationJobObject(
+                    self.as_raw_handle(),
+                    JobObjectBasicProcessIdList,
+                    buf.as_mut_ptr().cast(),
+                    mem::size_of_val(buf.as_slice()) as DWORD,
+                    ptr::null_mut(),
+                )
+            };
+            let list = unsafe { &*buf.as_ptr().cast::<JOBOBJECT_BASIC_PROCESS_ID_LIST>() };
+            if result != FALSE {
+                let ids = &buf[HEADER_LEN..][..list.NumberOfProcessIdsInList as usize];
+                return Ok(ids.iter().map(|&pid| pid as u32).collect());
+            }
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() != Some(ERROR_MORE_DATA as i32) {
+                return Err(err);
+            }
+            // processes may be added concurrently, so leave some room.
+            capacity = capacity.max(list.NumberOfAssignedProcesses as usize) * 2;
+        }
+    }
+
+    /// Terminates all processes that are currently part of this job with the given exit code.
+    pub fn terminate(&self, exit_code: u32) -> Result<(), io::Error> {
+        if unsafe { TerminateJobObject(self.as_raw_handle(), exit_code) } == FALSE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether the given process is part of any job.
+pub(crate) fn is_in_any_job(process: BorrowedProcess<'_>) -> Result<bool, io::Error> {
+    let mut result = FALSE;
+    if unsafe { IsProcessInJob(process.as_raw_handle(), ptr::null_mut(), &mut result) } == FALSE {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(result != FALSE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kill_on_close_can_be_toggled() {
+        let job = JobObject::new().unwrap();
+        assert!(!job.kill_on_close().unwrap());
+        job.set_kill_on_close(true).unwrap();
+        assert!(job.kill_on_close().unwrap());
+        job.set_kill_on_close(false).unwrap();
+        assert!(!job.kill_on_close().unwrap());
+    }
+
+    #[test]
+    fn new_job_is_empty() {
+        let job = JobObject::new().unwrap();
+        assert!(job.process_ids().unwrap().is_empty());
+        assert!(!job.contains(&BorrowedProcess::current()).unwrap());
+    }
+}
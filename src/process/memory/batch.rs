@@ -0,0 +1,177 @@
+use std::{io, ops::Range};
+
+use crate::{
+    process::{
+        memory::{ProcessMemoryBuffer, ProcessMemorySlice},
+        BorrowedProcess,
+    },
+    utils::trace_event,
+};
+
+/// The largest gap between two ranges on the same page that is read along with them instead of splitting the read.
+const MAX_READ_GAP: usize = 256;
+
+/// A contiguous span of memory covering one or more of the requested ranges.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Span {
+    range: Range<usize>,
+    // the indices of the requested ranges covered by this span.
+    members: Vec<usize>,
+}
+
+/// Groups the given ranges into spans that can each be transferred with a single call.
+///
+/// Overlapping and adjacent ranges are always merged. Ranges separated by a gap of at most `max_gap` bytes are merged
+/// too if the gap lies on a page that is also touched by one of the ranges, so the merged span does not reach into pages
+/// that were not requested (which may not be readable).
+fn coalesce(ranges: &[Range<usize>], max_gap: usize, page_size: usize) -> Vec<Span> {
+    let mut order = (0..ranges.len())
+        .filter(|&i| !ranges[i].is_empty())
+        .collect::<Vec<_>>();
+    order.sort_by_key(|&i| (ranges[i].start, ranges[i].end));
+
+    let mut spans = Vec::<Span>::new();
+    for i in order {
+        let range = &ranges[i];
+        if let Some(span) = spans.last_mut() {
+            let gap = range.start.saturating_sub(span.range.end);
+            let same_page = (span.range.end - 1) / page_size == range.start / page_size;
+            if gap == 0 || (gap <= max_gap && same_page) {
+                span.range.end = span.range.end.max(range.end);
+                span.members.push(i);
+                continue;
+            }
+        }
+        spans.push(Span {
+            range: range.clone(),
+            members: vec![i],
+        });
+    }
+    spans
+}
+
+pub(crate) fn read_many(
+    process: BorrowedProcess<'_>,
+    ranges: &[Range<usize>],
+) -> Result<Vec<Vec<u8>>, io::Error> {
+    let spans = coalesce(ranges, MAX_READ_GAP, ProcessMemoryBuffer::os_page_size());
+    trace_event!(
+        trace,
+        ranges = ranges.len(),
+        calls = spans.len(),
+        "batched memory read"
+    );
+
+    let mut results = vec![Vec::new(); ranges.len()];
+    for span in spans {
+        let memory = unsafe {
+            ProcessMemorySlice::from_raw_parts(
+                span.range.start as *mut u8,
+                span.range.len(),
+                process,
+            )
+        };
+        let bytes = memory.read_vec(0, span.range.len())?;
+        for i in span.members {
+            let range = &ranges[i];
+            results[i] =
+                bytes[range.start - span.range.start..range.end - span.range.start].to_vec();
+        }
+    }
+    Ok(results)
+}
+
+pub(crate) fn write_many(
+    process: BorrowedProcess<'_>,
+    writes: &[(usize, &[u8])],
+) -> Result<(), io::Error> {
+    let ranges = writes
+        .iter()
+        .map(|(address, bytes)| *address..*address + bytes.len())
+        .collect::<Vec<_>>();
+    // gaps can not be written without knowing their contents, so only touching ranges are merged.
+    let spans = coalesce(&ranges, 0, ProcessMemoryBuffer::os_page_size());
+    trace_event!(
+        trace,
+        writes = writes.len(),
+        calls = spans.len(),
+        "batched memory write"
+    );
+
+    for mut span in spans {
+        // overlapping writes are applied in the order they were given, so later writes win.
+        span.members.sort_unstable();
+        let mut buf = vec![0; span.range.len()];
+        for i in span.members {
+            let (address, bytes) = writes[i];
+            let offset = address - span.range.start;
+            buf[offset..offset + bytes.len()].copy_from_slice(bytes);
+        }
+        let memory = unsafe {
+            ProcessMemorySlice::from_raw_parts(span.range.start as *mut u8, buf.len(), process)
+        };
+        memory.write(0, &buf)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::Process;
+
+    #[test]
+    fn coalesce_merges_touching_and_nearby_ranges() {
+        let ranges = [
+            0x1010..0x1018,
+            0x1000..0x1008,
+            0x1004..0x1010,
+            0x1080..0x1084,
+        ];
+        let spans = coalesce(&ranges, 0x100, 0x1000);
+        assert_eq!(
+            spans,
+            [Span {
+                range: 0x1000..0x1084,
+                members: vec![1, 2, 0, 3],
+            }]
+        );
+
+        let spans = coalesce(&ranges, 0, 0x1000);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].range, 0x1000..0x1018);
+        assert_eq!(spans[1].range, 0x1080..0x1084);
+    }
+
+    #[test]
+    fn coalesce_does_not_bridge_pages() {
+        let spans = coalesce(&[0xff8..0x1000, 0x1008..0x1010], 0x100, 0x1000);
+        assert_eq!(spans.len(), 2);
+    }
+
+    #[test]
+    fn read_many_returns_requested_ranges() {
+        let data = *b"0123456789abcdef";
+        let base = data.as_ptr() as usize;
+        let ranges = [
+            base + 4..base + 8,
+            base..base + 6,
+            base + 12..base + 16,
+            base..base,
+        ];
+        let results = read_many(BorrowedProcess::current(), &ranges).unwrap();
+        assert_eq!(results, [&b"4567"[..], b"012345", b"cdef", b""]);
+    }
+
+    #[test]
+    fn write_many_applies_writes_in_order() {
+        let mut data = [0u8; 8];
+        let base = data.as_mut_ptr() as usize;
+        write_many(
+            BorrowedProcess::current(),
+            &[(base, b"aaaa"), (base + 2, b"bb"), (base + 6, b"cc")],
+        )
+        .unwrap();
+        assert_eq!(&data, b"aabb\0\0cc");
+    }
+}
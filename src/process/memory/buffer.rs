@@ -1,25 +1,31 @@
 use std::{
-    io,
+    cmp, io,
     marker::PhantomData,
     mem::{self, ManuallyDrop, MaybeUninit},
-    ops::{Deref, DerefMut, RangeBounds},
+    ops::{Deref, DerefMut, Range, RangeBounds},
     os::windows::prelude::AsRawHandle,
     ptr, slice,
 };
 
 use winapi::{
-    shared::minwindef::DWORD,
+    shared::{minwindef::DWORD, winerror::ERROR_INVALID_ADDRESS},
     um::{
         memoryapi::{ReadProcessMemory, VirtualAllocEx, VirtualFreeEx, WriteProcessMemory},
         processthreadsapi::FlushInstructionCache,
         sysinfoapi::GetSystemInfo,
-        winnt::{MEM_COMMIT, MEM_RELEASE, MEM_RESERVE, PAGE_EXECUTE_READWRITE, PAGE_READWRITE},
+        winnt::{
+            MEM_COMMIT, MEM_RELEASE, MEM_RESERVE, MEM_TOP_DOWN, PAGE_EXECUTE_READWRITE,
+            PAGE_READWRITE,
+        },
     },
 };
 
 use crate::{
-    process::{BorrowedProcess, Process},
-    utils,
+    process::{
+        memory::{AllocationPlacement, MemoryRegionIter, MemoryState, ProcessMemoryCursor},
+        BorrowedProcess, Process,
+    },
+    utils::{self, trace_event},
 };
 
 /// A owned buffer in the memory space of a (remote) process.
@@ -80,16 +86,113 @@ impl<'a> ProcessMemoryBuffer<'a> {
     pub fn allocate_code_page(process: BorrowedProcess<'a>) -> Result<Self, io::Error> {
         Self::allocate_code(process, Self::os_page_size())
     }
+    /// Allocates a new data buffer of the given length in the given process, placed according to the given placement.
+    pub fn allocate_data_with_placement(
+        process: BorrowedProcess<'a>,
+        len: usize,
+        placement: &AllocationPlacement,
+    ) -> Result<Self, io::Error> {
+        Self::allocate_with_placement(process, len, PAGE_READWRITE, placement)
+    }
+    /// Allocates a new code buffer of the given length in the given process, placed according to the given placement.
+    pub fn allocate_code_with_placement(
+        process: BorrowedProcess<'a>,
+        len: usize,
+        placement: &AllocationPlacement,
+    ) -> Result<Self, io::Error> {
+        Self::allocate_with_placement(process, len, PAGE_EXECUTE_READWRITE, placement)
+    }
+    /// Allocates a new buffer of the given length with the given protection in the given process.
+    #[cfg(feature = "syringe")]
+    pub(crate) fn allocate_with_protection(
+        process: BorrowedProcess<'a>,
+        len: usize,
+        protection: crate::process::memory::MemoryProtection,
+    ) -> Result<Self, io::Error> {
+        Self::allocate_with_options(process, len, MEM_COMMIT | MEM_RESERVE, protection.0)
+    }
+    fn allocate_with_placement(
+        process: BorrowedProcess<'a>,
+        len: usize,
+        protection: DWORD,
+        placement: &AllocationPlacement,
+    ) -> Result<Self, io::Error> {
+        let allocation_type = MEM_COMMIT | MEM_RESERVE;
+        let Some((range, fallback)) = placement.range() else {
+            let allocation_type = if *placement == AllocationPlacement::TopDown {
+                allocation_type | MEM_TOP_DOWN
+            } else {
+                allocation_type
+            };
+            return Self::allocate_with_options(process, len, allocation_type, protection);
+        };
+
+        let granularity = Self::os_allocation_granularity();
+        let regions = MemoryRegionIter::new(process);
+        // the first 64 KiB of the address space can never be allocated.
+        let mut address = range.start.max(granularity);
+        while address < range.end {
+            let Some(region) = regions.query(address)? else {
+                break;
+            };
+            let region_end = region.base() as usize + region.size();
+            if region.state() == MemoryState::Free {
+                let base = address.next_multiple_of(granularity);
+                if base
+                    .checked_add(len)
+                    .is_some_and(|end| end <= region_end.min(range.end))
+                {
+                    match Self::allocate_at(process, base, len, allocation_type, protection) {
+                        Ok(buffer) => return Ok(buffer),
+                        // the region was taken in the meantime.
+                        Err(err) if err.raw_os_error() == Some(ERROR_INVALID_ADDRESS as i32) => {
+                            address = base + granularity;
+                            continue;
+                        }
+                        Err(err) => return Err(err),
+                    }
+                }
+            }
+            address = region_end;
+        }
+
+        if fallback {
+            return Self::allocate_with_options(process, len, allocation_type, protection);
+        }
+        trace_event!(
+            debug,
+            len,
+            start = range.start,
+            end = range.end,
+            "no free memory in allocation range"
+        );
+        Err(io::Error::new(
+            io::ErrorKind::OutOfMemory,
+            format!(
+                "no free memory for {len:#x} bytes between {:#x} and {:#x}",
+                range.start, range.end
+            ),
+        ))
+    }
     fn allocate_with_options(
         process: BorrowedProcess<'a>,
         len: usize,
         allocation_type: DWORD,
         protection: DWORD,
+    ) -> Result<Self, io::Error> {
+        Self::allocate_at(process, 0, len, allocation_type, protection)
+    }
+    fn allocate_at(
+        process: BorrowedProcess<'a>,
+        address: usize,
+        len: usize,
+        allocation_type: DWORD,
+        protection: DWORD,
     ) -> Result<Self, io::Error> {
         let ptr = unsafe {
             VirtualAllocEx(
                 process.as_raw_handle(),
-                ptr::null_mut(),
+                address as *mut _,
                 len,
                 allocation_type,
                 protection,
@@ -97,9 +200,21 @@ impl<'a> ProcessMemoryBuffer<'a> {
         };
 
         return if ptr.is_null() {
-            Err(io::Error::last_os_error())
+            let err = io::Error::last_os_error();
+            trace_event!(debug, len, error = %err, "remote allocation failed");
+            Err(err)
         } else {
-            Ok(unsafe { Self::from_raw_parts(ptr.cast(), len, process) })
+            trace_event!(
+                trace,
+                ?ptr,
+                len,
+                protection = format_args!("{protection:#x}"),
+                "allocated remote memory"
+            );
+            let buffer = unsafe { Self::from_raw_parts(ptr.cast(), len, process) };
+            #[cfg(all(feature = "syringe", feature = "process-memory"))]
+            crate::journal::record_allocation(buffer.0);
+            Ok(buffer)
         };
     }
 
@@ -179,6 +294,8 @@ impl<'a> ProcessMemoryBuffer<'a> {
         unsafe { self._free() }.map_err(|e| (self, e))
     }
     unsafe fn _free(&mut self) -> Result<(), io::Error> {
+        #[cfg(all(feature = "syringe", feature = "process-memory"))]
+        crate::journal::forget_memory(self.0);
         let result = unsafe {
             VirtualFreeEx(
                 self.process.as_raw_handle(),
@@ -188,6 +305,7 @@ impl<'a> ProcessMemoryBuffer<'a> {
             )
         };
 
+        trace_event!(trace, ptr = ?self.as_ptr(), len = self.len(), "freed remote memory");
         if result != 0 || !self.process().is_alive() {
             Ok(())
         } else {
@@ -202,6 +320,14 @@ impl<'a> ProcessMemoryBuffer<'a> {
         unsafe { GetSystemInfo(system_info.as_mut_ptr()) };
         unsafe { system_info.assume_init() }.dwPageSize as usize
     }
+
+    /// Returns the granularity of the addresses new buffers can be allocated at, which is usually 64 KiB.
+    #[must_use]
+    pub fn os_allocation_granularity() -> usize {
+        let mut system_info = MaybeUninit::uninit();
+        unsafe { GetSystemInfo(system_info.as_mut_ptr()) };
+        unsafe { system_info.assume_init() }.dwAllocationGranularity as usize
+    }
 }
 
 impl Drop for ProcessMemoryBuffer<'_> {
@@ -224,6 +350,9 @@ pub struct ProcessMemorySlice<'a> {
 }
 
 impl<'a> ProcessMemorySlice<'a> {
+    /// The maximum number of bytes transferred by a single call to [`ReadProcessMemory`] or [`WriteProcessMemory`].
+    pub(crate) const IO_CHUNK_SIZE: usize = 1024 * 1024;
+
     /// Constructs a new slice from the given raw parts.
     ///
     /// # Safety
@@ -281,31 +410,114 @@ impl<'a> ProcessMemorySlice<'a> {
     /// # Panics
     /// This function will panic if the given offset plus the given buffer length exceeds this buffer's length.
     pub fn read(&self, offset: usize, buf: &mut [u8]) -> Result<(), io::Error> {
-        assert!(offset + buf.len() <= self.len, "read out of bounds");
+        unsafe { self.read_raw(offset, buf.as_mut_ptr(), buf.len(), &mut |_| {}) }
+    }
+
+    /// Copies the contents of this buffer starting from the given offset to the given local buffer like [`read`](Self::read),
+    /// calling the given function with the number of bytes copied so far after each chunk.
+    ///
+    /// # Panics
+    /// This function will panic if the given offset plus the given buffer length exceeds this buffer's length.
+    pub fn read_with_progress(
+        &self,
+        offset: usize,
+        buf: &mut [u8],
+        mut progress: impl FnMut(usize),
+    ) -> Result<(), io::Error> {
+        unsafe { self.read_raw(offset, buf.as_mut_ptr(), buf.len(), &mut progress) }
+    }
+
+    /// Copies the contents of this buffer starting from the given offset to the given uninitialized local buffer,
+    /// returning it as initialized bytes. This avoids zeroing large buffers that are overwritten anyway.
+    ///
+    /// # Panics
+    /// This function will panic if the given offset plus the given buffer length exceeds this buffer's length.
+    pub fn read_into_uninit<'b>(
+        &self,
+        offset: usize,
+        buf: &'b mut [MaybeUninit<u8>],
+    ) -> Result<&'b mut [u8], io::Error> {
+        unsafe { self.read_raw(offset, buf.as_mut_ptr().cast(), buf.len(), &mut |_| {}) }?;
+        Ok(unsafe { buf.assume_init_mut() })
+    }
+
+    /// Reads the given number of bytes of this buffer starting from the given offset into a new [`Vec`].
+    ///
+    /// # Panics
+    /// This function will panic if the given offset plus the given length exceeds this buffer's length.
+    pub fn read_vec(&self, offset: usize, len: usize) -> Result<Vec<u8>, io::Error> {
+        let mut buf = Vec::with_capacity(len);
+        self.read_into_uninit(offset, &mut buf.spare_capacity_mut()[..len])?;
+        unsafe { buf.set_len(len) };
+        Ok(buf)
+    }
+
+    unsafe fn read_raw(
+        &self,
+        offset: usize,
+        buf: *mut u8,
+        len: usize,
+        progress: &mut dyn FnMut(usize),
+    ) -> Result<(), io::Error> {
+        assert!(offset + len <= self.len, "read out of bounds");
 
         if self.is_local() {
             unsafe {
-                ptr::copy(self.ptr.add(offset), buf.as_mut_ptr(), buf.len());
+                ptr::copy(self.ptr.add(offset), buf, len);
             }
+            progress(len);
             return Ok(());
         }
 
-        let mut bytes_read = 0;
-        let result = unsafe {
-            ReadProcessMemory(
-                self.process.as_raw_handle(),
-                self.ptr.add(offset).cast(),
-                buf.as_mut_ptr().cast(),
-                buf.len(),
-                &mut bytes_read,
-            )
-        };
-        if result == 0 {
-            Err(io::Error::last_os_error())
-        } else {
-            assert_eq!(bytes_read, buf.len());
-            Ok(())
+        // ReadProcessMemory may only copy part of the requested range (e.g. if it spans multiple regions),
+        // so we read in bounded chunks and continue from wherever the previous call stopped.
+        let mut bytes_done = 0;
+        while bytes_done < len {
+            let chunk_len = cmp::min(len - bytes_done, Self::IO_CHUNK_SIZE);
+            let mut bytes_read = 0;
+            let result = unsafe {
+                ReadProcessMemory(
+                    self.process.as_raw_handle(),
+                    self.ptr.add(offset + bytes_done).cast(),
+                    buf.add(bytes_done).cast(),
+                    chunk_len,
+                    &mut bytes_read,
+                )
+            };
+            if result == 0 && bytes_read == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if bytes_read == 0 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+            }
+            bytes_done += bytes_read;
+            progress(bytes_done);
+        }
+
+        Ok(())
+    }
+
+    /// Copies the contents of this buffer starting from the given offset to the given local buffers in order.
+    /// The buffers are filled as if they were a single contiguous buffer.
+    ///
+    /// # Panics
+    /// This function will panic if the given offset plus the total length of the given buffers exceeds this buffer's length.
+    pub fn read_vectored(
+        &self,
+        offset: usize,
+        bufs: &mut [io::IoSliceMut<'_>],
+    ) -> Result<(), io::Error> {
+        let end = bufs
+            .iter()
+            .try_fold(offset, |end, buf| end.checked_add(buf.len()));
+        assert!(end.is_some_and(|end| end <= self.len), "read out of bounds");
+
+        let mut offset = offset;
+        for buf in bufs {
+            self.read(offset, buf)?;
+            offset += buf.len();
         }
+        Ok(())
     }
 
     /// Reads a value of type `T` from this buffer starting from the given offset.
@@ -328,37 +540,107 @@ impl<'a> ProcessMemorySlice<'a> {
     /// # Panics
     /// This function will panic if the given offset plus the size of the local buffer exceeds this buffer's length.
     pub fn write(&self, offset: usize, buf: &[u8]) -> Result<(), io::Error> {
+        self.write_raw(offset, buf, &mut |_| {})
+    }
+
+    /// Copies the contents of the given local buffer to this buffer at the given offset like [`write`](Self::write),
+    /// calling the given function with the number of bytes copied so far after each chunk.
+    ///
+    /// # Panics
+    /// This function will panic if the given offset plus the size of the local buffer exceeds this buffer's length.
+    pub fn write_with_progress(
+        &self,
+        offset: usize,
+        buf: &[u8],
+        mut progress: impl FnMut(usize),
+    ) -> Result<(), io::Error> {
+        self.write_raw(offset, buf, &mut progress)
+    }
+
+    fn write_raw(
+        &self,
+        offset: usize,
+        buf: &[u8],
+        progress: &mut dyn FnMut(usize),
+    ) -> Result<(), io::Error> {
         assert!(offset + buf.len() <= self.len, "write out of bounds");
 
+        #[cfg(all(feature = "syringe", feature = "process-memory"))]
+        let write = crate::journal::PendingWrite::new(self.slice(offset..offset + buf.len()));
+        self.write_unrecorded(offset, buf, progress)?;
+        #[cfg(all(feature = "syringe", feature = "process-memory"))]
+        write.commit();
+        Ok(())
+    }
+
+    fn write_unrecorded(
+        &self,
+        offset: usize,
+        buf: &[u8],
+        progress: &mut dyn FnMut(usize),
+    ) -> Result<(), io::Error> {
         if self.is_local() {
             unsafe {
                 ptr::copy(buf.as_ptr(), self.ptr.add(offset), buf.len());
             }
+            progress(buf.len());
             return Ok(());
         }
 
-        let mut bytes_written = 0;
         if buf.is_empty() {
             // This works around a discrepancy between Wine and actual Windows.
             // On Wine, a 0 sized write fails, on Windows this suceeds. Will file as bug soon.
             return Ok(());
         }
 
-        let result = unsafe {
-            WriteProcessMemory(
-                self.process.as_raw_handle(),
-                self.ptr.add(offset).cast(),
-                buf.as_ptr().cast(),
-                buf.len(),
-                &mut bytes_written,
-            )
-        };
-        if result == 0 {
-            Err(io::Error::last_os_error())
-        } else {
-            assert_eq!(bytes_written, buf.len());
-            Ok(())
+        // WriteProcessMemory may only copy part of the requested range for large buffers,
+        // so we write in bounded chunks and continue from wherever the previous call stopped.
+        let mut bytes_done = 0;
+        while bytes_done < buf.len() {
+            let chunk_len = cmp::min(buf.len() - bytes_done, Self::IO_CHUNK_SIZE);
+            let mut bytes_written = 0;
+            let result = unsafe {
+                WriteProcessMemory(
+                    self.process.as_raw_handle(),
+                    self.ptr.add(offset + bytes_done).cast(),
+                    buf.as_ptr().add(bytes_done).cast(),
+                    chunk_len,
+                    &mut bytes_written,
+                )
+            };
+            if result == 0 && bytes_written == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if bytes_written == 0 {
+                return Err(io::Error::from(io::ErrorKind::WriteZero));
+            }
+            bytes_done += bytes_written;
+            progress(bytes_done);
+        }
+
+        Ok(())
+    }
+
+    /// Copies the contents of the given local buffers in order to this buffer at the given offset.
+    /// The buffers are written as if they were a single contiguous buffer.
+    ///
+    /// # Panics
+    /// This function will panic if the given offset plus the total length of the given buffers exceeds this buffer's length.
+    pub fn write_vectored(&self, offset: usize, bufs: &[io::IoSlice<'_>]) -> Result<(), io::Error> {
+        let end = bufs
+            .iter()
+            .try_fold(offset, |end, buf| end.checked_add(buf.len()));
+        assert!(
+            end.is_some_and(|end| end <= self.len),
+            "write out of bounds"
+        );
+
+        let mut offset = offset;
+        for buf in bufs {
+            self.write(offset, buf)?;
+            offset += buf.len();
         }
+        Ok(())
     }
 
     /// Writes a value of type `T` to this buffer at the given offset.
@@ -371,6 +653,38 @@ impl<'a> ProcessMemorySlice<'a> {
         })
     }
 
+    /// Copies the entire contents of this buffer to the start of the given buffer, which may be located in a different process.
+    /// Data is streamed through a bounded local buffer instead of being copied into memory all at once.
+    ///
+    /// # Panics
+    /// This function will panic if the given buffer is shorter than this buffer.
+    pub fn copy_to(&self, other: &mut ProcessMemorySlice<'_>) -> Result<(), io::Error> {
+        assert!(other.len() >= self.len(), "copy out of bounds");
+
+        if self.is_local() && other.is_local() {
+            unsafe {
+                ptr::copy(self.ptr, other.ptr, self.len);
+            }
+            return Ok(());
+        }
+
+        let mut buf = vec![0u8; cmp::min(self.len, Self::IO_CHUNK_SIZE)];
+        let mut offset = 0;
+        while offset < self.len {
+            let chunk = &mut buf[..cmp::min(self.len - offset, Self::IO_CHUNK_SIZE)];
+            self.read(offset, chunk)?;
+            other.write(offset, chunk)?;
+            offset += chunk.len();
+        }
+        Ok(())
+    }
+
+    /// Returns a cursor over this buffer implementing [`io::Read`], [`io::Write`] and [`io::Seek`].
+    #[must_use]
+    pub const fn cursor(&self) -> ProcessMemoryCursor<'a> {
+        ProcessMemoryCursor::new(*self)
+    }
+
     /// Returns a pointer to the start of the buffer.
     ///
     /// # Note
@@ -381,9 +695,51 @@ impl<'a> ProcessMemorySlice<'a> {
     }
 
     /// Returns a slice of this buffer.
+    ///
+    /// # Panics
+    /// This function will panic if the given range is out of bounds.
     #[must_use]
     pub fn slice(&self, bounds: impl RangeBounds<usize>) -> Self {
-        let range = utils::range_from_bounds(self.ptr as usize, self.len, &bounds);
+        self.with_range(utils::range_from_bounds(
+            self.ptr as usize,
+            self.len,
+            &bounds,
+        ))
+    }
+
+    /// Returns a slice of this buffer, or [`None`] if the given range is out of bounds.
+    #[must_use]
+    pub fn get(&self, bounds: impl RangeBounds<usize>) -> Option<Self> {
+        utils::checked_range_from_bounds(self.ptr as usize, self.len, &bounds)
+            .map(|range| self.with_range(range))
+    }
+
+    /// Divides this buffer into two at the given offset, with the first slice containing the bytes before the offset
+    /// and the second one the remaining bytes.
+    ///
+    /// # Panics
+    /// This function will panic if the given offset exceeds this buffer's length.
+    #[must_use]
+    pub fn split_at(&self, offset: usize) -> (Self, Self) {
+        self.split_at_checked(offset)
+            .expect("split offset out of bounds")
+    }
+
+    /// Divides this buffer into two at the given offset like [`ProcessMemorySlice::split_at`],
+    /// or returns [`None`] if the given offset exceeds this buffer's length.
+    #[must_use]
+    pub fn split_at_checked(&self, offset: usize) -> Option<(Self, Self)> {
+        Some((self.get(..offset)?, self.get(offset..)?))
+    }
+
+    /// Returns the slice of this buffer holding a value of type `T` at the given offset, or [`None`] if the value
+    /// would exceed this buffer's length. This allows navigating the fields of a composite remote structure.
+    #[must_use]
+    pub fn get_for<T>(&self, offset: usize) -> Option<Self> {
+        self.get(offset..offset.checked_add(mem::size_of::<T>())?)
+    }
+
+    fn with_range(&self, range: Range<usize>) -> Self {
         Self {
             process: self.process,
             ptr: range.start as *mut _,
@@ -425,3 +781,171 @@ impl<'a> ProcessMemorySlice<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_vectored_writes_contiguously() {
+        let process = BorrowedProcess::current();
+        let buffer = ProcessMemoryBuffer::allocate_data(process, 8).unwrap();
+
+        buffer
+            .write_vectored(
+                1,
+                &[
+                    io::IoSlice::new(&[1, 2]),
+                    io::IoSlice::new(&[]),
+                    io::IoSlice::new(&[3, 4, 5]),
+                ],
+            )
+            .unwrap();
+
+        let mut first = [0u8; 3];
+        let mut second = [0u8; 3];
+        buffer
+            .read_vectored(
+                0,
+                &mut [
+                    io::IoSliceMut::new(&mut first),
+                    io::IoSliceMut::new(&mut second),
+                ],
+            )
+            .unwrap();
+        assert_eq!(first, [0, 1, 2]);
+        assert_eq!(second, [3, 4, 5]);
+    }
+
+    #[test]
+    #[should_panic(expected = "write out of bounds")]
+    fn write_vectored_rejects_overflowing_offset() {
+        let process = BorrowedProcess::current();
+        let buffer = ProcessMemoryBuffer::allocate_data(process, 8).unwrap();
+        buffer
+            .write_vectored(usize::MAX, &[io::IoSlice::new(&[1, 2])])
+            .unwrap();
+    }
+
+    #[test]
+    fn sub_views_are_bounds_checked() {
+        let process = BorrowedProcess::current();
+        let buffer = ProcessMemoryBuffer::allocate_data(process, 8).unwrap();
+        buffer.write(0, &[0, 1, 2, 3, 4, 5, 6, 7]).unwrap();
+
+        let middle = buffer.slice(2..5);
+        assert_eq!(middle.len(), 3);
+        assert_eq!(middle.read_vec(0, 3).unwrap(), [2, 3, 4]);
+        assert_eq!(buffer.slice(2..=5).len(), 4);
+
+        let (head, tail) = buffer.split_at(3);
+        assert_eq!(head.read_vec(0, 3).unwrap(), [0, 1, 2]);
+        assert_eq!(tail.read_vec(0, 5).unwrap(), [3, 4, 5, 6, 7]);
+        assert!(buffer.split_at_checked(9).is_none());
+
+        let field = buffer.get_for::<u32>(4).unwrap();
+        assert_eq!(unsafe { field.read_struct::<u32>(0) }.unwrap(), 0x0706_0504);
+        assert!(buffer.get_for::<u32>(5).is_none());
+        assert!(buffer.get(4..9).is_none());
+    }
+
+    #[test]
+    fn read_write_large_buffer() {
+        let process = BorrowedProcess::current();
+        let len = ProcessMemorySlice::IO_CHUNK_SIZE * 2 + 3;
+        let buffer = ProcessMemoryBuffer::allocate_data(process, len).unwrap();
+
+        let data = (0..len).map(|i| i as u8).collect::<Vec<_>>();
+        buffer.write(0, &data).unwrap();
+
+        let mut read_back = vec![0u8; len];
+        let mut progress = Vec::new();
+        buffer
+            .read_with_progress(0, &mut read_back, |done| progress.push(done))
+            .unwrap();
+        assert_eq!(data, read_back);
+        assert_eq!(progress.last(), Some(&len));
+    }
+
+    #[test]
+    fn read_vec_reads_without_initialized_buffer() {
+        let process = BorrowedProcess::current();
+        let len = ProcessMemorySlice::IO_CHUNK_SIZE + 7;
+        let buffer = ProcessMemoryBuffer::allocate_data(process, len).unwrap();
+
+        let data = (0..len).map(|i| (i % 253) as u8).collect::<Vec<_>>();
+        buffer.write(0, &data).unwrap();
+
+        assert_eq!(buffer.read_vec(0, len).unwrap(), data);
+        assert_eq!(buffer.read_vec(3, 4).unwrap(), &data[3..7]);
+
+        let mut uninit = [MaybeUninit::uninit(); 5];
+        assert_eq!(
+            buffer.read_into_uninit(len - 5, &mut uninit).unwrap(),
+            &data[len - 5..]
+        );
+    }
+
+    #[test]
+    fn copy_to_copies_all_chunks() {
+        let process = BorrowedProcess::current();
+        let len = ProcessMemorySlice::IO_CHUNK_SIZE + 5;
+        let source = ProcessMemoryBuffer::allocate_data(process, len).unwrap();
+        let mut target = ProcessMemoryBuffer::allocate_data(process, len + 1).unwrap();
+
+        let data = (0..len).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        source.write(0, &data).unwrap();
+        source.copy_to(&mut target).unwrap();
+
+        let mut read_back = vec![0u8; len];
+        target.read(0, &mut read_back).unwrap();
+        assert_eq!(data, read_back);
+    }
+
+    #[test]
+    fn allocations_respect_placement() {
+        let process = BorrowedProcess::current();
+        let granularity = ProcessMemoryBuffer::os_allocation_granularity();
+
+        let buffer = ProcessMemoryBuffer::allocate_data_with_placement(
+            process,
+            16,
+            &AllocationPlacement::Below4Gb,
+        )
+        .unwrap();
+        assert!((buffer.as_ptr() as u64) < 1 << 32);
+
+        let near = ProcessMemoryBuffer::allocate_code_with_placement(
+            process,
+            16,
+            &AllocationPlacement::near(buffer.as_ptr() as usize),
+        )
+        .unwrap();
+        assert!((near.as_ptr() as usize).abs_diff(buffer.as_ptr() as usize) <= i32::MAX as usize);
+        assert_eq!(near.as_ptr() as usize % granularity, 0);
+
+        let lowest = ProcessMemoryBuffer::allocate_data(process, 16).unwrap();
+        let highest = ProcessMemoryBuffer::allocate_data_with_placement(
+            process,
+            16,
+            &AllocationPlacement::TopDown,
+        )
+        .unwrap();
+        assert!(highest.as_ptr() > lowest.as_ptr());
+
+        // the first 64 KiB are never free.
+        let full = 0..granularity;
+        assert!(ProcessMemoryBuffer::allocate_data_with_placement(
+            process,
+            16,
+            &AllocationPlacement::Range(full.clone())
+        )
+        .is_err());
+        assert!(ProcessMemoryBuffer::allocate_data_with_placement(
+            process,
+            16,
+            &AllocationPlacement::PreferredRange(full)
+        )
+        .is_ok());
+    }
+}
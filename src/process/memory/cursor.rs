@@ -0,0 +1,153 @@
+use std::{
+    cmp,
+    io::{self, Read, Seek, SeekFrom, Write},
+};
+
+use crate::process::memory::ProcessMemorySlice;
+
+/// A cursor over a [`ProcessMemorySlice`] implementing [`Read`], [`Write`] and [`Seek`].
+///
+/// This allows remote memory to be consumed by code that expects a standard reader or writer
+/// without copying the whole region into a local buffer first.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[derive(Debug, Clone, Copy)]
+pub struct ProcessMemoryCursor<'a> {
+    memory: ProcessMemorySlice<'a>,
+    position: u64,
+}
+
+impl<'a> ProcessMemoryCursor<'a> {
+    /// Creates a new cursor starting at the beginning of the given memory slice.
+    #[must_use]
+    pub const fn new(memory: ProcessMemorySlice<'a>) -> Self {
+        Self {
+            memory,
+            position: 0,
+        }
+    }
+
+    /// Returns the underlying memory slice.
+    #[must_use]
+    pub const fn get_ref(&self) -> &ProcessMemorySlice<'a> {
+        &self.memory
+    }
+
+    /// Consumes this cursor and returns the underlying memory slice.
+    #[must_use]
+    pub const fn into_inner(self) -> ProcessMemorySlice<'a> {
+        self.memory
+    }
+
+    /// Returns the current position of this cursor.
+    #[must_use]
+    pub const fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Sets the position of this cursor.
+    pub fn set_position(&mut self, position: u64) {
+        self.position = position;
+    }
+
+    /// Returns the number of bytes between the current position and the end of the memory slice.
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        usize::try_from(self.position)
+            .map_or(0, |position| self.memory.len().saturating_sub(position))
+    }
+
+    fn start_and_len(&self, requested_len: usize) -> (usize, usize) {
+        let len = cmp::min(requested_len, self.remaining());
+        // if there are no remaining bytes the position may not fit into an usize, but is also not used.
+        let start = if len == 0 { 0 } else { self.position as usize };
+        (start, len)
+    }
+}
+
+impl Read for ProcessMemoryCursor<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let (start, len) = self.start_and_len(buf.len());
+        self.memory.read(start, &mut buf[..len])?;
+        self.position += len as u64;
+        Ok(len)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        if buf.len() > self.remaining() {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+        }
+        self.read(buf).map(|_| ())
+    }
+}
+
+impl Write for ProcessMemoryCursor<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let (start, len) = self.start_and_len(buf.len());
+        self.memory.write(start, &buf[..len])?;
+        self.position += len as u64;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for ProcessMemoryCursor<'_> {
+    fn seek(&mut self, style: SeekFrom) -> io::Result<u64> {
+        let (base, offset) = match style {
+            SeekFrom::Start(n) => {
+                self.position = n;
+                return Ok(n);
+            }
+            SeekFrom::End(n) => (self.memory.len() as u64, n),
+            SeekFrom::Current(n) => (self.position, n),
+        };
+        match base.checked_add_signed(offset) {
+            Some(n) => {
+                self.position = n;
+                Ok(n)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.position)
+    }
+}
+
+impl<'a> From<ProcessMemorySlice<'a>> for ProcessMemoryCursor<'a> {
+    fn from(memory: ProcessMemorySlice<'a>) -> Self {
+        Self::new(memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{memory::ProcessMemoryBuffer, BorrowedProcess, Process};
+
+    #[test]
+    fn write_seek_and_read_back() {
+        let process = BorrowedProcess::current();
+        let buffer = ProcessMemoryBuffer::allocate_data(process, 16).unwrap();
+        let mut cursor = buffer.cursor();
+
+        cursor.write_all(&[1, 2, 3, 4]).unwrap();
+        assert_eq!(cursor.position(), 4);
+
+        cursor.seek(SeekFrom::Current(-2)).unwrap();
+        let mut buf = [0u8; 2];
+        cursor.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, [3, 4]);
+
+        assert_eq!(cursor.seek(SeekFrom::End(0)).unwrap(), 16);
+        assert_eq!(cursor.read(&mut buf).unwrap(), 0);
+        assert_eq!(cursor.write(&buf).unwrap(), 0);
+        assert!(cursor.seek(SeekFrom::Current(-17)).is_err());
+    }
+}
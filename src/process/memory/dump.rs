@@ -0,0 +1,266 @@
+use std::{
+    cmp, env,
+    ffi::c_void,
+    fs::File,
+    io::{self, Seek, Write},
+    ops::Range,
+    os::windows::{fs::OpenOptionsExt, io::AsRawHandle},
+    ptr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use winapi::{
+    shared::minwindef::{BOOL, DWORD, FALSE},
+    um::{winbase::FILE_FLAG_DELETE_ON_CLOSE, winnt::HANDLE},
+};
+
+use crate::{
+    process::{
+        memory::{MemoryRegionIter, MemoryType, ProcessMemorySlice},
+        BorrowedProcess, Process,
+    },
+    CancellationToken,
+};
+
+const MINIDUMP_WITH_FULL_MEMORY: DWORD = 0x0000_0002;
+const MINIDUMP_WITH_HANDLE_DATA: DWORD = 0x0000_0004;
+const MINIDUMP_WITH_UNLOADED_MODULES: DWORD = 0x0000_0020;
+const MINIDUMP_WITH_THREAD_INFO: DWORD = 0x0000_1000;
+
+#[link(name = "dbghelp")]
+extern "system" {
+    fn MiniDumpWriteDump(
+        process: HANDLE,
+        process_id: DWORD,
+        file: HANDLE,
+        dump_type: DWORD,
+        exception_param: *const c_void,
+        user_stream_param: *const c_void,
+        callback_param: *const c_void,
+    ) -> BOOL;
+}
+
+/// The format of a dump written by [`Process::dump_all`](crate::process::Process::dump_all).
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DumpFormat {
+    /// The contents of all readable regions back to back, followed by an index describing them.
+    ///
+    /// The index consists of one entry per region with the little-endian fields
+    /// `address: u64`, `len: u64`, `offset: u64` (of the contents in the dump), `protection: u32` and `kind: u32`
+    /// (`1` for image, `2` for mapped and `3` for private memory, or `0` for a region without a type, see [`MemoryType::None`]).
+    /// It is followed by a trailer of `index_offset: u64`, `entry_count: u64` and the magic bytes [`DumpFormat::RAW_MAGIC`].
+    /// A region whose pages become unreadable while it is dumped is truncated to the part that could be read.
+    Raw,
+    /// A [minidump](https://docs.microsoft.com/en-us/windows/win32/api/minidumpapiset/nf-minidumpapiset-minidumpwritedump)
+    /// including the full memory, handles, threads and unloaded modules of the process, which can be opened by common debuggers.
+    Minidump,
+}
+
+impl DumpFormat {
+    /// The magic bytes at the end of a [`DumpFormat::Raw`] dump.
+    pub const RAW_MAGIC: [u8; 8] = *b"DSYRDUMP";
+}
+
+/// The number of bytes read from the process at once.
+const DUMP_CHUNK_LEN: usize = 64 * 1024;
+
+pub(crate) fn dump_region(
+    process: BorrowedProcess<'_>,
+    range: Range<usize>,
+    mut writer: impl Write,
+    cancellation: Option<&CancellationToken>,
+) -> Result<u64, io::Error> {
+    let len = range.end.saturating_sub(range.start);
+    let memory =
+        unsafe { ProcessMemorySlice::from_raw_parts(range.start as *mut u8, len, process) };
+    Ok(copy_memory(memory, &mut writer, false, cancellation)? as u64)
+}
+
+pub(crate) fn dump_all(
+    process: BorrowedProcess<'_>,
+    format: DumpFormat,
+    mut writer: impl Write,
+    cancellation: Option<&CancellationToken>,
+) -> Result<u64, io::Error> {
+    if let Some(token) = cancellation {
+        token.check()?;
+    }
+    match format {
+        DumpFormat::Raw => dump_raw(process, &mut writer, cancellation),
+        // the minidump is written by a single system call, so it can only be cancelled before it starts.
+        DumpFormat::Minidump => dump_minidump(process, &mut writer),
+    }
+}
+
+fn dump_raw(
+    process: BorrowedProcess<'_>,
+    writer: &mut impl Write,
+    cancellation: Option<&CancellationToken>,
+) -> Result<u64, io::Error> {
+    let mut index = Vec::new();
+    let mut offset = 0u64;
+    for region in MemoryRegionIter::new(process) {
+        let region = region?;
+        if !region.is_readable() {
+            continue;
+        }
+
+        let memory =
+            unsafe { ProcessMemorySlice::from_raw_parts(region.base(), region.size(), process) };
+        let len = copy_memory(memory, writer, true, cancellation)? as u64;
+        if len == 0 {
+            continue;
+        }
+
+        let kind: u32 = match region.kind() {
+            MemoryType::Image => 1,
+            MemoryType::Mapped => 2,
+            MemoryType::Private => 3,
+            // free regions are never readable, so this only keeps the encoding total.
+            MemoryType::None => 0,
+        };
+        index.extend_from_slice(&(region.base() as u64).to_le_bytes());
+        index.extend_from_slice(&len.to_le_bytes());
+        index.extend_from_slice(&offset.to_le_bytes());
+        index.extend_from_slice(&region.protection().0.to_le_bytes());
+        index.extend_from_slice(&kind.to_le_bytes());
+        offset += len;
+    }
+
+    let entry_count = (index.len() / 32) as u64;
+    writer.write_all(&index)?;
+    writer.write_all(&offset.to_le_bytes())?;
+    writer.write_all(&entry_count.to_le_bytes())?;
+    writer.write_all(&DumpFormat::RAW_MAGIC)?;
+    writer.flush()?;
+    Ok(offset + index.len() as u64 + 24)
+}
+
+fn dump_minidump(process: BorrowedProcess<'_>, writer: &mut impl Write) -> Result<u64, io::Error> {
+    // MiniDumpWriteDump can only write to a file, so the dump is staged in a temporary file that is deleted on close.
+    let pid = process.pid()?;
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    let path = env::temp_dir().join(format!("dll-syringe-{pid}-{nanos}.dmp"));
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .custom_flags(FILE_FLAG_DELETE_ON_CLOSE)
+        .open(path)?;
+
+    let result = unsafe {
+        MiniDumpWriteDump(
+            process.as_raw_handle(),
+            pid.get(),
+            file.as_raw_handle(),
+            MINIDUMP_WITH_FULL_MEMORY
+                | MINIDUMP_WITH_HANDLE_DATA
+                | MINIDUMP_WITH_UNLOADED_MODULES
+                | MINIDUMP_WITH_THREAD_INFO,
+            ptr::null(),
+            ptr::null(),
+            ptr::null(),
+        )
+    };
+    if result == FALSE {
+        return Err(io::Error::last_os_error());
+    }
+
+    file.rewind()?;
+    let len = io::copy(&mut file, writer)?;
+    writer.flush()?;
+    Ok(len)
+}
+
+/// Copies the given memory to the writer through a bounded buffer and returns the number of bytes copied.
+/// If `truncate_unreadable` is set, copying stops at the first chunk that cannot be read instead of failing.
+/// If a cancellation token is given, it throttles the reads and copying fails once it is cancelled.
+fn copy_memory(
+    memory: ProcessMemorySlice<'_>,
+    writer: &mut impl Write,
+    truncate_unreadable: bool,
+    cancellation: Option<&CancellationToken>,
+) -> Result<usize, io::Error> {
+    let mut buf = Vec::with_capacity(cmp::min(memory.len(), DUMP_CHUNK_LEN));
+    let mut offset = 0;
+    while offset < memory.len() {
+        let chunk_len = cmp::min(memory.len() - offset, DUMP_CHUNK_LEN);
+        if let Some(token) = cancellation {
+            token.throttle(chunk_len)?;
+        }
+        let chunk =
+            match memory.read_into_uninit(offset, &mut buf.spare_capacity_mut()[..chunk_len]) {
+                Ok(chunk) => chunk,
+                Err(_) if truncate_unreadable => break,
+                Err(err) => return Err(err),
+            };
+        writer.write_all(chunk)?;
+        offset += chunk_len;
+    }
+    Ok(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::memory::ProcessMemoryBuffer;
+
+    #[test]
+    fn raw_dump_indexes_region_contents() {
+        let process = BorrowedProcess::current();
+        let buffer = ProcessMemoryBuffer::allocate_data_page(process).unwrap();
+        let data = (0..buffer.len())
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        buffer.write(0, &data).unwrap();
+
+        // dumping into a growing buffer of the dumped process would also dump the buffer.
+        let mut file = tempfile::tempfile().unwrap();
+        let len = dump_all(process, DumpFormat::Raw, &mut file, None).unwrap();
+        let mut dump = Vec::new();
+        file.rewind().unwrap();
+        io::Read::read_to_end(&mut file, &mut dump).unwrap();
+        assert_eq!(len, dump.len() as u64);
+        assert!(dump.ends_with(&DumpFormat::RAW_MAGIC));
+
+        let read_u64 =
+            |offset: usize| u64::from_le_bytes(dump[offset..offset + 8].try_into().unwrap());
+        let trailer = dump.len() - 24;
+        let index_offset = read_u64(trailer) as usize;
+        let entry_count = read_u64(trailer + 8) as usize;
+        let entry = (0..entry_count)
+            .map(|i| index_offset + i * 32)
+            .find(|&entry| read_u64(entry) == buffer.as_ptr() as u64)
+            .unwrap();
+        assert_eq!(read_u64(entry + 8), data.len() as u64);
+        let contents = read_u64(entry + 16) as usize;
+        assert_eq!(&dump[contents..contents + data.len()], data);
+    }
+
+    #[test]
+    fn dump_region_writes_range() {
+        let process = BorrowedProcess::current();
+        let buffer = ProcessMemoryBuffer::allocate_data(process, 16).unwrap();
+        buffer.write(0, &[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+
+        let start = buffer.as_ptr() as usize;
+        let mut dump = Vec::new();
+        assert_eq!(
+            dump_region(process, start + 2..start + 6, &mut dump, None).unwrap(),
+            4
+        );
+        assert_eq!(dump, [3, 4, 5, 6]);
+
+        let token = CancellationToken::new();
+        token.cancel();
+        assert_eq!(
+            dump_region(process, start..start + 8, &mut dump, Some(&token))
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::Interrupted
+        );
+    }
+}
@@ -0,0 +1,383 @@
+use std::{
+    fmt, io, mem,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    process::{
+        memory::{NoUninit, ProcessMemorySlice, RemotePtr},
+        OwnedProcess, Process,
+    },
+    utils::trace_event,
+};
+
+/// The identifier of a value frozen by a [`FreezeList`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+pub struct FreezeId(u64);
+
+/// The state of a value frozen by a [`FreezeList`], see [`FreezeList::entry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+pub struct FrozenValue {
+    address: RemotePtr<u8>,
+    // shared with the writer thread, which writes the value without holding the lock of the list.
+    value: Arc<[u8]>,
+    interval: Duration,
+    enabled: bool,
+    writes: u64,
+    last_error: Option<io::ErrorKind>,
+}
+
+impl FrozenValue {
+    /// Returns the address the value is written to.
+    #[must_use]
+    pub const fn address(&self) -> RemotePtr<u8> {
+        self.address
+    }
+
+    /// Returns the bytes written to the address.
+    #[must_use]
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+
+    /// Returns how often the value is written.
+    #[must_use]
+    pub const fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns whether the value is currently written.
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns how often the value was written successfully.
+    #[must_use]
+    pub const fn writes(&self) -> u64 {
+        self.writes
+    }
+
+    /// Returns the kind of the error of the last write, or [`None`] if it succeeded or the value was not written yet.
+    #[must_use]
+    pub const fn last_error(&self) -> Option<io::ErrorKind> {
+        self.last_error
+    }
+}
+
+struct FreezeEntry {
+    id: FreezeId,
+    value: FrozenValue,
+    next_write: Instant,
+}
+
+#[derive(Default)]
+struct FreezeState {
+    entries: Vec<FreezeEntry>,
+    next_id: u64,
+    stopped: bool,
+}
+
+struct FreezeShared {
+    state: Mutex<FreezeState>,
+    // signalled whenever the entries change or the list is stopped, so the writer thread reschedules.
+    changed: Condvar,
+}
+
+/// Keeps values in the memory of a process frozen by periodically rewriting them on a background thread,
+/// e.g. to pin a counter of a game in a trainer.
+///
+/// Each value has its own interval and can be enabled and disabled without removing it.
+/// Failed writes, e.g. because the memory was freed, are recorded in the [entry](FreezeList::entry) of the value and do not
+/// stop the list. The background thread stops when the list is dropped.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::process::{memory::{FreezeList, RemotePtr}, OwnedProcess};
+/// use std::time::Duration;
+///
+/// let process = OwnedProcess::find_first_by_name("ExampleProcess").unwrap();
+/// let freeze = FreezeList::start(process).unwrap();
+/// let health = freeze.add_value(RemotePtr::<u32>::new(0x1234_5678), &100, Duration::from_millis(50));
+/// // ...
+/// freeze.set_enabled(health, false);
+/// ```
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+pub struct FreezeList {
+    shared: Arc<FreezeShared>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl fmt::Debug for FreezeList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FreezeList")
+            .field("entries", &self.lock().entries.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl FreezeList {
+    /// Creates a new empty list for the given process and starts its background thread.
+    pub fn start(process: OwnedProcess) -> Result<Self, io::Error> {
+        let shared = Arc::new(FreezeShared {
+            state: Mutex::new(FreezeState::default()),
+            changed: Condvar::new(),
+        });
+        let writer = thread::Builder::new()
+            .name("dll-syringe freeze list".to_string())
+            .spawn({
+                let shared = shared.clone();
+                move || run_writer(&process, &shared)
+            })?;
+        Ok(Self {
+            shared,
+            writer: Some(writer),
+        })
+    }
+
+    /// Freezes the given bytes at the given address, rewriting them with the given interval starting right away.
+    ///
+    /// # Panics
+    /// This function panics if the interval is zero.
+    pub fn add(
+        &self,
+        address: RemotePtr<u8>,
+        value: impl Into<Vec<u8>>,
+        interval: Duration,
+    ) -> FreezeId {
+        assert!(!interval.is_zero(), "freeze interval must not be zero");
+        let mut state = self.lock();
+        let id = FreezeId(state.next_id);
+        state.next_id += 1;
+        state.entries.push(FreezeEntry {
+            id,
+            value: FrozenValue {
+                address,
+                value: value.into().into(),
+                interval,
+                enabled: true,
+                writes: 0,
+                last_error: None,
+            },
+            next_write: Instant::now(),
+        });
+        self.shared.changed.notify_all();
+        id
+    }
+
+    /// Freezes the given value at the given address, see [`FreezeList::add`].
+    ///
+    /// # Panics
+    /// This function panics if the interval is zero.
+    pub fn add_value<T: NoUninit>(
+        &self,
+        address: RemotePtr<T>,
+        value: &T,
+        interval: Duration,
+    ) -> FreezeId {
+        // SAFETY: `NoUninit` guarantees that all bytes of the value are initialized.
+        let bytes = unsafe {
+            std::slice::from_raw_parts((value as *const T).cast::<u8>(), mem::size_of::<T>())
+        };
+        self.add(address.cast(), bytes, interval)
+    }
+
+    /// Returns the state of the value with the given id, or [`None`] if it was removed.
+    #[must_use]
+    pub fn entry(&self, id: FreezeId) -> Option<FrozenValue> {
+        self.lock()
+            .entries
+            .iter()
+            .find(|entry| entry.id == id)
+            .map(|entry| entry.value.clone())
+    }
+
+    /// Returns the ids of all values in this list in the order they were added.
+    #[must_use]
+    pub fn ids(&self) -> Vec<FreezeId> {
+        self.lock().entries.iter().map(|entry| entry.id).collect()
+    }
+
+    /// Enables or disables rewriting the value with the given id and returns whether there is such a value.
+    /// An enabled value is rewritten right away.
+    #[allow(clippy::must_use_candidate)]
+    pub fn set_enabled(&self, id: FreezeId, enabled: bool) -> bool {
+        self.update(id, |entry| {
+            entry.value.enabled = enabled;
+            entry.next_write = Instant::now();
+        })
+    }
+
+    /// Replaces the bytes written for the value with the given id and returns whether there is such a value.
+    pub fn set_value(&self, id: FreezeId, value: impl Into<Vec<u8>>) -> bool {
+        let value = value.into();
+        self.update(id, |entry| {
+            entry.value.value = value.into();
+            entry.next_write = Instant::now();
+        })
+    }
+
+    /// Changes the interval of the value with the given id and returns whether there is such a value.
+    ///
+    /// # Panics
+    /// This function panics if the interval is zero.
+    #[allow(clippy::must_use_candidate)]
+    pub fn set_interval(&self, id: FreezeId, interval: Duration) -> bool {
+        assert!(!interval.is_zero(), "freeze interval must not be zero");
+        self.update(id, |entry| {
+            entry.value.interval = interval;
+            entry.next_write = Instant::now();
+        })
+    }
+
+    /// Stops rewriting the value with the given id and returns whether there was such a value.
+    /// The memory keeps its current contents.
+    #[allow(clippy::must_use_candidate)]
+    pub fn remove(&self, id: FreezeId) -> bool {
+        let mut state = self.lock();
+        let len = state.entries.len();
+        state.entries.retain(|entry| entry.id != id);
+        len != state.entries.len()
+    }
+
+    fn update(&self, id: FreezeId, f: impl FnOnce(&mut FreezeEntry)) -> bool {
+        let mut state = self.lock();
+        let Some(entry) = state.entries.iter_mut().find(|entry| entry.id == id) else {
+            return false;
+        };
+        f(entry);
+        self.shared.changed.notify_all();
+        true
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FreezeState> {
+        self.shared.state.lock().unwrap()
+    }
+}
+
+impl Drop for FreezeList {
+    fn drop(&mut self) {
+        self.lock().stopped = true;
+        self.shared.changed.notify_all();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+fn run_writer(process: &OwnedProcess, shared: &FreezeShared) {
+    let mut state = shared.state.lock().unwrap();
+    while !state.stopped {
+        let now = Instant::now();
+        let due = state
+            .entries
+            .iter_mut()
+            .filter(|entry| entry.value.enabled && entry.next_write <= now)
+            .map(|entry| {
+                entry.next_write = now + entry.value.interval;
+                (entry.id, entry.value.address, entry.value.value.clone())
+            })
+            .collect::<Vec<_>>();
+        if !due.is_empty() {
+            // the list can be changed while the values are written, e.g. if the target is slow to respond.
+            drop(state);
+            let results = due
+                .into_iter()
+                .map(|(id, address, value)| (id, write_value(process, address, &value)))
+                .collect::<Vec<_>>();
+            state = shared.state.lock().unwrap();
+            for (id, result) in results {
+                // the value may have been removed in the meantime.
+                if let Some(entry) = state.entries.iter_mut().find(|entry| entry.id == id) {
+                    record_write(entry, result);
+                }
+            }
+            continue;
+        }
+
+        let next_wake = state
+            .entries
+            .iter()
+            .filter(|entry| entry.value.enabled)
+            .map(|entry| entry.next_write)
+            .min();
+        state = match next_wake {
+            Some(wake) => {
+                let timeout = wake.saturating_duration_since(Instant::now());
+                shared.changed.wait_timeout(state, timeout).unwrap().0
+            }
+            None => shared.changed.wait(state).unwrap(),
+        };
+    }
+}
+
+fn write_value(
+    process: &OwnedProcess,
+    address: RemotePtr<u8>,
+    value: &[u8],
+) -> Result<(), io::Error> {
+    let memory = unsafe {
+        ProcessMemorySlice::from_raw_parts(address.as_ptr(), value.len(), process.borrowed())
+    };
+    memory.write(0, value)
+}
+
+fn record_write(entry: &mut FreezeEntry, result: Result<(), io::Error>) {
+    match result {
+        Ok(()) => {
+            entry.value.writes += 1;
+            entry.value.last_error = None;
+        }
+        Err(err) => {
+            if entry.value.last_error.is_none() {
+                trace_event!(debug, address = ?entry.value.address, error = %err, "failed to write frozen value");
+            }
+            entry.value.last_error = Some(err.kind());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn wait_until(mut condition: impl FnMut() -> bool) {
+        let start = Instant::now();
+        while !condition() {
+            assert!(start.elapsed() < Duration::from_secs(10), "timed out");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn rewrites_enabled_values() {
+        let value = AtomicU32::new(0);
+        let freeze = FreezeList::start(OwnedProcess::current()).unwrap();
+        let id = freeze.add_value(
+            RemotePtr::from(value.as_ptr()),
+            &42u32,
+            Duration::from_millis(1),
+        );
+        wait_until(|| value.load(Ordering::SeqCst) == 42);
+
+        value.store(7, Ordering::SeqCst);
+        wait_until(|| value.load(Ordering::SeqCst) == 42);
+        assert!(freeze.entry(id).unwrap().writes() >= 2);
+
+        assert!(freeze.set_enabled(id, false));
+        let writes = freeze.entry(id).unwrap().writes();
+        value.store(7, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(value.load(Ordering::SeqCst), 7);
+        assert_eq!(freeze.entry(id).unwrap().writes(), writes);
+
+        assert!(freeze.remove(id));
+        assert!(!freeze.set_enabled(id, true));
+        assert_eq!(freeze.entry(id), None);
+    }
+}
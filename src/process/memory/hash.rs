@@ -0,0 +1,104 @@
+use std::{cmp, hash::Hasher, io};
+
+use crate::process::memory::ProcessMemorySlice;
+
+impl ProcessMemorySlice<'_> {
+    /// Feeds the contents of this buffer to the given hasher and returns the resulting hash.
+    /// The contents are streamed through a bounded local buffer instead of being copied into memory all at once.
+    pub fn hash<H: Hasher>(&self, mut hasher: H) -> Result<u64, io::Error> {
+        self.for_each_chunk(|chunk| hasher.write(chunk))?;
+        Ok(hasher.finish())
+    }
+
+    /// Computes the CRC-32 (IEEE) checksum of the contents of this buffer.
+    /// The contents are streamed through a bounded local buffer instead of being copied into memory all at once.
+    pub fn crc32(&self) -> Result<u32, io::Error> {
+        let mut crc = Crc32::new();
+        self.for_each_chunk(|chunk| crc.update(chunk))?;
+        Ok(crc.finish())
+    }
+
+    fn for_each_chunk(&self, mut f: impl FnMut(&[u8])) -> Result<(), io::Error> {
+        if let Some(local) = self.as_local_slice() {
+            f(local);
+            return Ok(());
+        }
+
+        let mut buf = vec![0u8; cmp::min(self.len(), Self::IO_CHUNK_SIZE)];
+        let mut offset = 0;
+        while offset < self.len() {
+            let chunk = &mut buf[..cmp::min(self.len() - offset, Self::IO_CHUNK_SIZE)];
+            self.read(offset, chunk)?;
+            f(chunk);
+            offset += chunk.len();
+        }
+        Ok(())
+    }
+}
+
+struct Crc32 {
+    value: u32,
+}
+
+impl Crc32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut value = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                value = if value & 1 != 0 {
+                    (value >> 1) ^ 0xEDB8_8320
+                } else {
+                    value >> 1
+                };
+                bit += 1;
+            }
+            table[i] = value;
+            i += 1;
+        }
+        table
+    };
+
+    const fn new() -> Self {
+        Self { value: !0 }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.value =
+                Self::TABLE[((self.value ^ u32::from(byte)) & 0xFF) as usize] ^ (self.value >> 8);
+        }
+    }
+
+    const fn finish(&self) -> u32 {
+        !self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+
+    use crate::process::{memory::ProcessMemoryBuffer, BorrowedProcess, Process};
+
+    #[test]
+    fn crc32_matches_reference() {
+        let process = BorrowedProcess::current();
+        let buffer = ProcessMemoryBuffer::allocate_data(process, 9).unwrap();
+        buffer.write(0, b"123456789").unwrap();
+        assert_eq!(buffer.crc32().unwrap(), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn hash_changes_with_contents() {
+        let process = BorrowedProcess::current();
+        let buffer = ProcessMemoryBuffer::allocate_data(process, 64).unwrap();
+        let before = buffer.hash(DefaultHasher::new()).unwrap();
+        assert_eq!(before, buffer.hash(DefaultHasher::new()).unwrap());
+
+        buffer.write(63, &[1]).unwrap();
+        assert_ne!(before, buffer.hash(DefaultHasher::new()).unwrap());
+    }
+}
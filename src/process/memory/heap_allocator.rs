@@ -0,0 +1,287 @@
+use std::{io, mem};
+
+#[cfg(feature = "assembler")]
+use iced_x86::{code_asm::*, IcedError};
+#[cfg(not(feature = "assembler"))]
+use {crate::stub_templates, std::convert::Infallible};
+
+use crate::{
+    process::{
+        memory::{Allocation, DynamicMultiBufferAllocator, ProcessMemorySlice, RawAllocator},
+        peb::process_heap_address,
+        BorrowedProcess, ModuleListFilter, Process, ProcessModule, RemoteThreadResult,
+    },
+    utils::trace_event,
+};
+
+/// The memory backing the data allocations made by a [`Syringe`](crate::Syringe) in its target process,
+/// see [`Syringe::set_allocation_backend`](crate::Syringe::set_allocation_backend).
+///
+/// The code of the stubs used by the syringe is always placed in pages allocated with `VirtualAllocEx`,
+/// as heap memory is not executable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub enum RemoteAllocationBackend {
+    /// Suballocate from whole pages allocated with `VirtualAllocEx`.
+    #[default]
+    Pages,
+    /// Allocate from the default heap of the target process using `RtlAllocateHeap`, so small allocations do not
+    /// occupy whole pages. Every allocation and free runs a thread in the target process, which makes this backend slower.
+    ProcessHeap,
+}
+
+/// An allocator for the default heap of a (remote) process, which calls `RtlAllocateHeap` and `RtlFreeHeap`
+/// in the process through a small stub.
+#[derive(Debug)]
+pub(crate) struct RemoteHeapAllocator {
+    process: BorrowedProcess<'static>,
+    heap: usize,
+    allocate_heap: usize,
+    free_heap: usize,
+    word_len: usize,
+    stub: Allocation,
+    call_block: Allocation,
+}
+
+impl RemoteHeapAllocator {
+    /// The number of words in the block passed to the stub: the function, its three arguments and its return value.
+    const CALL_BLOCK_WORDS: usize = 5;
+
+    /// Creates a new allocator for the default heap of the given process, placing the stub in memory of the given page allocator.
+    pub fn new(
+        process: BorrowedProcess<'static>,
+        pages: &mut DynamicMultiBufferAllocator<'static>,
+    ) -> Result<Self, io::Error> {
+        let is_x86 = process.is_x86()?;
+        let heap = process_heap_address(process)?;
+
+        // a WOW64 process has both a 32-bit and a 64-bit ntdll, the stub has to call the one matching its bitness.
+        let filter = if is_x86 {
+            ModuleListFilter::X86
+        } else {
+            ModuleListFilter::X64
+        };
+        let ntdll = process
+            .module_handles_with_filter(filter)?
+            .map(|handle| unsafe { ProcessModule::new_unchecked(handle, process) })
+            .find(|module| {
+                module
+                    .base_name()
+                    .is_ok_and(|name| name.eq_ignore_ascii_case("ntdll.dll"))
+            })
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "ntdll.dll is not loaded in the target process",
+                )
+            })?;
+        let find_export = |name: &str| {
+            ntdll
+                .get_procedure_address_from_exports(name)?
+                .map(|address| address as usize)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("ntdll.dll does not export {name}"),
+                    )
+                })
+        };
+        let allocate_heap = find_export("RtlAllocateHeap")?;
+        let free_heap = find_export("RtlFreeHeap")?;
+
+        let code = if is_x86 {
+            Self::build_code_x86()
+        } else {
+            Self::build_code_x64()
+        }
+        .unwrap();
+        let stub = pages.alloc(code.len())?;
+        unsafe { ProcessMemorySlice::from_raw_parts(stub.as_raw_ptr(), stub.len, process) }
+            .write(0, &code)?;
+
+        let word_len = if is_x86 { 4 } else { 8 };
+        let call_block = pages.alloc(Self::CALL_BLOCK_WORDS * word_len)?;
+        trace_event!(debug, heap = heap, stub = ?stub.as_raw_ptr(), "created remote heap allocator");
+
+        Ok(Self {
+            process,
+            heap,
+            allocate_heap,
+            free_heap,
+            word_len,
+            stub,
+            call_block,
+        })
+    }
+
+    /// Calls the given function with three arguments in the target process and returns its result.
+    fn call(&self, function: usize, args: [usize; 3]) -> Result<usize, io::Error> {
+        let call_block = unsafe {
+            ProcessMemorySlice::from_raw_parts(
+                self.call_block.as_raw_ptr(),
+                self.call_block.len,
+                self.process,
+            )
+        };
+        let mut block = Vec::with_capacity(self.call_block.len);
+        for word in [function, args[0], args[1], args[2], 0] {
+            block.extend_from_slice(&(word as u64).to_le_bytes()[..self.word_len]);
+        }
+        call_block.write(0, &block)?;
+
+        let result = self.process.run_remote_thread(
+            unsafe {
+                mem::transmute::<*mut u8, extern "system" fn(*mut u8) -> u32>(
+                    self.stub.as_raw_ptr(),
+                )
+            },
+            self.call_block.as_raw_ptr(),
+        )?;
+        if result != RemoteThreadResult::Returned(0) {
+            return Err(io::Error::other(format!("heap stub failed: {result:?}")));
+        }
+
+        let mut result = [0; mem::size_of::<u64>()];
+        call_block.read(
+            (Self::CALL_BLOCK_WORDS - 1) * self.word_len,
+            &mut result[..self.word_len],
+        )?;
+        Ok(u64::from_le_bytes(result) as usize)
+    }
+
+    #[cfg(feature = "assembler")]
+    fn build_code_x86() -> Result<Vec<u8>, IcedError> {
+        let mut asm = CodeAssembler::new(32)?;
+
+        asm.push(ebx)?;
+        asm.mov(ebx, dword_ptr(esp + 8))?; // CreateRemoteThread lpParameter
+        asm.push(dword_ptr(ebx + 12))?;
+        asm.push(dword_ptr(ebx + 8))?;
+        asm.push(dword_ptr(ebx + 4))?;
+        asm.call(dword_ptr(ebx))?; // stdcall, so the callee cleans up the arguments
+        asm.mov(dword_ptr(ebx + 16), eax)?;
+        asm.pop(ebx)?;
+        asm.xor(eax, eax)?; // return 0
+        asm.ret_1(4)?; // Restore stack ptr. (Callee cleanup)
+
+        let code = asm.assemble(0x1234_5678)?;
+        debug_assert_eq!(
+            code,
+            asm.assemble(0x1111_2222)?,
+            "heap x86 stub is not location independent"
+        );
+
+        Ok(code)
+    }
+
+    #[cfg(feature = "assembler")]
+    fn build_code_x64() -> Result<Vec<u8>, IcedError> {
+        let mut asm = CodeAssembler::new(64)?;
+
+        asm.push(rbx)?; // Re-align stack to 16 byte boundary
+        asm.sub(rsp, 32)?; // shadow space
+        asm.mov(rbx, rcx)?; // CreateRemoteThread lpParameter
+        asm.mov(rcx, qword_ptr(rbx + 8))?;
+        asm.mov(rdx, qword_ptr(rbx + 16))?;
+        asm.mov(r8, qword_ptr(rbx + 24))?;
+        asm.call(qword_ptr(rbx))?;
+        asm.mov(qword_ptr(rbx + 32), rax)?;
+        asm.add(rsp, 32)?;
+        asm.pop(rbx)?;
+        asm.xor(eax, eax)?; // return 0
+        asm.ret()?;
+
+        let code = asm.assemble(0x1234_5678)?;
+        debug_assert_eq!(
+            code,
+            asm.assemble(0x1111_2222)?,
+            "heap x64 stub is not location independent"
+        );
+
+        Ok(code)
+    }
+
+    #[cfg(not(feature = "assembler"))]
+    fn build_code_x86() -> Result<Vec<u8>, Infallible> {
+        Ok(stub_templates::HEAP_X86.to_vec())
+    }
+
+    #[cfg(not(feature = "assembler"))]
+    fn build_code_x64() -> Result<Vec<u8>, Infallible> {
+        Ok(stub_templates::HEAP_X64.to_vec())
+    }
+}
+
+impl RawAllocator for RemoteHeapAllocator {
+    type Error = io::Error;
+    type Alloc = Allocation;
+
+    fn alloc(&mut self, size: usize) -> Result<Allocation, io::Error> {
+        let base = self.call(self.allocate_heap, [self.heap, 0, size])?;
+        if base == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::OutOfMemory,
+                "RtlAllocateHeap failed in the target process",
+            ));
+        }
+        let allocation = Allocation { base, len: size };
+        #[cfg(feature = "process-memory")]
+        crate::journal::register_allocator_memory(unsafe {
+            ProcessMemorySlice::from_raw_parts(allocation.as_raw_ptr(), size, self.process)
+        });
+        Ok(allocation)
+    }
+
+    fn free(&mut self, allocation: &Allocation) {
+        #[cfg(feature = "process-memory")]
+        crate::journal::forget_memory(unsafe {
+            ProcessMemorySlice::from_raw_parts(
+                allocation.as_raw_ptr(),
+                allocation.len,
+                self.process,
+            )
+        });
+        // RtlFreeHeap only fails for blocks that were not allocated from the heap, which cannot happen here.
+        if let Err(_err) = self.call(self.free_heap, [self.heap, 0, allocation.base]) {
+            trace_event!(warn, address = ?allocation.as_raw_ptr(), error = %_err, "failed to free remote heap allocation");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{memory::RemoteBoxAllocator, OwnedProcess};
+
+    #[test]
+    fn heap_backend_does_not_allocate_pages() {
+        let allocator = RemoteBoxAllocator::with_backend(
+            OwnedProcess::current(),
+            RemoteAllocationBackend::ProcessHeap,
+        );
+        let first = allocator.alloc_and_copy(&0x1234_5678u32).unwrap();
+        // the first allocation places the stub in a page.
+        let allocated_page_bytes = allocator.0.allocator.borrow().count_allocated_bytes();
+
+        let second = allocator.alloc_and_copy(&0x1234_5678_9ABC_DEF0u64).unwrap();
+        assert_eq!(
+            allocator.0.allocator.borrow().count_allocated_bytes(),
+            allocated_page_bytes
+        );
+        assert_eq!(first.read().unwrap(), 0x1234_5678);
+        assert_eq!(second.read().unwrap(), 0x1234_5678_9ABC_DEF0);
+    }
+
+    #[test]
+    #[cfg(feature = "assembler")]
+    fn stub_templates_match_the_assembler() {
+        assert_eq!(
+            RemoteHeapAllocator::build_code_x86().unwrap(),
+            crate::stub_templates::HEAP_X86
+        );
+        assert_eq!(
+            RemoteHeapAllocator::build_code_x64().unwrap(),
+            crate::stub_templates::HEAP_X64
+        );
+    }
+}
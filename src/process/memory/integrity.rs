@@ -0,0 +1,194 @@
+use std::{cmp, fs, ops::Range};
+
+use goblin::pe::{section_table::SectionTable, PE};
+
+use crate::{
+    error::VerifyModuleError,
+    process::{memory::ProcessMemorySlice, Process, ProcessModule},
+};
+
+const IMAGE_REL_BASED_ABSOLUTE: u16 = 0;
+const IMAGE_REL_BASED_HIGHLOW: u16 = 3;
+const IMAGE_REL_BASED_DIR64: u16 = 10;
+
+impl<P: Process> ProcessModule<P> {
+    /// Compares the `.text` section of this module in memory with the `.text` section of the module file on disk
+    /// (after applying base relocations) and returns the ranges of bytes that differ, as offsets relative to the module base.
+    ///
+    /// An empty result means that the code of the module has not been modified (e.g. by hooks or patches).
+    ///
+    /// # Note
+    /// Bytes belonging to the import address table are ignored, as they are always written by the loader.
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+    pub fn verify_text_section(&self) -> Result<Vec<Range<usize>>, VerifyModuleError> {
+        let image = fs::read(self.path()?)?;
+        let pe = PE::parse(&image)?;
+
+        let text = pe
+            .sections
+            .iter()
+            .find(|section| section.name().is_ok_and(|name| name == ".text"))
+            .ok_or(VerifyModuleError::MissingTextSection)?;
+        let text_rva = text.virtual_address as usize;
+        let text_len = cmp::max(text.virtual_size, text.size_of_raw_data) as usize;
+
+        // build the expected in-memory contents from the file contents.
+        let mut expected = vec![0u8; text_len];
+        let raw_start = text.pointer_to_raw_data as usize;
+        let raw_len = cmp::min(text.size_of_raw_data as usize, text_len);
+        let raw = image
+            .get(raw_start..raw_start + raw_len)
+            .ok_or(VerifyModuleError::MalformedImage)?;
+        expected[..raw_len].copy_from_slice(raw);
+
+        let base = self.handle() as usize;
+        let delta = base.wrapping_sub(pe.image_base);
+        let optional_header = pe
+            .header
+            .optional_header
+            .ok_or(VerifyModuleError::MalformedImage)?;
+        if delta != 0 {
+            if let Some(relocations) = optional_header.data_directories.get_base_relocation_table()
+            {
+                let start = rva_to_file_offset(&pe.sections, relocations.virtual_address)
+                    .ok_or(VerifyModuleError::MalformedImage)?;
+                let data = image
+                    .get(start..start + relocations.size as usize)
+                    .ok_or(VerifyModuleError::MalformedImage)?;
+                apply_relocations(&mut expected, text_rva, data, delta)?;
+            }
+        }
+
+        let memory = unsafe {
+            ProcessMemorySlice::from_raw_parts(
+                (base + text_rva) as *mut u8,
+                text_len,
+                self.process().borrowed(),
+            )
+        };
+        let mut actual = memory.read_vec(0, text_len)?;
+
+        if let Some(iat) = optional_header.data_directories.get_import_address_table() {
+            let iat = iat.virtual_address as usize..(iat.virtual_address + iat.size) as usize;
+            let start = cmp::max(iat.start, text_rva) - text_rva;
+            let end = cmp::min(iat.end, text_rva + text_len).saturating_sub(text_rva);
+            if start < end {
+                actual[start..end].copy_from_slice(&expected[start..end]);
+            }
+        }
+
+        Ok(diff_ranges(&expected, &actual, text_rva))
+    }
+}
+
+fn rva_to_file_offset(sections: &[SectionTable], rva: u32) -> Option<usize> {
+    sections
+        .iter()
+        .find(|section| {
+            rva >= section.virtual_address
+                && rva - section.virtual_address
+                    < cmp::max(section.virtual_size, section.size_of_raw_data)
+        })
+        .map(|section| (rva - section.virtual_address + section.pointer_to_raw_data) as usize)
+}
+
+// Applies the base relocations in the given relocation directory that fall into the given section contents.
+fn apply_relocations(
+    section: &mut [u8],
+    section_rva: usize,
+    mut relocations: &[u8],
+    delta: usize,
+) -> Result<(), VerifyModuleError> {
+    while relocations.len() >= 8 {
+        let page_rva = u32::from_le_bytes(relocations[0..4].try_into().unwrap()) as usize;
+        let block_size = u32::from_le_bytes(relocations[4..8].try_into().unwrap()) as usize;
+        if block_size < 8 || block_size > relocations.len() {
+            return Err(VerifyModuleError::MalformedImage);
+        }
+
+        for entry in relocations[8..block_size].chunks_exact(2) {
+            let entry = u16::from_le_bytes([entry[0], entry[1]]);
+            let kind = entry >> 12;
+            let rva = page_rva + (entry & 0xFFF) as usize;
+            let size = match kind {
+                IMAGE_REL_BASED_ABSOLUTE => continue,
+                IMAGE_REL_BASED_HIGHLOW => 4,
+                IMAGE_REL_BASED_DIR64 => 8,
+                _ => return Err(VerifyModuleError::MalformedImage),
+            };
+            // relocations may partially overlap the section boundary, only those completely inside are applied.
+            let Some(offset) = rva.checked_sub(section_rva) else {
+                continue;
+            };
+            let Some(target) = section.get_mut(offset..offset + size) else {
+                continue;
+            };
+            if size == 4 {
+                let value = u32::from_le_bytes(target.try_into().unwrap());
+                target.copy_from_slice(&value.wrapping_add(delta as u32).to_le_bytes());
+            } else {
+                let value = u64::from_le_bytes(target.try_into().unwrap());
+                target.copy_from_slice(&value.wrapping_add(delta as u64).to_le_bytes());
+            }
+        }
+
+        relocations = &relocations[block_size..];
+    }
+    Ok(())
+}
+
+// Returns the ranges in which the given buffers differ, offset by the given base.
+fn diff_ranges(expected: &[u8], actual: &[u8], base: usize) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (i, (a, b)) in expected.iter().zip(actual).enumerate() {
+        if a == b {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(last) if last.end == base + i => last.end += 1,
+            _ => ranges.push(base + i..base + i + 1),
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::BorrowedProcess;
+
+    #[test]
+    fn diff_ranges_merges_adjacent_bytes() {
+        let expected = [0, 1, 2, 3, 4, 5];
+        let actual = [0, 9, 9, 3, 9, 5];
+        assert_eq!(
+            diff_ranges(&expected, &actual, 0x1000),
+            [0x1001..0x1003, 0x1004..0x1005]
+        );
+    }
+
+    #[test]
+    fn relocations_are_applied_inside_section() {
+        let mut section = [0u8; 16];
+        section[4..8].copy_from_slice(&0x1000u32.to_le_bytes());
+        let mut relocations = Vec::new();
+        relocations.extend_from_slice(&0x2000u32.to_le_bytes());
+        relocations.extend_from_slice(&12u32.to_le_bytes());
+        relocations.extend_from_slice(&((IMAGE_REL_BASED_HIGHLOW << 12) | 0x004).to_le_bytes());
+        relocations.extend_from_slice(&(IMAGE_REL_BASED_ABSOLUTE << 12).to_le_bytes());
+
+        apply_relocations(&mut section, 0x2000, &relocations, 0x10).unwrap();
+        assert_eq!(
+            u32::from_le_bytes(section[4..8].try_into().unwrap()),
+            0x1010
+        );
+    }
+
+    #[test]
+    fn verify_loaded_module() {
+        let kernel32 = ProcessModule::find_by_name("kernel32.dll", BorrowedProcess::current())
+            .unwrap()
+            .unwrap();
+        kernel32.verify_text_section().unwrap();
+    }
+}
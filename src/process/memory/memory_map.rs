@@ -0,0 +1,258 @@
+use std::{io, path::PathBuf};
+
+use crate::process::{
+    memory::{MemoryRegion, MemoryRegionIter, MemoryState, MemoryType},
+    BorrowedProcess, Process,
+};
+
+/// The granularity of the starting addresses of allocations made with `VirtualAlloc`.
+const ALLOCATION_GRANULARITY: usize = 64 * 1024;
+
+/// A summary of the virtual address space of a process, see [`Process::memory_map`](crate::process::Process::memory_map).
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::process::{OwnedProcess, Process};
+///
+/// let process = OwnedProcess::find_first_by_name("target_process").unwrap();
+/// let map = process.memory_map().unwrap();
+/// if !map.can_fit(256 * 1024 * 1024) {
+///     println!("the target is running out of address space");
+/// }
+/// ```
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryMap {
+    highest_address: usize,
+    committed_bytes: usize,
+    reserved_bytes: usize,
+    free_bytes: usize,
+    image_bytes: usize,
+    mapped_bytes: usize,
+    private_bytes: usize,
+    largest_free_block: Option<MemoryMapBlock>,
+    modules: Vec<MemoryMapModule>,
+}
+
+/// A contiguous range of addresses in a [`MemoryMap`].
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MemoryMapBlock {
+    base: usize,
+    size: usize,
+}
+
+impl MemoryMapBlock {
+    /// Returns the address of the start of the block.
+    #[must_use]
+    pub const fn base(&self) -> usize {
+        self.base
+    }
+
+    /// Returns the size of the block in bytes.
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+}
+
+/// The range of addresses occupied by a module image in a [`MemoryMap`].
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryMapModule {
+    block: MemoryMapBlock,
+    mapped_file_name: Option<PathBuf>,
+}
+
+impl MemoryMapModule {
+    /// Returns the address the module is loaded at.
+    #[must_use]
+    pub const fn base(&self) -> usize {
+        self.block.base
+    }
+
+    /// Returns the number of bytes of address space occupied by the module.
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        self.block.size
+    }
+
+    /// Returns the name of the file the module was mapped from, see [`MemoryRegion::mapped_file_name`].
+    #[must_use]
+    pub fn mapped_file_name(&self) -> Option<&PathBuf> {
+        self.mapped_file_name.as_ref()
+    }
+}
+
+impl MemoryMap {
+    pub(crate) fn read(process: BorrowedProcess<'_>) -> Result<Self, io::Error> {
+        // a 64-bit process sees the free address space above 4GB of a WOW64 process, which the target can not use.
+        let highest_address = if process.is_x86()? {
+            u32::MAX as usize
+        } else {
+            usize::MAX
+        };
+        let mut map = Self {
+            highest_address,
+            committed_bytes: 0,
+            reserved_bytes: 0,
+            free_bytes: 0,
+            image_bytes: 0,
+            mapped_bytes: 0,
+            private_bytes: 0,
+            largest_free_block: None,
+            modules: Vec::new(),
+        };
+        for region in MemoryRegionIter::new(process) {
+            map.add_region(region?);
+        }
+        Ok(map)
+    }
+
+    fn add_region(&mut self, region: MemoryRegion) {
+        let base = region.base() as usize;
+        match region.state() {
+            MemoryState::Commit => self.committed_bytes += region.size(),
+            MemoryState::Reserve => self.reserved_bytes += region.size(),
+            MemoryState::Free => {
+                if base > self.highest_address {
+                    return;
+                }
+                let end = (base + (region.size() - 1)).min(self.highest_address) + 1;
+                self.free_bytes += end - base;
+                // new allocations start at a multiple of the allocation granularity.
+                let usable_base = base.next_multiple_of(ALLOCATION_GRANULARITY);
+                let usable_size = end.saturating_sub(usable_base);
+                if usable_size > self.largest_free_block.map_or(0, |block| block.size) {
+                    self.largest_free_block = Some(MemoryMapBlock {
+                        base: usable_base,
+                        size: usable_size,
+                    });
+                }
+                return;
+            }
+        }
+
+        match region.kind() {
+            MemoryType::Image => self.image_bytes += region.size(),
+            MemoryType::Mapped => self.mapped_bytes += region.size(),
+            MemoryType::Private => self.private_bytes += region.size(),
+            MemoryType::None => {}
+        }
+
+        if region.kind() == MemoryType::Image {
+            // the regions of an image share its allocation base and are contiguous.
+            let allocation_base = region.allocation_base() as usize;
+            match self.modules.last_mut() {
+                Some(module) if module.block.base == allocation_base => {
+                    module.block.size = base + region.size() - allocation_base;
+                }
+                _ => self.modules.push(MemoryMapModule {
+                    block: MemoryMapBlock {
+                        base: allocation_base,
+                        size: base + region.size() - allocation_base,
+                    },
+                    mapped_file_name: region.mapped_file_name().cloned(),
+                }),
+            }
+        }
+    }
+
+    /// Returns the number of committed bytes, i.e. memory that is backed by physical storage.
+    #[must_use]
+    pub const fn committed_bytes(&self) -> usize {
+        self.committed_bytes
+    }
+
+    /// Returns the number of reserved bytes, i.e. address space that is claimed but not committed.
+    #[must_use]
+    pub const fn reserved_bytes(&self) -> usize {
+        self.reserved_bytes
+    }
+
+    /// Returns the number of free bytes of the user-mode address space.
+    ///
+    /// For 32-bit processes only the address space below 4GB is considered.
+    #[must_use]
+    pub const fn free_bytes(&self) -> usize {
+        self.free_bytes
+    }
+
+    /// Returns the number of bytes in use (committed or reserved) by module images.
+    #[must_use]
+    pub const fn image_bytes(&self) -> usize {
+        self.image_bytes
+    }
+
+    /// Returns the number of bytes in use (committed or reserved) by mapped views of sections, e.g. mapped files.
+    #[must_use]
+    pub const fn mapped_bytes(&self) -> usize {
+        self.mapped_bytes
+    }
+
+    /// Returns the number of bytes in use (committed or reserved) by private memory, e.g. heaps and stacks.
+    #[must_use]
+    pub const fn private_bytes(&self) -> usize {
+        self.private_bytes
+    }
+
+    /// Returns the largest block of free address space that a new allocation could be placed in.
+    ///
+    /// For 32-bit processes this is usually far smaller than [`free_bytes`](MemoryMap::free_bytes) due to fragmentation
+    /// and limits the size of payloads that can be loaded.
+    /// For 32-bit processes only the address space below 4GB is considered.
+    #[must_use]
+    pub const fn largest_free_block(&self) -> Option<MemoryMapBlock> {
+        self.largest_free_block
+    }
+
+    /// Returns whether a single contiguous allocation of the given size fits into the free address space.
+    #[must_use]
+    pub const fn can_fit(&self, size: usize) -> bool {
+        match self.largest_free_block {
+            Some(block) => block.size >= size,
+            None => false,
+        }
+    }
+
+    /// Returns the address ranges of the module images, ordered by address.
+    #[must_use]
+    pub fn modules(&self) -> &[MemoryMapModule] {
+        &self.modules
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{memory::ProcessMemoryBuffer, Process};
+
+    #[test]
+    fn memory_map_contains_allocations_and_modules() {
+        let process = BorrowedProcess::current();
+        let buffer = ProcessMemoryBuffer::allocate_data(process, 16).unwrap();
+        let map = process.memory_map().unwrap();
+
+        assert!(map.committed_bytes() >= buffer.len());
+        assert!(map.private_bytes() >= buffer.len());
+        assert!(map.can_fit(ALLOCATION_GRANULARITY));
+        let largest_free_block = map.largest_free_block().unwrap();
+        assert!(largest_free_block.size() <= map.free_bytes());
+        assert_eq!(largest_free_block.base() % ALLOCATION_GRANULARITY, 0);
+        assert!(largest_free_block.base() - 1 + largest_free_block.size() <= map.highest_address);
+
+        let kernel32 = process
+            .find_module_by_name("kernel32.dll")
+            .unwrap()
+            .unwrap();
+        let module = map
+            .modules()
+            .iter()
+            .find(|module| module.base() == kernel32.handle() as usize)
+            .unwrap();
+        assert_eq!(
+            module.size(),
+            kernel32.len().unwrap().next_multiple_of(0x1000)
+        );
+    }
+}
@@ -1,14 +1,92 @@
 mod buffer;
 pub use buffer::*;
 
+mod cursor;
+pub use cursor::*;
+
+mod remote_slice;
+pub use remote_slice::*;
+
+mod remote_vec;
+pub use remote_vec::*;
+
+mod remote_ptr;
+pub use remote_ptr::*;
+
+mod no_uninit;
+pub use no_uninit::*;
+
+mod region;
+pub use region::*;
+
+mod placement;
+pub use placement::*;
+
+#[cfg(feature = "process-memory")]
+mod memory_map;
+#[cfg(feature = "process-memory")]
+pub use memory_map::{MemoryMap, MemoryMapBlock, MemoryMapModule};
+
+mod protection;
+pub use protection::*;
+
+mod patch;
+pub use patch::*;
+
+mod string;
+pub(crate) use string::*;
+
+mod hash;
+
+mod pe;
+pub(crate) use pe::*;
+
+#[cfg(feature = "process-memory")]
+mod integrity;
+
+#[cfg(feature = "process-memory")]
+pub(crate) mod batch;
+
+#[cfg(feature = "process-memory")]
+pub(crate) mod dump;
+#[cfg(feature = "process-memory")]
+pub use dump::DumpFormat;
+
+#[cfg(feature = "process-memory")]
+pub mod scanner;
+
+#[cfg(feature = "process-memory")]
+mod freeze;
+#[cfg(feature = "process-memory")]
+pub use freeze::{FreezeId, FreezeList, FrozenValue};
+
+#[cfg(feature = "process-memory")]
+mod watchpoint;
+#[cfg(feature = "process-memory")]
+pub use watchpoint::{WriteAccess, WriteWatcher};
+
 #[cfg(feature = "syringe")]
 #[allow(dead_code)]
 mod raw_allocator;
 #[cfg(feature = "syringe")]
 pub(crate) use raw_allocator::*;
 
+#[cfg(feature = "syringe")]
+mod heap_allocator;
+#[cfg(feature = "syringe")]
+pub use heap_allocator::RemoteAllocationBackend;
+#[cfg(feature = "syringe")]
+pub(crate) use heap_allocator::RemoteHeapAllocator;
+
 #[cfg(feature = "syringe")]
 #[allow(dead_code)]
 mod remote_box;
 #[cfg(feature = "syringe")]
+pub use remote_box::RemoteBox;
+#[cfg(feature = "syringe")]
 pub(crate) use remote_box::*;
+
+#[cfg(feature = "syringe")]
+mod remote_result;
+#[cfg(feature = "syringe")]
+pub(crate) use remote_result::*;
@@ -0,0 +1,45 @@
+use crate::process::memory::{RemotePtr, RemotePtrWidth};
+
+/// A type whose values consist of initialized bytes only, so they can be copied into the memory of a process byte by byte,
+/// see [`FreezeList::add_value`](crate::process::memory::FreezeList::add_value).
+///
+/// Unlike [`Copy`], this rules out types with padding, whose padding bytes are uninitialized and must not be read.
+///
+/// # Safety
+/// Every byte of every value of the type must be initialized, i.e. the type must not contain padding, unions or
+/// [`MaybeUninit`](std::mem::MaybeUninit)s.
+/// A `#[repr(C)]` or `#[repr(transparent)]` struct whose fields are all `NoUninit` and that has no padding between or after
+/// them satisfies this.
+pub unsafe trait NoUninit: Copy + 'static {}
+
+macro_rules! impl_no_uninit {
+    ($($ty:ty),* $(,)?) => {
+        $(unsafe impl NoUninit for $ty {})*
+    };
+}
+
+impl_no_uninit!(
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    bool,
+    char,
+    ()
+);
+
+unsafe impl<T: 'static> NoUninit for *const T {}
+unsafe impl<T: 'static> NoUninit for *mut T {}
+unsafe impl<T: NoUninit, const N: usize> NoUninit for [T; N] {}
+// a remote pointer only consists of the integer storing its address.
+unsafe impl<T: 'static, W: RemotePtrWidth> NoUninit for RemotePtr<T, W> {}
@@ -0,0 +1,259 @@
+use std::{
+    io, mem,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError, Weak,
+    },
+};
+
+use winapi::um::winnt::PAGE_EXECUTE_READWRITE;
+
+use crate::{
+    process::{
+        memory::{MemoryProtection, ProcessMemorySlice},
+        Process, ProcessId,
+    },
+    utils::trace_event,
+};
+
+// The tables of the patches that are currently applied in each process.
+// The lock of this map is only held to look up a table, never while accessing remote memory.
+static PATCH_TABLES: Mutex<Vec<(ProcessId, Weak<PatchTable>)>> = Mutex::new(Vec::new());
+static NEXT_PATCH_ID: AtomicU64 = AtomicU64::new(0);
+
+// Bookkeeping for the patches that are currently applied in a single process, in the order they were applied.
+// This allows overlapping patches to be reverted in any order while still restoring the correct bytes.
+#[derive(Debug, Default)]
+struct PatchTable(Mutex<Vec<PatchRecord>>);
+
+impl PatchTable {
+    fn of(process: &(impl Process + ?Sized)) -> Result<Arc<Self>, io::Error> {
+        let id = ProcessId::of(process)?;
+        let mut tables = PATCH_TABLES.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(table) = tables
+            .iter()
+            .find(|(process_id, _)| *process_id == id)
+            .and_then(|(_, table)| table.upgrade())
+        {
+            return Ok(table);
+        }
+        // drop the entries of processes without patches, so the list does not grow with every patched process.
+        tables.retain(|(_, table)| table.strong_count() != 0);
+        let table = Arc::new(Self::default());
+        tables.push((id, Arc::downgrade(&table)));
+        Ok(table)
+    }
+
+    fn records(&self) -> MutexGuard<'_, Vec<PatchRecord>> {
+        // the bookkeeping never panics while the lock is held, so a poisoned table is still consistent.
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[derive(Debug)]
+struct PatchRecord {
+    id: u64,
+    address: usize,
+    // the bytes that will be written back when the patch is reverted.
+    original: Vec<u8>,
+}
+
+impl PatchRecord {
+    fn covers(&self, address: usize) -> bool {
+        address >= self.address && address - self.address < self.original.len()
+    }
+}
+
+/// A modification of the memory of a (remote) process that saves the original bytes and restores them when reverted or dropped.
+///
+/// Patches may overlap. Reverting a patch that is partially covered by a patch applied after it only restores the bytes that are not covered,
+/// while the covered bytes are restored once the later patch is reverted.
+///
+/// # Note
+/// The protection of the patched pages is temporarily changed to allow writing to read-only code pages.
+/// Overlapping patches must not be applied or reverted concurrently from multiple threads.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[must_use = "the patch is reverted immediately if it is dropped"]
+#[derive(Debug)]
+pub struct Patch<'a> {
+    id: u64,
+    table: Arc<PatchTable>,
+    memory: ProcessMemorySlice<'a>,
+}
+
+impl<'a> Patch<'a> {
+    /// Writes the given bytes to the given memory at the given offset, saving the bytes that were there before.
+    ///
+    /// # Panics
+    /// This function will panic if the given offset plus the length of the given bytes exceeds the length of the given memory.
+    pub fn apply(
+        memory: ProcessMemorySlice<'a>,
+        offset: usize,
+        bytes: &[u8],
+    ) -> Result<Self, io::Error> {
+        let memory = memory.slice(offset..offset + bytes.len());
+        let table = PatchTable::of(&memory.process())?;
+
+        let original = memory.read_vec(0, bytes.len())?;
+        write_patch_bytes(memory, bytes)?;
+
+        let id = NEXT_PATCH_ID.fetch_add(1, Ordering::Relaxed);
+        table.records().push(PatchRecord {
+            id,
+            address: memory.as_ptr() as usize,
+            original,
+        });
+
+        Ok(Self { id, table, memory })
+    }
+
+    /// Returns the patched memory.
+    #[must_use]
+    pub const fn memory(&self) -> ProcessMemorySlice<'a> {
+        self.memory
+    }
+
+    /// Returns the bytes that will be written back when this patch is reverted.
+    ///
+    /// # Note
+    /// For bytes that are also covered by a patch applied after this one, these are the bytes this patch replaced.
+    #[must_use]
+    pub fn original_bytes(&self) -> Vec<u8> {
+        self.table
+            .records()
+            .iter()
+            .find(|record| record.id == self.id)
+            .unwrap()
+            .original
+            .clone()
+    }
+
+    /// Reverts this patch, returning an error if the original bytes could not be restored.
+    pub fn revert(self) -> Result<(), io::Error> {
+        let result = self.revert_inner();
+        mem::forget(self);
+        result
+    }
+
+    /// Consumes this patch without reverting it.
+    pub fn keep(self) {
+        self.table.records().retain(|record| record.id != self.id);
+        mem::forget(self);
+    }
+
+    fn revert_inner(&self) -> Result<(), io::Error> {
+        // the bookkeeping is updated first, so the table is not locked while the memory is written.
+        let (record, runs) = {
+            let mut records = self.table.records();
+            let index = records
+                .iter()
+                .position(|record| record.id == self.id)
+                .unwrap();
+            let record = records.remove(index);
+
+            // bytes covered by a later patch must not be written, instead the next later patch takes over restoring them.
+            let mut runs = Vec::new();
+            let mut run_start = None;
+            for (i, &original) in record.original.iter().enumerate() {
+                let address = record.address + i;
+                match records[index..]
+                    .iter_mut()
+                    .find(|later| later.covers(address))
+                {
+                    Some(later) => {
+                        later.original[address - later.address] = original;
+                        if let Some(start) = run_start.take() {
+                            runs.push(start..i);
+                        }
+                    }
+                    None => {
+                        run_start.get_or_insert(i);
+                    }
+                }
+            }
+            if let Some(start) = run_start {
+                runs.push(start..record.original.len());
+            }
+            (record, runs)
+        };
+
+        for run in runs {
+            write_patch_bytes(self.memory.slice(run.clone()), &record.original[run])?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Patch<'_> {
+    fn drop(&mut self) {
+        if let Err(_err) = self.revert_inner() {
+            // the memory is gone with the process, so there is nothing left to restore.
+            if self.memory.process().is_alive() {
+                trace_event!(warn, error = %_err, "failed to revert patch");
+            }
+        }
+    }
+}
+
+pub(crate) fn write_patch_bytes(
+    memory: ProcessMemorySlice<'_>,
+    bytes: &[u8],
+) -> Result<(), io::Error> {
+    let guard = unsafe { memory.protect(MemoryProtection(PAGE_EXECUTE_READWRITE)) }?;
+    memory.write(0, bytes)?;
+    guard.restore()?;
+    memory.flush_instruction_cache()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{memory::ProcessMemoryBuffer, BorrowedProcess};
+
+    fn contents(buffer: &ProcessMemoryBuffer<'_>) -> Vec<u8> {
+        let mut bytes = vec![0u8; buffer.len()];
+        buffer.read(0, &mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn patch_is_reverted_on_drop() {
+        let process = BorrowedProcess::current();
+        let buffer = ProcessMemoryBuffer::allocate_data(process, 4).unwrap();
+        buffer.write(0, &[1, 2, 3, 4]).unwrap();
+
+        let patch = Patch::apply(*buffer.as_slice(), 1, &[9, 9]).unwrap();
+        assert_eq!(contents(&buffer), [1, 9, 9, 4]);
+        assert_eq!(patch.original_bytes(), [2, 3]);
+        drop(patch);
+        assert_eq!(contents(&buffer), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn overlapping_patches_reverted_out_of_order() {
+        let process = BorrowedProcess::current();
+        let buffer = ProcessMemoryBuffer::allocate_data(process, 6).unwrap();
+        buffer.write(0, &[1, 2, 3, 4, 5, 6]).unwrap();
+
+        let first = Patch::apply(*buffer.as_slice(), 0, &[7, 7, 7, 7]).unwrap();
+        let second = Patch::apply(*buffer.as_slice(), 2, &[8, 8, 8, 8]).unwrap();
+        assert_eq!(contents(&buffer), [7, 7, 8, 8, 8, 8]);
+
+        first.revert().unwrap();
+        assert_eq!(contents(&buffer), [1, 2, 8, 8, 8, 8]);
+        second.revert().unwrap();
+        assert_eq!(contents(&buffer), [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn patches_of_a_process_share_one_table() {
+        let process = BorrowedProcess::current();
+        let buffer = ProcessMemoryBuffer::allocate_data(process, 2).unwrap();
+
+        let first = Patch::apply(*buffer.as_slice(), 0, &[1]).unwrap();
+        let second = Patch::apply(*buffer.as_slice(), 1, &[2]).unwrap();
+        assert!(Arc::ptr_eq(&first.table, &second.table));
+        assert_eq!(first.original_bytes(), [0]);
+        assert_eq!(second.original_bytes(), [0]);
+    }
+}
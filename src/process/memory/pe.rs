@@ -0,0 +1,631 @@
+use std::{
+    cmp::Ordering,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+#[cfg(feature = "syringe")]
+use {
+    crate::process::Process,
+    std::ptr,
+    widestring::U16CString,
+    winapi::um::libloaderapi::{
+        FreeLibrary, LoadLibraryExW, LOAD_LIBRARY_AS_DATAFILE, LOAD_LIBRARY_AS_IMAGE_RESOURCE,
+    },
+};
+
+use crate::process::{
+    memory::{read_nul_terminated, ProcessMemorySlice},
+    BorrowedProcess, BorrowedProcessModule, ModuleResource, ResourceId,
+};
+
+const IMAGE_DOS_SIGNATURE: u16 = 0x5A4D;
+const IMAGE_NT_SIGNATURE: u32 = 0x0000_4550;
+const IMAGE_NT_OPTIONAL_HDR32_MAGIC: u16 = 0x10B;
+const IMAGE_NT_OPTIONAL_HDR64_MAGIC: u16 = 0x20B;
+const IMAGE_DIRECTORY_ENTRY_EXPORT: usize = 0;
+const IMAGE_DIRECTORY_ENTRY_IMPORT: usize = 1;
+const IMAGE_DIRECTORY_ENTRY_RESOURCE: usize = 2;
+const IMAGE_RESOURCE_DATA_IS_DIRECTORY: u32 = 0x8000_0000;
+const IMAGE_RESOURCE_NAME_IS_STRING: u32 = 0x8000_0000;
+
+/// A view of the headers of a PE image mapped into the memory of a (remote) process.
+/// All offsets are relative virtual addresses (RVAs) as the image is read in its loaded layout.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RemoteImage<'a> {
+    process: BorrowedProcess<'a>,
+    base: usize,
+    is_64: bool,
+    optional_header_rva: usize,
+}
+
+/// A data directory entry of a [`RemoteImage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RemoteDataDirectory {
+    pub rva: usize,
+    pub size: usize,
+}
+
+/// An exported function of a [`RemoteImage`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RemoteExport {
+    /// The RVA of the function.
+    Rva(usize),
+    /// The forwarder string of a function exported by another module, e.g. `NTDLL.RtlAllocateHeap` or `NTDLL.#12`.
+    Forwarded(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ExportDirectory {
+    directory: RemoteDataDirectory,
+    ordinal_base: usize,
+    function_count: usize,
+    name_count: usize,
+    functions_rva: usize,
+    names_rva: usize,
+    ordinals_rva: usize,
+}
+
+/// An export of a [`RemoteImage`] together with its name and ordinal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RemoteExportEntry {
+    /// The name of the export or [`None`] if it is only exported by ordinal.
+    pub name: Option<String>,
+    /// The ordinal of the export.
+    pub ordinal: u16,
+    /// The exported function.
+    pub export: RemoteExport,
+}
+
+/// An entry of the import address table of a [`RemoteImage`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RemoteImport {
+    /// The name of the module the function is imported from.
+    pub module_name: String,
+    /// The name of the imported function or [`None`] if it is imported by ordinal.
+    pub name: Option<String>,
+    /// The ordinal of the imported function if it is imported by ordinal.
+    pub ordinal: Option<u16>,
+    /// The RVA of the import address table slot holding the address of the imported function.
+    pub slot_rva: usize,
+}
+
+/// The target of an entry in the resource directory of a [`RemoteImage`], given as an offset from the root of the directory.
+#[derive(Debug, Clone, Copy)]
+enum ResourceEntry {
+    Directory(usize),
+    Data(usize),
+}
+
+impl ResourceEntry {
+    fn subdirectory(self) -> Result<usize, io::Error> {
+        match self {
+            Self::Directory(offset) => Ok(offset),
+            Self::Data(_) => Err(malformed("resource directory is too shallow")),
+        }
+    }
+}
+
+impl<'a> RemoteImage<'a> {
+    /// Reads the headers of the given loaded module.
+    pub fn new(module: BorrowedProcessModule<'a>) -> Result<Self, io::Error> {
+        Self::from_base(*module.process(), module.handle() as usize)
+    }
+
+    /// Reads the headers of the image loaded at the given base address in the given process.
+    pub fn from_base(process: BorrowedProcess<'a>, base: usize) -> Result<Self, io::Error> {
+        let mut image = Self {
+            process,
+            base,
+            is_64: false,
+            optional_header_rva: 0,
+        };
+
+        if image.read_u16(0)? != IMAGE_DOS_SIGNATURE {
+            return Err(malformed("invalid dos signature"));
+        }
+        let nt_headers_rva = image.read_u32(0x3C)? as usize;
+        if image.read_u32(nt_headers_rva)? != IMAGE_NT_SIGNATURE {
+            return Err(malformed("invalid nt signature"));
+        }
+        // skip the signature and the file header.
+        image.optional_header_rva = nt_headers_rva + 4 + 20;
+        image.is_64 = match image.read_u16(image.optional_header_rva)? {
+            IMAGE_NT_OPTIONAL_HDR32_MAGIC => false,
+            IMAGE_NT_OPTIONAL_HDR64_MAGIC => true,
+            _ => return Err(malformed("invalid optional header magic")),
+        };
+        Ok(image)
+    }
+
+    /// Returns the address the image is loaded at.
+    pub const fn base(&self) -> usize {
+        self.base
+    }
+
+    /// Returns the size of a pointer in the image.
+    pub const fn pointer_size(&self) -> usize {
+        if self.is_64 {
+            8
+        } else {
+            4
+        }
+    }
+
+    /// Returns the RVA of the entry point of the image or [`None`] if it has none.
+    #[cfg_attr(not(feature = "syringe"), allow(dead_code))]
+    pub fn entry_point_rva(&self) -> Result<Option<usize>, io::Error> {
+        let rva = self.read_u32(self.optional_header_rva + 16)? as usize;
+        Ok((rva != 0).then_some(rva))
+    }
+
+    /// Returns the data directory entry with the given index or [`None`] if it is empty.
+    pub fn data_directory(&self, index: usize) -> Result<Option<RemoteDataDirectory>, io::Error> {
+        let directories_rva = self.optional_header_rva + if self.is_64 { 112 } else { 96 };
+        let count_rva = directories_rva - 4;
+        if index >= self.read_u32(count_rva)? as usize {
+            return Ok(None);
+        }
+        let entry_rva = directories_rva + index * 8;
+        let rva = self.read_u32(entry_rva)? as usize;
+        let size = self.read_u32(entry_rva + 4)? as usize;
+        Ok(if rva == 0 {
+            None
+        } else {
+            Some(RemoteDataDirectory { rva, size })
+        })
+    }
+
+    /// Returns the memory of the image at the given RVA with the given length.
+    pub fn memory(&self, rva: usize, len: usize) -> ProcessMemorySlice<'a> {
+        unsafe {
+            ProcessMemorySlice::from_raw_parts((self.base + rva) as *mut u8, len, self.process)
+        }
+    }
+
+    pub fn read_bytes(&self, rva: usize, buf: &mut [u8]) -> Result<(), io::Error> {
+        self.memory(rva, buf.len()).read(0, buf)
+    }
+
+    pub fn read_u16(&self, rva: usize) -> Result<u16, io::Error> {
+        let mut buf = [0; 2];
+        self.read_bytes(rva, &mut buf)?;
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    pub fn read_u32(&self, rva: usize) -> Result<u32, io::Error> {
+        let mut buf = [0; 4];
+        self.read_bytes(rva, &mut buf)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    /// Reads a pointer sized value of the image at the given RVA.
+    pub fn read_pointer(&self, rva: usize) -> Result<u64, io::Error> {
+        let mut buf = [0; 8];
+        self.read_bytes(rva, &mut buf[..self.pointer_size()])?;
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    /// Reads a nul-terminated string of the image at the given RVA.
+    pub fn read_c_string(&self, rva: usize) -> Result<String, io::Error> {
+        let bytes = read_nul_terminated::<u8>(self.process, self.base + rva, 1024)?;
+        String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Returns the RVA of the function exported under the given name or [`None`] if there is no such export.
+    /// Forwarded exports are not resolved and reported as missing.
+    #[cfg_attr(
+        not(all(target_arch = "x86_64", feature = "into-x86-from-x64")),
+        allow(dead_code)
+    )]
+    pub fn export_rva(&self, name: &str) -> Result<Option<usize>, io::Error> {
+        Ok(match self.export(name)? {
+            Some(RemoteExport::Rva(rva)) => Some(rva),
+            Some(RemoteExport::Forwarded(_)) | None => None,
+        })
+    }
+
+    /// Returns the function exported under the given name or [`None`] if there is no such export.
+    pub fn export(&self, name: &str) -> Result<Option<RemoteExport>, io::Error> {
+        match self.export_index(name)? {
+            Some((directory, index)) => self.export_at(&directory, index),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the function exported with the given ordinal or [`None`] if there is no such export.
+    pub fn export_by_ordinal(&self, ordinal: u16) -> Result<Option<RemoteExport>, io::Error> {
+        match self.export_index_by_ordinal(ordinal)? {
+            Some((directory, index)) => self.export_at(&directory, index),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the RVA of the entry of the export address table holding the RVA of the function exported under the given
+    /// name, or [`None`] if there is no such export.
+    #[cfg_attr(not(feature = "rpc-core"), allow(dead_code))]
+    pub fn export_entry_rva(&self, name: &str) -> Result<Option<usize>, io::Error> {
+        Ok(self
+            .export_index(name)?
+            .map(|(directory, index)| directory.functions_rva + index * 4))
+    }
+
+    /// Returns the RVA of the entry of the export address table holding the RVA of the function exported with the given
+    /// ordinal, or [`None`] if there is no such export.
+    #[cfg_attr(not(feature = "rpc-core"), allow(dead_code))]
+    pub fn export_entry_rva_by_ordinal(&self, ordinal: u16) -> Result<Option<usize>, io::Error> {
+        Ok(self
+            .export_index_by_ordinal(ordinal)?
+            .filter(|(directory, index)| *index < directory.function_count)
+            .map(|(directory, index)| directory.functions_rva + index * 4))
+    }
+
+    fn export_index(&self, name: &str) -> Result<Option<(ExportDirectory, usize)>, io::Error> {
+        let Some(directory) = self.export_directory()? else {
+            return Ok(None);
+        };
+
+        // the name table is sorted, so it can be searched without reading every name.
+        let (mut low, mut high) = (0, directory.name_count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let name_rva = self.read_u32(directory.names_rva + mid * 4)? as usize;
+            match self.read_c_string(name_rva)?.as_str().cmp(name) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => {
+                    let index = self.read_u16(directory.ordinals_rva + mid * 2)? as usize;
+                    return Ok(Some((directory, index)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn export_index_by_ordinal(
+        &self,
+        ordinal: u16,
+    ) -> Result<Option<(ExportDirectory, usize)>, io::Error> {
+        let Some(directory) = self.export_directory()? else {
+            return Ok(None);
+        };
+        Ok((ordinal as usize)
+            .checked_sub(directory.ordinal_base)
+            .map(|index| (directory, index)))
+    }
+
+    /// Returns all exports of the image ordered by their ordinals.
+    pub fn exports(&self) -> Result<Vec<RemoteExportEntry>, io::Error> {
+        let Some(directory) = self.export_directory()? else {
+            return Ok(Vec::new());
+        };
+        // ordinals are 16 bit, so a larger table can only come from a malformed image.
+        if directory.function_count > usize::from(u16::MAX) + 1
+            || directory.name_count > directory.function_count
+        {
+            return Err(malformed("export directory too large"));
+        }
+
+        // the tables are read in one go, as images may export thousands of functions.
+        let functions = self.read_u32_table(directory.functions_rva, directory.function_count)?;
+        let name_rvas = self.read_u32_table(directory.names_rva, directory.name_count)?;
+        let mut name_indices = vec![0; directory.name_count * 2];
+        self.read_bytes(directory.ordinals_rva, &mut name_indices)?;
+
+        let mut names = vec![None; directory.function_count];
+        for (name_rva, index) in name_rvas.into_iter().zip(name_indices.chunks_exact(2)) {
+            let index = u16::from_le_bytes([index[0], index[1]]) as usize;
+            let name = names
+                .get_mut(index)
+                .ok_or_else(|| malformed("export ordinal out of range"))?;
+            *name = Some(self.read_c_string(name_rva as usize)?);
+        }
+
+        let range = directory.directory.rva..directory.directory.rva + directory.directory.size;
+        let mut exports = Vec::new();
+        for (index, (rva, name)) in functions.into_iter().zip(names).enumerate() {
+            let rva = rva as usize;
+            if rva == 0 {
+                continue;
+            }
+            // rvas pointing into the export directory are forwarder strings.
+            let export = if range.contains(&rva) {
+                RemoteExport::Forwarded(self.read_c_string(rva)?)
+            } else {
+                RemoteExport::Rva(rva)
+            };
+            exports.push(RemoteExportEntry {
+                name,
+                ordinal: (directory.ordinal_base + index) as u16,
+                export,
+            });
+        }
+        Ok(exports)
+    }
+
+    fn read_u32_table(&self, rva: usize, len: usize) -> Result<Vec<u32>, io::Error> {
+        let mut buf = vec![0; len * 4];
+        self.read_bytes(rva, &mut buf)?;
+        Ok(buf
+            .chunks_exact(4)
+            .map(|value| u32::from_le_bytes([value[0], value[1], value[2], value[3]]))
+            .collect())
+    }
+
+    fn export_directory(&self) -> Result<Option<ExportDirectory>, io::Error> {
+        let Some(directory) = self.data_directory(IMAGE_DIRECTORY_ENTRY_EXPORT)? else {
+            return Ok(None);
+        };
+        Ok(Some(ExportDirectory {
+            directory,
+            ordinal_base: self.read_u32(directory.rva + 16)? as usize,
+            function_count: self.read_u32(directory.rva + 20)? as usize,
+            name_count: self.read_u32(directory.rva + 24)? as usize,
+            functions_rva: self.read_u32(directory.rva + 28)? as usize,
+            names_rva: self.read_u32(directory.rva + 32)? as usize,
+            ordinals_rva: self.read_u32(directory.rva + 36)? as usize,
+        }))
+    }
+
+    fn export_at(
+        &self,
+        directory: &ExportDirectory,
+        index: usize,
+    ) -> Result<Option<RemoteExport>, io::Error> {
+        if index >= directory.function_count {
+            return Err(malformed("export ordinal out of range"));
+        }
+        let rva = self.read_u32(directory.functions_rva + index * 4)? as usize;
+        if rva == 0 {
+            return Ok(None);
+        }
+        // rvas pointing into the export directory are forwarder strings.
+        let range = directory.directory.rva..directory.directory.rva + directory.directory.size;
+        Ok(Some(if range.contains(&rva) {
+            RemoteExport::Forwarded(self.read_c_string(rva)?)
+        } else {
+            RemoteExport::Rva(rva)
+        }))
+    }
+
+    /// Returns all entries of the import address table of the image.
+    pub fn imports(&self) -> Result<Vec<RemoteImport>, io::Error> {
+        let Some(directory) = self.data_directory(IMAGE_DIRECTORY_ENTRY_IMPORT)? else {
+            return Ok(Vec::new());
+        };
+
+        let mut imports = Vec::new();
+        // the import descriptor array is terminated by an all-zero entry.
+        for descriptor_rva in (directory.rva..).step_by(20) {
+            let lookup_table_rva = self.read_u32(descriptor_rva)? as usize;
+            let name_rva = self.read_u32(descriptor_rva + 12)? as usize;
+            let address_table_rva = self.read_u32(descriptor_rva + 16)? as usize;
+            if name_rva == 0 && address_table_rva == 0 {
+                break;
+            }
+            let module_name = self.read_c_string(name_rva)?;
+
+            // the lookup table may be missing in which case the address table has to be used, which is only accurate if the image is not yet bound.
+            let lookup_table_rva = if lookup_table_rva == 0 {
+                address_table_rva
+            } else {
+                lookup_table_rva
+            };
+            let ordinal_flag = 1u64 << (self.pointer_size() * 8 - 1);
+            for i in 0.. {
+                let entry = self.read_pointer(lookup_table_rva + i * self.pointer_size())?;
+                if entry == 0 {
+                    break;
+                }
+                let (name, ordinal) = if entry & ordinal_flag != 0 {
+                    (None, Some(entry as u16))
+                } else {
+                    // skip the hint.
+                    let name = self.read_c_string((entry & 0x7FFF_FFFF) as usize + 2)?;
+                    (Some(name), None)
+                };
+                imports.push(RemoteImport {
+                    module_name: module_name.clone(),
+                    name,
+                    ordinal,
+                    slot_rva: address_table_rva + i * self.pointer_size(),
+                });
+            }
+        }
+        Ok(imports)
+    }
+
+    /// Returns all resources of the image, ordered by type, name and language as in the resource directory.
+    pub fn resources(&self) -> Result<Vec<ModuleResource>, io::Error> {
+        let Some(directory) = self.data_directory(IMAGE_DIRECTORY_ENTRY_RESOURCE)? else {
+            return Ok(Vec::new());
+        };
+
+        // the resource directory is a tree with the levels type, name and language, whose offsets are relative to its root.
+        let root = directory.rva;
+        let mut resources = Vec::new();
+        for (kind, names) in self.resource_directory(root, 0)? {
+            let names = names.subdirectory()?;
+            for (name, languages) in self.resource_directory(root, names)? {
+                let languages = languages.subdirectory()?;
+                for (language, data) in self.resource_directory(root, languages)? {
+                    let (ResourceId::Id(language), ResourceEntry::Data(data)) = (language, data)
+                    else {
+                        return Err(malformed("invalid resource language entry"));
+                    };
+                    resources.push(ModuleResource {
+                        kind: kind.clone(),
+                        name: name.clone(),
+                        language,
+                        rva: self.read_u32(root + data)? as usize,
+                        len: self.read_u32(root + data + 4)? as usize,
+                    });
+                }
+            }
+        }
+        Ok(resources)
+    }
+
+    /// Reads the entries of the resource directory at the given offset from the root of the resource directory.
+    fn resource_directory(
+        &self,
+        root: usize,
+        offset: usize,
+    ) -> Result<Vec<(ResourceId, ResourceEntry)>, io::Error> {
+        let named_count = self.read_u16(root + offset + 12)? as usize;
+        let id_count = self.read_u16(root + offset + 14)? as usize;
+        let table = self.read_u32_table(root + offset + 16, (named_count + id_count) * 2)?;
+
+        let mut entries = Vec::with_capacity(named_count + id_count);
+        for entry in table.chunks_exact(2) {
+            let id = if entry[0] & IMAGE_RESOURCE_NAME_IS_STRING != 0 {
+                // the name is a length-prefixed utf-16 string.
+                let name_rva = root + (entry[0] & !IMAGE_RESOURCE_NAME_IS_STRING) as usize;
+                let len = self.read_u16(name_rva)? as usize;
+                let mut name = vec![0; len * 2];
+                self.read_bytes(name_rva + 2, &mut name)?;
+                let name = name
+                    .chunks_exact(2)
+                    .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                    .collect::<Vec<_>>();
+                ResourceId::Name(String::from_utf16_lossy(&name))
+            } else {
+                ResourceId::Id(entry[0] as u16)
+            };
+            let target = if entry[1] & IMAGE_RESOURCE_DATA_IS_DIRECTORY != 0 {
+                ResourceEntry::Directory((entry[1] & !IMAGE_RESOURCE_DATA_IS_DIRECTORY) as usize)
+            } else {
+                ResourceEntry::Data(entry[1] as usize)
+            };
+            entries.push((id, target));
+        }
+        Ok(entries)
+    }
+}
+
+/// Reads the preferred base address from the optional header of the image file at the given path.
+///
+/// The loader overwrites the image base in the headers of a relocated image in memory, so the preferred base
+/// can only be read from the file.
+pub(crate) fn read_preferred_image_base(path: &Path) -> Result<u64, io::Error> {
+    let mut file = File::open(path)?;
+    let mut read_at = |offset: u64, buf: &mut [u8]| -> Result<(), io::Error> {
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(buf)
+    };
+
+    let mut word = [0; 2];
+    let mut dword = [0; 4];
+    read_at(0, &mut word)?;
+    if u16::from_le_bytes(word) != IMAGE_DOS_SIGNATURE {
+        return Err(malformed("invalid dos signature"));
+    }
+    read_at(0x3C, &mut dword)?;
+    let nt_headers = u64::from(u32::from_le_bytes(dword));
+    read_at(nt_headers, &mut dword)?;
+    if u32::from_le_bytes(dword) != IMAGE_NT_SIGNATURE {
+        return Err(malformed("invalid nt signature"));
+    }
+    // skip the signature and the file header.
+    let optional_header = nt_headers + 4 + 20;
+    read_at(optional_header, &mut word)?;
+    match u16::from_le_bytes(word) {
+        IMAGE_NT_OPTIONAL_HDR32_MAGIC => {
+            read_at(optional_header + 28, &mut dword)?;
+            Ok(u64::from(u32::from_le_bytes(dword)))
+        }
+        IMAGE_NT_OPTIONAL_HDR64_MAGIC => {
+            let mut qword = [0; 8];
+            read_at(optional_header + 24, &mut qword)?;
+            Ok(u64::from_le_bytes(qword))
+        }
+        _ => Err(malformed("invalid optional header magic")),
+    }
+}
+
+/// Maps the image file at the given path into the current process and calls the given function with a view of it.
+///
+/// The image is mapped with its image layout without running it or resolving its imports, which also works for images
+/// of the other architecture, and is unmapped once the function returns.
+#[cfg(feature = "syringe")]
+pub(crate) fn with_image_file<R>(
+    path: &Path,
+    f: impl FnOnce(RemoteImage<'_>) -> Result<R, io::Error>,
+) -> Result<R, io::Error> {
+    let wide_path = U16CString::from_os_str(path.as_os_str())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let handle = unsafe {
+        LoadLibraryExW(
+            wide_path.as_ptr(),
+            ptr::null_mut(),
+            LOAD_LIBRARY_AS_DATAFILE | LOAD_LIBRARY_AS_IMAGE_RESOURCE,
+        )
+    };
+    if handle.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+
+    // the low bits of the handle mark the module as a data file.
+    let base = handle as usize & !0b11;
+    let result = RemoteImage::from_base(BorrowedProcess::current(), base).and_then(f);
+    unsafe { FreeLibrary(handle) };
+    result
+}
+
+pub(crate) fn malformed(message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("malformed pe image: {message}"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{Process, ProcessModule};
+
+    #[test]
+    fn imports_of_loaded_module() {
+        let kernel32 = ProcessModule::find_by_name("kernel32.dll", BorrowedProcess::current())
+            .unwrap()
+            .unwrap();
+        let image = RemoteImage::new(kernel32.borrowed()).unwrap();
+        assert_eq!(image.pointer_size(), std::mem::size_of::<usize>());
+
+        let imports = image.imports().unwrap();
+        assert!(!imports.is_empty());
+        assert!(imports
+            .iter()
+            .all(|import| import.name.is_some() || import.ordinal.is_some()));
+    }
+
+    #[test]
+    fn export_rva_of_loaded_module() {
+        let kernel32 = ProcessModule::find_by_name("kernel32.dll", BorrowedProcess::current())
+            .unwrap()
+            .unwrap();
+        let image = RemoteImage::new(kernel32.borrowed()).unwrap();
+
+        let load_library = kernel32
+            .get_local_procedure_address("LoadLibraryW")
+            .unwrap();
+        assert_eq!(
+            image.export_rva("LoadLibraryW").unwrap(),
+            Some(load_library as usize - kernel32.handle() as usize)
+        );
+        assert_eq!(image.export_rva("NotAnExport").unwrap(), None);
+    }
+
+    #[test]
+    fn entry_point_of_current_exe() {
+        let exe = ProcessModule::find_by_name(
+            std::env::current_exe().unwrap().file_name().unwrap(),
+            BorrowedProcess::current(),
+        )
+        .unwrap()
+        .unwrap();
+        let image = RemoteImage::new(exe.borrowed()).unwrap();
+        assert!(image.entry_point_rva().unwrap().is_some());
+    }
+}
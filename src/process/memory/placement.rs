@@ -0,0 +1,51 @@
+use std::ops::Range;
+
+/// The largest distance between the end of an instruction and its target that a `rel32` displacement can reach.
+const REL32_REACH: usize = i32::MAX as usize;
+
+/// Where memory is placed in the address space of a process, see [`Syringe::set_allocation_placement`](crate::Syringe::set_allocation_placement)
+/// and `ProcessMemoryBuffer::allocate_code_with_placement`.
+///
+/// Allocations are always aligned to the allocation granularity of the system, which is usually 64 KiB.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::{process::{AllocationPlacement, OwnedProcess}, Syringe};
+///
+/// let mut syringe = Syringe::for_process(OwnedProcess::find_first_by_name("target_process").unwrap());
+/// // keep the stubs of the syringe addressable with 32-bit pointers.
+/// syringe.set_allocation_placement(AllocationPlacement::Below4Gb);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum AllocationPlacement {
+    /// Let the operating system choose the address, which is usually the lowest free address.
+    #[default]
+    Any,
+    /// Let the operating system choose the highest free address (`MEM_TOP_DOWN`).
+    TopDown,
+    /// Place the allocation below 4 GiB, so it can be addressed using 32-bit pointers.
+    Below4Gb,
+    /// Place the allocation entirely inside the given address range or fail if there is no free space in it.
+    Range(Range<usize>),
+    /// Place the allocation entirely inside the given address range if there is free space in it and let the operating system choose otherwise.
+    PreferredRange(Range<usize>),
+}
+
+impl AllocationPlacement {
+    /// Returns a placement that is reachable from the given address using a `rel32` displacement, e.g. by a `jmp` or `call` of a hook.
+    #[must_use]
+    pub fn near(address: usize) -> Self {
+        Self::Range(address.saturating_sub(REL32_REACH)..address.saturating_add(REL32_REACH))
+    }
+
+    /// Returns the address range the allocation has to be placed in and whether it may be placed elsewhere if the range is full.
+    pub(crate) fn range(&self) -> Option<(Range<usize>, bool)> {
+        match self {
+            Self::Any | Self::TopDown => None,
+            // a 32-bit process cannot address anything above 4 GiB anyway.
+            Self::Below4Gb => Some((0..usize::try_from(1u64 << 32).unwrap_or(usize::MAX), false)),
+            Self::Range(range) => Some((range.clone(), false)),
+            Self::PreferredRange(range) => Some((range.clone(), true)),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::{io, mem, os::windows::prelude::AsRawHandle};
+
+use winapi::um::memoryapi::VirtualProtectEx;
+
+use crate::process::memory::{MemoryProtection, ProcessMemorySlice};
+
+impl<'a> ProcessMemorySlice<'a> {
+    /// Changes the protection of all pages containing this buffer to the given protection using [`VirtualProtectEx`](https://docs.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-virtualprotectex).
+    /// The returned guard restores the previous protection when it is dropped.
+    ///
+    /// # Note
+    /// The previous protection is that of the first page of the buffer. If the buffer spans pages with differing protections,
+    /// all of them are restored to the protection of the first page.
+    ///
+    /// # Safety
+    /// The caller must ensure that changing the protection does not break code in the target process (e.g. by making memory it is using inaccessible).
+    pub unsafe fn protect(
+        &self,
+        new_protection: MemoryProtection,
+    ) -> Result<ProtectionGuard<'a>, io::Error> {
+        let old_protection = unsafe { self.set_protection(new_protection) }?;
+        Ok(ProtectionGuard {
+            memory: *self,
+            old_protection,
+        })
+    }
+
+    pub(crate) unsafe fn set_protection(
+        &self,
+        protection: MemoryProtection,
+    ) -> Result<MemoryProtection, io::Error> {
+        let mut old_protection = 0;
+        let result = unsafe {
+            VirtualProtectEx(
+                self.process().as_raw_handle(),
+                self.as_ptr().cast(),
+                self.len(),
+                protection.0,
+                &mut old_protection,
+            )
+        };
+        if result == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(MemoryProtection(old_protection))
+    }
+}
+
+/// A guard that restores the previous protection of a [`ProcessMemorySlice`] when dropped.
+///
+/// This struct is created by [`ProcessMemorySlice::protect`].
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[must_use = "the previous protection is restored immediately if the guard is dropped"]
+#[derive(Debug)]
+pub struct ProtectionGuard<'a> {
+    memory: ProcessMemorySlice<'a>,
+    old_protection: MemoryProtection,
+}
+
+impl<'a> ProtectionGuard<'a> {
+    /// Returns the memory whose protection was changed.
+    #[must_use]
+    pub const fn memory(&self) -> ProcessMemorySlice<'a> {
+        self.memory
+    }
+
+    /// Returns the protection that will be restored.
+    #[must_use]
+    pub const fn old_protection(&self) -> MemoryProtection {
+        self.old_protection
+    }
+
+    /// Restores the previous protection, returning an error if the operation failed.
+    pub fn restore(self) -> Result<(), io::Error> {
+        let result = unsafe { self.memory.set_protection(self.old_protection) };
+        mem::forget(self);
+        result.map(|_| ())
+    }
+
+    /// Consumes the guard without restoring the previous protection.
+    pub fn keep(self) {
+        mem::forget(self);
+    }
+}
+
+impl Drop for ProtectionGuard<'_> {
+    fn drop(&mut self) {
+        let result = unsafe { self.memory.set_protection(self.old_protection) };
+        debug_assert!(
+            result.is_ok(),
+            "failed to restore memory protection: {:?}",
+            result
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winapi::um::winnt::PAGE_READONLY;
+
+    use super::*;
+    use crate::process::{
+        memory::{MemoryRegionIter, ProcessMemoryBuffer},
+        BorrowedProcess, Process,
+    };
+
+    fn current_protection(memory: &ProcessMemorySlice<'_>) -> MemoryProtection {
+        MemoryRegionIter::new(memory.process())
+            .map(Result::unwrap)
+            .find(|region| region.contains(memory.as_ptr()))
+            .unwrap()
+            .protection()
+    }
+
+    #[test]
+    fn guard_restores_protection() {
+        let process = BorrowedProcess::current();
+        let buffer = ProcessMemoryBuffer::allocate_data(process, 16).unwrap();
+        assert!(current_protection(&buffer).is_writable());
+
+        let guard = unsafe { buffer.protect(MemoryProtection(PAGE_READONLY)) }.unwrap();
+        assert!(guard.old_protection().is_writable());
+        assert_eq!(current_protection(&buffer), MemoryProtection(PAGE_READONLY));
+
+        drop(guard);
+        assert!(current_protection(&buffer).is_writable());
+    }
+}
@@ -1,6 +1,16 @@
-use std::{collections::LinkedList, io, mem, ptr::NonNull};
+use std::{cmp, collections::LinkedList, io, mem, ptr::NonNull};
 
-use crate::process::{memory::ProcessMemoryBuffer, BorrowedProcess, Process};
+use crate::{
+    error::RemoteAllocationBudgetExceeded,
+    process::{
+        memory::{AllocationPlacement, ProcessMemoryBuffer},
+        BorrowedProcess, Process,
+    },
+    utils::trace_event,
+};
+
+/// The minimum alignment of allocations made by a [`FixedBufferAllocator`].
+const MIN_ALIGN: usize = mem::size_of::<u64>();
 
 pub trait RawAllocator {
     type Error;
@@ -14,6 +24,10 @@ pub trait RawAllocator {
 pub struct DynamicMultiBufferAllocator<'a> {
     process: BorrowedProcess<'a>,
     pages: Vec<FixedBufferAllocator<'a>>,
+    placement: AllocationPlacement,
+    budget: Option<usize>,
+    // bytes committed in the process outside of the pages, e.g. heap blocks, which count against the budget as well.
+    external_bytes: usize,
 }
 
 impl<'a> DynamicMultiBufferAllocator<'a> {
@@ -21,9 +35,69 @@ impl<'a> DynamicMultiBufferAllocator<'a> {
         Self {
             process,
             pages: Vec::new(),
+            placement: AllocationPlacement::Any,
+            budget: None,
+            external_bytes: 0,
         }
     }
 
+    pub const fn placement(&self) -> &AllocationPlacement {
+        &self.placement
+    }
+
+    /// Sets where new pages are placed in the process. Existing pages keep being used for allocations that fit into them.
+    pub fn set_placement(&mut self, placement: AllocationPlacement) {
+        self.placement = placement;
+    }
+
+    pub const fn budget(&self) -> Option<usize> {
+        self.budget
+    }
+
+    /// Sets the maximum number of bytes that may be committed in the process. Existing pages are kept even if they exceed the budget.
+    pub fn set_budget(&mut self, budget: Option<usize>) {
+        self.budget = budget;
+    }
+
+    /// Returns the number of bytes committed in the process, i.e. the reserved pages and the external bytes.
+    pub fn count_committed_bytes(&self) -> usize {
+        self.count_reserved_bytes() + self.external_bytes
+    }
+
+    /// Checks whether committing the given number of additional bytes stays within the budget.
+    pub fn check_budget(&self, requested: usize) -> Result<(), io::Error> {
+        let Some(budget) = self.budget else {
+            return Ok(());
+        };
+        let committed = self.count_committed_bytes();
+        if committed.saturating_add(requested) <= budget {
+            return Ok(());
+        }
+        trace_event!(
+            warn,
+            budget,
+            committed,
+            requested,
+            "remote allocation exceeds budget"
+        );
+        Err(RemoteAllocationBudgetExceeded {
+            budget,
+            committed,
+            requested,
+        }
+        .into_io_error())
+    }
+
+    /// Records bytes committed in the process outside of the pages of this allocator.
+    pub fn add_external_bytes(&mut self, len: usize) {
+        self.external_bytes += len;
+    }
+
+    /// Records that bytes previously added using [`add_external_bytes`](Self::add_external_bytes) were released.
+    pub fn remove_external_bytes(&mut self, len: usize) {
+        self.external_bytes -= len;
+    }
+
     pub fn process(&self) -> BorrowedProcess<'_> {
         self.process.borrowed()
     }
@@ -31,18 +105,75 @@ impl<'a> DynamicMultiBufferAllocator<'a> {
     fn alloc_page(&mut self, min_size: usize) -> Result<&mut FixedBufferAllocator<'a>, io::Error> {
         let os_page_size = ProcessMemoryBuffer::os_page_size();
         let page_size = (min_size / os_page_size + 1) * os_page_size;
-        let mem = ProcessMemoryBuffer::allocate(self.process, page_size)?;
+        self.check_budget(page_size)?;
+        let mem = ProcessMemoryBuffer::allocate_code_with_placement(
+            self.process,
+            page_size,
+            &self.placement,
+        )?;
+        trace_event!(debug, address = ?mem.as_ptr(), len = page_size, pages = self.pages.len() + 1, "allocated remote allocator page");
         let page = FixedBufferAllocator::new(mem);
         self.pages.push(page);
         Ok(self.pages.last_mut().unwrap())
     }
 
+    /// Allocates a block of the given size whose address is a multiple of the given alignment.
+    ///
+    /// # Panics
+    /// This function will panic if the alignment is not a power of two.
+    pub fn alloc_aligned(&mut self, size: usize, align: usize) -> Result<Allocation, io::Error> {
+        for page in &mut self.pages {
+            match page.alloc_aligned(size, align) {
+                Ok(allocation) => return Ok(allocation),
+                Err(AllocError::Io(e)) => return Err(e),
+                Err(AllocError::OutOfMemory) => continue,
+            }
+        }
+
+        // pages are page aligned, so only larger alignments require additional space for padding.
+        let padding = if align > ProcessMemoryBuffer::os_page_size() {
+            align
+        } else {
+            0
+        };
+        let page = self.alloc_page(size + padding)?;
+        match page.alloc_aligned(size, align) {
+            Ok(allocation) => Ok(allocation),
+            Err(AllocError::Io(e)) => Err(e),
+            Err(AllocError::OutOfMemory) => unreachable!(),
+        }
+    }
+
     pub fn count_allocated_bytes(&self) -> usize {
         self.pages
             .iter()
             .map(|page| page.count_allocated_bytes())
             .sum()
     }
+
+    pub fn count_reserved_bytes(&self) -> usize {
+        self.pages.iter().map(|page| page.memory().len()).sum()
+    }
+
+    /// Returns the memory of the pages of this allocator.
+    #[cfg(feature = "process-memory")]
+    pub fn pages(&self) -> impl Iterator<Item = &ProcessMemoryBuffer<'a>> {
+        self.pages.iter().map(FixedBufferAllocator::memory)
+    }
+
+    /// Releases all pages without live allocations back to the target process and returns the number of bytes released.
+    pub fn trim(&mut self) -> usize {
+        let reserved_bytes = self.count_reserved_bytes();
+        self.pages.retain(|page| !page.is_unused());
+        let released_bytes = reserved_bytes - self.count_reserved_bytes();
+        trace_event!(
+            debug,
+            released_bytes,
+            pages = self.pages.len(),
+            "trimmed remote allocator pages"
+        );
+        released_bytes
+    }
 }
 
 impl<'a> RawAllocator for DynamicMultiBufferAllocator<'a> {
@@ -50,20 +181,7 @@ impl<'a> RawAllocator for DynamicMultiBufferAllocator<'a> {
     type Alloc = Allocation;
 
     fn alloc(&mut self, size: usize) -> Result<Self::Alloc, Self::Error> {
-        for page in &mut self.pages {
-            match page.alloc(size) {
-                Ok(allocation) => return Ok(allocation),
-                Err(AllocError::Io(e)) => return Err(e),
-                Err(AllocError::OutOfMemory) => continue,
-            }
-        }
-
-        let page = self.alloc_page(size)?;
-        match page.alloc(size) {
-            Ok(allocation) => Ok(allocation),
-            Err(AllocError::Io(e)) => Err(e),
-            Err(AllocError::OutOfMemory) => unreachable!(),
-        }
+        self.alloc_aligned(size, MIN_ALIGN)
     }
 
     fn free(&mut self, allocation: &Self::Alloc) {
@@ -71,6 +189,10 @@ impl<'a> RawAllocator for DynamicMultiBufferAllocator<'a> {
             let page_start = page.mem.as_ptr() as usize;
             let page_end = page_start + page.mem.len();
             if allocation.base >= page_start && allocation.base < page_end {
+                #[cfg(feature = "process-memory")]
+                crate::journal::forget_allocation(page.mem.as_slice().slice(
+                    allocation.base - page_start..allocation.base - page_start + allocation.len,
+                ));
                 page.free(allocation);
                 return;
             }
@@ -88,6 +210,8 @@ pub struct FixedBufferAllocator<'a> {
 
 impl<'a> FixedBufferAllocator<'a> {
     pub fn new(mem: ProcessMemoryBuffer<'a>) -> Self {
+        #[cfg(feature = "process-memory")]
+        crate::journal::register_allocator_memory(*mem.as_slice());
         let free_list = LinkedList::from([MemoryBlock {
             base: mem.as_ptr() as usize,
             len: mem.len(),
@@ -110,28 +234,46 @@ impl<'a> FixedBufferAllocator<'a> {
     pub fn count_free_bytes(&self) -> usize {
         self.free_list.iter().map(|b| b.len).sum()
     }
-}
 
-impl RawAllocator for FixedBufferAllocator<'_> {
-    type Error = AllocError;
-    type Alloc = Allocation;
+    pub fn largest_free_block(&self) -> usize {
+        self.free_list.iter().map(|b| b.len).max().unwrap_or(0)
+    }
+
+    pub fn is_unused(&self) -> bool {
+        self.count_allocated_bytes() == 0
+    }
 
-    fn alloc(&mut self, mut size: usize) -> Result<Allocation, AllocError> {
-        // TODO: smarter alignment calculation
-        size = (size + mem::size_of::<u64>() - 1) & !(mem::size_of::<u64>() - 1);
+    /// Allocates a block of the given size whose address is a multiple of the given alignment.
+    ///
+    /// # Panics
+    /// This function will panic if the alignment is not a power of two.
+    pub fn alloc_aligned(&mut self, size: usize, align: usize) -> Result<Allocation, AllocError> {
+        assert!(align.is_power_of_two(), "alignment must be a power of two");
+        let align = cmp::max(align, MIN_ALIGN);
+        let size = size.next_multiple_of(MIN_ALIGN);
 
         let mut cursor = self.free_list.cursor_front_mut();
         while let Some(block) = cursor.current() {
-            if block.len >= size {
-                let alloc = Allocation {
-                    base: block.base,
-                    len: size,
+            let base = block.base.next_multiple_of(align);
+            let padding = base - block.base;
+            if block.len >= padding + size {
+                let alloc = Allocation { base, len: size };
+                let rest = MemoryBlock {
+                    base: base + size,
+                    len: block.len - padding - size,
                 };
-                block.base += size;
-                block.len -= size;
 
-                if block.len == 0 {
-                    cursor.remove_current();
+                if padding == 0 {
+                    *block = rest;
+                    if block.len == 0 {
+                        cursor.remove_current();
+                    }
+                } else {
+                    // the padding before the allocation stays free.
+                    block.len = padding;
+                    if rest.len != 0 {
+                        cursor.insert_after(rest);
+                    }
                 }
 
                 return Ok(alloc);
@@ -140,57 +282,48 @@ impl RawAllocator for FixedBufferAllocator<'_> {
         }
         Err(AllocError::OutOfMemory)
     }
+}
 
-    fn free(&mut self, alloc: &Allocation) {
-        let mut cursor = self.free_list.cursor_front_mut();
-        while let Some(block) = cursor.current() {
-            if alloc.base > block.base {
-                let prev_block = block;
-                let mut merged = false;
-
-                if alloc.base == prev_block.base + prev_block.len {
-                    // Alloc is directly after a free block -> merge
-                    prev_block.len += alloc.len;
-                    merged = true;
-                }
-
-                if let Some(next_block) = cursor.peek_next() {
-                    if alloc.base + alloc.len == next_block.base {
-                        // Alloc is directly before a free block -> merge
-                        if merged {
-                            // merging with and prev next block
-                            let prev_block = cursor.remove_current().unwrap();
-                            let next_block = cursor.current().unwrap();
-                            next_block.base = prev_block.base;
-                            next_block.len += prev_block.len;
-                        } else {
-                            // only merging with next block
-                            next_block.base = alloc.base;
-                            next_block.len += alloc.len;
-                            merged = true;
-                        }
-                    }
-                }
-
-                // Alloc is not directly before or after a free block -> insert
-                if !merged {
-                    cursor.insert_after(MemoryBlock {
-                        base: alloc.base,
-                        len: alloc.len,
-                    });
-                }
+impl RawAllocator for FixedBufferAllocator<'_> {
+    type Error = AllocError;
+    type Alloc = Allocation;
 
-                return;
-            }
+    fn alloc(&mut self, size: usize) -> Result<Allocation, AllocError> {
+        self.alloc_aligned(size, MIN_ALIGN)
+    }
 
+    fn free(&mut self, alloc: &Allocation) {
+        // the free list is ordered by address, so the block is merged with the free blocks directly around it.
+        let mut cursor = self.free_list.cursor_front_mut();
+        while cursor
+            .current()
+            .is_some_and(|block| block.base < alloc.base)
+        {
             cursor.move_next();
         }
 
-        // no free block found -> insert
-        cursor.insert_after(MemoryBlock {
-            base: alloc.base,
-            len: alloc.len,
-        });
+        let merges_prev = cursor
+            .peek_prev()
+            .is_some_and(|prev| prev.base + prev.len == alloc.base);
+        let merges_next = cursor
+            .current()
+            .is_some_and(|next| alloc.base + alloc.len == next.base);
+        match (merges_prev, merges_next) {
+            (true, true) => {
+                let next = cursor.remove_current().unwrap();
+                cursor.peek_prev().unwrap().len += alloc.len + next.len;
+            }
+            (true, false) => cursor.peek_prev().unwrap().len += alloc.len,
+            (false, true) => {
+                let next = cursor.current().unwrap();
+                next.base = alloc.base;
+                next.len += alloc.len;
+            }
+            (false, false) => cursor.insert_before(MemoryBlock {
+                base: alloc.base,
+                len: alloc.len,
+            }),
+        }
     }
 }
 
@@ -357,6 +490,131 @@ mod tests {
         );
     }
 
+    #[test]
+    fn over_aligned_alloc() {
+        let process = BorrowedProcess::current();
+        let memory = ProcessMemoryBuffer::allocate_page(process).unwrap();
+        let mut allocator = FixedBufferAllocator::new(memory);
+
+        let a = allocator.alloc(mem::size_of::<u8>()).unwrap();
+        let b = allocator.alloc_aligned(32, 64).unwrap();
+        assert_eq!(b.as_raw_ptr() as usize % 64, 0);
+        let c = allocator.alloc(mem::size_of::<u8>()).unwrap();
+        // the padding before the aligned allocation is reused.
+        assert!(c.base < b.base);
+
+        let free_bytes = allocator.count_free_bytes();
+        allocator.free(&c);
+        allocator.free(&b);
+        allocator.free(&a);
+        assert_eq!(allocator.count_allocated_bytes(), 0);
+        assert!(allocator.count_free_bytes() > free_bytes);
+
+        let mut allocator = DynamicMultiBufferAllocator::new(process);
+        let page_size = ProcessMemoryBuffer::os_page_size();
+        let d = allocator.alloc_aligned(16, page_size * 4).unwrap();
+        assert_eq!(d.as_raw_ptr() as usize % (page_size * 4), 0);
+    }
+
+    #[test]
+    fn free_coalesces_neighbors_in_any_order() {
+        let process = BorrowedProcess::current();
+        let memory = ProcessMemoryBuffer::allocate_page(process).unwrap();
+        let len = memory.len();
+        let mut allocator = FixedBufferAllocator::new(memory);
+
+        let allocations = (0..16)
+            .map(|_| allocator.alloc(64).unwrap())
+            .collect::<Vec<_>>();
+        for i in [3, 1, 2, 0, 15, 8, 10, 9, 4, 6, 5, 7, 12, 14, 13, 11] {
+            allocator.free(&allocations[i]);
+        }
+
+        assert_eq!(allocator.free_list.len(), 1);
+        assert_eq!(allocator.largest_free_block(), len);
+    }
+
+    #[test]
+    fn churn_does_not_fragment() {
+        let process = BorrowedProcess::current();
+        let memory = ProcessMemoryBuffer::allocate_page(process).unwrap();
+        let len = memory.len();
+        let mut allocator = FixedBufferAllocator::new(memory);
+
+        // deterministic pseudo random sizes and free order.
+        let mut state = 0x2545_f491_u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as usize
+        };
+
+        let mut live = Vec::new();
+        for _ in 0..10_000 {
+            if live.len() < 16 && next() % 3 != 0 {
+                if let Ok(allocation) = allocator.alloc(next() % 200 + 1) {
+                    live.push(allocation);
+                }
+            } else if !live.is_empty() {
+                let allocation = live.swap_remove(next() % live.len());
+                allocator.free(&allocation);
+            }
+        }
+        for allocation in live.drain(..) {
+            allocator.free(&allocation);
+        }
+
+        assert_eq!(allocator.count_allocated_bytes(), 0);
+        assert_eq!(allocator.free_list.len(), 1);
+        assert!(allocator.alloc(len).is_ok());
+    }
+
+    #[test]
+    fn trim_releases_unused_pages() {
+        let process = BorrowedProcess::current();
+        let mut allocator = DynamicMultiBufferAllocator::new(process);
+
+        let page_size = ProcessMemoryBuffer::os_page_size();
+        let kept = allocator.alloc(16).unwrap();
+        let released = allocator.alloc(page_size).unwrap();
+        allocator.free(&released);
+        let reserved_bytes = allocator.count_reserved_bytes();
+
+        assert!(allocator.trim() > 0);
+        assert!(allocator.count_reserved_bytes() < reserved_bytes);
+        assert_eq!(allocator.count_allocated_bytes(), kept.len);
+        assert_eq!(allocator.trim(), 0);
+    }
+
+    #[test]
+    fn budget_limits_committed_pages() {
+        let process = BorrowedProcess::current();
+        let mut allocator = DynamicMultiBufferAllocator::new(process);
+
+        let page_size = ProcessMemoryBuffer::os_page_size();
+        allocator.set_budget(Some(2 * page_size));
+        let first = allocator.alloc(page_size / 2).unwrap();
+        // fits into the existing page, so no memory is committed.
+        let _second = allocator.alloc(page_size / 4).unwrap();
+        assert_eq!(allocator.count_committed_bytes(), page_size);
+
+        let err = allocator.alloc(page_size).unwrap_err();
+        let err = RemoteAllocationBudgetExceeded::from_io_error(&err).unwrap();
+        assert_eq!(err.budget(), 2 * page_size);
+        assert_eq!(err.committed(), page_size);
+        assert_eq!(err.requested(), 2 * page_size);
+
+        allocator.add_external_bytes(page_size);
+        assert!(allocator.alloc(page_size / 2).is_err());
+        allocator.remove_external_bytes(page_size);
+        assert!(allocator.alloc(page_size / 2).is_ok());
+
+        allocator.free(&first);
+        allocator.set_budget(None);
+        assert!(allocator.alloc(page_size * 4).is_ok());
+    }
+
     #[test]
     fn large_alloc() {
         let process = BorrowedProcess::current();
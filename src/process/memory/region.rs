@@ -0,0 +1,345 @@
+use std::{
+    io,
+    iter::FusedIterator,
+    mem::{self, MaybeUninit},
+    os::windows::prelude::AsRawHandle,
+    path::PathBuf,
+};
+
+use winapi::{
+    shared::winerror::{ERROR_INSUFFICIENT_BUFFER, ERROR_INVALID_PARAMETER},
+    um::{
+        memoryapi::VirtualQueryEx,
+        psapi::GetMappedFileNameW,
+        winnt::{
+            MEMORY_BASIC_INFORMATION, MEM_COMMIT, MEM_IMAGE, MEM_MAPPED, MEM_PRIVATE, MEM_RESERVE,
+            PAGE_EXECUTE, PAGE_EXECUTE_READ, PAGE_EXECUTE_READWRITE, PAGE_EXECUTE_WRITECOPY,
+            PAGE_GUARD, PAGE_NOACCESS, PAGE_READONLY, PAGE_READWRITE, PAGE_WRITECOPY,
+        },
+    },
+};
+
+use crate::{
+    process::BorrowedProcess,
+    utils::{win_fill_path_buf_helper, FillPathBufResult},
+};
+
+/// The state of the pages in a [`MemoryRegion`].
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemoryState {
+    /// The pages are committed and backed by physical storage.
+    Commit,
+    /// The pages are reserved but not backed by physical storage.
+    Reserve,
+    /// The pages are free and inaccessible.
+    Free,
+}
+
+/// The type of the pages in a [`MemoryRegion`].
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemoryType {
+    /// The pages are mapped into the view of an image section.
+    Image,
+    /// The pages are mapped into the view of a section.
+    Mapped,
+    /// The pages are private.
+    Private,
+    /// The pages have no type, which is the case for free regions
+    /// (i.e. [`MEMORY_BASIC_INFORMATION::Type`](https://docs.microsoft.com/en-us/windows/win32/api/winnt/ns-winnt-memory_basic_information) is none of `MEM_IMAGE`, `MEM_MAPPED` or `MEM_PRIVATE`).
+    None,
+}
+
+/// The [memory protection](https://docs.microsoft.com/en-us/windows/win32/memory/memory-protection-constants) of the pages in a [`MemoryRegion`].
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MemoryProtection(pub u32);
+
+impl MemoryProtection {
+    const READABLE: u32 = PAGE_READONLY
+        | PAGE_READWRITE
+        | PAGE_WRITECOPY
+        | PAGE_EXECUTE_READ
+        | PAGE_EXECUTE_READWRITE
+        | PAGE_EXECUTE_WRITECOPY;
+    const WRITABLE: u32 =
+        PAGE_READWRITE | PAGE_WRITECOPY | PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY;
+    const EXECUTABLE: u32 =
+        PAGE_EXECUTE | PAGE_EXECUTE_READ | PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY;
+
+    /// Returns whether the pages can be read.
+    #[must_use]
+    pub const fn is_readable(&self) -> bool {
+        self.0 & Self::READABLE != 0 && !self.is_guard()
+    }
+
+    /// Returns whether the pages can be written to.
+    #[must_use]
+    pub const fn is_writable(&self) -> bool {
+        self.0 & Self::WRITABLE != 0 && !self.is_guard()
+    }
+
+    /// Returns whether the pages can be executed.
+    #[must_use]
+    pub const fn is_executable(&self) -> bool {
+        self.0 & Self::EXECUTABLE != 0
+    }
+
+    /// Returns whether the pages are guard pages.
+    #[must_use]
+    pub const fn is_guard(&self) -> bool {
+        self.0 & PAGE_GUARD != 0
+    }
+
+    /// Returns whether the pages are inaccessible.
+    #[must_use]
+    pub const fn is_no_access(&self) -> bool {
+        self.0 & PAGE_NOACCESS != 0
+    }
+}
+
+/// A contiguous region of pages with identical attributes in the memory space of a process, as reported by [`VirtualQueryEx`](https://docs.microsoft.com/en-us/windows/win32/api/memoryapi/nf-memoryapi-virtualqueryex).
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryRegion {
+    base: *mut u8,
+    allocation_base: *mut u8,
+    size: usize,
+    state: MemoryState,
+    protection: MemoryProtection,
+    allocation_protection: MemoryProtection,
+    kind: MemoryType,
+    mapped_file_name: Option<PathBuf>,
+}
+
+impl MemoryRegion {
+    /// Returns a pointer to the start of the region.
+    ///
+    /// # Note
+    /// The returned pointer is only valid in the target process.
+    #[must_use]
+    pub const fn base(&self) -> *mut u8 {
+        self.base
+    }
+
+    /// Returns a pointer to the start of the allocation this region is part of.
+    ///
+    /// # Note
+    /// The returned pointer is only valid in the target process.
+    #[must_use]
+    pub const fn allocation_base(&self) -> *mut u8 {
+        self.allocation_base
+    }
+
+    /// Returns the size of the region in bytes.
+    #[must_use]
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns whether the given address lies inside this region.
+    #[must_use]
+    pub fn contains(&self, address: *const u8) -> bool {
+        let address = address as usize;
+        let base = self.base as usize;
+        address >= base && address - base < self.size
+    }
+
+    /// Returns the state of the pages in the region.
+    #[must_use]
+    pub const fn state(&self) -> MemoryState {
+        self.state
+    }
+
+    /// Returns the current protection of the pages in the region.
+    #[must_use]
+    pub const fn protection(&self) -> MemoryProtection {
+        self.protection
+    }
+
+    /// Returns the protection the pages in the region were initially allocated with.
+    #[must_use]
+    pub const fn allocation_protection(&self) -> MemoryProtection {
+        self.allocation_protection
+    }
+
+    /// Returns the type of the pages in the region.
+    #[must_use]
+    pub const fn kind(&self) -> MemoryType {
+        self.kind
+    }
+
+    /// Returns the name of the file mapped into this region if the region is of type [`MemoryType::Image`] or [`MemoryType::Mapped`].
+    ///
+    /// # Note
+    /// The returned path is in device form (e.g. `\Device\HarddiskVolume1\Windows\System32\kernel32.dll`).
+    #[must_use]
+    pub fn mapped_file_name(&self) -> Option<&PathBuf> {
+        self.mapped_file_name.as_ref()
+    }
+
+    /// Returns whether the pages in the region are committed and readable.
+    #[must_use]
+    pub const fn is_readable(&self) -> bool {
+        matches!(self.state, MemoryState::Commit) && self.protection.is_readable()
+    }
+}
+
+/// An iterator over the [`MemoryRegion`]s of a process.
+///
+/// This struct is created by [`Process::memory_regions`](crate::process::Process::memory_regions).
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[derive(Debug, Clone)]
+pub struct MemoryRegionIter<'a> {
+    process: BorrowedProcess<'a>,
+    address: Option<usize>,
+}
+
+impl<'a> MemoryRegionIter<'a> {
+    /// Creates a new iterator over the memory regions of the given process.
+    #[must_use]
+    pub const fn new(process: BorrowedProcess<'a>) -> Self {
+        Self {
+            process,
+            address: Some(0),
+        }
+    }
+
+    /// Returns the process whose memory regions are iterated.
+    #[must_use]
+    pub const fn process(&self) -> BorrowedProcess<'a> {
+        self.process
+    }
+
+    pub(crate) fn query(&self, address: usize) -> Result<Option<MemoryRegion>, io::Error> {
+        let mut info = MaybeUninit::<MEMORY_BASIC_INFORMATION>::uninit();
+        let result = unsafe {
+            VirtualQueryEx(
+                self.process.as_raw_handle(),
+                address as *const _,
+                info.as_mut_ptr(),
+                mem::size_of::<MEMORY_BASIC_INFORMATION>(),
+            )
+        };
+        if result == 0 {
+            let err = io::Error::last_os_error();
+            // VirtualQueryEx fails with ERROR_INVALID_PARAMETER once the address is above the highest user-mode address.
+            if err.raw_os_error() == Some(ERROR_INVALID_PARAMETER as i32) {
+                return Ok(None);
+            }
+            return Err(err);
+        }
+        let info = unsafe { info.assume_init() };
+
+        let state = match info.State {
+            MEM_COMMIT => MemoryState::Commit,
+            MEM_RESERVE => MemoryState::Reserve,
+            _ => MemoryState::Free,
+        };
+        let kind = match info.Type {
+            MEM_IMAGE => MemoryType::Image,
+            MEM_MAPPED => MemoryType::Mapped,
+            MEM_PRIVATE => MemoryType::Private,
+            _ => MemoryType::None,
+        };
+        let base = info.BaseAddress.cast::<u8>();
+        let mapped_file_name = match kind {
+            MemoryType::Image | MemoryType::Mapped if state != MemoryState::Free => {
+                self.mapped_file_name(base).ok()
+            }
+            _ => None,
+        };
+
+        Ok(Some(MemoryRegion {
+            base,
+            allocation_base: info.AllocationBase.cast(),
+            size: info.RegionSize,
+            state,
+            protection: MemoryProtection(info.Protect),
+            allocation_protection: MemoryProtection(info.AllocationProtect),
+            kind,
+            mapped_file_name,
+        }))
+    }
+
+    fn mapped_file_name(&self, base: *mut u8) -> Result<PathBuf, io::Error> {
+        win_fill_path_buf_helper(|buf_ptr, buf_size| {
+            let buf_size = buf_size as u32;
+            let result = unsafe {
+                GetMappedFileNameW(self.process.as_raw_handle(), base.cast(), buf_ptr, buf_size)
+            };
+            if result == 0 {
+                let err = io::Error::last_os_error();
+                if err.raw_os_error().unwrap() == ERROR_INSUFFICIENT_BUFFER as i32 {
+                    FillPathBufResult::BufTooSmall { size_hint: None }
+                } else {
+                    FillPathBufResult::Error(err)
+                }
+            } else if result >= buf_size {
+                FillPathBufResult::BufTooSmall { size_hint: None }
+            } else {
+                FillPathBufResult::Success {
+                    actual_len: result as usize,
+                }
+            }
+        })
+    }
+}
+
+impl Iterator for MemoryRegionIter<'_> {
+    type Item = Result<MemoryRegion, io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let address = self.address?;
+        match self.query(address) {
+            Ok(Some(region)) => {
+                self.address = (region.base as usize).checked_add(region.size);
+                Some(Ok(region))
+            }
+            Ok(None) => {
+                self.address = None;
+                None
+            }
+            Err(e) => {
+                self.address = None;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl FusedIterator for MemoryRegionIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{memory::ProcessMemoryBuffer, Process};
+
+    #[test]
+    fn regions_contain_allocation() {
+        let process = BorrowedProcess::current();
+        let buffer = ProcessMemoryBuffer::allocate_data(process, 16).unwrap();
+
+        let region = MemoryRegionIter::new(process)
+            .map(Result::unwrap)
+            .find(|region| region.contains(buffer.as_ptr()))
+            .unwrap();
+        assert_eq!(region.state(), MemoryState::Commit);
+        assert_eq!(region.kind(), MemoryType::Private);
+        assert!(region.protection().is_writable());
+        assert!(region.mapped_file_name().is_none());
+    }
+
+    #[test]
+    fn regions_are_contiguous() {
+        let process = BorrowedProcess::current();
+        let mut expected_base = 0;
+        for region in MemoryRegionIter::new(process) {
+            let region = region.unwrap();
+            assert_eq!(region.base() as usize, expected_base);
+            expected_base = region.base() as usize + region.size();
+        }
+        assert_ne!(expected_base, 0);
+    }
+}
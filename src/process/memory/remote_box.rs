@@ -1,44 +1,415 @@
-use std::{cell::RefCell, io, marker::PhantomData, mem, ptr::NonNull, rc::Rc, slice};
+use std::{
+    cell::{Cell, OnceCell, RefCell},
+    collections::HashMap,
+    io,
+    marker::PhantomData,
+    mem,
+    ptr::NonNull,
+    rc::{Rc, Weak},
+    slice,
+    time::Duration,
+};
+
+use winapi::shared::winerror::ERROR_PARTIAL_COPY;
 
-use crate::process::{
-    memory::{Allocation, DynamicMultiBufferAllocator, ProcessMemorySlice, RawAllocator},
-    BorrowedProcess, OwnedProcess, Process,
+use crate::{
+    process::{
+        memory::{
+            Allocation, AllocationPlacement, DynamicMultiBufferAllocator, ProcessMemorySlice,
+            RawAllocator, RemoteAllocationBackend, RemoteHeapAllocator,
+        },
+        BorrowedProcess, OwnedProcess, Process, RemoteThreadOptions, RemoteThreadResult,
+    },
+    remote_worker::RemoteWorker,
+    utils::trace_event,
 };
 
+/// The alignment of blocks returned by `RtlAllocateHeap` in both 32-bit and 64-bit processes.
+const HEAP_ALIGNMENT: usize = 8;
+
+/// Code of an active code batch that was not written yet, as pairs of its address and bytes.
+type PendingCode = Vec<(usize, Vec<u8>)>;
+
 #[derive(Debug, Clone)]
 pub struct RemoteBoxAllocator(pub(crate) Rc<RemoteBoxAllocatorInner>);
 
 #[derive(Debug)]
 pub(crate) struct RemoteBoxAllocatorInner {
+    // declared first, so the worker thread stops before the process handle is closed and its pages are freed.
+    worker: RefCell<Option<RemoteWorker>>,
+    // how long calls on the worker are waited for before giving up on them.
+    worker_call_timeout: Cell<Option<Duration>>,
     pub(crate) process: OwnedProcess,
     pub(crate) allocator: RefCell<DynamicMultiBufferAllocator<'static>>,
+    backend: Cell<RemoteAllocationBackend>,
+    // created on first use, as it has to locate the heap and place a stub in the target.
+    heap_allocator: RefCell<Option<RemoteHeapAllocator>>,
+    // code that does not embed any addresses is shared between its users, keyed by its bytes.
+    shared_code: RefCell<HashMap<Vec<u8>, Weak<RemoteAllocation>>>,
+    // code waiting to be written by the active code batch, or `None` if no batch is active.
+    pending_code: RefCell<Option<PendingCode>>,
+    // set once the target is found to have exited, after which the remote memory is no longer touched.
+    dead: Cell<bool>,
+    // the architecture of a process never changes, so it is only queried once.
+    is_x86: OnceCell<bool>,
+    // the number and total size of the allocations that were not dropped yet.
+    live_allocations: Cell<usize>,
+    live_allocation_bytes: Cell<usize>,
+    // memory that could not be reused because a worker thread did not stop in time.
+    leaked_bytes: Cell<usize>,
 }
 
 impl RemoteBoxAllocator {
     pub fn new(process: OwnedProcess) -> Self {
+        Self::with_backend(process, RemoteAllocationBackend::default())
+    }
+
+    pub fn with_backend(process: OwnedProcess, backend: RemoteAllocationBackend) -> Self {
         Self(Rc::new(RemoteBoxAllocatorInner {
             allocator: RefCell::new(DynamicMultiBufferAllocator::new(unsafe {
                 process.borrowed_static()
             })),
+            worker: RefCell::new(None),
+            worker_call_timeout: Cell::new(None),
             process,
+            backend: Cell::new(backend),
+            heap_allocator: RefCell::new(None),
+            shared_code: RefCell::new(HashMap::new()),
+            pending_code: RefCell::new(None),
+            dead: Cell::new(false),
+            is_x86: OnceCell::new(),
+            live_allocations: Cell::new(0),
+            live_allocation_bytes: Cell::new(0),
+            leaked_bytes: Cell::new(0),
         }))
     }
 
+    pub fn backend(&self) -> RemoteAllocationBackend {
+        self.0.backend.get()
+    }
+
+    /// Sets the backend used for future data allocations, existing allocations are freed by the backend they came from.
+    pub fn set_backend(&self, backend: RemoteAllocationBackend) {
+        self.0.backend.set(backend);
+    }
+
     pub fn process(&self) -> BorrowedProcess<'_> {
         self.0.process.borrowed()
     }
 
+    /// Returns whether the target process is a 32-bit process, see [`Process::is_x86`].
+    /// The result is cached, so stubs and calls can query it without a system call each time.
+    pub fn is_x86(&self) -> Result<bool, io::Error> {
+        self.0
+            .is_x86
+            .get_or_try_init(|| self.0.process.is_x86())
+            .copied()
+    }
+
+    /// Returns whether the target process was found to have exited.
+    /// Allocations of a dead allocator fail and dropping existing allocations does not touch the target anymore.
+    pub fn is_dead(&self) -> bool {
+        self.0.dead.get()
+    }
+
+    /// Checks whether a failed operation failed because the target exited, in which case the allocator is marked as dead
+    /// and the error is replaced with one that is reported as an inaccessible process.
+    pub fn check_target(&self, err: io::Error) -> io::Error {
+        if !self.is_dead() && !self.0.process.is_alive() {
+            trace_event!(debug, error = %err, "target of remote allocator exited");
+            self.0.dead.set(true);
+        }
+        if self.is_dead() {
+            Self::dead_target_error()
+        } else {
+            err
+        }
+    }
+
+    fn ensure_alive(&self) -> Result<(), io::Error> {
+        if self.is_dead() {
+            Err(Self::dead_target_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn dead_target_error() -> io::Error {
+        // this is the error reading from an exited process fails with, which all error types map to `ProcessInaccessible`.
+        io::Error::from_raw_os_error(ERROR_PARTIAL_COPY as _)
+    }
+
+    /// Starts a worker thread in the target that runs all following calls made through [`RemoteBoxAllocator::run_remote_thread`],
+    /// replacing a running worker. The given functions have to be the addresses of `WaitForSingleObject` and `SetEvent` in the target.
+    pub fn start_worker(
+        &self,
+        wait_for_single_object: usize,
+        set_event: usize,
+        options: &RemoteThreadOptions,
+    ) -> Result<(), io::Error> {
+        self.ensure_alive()?;
+        self.stop_worker()?;
+        let worker = RemoteWorker::start(
+            unsafe { self.0.process.borrowed_static() },
+            &mut self.0.allocator.borrow_mut(),
+            wait_for_single_object,
+            set_event,
+            options,
+        )
+        .map_err(|err| self.check_target(err))?;
+        *self.0.worker.borrow_mut() = Some(worker);
+        Ok(())
+    }
+
+    /// Stops the worker thread if one is running and returns whether one was running.
+    pub fn stop_worker(&self) -> Result<bool, io::Error> {
+        let Some(worker) = self.0.worker.borrow_mut().take() else {
+            return Ok(false);
+        };
+        if let Err(err) = worker.stop(&mut self.0.allocator.borrow_mut()) {
+            if err.kind() == io::ErrorKind::TimedOut {
+                self.0
+                    .leaked_bytes
+                    .set(self.0.leaked_bytes.get() + worker.memory_len());
+            }
+            return Err(err);
+        }
+        Ok(true)
+    }
+
+    /// Returns the id of the running worker thread, if any.
+    pub fn worker_tid(&self) -> Option<u32> {
+        self.0.worker.borrow().as_ref().map(RemoteWorker::tid)
+    }
+
+    /// Returns how long calls on the worker thread are waited for, see [`RemoteBoxAllocator::set_worker_call_timeout`].
+    pub fn worker_call_timeout(&self) -> Option<Duration> {
+        self.0.worker_call_timeout.get()
+    }
+
+    /// Sets how long calls on the worker thread are waited for before they fail with [`io::ErrorKind::TimedOut`].
+    /// While a call that timed out is still running, calls fall back to creating a thread each.
+    pub fn set_worker_call_timeout(&self, timeout: Option<Duration>) {
+        self.0.worker_call_timeout.set(timeout);
+    }
+
+    /// Calls the given function with the given parameter in the target and returns its result, see [`Process::run_remote_thread_with_options`].
+    /// The call is made on the worker thread if one is running and idle, in which case the options are ignored, and on a new thread otherwise.
+    ///
+    /// As the function returns a `u32`, only that is reported either way. Functions returning a full word are called
+    /// using [`RemoteBoxAllocator::call_on_worker`] instead.
+    pub fn run_remote_thread<T>(
+        &self,
+        remote_fn: extern "system" fn(*mut T) -> u32,
+        parameter: *mut T,
+        options: &RemoteThreadOptions,
+    ) -> Result<RemoteThreadResult, io::Error> {
+        self.write_pending_code()?;
+        match self.call_on_worker(remote_fn as usize, parameter as usize) {
+            // the worker passes on the full word left in `eax`/`rax`, of which only the lower 32 bits are defined for a function
+            // returning a `u32`. unlike the exit code of a thread, it is always a returned value, even if it looks like a status.
+            Some(result) => result
+                .map(|result| RemoteThreadResult::Returned((result & u64::from(u32::MAX)) as u32)),
+            None => self
+                .process()
+                .run_remote_thread_with_options(remote_fn, parameter, options),
+        }
+    }
+
+    /// Waits for at most the given timeout for a call on the worker thread that timed out to return and returns whether no call is
+    /// running on it anymore, which is also the case if no worker thread is running.
+    pub fn wait_for_worker(&self, timeout: Option<Duration>) -> Result<bool, io::Error> {
+        match self.0.worker.borrow().as_ref() {
+            Some(worker) => worker.wait_for_call(timeout),
+            None => Ok(true),
+        }
+    }
+
+    /// Calls the given function with the given parameter on the worker thread and returns the full word it returned,
+    /// or [`None`] if no worker thread is running or it is still busy with a call that timed out.
+    pub fn call_on_worker(
+        &self,
+        function: usize,
+        parameter: usize,
+    ) -> Option<Result<u64, io::Error>> {
+        if let Err(err) = self.write_pending_code() {
+            return Some(Err(err));
+        }
+        let worker = self.0.worker.borrow();
+        let running = worker.as_ref()?;
+        if running.is_busy() {
+            trace_event!(
+                debug,
+                tid = running.tid(),
+                "remote worker thread is busy, falling back to a new thread"
+            );
+            return None;
+        }
+
+        let result = running.call(function, parameter, self.0.worker_call_timeout.get());
+        if running.has_exited() {
+            drop(worker);
+            // later calls fall back to creating a thread each.
+            if let Err(err) = self.stop_worker() {
+                return Some(Err(err));
+            }
+        }
+        Some(result)
+    }
+
     pub fn alloc_raw(&self, size: usize) -> Result<RemoteAllocation, io::Error> {
+        self.alloc_raw_aligned(size, 1)
+    }
+    /// Allocates a block of the given size whose address in the target is a multiple of the given alignment.
+    pub fn alloc_raw_aligned(
+        &self,
+        size: usize,
+        align: usize,
+    ) -> Result<RemoteAllocation, io::Error> {
+        self.ensure_alive()?;
+        self.try_alloc_raw_aligned(size, align)
+            .map_err(|err| self.check_target(err))
+    }
+    fn try_alloc_raw_aligned(
+        &self,
+        size: usize,
+        align: usize,
+    ) -> Result<RemoteAllocation, io::Error> {
+        match self.backend() {
+            // heap blocks are only guaranteed to be aligned to 8 bytes in 32-bit processes.
+            RemoteAllocationBackend::ProcessHeap if align <= HEAP_ALIGNMENT => {
+                let mut heap_allocator = self.0.heap_allocator.borrow_mut();
+                let heap_allocator = match &mut *heap_allocator {
+                    Some(heap_allocator) => heap_allocator,
+                    None => heap_allocator.insert(RemoteHeapAllocator::new(
+                        unsafe { self.0.process.borrowed_static() },
+                        &mut self.0.allocator.borrow_mut(),
+                    )?),
+                };
+                self.0.allocator.borrow().check_budget(size)?;
+                let allocation = heap_allocator.alloc(size)?;
+                self.0
+                    .allocator
+                    .borrow_mut()
+                    .add_external_bytes(allocation.len);
+                Ok(RemoteAllocation::new(
+                    self.clone(),
+                    allocation,
+                    RemoteAllocationBackend::ProcessHeap,
+                ))
+            }
+            _ => self.alloc_from_pages(size, align),
+        }
+    }
+    fn alloc_from_pages(&self, size: usize, align: usize) -> Result<RemoteAllocation, io::Error> {
         // TODO: optimize empty allocations
-        let allocation = self.0.allocator.borrow_mut().alloc(size)?;
-        Ok(RemoteAllocation::new(self.clone(), allocation))
+        let allocation = self.0.allocator.borrow_mut().alloc_aligned(size, align)?;
+        Ok(RemoteAllocation::new(
+            self.clone(),
+            allocation,
+            RemoteAllocationBackend::Pages,
+        ))
+    }
+    /// Allocates executable memory for the given code, which is always placed in pages regardless of the backend.
+    /// The code is written and the instruction cache flushed right away, unless a code batch is active
+    /// (see [`RemoteBoxAllocator::code_batch`]), in which case both are deferred until the code is about to run.
+    pub fn alloc_and_copy_code(&self, code: &[u8]) -> Result<RemoteAllocation, io::Error> {
+        self.ensure_alive()?;
+        let allocation = self
+            .alloc_from_pages(code.len(), 1)
+            .map_err(|err| self.check_target(err))?;
+        if let Some(pending) = &mut *self.0.pending_code.borrow_mut() {
+            pending.push((allocation.as_raw_ptr() as usize, code.to_vec()));
+            return Ok(allocation);
+        }
+        allocation.write_bytes(code)?;
+        allocation
+            .memory()
+            .flush_instruction_cache()
+            .map_err(|err| self.check_target(err))?;
+        Ok(allocation)
+    }
+    /// Starts batching the writes of code allocated by [`RemoteBoxAllocator::alloc_and_copy_code`], so stubs placed next to
+    /// each other are written with a single `WriteProcessMemory` and `FlushInstructionCache` call. The batch ends once the
+    /// returned guard is finished or dropped. A batch started while another one is active joins it.
+    ///
+    /// Pending code is written before any call in the target, so it is always in place once it runs.
+    pub fn code_batch(&self) -> CodeBatch<'_> {
+        let mut pending = self.0.pending_code.borrow_mut();
+        let active = pending.is_none();
+        if active {
+            *pending = Some(Vec::new());
+        }
+        CodeBatch {
+            allocator: self,
+            active,
+        }
+    }
+    fn end_code_batch(&self) -> Result<(), io::Error> {
+        let result = self.write_pending_code();
+        *self.0.pending_code.borrow_mut() = None;
+        result
+    }
+    /// Writes the pending code of the active code batch, if any, coalescing adjacent allocations into single writes
+    /// and instruction cache flushes.
+    pub fn write_pending_code(&self) -> Result<(), io::Error> {
+        let mut pending = match &mut *self.0.pending_code.borrow_mut() {
+            Some(pending) if !pending.is_empty() => mem::take(pending),
+            _ => return Ok(()),
+        };
+        self.ensure_alive()?;
+        pending.sort_unstable_by_key(|(address, _)| *address);
+
+        let mut pending = pending.into_iter().peekable();
+        while let Some((start, mut code)) = pending.next() {
+            while let Some((_, next)) =
+                pending.next_if(|(address, _)| *address == start + code.len())
+            {
+                code.extend_from_slice(&next);
+            }
+            trace_event!(
+                trace,
+                address = start,
+                len = code.len(),
+                "writing batched code"
+            );
+            let memory = unsafe {
+                ProcessMemorySlice::from_raw_parts(start as *mut u8, code.len(), self.process())
+            };
+            memory
+                .write(0, &code)
+                .and_then(|()| memory.flush_instruction_cache())
+                .map_err(|err| self.check_target(err))?;
+        }
+        Ok(())
+    }
+    /// Returns executable memory containing the given location independent code, which is shared with all other users
+    /// of the same code and freed once the last of them is dropped.
+    pub fn alloc_shared_code(&self, code: &[u8]) -> Result<Rc<RemoteAllocation>, io::Error> {
+        if let Some(shared) = self
+            .0
+            .shared_code
+            .borrow()
+            .get(code)
+            .and_then(Weak::upgrade)
+        {
+            return Ok(shared);
+        }
+
+        let allocation = self.alloc_and_copy_code(code)?;
+        let allocation = Rc::new(allocation);
+        let mut shared_code = self.0.shared_code.borrow_mut();
+        shared_code.retain(|_, shared| shared.strong_count() != 0);
+        shared_code.insert(code.to_vec(), Rc::downgrade(&allocation));
+        Ok(allocation)
     }
     pub fn alloc_uninit<T: Copy>(&self) -> Result<RemoteBox<T>, io::Error> {
-        let allocation = self.alloc_raw(mem::size_of::<T>())?;
+        let allocation = self.alloc_raw_aligned(mem::size_of::<T>(), mem::align_of::<T>())?;
         Ok(unsafe { RemoteBox::new(allocation) })
     }
     pub fn alloc_uninit_for<T: Copy>(&self, value: &T) -> Result<RemoteBox<T>, io::Error> {
-        let allocation = self.alloc_raw(mem::size_of_val(value))?;
+        let allocation =
+            self.alloc_raw_aligned(mem::size_of_val(value), mem::align_of_val(value))?;
         Ok(unsafe { RemoteBox::new(allocation) })
     }
     pub fn alloc_and_copy<T: Copy>(&self, value: &T) -> Result<RemoteBox<T>, io::Error> {
@@ -47,20 +418,132 @@ impl RemoteBoxAllocator {
         Ok(b)
     }
     pub fn alloc_buf<T: Copy>(&self, len: usize) -> Result<RemoteAllocation, io::Error> {
-        let allocation = self.alloc_raw(len * mem::size_of::<T>())?;
+        let allocation = self.alloc_raw_aligned(len * mem::size_of::<T>(), mem::align_of::<T>())?;
         Ok(allocation)
     }
     pub fn alloc_and_copy_buf<T: Copy>(&self, buf: &[T]) -> Result<RemoteAllocation, io::Error> {
         let bytes = unsafe {
             slice::from_raw_parts(buf.as_ptr() as *const u8, std::mem::size_of_val(buf))
         };
-        let allocation = self.alloc_raw(bytes.len())?;
+        let allocation = self.alloc_raw_aligned(bytes.len(), mem::align_of::<T>())?;
         allocation.write_bytes(bytes)?;
         Ok(allocation)
     }
 
-    fn free(&self, allocation: &Allocation) {
-        self.0.allocator.borrow_mut().free(allocation);
+    pub fn placement(&self) -> AllocationPlacement {
+        self.0.allocator.borrow().placement().clone()
+    }
+
+    /// Sets where new pages are placed in the target, heap allocations are placed by the heap.
+    pub fn set_placement(&self, placement: AllocationPlacement) {
+        self.0.allocator.borrow_mut().set_placement(placement);
+    }
+
+    pub fn budget(&self) -> Option<usize> {
+        self.0.allocator.borrow().budget()
+    }
+
+    /// Sets the maximum number of bytes that may be committed in the target, after which allocations fail with [`RemoteAllocationBudgetExceeded`](crate::error::RemoteAllocationBudgetExceeded).
+    pub fn set_budget(&self, budget: Option<usize>) {
+        self.0.allocator.borrow_mut().set_budget(budget);
+    }
+
+    /// Returns the number of bytes committed in the target, i.e. the reserved pages and the live heap allocations.
+    pub fn count_committed_bytes(&self) -> usize {
+        self.0.allocator.borrow().count_committed_bytes()
+    }
+
+    /// Returns the number of allocations that were not dropped yet.
+    pub fn count_live_allocations(&self) -> usize {
+        self.0.live_allocations.get()
+    }
+
+    /// Returns the total size of the allocations that were not dropped yet in bytes.
+    pub fn count_live_allocation_bytes(&self) -> usize {
+        self.0.live_allocation_bytes.get()
+    }
+
+    /// Returns the number of bytes that can not be reused anymore, e.g. the memory of a worker thread that did not stop in time.
+    pub fn count_leaked_bytes(&self) -> usize {
+        self.0.leaked_bytes.get()
+    }
+
+    /// Returns the number of blocks of code shared between their users that are still in use.
+    pub fn count_shared_code(&self) -> usize {
+        self.0
+            .shared_code
+            .borrow()
+            .values()
+            .filter(|shared| shared.strong_count() != 0)
+            .count()
+    }
+
+    /// Releases the pages without live allocations back to the target process and returns the number of bytes released.
+    pub fn trim(&self) -> usize {
+        self.0.allocator.borrow_mut().trim()
+    }
+
+    fn free(&self, allocation: &Allocation, backend: RemoteAllocationBackend) {
+        self.0
+            .live_allocations
+            .set(self.0.live_allocations.get() - 1);
+        self.0
+            .live_allocation_bytes
+            .set(self.0.live_allocation_bytes.get() - allocation.len);
+        // code that was never written must not be written to the memory once it is reused.
+        if let Some(pending) = &mut *self.0.pending_code.borrow_mut() {
+            pending.retain(|(address, _)| *address != allocation.as_raw_ptr() as usize);
+        }
+        match backend {
+            RemoteAllocationBackend::Pages => self.0.allocator.borrow_mut().free(allocation),
+            RemoteAllocationBackend::ProcessHeap => {
+                self.0
+                    .allocator
+                    .borrow_mut()
+                    .remove_external_bytes(allocation.len);
+                // freeing a heap block runs a thread in the target, which is pointless once it exited.
+                if self.is_dead() || !self.0.process.is_alive() {
+                    self.0.dead.set(true);
+                    return;
+                }
+                self.0
+                    .heap_allocator
+                    .borrow_mut()
+                    .as_mut()
+                    .expect("heap allocation without heap allocator")
+                    .free(allocation);
+            }
+        }
+    }
+}
+
+/// A batch of code writes started by [`RemoteBoxAllocator::code_batch`], which writes the pending code when it ends.
+#[derive(Debug)]
+#[must_use = "the batch ends immediately if the guard is dropped"]
+pub struct CodeBatch<'a> {
+    allocator: &'a RemoteBoxAllocator,
+    // whether this guard started the batch and thus ends it, as opposed to having joined an active one.
+    active: bool,
+}
+
+impl CodeBatch<'_> {
+    /// Ends the batch, writing the pending code, unless it was joined from an outer batch which writes it instead.
+    pub fn finish(mut self) -> Result<(), io::Error> {
+        if mem::replace(&mut self.active, false) {
+            self.allocator.end_code_batch()
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Drop for CodeBatch<'_> {
+    fn drop(&mut self) {
+        if self.active {
+            if let Err(_err) = self.allocator.end_code_batch() {
+                trace_event!(warn, error = %_err, "failed to write batched code");
+            }
+        }
     }
 }
 
@@ -68,13 +551,24 @@ impl RemoteBoxAllocator {
 pub struct RemoteAllocation {
     allocation: Allocation,
     allocator: RemoteBoxAllocator,
+    backend: RemoteAllocationBackend,
 }
 
 impl RemoteAllocation {
-    const fn new(allocator: RemoteBoxAllocator, allocation: Allocation) -> Self {
+    fn new(
+        allocator: RemoteBoxAllocator,
+        allocation: Allocation,
+        backend: RemoteAllocationBackend,
+    ) -> Self {
+        let inner = &allocator.0;
+        inner.live_allocations.set(inner.live_allocations.get() + 1);
+        inner
+            .live_allocation_bytes
+            .set(inner.live_allocation_bytes.get() + allocation.len);
         Self {
             allocation,
             allocator,
+            backend,
         }
     }
 
@@ -82,6 +576,10 @@ impl RemoteAllocation {
         self.allocator.process()
     }
 
+    pub const fn allocator(&self) -> &RemoteBoxAllocator {
+        &self.allocator
+    }
+
     pub fn memory(&self) -> ProcessMemorySlice<'_> {
         unsafe {
             ProcessMemorySlice::from_raw_parts(
@@ -93,11 +591,17 @@ impl RemoteAllocation {
     }
 
     pub fn write_bytes(&self, value: &[u8]) -> Result<(), io::Error> {
-        self.memory().write(0, value)
+        self.allocator.ensure_alive()?;
+        self.memory()
+            .write(0, value)
+            .map_err(|err| self.allocator.check_target(err))
     }
 
     pub fn read_bytes(&self, buf: &mut [u8]) -> Result<(), io::Error> {
-        self.memory().read(0, buf)
+        self.allocator.ensure_alive()?;
+        self.memory()
+            .read(0, buf)
+            .map_err(|err| self.allocator.check_target(err))
     }
 
     pub const fn len(&self) -> usize {
@@ -115,11 +619,17 @@ impl RemoteAllocation {
 
 impl Drop for RemoteAllocation {
     fn drop(&mut self) {
-        self.allocator.free(&self.allocation);
+        self.allocator.free(&self.allocation, self.backend);
     }
 }
 
+/// A value of type `T` allocated in the memory space of a remote process, see [`Syringe::alloc_box`](crate::Syringe::alloc_box).
+///
+/// Unlike [`RemoteVec`](crate::process::memory::RemoteVec), the box does not borrow the process, but shares the process handle
+/// of the allocator it was allocated from, so it can be stored independently of the [`Syringe`](crate::Syringe).
+/// The allocation is freed once the box is dropped.
 #[derive(Debug)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
 pub struct RemoteBox<T: ?Sized> {
     allocation: RemoteAllocation,
     phantom: PhantomData<T>,
@@ -133,31 +643,189 @@ impl<T: ?Sized> RemoteBox<T> {
         }
     }
 
+    /// Returns the process the value is allocated in.
+    #[must_use]
     pub fn process(&self) -> BorrowedProcess<'_> {
         self.allocation.process()
     }
 
+    /// Returns the memory of the allocation backing the value.
+    #[must_use]
     pub fn memory(&self) -> ProcessMemorySlice<'_> {
         self.allocation.memory()
     }
 
+    /// Returns the address of the value in the remote process.
+    #[must_use]
     pub const fn as_raw_ptr(&self) -> *mut u8 {
         self.allocation.as_raw_ptr()
     }
 }
 
 impl<T: ?Sized + Copy> RemoteBox<T> {
+    /// Overwrites the value in the remote process with the given one.
     pub fn write(&self, value: &T) -> Result<(), io::Error> {
-        self.allocation.memory().write_struct(0, value)
+        self.allocation.allocator.ensure_alive()?;
+        self.allocation
+            .memory()
+            .write_struct(0, value)
+            .map_err(|err| self.allocation.allocator.check_target(err))
     }
 }
 
 impl<T: Sized + Copy> RemoteBox<T> {
+    /// Reads the current value from the remote process.
     pub fn read(&self) -> Result<T, io::Error> {
+        self.allocation.allocator.ensure_alive()?;
         unsafe { self.allocation.memory().read_struct(0) }
+            .map_err(|err| self.allocation.allocator.check_target(err))
     }
 
+    /// Returns the typed address of the value in the remote process.
+    #[must_use]
     pub const fn as_ptr(&self) -> NonNull<T> {
         self.allocation.as_ptr().cast()
     }
+
+    /// Writes the given value at the given byte offset into the boxed value, e.g. to update a single field
+    /// (see [`field_ptr`](Self::field_ptr)) without rewriting the entire value.
+    ///
+    /// # Panics
+    /// This function will panic if the written value would exceed the bounds of the boxed value.
+    pub fn write_at<U: Copy>(&self, offset: usize, value: &U) -> Result<(), io::Error> {
+        self.assert_in_bounds::<U>(offset);
+        self.allocation.memory().write_struct(offset, value)
+    }
+
+    /// Reads a value at the given byte offset into the boxed value.
+    ///
+    /// # Panics
+    /// This function will panic if the read value would exceed the bounds of the boxed value.
+    ///
+    /// # Safety
+    /// The bytes at the given offset must be a valid instance of `U`.
+    pub unsafe fn read_at<U: Copy>(&self, offset: usize) -> Result<U, io::Error> {
+        self.assert_in_bounds::<U>(offset);
+        unsafe { self.allocation.memory().read_struct(offset) }
+    }
+
+    /// Returns the remote address of the value at the given byte offset into the boxed value.
+    /// Combined with [`mem::offset_of`] this projects to the address of a field of the remote struct.
+    ///
+    /// # Panics
+    /// This function will panic if the value would exceed the bounds of the boxed value.
+    #[must_use]
+    pub fn field_ptr<U>(&self, offset: usize) -> NonNull<U> {
+        self.assert_in_bounds::<U>(offset);
+        unsafe { NonNull::new_unchecked(self.as_raw_ptr().wrapping_add(offset)).cast() }
+    }
+
+    fn assert_in_bounds<U>(&self, offset: usize) {
+        assert!(
+            offset
+                .checked_add(mem::size_of::<U>())
+                .is_some_and(|end| end <= mem::size_of::<T>()),
+            "field out of bounds"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(C)]
+    struct Pair {
+        a: u32,
+        b: u64,
+    }
+
+    #[test]
+    fn fields_are_written_and_read_in_place() {
+        let allocator = RemoteBoxAllocator::new(OwnedProcess::current());
+        let remote = allocator.alloc_and_copy(&Pair { a: 1, b: 2 }).unwrap();
+
+        remote.write_at(mem::offset_of!(Pair, b), &42u64).unwrap();
+        assert_eq!(remote.read().unwrap(), Pair { a: 1, b: 42 });
+        assert_eq!(
+            unsafe { remote.read_at::<u32>(mem::offset_of!(Pair, a)) }.unwrap(),
+            1
+        );
+
+        let b = remote.field_ptr::<u64>(mem::offset_of!(Pair, b));
+        assert_eq!(b.as_ptr() as usize, remote.as_raw_ptr() as usize + 8);
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(C, align(64))]
+    struct OverAligned([u8; 16]);
+
+    #[test]
+    fn over_aligned_values_are_aligned_in_the_target() {
+        for backend in [
+            RemoteAllocationBackend::Pages,
+            RemoteAllocationBackend::ProcessHeap,
+        ] {
+            let allocator = RemoteBoxAllocator::with_backend(OwnedProcess::current(), backend);
+            let _padding = allocator.alloc_and_copy(&0u8).unwrap();
+            let remote = allocator.alloc_and_copy(&OverAligned([7; 16])).unwrap();
+            assert_eq!(
+                remote.as_raw_ptr() as usize % mem::align_of::<OverAligned>(),
+                0
+            );
+            assert_eq!(remote.read().unwrap(), OverAligned([7; 16]));
+        }
+    }
+
+    #[test]
+    fn live_allocations_are_counted_until_dropped() {
+        let allocator = RemoteBoxAllocator::new(OwnedProcess::current());
+        let first = allocator.alloc_and_copy(&0u32).unwrap();
+        let second = allocator.alloc_buf::<u8>(10).unwrap();
+        assert_eq!(allocator.count_live_allocations(), 2);
+        assert_eq!(allocator.count_live_allocation_bytes(), 14);
+
+        drop(first);
+        assert_eq!(allocator.count_live_allocations(), 1);
+        assert_eq!(allocator.count_live_allocation_bytes(), 10);
+        drop(second);
+        assert_eq!(allocator.count_live_allocations(), 0);
+        assert_eq!(allocator.count_leaked_bytes(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "field out of bounds")]
+    fn writes_past_the_value_panic() {
+        let allocator = RemoteBoxAllocator::new(OwnedProcess::current());
+        let remote = allocator.alloc_and_copy(&0u32).unwrap();
+        remote.write_at(2, &0u32).unwrap();
+    }
+
+    #[test]
+    fn batched_code_is_written_when_the_batch_ends() {
+        let allocator = RemoteBoxAllocator::new(OwnedProcess::current());
+        let read = |allocation: &RemoteAllocation| {
+            let mut buf = vec![0; allocation.len()];
+            allocation.read_bytes(&mut buf).unwrap();
+            buf
+        };
+
+        let batch = allocator.code_batch();
+        let first = allocator.alloc_and_copy_code(&[0xC3; 4]).unwrap();
+        let nested = allocator.code_batch();
+        let second = allocator.alloc_and_copy_code(&[0xCC; 8]).unwrap();
+        let dropped = allocator.alloc_and_copy_code(&[0x90; 2]).unwrap();
+        drop(dropped);
+        nested.finish().unwrap();
+        assert_eq!(read(&first), [0; 4]);
+        assert_eq!(read(&second), [0; 8]);
+
+        batch.finish().unwrap();
+        assert_eq!(read(&first), [0xC3; 4]);
+        assert_eq!(read(&second), [0xCC; 8]);
+
+        let unbatched = allocator.alloc_and_copy_code(&[0x90; 2]).unwrap();
+        assert_eq!(read(&unbatched), [0x90; 2]);
+    }
 }
@@ -0,0 +1,372 @@
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    io,
+    marker::PhantomData,
+};
+
+use crate::process::{memory::ProcessMemorySlice, BorrowedProcess, Process};
+
+/// The width of pointers in a process, which differs from the one of the current process for WOW64 targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PointerWidth {
+    /// 4 byte pointers of x86 processes.
+    Bits32,
+    /// 8 byte pointers of x64 processes.
+    Bits64,
+}
+
+impl PointerWidth {
+    /// Returns the pointer width of the given process.
+    pub fn of(process: BorrowedProcess<'_>) -> Result<Self, io::Error> {
+        Ok(if process.is_x86()? {
+            Self::Bits32
+        } else {
+            Self::Bits64
+        })
+    }
+
+    /// Returns the pointer width of the current process.
+    #[must_use]
+    pub const fn native() -> Self {
+        if cfg!(target_pointer_width = "64") {
+            Self::Bits64
+        } else {
+            Self::Bits32
+        }
+    }
+
+    /// Returns the size of a pointer in bytes.
+    #[must_use]
+    pub const fn size(self) -> usize {
+        match self {
+            Self::Bits32 => 4,
+            Self::Bits64 => 8,
+        }
+    }
+
+    /// Returns whether the given address can be represented by a pointer of this width.
+    #[must_use]
+    pub const fn fits(self, address: u64) -> bool {
+        match self {
+            Self::Bits32 => address <= u32::MAX as u64,
+            Self::Bits64 => true,
+        }
+    }
+
+    /// Decodes an address of this width stored in little endian byte order at the start of the given bytes.
+    ///
+    /// # Panics
+    /// This function panics if fewer bytes than the size of a pointer are given.
+    #[must_use]
+    pub fn decode_address(self, bytes: &[u8]) -> u64 {
+        let mut buf = [0; 8];
+        buf[..self.size()].copy_from_slice(&bytes[..self.size()]);
+        u64::from_le_bytes(buf)
+    }
+
+    /// Reads an address of this width from the memory of the given process at the given address,
+    /// e.g. to follow a pointer whose width is only known at runtime.
+    pub fn read_address(
+        self,
+        process: BorrowedProcess<'_>,
+        address: usize,
+    ) -> Result<u64, io::Error> {
+        let mut buf = [0; 8];
+        unsafe { ProcessMemorySlice::from_raw_parts(address as *mut u8, self.size(), process) }
+            .read(0, &mut buf[..self.size()])?;
+        Ok(self.decode_address(&buf))
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A pointer width that is known at compile time, which determines the size of a [`RemotePtr`].
+pub trait RemotePtrWidth: sealed::Sealed + 'static {
+    /// The unsigned integer an address of this width is stored in.
+    type Address: Copy + Eq + Hash + Default + fmt::LowerHex + Into<u64> + TryFrom<u64> + 'static;
+    /// The width as a runtime value.
+    const WIDTH: PointerWidth;
+}
+
+/// The width of pointers of x86 processes, whose [`RemotePtr`]s are 4 bytes wide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Ptr32 {}
+
+/// The width of pointers of x64 processes, whose [`RemotePtr`]s are 8 bytes wide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Ptr64 {}
+
+/// The width of pointers of the current process.
+#[cfg(target_pointer_width = "64")]
+pub type NativePtr = Ptr64;
+/// The width of pointers of the current process.
+#[cfg(target_pointer_width = "32")]
+pub type NativePtr = Ptr32;
+
+impl sealed::Sealed for Ptr32 {}
+impl RemotePtrWidth for Ptr32 {
+    type Address = u32;
+    const WIDTH: PointerWidth = PointerWidth::Bits32;
+}
+
+impl sealed::Sealed for Ptr64 {}
+impl RemotePtrWidth for Ptr64 {
+    type Address = u64;
+    const WIDTH: PointerWidth = PointerWidth::Bits64;
+}
+
+/// A pointer to a `T` in the memory space of a (remote) process with the pointer width `W`, which may differ from the one
+/// of the current process.
+///
+/// A remote pointer has the size and alignment of a pointer of width `W`, i.e. 4 bytes for [`Ptr32`] and 8 bytes for [`Ptr64`],
+/// so `#[repr(C)]` structures containing remote pointers have the layout the target process expects and can be written to
+/// its memory as is. Structures for targets of either bitness can be declared generic over the width and instantiated with
+/// the one of the target, see [`PointerWidth::of`].
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::process::{
+///     memory::{PointerWidth, Ptr32, Ptr64, RemotePtr, RemotePtrWidth},
+///     OwnedProcess, Process,
+/// };
+///
+/// #[derive(Clone, Copy)]
+/// #[repr(C)]
+/// struct Buffer<W: RemotePtrWidth> {
+///     data: RemotePtr<u8, W>,
+///     len: u32,
+/// }
+///
+/// let process = OwnedProcess::find_first_by_name("ExampleProcess").unwrap();
+/// let address = 0x1234_5678;
+/// // reads 4 or 8 bytes depending on the bitness of the target.
+/// let data = match PointerWidth::of(process.borrowed()).unwrap() {
+///     PointerWidth::Bits32 => RemotePtr::<u8, Ptr32>::read(process.borrowed(), address).unwrap().address(),
+///     PointerWidth::Bits64 => RemotePtr::<u8, Ptr64>::read(process.borrowed(), address).unwrap().address(),
+/// };
+/// println!("data at {data:#x}");
+/// ```
+#[repr(transparent)]
+pub struct RemotePtr<T, W: RemotePtrWidth = NativePtr> {
+    address: W::Address,
+    phantom: PhantomData<*mut T>,
+}
+
+// SAFETY: the address is never dereferenced by this process.
+unsafe impl<T, W: RemotePtrWidth> Send for RemotePtr<T, W> {}
+// SAFETY: see the `Send` impl.
+unsafe impl<T, W: RemotePtrWidth> Sync for RemotePtr<T, W> {}
+
+impl<T, W: RemotePtrWidth> fmt::Debug for RemotePtr<T, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RemotePtr({:#x})", self.address)
+    }
+}
+
+impl<T, W: RemotePtrWidth> Clone for RemotePtr<T, W> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T, W: RemotePtrWidth> Copy for RemotePtr<T, W> {}
+
+impl<T, W: RemotePtrWidth> PartialEq for RemotePtr<T, W> {
+    fn eq(&self, other: &Self) -> bool {
+        self.address == other.address
+    }
+}
+impl<T, W: RemotePtrWidth> Eq for RemotePtr<T, W> {}
+
+impl<T, W: RemotePtrWidth> Hash for RemotePtr<T, W> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.address.hash(state);
+    }
+}
+
+impl<T, W: RemotePtrWidth> Default for RemotePtr<T, W> {
+    fn default() -> Self {
+        Self::null()
+    }
+}
+
+impl<T> From<*mut T> for RemotePtr<T> {
+    fn from(ptr: *mut T) -> Self {
+        Self::new(ptr as usize as _)
+    }
+}
+
+impl<T> From<*const T> for RemotePtr<T> {
+    fn from(ptr: *const T) -> Self {
+        Self::new(ptr as usize as _)
+    }
+}
+
+impl<T, W: RemotePtrWidth> RemotePtr<T, W> {
+    /// Creates a new pointer to the given address.
+    #[must_use]
+    pub const fn new(address: W::Address) -> Self {
+        Self {
+            address,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Creates a new pointer to the given address.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the address does not fit into a pointer of width `W`.
+    pub fn try_new(address: u64) -> Result<Self, io::Error> {
+        W::Address::try_from(address).map(Self::new).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "address {address:#x} does not fit into a {} byte pointer",
+                    W::WIDTH.size()
+                ),
+            )
+        })
+    }
+
+    /// Creates a null pointer.
+    #[must_use]
+    pub fn null() -> Self {
+        Self::new(W::Address::default())
+    }
+
+    /// Returns the address this pointer points to.
+    #[must_use]
+    pub fn address(self) -> u64 {
+        self.address.into()
+    }
+
+    /// Returns whether this pointer is null.
+    #[must_use]
+    pub fn is_null(self) -> bool {
+        self.address() == 0
+    }
+
+    /// Returns this pointer as a pointer of the current process.
+    ///
+    /// # Panics
+    /// This function panics if the address does not fit into a pointer of the current process.
+    #[must_use]
+    pub fn as_ptr(self) -> *mut T {
+        usize::try_from(self.address()).expect("remote address does not fit into a local pointer")
+            as *mut T
+    }
+
+    /// Casts this pointer to a pointer of another type.
+    #[must_use]
+    pub const fn cast<U>(self) -> RemotePtr<U, W> {
+        RemotePtr::new(self.address)
+    }
+
+    /// Returns this pointer offset by the given number of bytes, wrapping around on overflow of width `W`.
+    #[must_use]
+    pub fn wrapping_byte_add(self, count: u64) -> Self {
+        let address = self.address().wrapping_add(count) & (u64::MAX >> (64 - 8 * W::WIDTH.size()));
+        Self::try_new(address).expect("masked address fits into the pointer width")
+    }
+
+    /// Reads a pointer from the memory of the given process at the given address.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the pointers of the process do not have width `W`.
+    pub fn read(process: BorrowedProcess<'_>, address: usize) -> Result<Self, io::Error> {
+        check_width::<W>(process)?;
+        Self::try_new(W::WIDTH.read_address(process, address)?)
+    }
+
+    /// Writes this pointer to the memory of the given process at the given address.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the pointers of the process do not have width `W`.
+    pub fn write(self, process: BorrowedProcess<'_>, address: usize) -> Result<(), io::Error> {
+        check_width::<W>(process)?;
+        let bytes = self.address().to_le_bytes();
+        unsafe { ProcessMemorySlice::from_raw_parts(address as *mut u8, W::WIDTH.size(), process) }
+            .write(0, &bytes[..W::WIDTH.size()])
+    }
+}
+
+fn check_width<W: RemotePtrWidth>(process: BorrowedProcess<'_>) -> Result<(), io::Error> {
+    let width = PointerWidth::of(process)?;
+    if width == W::WIDTH {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "process has {} byte pointers instead of {} byte pointers",
+                width.size(),
+                W::WIDTH.size()
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem;
+
+    #[test]
+    fn size_follows_width() {
+        assert_eq!(mem::size_of::<RemotePtr<u8, Ptr32>>(), 4);
+        assert_eq!(mem::size_of::<RemotePtr<u8, Ptr64>>(), 8);
+        assert_eq!(mem::size_of::<RemotePtr<u8>>(), mem::size_of::<*mut u8>());
+    }
+
+    #[test]
+    fn addresses_must_fit_into_width() {
+        assert_eq!(
+            RemotePtr::<u8, Ptr32>::try_new(0x1234_5678)
+                .unwrap()
+                .address(),
+            0x1234_5678
+        );
+        let err = RemotePtr::<u8, Ptr32>::try_new(1 << 32).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            RemotePtr::<u8, Ptr32>::new(u32::MAX).wrapping_byte_add(2),
+            RemotePtr::new(1)
+        );
+    }
+
+    #[test]
+    fn decodes_with_runtime_width() {
+        let bytes = [0x78, 0x56, 0x34, 0x12, 0x01, 0, 0, 0];
+        assert_eq!(PointerWidth::Bits32.decode_address(&bytes), 0x1234_5678);
+        assert_eq!(PointerWidth::Bits64.decode_address(&bytes), 0x1_1234_5678);
+    }
+
+    #[test]
+    fn reads_and_writes_pointers_in_current_process() {
+        let process = BorrowedProcess::current();
+        let target = 42u32;
+        let mut slot = 0usize;
+        let slot_address = &mut slot as *mut usize as usize;
+
+        RemotePtr::from(&target as *const u32)
+            .write(process, slot_address)
+            .unwrap();
+        let ptr = RemotePtr::<u32>::read(process, slot_address).unwrap();
+        assert_eq!(ptr.as_ptr().cast_const(), &target as *const u32);
+        assert_eq!(PointerWidth::of(process).unwrap(), PointerWidth::native());
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn reading_with_another_width_fails() {
+        let slot = 0usize;
+        let err = RemotePtr::<u8, Ptr32>::read(
+            BorrowedProcess::current(),
+            &slot as *const usize as usize,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,259 @@
+use std::{io, mem};
+
+#[cfg(feature = "assembler")]
+use iced_x86::{code_asm::*, IcedError};
+
+use crate::process::memory::{RemoteAllocation, RemoteBoxAllocator};
+
+/// The status stored in the header of a [`RemoteResultBuf`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub(crate) enum RemoteResultStatus {
+    /// The stub did not write a result, e.g. because the thread was terminated.
+    Pending = 0,
+    /// The payload contains the value produced by the stub.
+    Ok = 1,
+    /// The payload contains a 32-bit error code, usually the result of `GetLastError`.
+    Error = 2,
+}
+
+/// The header that precedes the payload of a [`RemoteResultBuf`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+pub(crate) struct RemoteResultHeader {
+    pub status: u32,
+    pub len: u32,
+}
+
+/// A result read from a [`RemoteResultBuf`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RemoteResult<T> {
+    /// The stub produced a value.
+    Ok(T),
+    /// The stub reported an error code.
+    Err(u32),
+    /// The stub did not write a result.
+    Missing,
+}
+
+impl<T> RemoteResult<T> {
+    /// Converts the result into a [`Result`], mapping error codes to OS errors and missing results to an
+    /// error mentioning the given stub.
+    pub fn into_io_result(self, stub_name: &str) -> Result<T, io::Error> {
+        match self {
+            Self::Ok(value) => Ok(value),
+            Self::Err(code) => Err(io::Error::from_raw_os_error(code as _)),
+            Self::Missing => Err(io::Error::other(format!(
+                "{stub_name} stub did not write a result"
+            ))),
+        }
+    }
+}
+
+/// A buffer in the target process into which a stub writes its result, consisting of a [`RemoteResultHeader`]
+/// followed by the payload.
+///
+/// Routing results through this buffer instead of the thread exit code keeps values wider than 32 bits intact and
+/// separates errors reported by the stub from the values it produced. Stubs write the payload and its length before
+/// the status, so a stub that is interrupted leaves the result [`Missing`](RemoteResult::Missing).
+#[derive(Debug)]
+pub(crate) struct RemoteResultBuf {
+    allocation: RemoteAllocation,
+}
+
+impl RemoteResultBuf {
+    /// The offset of the payload from the start of the buffer.
+    pub const PAYLOAD_OFFSET: usize = mem::size_of::<RemoteResultHeader>();
+
+    /// The size of a payload holding a single word, which is enough for a value of the target pointer size
+    /// or an `edx:eax` pair.
+    pub const WORD_LEN: usize = mem::size_of::<u64>();
+
+    /// Allocates a new result buffer with room for the given number of payload bytes.
+    pub fn new(allocator: &RemoteBoxAllocator, capacity: usize) -> Result<Self, io::Error> {
+        let allocation = allocator.alloc_raw(Self::PAYLOAD_OFFSET + capacity)?;
+        let buf = Self { allocation };
+        buf.reset()?;
+        Ok(buf)
+    }
+
+    /// Allocates a new result buffer with room for a single word.
+    pub fn for_word(allocator: &RemoteBoxAllocator) -> Result<Self, io::Error> {
+        Self::new(allocator, Self::WORD_LEN)
+    }
+
+    /// Returns the address of the header in the target process.
+    pub const fn as_raw_ptr(&self) -> *mut u8 {
+        self.allocation.as_raw_ptr()
+    }
+
+    /// Returns the number of payload bytes the buffer can hold.
+    pub const fn capacity(&self) -> usize {
+        self.allocation.len() - Self::PAYLOAD_OFFSET
+    }
+
+    /// Marks the result as missing, so a result of a previous call cannot be mistaken for the result of the next one.
+    pub fn reset(&self) -> Result<(), io::Error> {
+        self.allocation.memory().write_struct(
+            0,
+            &RemoteResultHeader {
+                status: RemoteResultStatus::Pending as u32,
+                len: 0,
+            },
+        )
+    }
+
+    /// Reads the result into the given buffer, returning the length of the payload.
+    pub fn read_into(&self, buf: &mut [u8]) -> Result<RemoteResult<usize>, io::Error> {
+        let header = unsafe {
+            self.allocation
+                .memory()
+                .read_struct::<RemoteResultHeader>(0)
+        }?;
+        let len = header.len as usize;
+        if len > self.capacity() || len > buf.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("remote result of {len} bytes exceeds the result buffer"),
+            ));
+        }
+
+        match header.status {
+            status if status == RemoteResultStatus::Ok as u32 => {
+                self.allocation
+                    .memory()
+                    .read(Self::PAYLOAD_OFFSET, &mut buf[..len])?;
+                Ok(RemoteResult::Ok(len))
+            }
+            status if status == RemoteResultStatus::Error as u32 => {
+                let mut code = [0u8; mem::size_of::<u32>()];
+                self.allocation
+                    .memory()
+                    .read(Self::PAYLOAD_OFFSET, &mut code)?;
+                Ok(RemoteResult::Err(u32::from_le_bytes(code)))
+            }
+            _ => Ok(RemoteResult::Missing),
+        }
+    }
+
+    /// Reads a single word result, zero-extending payloads shorter than a word.
+    pub fn read_word(&self) -> Result<RemoteResult<u64>, io::Error> {
+        let mut buf = [0u8; Self::WORD_LEN];
+        Ok(match self.read_into(&mut buf)? {
+            RemoteResult::Ok(_) => RemoteResult::Ok(u64::from_le_bytes(buf)),
+            RemoteResult::Err(code) => RemoteResult::Err(code),
+            RemoteResult::Missing => RemoteResult::Missing,
+        })
+    }
+}
+
+#[cfg(feature = "assembler")]
+impl RemoteResultBuf {
+    /// Emits x86 code that stores `eax` (and `edx` for payloads longer than 4 bytes) as the result
+    /// into the single word result buffer at the given address. Clobbers `ecx`.
+    pub fn emit_write_x86_to(
+        asm: &mut CodeAssembler,
+        address: usize,
+        status: RemoteResultStatus,
+        len: u32,
+    ) -> Result<(), IcedError> {
+        assert_eq!(address as u32 as usize, address);
+
+        asm.mov(ecx, address as u32)?;
+        Self::emit_write_x86_at_ecx(asm, status, len)
+    }
+
+    /// Emits x86 code that stores `eax` (and `edx` for payloads longer than 4 bytes) as the result
+    /// into the single word result buffer whose address is in `ecx`.
+    pub fn emit_write_x86_at_ecx(
+        asm: &mut CodeAssembler,
+        status: RemoteResultStatus,
+        len: u32,
+    ) -> Result<(), IcedError> {
+        assert!(len as usize <= Self::WORD_LEN);
+
+        let payload = Self::PAYLOAD_OFFSET as u32;
+        asm.mov(dword_ptr(ecx + payload), eax)?;
+        if len > 4 {
+            asm.mov(dword_ptr(ecx + payload + 4), edx)?;
+        }
+        asm.mov(dword_ptr(ecx + 4), len)?;
+        asm.mov(dword_ptr(ecx), status as u32)?;
+        Ok(())
+    }
+
+    /// Emits x64 code that stores `rax` as the result into the single word result buffer at the given address.
+    /// Clobbers `r11`.
+    pub fn emit_write_x64_to(
+        asm: &mut CodeAssembler,
+        address: usize,
+        status: RemoteResultStatus,
+        len: u32,
+    ) -> Result<(), IcedError> {
+        asm.mov(r11, address as u64)?;
+        Self::emit_write_x64_at_r11(asm, status, len)
+    }
+
+    /// Emits x64 code that stores `rax` as the result into the single word result buffer whose address is in `r11`.
+    pub fn emit_write_x64_at_r11(
+        asm: &mut CodeAssembler,
+        status: RemoteResultStatus,
+        len: u32,
+    ) -> Result<(), IcedError> {
+        assert!(len as usize <= Self::WORD_LEN);
+
+        let payload = Self::PAYLOAD_OFFSET as u32;
+        asm.mov(qword_ptr(r11 + payload), rax)?;
+        asm.mov(dword_ptr(r11 + 4), len)?;
+        asm.mov(dword_ptr(r11), status as u32)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{OwnedProcess, Process};
+
+    #[test]
+    fn read_reports_status_of_written_result() {
+        let allocator = RemoteBoxAllocator::new(OwnedProcess::current());
+        let buf = RemoteResultBuf::for_word(&allocator).unwrap();
+        assert_eq!(buf.read_word().unwrap(), RemoteResult::Missing);
+
+        let write = |status: RemoteResultStatus, payload: &[u8]| {
+            let memory = buf.allocation.memory();
+            memory
+                .write(RemoteResultBuf::PAYLOAD_OFFSET, payload)
+                .unwrap();
+            memory
+                .write_struct(
+                    0,
+                    &RemoteResultHeader {
+                        status: status as u32,
+                        len: payload.len() as u32,
+                    },
+                )
+                .unwrap();
+        };
+
+        write(
+            RemoteResultStatus::Ok,
+            &0x1234_5678_9ABC_DEF0u64.to_le_bytes(),
+        );
+        assert_eq!(
+            buf.read_word().unwrap(),
+            RemoteResult::Ok(0x1234_5678_9ABC_DEF0)
+        );
+
+        buf.reset().unwrap();
+        write(RemoteResultStatus::Ok, &0xDEAD_BEEFu32.to_le_bytes());
+        assert_eq!(buf.read_word().unwrap(), RemoteResult::Ok(0xDEAD_BEEF));
+
+        write(RemoteResultStatus::Error, &5u32.to_le_bytes());
+        assert_eq!(buf.read_word().unwrap(), RemoteResult::Err(5));
+
+        buf.reset().unwrap();
+        assert_eq!(buf.read_word().unwrap(), RemoteResult::Missing);
+    }
+}
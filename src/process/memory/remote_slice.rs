@@ -0,0 +1,215 @@
+use std::{fmt, io, marker::PhantomData, mem, ops::RangeBounds, slice};
+
+use crate::{
+    process::{memory::ProcessMemorySlice, BorrowedProcess},
+    utils,
+};
+
+/// A typed view of an array of `T` in the memory space of a (remote) process.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+pub struct RemoteSlice<'a, T> {
+    memory: ProcessMemorySlice<'a>,
+    len: usize,
+    phantom: PhantomData<*mut [T]>,
+}
+
+impl<T> fmt::Debug for RemoteSlice<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteSlice")
+            .field("memory", &self.memory)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl<T> Clone for RemoteSlice<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for RemoteSlice<'_, T> {}
+
+impl<'a, T: Copy> RemoteSlice<'a, T> {
+    /// Constructs a new typed slice spanning as many elements of type `T` as fit into the given memory.
+    ///
+    /// # Panics
+    /// This function will panic if `T` is zero-sized.
+    ///
+    /// # Safety
+    /// The caller must ensure that the given memory is suitably aligned for `T` and that
+    /// every element contains a valid instance of type `T` whenever it is read.
+    #[must_use]
+    pub unsafe fn from_memory(memory: ProcessMemorySlice<'a>) -> Self {
+        assert_ne!(mem::size_of::<T>(), 0, "zero-sized types are not supported");
+        let len = memory.len() / mem::size_of::<T>();
+        Self {
+            memory: memory.slice(..len * mem::size_of::<T>()),
+            len,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Constructs a new typed slice from the given raw parts.
+    ///
+    /// # Panics
+    /// This function will panic if `T` is zero-sized.
+    ///
+    /// # Safety
+    /// The caller must ensure that the designated region of memory
+    /// - is valid for `len` elements of type `T`
+    /// - is suitably aligned for `T`
+    /// - contains valid instances of type `T` whenever they are read
+    /// - will live as long as the slice is used
+    #[must_use]
+    pub unsafe fn from_raw_parts(ptr: *mut T, len: usize, process: BorrowedProcess<'a>) -> Self {
+        unsafe {
+            Self::from_memory(ProcessMemorySlice::from_raw_parts(
+                ptr.cast(),
+                len * mem::size_of::<T>(),
+                process,
+            ))
+        }
+    }
+
+    /// Returns the number of elements in this slice.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether this slice contains no elements.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the process the slice is located in.
+    #[must_use]
+    pub const fn process(&self) -> BorrowedProcess<'a> {
+        self.memory.process()
+    }
+
+    /// Returns the untyped memory spanned by this slice.
+    #[must_use]
+    pub const fn memory(&self) -> ProcessMemorySlice<'a> {
+        self.memory
+    }
+
+    /// Returns a pointer to the first element of this slice.
+    ///
+    /// # Note
+    /// The returned pointer is only valid in the target process.
+    #[must_use]
+    pub const fn as_ptr(&self) -> *mut T {
+        self.memory.as_ptr().cast()
+    }
+
+    /// Reads the element at the given index.
+    ///
+    /// # Panics
+    /// This function will panic if the given index is out of bounds.
+    pub fn read_at(&self, index: usize) -> Result<T, io::Error> {
+        assert!(index < self.len, "index out of bounds");
+        unsafe { self.memory.read_struct(index * mem::size_of::<T>()) }
+    }
+
+    /// Writes the given value to the element at the given index.
+    ///
+    /// # Panics
+    /// This function will panic if the given index is out of bounds.
+    pub fn write_at(&self, index: usize, value: &T) -> Result<(), io::Error> {
+        assert!(index < self.len, "index out of bounds");
+        self.memory.write_struct(index * mem::size_of::<T>(), value)
+    }
+
+    /// Reads the elements starting at the given index into the given local buffer.
+    ///
+    /// # Panics
+    /// This function will panic if the given index plus the length of the given buffer exceeds this slice's length.
+    pub fn read_into(&self, index: usize, buf: &mut [T]) -> Result<(), io::Error> {
+        assert!(index + buf.len() <= self.len, "read out of bounds");
+        let bytes = unsafe {
+            slice::from_raw_parts_mut(buf.as_mut_ptr().cast::<u8>(), mem::size_of_val(buf))
+        };
+        self.memory.read(index * mem::size_of::<T>(), bytes)
+    }
+
+    /// Writes the elements of the given local buffer starting at the given index.
+    ///
+    /// # Panics
+    /// This function will panic if the given index plus the length of the given buffer exceeds this slice's length.
+    pub fn write_from(&self, index: usize, buf: &[T]) -> Result<(), io::Error> {
+        assert!(index + buf.len() <= self.len, "write out of bounds");
+        self.memory.write_struct(index * mem::size_of::<T>(), buf)
+    }
+
+    /// Reads all elements of this slice into a new [`Vec`].
+    pub fn read_to_vec(&self) -> Result<Vec<T>, io::Error> {
+        let mut vec = Vec::<T>::with_capacity(self.len);
+        let bytes = unsafe {
+            slice::from_raw_parts_mut(
+                vec.as_mut_ptr().cast::<u8>(),
+                self.len * mem::size_of::<T>(),
+            )
+        };
+        self.memory.read(0, bytes)?;
+        unsafe { vec.set_len(self.len) };
+        Ok(vec)
+    }
+
+    /// Returns a subslice of this slice.
+    ///
+    /// # Panics
+    /// This function will panic if the given range is out of bounds.
+    #[must_use]
+    pub fn slice(&self, bounds: impl RangeBounds<usize>) -> Self {
+        let range = utils::range_from_bounds(0, self.len, &bounds);
+        Self {
+            memory: self
+                .memory
+                .slice((range.start * mem::size_of::<T>())..(range.end * mem::size_of::<T>())),
+            len: range.len(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a> ProcessMemorySlice<'a> {
+    /// Returns a typed view of this buffer as an array of `T`.
+    /// Trailing bytes that do not form a full element are not part of the returned slice.
+    ///
+    /// # Panics
+    /// This function will panic if `T` is zero-sized.
+    ///
+    /// # Safety
+    /// The caller must ensure that this buffer is suitably aligned for `T` and that
+    /// every element contains a valid instance of type `T` whenever it is read.
+    #[must_use]
+    pub unsafe fn as_remote_slice<T: Copy>(&self) -> RemoteSlice<'a, T> {
+        unsafe { RemoteSlice::from_memory(*self) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::process::{memory::ProcessMemoryBuffer, BorrowedProcess, Process};
+
+    #[test]
+    fn element_access() {
+        let process = BorrowedProcess::current();
+        let buffer = ProcessMemoryBuffer::allocate_data(process, 4 * 8 + 3).unwrap();
+        let remote = unsafe { buffer.as_remote_slice::<u32>() };
+        assert_eq!(remote.len(), 8);
+
+        remote.write_from(0, &[0; 8]).unwrap();
+        remote.write_at(3, &42).unwrap();
+        assert_eq!(remote.read_at(3).unwrap(), 42);
+        assert_eq!(remote.read_to_vec().unwrap(), [0, 0, 0, 42, 0, 0, 0, 0]);
+
+        let sub = remote.slice(2..5);
+        assert_eq!(sub.len(), 3);
+        let mut buf = [0u32; 3];
+        sub.read_into(0, &mut buf).unwrap();
+        assert_eq!(buf, [0, 42, 0]);
+    }
+}
@@ -0,0 +1,314 @@
+use std::{cmp, fmt, io, marker::PhantomData, mem, ptr};
+
+use crate::process::{
+    memory::{ProcessMemoryBuffer, RemoteSlice},
+    BorrowedProcess,
+};
+
+/// A growable array of `T` allocated in the memory space of a (remote) process.
+///
+/// The backing allocation is moved to a larger one in the target process whenever an element is pushed beyond the current capacity.
+/// As a consequence, the pointer returned by [`RemoteVec::as_ptr`] is invalidated by any operation that grows the vector.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+pub struct RemoteVec<'a, T: Copy> {
+    process: BorrowedProcess<'a>,
+    buffer: Option<ProcessMemoryBuffer<'a>>,
+    len: usize,
+    phantom: PhantomData<*mut [T]>,
+}
+
+impl<T: Copy> fmt::Debug for RemoteVec<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteVec")
+            .field("process", &self.process)
+            .field("buffer", &self.buffer)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl<'a, T: Copy> RemoteVec<'a, T> {
+    /// Creates a new empty vector in the given process.
+    /// No memory is allocated until the first element is pushed.
+    ///
+    /// # Panics
+    /// This function will panic if `T` is zero-sized.
+    #[must_use]
+    pub fn new(process: BorrowedProcess<'a>) -> Self {
+        assert_ne!(mem::size_of::<T>(), 0, "zero-sized types are not supported");
+        Self {
+            process,
+            buffer: None,
+            len: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Creates a new empty vector in the given process with space for at least `capacity` elements.
+    ///
+    /// # Panics
+    /// This function will panic if `T` is zero-sized.
+    pub fn with_capacity(process: BorrowedProcess<'a>, capacity: usize) -> Result<Self, io::Error> {
+        let mut vec = Self::new(process);
+        vec.reserve_exact(capacity)?;
+        Ok(vec)
+    }
+
+    /// Creates a new vector in the given process containing a copy of the given elements.
+    ///
+    /// # Panics
+    /// This function will panic if `T` is zero-sized.
+    pub fn from_slice(process: BorrowedProcess<'a>, values: &[T]) -> Result<Self, io::Error> {
+        let mut vec = Self::with_capacity(process, values.len())?;
+        vec.extend_from_slice(values)?;
+        Ok(vec)
+    }
+
+    /// Returns the process the vector is allocated in.
+    #[must_use]
+    pub const fn process(&self) -> BorrowedProcess<'a> {
+        self.process
+    }
+
+    /// Returns the number of elements in this vector.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether this vector contains no elements.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of elements this vector can hold without reallocating.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.buffer
+            .as_ref()
+            .map_or(0, |buffer| buffer.len() / mem::size_of::<T>())
+    }
+
+    /// Returns a pointer to the first element of this vector or a null pointer if nothing has been allocated yet.
+    ///
+    /// # Note
+    /// The returned pointer is only valid in the target process and only until the vector is grown or dropped.
+    #[must_use]
+    pub fn as_ptr(&self) -> *mut T {
+        self.buffer
+            .as_ref()
+            .map_or(ptr::null_mut(), |buffer| buffer.as_ptr().cast())
+    }
+
+    /// Returns a typed view of the initialized elements of this vector.
+    #[must_use]
+    pub fn as_remote_slice(&self) -> RemoteSlice<'_, T> {
+        match &self.buffer {
+            Some(buffer) => unsafe { buffer.as_remote_slice::<T>() }.slice(..self.len),
+            None => unsafe { RemoteSlice::from_raw_parts(ptr::null_mut(), 0, self.process) },
+        }
+    }
+
+    /// Reserves capacity for at least `additional` more elements, possibly reallocating the vector in the target process.
+    pub fn reserve(&mut self, additional: usize) -> Result<(), io::Error> {
+        let required = self.required_capacity(additional)?;
+        if required <= self.capacity() {
+            return Ok(());
+        }
+        let new_capacity = cmp::max(cmp::max(self.capacity() * 2, required), 4);
+        self.reallocate(new_capacity)
+    }
+
+    /// Reserves capacity for exactly `additional` more elements, possibly reallocating the vector in the target process.
+    pub fn reserve_exact(&mut self, additional: usize) -> Result<(), io::Error> {
+        let required = self.required_capacity(additional)?;
+        if required <= self.capacity() {
+            return Ok(());
+        }
+        self.reallocate(required)
+    }
+
+    fn required_capacity(&self, additional: usize) -> Result<usize, io::Error> {
+        self.len
+            .checked_add(additional)
+            .filter(|required| required.checked_mul(mem::size_of::<T>()).is_some())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::OutOfMemory, "capacity overflow"))
+    }
+
+    fn reallocate(&mut self, new_capacity: usize) -> Result<(), io::Error> {
+        let new_buffer =
+            ProcessMemoryBuffer::allocate_data(self.process, new_capacity * mem::size_of::<T>())?;
+        if let Some(old_buffer) = &self.buffer {
+            let used = ..self.len * mem::size_of::<T>();
+            old_buffer
+                .slice(used)
+                .copy_to(&mut new_buffer.slice(used))?;
+        }
+        self.buffer = Some(new_buffer);
+        Ok(())
+    }
+
+    /// Appends an element to the end of this vector.
+    pub fn push(&mut self, value: &T) -> Result<(), io::Error> {
+        self.extend_from_slice(std::slice::from_ref(value))
+    }
+
+    /// Appends all elements of the given slice to the end of this vector.
+    pub fn extend_from_slice(&mut self, values: &[T]) -> Result<(), io::Error> {
+        if values.is_empty() {
+            return Ok(());
+        }
+        self.reserve(values.len())?;
+        let buffer = self.buffer.as_ref().unwrap();
+        buffer.write_struct(self.len * mem::size_of::<T>(), values)?;
+        self.len += values.len();
+        Ok(())
+    }
+
+    /// Removes the last element from this vector and returns it, or [`None`] if it is empty.
+    pub fn pop(&mut self) -> Result<Option<T>, io::Error> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let value = self.as_remote_slice().read_at(self.len - 1)?;
+        self.len -= 1;
+        Ok(Some(value))
+    }
+
+    /// Shortens this vector to the given length. Has no effect if the vector is already shorter.
+    pub fn truncate(&mut self, len: usize) {
+        self.len = cmp::min(self.len, len);
+    }
+
+    /// Removes all elements from this vector without releasing the remote allocation.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Reads all elements of this vector into a new local [`Vec`].
+    pub fn read_to_vec(&self) -> Result<Vec<T>, io::Error> {
+        self.as_remote_slice().read_to_vec()
+    }
+}
+
+/// A growable UTF-8 string allocated in the memory space of a (remote) process.
+///
+/// The contents are always followed by a nul terminator, so the pointer returned by
+/// [`RemoteString::as_ptr`] can be passed to remote procedures expecting a C string.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[derive(Debug)]
+pub struct RemoteString<'a> {
+    // contains the string followed by a nul terminator.
+    vec: RemoteVec<'a, u8>,
+}
+
+impl<'a> RemoteString<'a> {
+    /// Creates a new empty string in the given process.
+    pub fn new(process: BorrowedProcess<'a>) -> Result<Self, io::Error> {
+        Self::from_str(process, "")
+    }
+
+    /// Creates a new string in the given process containing a copy of the given string.
+    pub fn from_str(process: BorrowedProcess<'a>, s: &str) -> Result<Self, io::Error> {
+        let mut vec = RemoteVec::with_capacity(process, s.len() + 1)?;
+        vec.extend_from_slice(s.as_bytes())?;
+        vec.push(&0)?;
+        Ok(Self { vec })
+    }
+
+    /// Returns the process the string is allocated in.
+    #[must_use]
+    pub const fn process(&self) -> BorrowedProcess<'a> {
+        self.vec.process()
+    }
+
+    /// Returns the length of this string in bytes, excluding the nul terminator.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.vec.len() - 1
+    }
+
+    /// Returns whether this string is empty.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of bytes this string can hold without reallocating, excluding the nul terminator.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.vec.capacity() - 1
+    }
+
+    /// Returns a pointer to the first byte of this string.
+    ///
+    /// # Note
+    /// The returned pointer is only valid in the target process and only until the string is grown or dropped.
+    #[must_use]
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.vec.as_ptr()
+    }
+
+    /// Appends the given string to the end of this string.
+    pub fn push_str(&mut self, s: &str) -> Result<(), io::Error> {
+        self.vec.reserve(s.len())?;
+        self.vec.truncate(self.len());
+        self.vec.extend_from_slice(s.as_bytes())?;
+        self.vec.push(&0)
+    }
+
+    /// Appends the given character to the end of this string.
+    pub fn push(&mut self, c: char) -> Result<(), io::Error> {
+        self.push_str(c.encode_utf8(&mut [0; 4]))
+    }
+
+    /// Removes all contents of this string without releasing the remote allocation.
+    pub fn clear(&mut self) -> Result<(), io::Error> {
+        self.vec.clear();
+        self.vec.push(&0)
+    }
+
+    /// Reads the contents of this string into a new local [`String`].
+    pub fn read_to_string(&self) -> Result<String, io::Error> {
+        let mut bytes = self.vec.read_to_vec()?;
+        bytes.pop();
+        String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::Process;
+
+    #[test]
+    fn vec_grows_and_keeps_contents() {
+        let process = BorrowedProcess::current();
+        let mut vec = RemoteVec::<u64>::new(process);
+        assert_eq!(vec.capacity(), 0);
+        assert!(vec.as_ptr().is_null());
+
+        for i in 0..100 {
+            vec.push(&i).unwrap();
+        }
+        assert_eq!(vec.len(), 100);
+        assert!(vec.capacity() >= 100);
+        assert_eq!(vec.read_to_vec().unwrap(), (0..100).collect::<Vec<_>>());
+        assert_eq!(vec.pop().unwrap(), Some(99));
+        assert_eq!(vec.len(), 99);
+    }
+
+    #[test]
+    fn string_is_nul_terminated() {
+        let process = BorrowedProcess::current();
+        let mut s = RemoteString::from_str(process, "hello").unwrap();
+        s.push(' ').unwrap();
+        s.push_str("world").unwrap();
+        assert_eq!(s.len(), 11);
+        assert_eq!(s.read_to_string().unwrap(), "hello world");
+
+        let c_str = unsafe { std::ffi::CStr::from_ptr(s.as_ptr().cast()) };
+        assert_eq!(c_str.to_str().unwrap(), "hello world");
+    }
+}
@@ -0,0 +1,549 @@
+//! Byte pattern (array of bytes) and pointer scanning over the memory of a process.
+//!
+//! Scans over all regions of a process can take a while, so they have `_cancellable` variants that take a
+//! [`CancellationToken`], which can also limit the rate at which they read target memory.
+//!
+//! # Example
+//! ```no_run
+//! use dll_syringe::process::{memory::scanner::{self, Pattern}, OwnedProcess, Process};
+//!
+//! let process = OwnedProcess::find_first_by_name("ExampleProcess").unwrap();
+//! let pattern: Pattern = "48 8B ?? ?? 05".parse().unwrap();
+//! for address in scanner::scan_process(process.borrowed(), &pattern).unwrap() {
+//!     println!("match at {address:p}");
+//! }
+//! ```
+
+use std::{
+    cmp, fmt, io, mem,
+    num::NonZeroUsize,
+    ops::Range,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use crate::{
+    error::PatternParseError,
+    process::{
+        memory::{MemoryRegion, MemoryRegionIter, ProcessMemorySlice},
+        BorrowedProcess, Process, ProcessModule,
+    },
+    CancellationToken,
+};
+
+/// A byte pattern where each byte is either a concrete value or a wildcard matching any value.
+///
+/// Patterns can be parsed from strings of space-separated hex bytes, where `?` or `??` denotes a wildcard (e.g. `"48 8B ?? ?? 05"`).
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Pattern {
+    bytes: Vec<Option<u8>>,
+}
+
+impl Pattern {
+    /// Creates a new pattern from the given bytes where [`None`] denotes a wildcard.
+    ///
+    /// # Panics
+    /// This function will panic if the given pattern is empty.
+    #[must_use]
+    pub fn new(bytes: impl IntoIterator<Item = Option<u8>>) -> Self {
+        let bytes = bytes.into_iter().collect::<Vec<_>>();
+        assert!(!bytes.is_empty(), "pattern must not be empty");
+        Self { bytes }
+    }
+
+    /// Creates a new pattern matching exactly the given bytes.
+    ///
+    /// # Panics
+    /// This function will panic if the given slice is empty.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self::new(bytes.iter().copied().map(Some))
+    }
+
+    /// Returns the length of the pattern in bytes.
+    #[must_use]
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns the bytes of this pattern where [`None`] denotes a wildcard.
+    #[must_use]
+    pub fn bytes(&self) -> &[Option<u8>] {
+        &self.bytes
+    }
+
+    /// Returns whether the given bytes start with this pattern.
+    #[must_use]
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        bytes.len() >= self.len()
+            && self
+                .bytes
+                .iter()
+                .zip(bytes)
+                .all(|(expected, actual)| expected.is_none() || *expected == Some(*actual))
+    }
+
+    /// Returns an iterator over the offsets of all (possibly overlapping) occurrences of this pattern in the given bytes.
+    pub fn find_iter<'a>(&'a self, haystack: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        let end = (haystack.len() + 1).saturating_sub(self.len());
+        // only offsets where the first concrete byte of the pattern matches are fully compared.
+        let anchor = self.bytes.iter().position(Option::is_some);
+        (0..end).filter(move |&offset| {
+            anchor.is_none_or(|anchor| Some(haystack[offset + anchor]) == self.bytes[anchor])
+                && self.matches(&haystack[offset..])
+        })
+    }
+}
+
+impl fmt::Debug for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Pattern").field(&self.to_string()).finish()
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.bytes.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            match byte {
+                Some(byte) => write!(f, "{byte:02X}")?,
+                None => f.write_str("??")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = PatternParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = s
+            .split_whitespace()
+            .map(|token| match token {
+                "?" | "??" => Ok(None),
+                _ if token.len() == 2 => u8::from_str_radix(token, 16)
+                    .map(Some)
+                    .map_err(|_| PatternParseError::InvalidToken(token.to_string())),
+                _ => Err(PatternParseError::InvalidToken(token.to_string())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if bytes.is_empty() {
+            return Err(PatternParseError::Empty);
+        }
+        Ok(Self { bytes })
+    }
+}
+
+const SCAN_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+// A contiguous range of memory in the target process that is read and searched as a unit by a single worker.
+// Addresses are stored as integers so that chunks can be sent to worker threads.
+#[derive(Debug, Clone, Copy)]
+struct ScanChunk {
+    address: usize,
+    len: usize,
+}
+
+// Splits the given range into chunks of at most `SCAN_CHUNK_SIZE` bytes plus an overlap of the match length - 1,
+// so that matches spanning a chunk boundary are found exactly once.
+fn push_chunks(chunks: &mut Vec<ScanChunk>, address: usize, len: usize, match_len: usize) {
+    let overlap = match_len - 1;
+    let mut offset = 0;
+    while offset < len && len - offset >= match_len {
+        chunks.push(ScanChunk {
+            address: address + offset,
+            len: cmp::min(len - offset, SCAN_CHUNK_SIZE + overlap),
+        });
+        offset += SCAN_CHUNK_SIZE;
+    }
+}
+
+// Reads the given chunks on a pool of worker threads and collects the matches the given function finds in the data of each chunk,
+// which it is passed together with the address of the chunk.
+// If `skip_unreadable` is set, chunks that cannot be read are ignored, otherwise the first read error is returned.
+// If a cancellation token is given, it throttles the reads and the scan stops with an error once it is cancelled.
+fn scan_chunks<T: Ord + Send>(
+    process: BorrowedProcess<'_>,
+    chunks: &[ScanChunk],
+    skip_unreadable: bool,
+    cancellation: Option<&CancellationToken>,
+    find: impl Fn(usize, &[u8], &mut Vec<T>) + Sync,
+) -> Result<Vec<T>, io::Error> {
+    let worker_count = thread::available_parallelism()
+        .map_or(1, NonZeroUsize::get)
+        .clamp(1, cmp::max(chunks.len(), 1));
+    let next_chunk = AtomicUsize::new(0);
+
+    let worker = || -> Result<Vec<T>, io::Error> {
+        let mut matches = Vec::new();
+        let mut buf = Vec::new();
+        loop {
+            let index = next_chunk.fetch_add(1, Ordering::Relaxed);
+            let Some(chunk) = chunks.get(index) else {
+                break;
+            };
+            if let Some(Err(e)) = cancellation.map(|token| token.throttle(chunk.len)) {
+                next_chunk.store(chunks.len(), Ordering::Relaxed);
+                return Err(e);
+            }
+            // the buffer is reused without zeroing it, as every read overwrites the whole chunk.
+            buf.clear();
+            buf.reserve(chunk.len);
+            let memory = unsafe {
+                ProcessMemorySlice::from_raw_parts(chunk.address as *mut u8, chunk.len, process)
+            };
+            let data = match memory.read_into_uninit(0, &mut buf.spare_capacity_mut()[..chunk.len])
+            {
+                Ok(data) => data,
+                Err(_) if skip_unreadable => continue,
+                Err(e) => {
+                    // make the other workers stop early.
+                    next_chunk.store(chunks.len(), Ordering::Relaxed);
+                    return Err(e);
+                }
+            };
+            find(chunk.address, data, &mut matches);
+        }
+        Ok(matches)
+    };
+
+    let results = if worker_count == 1 {
+        vec![worker()]
+    } else {
+        thread::scope(|scope| {
+            let handles = (0..worker_count)
+                .map(|_| scope.spawn(worker))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        })
+    };
+
+    let mut matches = Vec::new();
+    for result in results {
+        matches.extend(result?);
+    }
+    matches.sort_unstable();
+    Ok(matches)
+}
+
+// Reads and searches the given chunks for the given pattern, see `scan_chunks`.
+fn scan_chunks_for_pattern(
+    process: BorrowedProcess<'_>,
+    pattern: &Pattern,
+    chunks: &[ScanChunk],
+    skip_unreadable: bool,
+    cancellation: Option<&CancellationToken>,
+) -> Result<Vec<*mut u8>, io::Error> {
+    let matches = scan_chunks(
+        process,
+        chunks,
+        skip_unreadable,
+        cancellation,
+        |address, data, matches| {
+            matches.extend(pattern.find_iter(data).map(|offset| address + offset));
+        },
+    )?;
+    Ok(matches
+        .into_iter()
+        .map(|address| address as *mut u8)
+        .collect())
+}
+
+/// Scans the given memory for the given pattern and returns the addresses of all matches in ascending order.
+///
+/// # Note
+/// Large buffers are read in chunks which are searched in parallel.
+pub fn scan(memory: ProcessMemorySlice<'_>, pattern: &Pattern) -> Result<Vec<*mut u8>, io::Error> {
+    if let Some(local) = memory.as_local_slice() {
+        return Ok(pattern
+            .find_iter(local)
+            .map(|offset| unsafe { memory.as_ptr().add(offset) })
+            .collect());
+    }
+
+    let mut chunks = Vec::new();
+    push_chunks(
+        &mut chunks,
+        memory.as_ptr() as usize,
+        memory.len(),
+        pattern.len(),
+    );
+    scan_chunks_for_pattern(memory.process(), pattern, &chunks, false, None)
+}
+
+// Splits the readable regions of the given process accepted by the given filter into chunks, see `push_chunks`.
+fn region_chunks(
+    process: BorrowedProcess<'_>,
+    match_len: usize,
+    mut filter: impl FnMut(&MemoryRegion) -> bool,
+) -> Result<Vec<ScanChunk>, io::Error> {
+    let mut chunks = Vec::new();
+    for region in MemoryRegionIter::new(process) {
+        let region = region?;
+        if region.is_readable() && filter(&region) {
+            push_chunks(
+                &mut chunks,
+                region.base() as usize,
+                region.size(),
+                match_len,
+            );
+        }
+    }
+    Ok(chunks)
+}
+
+fn scan_regions(
+    process: BorrowedProcess<'_>,
+    pattern: &Pattern,
+    cancellation: Option<&CancellationToken>,
+    filter: impl FnMut(&MemoryRegion) -> bool,
+) -> Result<Vec<*mut u8>, io::Error> {
+    if let Some(token) = cancellation {
+        token.check()?;
+    }
+    let chunks = region_chunks(process, pattern.len(), filter)?;
+    // the regions may have been freed or reprotected since they were queried, so failed reads are skipped.
+    scan_chunks_for_pattern(process, pattern, &chunks, true, cancellation)
+}
+
+/// Scans all committed and readable memory regions of the given process for the given pattern and returns the addresses of all matches in ascending order.
+///
+/// # Note
+/// Memory is read in large chunks which are searched in parallel.
+/// Chunks that cannot be read (e.g. because they were freed during the scan) are skipped.
+pub fn scan_process(
+    process: BorrowedProcess<'_>,
+    pattern: &Pattern,
+) -> Result<Vec<*mut u8>, io::Error> {
+    scan_regions(process, pattern, None, |_| true)
+}
+
+/// Scans all committed and readable memory regions of the given process for the given pattern like [`scan_process`], but stops
+/// with an error of kind [`io::ErrorKind::Interrupted`] once the given token is cancelled and reads no faster than its rate limit.
+pub fn scan_process_cancellable(
+    process: BorrowedProcess<'_>,
+    pattern: &Pattern,
+    cancellation: &CancellationToken,
+) -> Result<Vec<*mut u8>, io::Error> {
+    scan_regions(process, pattern, Some(cancellation), |_| true)
+}
+
+/// Scans the memory of the given module for the given pattern and returns the addresses of all matches in ascending order.
+///
+/// # Note
+/// Memory is read in large chunks which are searched in parallel.
+/// Sections of the module that cannot be read are skipped.
+pub fn scan_module<P: Process>(
+    module: &ProcessModule<P>,
+    pattern: &Pattern,
+) -> Result<Vec<*mut u8>, io::Error> {
+    scan_module_regions(module, pattern, None)
+}
+
+/// Scans the memory of the given module for the given pattern like [`scan_module`], but stops with an error of kind
+/// [`io::ErrorKind::Interrupted`] once the given token is cancelled and reads no faster than its rate limit.
+pub fn scan_module_cancellable<P: Process>(
+    module: &ProcessModule<P>,
+    pattern: &Pattern,
+    cancellation: &CancellationToken,
+) -> Result<Vec<*mut u8>, io::Error> {
+    scan_module_regions(module, pattern, Some(cancellation))
+}
+
+fn scan_module_regions<P: Process>(
+    module: &ProcessModule<P>,
+    pattern: &Pattern,
+    cancellation: Option<&CancellationToken>,
+) -> Result<Vec<*mut u8>, io::Error> {
+    let module_base = module.handle().cast::<u8>();
+    scan_regions(
+        module.process().borrowed(),
+        pattern,
+        cancellation,
+        |region| region.allocation_base() == module_base,
+    )
+}
+
+/// Scans all committed and readable memory regions of the given process for pointers to the given address and returns the
+/// addresses of all pointers in ascending order. This answers the question which objects refer to the object at the given address.
+///
+/// # Note
+/// Pointers are read with the pointer width of the process and are only found at addresses aligned to it, as compilers
+/// align pointer fields. Chunks that cannot be read (e.g. because they were freed during the scan) are skipped.
+pub fn find_references(
+    process: BorrowedProcess<'_>,
+    target: usize,
+) -> Result<Vec<*mut u8>, io::Error> {
+    Ok(find_references_in_range(process, target..target + 1)?
+        .into_iter()
+        .map(|(address, _)| address)
+        .collect())
+}
+
+/// Scans all committed and readable memory regions of the given process for pointers into the given range, e.g. to any field
+/// of an object, and returns the addresses of all pointers in ascending order together with the address each one points to.
+///
+/// # Note
+/// Pointers are read with the pointer width of the process and are only found at addresses aligned to it, as compilers
+/// align pointer fields. Chunks that cannot be read (e.g. because they were freed during the scan) are skipped.
+pub fn find_references_in_range(
+    process: BorrowedProcess<'_>,
+    targets: Range<usize>,
+) -> Result<Vec<(*mut u8, usize)>, io::Error> {
+    find_references_in_range_with(process, targets, None)
+}
+
+/// Scans all committed and readable memory regions of the given process for pointers into the given range like
+/// [`find_references_in_range`], but stops with an error of kind [`io::ErrorKind::Interrupted`] once the given token is
+/// cancelled and reads no faster than its rate limit.
+pub fn find_references_in_range_cancellable(
+    process: BorrowedProcess<'_>,
+    targets: Range<usize>,
+    cancellation: &CancellationToken,
+) -> Result<Vec<(*mut u8, usize)>, io::Error> {
+    find_references_in_range_with(process, targets, Some(cancellation))
+}
+
+fn find_references_in_range_with(
+    process: BorrowedProcess<'_>,
+    targets: Range<usize>,
+    cancellation: Option<&CancellationToken>,
+) -> Result<Vec<(*mut u8, usize)>, io::Error> {
+    if let Some(token) = cancellation {
+        token.check()?;
+    }
+    let pointer_size = if process.is_x86()? {
+        mem::size_of::<u32>()
+    } else {
+        mem::size_of::<u64>()
+    };
+    let chunks = region_chunks(process, pointer_size, |_| true)?;
+    let references = scan_chunks(
+        process,
+        &chunks,
+        true,
+        cancellation,
+        |address, data, references| {
+            references.extend(data.chunks_exact(pointer_size).enumerate().filter_map(
+                |(index, bytes)| {
+                    let mut value = [0; mem::size_of::<u64>()];
+                    value[..pointer_size].copy_from_slice(bytes);
+                    let value = u64::from_le_bytes(value) as usize;
+                    targets
+                        .contains(&value)
+                        .then_some((address + index * pointer_size, value))
+                },
+            ));
+        },
+    )?;
+    Ok(references
+        .into_iter()
+        .map(|(address, value)| (address as *mut u8, value))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::memory::ProcessMemoryBuffer;
+
+    #[test]
+    fn parse_pattern() {
+        let pattern: Pattern = "48 8b ?? ? 05".parse().unwrap();
+        assert_eq!(
+            pattern.bytes(),
+            &[Some(0x48), Some(0x8B), None, None, Some(0x05)]
+        );
+        assert_eq!(pattern.to_string(), "48 8B ?? ?? 05");
+
+        assert!(matches!(
+            "".parse::<Pattern>(),
+            Err(PatternParseError::Empty)
+        ));
+        assert!(matches!(
+            "48 8G".parse::<Pattern>(),
+            Err(PatternParseError::InvalidToken(token)) if token == "8G"
+        ));
+        assert!(matches!(
+            "488B".parse::<Pattern>(),
+            Err(PatternParseError::InvalidToken(_))
+        ));
+    }
+
+    #[test]
+    fn find_iter_with_wildcards() {
+        let pattern: Pattern = "AA ?? CC".parse().unwrap();
+        let haystack = [0xAA, 0xBB, 0xCC, 0xAA, 0x00, 0xCC, 0xAA, 0xCC];
+        assert_eq!(pattern.find_iter(&haystack).collect::<Vec<_>>(), [0, 3]);
+    }
+
+    #[test]
+    fn scan_finds_matches_across_chunks() {
+        let process = BorrowedProcess::current();
+        let buffer = ProcessMemoryBuffer::allocate_data(process, SCAN_CHUNK_SIZE + 16).unwrap();
+        let needle = [0xDE, 0xAD, 0xBE, 0xEF];
+        buffer.write(10, &needle).unwrap();
+        buffer.write(SCAN_CHUNK_SIZE - 2, &needle).unwrap();
+
+        let pattern: Pattern = "DE AD ?? EF".parse().unwrap();
+        let expected = [unsafe { buffer.as_ptr().add(10) }, unsafe {
+            buffer.as_ptr().add(SCAN_CHUNK_SIZE - 2)
+        }];
+        assert_eq!(scan(*buffer.as_slice(), &pattern).unwrap(), expected);
+
+        // force the chunked path even though the buffer is local.
+        let mut chunks = Vec::new();
+        push_chunks(
+            &mut chunks,
+            buffer.as_ptr() as usize,
+            buffer.len(),
+            pattern.len(),
+        );
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(
+            scan_chunks_for_pattern(process, &pattern, &chunks, false, None).unwrap(),
+            expected
+        );
+
+        let matches = scan_process(process, &pattern).unwrap();
+        assert!(matches.contains(&unsafe { buffer.as_ptr().add(10) }));
+
+        let token = CancellationToken::new();
+        token.cancel();
+        assert_eq!(
+            scan_process_cancellable(process, &pattern, &token)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::Interrupted
+        );
+    }
+
+    #[test]
+    fn find_references_finds_aligned_pointers() {
+        let process = BorrowedProcess::current();
+        let object = ProcessMemoryBuffer::allocate_data(process, 64).unwrap();
+        let target = object.as_ptr() as usize;
+        let buffer = ProcessMemoryBuffer::allocate_data(process, 64).unwrap();
+        buffer.write_struct(8, &target).unwrap();
+        buffer.write_struct(24, &(target + 16)).unwrap();
+        // unaligned pointers are not found.
+        buffer.write_struct(41, &target).unwrap();
+
+        let references = find_references(process, target).unwrap();
+        assert!(references.contains(&unsafe { buffer.as_ptr().add(8) }));
+        assert!(!references.contains(&unsafe { buffer.as_ptr().add(24) }));
+        assert!(!references.contains(&unsafe { buffer.as_ptr().add(41) }));
+
+        let references = find_references_in_range(process, target..target + 64).unwrap();
+        assert!(references.contains(&(unsafe { buffer.as_ptr().add(24) }, target + 16)));
+        assert!(references.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+}
@@ -0,0 +1,90 @@
+use std::{cmp, io, mem, slice};
+
+use crate::process::{
+    memory::{ProcessMemoryBuffer, ProcessMemorySlice},
+    BorrowedProcess,
+};
+
+// Reads elements starting at the given address until a zero element is found, without reading across
+// more page boundaries than necessary, so that strings at the very end of a region can be read.
+pub(crate) fn read_nul_terminated<T: Copy + Default + PartialEq>(
+    process: BorrowedProcess<'_>,
+    address: usize,
+    max_len: usize,
+) -> Result<Vec<T>, io::Error> {
+    let element_size = mem::size_of::<T>();
+    let page_size = ProcessMemoryBuffer::os_page_size();
+
+    let mut result = Vec::new();
+    let mut buf = Vec::new();
+    let mut current = address;
+    while result.len() <= max_len {
+        let to_page_end = page_size - current % page_size;
+        let chunk_len = cmp::max(to_page_end / element_size, 1).min(max_len - result.len() + 1);
+        buf.resize(chunk_len, T::default());
+        let memory = unsafe {
+            ProcessMemorySlice::from_raw_parts(
+                current as *mut u8,
+                chunk_len * element_size,
+                process,
+            )
+        };
+        memory.read(0, unsafe {
+            slice::from_raw_parts_mut(buf.as_mut_ptr().cast(), chunk_len * element_size)
+        })?;
+
+        if let Some(end) = buf.iter().position(|c| *c == T::default()) {
+            result.extend_from_slice(&buf[..end]);
+            return Ok(result);
+        }
+        result.extend_from_slice(&buf);
+        current += chunk_len * element_size;
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("no nul terminator found within {max_len} characters"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::Process;
+
+    #[test]
+    fn reads_until_terminator() {
+        let process = BorrowedProcess::current();
+        let data = b"hello\0world\0";
+        let address = data.as_ptr() as usize;
+        assert_eq!(
+            read_nul_terminated::<u8>(process, address, 5).unwrap(),
+            b"hello"
+        );
+        assert_eq!(
+            read_nul_terminated::<u8>(process, address, 100).unwrap(),
+            b"hello"
+        );
+        assert_eq!(
+            read_nul_terminated::<u8>(process, address, 4)
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn reads_across_page_boundary() {
+        let process = BorrowedProcess::current();
+        let page_size = ProcessMemoryBuffer::os_page_size();
+        let buffer = ProcessMemoryBuffer::allocate_data(process, page_size * 2).unwrap();
+        let wide = [u16::from(b'a'); 8];
+        buffer.write_struct(page_size - 8, &wide).unwrap();
+
+        let address = buffer.as_ptr() as usize + page_size - 8;
+        assert_eq!(
+            read_nul_terminated::<u16>(process, address, 16).unwrap(),
+            wide
+        );
+    }
+}
@@ -0,0 +1,451 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    io,
+    marker::PhantomData,
+    mem::{self, MaybeUninit},
+    ops::Range,
+    os::windows::io::AsRawHandle,
+    time::{Duration, Instant},
+};
+
+use winapi::{
+    shared::{
+        minwindef::{DWORD, FALSE},
+        ntstatus::{STATUS_WX86_BREAKPOINT, STATUS_WX86_SINGLE_STEP},
+        winerror::ERROR_SEM_TIMEOUT,
+    },
+    um::{
+        debugapi::{
+            ContinueDebugEvent, DebugActiveProcess, DebugActiveProcessStop, WaitForDebugEvent,
+        },
+        handleapi::CloseHandle,
+        minwinbase::{
+            CREATE_PROCESS_DEBUG_EVENT, DEBUG_EVENT, EXCEPTION_BREAKPOINT, EXCEPTION_DEBUG_EVENT,
+            EXCEPTION_GUARD_PAGE, EXCEPTION_SINGLE_STEP, EXIT_PROCESS_DEBUG_EVENT,
+            LOAD_DLL_DEBUG_EVENT,
+        },
+        processthreadsapi::{GetThreadContext, SetThreadContext},
+        winbase::{DebugSetProcessKillOnExit, INFINITE},
+        winnt::{CONTEXT, CONTEXT_CONTROL, DBG_CONTINUE, DBG_EXCEPTION_NOT_HANDLED, PAGE_GUARD},
+    },
+};
+
+use crate::{
+    process::{
+        memory::{MemoryProtection, MemoryRegionIter, ProcessMemoryBuffer, ProcessMemorySlice},
+        BorrowedProcess, Process, ProcessThread,
+    },
+    utils::trace_event,
+    CancellationToken,
+};
+
+/// The trap flag in `EFLAGS`, which makes the processor raise a single step exception after the next instruction.
+const TRAP_FLAG: DWORD = 0x100;
+
+/// The value of the first exception parameter of an access violation or guard page violation caused by a write.
+const WRITE_ACCESS: usize = 1;
+
+/// How long [`WriteWatcher::wait_for_write_cancellable`] waits for a debug event before checking for cancellation again.
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long [`WriteWatcher`] waits for threads that are single stepped over a write when it detaches.
+const DETACH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A write to watched memory reported by a [`WriteWatcher`].
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WriteAccess {
+    thread_id: u32,
+    instruction_address: usize,
+    address: usize,
+}
+
+impl WriteAccess {
+    /// Returns the id of the thread that performed the write.
+    #[must_use]
+    pub const fn thread_id(&self) -> u32 {
+        self.thread_id
+    }
+
+    /// Returns the address of the instruction that performed the write.
+    #[must_use]
+    pub const fn instruction_address(&self) -> usize {
+        self.instruction_address
+    }
+
+    /// Returns the address that was written to.
+    #[must_use]
+    pub const fn address(&self) -> usize {
+        self.address
+    }
+}
+
+/// Reports the instructions that write to selected memory of a process, i.e. a "find out what writes to this address" tool.
+///
+/// The watcher attaches to the process as a debugger and sets `PAGE_GUARD` on the pages containing the watched memory.
+/// Every access to one of these pages raises an exception, which is reported to the watcher if it was a write to watched memory.
+/// The faulting thread is then single stepped over the access, after which the page is guarded again.
+/// The original protections are restored and the watcher detaches when it is dropped.
+///
+/// # Note
+/// - The debug events of the process are delivered to the thread that attached, so the watcher has to be used on the thread that created it.
+/// - Only one debugger can be attached to a process at once.
+/// - Writes by the kernel (e.g. system calls filling a buffer) and by other processes (e.g. `WriteProcessMemory`) are not reported,
+///   and neither are writes by other threads to a page while a thread is being stepped over an access to it.
+/// - Every access to a guarded page is slowed down considerably, including reads and accesses to unwatched memory on the same page.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// use dll_syringe::process::{memory::WriteWatcher, OwnedProcess, Process};
+///
+/// let process = OwnedProcess::find_first_by_name("target_process").unwrap();
+/// let mut watcher = WriteWatcher::attach(process.borrowed()).unwrap();
+/// watcher.watch(0x1234_0000..0x1234_0004).unwrap();
+/// while let Some(access) = watcher.wait_for_write(Some(Duration::from_secs(10))).unwrap() {
+///     println!("{:#x} was written by the instruction at {:#x}", access.address(), access.instruction_address());
+/// }
+/// ```
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+#[derive(Debug)]
+pub struct WriteWatcher<'a> {
+    process: BorrowedProcess<'a>,
+    pid: u32,
+    is_wow64: bool,
+    ranges: Vec<Range<usize>>,
+    pages: BTreeSet<usize>,
+    page_size: usize,
+    /// The watched pages accessed by each thread that is currently single stepped over the access.
+    stepping_threads: HashMap<u32, Vec<usize>>,
+    is_armed: bool,
+    seen_initial_breakpoint: bool,
+    has_exited: bool,
+    // the debug events are delivered to the attaching thread only.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl<'a> WriteWatcher<'a> {
+    /// Attaches to the given process as a debugger without watching any memory yet.
+    pub fn attach(process: BorrowedProcess<'a>) -> Result<Self, io::Error> {
+        let pid = process.pid()?.get();
+        let is_wow64 = process.is_x86()? && BorrowedProcess::current().is_x64()?;
+        if unsafe { DebugActiveProcess(pid) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let watcher = Self {
+            process,
+            pid,
+            is_wow64,
+            ranges: Vec::new(),
+            pages: BTreeSet::new(),
+            page_size: ProcessMemoryBuffer::os_page_size(),
+            stepping_threads: HashMap::new(),
+            is_armed: true,
+            seen_initial_breakpoint: false,
+            has_exited: false,
+            _not_send: PhantomData,
+        };
+        // the target must survive the current thread exiting before the watcher detached.
+        if unsafe { DebugSetProcessKillOnExit(FALSE) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(watcher)
+    }
+
+    /// Returns the watched process.
+    #[must_use]
+    pub const fn process(&self) -> BorrowedProcess<'a> {
+        self.process
+    }
+
+    /// Returns the watched address ranges.
+    #[must_use]
+    pub fn ranges(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+
+    /// Starts watching the given address range for writes by guarding the pages containing it.
+    ///
+    /// # Note
+    /// Reading or writing a guarded page using [`ProcessMemorySlice`] or other `ReadProcessMemory` based methods fails
+    /// and removes the guard of the page.
+    pub fn watch(&mut self, range: Range<usize>) -> Result<(), io::Error> {
+        if range.is_empty() {
+            return Ok(());
+        }
+
+        let first_page = range.start - range.start % self.page_size;
+        for page in (first_page..range.end).step_by(self.page_size) {
+            if !self.pages.contains(&page) {
+                self.guard_page(page)?;
+                self.pages.insert(page);
+            }
+        }
+        trace_event!(
+            debug,
+            start = range.start,
+            end = range.end,
+            "watching remote memory for writes"
+        );
+        self.ranges.push(range);
+        Ok(())
+    }
+
+    /// Handles the debug events of the process until a write to watched memory occurs or the given timeout elapses,
+    /// returning [`None`] in the latter case. If the timeout is [`None`], this waits indefinitely.
+    ///
+    /// Returns an error if the process exits.
+    pub fn wait_for_write(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> Result<Option<WriteAccess>, io::Error> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if self.has_exited {
+                return Err(io::Error::other("the watched process exited"));
+            }
+            let Some(event) = Self::wait_for_debug_event(deadline)? else {
+                return Ok(None);
+            };
+            if let Some(access) = self.handle_debug_event(&event)? {
+                return Ok(Some(access));
+            }
+        }
+    }
+
+    /// Handles the debug events of the process until a write to watched memory occurs like [`WriteWatcher::wait_for_write`],
+    /// but without a timeout. Instead, this returns an error of kind [`io::ErrorKind::Interrupted`] once the given token is
+    /// cancelled, so the watcher can be stopped from another thread.
+    ///
+    /// Returns an error if the process exits.
+    pub fn wait_for_write_cancellable(
+        &mut self,
+        cancellation: &CancellationToken,
+    ) -> Result<WriteAccess, io::Error> {
+        loop {
+            cancellation.check()?;
+            if let Some(access) = self.wait_for_write(Some(CANCELLATION_POLL_INTERVAL))? {
+                return Ok(access);
+            }
+        }
+    }
+
+    fn wait_for_debug_event(deadline: Option<Instant>) -> Result<Option<DEBUG_EVENT>, io::Error> {
+        let timeout = match deadline {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                remaining.as_millis().min(u128::from(INFINITE - 1)) as DWORD
+            }
+            None => INFINITE,
+        };
+        let mut event = MaybeUninit::<DEBUG_EVENT>::uninit();
+        if unsafe { WaitForDebugEvent(event.as_mut_ptr(), timeout) } == 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() == Some(ERROR_SEM_TIMEOUT as i32) {
+                return Ok(None);
+            }
+            return Err(err);
+        }
+        Ok(Some(unsafe { event.assume_init() }))
+    }
+
+    fn handle_debug_event(
+        &mut self,
+        event: &DEBUG_EVENT,
+    ) -> Result<Option<WriteAccess>, io::Error> {
+        let mut continue_status = DBG_CONTINUE;
+        let mut access = None;
+        let mut result = Ok(());
+        match event.dwDebugEventCode {
+            CREATE_PROCESS_DEBUG_EVENT => {
+                close_file_handle(unsafe { event.u.CreateProcessInfo() }.hFile);
+            }
+            LOAD_DLL_DEBUG_EVENT => {
+                close_file_handle(unsafe { event.u.LoadDll() }.hFile);
+            }
+            EXCEPTION_DEBUG_EVENT => {
+                let record = &unsafe { event.u.Exception() }.ExceptionRecord;
+                match record.ExceptionCode {
+                    EXCEPTION_GUARD_PAGE => {
+                        let kind = record.ExceptionInformation[0];
+                        let address = record.ExceptionInformation[1];
+                        let page = address - address % self.page_size;
+                        if self.pages.contains(&page) {
+                            // the guard of the page is removed by the exception, so the thread is stepped over the access
+                            // before guarding the page again.
+                            if self.is_armed {
+                                result = self.step_over_access(event.dwThreadId, page);
+                            }
+                            if kind == WRITE_ACCESS && self.is_watched(address) {
+                                access = Some(WriteAccess {
+                                    thread_id: event.dwThreadId,
+                                    instruction_address: record.ExceptionAddress as usize,
+                                    address,
+                                });
+                            }
+                        } else {
+                            continue_status = DBG_EXCEPTION_NOT_HANDLED;
+                        }
+                    }
+                    code if code == EXCEPTION_SINGLE_STEP
+                        || code == STATUS_WX86_SINGLE_STEP as DWORD =>
+                    {
+                        match self.stepping_threads.remove(&event.dwThreadId) {
+                            Some(pages) if self.is_armed => {
+                                result =
+                                    pages.into_iter().try_for_each(|page| self.guard_page(page));
+                            }
+                            Some(_) => {}
+                            None => continue_status = DBG_EXCEPTION_NOT_HANDLED,
+                        }
+                    }
+                    code if !self.seen_initial_breakpoint
+                        && (code == EXCEPTION_BREAKPOINT
+                            || code == STATUS_WX86_BREAKPOINT as DWORD) =>
+                    {
+                        // raised by the thread the system creates when attaching.
+                        self.seen_initial_breakpoint = true;
+                    }
+                    _ => continue_status = DBG_EXCEPTION_NOT_HANDLED,
+                }
+            }
+            EXIT_PROCESS_DEBUG_EVENT => self.has_exited = true,
+            _ => {}
+        }
+
+        if unsafe { ContinueDebugEvent(event.dwProcessId, event.dwThreadId, continue_status) } == 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+        result?;
+        if let Some(_access) = access {
+            trace_event!(
+                debug,
+                address = _access.address,
+                instruction = _access.instruction_address,
+                "watched memory was written"
+            );
+        }
+        Ok(access)
+    }
+
+    fn is_watched(&self, address: usize) -> bool {
+        self.ranges.iter().any(|range| range.contains(&address))
+    }
+
+    fn step_over_access(&mut self, thread_id: u32, page: usize) -> Result<(), io::Error> {
+        let pages = self.stepping_threads.entry(thread_id).or_default();
+        if pages.is_empty() {
+            let thread = ProcessThread::from_tid(thread_id)?;
+            set_trap_flag(&thread, self.is_wow64)?;
+        }
+        pages.push(page);
+        Ok(())
+    }
+
+    fn page_memory(&self, page: usize) -> ProcessMemorySlice<'a> {
+        unsafe { ProcessMemorySlice::from_raw_parts(page as *mut u8, self.page_size, self.process) }
+    }
+
+    fn page_protection(&self, page: usize) -> Result<MemoryProtection, io::Error> {
+        MemoryRegionIter::new(self.process)
+            .query(page)?
+            .map(|region| region.protection())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "address is not in user space")
+            })
+    }
+
+    fn guard_page(&self, page: usize) -> Result<(), io::Error> {
+        // the protection is queried every time as writes to copy-on-write pages change it.
+        let protection = self.page_protection(page)?;
+        unsafe {
+            self.page_memory(page)
+                .set_protection(MemoryProtection(protection.0 | PAGE_GUARD))
+        }?;
+        Ok(())
+    }
+
+    fn unguard_page(&self, page: usize) -> Result<(), io::Error> {
+        let protection = self.page_protection(page)?;
+        if protection.0 & PAGE_GUARD != 0 {
+            unsafe {
+                self.page_memory(page)
+                    .set_protection(MemoryProtection(protection.0 & !PAGE_GUARD))
+            }?;
+        }
+        Ok(())
+    }
+
+    fn detach(&mut self) {
+        self.is_armed = false;
+        for &page in &self.pages {
+            if let Err(_err) = self.unguard_page(page) {
+                trace_event!(warn, page = page, error = %_err, "failed to remove page guard");
+            }
+        }
+
+        // a thread that raises a single step exception after the debugger detached would crash the process.
+        let deadline = Instant::now() + DETACH_TIMEOUT;
+        while !self.stepping_threads.is_empty() && !self.has_exited {
+            match Self::wait_for_debug_event(Some(deadline)) {
+                Ok(Some(event)) => {
+                    let _ = self.handle_debug_event(&event);
+                }
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        unsafe { DebugActiveProcessStop(self.pid) };
+    }
+}
+
+impl Drop for WriteWatcher<'_> {
+    fn drop(&mut self) {
+        self.detach();
+    }
+}
+
+/// A [`CONTEXT`] with the alignment required by `GetThreadContext`.
+#[repr(C, align(16))]
+struct AlignedContext(CONTEXT);
+
+fn set_trap_flag(thread: &ProcessThread, is_wow64: bool) -> Result<(), io::Error> {
+    #[cfg(target_pointer_width = "64")]
+    if is_wow64 {
+        use winapi::um::{
+            winbase::{Wow64GetThreadContext, Wow64SetThreadContext},
+            winnt::{WOW64_CONTEXT, WOW64_CONTEXT_CONTROL},
+        };
+
+        let mut context = unsafe { mem::zeroed::<WOW64_CONTEXT>() };
+        context.ContextFlags = WOW64_CONTEXT_CONTROL;
+        if unsafe { Wow64GetThreadContext(thread.as_raw_handle(), &mut context) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        context.EFlags |= TRAP_FLAG;
+        if unsafe { Wow64SetThreadContext(thread.as_raw_handle(), &context) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        return Ok(());
+    }
+    #[cfg(not(target_pointer_width = "64"))]
+    let _ = is_wow64;
+
+    let mut context = unsafe { mem::zeroed::<AlignedContext>() };
+    context.0.ContextFlags = CONTEXT_CONTROL;
+    if unsafe { GetThreadContext(thread.as_raw_handle(), &mut context.0) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    context.0.EFlags |= TRAP_FLAG;
+    if unsafe { SetThreadContext(thread.as_raw_handle(), &context.0) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn close_file_handle(handle: winapi::um::winnt::HANDLE) {
+    if !handle.is_null() {
+        unsafe { CloseHandle(handle) };
+    }
+}
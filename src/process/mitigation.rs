@@ -0,0 +1,173 @@
+use std::{io, mem, os::windows::prelude::AsRawHandle, sync::OnceLock};
+
+use cstr::cstr;
+use widestring::u16cstr;
+use winapi::{
+    shared::{
+        basetsd::SIZE_T,
+        minwindef::{BOOL, DWORD, FARPROC},
+        winerror::ERROR_INVALID_PARAMETER,
+    },
+    um::{
+        libloaderapi::{GetModuleHandleW, GetProcAddress},
+        winnt::{
+            ProcessControlFlowGuardPolicy, ProcessDynamicCodePolicy, ProcessImageLoadPolicy,
+            ProcessSignaturePolicy, ProcessSystemCallDisablePolicy, HANDLE,
+            PROCESS_MITIGATION_POLICY,
+        },
+    },
+};
+
+use crate::process::BorrowedProcess;
+
+/// The [mitigation policies](https://docs.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getprocessmitigationpolicy) of a process that are relevant for injection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MitigationPolicies {
+    dynamic_code: DWORD,
+    signature: DWORD,
+    image_load: DWORD,
+    control_flow_guard: DWORD,
+    system_call_disable: DWORD,
+}
+
+impl MitigationPolicies {
+    /// Returns whether the process prohibits the creation of executable memory (arbitrary code guard).
+    /// Injection requires executable memory for its stubs and is therefore not possible.
+    #[must_use]
+    pub const fn prohibits_dynamic_code(&self) -> bool {
+        self.dynamic_code & 1 != 0
+    }
+
+    /// Returns whether the process only loads modules signed by Microsoft.
+    #[must_use]
+    pub const fn microsoft_signed_only(&self) -> bool {
+        self.signature & 1 != 0
+    }
+
+    /// Returns whether the process only loads modules signed by the Microsoft Store.
+    #[must_use]
+    pub const fn store_signed_only(&self) -> bool {
+        self.signature & 0b10 != 0
+    }
+
+    /// Returns whether the process refuses to load modules from remote locations such as network shares.
+    #[must_use]
+    pub const fn no_remote_images(&self) -> bool {
+        self.image_load & 1 != 0
+    }
+
+    /// Returns whether the process refuses to load modules with a low mandatory label.
+    #[must_use]
+    pub const fn no_low_mandatory_label_images(&self) -> bool {
+        self.image_load & 0b10 != 0
+    }
+
+    /// Returns whether control flow guard is enabled for the process.
+    #[must_use]
+    pub const fn control_flow_guard(&self) -> bool {
+        self.control_flow_guard & 1 != 0
+    }
+
+    /// Returns whether the process is prevented from using win32k system calls.
+    #[must_use]
+    pub const fn disallows_win32k_system_calls(&self) -> bool {
+        self.system_call_disable & 1 != 0
+    }
+}
+
+type GetProcessMitigationPolicyFn = unsafe extern "system" fn(
+    HANDLE,
+    PROCESS_MITIGATION_POLICY,
+    *mut std::ffi::c_void,
+    SIZE_T,
+) -> BOOL;
+
+/// Returns `GetProcessMitigationPolicy` if the running windows supports mitigation policies.
+///
+/// It is resolved at runtime, as a static import would prevent the crate from loading on windows 7, which does not export it.
+fn get_process_mitigation_policy() -> Option<GetProcessMitigationPolicyFn> {
+    static FUNCTION: OnceLock<Option<GetProcessMitigationPolicyFn>> = OnceLock::new();
+    *FUNCTION.get_or_init(|| {
+        let kernel32 = unsafe { GetModuleHandleW(u16cstr!("kernel32.dll").as_ptr()) };
+        if kernel32.is_null() {
+            return None;
+        }
+        let function =
+            unsafe { GetProcAddress(kernel32, cstr!("GetProcessMitigationPolicy").as_ptr()) };
+        if function.is_null() {
+            return None;
+        }
+        let function: GetProcessMitigationPolicyFn =
+            unsafe { mem::transmute::<FARPROC, GetProcessMitigationPolicyFn>(function) };
+        Some(function)
+    })
+}
+
+/// Returns whether the running windows supports querying the mitigation policies of processes, which was added in windows 8.
+/// If it does not, all policies are reported as disabled, as they cannot be enabled either.
+#[must_use]
+pub fn mitigation_policies_supported() -> bool {
+    get_process_mitigation_policy().is_some()
+}
+
+fn query_policy_flags(
+    process: BorrowedProcess<'_>,
+    policy: PROCESS_MITIGATION_POLICY,
+) -> Result<DWORD, io::Error> {
+    let Some(get_process_mitigation_policy) = get_process_mitigation_policy() else {
+        return Ok(0);
+    };
+
+    // all queried policy structures consist of a single flags field.
+    let mut flags: DWORD = 0;
+    let result = unsafe {
+        get_process_mitigation_policy(
+            process.as_raw_handle(),
+            policy,
+            (&mut flags as *mut DWORD).cast(),
+            mem::size_of::<DWORD>(),
+        )
+    };
+    if result == 0 {
+        let err = io::Error::last_os_error();
+        // the policy is not supported on this version of windows.
+        if err.raw_os_error() == Some(ERROR_INVALID_PARAMETER as i32) {
+            return Ok(0);
+        }
+        return Err(err);
+    }
+    Ok(flags)
+}
+
+pub(crate) fn mitigation_policies(
+    process: BorrowedProcess<'_>,
+) -> Result<MitigationPolicies, io::Error> {
+    Ok(MitigationPolicies {
+        dynamic_code: query_policy_flags(process, ProcessDynamicCodePolicy)?,
+        signature: query_policy_flags(process, ProcessSignaturePolicy)?,
+        image_load: query_policy_flags(process, ProcessImageLoadPolicy)?,
+        control_flow_guard: query_policy_flags(process, ProcessControlFlowGuardPolicy)?,
+        system_call_disable: query_policy_flags(process, ProcessSystemCallDisablePolicy)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::{windows_version, Process, WindowsVersion};
+
+    #[test]
+    fn current_process_allows_dynamic_code() {
+        let policies = mitigation_policies(BorrowedProcess::current()).unwrap();
+        assert!(!policies.prohibits_dynamic_code());
+        assert!(!policies.microsoft_signed_only());
+    }
+
+    #[test]
+    fn mitigation_policies_are_supported_since_windows_8() {
+        assert_eq!(
+            mitigation_policies_supported(),
+            windows_version().is_at_least(WindowsVersion::WINDOWS_8)
+        );
+    }
+}
@@ -10,12 +10,113 @@ pub use borrowed::*;
 mod module;
 pub use module::*;
 
+mod module_export;
+pub use module_export::{ExportChange, ExportSnapshot, ExportSnapshotDiff, ModuleExport};
+
+mod module_path;
+pub use module_path::canonical_module_path;
+pub(crate) use module_path::ModulePathMatcher;
+
+mod module_resource;
+pub use module_resource::{ModuleResource, ResourceId};
+
+mod module_iter;
+pub use module_iter::ProcessModuleIter;
+
+mod module_snapshot;
+pub use module_snapshot::*;
+
+mod module_id;
+pub use module_id::ModuleId;
+
+mod module_symbol;
+pub use module_symbol::RemoteSymbol;
+
+mod version_info;
+pub use version_info::{ModuleVersion, ModuleVersionInfo};
+
+mod exit_watch;
+pub use exit_watch::ProcessExitWatch;
+
+mod retry_policy;
+pub use retry_policy::RetryPolicy;
+
+mod process_iter;
+pub use process_iter::*;
+
+mod name_match;
+pub(crate) use name_match::eq_ignore_case;
+pub use name_match::NameMatchOptions;
+
+mod process_selector;
+pub use process_selector::ProcessSelector;
+
+mod process_watcher;
+pub use process_watcher::ProcessWatcher;
+
+mod process_id;
+pub use process_id::*;
+
+mod spawn;
+pub use spawn::*;
+
+mod job;
+pub use job::{JobObject, JOB_ASSIGN_ACCESS};
+
+mod thread;
+pub use thread::{
+    ProcessThread, RemoteThreadCreationMethod, RemoteThreadOptions, RemoteThreadResult,
+    SecurityDescriptor, ThreadPriority, THREAD_ACCESS,
+};
+
+mod teb;
+pub use teb::StackSnapshot;
+
+mod acl;
+pub use acl::grant_app_container_access;
+#[cfg(feature = "syringe")]
+pub(crate) use acl::has_low_mandatory_label;
+
+mod mitigation;
+pub use mitigation::{mitigation_policies_supported, MitigationPolicies};
+
+mod protection_level;
+pub use protection_level::{ProtectionKind, ProtectionLevel, ProtectionSigner};
+
+mod token;
+pub use token::{enable_debug_privilege, IntegrityLevel};
+
+mod wine;
+pub use wine::{is_wine, wine_version};
+
+mod windows_version;
+pub use windows_version::{windows_version, WindowsVersion};
+
+mod capabilities;
+pub use capabilities::{Capabilities, CapabilityProbe, InjectionBlocker};
+
+#[cfg(any(feature = "windows-sys", feature = "windows"))]
+mod interop;
+
+mod window;
+
+pub(crate) mod peb;
+
+mod api_set;
+#[cfg(feature = "syringe")]
+pub(crate) use api_set::ApiSetSchema;
+
+pub(crate) mod ntdll;
+
 #[cfg_attr(not(feature = "process-memory"), allow(dead_code))]
 #[cfg(feature = "process-memory")]
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
 /// Module containing utilities for dealing with memory of another process.
 pub mod memory;
-#[cfg_attr(not(feature = "process-memory"), allow(dead_code))]
+#[cfg_attr(not(feature = "process-memory"), allow(dead_code, unused_imports))]
 #[cfg(not(feature = "process-memory"))]
 /// Module containing utilities for dealing with memory of another process.
 pub(crate) mod memory;
+pub use memory::AllocationPlacement;
+#[cfg(feature = "syringe")]
+pub use memory::RemoteAllocationBackend;
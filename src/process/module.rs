@@ -6,11 +6,22 @@ use std::{
     ptr::NonNull,
 };
 
+#[cfg(feature = "process-memory")]
+use crate::process::memory::ProcessMemorySlice;
+use crate::process::{
+    api_set::ApiSetSchema,
+    is_x64_windows,
+    memory::{malformed, read_preferred_image_base, RemoteExport, RemoteImage},
+    system_windows_dir,
+};
 use crate::{
     error::{GetLocalProcedureAddressError, IoOrNulError},
     function::{FunctionPtr, RawFunctionPtr},
-    process::{BorrowedProcess, OwnedProcess, Process},
-    utils::{win_fill_path_buf_helper, FillPathBufResult},
+    process::{
+        BorrowedProcess, ExportSnapshot, ModuleExport, ModuleId, ModuleResource, ModuleVersionInfo,
+        OwnedProcess, Process, RemoteSymbol, ResourceId,
+    },
+    utils::{redirect_system_path, win_fill_path_buf_helper, FillPathBufResult},
 };
 use path_absolutize::Absolutize;
 use widestring::{U16CStr, U16CString};
@@ -22,7 +33,7 @@ use winapi::{
     um::{
         libloaderapi::{GetModuleFileNameW, GetModuleHandleW, GetProcAddress},
         memoryapi::VirtualQueryEx,
-        psapi::{GetModuleBaseNameW, GetModuleFileNameExW},
+        psapi::{GetModuleBaseNameW, GetModuleFileNameExW, GetModuleInformation, MODULEINFO},
         winnt::{MEMORY_BASIC_INFORMATION, PAGE_NOACCESS},
     },
 };
@@ -194,6 +205,114 @@ impl<P: Process> ProcessModule<P> {
         self.handle.as_ptr()
     }
 
+    /// Returns the base address of the module, which is the same as its handle.
+    #[must_use]
+    pub fn base(&self) -> *mut u8 {
+        self.handle().cast()
+    }
+
+    /// Returns the size of the module image in memory in bytes.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> Result<usize, io::Error> {
+        self.info().map(|info| info.SizeOfImage as usize)
+    }
+
+    /// Returns the address of the entry point of the module or [`None`] if it has none.
+    pub fn entry_point(&self) -> Result<Option<RawFunctionPtr>, io::Error> {
+        self.info().map(|info| {
+            NonNull::new(info.EntryPoint).map(|entry_point| entry_point.as_ptr().cast())
+        })
+    }
+
+    /// Returns the address the module prefers to be loaded at, as given in the headers of the file it was loaded from.
+    /// This is the base address static analysis tools like IDA or Ghidra use by default.
+    ///
+    /// # Note
+    /// The loader updates the headers of a relocated module in memory, so the preferred base is read from the file.
+    /// For modules of WOW64 processes the file is looked up in the same way the target process sees it, e.g. `System32` paths
+    /// refer to `SysWOW64`.
+    pub fn preferred_base(&self) -> Result<usize, io::Error> {
+        let preferred_base = read_preferred_image_base(&self.local_path()?)?;
+        usize::try_from(preferred_base)
+            .map_err(|_| malformed("preferred image base does not fit into a pointer"))
+    }
+
+    /// Returns the difference between the address the module is loaded at and its [preferred base](Self::preferred_base),
+    /// i.e. the value that was added to every absolute address in the module when it was relocated.
+    pub fn relocation_delta(&self) -> Result<isize, io::Error> {
+        Ok((self.base() as usize).wrapping_sub(self.preferred_base()?) as isize)
+    }
+
+    /// Translates the given relative virtual address (RVA) in the module to its address in the process.
+    #[must_use]
+    pub fn rva_to_remote(&self, rva: usize) -> usize {
+        self.base() as usize + rva
+    }
+
+    /// Translates the given address in the module as loaded at its [preferred base](Self::preferred_base), e.g. an address
+    /// shown by a static analysis tool, to the address in the process.
+    ///
+    /// If the module was rebased in the static analysis tool, subtract the base used there and use [`rva_to_remote`](Self::rva_to_remote) instead.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the address does not lie inside the module.
+    pub fn static_to_remote(&self, address: usize) -> Result<usize, io::Error> {
+        let preferred_base = self.preferred_base()?;
+        match address.checked_sub(preferred_base) {
+            Some(rva) if rva < self.len()? => Ok(self.rva_to_remote(rva)),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "address {address:#x} does not lie inside the module preferring base {preferred_base:#x}"
+                ),
+            )),
+        }
+    }
+
+    /// Returns the path of the file the module was loaded from as seen by the current process.
+    fn local_path(&self) -> Result<PathBuf, io::Error> {
+        let path = self.path()?;
+        if self.is_local() || !is_x64_windows()? {
+            return Ok(path);
+        }
+        let redirected = redirect_system_path(
+            &path,
+            &system_windows_dir()?,
+            self.process().runs_under_wow64()?,
+            BorrowedProcess::current().runs_under_wow64()?,
+        );
+        Ok(redirected.unwrap_or(path))
+    }
+
+    /// Returns the memory of the module image.
+    #[cfg(feature = "process-memory")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+    pub fn memory(&self) -> Result<ProcessMemorySlice<'_>, io::Error> {
+        let len = self.len()?;
+        Ok(
+            unsafe {
+                ProcessMemorySlice::from_raw_parts(self.base(), len, self.process.borrowed())
+            },
+        )
+    }
+
+    fn info(&self) -> Result<MODULEINFO, io::Error> {
+        let mut info = MaybeUninit::uninit();
+        let result = unsafe {
+            GetModuleInformation(
+                self.process.as_raw_handle(),
+                self.handle(),
+                info.as_mut_ptr(),
+                mem::size_of::<MODULEINFO>() as u32,
+            )
+        };
+        if result == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(unsafe { info.assume_init() })
+        }
+    }
+
     /// Returns the process this module is loaded in.
     #[must_use]
     pub fn process(&self) -> &P {
@@ -211,6 +330,11 @@ impl<P: Process> ProcessModule<P> {
         !self.is_local()
     }
 
+    /// Returns an identifier for this module that does not hold a handle to its process and can be stored or serialized.
+    pub fn id(&self) -> Result<ModuleId, io::Error> {
+        ModuleId::of(self)
+    }
+
     /// Returns the path that the module was loaded from.
     pub fn path(&self) -> Result<PathBuf, io::Error> {
         if self.is_local() {
@@ -295,6 +419,181 @@ impl<P: Process> ProcessModule<P> {
         }
     }
 
+    /// Returns the version information of the module, which is read from the file the module was loaded from.
+    /// Returns [`None`] if the module has no version resource.
+    pub fn version_info(&self) -> Result<Option<ModuleVersionInfo>, io::Error> {
+        ModuleVersionInfo::read_from_file(&self.path()?)
+    }
+
+    /// Returns the exports of this module ordered by their ordinals, which are read from its export directory.
+    pub fn exports(&self) -> Result<Vec<ModuleExport>, io::Error> {
+        let image = RemoteImage::new(self.borrowed())?;
+        Ok(image
+            .exports()?
+            .into_iter()
+            .map(|entry| {
+                let (address, forwarder) = match entry.export {
+                    RemoteExport::Rva(rva) => (Some((image.base() + rva) as RawFunctionPtr), None),
+                    RemoteExport::Forwarded(forwarder) => (None, Some(forwarder)),
+                };
+                ModuleExport {
+                    name: entry.name,
+                    ordinal: entry.ordinal,
+                    address,
+                    forwarder,
+                }
+            })
+            .collect())
+    }
+
+    /// Returns a snapshot of the exports of this module, which can be compared to a later one to detect changed exports.
+    /// See [`ExportSnapshot::diff`].
+    pub fn export_snapshot(&self) -> Result<ExportSnapshot, io::Error> {
+        Ok(ExportSnapshot::new(self.handle(), self.exports()?))
+    }
+
+    /// Returns the resources of this module ordered by type, name and language, which are read from its resource directory
+    /// in the memory of its process. This also works for modules whose file is no longer available.
+    pub fn resources(&self) -> Result<Vec<ModuleResource>, io::Error> {
+        RemoteImage::new(self.borrowed())?.resources()
+    }
+
+    /// Returns the resource with the given type and name, e.g. `(ResourceId::RCDATA, 101)`, or [`None`] if there is no such resource.
+    /// If the resource exists in multiple languages, the first one in the resource directory is returned.
+    pub fn find_resource(
+        &self,
+        kind: impl Into<ResourceId>,
+        name: impl Into<ResourceId>,
+    ) -> Result<Option<ModuleResource>, io::Error> {
+        let (kind, name) = (kind.into(), name.into());
+        Ok(self
+            .resources()?
+            .into_iter()
+            .find(|resource| resource.kind.matches(&kind) && resource.name.matches(&name)))
+    }
+
+    /// Reads the data of the given resource of this module.
+    pub fn read_resource(&self, resource: &ModuleResource) -> Result<Vec<u8>, io::Error> {
+        RemoteImage::new(self.borrowed())?
+            .memory(resource.rva, resource.len)
+            .read_vec(0, resource.len)
+    }
+
+    /// Searches the exports of this module for one with the given name, which may be given in its decorated form
+    /// (e.g. `?Foo@@YAXXZ`), its complete undecorated form (e.g. `void __cdecl Foo(void)`) or as the undecorated name only (e.g. `Foo`).
+    /// See [`ModuleExport::matches_demangled`].
+    ///
+    /// If multiple overloads share an undecorated name, the one with the lowest ordinal is returned.
+    #[cfg(feature = "demangle")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "demangle")))]
+    pub fn find_export_by_demangled_name(
+        &self,
+        name: impl AsRef<str>,
+    ) -> Result<Option<ModuleExport>, io::Error> {
+        let name = name.as_ref();
+        Ok(self
+            .exports()?
+            .into_iter()
+            .find(|export| export.matches_demangled(name)))
+    }
+
+    /// Returns a pointer to the procedure with the given name from this module by reading its export directory,
+    /// without executing any code in the process of the module.
+    /// Returns [`None`] if the module has no such export.
+    ///
+    /// Forwarded exports are followed to the module they are forwarded to, which has to be loaded in the same process.
+    /// Forwarders to api set contracts are resolved using the api set schema of the process, which is only supported on Windows 10 and later.
+    pub fn get_procedure_address_from_exports(
+        &self,
+        proc_name: impl AsRef<str>,
+    ) -> Result<Option<RawFunctionPtr>, io::Error> {
+        let image = RemoteImage::new(self.borrowed())?;
+        let export = image.export(proc_name.as_ref())?;
+        self.resolve_export(image, export)
+    }
+
+    /// Returns a pointer to the procedure with the given ordinal from this module by reading its export directory,
+    /// without executing any code in the process of the module.
+    /// Returns [`None`] if the module has no such export.
+    ///
+    /// Forwarded exports are followed like in [`ProcessModule::get_procedure_address_from_exports`].
+    pub fn get_procedure_address_from_exports_by_ordinal(
+        &self,
+        ordinal: u16,
+    ) -> Result<Option<RawFunctionPtr>, io::Error> {
+        let image = RemoteImage::new(self.borrowed())?;
+        let export = image.export_by_ordinal(ordinal)?;
+        self.resolve_export(image, export)
+    }
+
+    /// Returns a typed pointer to the procedure with the given name from this module, which can be called remotely or
+    /// have its address taken, see [`RemoteSymbol`].
+    /// The procedure is looked up like in [`ProcessModule::get_procedure_address_from_exports`].
+    /// Returns [`None`] if the module has no such export.
+    ///
+    /// # Safety
+    /// The procedure must abide by the given signature.
+    pub unsafe fn get<F: FunctionPtr>(
+        &self,
+        name: impl AsRef<str>,
+    ) -> Result<Option<RemoteSymbol<'_, F>>, io::Error> {
+        Ok(self
+            .get_procedure_address_from_exports(name)?
+            .map(|ptr| RemoteSymbol::new(unsafe { F::from_ptr(ptr) }, self.borrowed())))
+    }
+
+    fn resolve_export<'a>(
+        &'a self,
+        mut image: RemoteImage<'a>,
+        mut export: Option<RemoteExport>,
+    ) -> Result<Option<RawFunctionPtr>, io::Error> {
+        const MAX_FORWARDS: usize = 16;
+
+        let process = self.process.borrowed();
+        let mut module = self.borrowed();
+        for _ in 0..MAX_FORWARDS {
+            let forwarder = match export {
+                None => return Ok(None),
+                Some(RemoteExport::Rva(rva)) => return Ok(Some((image.base() + rva) as _)),
+                Some(RemoteExport::Forwarded(forwarder)) => forwarder,
+            };
+            let (module_name, target) = forwarder
+                .rsplit_once('.')
+                .ok_or_else(|| malformed("invalid export forwarder"))?;
+
+            module = match process.find_module_by_name(module_name)? {
+                Some(module) => module,
+                None => {
+                    let importer = module.base_name()?;
+                    let host = ApiSetSchema::read(process)?.and_then(|schema| {
+                        schema.resolve(module_name, &importer.to_string_lossy())
+                    });
+                    match host {
+                        Some(host) => process.find_module_by_name(host)?,
+                        None => None,
+                    }
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::NotFound,
+                            format!("export forwarded to {forwarder}, which is not loaded"),
+                        )
+                    })?
+                }
+            };
+
+            image = RemoteImage::new(module)?;
+            export = match target.strip_prefix('#') {
+                Some(ordinal) => image.export_by_ordinal(
+                    ordinal
+                        .parse()
+                        .map_err(|_| malformed("invalid export forwarder"))?,
+                )?,
+                None => image.export(target)?,
+            };
+        }
+        Err(malformed("export forwarded too many times"))
+    }
+
     /// Returns a pointer to the procedure with the given name from this module.
     ///
     /// # Note
@@ -385,6 +684,19 @@ impl BorrowedProcessModule<'_> {
     }
 }
 
+impl OwnedProcessModule {
+    /// Tries to create a new instance for the same module with a duplicated process handle.
+    pub fn try_clone(&self) -> Result<Self, io::Error> {
+        self.borrowed().try_to_owned()
+    }
+
+    /// Returns the owned process of this module, consuming the module.
+    #[must_use]
+    pub fn into_process(self) -> OwnedProcess {
+        self.process
+    }
+}
+
 impl TryFrom<BorrowedProcessModule<'_>> for OwnedProcessModule {
     type Error = io::Error;
 
@@ -414,6 +726,94 @@ mod tests {
         assert!(!module.handle().is_null());
     }
 
+    #[test]
+    fn local_module_layout() {
+        let module = BorrowedProcessModule::find_local_by_name("kernel32.dll")
+            .unwrap()
+            .unwrap();
+        let len = module.len().unwrap();
+        assert!(len > 0);
+
+        let entry_point = module.entry_point().unwrap().unwrap() as usize;
+        let base = module.base() as usize;
+        assert!(entry_point > base && entry_point < base + len);
+    }
+
+    #[test]
+    fn procedure_address_from_exports_matches_get_proc_address() {
+        let kernel32 = BorrowedProcessModule::find_local_by_name("kernel32.dll")
+            .unwrap()
+            .unwrap();
+        // the latter is forwarded to ntdll.
+        for name in ["LoadLibraryW", "AcquireSRWLockExclusive"] {
+            assert_eq!(
+                kernel32.get_procedure_address_from_exports(name).unwrap(),
+                Some(kernel32.get_local_procedure_address(name).unwrap()),
+                "{name}"
+            );
+        }
+        assert_eq!(
+            kernel32
+                .get_procedure_address_from_exports("NotAnExport")
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn version_resource_is_read_from_memory() {
+        let kernel32 = BorrowedProcessModule::find_local_by_name("kernel32.dll")
+            .unwrap()
+            .unwrap();
+        let resources = kernel32.resources().unwrap();
+        assert!(resources
+            .iter()
+            .any(|resource| resource.kind() == &ResourceId::VERSION));
+
+        let version = kernel32
+            .find_resource(ResourceId::VERSION, 1)
+            .unwrap()
+            .unwrap();
+        let data = kernel32.read_resource(&version).unwrap();
+        assert_eq!(data.len(), version.len());
+        // a VS_VERSIONINFO structure starts with its length followed by the value length, the type and the utf-16 key.
+        let key = "VS_VERSION_INFO"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        assert_eq!(&data[6..6 + key.len()], key);
+        assert_eq!(
+            kernel32
+                .find_resource(ResourceId::RCDATA, "NOT_A_RESOURCE")
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn exports_include_named_and_forwarded_functions() {
+        let kernel32 = BorrowedProcessModule::find_local_by_name("kernel32.dll")
+            .unwrap()
+            .unwrap();
+        let exports = kernel32.exports().unwrap();
+        assert!(exports.windows(2).all(|w| w[0].ordinal() < w[1].ordinal()));
+
+        let load_library = exports
+            .iter()
+            .find(|export| export.name() == Some("LoadLibraryW"))
+            .unwrap();
+        assert_eq!(
+            load_library.address(),
+            Some(kernel32.get_local_procedure_address("LoadLibraryW").unwrap())
+        );
+        let acquire_lock = exports
+            .iter()
+            .find(|export| export.name() == Some("AcquireSRWLockExclusive"))
+            .unwrap();
+        assert_eq!(acquire_lock.address(), None);
+        assert!(acquire_lock.forwarder().unwrap().starts_with("NTDLL."));
+    }
+
     #[test]
     fn find_local_by_name_absent() {
         let result = BorrowedProcessModule::find_local_by_name("kernel33.dll");
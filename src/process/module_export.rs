@@ -0,0 +1,276 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
+
+use crate::{function::RawFunctionPtr, process::ModuleHandle};
+
+#[cfg(feature = "demangle")]
+use crate::utils::{demangle, demangle_name_only};
+
+/// An exported function or variable of a module, see [`ProcessModule::exports`](crate::process::ProcessModule::exports).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ModuleExport {
+    pub(crate) name: Option<String>,
+    pub(crate) ordinal: u16,
+    pub(crate) address: Option<RawFunctionPtr>,
+    pub(crate) forwarder: Option<String>,
+}
+
+unsafe impl Send for ModuleExport {}
+unsafe impl Sync for ModuleExport {}
+
+impl ModuleExport {
+    /// Returns the name of the export as stored in the module, which may be decorated (mangled),
+    /// or [`None`] if it is only exported by ordinal.
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the ordinal of the export.
+    #[must_use]
+    pub fn ordinal(&self) -> u16 {
+        self.ordinal
+    }
+
+    /// Returns the address of the export in the process of the module or [`None`] if it is forwarded to another module.
+    #[must_use]
+    pub fn address(&self) -> Option<RawFunctionPtr> {
+        self.address
+    }
+
+    /// Returns the forwarder string (e.g. `NTDLL.RtlAllocateHeap`) if the export is forwarded to another module.
+    #[must_use]
+    pub fn forwarder(&self) -> Option<&str> {
+        self.forwarder.as_deref()
+    }
+
+    /// Returns the undecorated form of the name of the export (e.g. `void __cdecl Foo(void)` for `?Foo@@YAXXZ`)
+    /// or [`None`] if the name is not decorated by the MSVC compiler.
+    #[cfg(feature = "demangle")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "demangle")))]
+    #[must_use]
+    pub fn demangled_name(&self) -> Option<String> {
+        self.name.as_deref().and_then(demangle)
+    }
+
+    /// Returns whether the export has the given name, either as stored in the module, in its complete undecorated form
+    /// (e.g. `void __cdecl Foo(void)`) or as the undecorated name only (e.g. `Foo`).
+    #[cfg(feature = "demangle")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "demangle")))]
+    #[must_use]
+    pub fn matches_demangled(&self, name: &str) -> bool {
+        let Some(export_name) = self.name.as_deref() else {
+            return false;
+        };
+        export_name == name
+            || demangle_name_only(export_name).is_some_and(|demangled| demangled == name)
+            || demangle(export_name).is_some_and(|demangled| demangled == name)
+    }
+}
+
+/// The exports of a module at a point in time, see [`ProcessModule::export_snapshot`](crate::process::ProcessModule::export_snapshot).
+///
+/// Comparing two snapshots of a module detects exports that were added, removed or redirected in the meantime,
+/// e.g. by a payload that patches its export directory at runtime or a module that was reloaded at the same address.
+#[derive(Debug, Clone)]
+pub struct ExportSnapshot {
+    module: ModuleHandle,
+    taken_at: Instant,
+    // ordered by ordinal.
+    exports: Vec<ModuleExport>,
+}
+
+// SAFETY: the module handle and the addresses of the exports are addresses in the target process, which are only
+// compared and never dereferenced in this process.
+unsafe impl Send for ExportSnapshot {}
+// SAFETY: see the `Send` impl, a snapshot is never mutated through a shared reference.
+unsafe impl Sync for ExportSnapshot {}
+
+/// An export whose address or forwarder differs between two [`ExportSnapshot`]s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportChange {
+    /// The export in the earlier snapshot.
+    pub before: ModuleExport,
+    /// The export in the later snapshot.
+    pub after: ModuleExport,
+}
+
+/// The changes between two [`ExportSnapshot`]s, see [`ExportSnapshot::diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportSnapshotDiff {
+    /// The module of the later snapshot.
+    pub module: ModuleHandle,
+    /// The exports that are only part of the later snapshot.
+    pub added: Vec<ModuleExport>,
+    /// The exports that are only part of the earlier snapshot.
+    pub removed: Vec<ModuleExport>,
+    /// The exports that are part of both snapshots but resolve to a different address or forwarder.
+    pub changed: Vec<ExportChange>,
+}
+
+// SAFETY: like an `ExportSnapshot`, a diff only holds addresses in the target process that are never dereferenced here.
+unsafe impl Send for ExportSnapshotDiff {}
+// SAFETY: see the `Send` impl, a diff has no interior mutability.
+unsafe impl Sync for ExportSnapshotDiff {}
+
+impl ExportSnapshotDiff {
+    /// Returns whether no exports were added, removed or changed between the two snapshots.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Returns the exports of the earlier snapshot whose addresses are no longer valid, i.e. the removed and changed ones.
+    pub fn stale(&self) -> impl Iterator<Item = &ModuleExport> {
+        self.removed
+            .iter()
+            .chain(self.changed.iter().map(|change| &change.before))
+    }
+}
+
+/// The key an export is matched by between two snapshots: its name, or its ordinal if it is only exported by ordinal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ExportKey<'a> {
+    Name(&'a str),
+    Ordinal(u16),
+}
+
+impl ModuleExport {
+    fn key(&self) -> ExportKey<'_> {
+        match &self.name {
+            Some(name) => ExportKey::Name(name),
+            None => ExportKey::Ordinal(self.ordinal),
+        }
+    }
+}
+
+impl ExportSnapshot {
+    pub(crate) fn new(module: ModuleHandle, exports: Vec<ModuleExport>) -> Self {
+        Self {
+            module,
+            taken_at: Instant::now(),
+            exports,
+        }
+    }
+
+    /// Returns the handle of the module this snapshot was taken of.
+    #[must_use]
+    pub fn module(&self) -> ModuleHandle {
+        self.module
+    }
+
+    /// Returns the point in time at which this snapshot was taken.
+    #[must_use]
+    pub fn taken_at(&self) -> Instant {
+        self.taken_at
+    }
+
+    /// Returns the exports in this snapshot ordered by their ordinals.
+    #[must_use]
+    pub fn exports(&self) -> &[ModuleExport] {
+        &self.exports
+    }
+
+    /// Returns the exports that were added, removed and changed between this snapshot and the given later one.
+    ///
+    /// Exports are matched by name, or by ordinal if they are only exported by ordinal, so an export that moved to another
+    /// ordinal but kept its name and address is not reported.
+    #[must_use]
+    pub fn diff(&self, later: &ExportSnapshot) -> ExportSnapshotDiff {
+        let earlier = self
+            .exports
+            .iter()
+            .map(|export| (export.key(), export))
+            .collect::<HashMap<_, _>>();
+        let later_keys = later
+            .exports
+            .iter()
+            .map(ModuleExport::key)
+            .collect::<HashSet<_>>();
+
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+        for export in &later.exports {
+            match earlier.get(&export.key()) {
+                None => added.push(export.clone()),
+                Some(before)
+                    if before.address != export.address || before.forwarder != export.forwarder =>
+                {
+                    changed.push(ExportChange {
+                        before: (*before).clone(),
+                        after: export.clone(),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+        let removed = self
+            .exports
+            .iter()
+            .filter(|export| !later_keys.contains(&export.key()))
+            .cloned()
+            .collect();
+
+        ExportSnapshotDiff {
+            module: later.module,
+            added,
+            removed,
+            changed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export(name: Option<&str>, ordinal: u16, address: usize) -> ModuleExport {
+        ModuleExport {
+            name: name.map(str::to_string),
+            ordinal,
+            address: Some(address as RawFunctionPtr),
+            forwarder: None,
+        }
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_changed_exports() {
+        let module = 0x1000_0000 as ModuleHandle;
+        let earlier = ExportSnapshot::new(
+            module,
+            vec![
+                export(Some("kept"), 1, 0x1000),
+                export(Some("patched"), 2, 0x2000),
+                export(Some("removed"), 3, 0x3000),
+                export(None, 4, 0x4000),
+            ],
+        );
+        let later = ExportSnapshot::new(
+            module,
+            vec![
+                export(Some("kept"), 1, 0x1000),
+                export(Some("patched"), 2, 0x5000),
+                export(None, 4, 0x4000),
+                export(Some("added"), 5, 0x6000),
+            ],
+        );
+
+        let diff = earlier.diff(&later);
+        assert_eq!(diff.added, [export(Some("added"), 5, 0x6000)]);
+        assert_eq!(diff.removed, [export(Some("removed"), 3, 0x3000)]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(
+            diff.changed[0].after.address(),
+            Some(0x5000 as RawFunctionPtr)
+        );
+        assert_eq!(
+            diff.stale()
+                .filter_map(ModuleExport::name)
+                .collect::<Vec<_>>(),
+            ["removed", "patched"]
+        );
+        assert!(earlier.diff(&earlier).is_empty());
+    }
+}
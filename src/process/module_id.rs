@@ -0,0 +1,128 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::process::{OwnedProcessModule, Process, ProcessId, ProcessModule};
+
+/// A stable identifier of a loaded module consisting of the [`ProcessId`] of its process, its path and its base address.
+///
+/// Unlike a [`ProcessModule`], it does not hold a handle to the process, so it can be stored or sent to another process,
+/// e.g. to record which payloads were injected where and to find them again after a restart.
+/// With the `serde` feature, both [`ModuleId`] and [`ProcessId`] implement `Serialize` and `Deserialize`.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::{process::{ModuleId, OwnedProcess}, Syringe};
+///
+/// let syringe = Syringe::for_process(OwnedProcess::find_first_by_name("target_process").unwrap());
+/// let payload = syringe.inject("injection_payload.dll").unwrap();
+/// let id = payload.id().unwrap();
+///
+/// // later, possibly in another process:
+/// let payload = id.open().unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleId {
+    process_id: ProcessId,
+    path: PathBuf,
+    base: usize,
+}
+
+impl ModuleId {
+    /// Returns the identifier of the given module.
+    pub fn of<P: Process>(module: &ProcessModule<P>) -> Result<Self, io::Error> {
+        Ok(Self {
+            process_id: module.process().id()?,
+            path: module.path()?,
+            base: module.handle() as usize,
+        })
+    }
+
+    /// Returns the identifier of the process the identified module was loaded in.
+    #[must_use]
+    pub const fn process_id(&self) -> ProcessId {
+        self.process_id
+    }
+
+    /// Returns the path of the identified module.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the base address of the identified module in its process.
+    #[must_use]
+    pub const fn base(&self) -> usize {
+        self.base
+    }
+
+    /// Returns whether the given module is the module identified by this identifier.
+    /// A module that was unloaded and loaded again at the same address is considered to be the same module.
+    pub fn matches<P: Process>(&self, module: &ProcessModule<P>) -> Result<bool, io::Error> {
+        Ok(module.handle() as usize == self.base
+            && self.process_id.matches(module.process())?
+            && module.path()? == self.path)
+    }
+
+    /// Opens the process of the identified module and returns the module if it is still loaded.
+    ///
+    /// # Errors
+    /// Returns an error with kind [`io::ErrorKind::NotFound`] if the process exited, its pid was reused or the module is no longer loaded at the same address.
+    pub fn open(&self) -> Result<OwnedProcessModule, io::Error> {
+        let process = self.process_id.open()?;
+        match process.find_module_by_path(&self.path)? {
+            Some(module) if module.handle() as usize == self.base => Ok(module),
+            _ => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "{} is no longer loaded at {:#x} in process {}",
+                    self.path.display(),
+                    self.base,
+                    self.process_id.pid()
+                ),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::OwnedProcess;
+
+    #[test]
+    fn id_opens_module_it_was_taken_from() {
+        let process = OwnedProcess::current();
+        let module = process
+            .find_module_by_name("kernel32.dll")
+            .unwrap()
+            .unwrap();
+        let id = module.id().unwrap();
+        assert!(id.matches(&module).unwrap());
+
+        let opened = id.open().unwrap();
+        assert_eq!(opened.handle(), module.handle());
+
+        let moved = ModuleId {
+            base: id.base() + 0x1000,
+            ..id
+        };
+        assert_eq!(moved.open().unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    #[cfg(feature = "bincode")]
+    #[cfg(feature = "serde")]
+    fn id_roundtrips_through_serde() {
+        let process = OwnedProcess::current();
+        let module = process.find_module_by_name("ntdll.dll").unwrap().unwrap();
+        let id = module.id().unwrap();
+
+        let bytes = bincode::serialize(&id).unwrap();
+        let deserialized = bincode::deserialize::<ModuleId>(&bytes).unwrap();
+        assert_eq!(deserialized, id);
+        assert!(deserialized.matches(&module).unwrap());
+    }
+}
@@ -0,0 +1,55 @@
+use std::{fmt, io, iter::FusedIterator};
+
+use crate::process::{
+    borrowed::ModuleHandleIter, BorrowedProcess, BorrowedProcessModule, ModuleListFilter,
+    ProcessModule,
+};
+
+/// An iterator over the modules loaded in a process, see [`Process::module_iter`](crate::process::Process::module_iter).
+///
+/// The module handles are read once when the iterator is created, which does not allocate for most processes.
+/// The modules borrow the process, so no handles are duplicated and their names and paths are only queried when requested.
+pub struct ProcessModuleIter<'a> {
+    process: BorrowedProcess<'a>,
+    handles: ModuleHandleIter,
+}
+
+impl<'a> ProcessModuleIter<'a> {
+    pub(crate) fn new(
+        process: BorrowedProcess<'a>,
+        filter: ModuleListFilter,
+    ) -> Result<Self, io::Error> {
+        Ok(Self {
+            handles: process.module_handle_iter(filter)?,
+            process,
+        })
+    }
+}
+
+impl<'a> Iterator for ProcessModuleIter<'a> {
+    type Item = BorrowedProcessModule<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.handles
+            .next()
+            .map(|handle| unsafe { ProcessModule::new_unchecked(handle, self.process) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.handles.len();
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for ProcessModuleIter<'_> {}
+
+impl FusedIterator for ProcessModuleIter<'_> {}
+
+impl fmt::Debug for ProcessModuleIter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProcessModuleIter")
+            .field("process", &self.process)
+            .field("remaining", &self.handles.len())
+            .finish()
+    }
+}
@@ -0,0 +1,309 @@
+use std::{
+    ffi::OsString,
+    fs, io,
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    path::{Component, Path, PathBuf, Prefix},
+};
+
+use path_absolutize::Absolutize;
+use widestring::U16CString;
+use winapi::um::fileapi::{GetLogicalDrives, QueryDosDeviceW};
+
+use crate::process::eq_ignore_case;
+
+const BACKSLASH: u16 = b'\\' as u16;
+
+/// Returns the canonical form of the given module path, which is the same for all names of a file that resolve to the
+/// same path, i.e. it is the same for short names, symbolic links, junctions and substituted drive letters.
+/// Hard links are distinct paths of the same file and keep distinct canonical paths.
+///
+/// Modules are matched by path (e.g. by [`Process::find_module_by_path`](crate::process::Process::find_module_by_path) and
+/// when injecting or ejecting a payload) by comparing their paths to the canonical form of the given path, falling back to
+/// comparing the files by identity, which also matches hard links.
+///
+/// The path is resolved as follows:
+/// - The default library extension `.dll` is appended if the extension is omitted and relative paths are resolved against
+///   the current directory.
+/// - Native device paths (e.g. `\Device\HarddiskVolume1\Windows\System32\kernel32.dll` as reported for mapped files)
+///   are converted to paths with a drive letter.
+/// - Short names, links and substituted drive letters are resolved and the case of each component is taken from the file system.
+/// - The returned path never has a verbatim (`\\?\`) prefix.
+///
+/// # Errors
+/// Returns an error if the file does not exist or the device of a native path is not mapped to a drive letter.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::process::canonical_module_path;
+///
+/// assert_eq!(
+///     canonical_module_path(r"c:\windows\system32\KERNEL32").unwrap(),
+///     canonical_module_path(r"\\?\C:\Windows\System32\kernel32.dll").unwrap(),
+/// );
+/// ```
+pub fn canonical_module_path(path: impl AsRef<Path>) -> Result<PathBuf, io::Error> {
+    let path = absolute_module_path(path.as_ref())?;
+    Ok(strip_verbatim_prefix(fs::canonicalize(path)?))
+}
+
+/// A module path that is resolved and opened once for repeated comparisons against the paths of loaded modules.
+#[derive(Debug)]
+pub(crate) struct ModulePathMatcher {
+    // the canonical path as UTF-16, as the paths are compared like the file system does.
+    path: Vec<u16>,
+    handle: Option<same_file::Handle>,
+}
+
+impl ModulePathMatcher {
+    /// Creates a new matcher for the given path, which is resolved using [`canonical_module_path`].
+    /// Paths of files that do not exist are only made absolute.
+    pub fn new(path: &Path) -> Self {
+        let path = canonical_module_path(path)
+            .or_else(|_| absolute_module_path(path))
+            .unwrap_or_else(|_| path.to_path_buf());
+        let handle = same_file::Handle::from_path(&path).ok();
+        Self {
+            path: path.as_os_str().encode_wide().collect(),
+            handle,
+        }
+    }
+
+    /// Returns whether the given module path refers to the same file as the path of this matcher.
+    ///
+    /// Module paths reported by the loader usually equal the canonical path of the matcher, ignoring case like the file
+    /// system does. If they differ, the files are compared by identity, so hard links and paths the loader reports
+    /// differently (e.g. through a substituted drive letter) are matched as well.
+    pub fn matches(&self, module_path: &Path) -> bool {
+        let module_path = to_dos_path(module_path).unwrap_or_else(|_| module_path.to_path_buf());
+        let wide_module_path = module_path.as_os_str().encode_wide().collect::<Vec<_>>();
+        if eq_ignore_case(&wide_module_path, &self.path) {
+            return true;
+        }
+        let Some(handle) = &self.handle else {
+            return false;
+        };
+        same_file::Handle::from_path(&module_path)
+            .is_ok_and(|module_handle| module_handle == *handle)
+    }
+}
+
+/// Converts the given path to an absolute dos path with the default library extension, without resolving links.
+fn absolute_module_path(path: &Path) -> Result<PathBuf, io::Error> {
+    let path = to_dos_path(path)?;
+    let path = if path.extension().is_none() {
+        path.with_extension("dll")
+    } else {
+        path
+    };
+    Ok(path.absolutize()?.into_owned())
+}
+
+/// Converts the given path to a path the file system apis of the current process understand without resolving it,
+/// i.e. native device paths are mapped to drive letters and verbatim prefixes are stripped.
+fn to_dos_path(path: &Path) -> Result<PathBuf, io::Error> {
+    // the path is handled as UTF-16, as paths reported for modules are not necessarily valid unicode.
+    let raw = path.as_os_str().encode_wide().collect::<Vec<_>>();
+    let device_path = strip_prefix_ignore_case(&raw, r"\\?\GLOBALROOT")
+        .or_else(|| strip_prefix_ignore_case(&raw, r"\\.\GLOBALROOT"))
+        .unwrap_or(&raw);
+    if strip_prefix_ignore_case(device_path, r"\Device\").is_some() {
+        return device_to_dos_path(device_path);
+    }
+    // `\??\` is the native form of a verbatim prefix.
+    if let Some(rest) = strip_prefix_ignore_case(&raw, r"\??\") {
+        return Ok(strip_verbatim_prefix(join_wide(r"\\?\", rest)));
+    }
+    Ok(strip_verbatim_prefix(path.to_path_buf()))
+}
+
+fn device_to_dos_path(device_path: &[u16]) -> Result<PathBuf, io::Error> {
+    // network shares are exposed through the multiple UNC provider.
+    if let Some(unc) = strip_prefix_ignore_case(device_path, r"\Device\Mup\") {
+        return Ok(join_wide(r"\\", unc));
+    }
+
+    let drives = unsafe { GetLogicalDrives() };
+    for (index, letter) in (b'A'..=b'Z').enumerate() {
+        if drives & (1 << index) == 0 {
+            continue;
+        }
+        let drive = format!("{}:", letter as char);
+        let Ok(device) = query_dos_device(&drive) else {
+            continue;
+        };
+        // substituted drives map to another path instead of a device and are resolved by canonicalization.
+        let device = device.encode_wide().collect::<Vec<_>>();
+        if strip_prefix_ignore_case(&device, r"\Device\").is_none() {
+            continue;
+        }
+        if let Some(rest) = strip_wide_prefix_ignore_case(device_path, &device) {
+            if rest.is_empty() || rest[0] == BACKSLASH {
+                let start = rest
+                    .iter()
+                    .position(|&c| c != BACKSLASH)
+                    .unwrap_or(rest.len());
+                return Ok(join_wide(&format!(r"{drive}\"), &rest[start..]));
+            }
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!(
+            "device of path {} is not mapped to a drive letter",
+            String::from_utf16_lossy(device_path)
+        ),
+    ))
+}
+
+/// Returns the first target of the given dos device name, e.g. `\Device\HarddiskVolume1` for `C:`.
+fn query_dos_device(name: &str) -> Result<OsString, io::Error> {
+    let name = U16CString::from_str(name)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let mut buf = vec![0u16; 1024];
+    let len = unsafe { QueryDosDeviceW(name.as_ptr(), buf.as_mut_ptr(), buf.len() as u32) };
+    if len == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // the buffer holds a list of nul terminated targets.
+    let end = buf.iter().position(|&c| c == 0).unwrap_or(len as usize);
+    Ok(OsString::from_wide(&buf[..end]))
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a [u16], prefix: &str) -> Option<&'a [u16]> {
+    let prefix = prefix.encode_utf16().collect::<Vec<_>>();
+    strip_wide_prefix_ignore_case(s, &prefix)
+}
+
+fn strip_wide_prefix_ignore_case<'a>(s: &'a [u16], prefix: &[u16]) -> Option<&'a [u16]> {
+    let head = s.get(..prefix.len())?;
+    eq_ignore_case(head, prefix).then(|| &s[prefix.len()..])
+}
+
+fn join_wide(head: &str, tail: &[u16]) -> PathBuf {
+    let mut path = OsString::from(head);
+    path.push(OsString::from_wide(tail));
+    PathBuf::from(path)
+}
+
+fn strip_verbatim_prefix(path: PathBuf) -> PathBuf {
+    let mut components = path.components();
+    let Some(Component::Prefix(prefix)) = components.next() else {
+        return path;
+    };
+    let rest = components.as_path();
+    match prefix.kind() {
+        Prefix::VerbatimDisk(disk) => PathBuf::from(format!("{}:\\", disk as char)).join(rest),
+        Prefix::VerbatimUNC(server, share) => {
+            let mut unc = OsString::from("\\\\");
+            unc.push(server);
+            unc.push("\\");
+            unc.push(share);
+            unc.push("\\");
+            PathBuf::from(unc).join(rest)
+        }
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_verbatim_prefixes() {
+        assert_eq!(
+            strip_verbatim_prefix(PathBuf::from(r"\\?\C:\Windows\System32\kernel32.dll")),
+            PathBuf::from(r"C:\Windows\System32\kernel32.dll")
+        );
+        assert_eq!(
+            strip_verbatim_prefix(PathBuf::from(r"\\?\UNC\server\share\payload.dll")),
+            PathBuf::from(r"\\server\share\payload.dll")
+        );
+        assert_eq!(
+            strip_verbatim_prefix(PathBuf::from(r"C:\payload.dll")),
+            PathBuf::from(r"C:\payload.dll")
+        );
+        assert_eq!(
+            to_dos_path(Path::new(r"\??\C:\payload.dll")).unwrap(),
+            PathBuf::from(r"C:\payload.dll")
+        );
+    }
+
+    #[test]
+    fn keeps_paths_that_are_not_valid_unicode() {
+        // an unpaired surrogate, which is not replaced by the conversion.
+        let mut wide = r"\??\C:\payload".encode_utf16().collect::<Vec<_>>();
+        wide.extend([
+            0xD800,
+            u16::from(b'.'),
+            u16::from(b'd'),
+            u16::from(b'l'),
+            u16::from(b'l'),
+        ]);
+        let expected = OsString::from_wide(&wide[4..]);
+        assert_eq!(
+            to_dos_path(Path::new(&OsString::from_wide(&wide))).unwrap(),
+            PathBuf::from(expected)
+        );
+    }
+
+    #[test]
+    fn canonicalizes_device_and_verbatim_paths() {
+        let system_dir = PathBuf::from(std::env::var_os("SystemRoot").unwrap()).join("System32");
+        let expected = canonical_module_path(system_dir.join("kernel32.dll")).unwrap();
+        assert!(expected.is_absolute());
+
+        let lowercase = system_dir.to_string_lossy().to_lowercase();
+        assert_eq!(
+            canonical_module_path(Path::new(&lowercase).join("KERNEL32")).unwrap(),
+            expected
+        );
+        assert_eq!(
+            canonical_module_path(format!(r"\\?\{}", expected.display())).unwrap(),
+            expected
+        );
+
+        let drive = &expected.to_string_lossy()[..2];
+        let device = query_dos_device(drive).unwrap();
+        let device_path = format!(
+            r"{}{}",
+            device.to_string_lossy(),
+            &expected.to_string_lossy()[2..]
+        );
+        assert_eq!(canonical_module_path(device_path).unwrap(), expected);
+    }
+
+    #[test]
+    fn matches_differently_cased_path() {
+        let system_dir = PathBuf::from(std::env::var_os("SystemRoot").unwrap()).join("System32");
+        let matcher = ModulePathMatcher::new(&system_dir.join("kernel32"));
+        assert!(matcher.matches(&system_dir.join("KERNEL32.DLL")));
+        assert!(!matcher.matches(&system_dir.join("ntdll.dll")));
+    }
+
+    #[test]
+    fn matches_differently_cased_non_ascii_path() {
+        let matcher = ModulePathMatcher::new(Path::new(r"C:\Ünïcödé\Payload.dll"));
+        assert!(matcher.matches(Path::new(r"c:\üNÏCÖDÉ\PAYLOAD.DLL")));
+        assert!(!matcher.matches(Path::new(r"C:\Unicode\Payload.dll")));
+    }
+
+    #[test]
+    fn matches_hard_link() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("original.dll");
+        let link = dir.path().join("link.dll");
+        std::fs::write(&original, b"MZ").unwrap();
+        std::fs::hard_link(&original, &link).unwrap();
+
+        // hard links keep distinct canonical paths and are only matched by identity.
+        assert_ne!(
+            canonical_module_path(&original).unwrap(),
+            canonical_module_path(&link).unwrap()
+        );
+        let matcher = ModulePathMatcher::new(&original);
+        assert!(matcher.matches(&link));
+        assert!(!matcher.matches(&dir.path().join("missing.dll")));
+    }
+}
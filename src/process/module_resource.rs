@@ -0,0 +1,116 @@
+use std::fmt;
+
+/// The type or name of a resource, which is either a number or a string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ResourceId {
+    /// A numeric identifier, e.g. a predefined resource type such as [`ResourceId::RCDATA`].
+    Id(u16),
+    /// A string identifier. Resource compilers store these in upper case.
+    Name(String),
+}
+
+impl ResourceId {
+    /// The resource type of a hardware-dependent cursor (`RT_CURSOR`).
+    pub const CURSOR: Self = Self::Id(1);
+    /// The resource type of a bitmap (`RT_BITMAP`).
+    pub const BITMAP: Self = Self::Id(2);
+    /// The resource type of a hardware-dependent icon (`RT_ICON`).
+    pub const ICON: Self = Self::Id(3);
+    /// The resource type of a menu (`RT_MENU`).
+    pub const MENU: Self = Self::Id(4);
+    /// The resource type of a dialog box (`RT_DIALOG`).
+    pub const DIALOG: Self = Self::Id(5);
+    /// The resource type of a string table (`RT_STRING`).
+    pub const STRING: Self = Self::Id(6);
+    /// The resource type of application-defined raw data (`RT_RCDATA`).
+    pub const RCDATA: Self = Self::Id(10);
+    /// The resource type of a hardware-independent icon (`RT_GROUP_ICON`).
+    pub const GROUP_ICON: Self = Self::Id(14);
+    /// The resource type of version information (`RT_VERSION`).
+    pub const VERSION: Self = Self::Id(16);
+    /// The resource type of a side-by-side assembly manifest (`RT_MANIFEST`).
+    pub const MANIFEST: Self = Self::Id(24);
+
+    /// Returns whether this identifier refers to the same resource as the given one, ignoring the case of string identifiers
+    /// like `FindResource` does.
+    #[must_use]
+    pub fn matches(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Id(id), Self::Id(other)) => id == other,
+            (Self::Name(name), Self::Name(other)) => name.eq_ignore_ascii_case(other),
+            _ => false,
+        }
+    }
+}
+
+impl From<u16> for ResourceId {
+    fn from(id: u16) -> Self {
+        Self::Id(id)
+    }
+}
+
+impl From<&str> for ResourceId {
+    fn from(name: &str) -> Self {
+        Self::Name(name.to_owned())
+    }
+}
+
+impl From<String> for ResourceId {
+    fn from(name: String) -> Self {
+        Self::Name(name)
+    }
+}
+
+impl fmt::Display for ResourceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "#{id}"),
+            Self::Name(name) => f.write_str(name),
+        }
+    }
+}
+
+/// A resource of a module, see [`ProcessModule::resources`](crate::process::ProcessModule::resources).
+///
+/// The data of the resource can be read using [`ProcessModule::read_resource`](crate::process::ProcessModule::read_resource).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ModuleResource {
+    pub(crate) kind: ResourceId,
+    pub(crate) name: ResourceId,
+    pub(crate) language: u16,
+    pub(crate) rva: usize,
+    pub(crate) len: usize,
+}
+
+impl ModuleResource {
+    /// Returns the type of the resource, e.g. [`ResourceId::RCDATA`].
+    #[must_use]
+    pub fn kind(&self) -> &ResourceId {
+        &self.kind
+    }
+
+    /// Returns the name of the resource.
+    #[must_use]
+    pub fn name(&self) -> &ResourceId {
+        &self.name
+    }
+
+    /// Returns the language id of the resource, e.g. `0x0409` for US English or `0` for a language neutral resource.
+    #[must_use]
+    pub fn language(&self) -> u16 {
+        self.language
+    }
+
+    /// Returns the offset of the data of the resource from the base of its module.
+    #[must_use]
+    pub fn rva(&self) -> usize {
+        self.rva
+    }
+
+    /// Returns the size of the data of the resource in bytes.
+    #[allow(clippy::len_without_is_empty)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+}
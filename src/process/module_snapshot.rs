@@ -0,0 +1,159 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use crate::process::{
+    BorrowedProcess, ModuleHandle, ModulePathMatcher, Process, ProcessId, ProcessModule,
+};
+
+/// A module recorded in a [`ModuleSnapshot`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SnapshotModule {
+    handle: ModuleHandle,
+    path: PathBuf,
+}
+
+unsafe impl Send for SnapshotModule {}
+unsafe impl Sync for SnapshotModule {}
+
+impl SnapshotModule {
+    /// Returns the handle of the module at the time the snapshot was taken.
+    #[must_use]
+    pub fn handle(&self) -> ModuleHandle {
+        self.handle
+    }
+
+    /// Returns the path of the module at the time the snapshot was taken.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// The modules loaded in a process at a point in time, see [`Process::module_snapshot`].
+#[derive(Debug, Clone)]
+pub struct ModuleSnapshot {
+    process_id: ProcessId,
+    taken_at: Instant,
+    // sorted by handle.
+    modules: Vec<SnapshotModule>,
+}
+
+/// The changes between two [`ModuleSnapshot`]s, see [`ModuleSnapshot::diff`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModuleSnapshotDiff {
+    /// The modules that were loaded between the two snapshots.
+    pub loaded: Vec<SnapshotModule>,
+    /// The modules that were unloaded between the two snapshots.
+    pub unloaded: Vec<SnapshotModule>,
+}
+
+impl ModuleSnapshotDiff {
+    /// Returns whether no modules were loaded or unloaded between the two snapshots.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.loaded.is_empty() && self.unloaded.is_empty()
+    }
+}
+
+impl ModuleSnapshot {
+    pub(crate) fn capture(process: BorrowedProcess<'_>) -> Result<Self, io::Error> {
+        let process_id = ProcessId::of(&process)?;
+        let taken_at = Instant::now();
+
+        let mut modules = Vec::new();
+        for handle in process.module_handles()? {
+            let module = unsafe { ProcessModule::new_unchecked(handle, process) };
+            match module.path() {
+                Ok(path) => modules.push(SnapshotModule { handle, path }),
+                // the module was unloaded while the snapshot was taken.
+                Err(_) if !module.guess_is_loaded() && process.is_alive() => {}
+                Err(err) => return Err(err),
+            }
+        }
+        modules.sort_unstable_by_key(|module| module.handle as usize);
+
+        Ok(Self {
+            process_id,
+            taken_at,
+            modules,
+        })
+    }
+
+    /// Returns the id of the process this snapshot was taken of.
+    #[must_use]
+    pub fn process_id(&self) -> ProcessId {
+        self.process_id
+    }
+
+    /// Returns the point in time at which this snapshot was taken.
+    #[must_use]
+    pub fn taken_at(&self) -> Instant {
+        self.taken_at
+    }
+
+    /// Returns the modules in this snapshot ordered by their handles.
+    #[must_use]
+    pub fn modules(&self) -> &[SnapshotModule] {
+        &self.modules
+    }
+
+    /// Returns whether a module with the given path is part of this snapshot.
+    /// The comparison of paths is case-insensitive.
+    #[must_use]
+    pub fn contains_path(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref().as_os_str();
+        self.modules
+            .iter()
+            .any(|module| module.path.as_os_str().eq_ignore_ascii_case(path))
+    }
+
+    /// Returns the module in this snapshot that was loaded from the given path.
+    /// The path is matched in the same way as by [`Process::find_module_by_path`], but the module paths recorded in
+    /// the snapshot are used instead of querying them from the process again.
+    #[must_use]
+    pub fn find_by_path(&self, path: impl AsRef<Path>) -> Option<&SnapshotModule> {
+        let matcher = ModulePathMatcher::new(path.as_ref());
+        self.modules
+            .iter()
+            .find(|module| matcher.matches(&module.path))
+    }
+
+    /// Returns the modules that were loaded and unloaded between this snapshot and the given later one.
+    ///
+    /// A module is considered to be the same in both snapshots if it has the same handle and path,
+    /// so a module that was unloaded and reloaded at a different address is reported as both unloaded and loaded.
+    ///
+    /// # Panics
+    /// This method panics if the snapshots were taken of different processes.
+    #[must_use]
+    pub fn diff(&self, later: &ModuleSnapshot) -> ModuleSnapshotDiff {
+        assert_eq!(
+            self.process_id, later.process_id,
+            "trying to diff module snapshots of different processes"
+        );
+
+        let is_in = |module: &SnapshotModule, snapshot: &ModuleSnapshot| {
+            snapshot
+                .modules
+                .binary_search_by_key(&(module.handle as usize), |m| m.handle as usize)
+                .is_ok_and(|i| snapshot.modules[i].path == module.path)
+        };
+        ModuleSnapshotDiff {
+            loaded: later
+                .modules
+                .iter()
+                .filter(|module| !is_in(module, self))
+                .cloned()
+                .collect(),
+            unloaded: self
+                .modules
+                .iter()
+                .filter(|module| !is_in(module, later))
+                .cloned()
+                .collect(),
+        }
+    }
+}
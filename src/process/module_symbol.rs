@@ -0,0 +1,108 @@
+use std::fmt;
+
+use crate::{
+    function::{FunctionPtr, RawFunctionPtr},
+    process::BorrowedProcessModule,
+};
+
+/// A typed pointer to an exported function of a module in a (possibly remote) process, see [`ProcessModule::get`](crate::process::ProcessModule::get).
+///
+/// Like the symbols of `libloading`, it borrows the module it was taken from. The function can be called using a
+/// [`Syringe`](crate::Syringe) of the same process with `RemoteSymbol::to_raw_procedure` (requires the `rpc-raw` feature)
+/// or its address can be taken to be passed on.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::{process::{OwnedProcess, Process}, Syringe};
+///
+/// let syringe = Syringe::for_process(OwnedProcess::find_first_by_name("target_process").unwrap());
+/// let kernel32 = syringe.process().find_module_by_name("kernel32.dll").unwrap().unwrap();
+/// let get_current_process_id = unsafe { kernel32.get::<extern "system" fn() -> u32>("GetCurrentProcessId") }
+///     .unwrap()
+///     .unwrap();
+/// println!("GetCurrentProcessId is at {:p}", get_current_process_id.as_raw_ptr());
+/// # #[cfg(feature = "rpc-raw")]
+/// println!("pid: {}", get_current_process_id.to_raw_procedure(&syringe).unwrap().call().unwrap());
+/// ```
+pub struct RemoteSymbol<'a, F> {
+    ptr: F,
+    module: BorrowedProcessModule<'a>,
+}
+
+impl<F: FunctionPtr> fmt::Debug for RemoteSymbol<'_, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteSymbol")
+            .field("ptr", &self.ptr.as_ptr())
+            .field("module", &self.module)
+            .finish()
+    }
+}
+
+impl<F: FunctionPtr> Clone for RemoteSymbol<'_, F> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<F: FunctionPtr> Copy for RemoteSymbol<'_, F> {}
+
+impl<'a, F: FunctionPtr> RemoteSymbol<'a, F> {
+    pub(crate) const fn new(ptr: F, module: BorrowedProcessModule<'a>) -> Self {
+        Self { ptr, module }
+    }
+
+    /// Returns the module this symbol was taken from.
+    #[must_use]
+    pub const fn module(&self) -> BorrowedProcessModule<'a> {
+        self.module
+    }
+
+    /// Returns the typed pointer to the function in the process of the module.
+    ///
+    /// # Note
+    /// The pointer is only valid in the process of the module, so it must only be called directly if the module is local.
+    #[must_use]
+    pub fn as_ptr(&self) -> F {
+        self.ptr
+    }
+
+    /// Returns the untyped pointer to the function in the process of the module.
+    #[must_use]
+    pub fn as_raw_ptr(&self) -> RawFunctionPtr {
+        self.ptr.as_ptr()
+    }
+
+    /// Returns the address of the function in the process of the module.
+    #[must_use]
+    pub fn address(&self) -> usize {
+        self.as_raw_ptr() as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::process::{BorrowedProcess, Process};
+
+    #[test]
+    fn get_returns_typed_local_symbol() {
+        let kernel32 = BorrowedProcess::current()
+            .find_module_by_name("kernel32.dll")
+            .unwrap()
+            .unwrap();
+        let get_current_process_id =
+            unsafe { kernel32.get::<unsafe extern "system" fn() -> u32>("GetCurrentProcessId") }
+                .unwrap()
+                .unwrap();
+        assert_eq!(get_current_process_id.module(), kernel32.borrowed());
+        assert_eq!(
+            unsafe { (get_current_process_id.as_ptr())() },
+            std::process::id()
+        );
+
+        assert!(
+            unsafe { kernel32.get::<extern "system" fn()>("NoSuchFunction") }
+                .unwrap()
+                .is_none()
+        );
+    }
+}
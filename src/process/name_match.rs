@@ -0,0 +1,226 @@
+use std::{io, os::windows::prelude::AsRawHandle};
+
+use winapi::{
+    shared::{minwindef::TRUE, winerror::ERROR_INSUFFICIENT_BUFFER},
+    um::{stringapiset::CompareStringOrdinal, winbase::QueryFullProcessImageNameW},
+};
+
+use crate::process::{OwnedProcess, PROCESS_QUERY_ACCESS};
+
+const CSTR_EQUAL: i32 = 2;
+const EXE_EXTENSION: [u16; 4] = [b'.' as u16, b'e' as u16, b'x' as u16, b'e' as u16];
+// the maximum length of an extended-length path.
+const MAX_IMAGE_PATH_LEN: usize = 32768;
+
+/// Options controlling how a name is matched against running processes by [`OwnedProcess::find_first_by_name_with`] and [`OwnedProcess::find_all_by_name_with`].
+///
+/// The default options match like [`OwnedProcess::find_first_by_name`]: the executable file name has to contain the name, respecting case.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::process::{NameMatchOptions, OwnedProcess};
+///
+/// // matches `Notepad.exe`, but not `notepad++.exe`.
+/// let options = NameMatchOptions::new()
+///     .with_exact(true)
+///     .with_case_sensitive(false)
+///     .with_optional_extension(true);
+/// let process = OwnedProcess::find_first_by_name_with("notepad", &options);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NameMatchOptions {
+    case_sensitive: bool,
+    exact: bool,
+    optional_extension: bool,
+    full_path: bool,
+}
+
+impl Default for NameMatchOptions {
+    fn default() -> Self {
+        Self {
+            case_sensitive: true,
+            exact: false,
+            optional_extension: false,
+            full_path: false,
+        }
+    }
+}
+
+impl NameMatchOptions {
+    /// Creates a new set of options that matches executable file names containing the name, respecting case.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the case of the name has to match. Defaults to `true`.
+    ///
+    /// Case is compared like the file system does, i.e. using the uppercase mapping of the operating system.
+    #[must_use]
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Sets whether the name has to match the whole executable file name (or path) instead of only a part of it. Defaults to `false`.
+    #[must_use]
+    pub fn with_exact(mut self, exact: bool) -> Self {
+        self.exact = exact;
+        self
+    }
+
+    /// Sets whether a trailing `.exe` is ignored on both the name and the executable file name, so that e.g. `notepad` and `notepad.exe` are equivalent.
+    /// The extension is always compared ignoring case. Defaults to `false`.
+    #[must_use]
+    pub fn with_optional_extension(mut self, optional_extension: bool) -> Self {
+        self.optional_extension = optional_extension;
+        self
+    }
+
+    /// Sets whether the name is matched against the full path of the executable instead of its file name. Defaults to `false`.
+    ///
+    /// The path can only be queried for processes that can be opened with [`PROCESS_QUERY_ACCESS`], others are skipped.
+    #[must_use]
+    pub fn with_full_path(mut self, full_path: bool) -> Self {
+        self.full_path = full_path;
+        self
+    }
+
+    /// Returns whether the case of the name has to match.
+    #[must_use]
+    pub fn case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    /// Returns whether the name has to match the whole executable file name (or path).
+    #[must_use]
+    pub fn exact(&self) -> bool {
+        self.exact
+    }
+
+    /// Returns whether a trailing `.exe` is ignored.
+    #[must_use]
+    pub fn optional_extension(&self) -> bool {
+        self.optional_extension
+    }
+
+    /// Returns whether the name is matched against the full path of the executable.
+    #[must_use]
+    pub fn full_path(&self) -> bool {
+        self.full_path
+    }
+
+    /// Returns whether the process with the given id and executable file name matches the given name.
+    /// The buffer is used to query the path of the executable and reused across calls, so that matching does not allocate for every process.
+    pub(crate) fn matches_process(
+        &self,
+        name: &[u16],
+        pid: u32,
+        file_name: &[u16],
+        path_buf: &mut Vec<u16>,
+    ) -> bool {
+        if !self.full_path {
+            return self.matches(name, file_name);
+        }
+        match query_image_path(pid, path_buf) {
+            Ok(path) => self.matches(name, path),
+            // the process exited or denied access.
+            Err(_) => false,
+        }
+    }
+
+    fn matches(&self, name: &[u16], candidate: &[u16]) -> bool {
+        let name = self.strip_extension(name);
+        let candidate = self.strip_extension(candidate);
+        if self.exact {
+            self.eq(name, candidate)
+        } else {
+            name.is_empty()
+                || candidate
+                    .windows(name.len())
+                    .any(|window| self.eq(window, name))
+        }
+    }
+
+    fn strip_extension<'a>(&self, name: &'a [u16]) -> &'a [u16] {
+        if !self.optional_extension {
+            return name;
+        }
+        match name.len().checked_sub(EXE_EXTENSION.len()) {
+            Some(len) if eq_ignore_case(&name[len..], &EXE_EXTENSION) => &name[..len],
+            _ => name,
+        }
+    }
+
+    fn eq(&self, a: &[u16], b: &[u16]) -> bool {
+        a == b || (!self.case_sensitive && eq_ignore_case(a, b))
+    }
+}
+
+/// Compares the given UTF-16 strings ignoring case the way the file system does, i.e. using the uppercase table of the system
+/// instead of ascii or locale dependent case folding.
+pub(crate) fn eq_ignore_case(a: &[u16], b: &[u16]) -> bool {
+    a.len() == b.len()
+        && unsafe {
+            CompareStringOrdinal(a.as_ptr(), a.len() as i32, b.as_ptr(), b.len() as i32, TRUE)
+        } == CSTR_EQUAL
+}
+
+fn query_image_path(pid: u32, buf: &mut Vec<u16>) -> Result<&[u16], io::Error> {
+    let process = OwnedProcess::from_pid_with_access(pid, PROCESS_QUERY_ACCESS)?;
+    if buf.is_empty() {
+        buf.resize(260, 0);
+    }
+    loop {
+        let mut len = buf.len() as u32;
+        let result = unsafe {
+            QueryFullProcessImageNameW(process.as_raw_handle(), 0, buf.as_mut_ptr(), &mut len)
+        };
+        if result != 0 {
+            return Ok(&buf[..len as usize]);
+        }
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(ERROR_INSUFFICIENT_BUFFER as i32)
+            || buf.len() >= MAX_IMAGE_PATH_LEN
+        {
+            return Err(err);
+        }
+        buf.resize((buf.len() * 2).min(MAX_IMAGE_PATH_LEN), 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(options: NameMatchOptions, name: &str, candidate: &str) -> bool {
+        let name = name.encode_utf16().collect::<Vec<_>>();
+        let candidate = candidate.encode_utf16().collect::<Vec<_>>();
+        options.matches(&name, &candidate)
+    }
+
+    #[test]
+    fn default_options_match_substrings_respecting_case() {
+        let options = NameMatchOptions::new();
+        assert!(matches(options, "pad", "notepad.exe"));
+        assert!(matches(options, "", "notepad.exe"));
+        assert!(!matches(options, "Notepad", "notepad.exe"));
+    }
+
+    #[test]
+    fn options_are_combined() {
+        let options = NameMatchOptions::new()
+            .with_exact(true)
+            .with_case_sensitive(false)
+            .with_optional_extension(true);
+        assert!(matches(options, "NOTEPAD", "notepad.exe"));
+        assert!(matches(options, "notepad.EXE", "Notepad.exe"));
+        assert!(matches(options, "ÄPFEL", "äpfel.exe"));
+        assert!(!matches(options, "notepad", "notepad++.exe"));
+        assert!(!matches(
+            options.with_optional_extension(false),
+            "notepad",
+            "notepad.exe"
+        ));
+    }
+}
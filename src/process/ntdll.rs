@@ -0,0 +1,130 @@
+// Bindings for the few undocumented `ntdll` functions that are not part of `winapi`.
+
+use std::{ffi::c_void, io, mem, os::windows::prelude::AsRawHandle, ptr};
+
+use winapi::{
+    shared::{
+        basetsd::SIZE_T,
+        minwindef::ULONG,
+        ntdef::{BOOLEAN, NTSTATUS},
+    },
+    um::winnt::{ACCESS_MASK, HANDLE, OSVERSIONINFOW},
+};
+
+use crate::process::BorrowedProcess;
+
+#[repr(C)]
+pub struct ClientId {
+    pub unique_process: HANDLE,
+    pub unique_thread: HANDLE,
+}
+
+/// `PUBLIC_OBJECT_BASIC_INFORMATION`, queried using the `ObjectBasicInformation` class.
+#[repr(C)]
+struct ObjectBasicInformation {
+    attributes: ULONG,
+    granted_access: ACCESS_MASK,
+    handle_count: ULONG,
+    pointer_count: ULONG,
+    reserved: [ULONG; 10],
+}
+
+const OBJECT_BASIC_INFORMATION_CLASS: ULONG = 0;
+
+#[link(name = "ntdll")]
+extern "system" {
+    pub fn NtQueryInformationProcess(
+        process_handle: HANDLE,
+        process_information_class: ULONG,
+        process_information: *mut c_void,
+        process_information_length: ULONG,
+        return_length: *mut ULONG,
+    ) -> NTSTATUS;
+
+    pub fn NtQueryInformationThread(
+        thread_handle: HANDLE,
+        thread_information_class: ULONG,
+        thread_information: *mut c_void,
+        thread_information_length: ULONG,
+        return_length: *mut ULONG,
+    ) -> NTSTATUS;
+
+    pub fn RtlCreateUserThread(
+        process_handle: HANDLE,
+        thread_security_descriptor: *mut c_void,
+        create_suspended: BOOLEAN,
+        zero_bits: ULONG,
+        maximum_stack_size: SIZE_T,
+        committed_stack_size: SIZE_T,
+        start_address: *mut c_void,
+        parameter: *mut c_void,
+        thread_handle: *mut HANDLE,
+        client_id: *mut ClientId,
+    ) -> NTSTATUS;
+
+    pub fn NtSuspendProcess(process_handle: HANDLE) -> NTSTATUS;
+
+    pub fn NtResumeProcess(process_handle: HANDLE) -> NTSTATUS;
+
+    pub fn RtlGetVersion(version_information: *mut OSVERSIONINFOW) -> NTSTATUS;
+
+    pub fn NtQueryObject(
+        handle: HANDLE,
+        object_information_class: ULONG,
+        object_information: *mut c_void,
+        object_information_length: ULONG,
+        return_length: *mut ULONG,
+    ) -> NTSTATUS;
+
+    fn RtlNtStatusToDosError(status: NTSTATUS) -> ULONG;
+}
+
+/// Returns the win32 error equivalent to the given status.
+/// Statuses without an equivalent map to `ERROR_MR_MID_NOT_FOUND`.
+pub fn status_to_win32_error(status: NTSTATUS) -> u32 {
+    unsafe { RtlNtStatusToDosError(status) }
+}
+
+/// Converts the given status into a result, mapping failure codes to the equivalent win32 error.
+pub fn check_status(status: NTSTATUS) -> Result<(), io::Error> {
+    if status < 0 {
+        let code = status_to_win32_error(status);
+        return Err(io::Error::from_raw_os_error(code as i32));
+    }
+    Ok(())
+}
+
+/// Queries the process information of the given class, which must be represented by `T`.
+pub fn query_process_information<T>(
+    process: BorrowedProcess<'_>,
+    class: ULONG,
+) -> Result<T, io::Error> {
+    let mut info = mem::MaybeUninit::<T>::uninit();
+    let status = unsafe {
+        NtQueryInformationProcess(
+            process.as_raw_handle(),
+            class,
+            info.as_mut_ptr().cast(),
+            mem::size_of::<T>() as ULONG,
+            ptr::null_mut(),
+        )
+    };
+    check_status(status)?;
+    Ok(unsafe { info.assume_init() })
+}
+
+/// Returns the access rights granted to the given handle.
+pub fn query_granted_access(handle: HANDLE) -> Result<ACCESS_MASK, io::Error> {
+    let mut info = mem::MaybeUninit::<ObjectBasicInformation>::uninit();
+    let status = unsafe {
+        NtQueryObject(
+            handle,
+            OBJECT_BASIC_INFORMATION_CLASS,
+            info.as_mut_ptr().cast(),
+            mem::size_of::<ObjectBasicInformation>() as ULONG,
+            ptr::null_mut(),
+        )
+    };
+    check_status(status)?;
+    Ok(unsafe { info.assume_init() }.granted_access)
+}
@@ -1,6 +1,7 @@
 use std::{
+    ffi::OsStr,
     hash::{Hash, Hasher},
-    io,
+    io, iter,
     os::windows::{
         prelude::{
             AsHandle, AsRawHandle, BorrowedHandle, FromRawHandle, IntoRawHandle, OwnedHandle,
@@ -10,24 +11,39 @@ use std::{
     },
     path::Path,
     process::Child,
+    thread,
     time::Duration,
 };
 
-use sysinfo::{PidExt, ProcessExt, SystemExt};
-use winapi::{shared::minwindef::FALSE, um::processthreadsapi::OpenProcess};
+use winapi::{
+    shared::{minwindef::FALSE, winerror::ERROR_ACCESS_DENIED},
+    um::processthreadsapi::OpenProcess,
+};
 
-use crate::process::{BorrowedProcess, OwnedProcessModule, Process, PROCESS_INJECTION_ACCESS};
+use crate::{
+    process::{
+        check_process_handle,
+        token::try_enable_debug_privilege_once,
+        window::{top_level_windows, TopLevelWindow},
+        BorrowedProcess, JobObject, NameMatchOptions, OwnedProcessModule, Process, ProcessBuilder,
+        ProcessEntry, ProcessIter, ProcessSelector, PROCESS_INJECTION_ACCESS,
+    },
+    utils::{retry_with_timeout, trace_event},
+};
+
+pub(crate) const PROCESS_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 /// A struct representing a running process.
 /// This struct owns the underlying process handle (see also [`BorrowedProcess`] for a borrowed version).
 ///
 /// # Note
-/// The underlying handle has the following [privileges](https://docs.microsoft.com/en-us/windows/win32/procthread/process-security-and-access-rights):
+/// Unless opened using [`OwnedProcess::from_pid_with_access`], the underlying handle has the following [privileges](https://docs.microsoft.com/en-us/windows/win32/procthread/process-security-and-access-rights):
 ///  - `PROCESS_CREATE_THREAD`
 ///  - `PROCESS_QUERY_INFORMATION`
 ///  - `PROCESS_VM_OPERATION`
 ///  - `PROCESS_VM_WRITE`
 ///  - `PROCESS_VM_READ`
+///  - `SYNCHRONIZE`
 #[repr(transparent)]
 #[derive(Debug)]
 pub struct OwnedProcess(OwnedHandle);
@@ -65,6 +81,25 @@ impl From<Child> for OwnedProcess {
     }
 }
 
+impl TryFrom<OwnedHandle> for OwnedProcess {
+    type Error = io::Error;
+
+    /// Creates a new instance from a process handle obtained elsewhere (e.g. from `CreateProcessW`).
+    ///
+    /// # Errors
+    /// Returns an error if the handle does not refer to a process or lacks the `PROCESS_QUERY_LIMITED_INFORMATION` access right.
+    fn try_from(handle: OwnedHandle) -> Result<Self, Self::Error> {
+        check_process_handle(handle.as_raw_handle())?;
+        Ok(Self(handle))
+    }
+}
+
+impl From<OwnedProcess> for OwnedHandle {
+    fn from(process: OwnedProcess) -> Self {
+        process.0
+    }
+}
+
 impl TryFrom<BorrowedProcess<'_>> for OwnedProcess {
     type Error = io::Error;
 
@@ -165,15 +200,38 @@ impl Process for OwnedProcess {
 
 impl OwnedProcess {
     /// Creates a new instance from the given pid.
+    ///
+    /// If access to the process is denied, this function tries to enable the `SeDebugPrivilege` privilege
+    /// (see [`enable_debug_privilege`](crate::enable_debug_privilege)) and opens the process again.
     pub fn from_pid(pid: u32) -> Result<OwnedProcess, io::Error> {
-        let handle = unsafe {
-            OpenProcess(
-                // access required for performing dll injection
-                PROCESS_INJECTION_ACCESS,
-                FALSE,
-                pid,
-            )
+        // access required for performing dll injection
+        Self::from_pid_with_access(pid, PROCESS_INJECTION_ACCESS)
+    }
+
+    /// Creates a new instance from the given pid with the given [access rights](https://docs.microsoft.com/en-us/windows/win32/procthread/process-security-and-access-rights).
+    ///
+    /// Hardened processes may deny the rights required for injection while still allowing more limited access,
+    /// e.g. [`PROCESS_QUERY_ACCESS`](crate::process::PROCESS_QUERY_ACCESS) or [`PROCESS_MEMORY_READ_ACCESS`](crate::process::PROCESS_MEMORY_READ_ACCESS).
+    /// Methods requiring rights that were not requested fail with [`io::ErrorKind::PermissionDenied`].
+    ///
+    /// Like [`OwnedProcess::from_pid`], this function tries to enable the `SeDebugPrivilege` privilege if access is denied.
+    pub fn from_pid_with_access(pid: u32, access: u32) -> Result<OwnedProcess, io::Error> {
+        let result = match Self::open(pid, access) {
+            Err(err)
+                if err.raw_os_error() == Some(ERROR_ACCESS_DENIED as i32)
+                    && try_enable_debug_privilege_once() =>
+            {
+                trace_event!(debug, pid, "access denied, retrying with debug privilege");
+                Self::open(pid, access)
+            }
+            result => result,
         };
+        trace_event!(debug, pid, access = format_args!("{access:#x}"), error = ?result.as_ref().err(), "opened process");
+        result
+    }
+
+    fn open(pid: u32, access: u32) -> Result<OwnedProcess, io::Error> {
+        let handle = unsafe { OpenProcess(access, FALSE, pid) };
 
         if handle.is_null() {
             return Err(io::Error::last_os_error());
@@ -182,50 +240,188 @@ impl OwnedProcess {
         Ok(unsafe { OwnedProcess::from_raw_handle(handle) })
     }
 
+    /// Returns an iterator over a snapshot of all currently running processes.
+    /// The processes are only opened when requested using [`ProcessEntry::open`].
+    pub fn iter() -> Result<ProcessIter, io::Error> {
+        ProcessIter::new()
+    }
+
     /// Returns a list of all currently running processes.
     #[must_use]
     pub fn all() -> Vec<OwnedProcess> {
-        // TODO: avoid using sysinfo for this
-        // TODO: deduplicate code
-        let mut system = sysinfo::System::new();
-        system.refresh_processes();
-        system
-            .processes()
-            .values()
-            .map(|process| process.pid())
-            .filter_map(|pid| OwnedProcess::from_pid(pid.as_u32()).ok())
-            .collect()
+        Self::open_matching(|_| true).collect()
     }
 
     /// Finds all processes whose name contains the given string.
     #[must_use]
     pub fn find_all_by_name(name: impl AsRef<str>) -> Vec<OwnedProcess> {
-        // TODO: avoid using sysinfo for this
-        // TODO: deduplicate code
-        let mut system = sysinfo::System::new();
-        system.refresh_processes();
-        system
-            .processes()
-            .values()
-            .filter(move |process| process.name().contains(name.as_ref()))
-            .map(|process| process.pid())
-            .filter_map(|pid| OwnedProcess::from_pid(pid.as_u32()).ok())
-            .collect()
+        Self::find_all_by_name_with(name, &NameMatchOptions::new())
     }
 
     /// Finds the first process whose name contains the given string.
+    ///
+    /// The process snapshot is walked lazily and only the first matching process is opened.
     #[must_use]
     pub fn find_first_by_name(name: impl AsRef<str>) -> Option<OwnedProcess> {
-        // TODO: avoid using sysinfo for this
-        // TODO: deduplicate code
-        let mut system = sysinfo::System::new();
-        system.refresh_processes();
-        system
-            .processes()
-            .values()
-            .filter(move |process| process.name().contains(name.as_ref()))
-            .map(|process| process.pid())
-            .find_map(|pid| OwnedProcess::from_pid(pid.as_u32()).ok())
+        Self::find_first_by_name_with(name, &NameMatchOptions::new())
+    }
+
+    /// Finds all processes whose name matches the given name according to the given options.
+    #[must_use]
+    pub fn find_all_by_name_with(
+        name: impl AsRef<str>,
+        options: &NameMatchOptions,
+    ) -> Vec<OwnedProcess> {
+        Self::open_matching_name(name.as_ref(), options).collect()
+    }
+
+    /// Finds the first process whose name matches the given name according to the given options.
+    ///
+    /// The process snapshot is walked lazily and only the first matching process is opened.
+    #[must_use]
+    pub fn find_first_by_name_with(
+        name: impl AsRef<str>,
+        options: &NameMatchOptions,
+    ) -> Option<OwnedProcess> {
+        Self::open_matching_name(name.as_ref(), options).next()
+    }
+
+    /// Searches for a process whose name contains the given string, repeatedly until a matching process is found or the given timeout elapses.
+    #[must_use]
+    pub fn wait_for_by_name(name: impl AsRef<str>, timeout: Duration) -> Option<OwnedProcess> {
+        retry_with_timeout(
+            || {
+                let process = Self::find_first_by_name(name.as_ref());
+                if process.is_none() {
+                    // taking a process snapshot is comparatively expensive, so do not poll in a tight loop.
+                    thread::sleep(PROCESS_POLL_INTERVAL);
+                }
+                process
+            },
+            timeout,
+        )
+    }
+
+    /// Finds all processes matching the given predicate.
+    /// Only processes that match are opened.
+    #[must_use]
+    pub fn find_all_where(predicate: impl FnMut(&ProcessEntry) -> bool) -> Vec<OwnedProcess> {
+        Self::open_matching(predicate).collect()
+    }
+
+    /// Finds the first process matching the given predicate that can be opened.
+    #[must_use]
+    pub fn find_first_where(predicate: impl FnMut(&ProcessEntry) -> bool) -> Option<OwnedProcess> {
+        Self::open_matching(predicate).next()
+    }
+
+    /// Finds all processes selected by the given selector.
+    /// Only processes that are selected are opened.
+    #[must_use]
+    pub fn find_all_by_selector(selector: &ProcessSelector) -> Vec<OwnedProcess> {
+        Self::open_matching(|entry| selector.matches(entry)).collect()
+    }
+
+    /// Finds the first process selected by the given selector that can be opened.
+    #[must_use]
+    pub fn find_first_by_selector(selector: &ProcessSelector) -> Option<OwnedProcess> {
+        Self::open_matching(|entry| selector.matches(entry)).next()
+    }
+
+    /// Searches for a process selected by the given selector, repeatedly until a selected process is found or the given timeout elapses.
+    ///
+    /// This also returns processes that were already running, use a [`ProcessWatcher`](crate::process::ProcessWatcher)
+    /// to only observe newly started processes.
+    #[must_use]
+    pub fn wait_for_by_selector(
+        selector: &ProcessSelector,
+        timeout: Duration,
+    ) -> Option<OwnedProcess> {
+        retry_with_timeout(
+            || {
+                let process = Self::find_first_by_selector(selector);
+                if process.is_none() {
+                    thread::sleep(PROCESS_POLL_INTERVAL);
+                }
+                process
+            },
+            timeout,
+        )
+    }
+
+    /// Searches for a process selected by the given selector that can be assigned to the given job, repeatedly until one is found
+    /// or the given timeout elapses. See [`wait_for_by_selector`](Self::wait_for_by_selector).
+    ///
+    /// With [kill on close](JobObject::set_kill_on_close) the returned process (and the children it spawns afterwards)
+    /// is terminated once the job is closed. Children it spawned before are not part of the job.
+    #[must_use]
+    pub fn wait_for_by_selector_in_job(
+        selector: &ProcessSelector,
+        timeout: Duration,
+        job: &JobObject,
+    ) -> Option<OwnedProcess> {
+        retry_with_timeout(
+            || {
+                let process = Self::open_matching(|entry| selector.matches(entry))
+                    .find(|process| job.assign(process).is_ok());
+                if process.is_none() {
+                    thread::sleep(PROCESS_POLL_INTERVAL);
+                }
+                process
+            },
+            timeout,
+        )
+    }
+
+    /// Finds the first process owning a top-level window whose title contains the given string.
+    pub fn find_by_window_title(title: impl AsRef<str>) -> Result<Option<OwnedProcess>, io::Error> {
+        Self::find_by_window(|window| window.title.contains(title.as_ref()))
+    }
+
+    /// Finds the first process owning a top-level window with the given window class name.
+    /// The comparison of class names is case-insensitive.
+    pub fn find_by_window_class(class: impl AsRef<str>) -> Result<Option<OwnedProcess>, io::Error> {
+        Self::find_by_window(|window| window.class.eq_ignore_ascii_case(class.as_ref()))
+    }
+
+    fn find_by_window(
+        predicate: impl FnMut(&TopLevelWindow) -> bool,
+    ) -> Result<Option<OwnedProcess>, io::Error> {
+        Ok(top_level_windows()?
+            .into_iter()
+            .filter(predicate)
+            .find_map(|window| OwnedProcess::from_pid(window.pid).ok()))
+    }
+
+    fn open_matching_name(
+        name: &str,
+        options: &NameMatchOptions,
+    ) -> impl Iterator<Item = OwnedProcess> {
+        let name = name.encode_utf16().collect::<Vec<_>>();
+        let options = *options;
+        let mut path_buf = Vec::new();
+        let mut iter = ProcessIter::new().ok();
+        iter::from_fn(move || {
+            let iter = iter.as_mut()?;
+            loop {
+                let entry = iter.next_matching(|pid, file_name| {
+                    options.matches_process(&name, pid, file_name, &mut path_buf)
+                })?;
+                if let Ok(process) = entry.open() {
+                    return Some(process);
+                }
+            }
+        })
+    }
+
+    fn open_matching(
+        mut predicate: impl FnMut(&ProcessEntry) -> bool,
+    ) -> impl Iterator<Item = OwnedProcess> {
+        ProcessIter::new()
+            .into_iter()
+            .flatten()
+            .filter(move |entry| predicate(entry))
+            .filter_map(|entry| entry.open().ok())
     }
 
     /// Creates a new instance from the given child process.
@@ -234,6 +430,20 @@ impl OwnedProcess {
         unsafe { OwnedProcess::from_raw_handle(child.into_raw_handle()) }
     }
 
+    /// Returns a [`ProcessBuilder`] for spawning a new process running the given program.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dll_syringe::process::OwnedProcess;
+    ///
+    /// let spawned = OwnedProcess::spawn("target.exe").suspended(true).spawn().unwrap();
+    /// // inject before the target runs any code
+    /// spawned.resume().unwrap();
+    /// ```
+    pub fn spawn(program: impl AsRef<OsStr>) -> ProcessBuilder {
+        ProcessBuilder::new(program)
+    }
+
     /// Returns a borrowed instance of this process that lives for `'static`.
     ///
     /// # Safety
@@ -0,0 +1,248 @@
+use std::{ffi::OsString, io, mem};
+
+use winapi::shared::{minwindef::ULONG, ntdef::NTSTATUS};
+
+use crate::process::{
+    memory::ProcessMemorySlice, ntdll::query_process_information, BorrowedProcess, Process,
+};
+
+const PROCESS_BASIC_INFORMATION_CLASS: ULONG = 0;
+#[cfg(target_pointer_width = "64")]
+const PROCESS_WOW64_INFORMATION_CLASS: ULONG = 26;
+
+#[repr(C)]
+struct ProcessBasicInformation {
+    exit_status: NTSTATUS,
+    peb_base_address: usize,
+    affinity_mask: usize,
+    base_priority: i32,
+    unique_process_id: usize,
+    inherited_from_unique_process_id: usize,
+}
+
+/// Offsets into the `PEB` and `RTL_USER_PROCESS_PARAMETERS` structures for one bitness.
+#[derive(Debug)]
+struct PebLayout {
+    pointer_size: usize,
+    process_parameters: usize,
+    current_directory: usize,
+    command_line: usize,
+    environment: usize,
+    environment_size: usize,
+    api_set_map: usize,
+    #[cfg_attr(not(feature = "syringe"), allow(dead_code))]
+    process_heap: usize,
+    #[cfg_attr(not(feature = "syringe"), allow(dead_code))]
+    loader_data: usize,
+}
+
+#[cfg(target_pointer_width = "64")]
+const PEB_LAYOUT_X64: PebLayout = PebLayout {
+    pointer_size: 8,
+    process_parameters: 0x20,
+    current_directory: 0x38,
+    command_line: 0x70,
+    environment: 0x80,
+    environment_size: 0x3F0,
+    api_set_map: 0x68,
+    process_heap: 0x30,
+    loader_data: 0x18,
+};
+
+const PEB_LAYOUT_X86: PebLayout = PebLayout {
+    pointer_size: 4,
+    process_parameters: 0x10,
+    current_directory: 0x24,
+    command_line: 0x40,
+    environment: 0x48,
+    environment_size: 0x290,
+    api_set_map: 0x38,
+    process_heap: 0x18,
+    loader_data: 0x0C,
+};
+
+/// The `RTL_USER_PROCESS_PARAMETERS` of a (possibly remote) process, located through its `PEB`.
+#[derive(Debug)]
+pub(crate) struct RemoteProcessParameters<'a> {
+    process: BorrowedProcess<'a>,
+    layout: &'static PebLayout,
+    address: usize,
+}
+
+impl<'a> RemoteProcessParameters<'a> {
+    pub fn new(process: BorrowedProcess<'a>) -> Result<Self, io::Error> {
+        let (peb, layout) = locate_peb(process)?;
+        let mut this = Self {
+            process,
+            layout,
+            address: 0,
+        };
+        this.address = this.read_pointer(peb + layout.process_parameters)?;
+        if this.address == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "process parameters have not been initialized yet",
+            ));
+        }
+        Ok(this)
+    }
+
+    pub fn command_line(&self) -> Result<Vec<u16>, io::Error> {
+        self.read_unicode_string(self.address + self.layout.command_line)
+    }
+
+    /// Returns the DOS path of the current directory, which always ends with a backslash.
+    pub fn current_directory(&self) -> Result<Vec<u16>, io::Error> {
+        self.read_unicode_string(self.address + self.layout.current_directory)
+    }
+
+    /// Returns the environment block, a sequence of nul-terminated `name=value` strings.
+    pub fn environment_block(&self) -> Result<Vec<u16>, io::Error> {
+        let block = self.read_pointer(self.address + self.layout.environment)?;
+        let size = self.read_pointer(self.address + self.layout.environment_size)?;
+        let mut buf = vec![0u16; size / mem::size_of::<u16>()];
+        self.read(block, u16_slice_as_bytes_mut(&mut buf))?;
+        Ok(buf)
+    }
+
+    fn read(&self, address: usize, buf: &mut [u8]) -> Result<(), io::Error> {
+        unsafe { ProcessMemorySlice::from_raw_parts(address as *mut u8, buf.len(), self.process) }
+            .read(0, buf)
+    }
+
+    fn read_pointer(&self, address: usize) -> Result<usize, io::Error> {
+        let mut buf = [0; mem::size_of::<u64>()];
+        self.read(address, &mut buf[..self.layout.pointer_size])?;
+        Ok(u64::from_le_bytes(buf) as usize)
+    }
+
+    fn read_unicode_string(&self, address: usize) -> Result<Vec<u16>, io::Error> {
+        let mut len = [0; mem::size_of::<u16>()];
+        self.read(address, &mut len)?;
+        let len = u16::from_le_bytes(len) as usize;
+        // the buffer pointer follows the two u16 length fields, aligned to the pointer size.
+        let buffer = self.read_pointer(address + self.layout.pointer_size)?;
+
+        let mut buf = vec![0u16; len / mem::size_of::<u16>()];
+        self.read(buffer, u16_slice_as_bytes_mut(&mut buf))?;
+        Ok(buf)
+    }
+}
+
+/// Returns the address of the api set schema mapped into the given process.
+pub(crate) fn api_set_map_address(process: BorrowedProcess<'_>) -> Result<usize, io::Error> {
+    read_peb_pointer(process, |layout| layout.api_set_map)
+}
+
+/// Returns the handle of the default heap of the given process.
+#[cfg(feature = "syringe")]
+pub(crate) fn process_heap_address(process: BorrowedProcess<'_>) -> Result<usize, io::Error> {
+    read_peb_pointer(process, |layout| layout.process_heap)
+}
+
+/// Returns whether the loader of the given process has been initialized, i.e. whether its main thread started running.
+#[cfg(feature = "syringe")]
+pub(crate) fn is_loader_initialized(process: BorrowedProcess<'_>) -> Result<bool, io::Error> {
+    Ok(read_peb_pointer(process, |layout| layout.loader_data)? != 0)
+}
+
+fn read_peb_pointer(
+    process: BorrowedProcess<'_>,
+    offset: impl FnOnce(&PebLayout) -> usize,
+) -> Result<usize, io::Error> {
+    let (peb, layout) = locate_peb(process)?;
+    let mut buf = [0; mem::size_of::<u64>()];
+    unsafe {
+        ProcessMemorySlice::from_raw_parts(
+            (peb + offset(layout)) as *mut u8,
+            layout.pointer_size,
+            process,
+        )
+    }
+    .read(0, &mut buf[..layout.pointer_size])?;
+    Ok(u64::from_le_bytes(buf) as usize)
+}
+
+fn u16_slice_as_bytes_mut(buf: &mut [u16]) -> &mut [u8] {
+    unsafe { std::slice::from_raw_parts_mut(buf.as_mut_ptr().cast(), mem::size_of_val(buf)) }
+}
+
+fn locate_peb(process: BorrowedProcess<'_>) -> Result<(usize, &'static PebLayout), io::Error> {
+    #[cfg(target_pointer_width = "64")]
+    if process.is_x86()? {
+        let peb32 = query_process_information::<usize>(process, PROCESS_WOW64_INFORMATION_CLASS)?;
+        return Ok((peb32, &PEB_LAYOUT_X86));
+    }
+
+    #[cfg(target_pointer_width = "32")]
+    if process.is_x64()? {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "cannot read the PEB of a 64-bit process from a 32-bit process",
+        ));
+    }
+
+    let info = query_process_information::<ProcessBasicInformation>(
+        process,
+        PROCESS_BASIC_INFORMATION_CLASS,
+    )?;
+    #[cfg(target_pointer_width = "64")]
+    let layout = &PEB_LAYOUT_X64;
+    #[cfg(target_pointer_width = "32")]
+    let layout = &PEB_LAYOUT_X86;
+    Ok((info.peb_base_address, layout))
+}
+
+/// Splits an environment block into its `name=value` pairs.
+pub(crate) fn parse_environment_block(block: &[u16]) -> Vec<(OsString, OsString)> {
+    block
+        .split(|&c| c == 0)
+        .take_while(|entry| !entry.is_empty())
+        .map(|entry| {
+            // names of hidden variables such as `=C:` start with '=', so the separator search skips the first character.
+            let separator = entry
+                .iter()
+                .skip(1)
+                .position(|&c| c == u16::from(b'='))
+                .map_or(entry.len(), |pos| pos + 1);
+            let name = widestring::U16Str::from_slice(&entry[..separator]).to_os_string();
+            let value = widestring::U16Str::from_slice(entry.get(separator + 1..).unwrap_or(&[]))
+                .to_os_string();
+            (name, value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().collect()
+    }
+
+    #[test]
+    fn parse_environment_block_splits_pairs() {
+        let block = wide("=C:=C:\\dir\0PATH=a;b\0EMPTY=\0\0");
+        let env = parse_environment_block(&block);
+        assert_eq!(
+            env,
+            vec![
+                (OsString::from("=C:"), OsString::from("C:\\dir")),
+                (OsString::from("PATH"), OsString::from("a;b")),
+                (OsString::from("EMPTY"), OsString::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn current_process_command_line_is_readable() {
+        let parameters = RemoteProcessParameters::new(BorrowedProcess::current()).unwrap();
+        assert!(!parameters.command_line().unwrap().is_empty());
+        assert_eq!(
+            parameters.current_directory().unwrap().last(),
+            Some(&u16::from(b'\\'))
+        );
+        assert!(!parameters.environment_block().unwrap().is_empty());
+    }
+}
@@ -1,38 +1,79 @@
 use std::{
-    ffi::OsString,
+    ffi::{c_void, OsString},
     io,
     mem::{self, MaybeUninit},
     num::NonZeroU32,
+    ops::Range,
     os::windows::prelude::{AsHandle, AsRawHandle, FromRawHandle, OwnedHandle},
     path::{Path, PathBuf},
     ptr,
+    sync::OnceLock,
     time::Duration,
 };
 
 use winapi::{
     shared::{
         minwindef::{DWORD, FALSE},
-        winerror::{ERROR_CALL_NOT_IMPLEMENTED, ERROR_INSUFFICIENT_BUFFER},
+        winerror::{
+            ERROR_ACCESS_DENIED, ERROR_CALL_NOT_IMPLEMENTED, ERROR_INSUFFICIENT_BUFFER,
+            ERROR_NOT_ENOUGH_MEMORY,
+        },
     },
     um::{
-        minwinbase::STILL_ACTIVE,
+        handleapi::DuplicateHandle,
+        minwinbase::{SECURITY_ATTRIBUTES, STILL_ACTIVE},
         processthreadsapi::{
             CreateRemoteThread, GetCurrentProcess, GetExitCodeProcess, GetExitCodeThread,
-            GetProcessId, TerminateProcess,
+            GetProcessId, ProcessIdToSessionId, TerminateProcess,
         },
         synchapi::WaitForSingleObject,
-        winbase::{QueryFullProcessImageNameW, INFINITE, WAIT_FAILED},
+        sysinfoapi::GetSystemWindowsDirectoryW,
+        winbase::{
+            QueryFullProcessImageNameW, CREATE_SUSPENDED, INFINITE, WAIT_FAILED, WAIT_OBJECT_0,
+        },
         winnt::{
-            PROCESS_CREATE_THREAD, PROCESS_QUERY_INFORMATION, PROCESS_VM_OPERATION,
-            PROCESS_VM_READ, PROCESS_VM_WRITE,
+            PROCESS_ALL_ACCESS, PROCESS_CREATE_THREAD, PROCESS_QUERY_INFORMATION,
+            PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SUSPEND_RESUME, PROCESS_VM_OPERATION,
+            PROCESS_VM_READ, PROCESS_VM_WRITE, SYNCHRONIZE,
         },
         wow64apiset::{GetSystemWow64DirectoryA, IsWow64Process},
     },
 };
 
 use crate::{
-    process::{BorrowedProcess, ProcessModule},
-    utils::{win_fill_path_buf_helper, FillPathBufResult},
+    error::TerminateError,
+    function::RawFunctionPtr,
+    process::{
+        is_wine, job,
+        mitigation::mitigation_policies,
+        ntdll::{
+            check_status, query_granted_access, ClientId, NtResumeProcess, NtSuspendProcess,
+            RtlCreateUserThread,
+        },
+        peb::{parse_environment_block, RemoteProcessParameters},
+        protection_level::protection_level,
+        teb,
+        thread::{first_created, threads_of},
+        token, BorrowedProcess, BorrowedProcessModule, Capabilities, IntegrityLevel,
+        MitigationPolicies, ModuleListFilter, ModuleSnapshot, OwnedProcess, ProcessExitWatch,
+        ProcessId, ProcessIter, ProcessModule, ProcessModuleIter, ProcessThread, ProtectionLevel,
+        RemoteThreadCreationMethod, RemoteThreadOptions, RemoteThreadResult, SecurityDescriptor,
+        StackSnapshot,
+    },
+    utils::{
+        redirect_system_path, trace_event, trace_span, win_fill_path_buf_helper, FillPathBufResult,
+    },
+};
+#[cfg(feature = "process-memory")]
+use {
+    crate::{
+        process::memory::{
+            read_nul_terminated, DumpFormat, MemoryMap, MemoryRegionIter, PointerWidth,
+        },
+        utils::from_ansi_bytes,
+        CancellationToken,
+    },
+    std::io::Write,
 };
 
 /// A handle to a running process.
@@ -43,7 +84,21 @@ pub const PROCESS_INJECTION_ACCESS: DWORD = PROCESS_CREATE_THREAD
     | PROCESS_QUERY_INFORMATION
     | PROCESS_VM_OPERATION
     | PROCESS_VM_READ
-    | PROCESS_VM_WRITE;
+    | PROCESS_VM_WRITE
+    | SYNCHRONIZE;
+
+/// The [privileges](https://docs.microsoft.com/en-us/windows/win32/procthread/process-security-and-access-rights) required for querying basic information about a process
+/// (e.g. [`Process::path`], [`Process::is_alive`] or [`Process::wait_for_exit`]).
+///
+/// This is the most limited access right that can still be granted for many protected processes.
+pub const PROCESS_QUERY_ACCESS: DWORD = PROCESS_QUERY_LIMITED_INFORMATION | SYNCHRONIZE;
+
+/// The [privileges](https://docs.microsoft.com/en-us/windows/win32/procthread/process-security-and-access-rights) required for reading the memory of a process.
+pub const PROCESS_MEMORY_READ_ACCESS: DWORD = PROCESS_QUERY_ACCESS | PROCESS_VM_READ;
+
+/// The [privileges](https://docs.microsoft.com/en-us/windows/win32/procthread/process-security-and-access-rights) required for reading, writing and allocating the memory of a process.
+pub const PROCESS_MEMORY_ACCESS: DWORD =
+    PROCESS_MEMORY_READ_ACCESS | PROCESS_VM_WRITE | PROCESS_VM_OPERATION;
 
 /// A trait representing a running process.
 ///
@@ -54,6 +109,7 @@ pub const PROCESS_INJECTION_ACCESS: DWORD = PROCESS_CREATE_THREAD
 ///  - `PROCESS_VM_OPERATION`
 ///  - `PROCESS_VM_WRITE`
 ///  - `PROCESS_VM_READ`
+///  - `SYNCHRONIZE`
 pub trait Process: AsHandle + AsRawHandle {
     /// The underlying handle type.
     type Handle;
@@ -66,6 +122,30 @@ pub trait Process: AsHandle + AsRawHandle {
     where
         Self: Sized;
 
+    /// Duplicates the underlying handle into a new [`OwnedProcess`] with the given [access rights](https://docs.microsoft.com/en-us/windows/win32/procthread/process-security-and-access-rights).
+    ///
+    /// This allows sharing a process with other components (e.g. a watcher thread) that only require limited access.
+    /// The requested rights need not be a subset of the rights of this handle, but requesting additional rights may fail.
+    fn duplicate_with_access(&self, access: u32) -> Result<OwnedProcess, io::Error> {
+        let current_process = unsafe { GetCurrentProcess() };
+        let mut new_handle = MaybeUninit::uninit();
+        let result = unsafe {
+            DuplicateHandle(
+                current_process,
+                self.as_raw_handle(),
+                current_process,
+                new_handle.as_mut_ptr(),
+                access,
+                FALSE,
+                0,
+            )
+        };
+        if result == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(unsafe { OwnedProcess::from_raw_handle(new_handle.assume_init()) })
+    }
+
     /// Returns the underlying process handle.
     #[must_use]
     fn into_handle(self) -> Self::Handle;
@@ -117,12 +197,91 @@ pub trait Process: AsHandle + AsRawHandle {
         result != FALSE && unsafe { exit_code.assume_init() } == STILL_ACTIVE
     }
 
+    /// Registers for the exit of this process, so [`ProcessExitWatch::has_exited`] can be checked without querying the process.
+    /// The handle needs the `SYNCHRONIZE` access right.
+    fn watch_exit(&self) -> Result<ProcessExitWatch, io::Error> {
+        ProcessExitWatch::new(self.borrowed().try_to_owned()?, None)
+    }
+
+    /// Registers the given callback to be called on a thread pool thread once this process exits.
+    /// The callback is not called if the returned watch is dropped before the process exits.
+    /// The handle needs the `SYNCHRONIZE` access right.
+    fn on_exit(
+        &self,
+        callback: impl FnOnce() + Send + 'static,
+    ) -> Result<ProcessExitWatch, io::Error> {
+        ProcessExitWatch::new(self.borrowed().try_to_owned()?, Some(Box::new(callback)))
+    }
+
+    /// Suspends all threads of this process.
+    ///
+    /// This can be used to freeze the process while patching or scanning its memory.
+    /// Suspensions nest, so the process only continues running once [`resume`](Process::resume) has been called as often as this method.
+    ///
+    /// If the handle lacks the `PROCESS_SUSPEND_RESUME` access right, the process is reopened with it.
+    fn suspend(&self) -> Result<(), io::Error> {
+        with_suspend_resume_access(self.borrowed(), |handle| {
+            check_status(unsafe { NtSuspendProcess(handle) })
+        })
+    }
+
+    /// Resumes all threads of this process that were suspended by [`suspend`](Process::suspend).
+    ///
+    /// If the handle lacks the `PROCESS_SUSPEND_RESUME` access right, the process is reopened with it.
+    fn resume(&self) -> Result<(), io::Error> {
+        with_suspend_resume_access(self.borrowed(), |handle| {
+            check_status(unsafe { NtResumeProcess(handle) })
+        })
+    }
+
     /// Returns the id of this process.
     fn pid(&self) -> Result<NonZeroU32, io::Error> {
         let result = unsafe { GetProcessId(self.as_raw_handle()) };
         NonZeroU32::new(result).ok_or_else(io::Error::last_os_error)
     }
 
+    /// Returns an identifier for this process that is not affected by pid reuse.
+    fn id(&self) -> Result<ProcessId, io::Error> {
+        ProcessId::of(self)
+    }
+
+    /// Returns the [access rights](https://docs.microsoft.com/en-us/windows/win32/procthread/process-security-and-access-rights) granted to the handle of this process.
+    ///
+    /// The pseudo handle of the current process has all access rights.
+    fn granted_access(&self) -> Result<DWORD, io::Error> {
+        if self.as_raw_handle() == Self::raw_current_handle() {
+            return Ok(PROCESS_ALL_ACCESS);
+        }
+        query_granted_access(self.as_raw_handle())
+    }
+
+    /// Returns the processes that were created by this process and are still running.
+    ///
+    /// # Note
+    /// Processes whose parent pid matches but which were created before this process are ignored,
+    /// as their actual parent exited and its pid was reused by this process.
+    /// Children that cannot be opened, e.g. due to insufficient privileges, are skipped as well.
+    fn children(&self) -> Result<Vec<OwnedProcess>, io::Error> {
+        let id = self.id()?;
+        let mut children = Vec::new();
+        for entry in ProcessIter::new()? {
+            if entry.parent_pid() != id.pid() || entry.pid() == id.pid() {
+                continue;
+            }
+            // the process may have exited since the snapshot was taken or may not be accessible to us.
+            let child = match entry.open().and_then(|child| Ok((child.id()?, child))) {
+                Ok((child_id, child)) if child_id.creation_time() >= id.creation_time() => child,
+                Ok(_) => continue,
+                Err(_err) => {
+                    trace_event!(debug, pid = entry.pid(), error = %_err, "skipping child process that cannot be opened");
+                    continue;
+                }
+            };
+            children.push(child);
+        }
+        Ok(children)
+    }
+
     /// Returns whether this process is running under [WOW64](https://docs.microsoft.com/en-us/windows/win32/winprog64/running-32-bit-applications).
     /// This is the case for 32-bit programs running on a 64-bit platform.
     ///
@@ -147,6 +306,176 @@ pub trait Process: AsHandle + AsRawHandle {
         Ok(is_x32_windows()? || is_x64_windows()? && self.runs_under_wow64()?)
     }
 
+    /// Translates a path as seen by the current process into a path that resolves to the same file in this process.
+    ///
+    /// Paths below the windows directory are subject to the [WOW64 file system redirection](https://docs.microsoft.com/en-us/windows/win32/winprog64/file-system-redirector),
+    /// so e.g. `System32` refers to different directories in a 64-bit injector and a 32-bit target.
+    /// This method rewrites such paths to use `Sysnative` or `SysWOW64` where necessary and returns all other paths unchanged.
+    fn translate_path(&self, path: impl AsRef<Path>) -> Result<PathBuf, io::Error>
+    where
+        Self: Sized,
+    {
+        let path = path.as_ref();
+        if !is_x64_windows()? {
+            return Ok(path.to_path_buf());
+        }
+        let redirected = redirect_system_path(
+            path,
+            &system_windows_dir()?,
+            BorrowedProcess::current().runs_under_wow64()?,
+            self.runs_under_wow64()?,
+        );
+        Ok(redirected.unwrap_or_else(|| path.to_path_buf()))
+    }
+
+    /// Returns the threads of this process that are currently running.
+    ///
+    /// # Note
+    /// The threads are opened with the [`THREAD_ACCESS`](crate::process::THREAD_ACCESS) access rights.
+    fn threads(&self) -> Result<Vec<ProcessThread>, io::Error> {
+        threads_of(self.pid()?.get())
+    }
+
+    /// Returns the main thread of this process, i.e. its oldest running thread.
+    ///
+    /// # Note
+    /// If the original main thread has exited, this returns the oldest remaining thread.
+    fn main_thread(&self) -> Result<Option<ProcessThread>, io::Error> {
+        first_created(self.threads()?)
+    }
+
+    /// Returns the address of the thread environment block (`TEB`) of the thread of this process with the given id.
+    /// For WOW64 processes this is the 32-bit `TEB` the code of the process uses.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if the thread does not belong to this process.
+    fn thread_teb(&self, tid: u32) -> Result<usize, io::Error> {
+        teb::thread_teb(self.borrowed(), tid)
+    }
+
+    /// Returns the committed range of the stack of the thread of this process with the given id, read from its `TEB`.
+    /// The stack grows downwards from the end of the range.
+    fn thread_stack_range(&self, tid: u32) -> Result<Range<usize>, io::Error> {
+        teb::thread_stack_range(self.borrowed(), tid)
+    }
+
+    /// Copies up to the given number of bytes from the top of the committed stack of the thread of this process with
+    /// the given id, e.g. to check whether the thread is executing inside a payload before ejecting it.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dll_syringe::process::{OwnedProcess, Process};
+    ///
+    /// let process = OwnedProcess::find_first_by_name("ExampleProcess").unwrap();
+    /// let payload = process.find_module_by_name("payload.dll").unwrap().unwrap();
+    /// let image = payload.base() as usize..payload.base() as usize + payload.len().unwrap();
+    /// for thread in process.threads().unwrap() {
+    ///     let stack = process.capture_thread_stack(thread.tid(), 64 * 1024).unwrap();
+    ///     if stack.values_in(image.clone()).next().is_some() {
+    ///         println!("thread {} may be executing inside the payload", thread.tid());
+    ///     }
+    /// }
+    /// ```
+    fn capture_thread_stack(&self, tid: u32, max_len: usize) -> Result<StackSnapshot, io::Error> {
+        StackSnapshot::capture(self.borrowed(), tid, max_len)
+    }
+
+    /// Returns whether this process is part of any job, e.g. a [`JobObject`](crate::process::JobObject) or the job of a launcher or service host.
+    /// Use [`JobObject::contains`](crate::process::JobObject::contains) to check for a specific job.
+    fn is_in_job(&self) -> Result<bool, io::Error> {
+        job::is_in_any_job(self.borrowed())
+    }
+
+    /// Returns whether this process is running elevated, i.e. with a full administrator token.
+    fn is_elevated(&self) -> Result<bool, io::Error> {
+        token::is_elevated(self.borrowed())
+    }
+
+    /// Returns the mandatory integrity level of this process.
+    fn integrity_level(&self) -> Result<IntegrityLevel, io::Error> {
+        token::integrity_level(self.borrowed())
+    }
+
+    /// Returns the protection level of this process, or `None` if it is not a protected process.
+    ///
+    /// Protected processes (including protected processes light) can never be injected into from user mode.
+    fn protection_level(&self) -> Result<Option<ProtectionLevel>, io::Error> {
+        protection_level(self.borrowed())
+    }
+
+    /// Returns the mitigation policies of this process.
+    ///
+    /// # Note
+    /// Policies not supported by the current version of windows are reported as disabled,
+    /// as are all policies before Windows 8 (see [`mitigation_policies_supported`](crate::process::mitigation_policies_supported)).
+    fn mitigation_policies(&self) -> Result<MitigationPolicies, io::Error> {
+        mitigation_policies(self.borrowed())
+    }
+
+    /// Returns a report of what is supported when working with this process from the current process,
+    /// e.g. to tell users why injecting into it will fail before trying.
+    ///
+    /// Under Wine the protection level and mitigation policies are not queried, as it supports neither.
+    /// Properties that can not be queried are reported as [unavailable](Capabilities::unavailable).
+    #[must_use]
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::probe(self.borrowed(), is_wine())
+    }
+
+    /// Returns whether this process is running in an [AppContainer](https://docs.microsoft.com/en-us/windows/win32/secauthz/appcontainer-isolation) sandbox (e.g. a UWP app).
+    ///
+    /// Such processes can only load modules that were made accessible to them using [`grant_app_container_access`](crate::grant_app_container_access).
+    /// Before Windows 8, which introduced AppContainers, this always returns `false`.
+    fn is_app_container(&self) -> Result<bool, io::Error> {
+        token::is_app_container(self.borrowed())
+    }
+
+    /// Returns the id of the terminal services session this process is running in.
+    fn session_id(&self) -> Result<u32, io::Error> {
+        let pid = self.pid()?.get();
+        let mut session_id = MaybeUninit::uninit();
+        let result = unsafe { ProcessIdToSessionId(pid, session_id.as_mut_ptr()) };
+        if result == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(unsafe { session_id.assume_init() })
+    }
+
+    /// Returns the command line this process was started with.
+    ///
+    /// # Note
+    /// The command line is read from the memory of the process and may have been modified by the process itself.
+    /// Reading the command line of a 64-bit process from a 32-bit process is not supported.
+    fn command_line(&self) -> Result<OsString, io::Error> {
+        let command_line = RemoteProcessParameters::new(self.borrowed())?.command_line()?;
+        Ok(widestring::U16Str::from_slice(&command_line).to_os_string())
+    }
+
+    /// Returns the current working directory of this process.
+    ///
+    /// # Note
+    /// The directory is read from the memory of the process and may change at any time.
+    /// Reading the working directory of a 64-bit process from a 32-bit process is not supported.
+    fn current_dir(&self) -> Result<PathBuf, io::Error> {
+        let mut current_dir = RemoteProcessParameters::new(self.borrowed())?.current_directory()?;
+        // the directory is stored with a trailing backslash, which is only part of the path for a root like `C:\`.
+        if current_dir.len() > 3 && current_dir.last() == Some(&u16::from(b'\\')) {
+            current_dir.pop();
+        }
+        Ok(PathBuf::from(
+            widestring::U16Str::from_slice(&current_dir).to_os_string(),
+        ))
+    }
+
+    /// Returns the environment variables of this process as `(name, value)` pairs.
+    ///
+    /// # Note
+    /// Reading the environment of a 64-bit process from a 32-bit process is not supported.
+    fn environment(&self) -> Result<Vec<(OsString, OsString)>, io::Error> {
+        let block = RemoteProcessParameters::new(self.borrowed())?.environment_block()?;
+        Ok(parse_environment_block(&block))
+    }
+
     /// Returns the executable path of this process.
     fn path(&self) -> Result<PathBuf, io::Error> {
         win_fill_path_buf_helper(|buf_ptr, buf_size| {
@@ -191,57 +520,170 @@ pub trait Process: AsHandle + AsRawHandle {
         Ok(())
     }
 
-    /// Starts a new thread in this process with the given entry point and argument, and waits for it to finish, returning the exit code.
+    /// Terminates this process with the given exit code.
+    ///
+    /// In contrast to [`kill_with_exit_code`](Process::kill_with_exit_code) this succeeds if the process already exited
+    /// and reports a handle without the `PROCESS_TERMINATE` access right as [`TerminateError::AccessDenied`].
+    /// Termination is asynchronous, see [`terminate_and_wait`](Process::terminate_and_wait) to wait for the process to be torn down.
+    fn terminate(&self, exit_code: u32) -> Result<(), TerminateError> {
+        if unsafe { TerminateProcess(self.as_raw_handle(), exit_code) } != 0 {
+            return Ok(());
+        }
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(ERROR_ACCESS_DENIED as i32) {
+            return Err(err.into());
+        }
+
+        // terminating a process that already exited fails with access denied as well.
+        let mut current_exit_code = MaybeUninit::uninit();
+        let result =
+            unsafe { GetExitCodeProcess(self.as_raw_handle(), current_exit_code.as_mut_ptr()) };
+        if result != FALSE && unsafe { current_exit_code.assume_init() } != STILL_ACTIVE {
+            Ok(())
+        } else {
+            Err(TerminateError::AccessDenied)
+        }
+    }
+
+    /// Terminates this process with the given exit code and waits until it is torn down or the given timeout elapses.
+    /// Returns the exit code of the process, which differs from the given one if the process exited on its own before.
+    ///
+    /// # Errors
+    /// Returns [`TerminateError::TimedOut`] if the process did not exit within the given timeout.
+    fn terminate_and_wait(&self, exit_code: u32, timeout: Duration) -> Result<u32, TerminateError> {
+        self.terminate(exit_code)?;
+        self.wait_for_exit(timeout)?.ok_or(TerminateError::TimedOut)
+    }
+
+    /// Waits until this process exits or the given timeout elapses and returns the exit code of the process or [`None`] if the timeout elapsed.
+    /// Timeouts of [`u32::MAX`] milliseconds or longer wait indefinitely.
+    fn wait_for_exit(&self, timeout: Duration) -> Result<Option<u32>, io::Error> {
+        let timeout_ms = u32::try_from(timeout.as_millis()).unwrap_or(INFINITE);
+        let reason = unsafe { WaitForSingleObject(self.as_raw_handle(), timeout_ms) };
+        if reason == WAIT_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        if reason != WAIT_OBJECT_0 {
+            return Ok(None);
+        }
+
+        let mut exit_code = MaybeUninit::uninit();
+        let result = unsafe { GetExitCodeProcess(self.as_raw_handle(), exit_code.as_mut_ptr()) };
+        if result == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Some(unsafe { exit_code.assume_init() }))
+    }
+
+    /// Starts a new thread in this process with the given entry point and argument, and waits for it to finish, returning how it ended.
     fn run_remote_thread<T>(
         &self,
         remote_fn: extern "system" fn(*mut T) -> u32,
         parameter: *mut T,
-    ) -> Result<u32, io::Error> {
-        let thread_handle = self.start_remote_thread(remote_fn, parameter)?;
+    ) -> Result<RemoteThreadResult, io::Error> {
+        self.run_remote_thread_with_options(remote_fn, parameter, &RemoteThreadOptions::new())
+    }
 
-        let reason = unsafe { WaitForSingleObject(thread_handle.as_raw_handle(), INFINITE) };
+    /// Starts a new thread in this process with the given entry point, argument and options, and waits for it to finish, returning how it ended.
+    ///
+    /// # Note
+    /// [`RemoteThreadOptions::suspended`] is ignored, as the thread has to run for this method to return.
+    fn run_remote_thread_with_options<T>(
+        &self,
+        remote_fn: extern "system" fn(*mut T) -> u32,
+        parameter: *mut T,
+        options: &RemoteThreadOptions,
+    ) -> Result<RemoteThreadResult, io::Error> {
+        self.run_remote_thread_with_timeout(remote_fn, parameter, options, None)
+    }
+
+    /// Starts a new thread in this process with the given entry point, argument and options, and waits for it to finish or
+    /// the given timeout to elapse, returning how it ended or [`RemoteThreadResult::StillActive`] if it is still running.
+    /// Timeouts of [`u32::MAX`] milliseconds or longer and [`None`] wait indefinitely.
+    ///
+    /// # Note
+    /// A thread that is still running keeps using the parameter, so its memory must not be freed until the thread exits.
+    /// [`RemoteThreadOptions::suspended`] is ignored, as the thread has to run for this method to return.
+    fn run_remote_thread_with_timeout<T>(
+        &self,
+        remote_fn: extern "system" fn(*mut T) -> u32,
+        parameter: *mut T,
+        options: &RemoteThreadOptions,
+        timeout: Option<Duration>,
+    ) -> Result<RemoteThreadResult, io::Error> {
+        let options = options.clone().with_suspended(false);
+        let thread = self.start_remote_thread_with_options(remote_fn, parameter, &options)?;
+        trace_span!(DEBUG, "remote_thread", tid = thread.tid());
+
+        let timeout_ms = timeout.map_or(INFINITE, |timeout| {
+            u32::try_from(timeout.as_millis()).unwrap_or(INFINITE)
+        });
+        let reason = unsafe { WaitForSingleObject(thread.as_raw_handle(), timeout_ms) };
         if reason == WAIT_FAILED {
             return Err(io::Error::last_os_error());
         }
+        if reason != WAIT_OBJECT_0 {
+            trace_event!(debug, "remote thread is still running after timeout");
+            return Ok(RemoteThreadResult::StillActive);
+        }
 
         let mut exit_code = MaybeUninit::uninit();
-        let result =
-            unsafe { GetExitCodeThread(thread_handle.as_raw_handle(), exit_code.as_mut_ptr()) };
+        let result = unsafe { GetExitCodeThread(thread.as_raw_handle(), exit_code.as_mut_ptr()) };
         if result == 0 {
             return Err(io::Error::last_os_error());
         }
-        debug_assert_ne!(
-            result as u32, STILL_ACTIVE,
-            "GetExitCodeThread returned STILL_ACTIVE after WaitForSingleObject"
-        );
 
-        Ok(unsafe { exit_code.assume_init() })
+        let exit_code = unsafe { exit_code.assume_init() };
+        trace_event!(
+            debug,
+            exit_code = format_args!("{exit_code:#x}"),
+            "remote thread exited"
+        );
+        Ok(RemoteThreadResult::from_exit_code(exit_code))
     }
 
     /// Starts a new thread in this process with the given entry point and argument and returns the thread handle.
-    #[allow(clippy::not_unsafe_ptr_arg_deref)] // not relevant as ptr is dereffed in the target process and any invalid deref will only result in an io::Error.
     fn start_remote_thread<T>(
         &self,
         remote_fn: unsafe extern "system" fn(*mut T) -> u32,
         parameter: *mut T,
     ) -> Result<OwnedHandle, io::Error> {
-        // create a remote thread that will call LoadLibraryW with payload_path as its argument.
-        let thread_handle = unsafe {
-            CreateRemoteThread(
-                self.as_raw_handle(),
-                ptr::null_mut(),
-                0,
-                Some(mem::transmute(remote_fn)),
-                parameter.cast(),
-                0, // RUN_IMMEDIATELY
-                ptr::null_mut(),
-            )
-        };
-        if thread_handle.is_null() {
-            return Err(io::Error::last_os_error());
+        let thread = self.start_remote_thread_with_options(
+            remote_fn,
+            parameter,
+            &RemoteThreadOptions::new(),
+        )?;
+        Ok(thread.into())
+    }
+
+    /// Starts a new thread in this process with the given entry point, argument and options and returns the thread.
+    #[allow(clippy::not_unsafe_ptr_arg_deref)] // not relevant as ptr is dereffed in the target process and any invalid deref will only result in an io::Error.
+    fn start_remote_thread_with_options<T>(
+        &self,
+        remote_fn: unsafe extern "system" fn(*mut T) -> u32,
+        parameter: *mut T,
+        options: &RemoteThreadOptions,
+    ) -> Result<ProcessThread, io::Error> {
+        if !options.needs_configuration() {
+            return create_remote_thread(self.borrowed(), remote_fn, parameter, options);
         }
 
-        Ok(unsafe { OwnedHandle::from_raw_handle(thread_handle) })
+        // the thread must not run before its priority and affinity are set.
+        let thread = create_remote_thread(
+            self.borrowed(),
+            remote_fn,
+            parameter,
+            &options.clone().with_suspended(true),
+        )?;
+        thread.start_with_options(options)?;
+        trace_event!(
+            debug,
+            tid = thread.tid(),
+            priority = ?options.priority(),
+            affinity_mask = ?options.affinity_mask(),
+            "configured remote thread"
+        );
+        Ok(thread)
     }
 
     /// Searches the modules in this process for one with the given name.
@@ -262,6 +704,9 @@ pub trait Process: AsHandle + AsRawHandle {
     /// The comparison of paths is case-insensitive.
     /// If the extension is omitted, the default library extension `.dll` is appended.
     ///
+    /// The path is resolved using [`canonical_module_path`](crate::process::canonical_module_path) and compared to the module
+    /// paths ignoring case. Paths that still differ are compared by file identity, so hard links are matched as well. For repeated lookups, search a [`ModuleSnapshot`] using [`ModuleSnapshot::find_by_path`] instead.
+    ///
     /// # Note
     /// If the process is currently starting up and has not loaded all its modules, the returned list may be incomplete.
     /// See also [`Process::wait_for_module_by_path`].
@@ -294,6 +739,171 @@ pub trait Process: AsHandle + AsRawHandle {
     where
         Self: Sized;
 
+    /// Returns an iterator over the regions of the virtual address space of this process.
+    #[cfg(feature = "process-memory")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+    fn memory_regions(&self) -> MemoryRegionIter<'_> {
+        MemoryRegionIter::new(self.borrowed())
+    }
+
+    /// Returns a summary of the virtual address space of this process, e.g. to check whether a large payload still fits
+    /// into the address space of a 32-bit process.
+    #[cfg(feature = "process-memory")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+    fn memory_map(&self) -> Result<MemoryMap, io::Error> {
+        MemoryMap::read(self.borrowed())
+    }
+
+    /// Writes the memory in the given range of addresses of this process to the given writer and returns the number of bytes written.
+    ///
+    /// The memory is streamed through a bounded buffer, so arbitrarily large ranges can be dumped.
+    /// Fails if any page in the range can not be read.
+    #[cfg(feature = "process-memory")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+    fn dump_region(&self, range: Range<usize>, writer: impl Write) -> Result<u64, io::Error>
+    where
+        Self: Sized,
+    {
+        crate::process::memory::dump::dump_region(self.borrowed(), range, writer, None)
+    }
+
+    /// Writes the memory in the given range of addresses of this process to the given writer like [`Process::dump_region`],
+    /// but stops with an error of kind [`io::ErrorKind::Interrupted`] once the given token is cancelled and reads no faster
+    /// than its rate limit.
+    #[cfg(feature = "process-memory")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+    fn dump_region_cancellable(
+        &self,
+        range: Range<usize>,
+        writer: impl Write,
+        cancellation: &CancellationToken,
+    ) -> Result<u64, io::Error>
+    where
+        Self: Sized,
+    {
+        crate::process::memory::dump::dump_region(
+            self.borrowed(),
+            range,
+            writer,
+            Some(cancellation),
+        )
+    }
+
+    /// Writes a dump of this process in the given format to the given writer and returns the number of bytes written.
+    ///
+    /// # Note
+    /// The process keeps running while it is dumped, [suspend](Process::suspend) it first to get a consistent snapshot.
+    /// Dumping the current process into memory also dumps the growing output, so write to a file instead.
+    #[cfg(feature = "process-memory")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+    fn dump_all(&self, format: DumpFormat, writer: impl Write) -> Result<u64, io::Error>
+    where
+        Self: Sized,
+    {
+        crate::process::memory::dump::dump_all(self.borrowed(), format, writer, None)
+    }
+
+    /// Writes a dump of this process in the given format to the given writer like [`Process::dump_all`], but stops with an
+    /// error of kind [`io::ErrorKind::Interrupted`] once the given token is cancelled and reads no faster than its rate limit.
+    ///
+    /// # Note
+    /// A [`DumpFormat::Minidump`] is written by the system in one go, so it can only be cancelled before it starts and is not throttled.
+    #[cfg(feature = "process-memory")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+    fn dump_all_cancellable(
+        &self,
+        format: DumpFormat,
+        writer: impl Write,
+        cancellation: &CancellationToken,
+    ) -> Result<u64, io::Error>
+    where
+        Self: Sized,
+    {
+        crate::process::memory::dump::dump_all(self.borrowed(), format, writer, Some(cancellation))
+    }
+
+    /// Follows a multi-level pointer path starting at the given base address and returns the resulting address.
+    ///
+    /// For each offset, the pointer stored at the current address is read and the offset is added to it, i.e. the result of
+    /// `resolve_pointer_chain(base, &[a, b])` is `[[base] + a] + b`.
+    /// Pointers are read as 4 or 8 bytes depending on the bitness of this process.
+    ///
+    /// # Errors
+    /// Returns an error with kind [`io::ErrorKind::InvalidData`] if a null pointer is encountered along the path.
+    #[cfg(feature = "process-memory")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+    fn resolve_pointer_chain(&self, base: usize, offsets: &[usize]) -> Result<usize, io::Error> {
+        let width = PointerWidth::of(self.borrowed())?;
+        let mut address = base;
+        for (level, offset) in offsets.iter().enumerate() {
+            let pointer = width.read_address(self.borrowed(), address)? as usize;
+            if pointer == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "null pointer at level {level} of pointer chain (address {address:#x})"
+                    ),
+                ));
+            }
+            address = pointer.wrapping_add(*offset);
+        }
+        Ok(address)
+    }
+
+    /// Reads a nul-terminated ANSI string of at most `max_len` bytes starting at the given address in this process,
+    /// decoding it from the ANSI code page of the system (which is UTF-8 if the system is configured to use it).
+    /// Use [`read_c_string_bytes`](Self::read_c_string_bytes) for strings in other encodings.
+    ///
+    /// # Errors
+    /// Returns an error with kind [`io::ErrorKind::InvalidData`] if no nul terminator is found within `max_len` bytes or if the string is not valid in the code page.
+    #[cfg(feature = "process-memory")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+    fn read_c_string(&self, address: usize, max_len: usize) -> Result<String, io::Error> {
+        from_ansi_bytes(&self.read_c_string_bytes(address, max_len)?)
+    }
+
+    /// Reads the bytes of a nul-terminated string of at most `max_len` bytes starting at the given address in this process,
+    /// without the terminator.
+    ///
+    /// # Errors
+    /// Returns an error with kind [`io::ErrorKind::InvalidData`] if no nul terminator is found within `max_len` bytes.
+    #[cfg(feature = "process-memory")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+    fn read_c_string_bytes(&self, address: usize, max_len: usize) -> Result<Vec<u8>, io::Error> {
+        read_nul_terminated::<u8>(self.borrowed(), address, max_len)
+    }
+
+    /// Reads a nul-terminated UTF-16 string of at most `max_len` code units starting at the given address in this process.
+    ///
+    /// # Errors
+    /// Returns an error with kind [`io::ErrorKind::InvalidData`] if no nul terminator is found within `max_len` code units.
+    #[cfg(feature = "process-memory")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+    fn read_wide_c_string(&self, address: usize, max_len: usize) -> Result<OsString, io::Error> {
+        let wide = read_nul_terminated::<u16>(self.borrowed(), address, max_len)?;
+        Ok(widestring::U16Str::from_slice(&wide).to_os_string())
+    }
+
+    /// Reads the memory in each of the given ranges of addresses of this process and returns the bytes in the same order.
+    ///
+    /// Overlapping, adjacent and nearby ranges on the same page are coalesced and read with a single call, which is
+    /// considerably faster than reading many small scattered values one by one. Fails if any of the ranges can not be read.
+    #[cfg(feature = "process-memory")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+    fn read_many(&self, ranges: &[Range<usize>]) -> Result<Vec<Vec<u8>>, io::Error> {
+        crate::process::memory::batch::read_many(self.borrowed(), ranges)
+    }
+
+    /// Writes each of the given byte slices to the memory of this process at the address it is paired with.
+    ///
+    /// Overlapping and adjacent writes are coalesced and performed with a single call, where later writes take precedence.
+    /// Writes are not atomic, so if one fails, the writes before it may have been performed.
+    #[cfg(feature = "process-memory")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "process-memory")))]
+    fn write_many(&self, writes: &[(usize, &[u8])]) -> Result<(), io::Error> {
+        crate::process::memory::batch::write_many(self.borrowed(), writes)
+    }
+
     /// Returns a snapshot of all modules currently loaded in this process.
     ///
     /// # Note
@@ -309,9 +919,180 @@ pub trait Process: AsHandle + AsRawHandle {
         }
         Ok(modules)
     }
+
+    /// Returns an iterator over the modules currently loaded in this process.
+    ///
+    /// In contrast to [`modules`](Process::modules) the modules borrow this process instead of each owning a copy of it,
+    /// which makes listing the modules of many processes much cheaper.
+    ///
+    /// # Note
+    /// If the process is currently starting up and has not loaded all its modules yet, the returned list may be incomplete.
+    fn module_iter(&self) -> Result<ProcessModuleIter<'_>, io::Error> {
+        ProcessModuleIter::new(self.borrowed(), ModuleListFilter::All)
+    }
+
+    /// Takes a snapshot of the modules currently loaded in this process.
+    /// Snapshots taken at different points in time can be compared using [`ModuleSnapshot::diff`].
+    fn module_snapshot(&self) -> Result<ModuleSnapshot, io::Error> {
+        ModuleSnapshot::capture(self.borrowed())
+    }
+
+    /// Searches the export tables of the modules in this process for a procedure with the given name and returns the first
+    /// module in load order exporting it together with the address of the procedure.
+    ///
+    /// This finds modules that were renamed or repackaged, whose set of exports usually stays the same. The address is resolved
+    /// like in [`ProcessModule::get_procedure_address_from_exports`], so for a forwarded export it points into the module the
+    /// export is forwarded to. Modules whose export table cannot be read, e.g. because they are unloaded during the search,
+    /// are skipped.
+    fn find_module_exporting(
+        &self,
+        proc_name: impl AsRef<str>,
+    ) -> Result<Option<(BorrowedProcessModule<'_>, RawFunctionPtr)>, io::Error> {
+        let proc_name = proc_name.as_ref();
+        for module in self.module_iter()? {
+            if let Ok(Some(address)) = module.get_procedure_address_from_exports(proc_name) {
+                return Ok(Some((module, address)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Creates a new thread in the given process with the given entry point, argument and options and returns the thread.
+fn create_remote_thread<T>(
+    process: BorrowedProcess<'_>,
+    remote_fn: unsafe extern "system" fn(*mut T) -> u32,
+    parameter: *mut T,
+    options: &RemoteThreadOptions,
+) -> Result<ProcessThread, io::Error> {
+    let creation_flags = if options.suspended() {
+        CREATE_SUSPENDED
+    } else {
+        0 // RUN_IMMEDIATELY
+    };
+    let security_descriptor = options
+        .security_descriptor()
+        .map_or(ptr::null_mut(), SecurityDescriptor::as_ptr);
+    let mut security_attributes = SECURITY_ATTRIBUTES {
+        nLength: mem::size_of::<SECURITY_ATTRIBUTES>() as DWORD,
+        lpSecurityDescriptor: security_descriptor,
+        bInheritHandle: FALSE,
+    };
+    let mut tid = MaybeUninit::uninit();
+    let thread_handle = unsafe {
+        CreateRemoteThread(
+            process.as_raw_handle(),
+            &mut security_attributes,
+            options.stack_size(),
+            Some(mem::transmute::<
+                unsafe extern "system" fn(*mut T) -> u32,
+                unsafe extern "system" fn(*mut c_void) -> DWORD,
+            >(remote_fn)),
+            parameter.cast(),
+            creation_flags,
+            tid.as_mut_ptr(),
+        )
+    };
+    if !thread_handle.is_null() {
+        trace_event!(
+            debug,
+            tid = unsafe { tid.assume_init() },
+            ?remote_fn,
+            suspended = options.suspended(),
+            "started remote thread using CreateRemoteThread"
+        );
+        return Ok(unsafe {
+            ProcessThread::from_remote_parts(
+                OwnedHandle::from_raw_handle(thread_handle),
+                tid.assume_init(),
+                RemoteThreadCreationMethod::CreateRemoteThread,
+            )
+        });
+    }
+
+    // CreateRemoteThread is refused for some targets with restrictive mitigation policies, while the native api still works.
+    let err = io::Error::last_os_error();
+    if !matches!(
+        err.raw_os_error().map(|code| code as u32),
+        Some(ERROR_ACCESS_DENIED | ERROR_NOT_ENOUGH_MEMORY)
+    ) {
+        return Err(err);
+    }
+
+    let mut thread_handle = ptr::null_mut();
+    let mut client_id = MaybeUninit::<ClientId>::uninit();
+    let status = unsafe {
+        RtlCreateUserThread(
+            process.as_raw_handle(),
+            security_descriptor,
+            u8::from(options.suspended()),
+            0,
+            0,
+            options.stack_size(),
+            remote_fn as *mut c_void,
+            parameter.cast(),
+            &mut thread_handle,
+            client_id.as_mut_ptr(),
+        )
+    };
+    // report the original error, as it is more meaningful to users than the fallback's.
+    if check_status(status).is_err() {
+        return Err(err);
+    }
+
+    let tid = unsafe { client_id.assume_init() }.unique_thread as usize as u32;
+    trace_event!(
+        debug,
+        tid,
+        ?remote_fn,
+        suspended = options.suspended(),
+        "started remote thread using RtlCreateUserThread"
+    );
+    Ok(unsafe {
+        ProcessThread::from_remote_parts(
+            OwnedHandle::from_raw_handle(thread_handle),
+            tid,
+            RemoteThreadCreationMethod::RtlCreateUserThread,
+        )
+    })
+}
+
+/// Calls the given function with the handle of the given process, reopening the process with the `PROCESS_SUSPEND_RESUME`
+/// access right if the handle lacks it, as that right is not part of [`PROCESS_INJECTION_ACCESS`].
+fn with_suspend_resume_access(
+    process: BorrowedProcess<'_>,
+    f: impl Fn(ProcessHandle) -> Result<(), io::Error>,
+) -> Result<(), io::Error> {
+    match f(process.as_raw_handle()) {
+        Err(err) if err.raw_os_error() == Some(ERROR_ACCESS_DENIED as i32) => {
+            // the handle we hold keeps the pid from being reused, so reopening it refers to the same process.
+            let process =
+                OwnedProcess::from_pid_with_access(process.pid()?.get(), PROCESS_SUSPEND_RESUME)?;
+            f(process.as_raw_handle())
+        }
+        result => result,
+    }
+}
+
+/// Checks whether the given handle refers to a process and can be used to query information about it.
+pub(crate) fn check_process_handle(handle: ProcessHandle) -> Result<(), io::Error> {
+    if unsafe { GetProcessId(handle) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
 }
 
 fn is_x32_windows() -> Result<bool, io::Error> {
+    // the architecture of the system never changes, so it is only determined once.
+    static IS_X32_WINDOWS: OnceLock<bool> = OnceLock::new();
+    if let Some(&is_x32) = IS_X32_WINDOWS.get() {
+        return Ok(is_x32);
+    }
+    let is_x32 = query_is_x32_windows()?;
+    Ok(*IS_X32_WINDOWS.get_or_init(|| is_x32))
+}
+
+fn query_is_x32_windows() -> Result<bool, io::Error> {
     // TODO: use GetNativeSystemInfo() instead?
     let result = unsafe { GetSystemWow64DirectoryA(ptr::null_mut(), 0) };
     if result == 0 {
@@ -326,10 +1107,28 @@ fn is_x32_windows() -> Result<bool, io::Error> {
     }
 }
 
-fn is_x64_windows() -> Result<bool, io::Error> {
+pub(crate) fn is_x64_windows() -> Result<bool, io::Error> {
     if cfg!(target_arch = "x86_64") {
         Ok(true)
     } else {
         Ok(!is_x32_windows()?)
     }
 }
+
+pub(crate) fn system_windows_dir() -> Result<PathBuf, io::Error> {
+    win_fill_path_buf_helper(|buf_ptr, buf_size| {
+        let result = unsafe { GetSystemWindowsDirectoryW(buf_ptr, buf_size as u32) };
+        if result == 0 {
+            FillPathBufResult::Error(io::Error::last_os_error())
+        } else if result as usize >= buf_size {
+            // the returned size includes the nul terminator if the buffer is too small.
+            FillPathBufResult::BufTooSmall {
+                size_hint: Some(result as usize),
+            }
+        } else {
+            FillPathBufResult::Success {
+                actual_len: result as usize,
+            }
+        }
+    })
+}
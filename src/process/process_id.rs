@@ -0,0 +1,117 @@
+use std::{
+    io,
+    mem::MaybeUninit,
+    time::{Duration, SystemTime},
+};
+
+use winapi::{
+    shared::{minwindef::FILETIME, winerror::ERROR_INVALID_PARAMETER},
+    um::processthreadsapi::GetProcessTimes,
+};
+
+use crate::process::{OwnedProcess, Process};
+
+/// The number of 100ns intervals between the windows epoch (1601-01-01) and the unix epoch (1970-01-01).
+const WINDOWS_TO_UNIX_EPOCH: u64 = 116_444_736_000_000_000;
+
+/// A stable identifier of a process consisting of its pid and its creation time.
+///
+/// Windows reuses process ids once a process has exited, so a cached pid may refer to an unrelated process later on.
+/// Unlike a bare pid, a [`ProcessId`] only ever matches the process it was created from.
+/// With the `serde` feature, it can be serialized to be stored or sent to another process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProcessId {
+    pid: u32,
+    creation_time: u64,
+}
+
+impl ProcessId {
+    /// Returns the identifier of the given process.
+    pub fn of(process: &(impl Process + ?Sized)) -> Result<Self, io::Error> {
+        Ok(Self {
+            pid: process.pid()?.get(),
+            creation_time: creation_time(process)?,
+        })
+    }
+
+    /// Returns the pid part of this identifier.
+    #[must_use]
+    pub const fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Returns the time at which the identified process was created.
+    #[must_use]
+    pub fn creation_time(&self) -> SystemTime {
+        filetime_to_system_time(self.creation_time)
+    }
+
+    /// Returns whether the given process is the process identified by this identifier.
+    pub fn matches(&self, process: &(impl Process + ?Sized)) -> Result<bool, io::Error> {
+        Ok(process.pid()?.get() == self.pid && creation_time(process)? == self.creation_time)
+    }
+
+    /// Opens the process identified by this identifier.
+    ///
+    /// # Errors
+    /// Returns an error with kind [`io::ErrorKind::NotFound`] if the process has exited or the pid now belongs to a different process.
+    pub fn open(&self) -> Result<OwnedProcess, io::Error> {
+        let process = match OwnedProcess::from_pid(self.pid) {
+            Ok(process) => process,
+            Err(err) if err.raw_os_error() == Some(ERROR_INVALID_PARAMETER as i32) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("process {} has exited", self.pid),
+                ));
+            }
+            Err(err) => return Err(err),
+        };
+        if !self.matches(&process)? {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "process id {} has been reused by a different process",
+                    self.pid
+                ),
+            ));
+        }
+        // the process object outlives the process as long as someone holds a handle to it.
+        if !process.is_alive() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("process {} has exited", self.pid),
+            ));
+        }
+        Ok(process)
+    }
+}
+
+fn creation_time(process: &(impl Process + ?Sized)) -> Result<u64, io::Error> {
+    let mut creation_time = MaybeUninit::<FILETIME>::uninit();
+    let mut exit_time = MaybeUninit::uninit();
+    let mut kernel_time = MaybeUninit::uninit();
+    let mut user_time = MaybeUninit::uninit();
+    let result = unsafe {
+        GetProcessTimes(
+            process.as_raw_handle(),
+            creation_time.as_mut_ptr(),
+            exit_time.as_mut_ptr(),
+            kernel_time.as_mut_ptr(),
+            user_time.as_mut_ptr(),
+        )
+    };
+    if result == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(filetime_to_u64(unsafe { creation_time.assume_init() }))
+}
+
+pub(crate) fn filetime_to_u64(filetime: FILETIME) -> u64 {
+    u64::from(filetime.dwHighDateTime) << 32 | u64::from(filetime.dwLowDateTime)
+}
+
+pub(crate) fn filetime_to_system_time(filetime: u64) -> SystemTime {
+    let since_unix_epoch = filetime.saturating_sub(WINDOWS_TO_UNIX_EPOCH);
+    SystemTime::UNIX_EPOCH + Duration::from_nanos(since_unix_epoch.saturating_mul(100))
+}
@@ -0,0 +1,172 @@
+use std::{
+    ffi::{OsStr, OsString},
+    fmt,
+    io,
+    iter::FusedIterator,
+    mem,
+    os::windows::prelude::{AsRawHandle, FromRawHandle, OwnedHandle},
+};
+
+use winapi::{
+    shared::winerror::ERROR_NO_MORE_FILES,
+    um::{
+        handleapi::INVALID_HANDLE_VALUE,
+        tlhelp32::{
+            CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+            TH32CS_SNAPPROCESS,
+        },
+    },
+};
+
+use crate::process::OwnedProcess;
+
+/// A lightweight description of a running process as returned by [`ProcessIter`].
+/// The process is not opened until [`ProcessEntry::open`] is called.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProcessEntry {
+    pid: u32,
+    parent_pid: u32,
+    thread_count: u32,
+    name: OsString,
+}
+
+impl ProcessEntry {
+    /// Returns the id of the process.
+    #[must_use]
+    pub const fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Returns the id of the process that created this process.
+    ///
+    /// # Note
+    /// The parent process may have exited and its id may have been reused since.
+    #[must_use]
+    pub const fn parent_pid(&self) -> u32 {
+        self.parent_pid
+    }
+
+    /// Returns the number of threads of the process at the time the snapshot was taken.
+    #[must_use]
+    pub const fn thread_count(&self) -> u32 {
+        self.thread_count
+    }
+
+    /// Returns the name of the executable file of the process.
+    #[must_use]
+    pub fn name(&self) -> &OsStr {
+        &self.name
+    }
+
+    /// Opens the process described by this entry.
+    ///
+    /// # Note
+    /// The process may have exited since the snapshot was taken, in which case this returns an error.
+    pub fn open(&self) -> Result<OwnedProcess, io::Error> {
+        OwnedProcess::from_pid(self.pid)
+    }
+}
+
+/// An iterator over a snapshot of the processes running on the system.
+///
+/// This struct is created by [`OwnedProcess::iter`].
+pub struct ProcessIter {
+    snapshot: OwnedHandle,
+    entry: PROCESSENTRY32W,
+    started: bool,
+    done: bool,
+}
+
+impl fmt::Debug for ProcessIter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProcessIter")
+            .field("snapshot", &self.snapshot)
+            .field("started", &self.started)
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ProcessIter {
+    /// Takes a snapshot of the currently running processes.
+    pub fn new() -> Result<Self, io::Error> {
+        let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) };
+        if snapshot == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+        let snapshot = unsafe { OwnedHandle::from_raw_handle(snapshot) };
+
+        let mut entry: PROCESSENTRY32W = unsafe { mem::zeroed() };
+        entry.dwSize = mem::size_of::<PROCESSENTRY32W>() as u32;
+
+        Ok(Self {
+            snapshot,
+            entry,
+            started: false,
+            done: false,
+        })
+    }
+
+    fn advance(&mut self) -> Result<bool, io::Error> {
+        let result = unsafe {
+            if self.started {
+                Process32NextW(self.snapshot.as_raw_handle(), &mut self.entry)
+            } else {
+                self.started = true;
+                Process32FirstW(self.snapshot.as_raw_handle(), &mut self.entry)
+            }
+        };
+        if result == 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() == Some(ERROR_NO_MORE_FILES as i32) {
+                return Ok(false);
+            }
+            return Err(err);
+        }
+        Ok(true)
+    }
+
+    /// Advances to the next process for which the given predicate returns `true` and returns its entry.
+    /// The predicate is called with the id and the executable name of each process, so no entries are created for skipped processes.
+    pub(crate) fn next_matching(
+        &mut self,
+        mut predicate: impl FnMut(u32, &[u16]) -> bool,
+    ) -> Option<ProcessEntry> {
+        while !self.done {
+            // an error while walking the snapshot is treated like its end.
+            if !matches!(self.advance(), Ok(true)) {
+                self.done = true;
+                break;
+            }
+
+            let name_len = self
+                .entry
+                .szExeFile
+                .iter()
+                .position(|&c| c == 0)
+                .unwrap_or(self.entry.szExeFile.len());
+            let name = &self.entry.szExeFile[..name_len];
+            if !predicate(self.entry.th32ProcessID, name) {
+                continue;
+            }
+
+            return Some(ProcessEntry {
+                pid: self.entry.th32ProcessID,
+                parent_pid: self.entry.th32ParentProcessID,
+                thread_count: self.entry.cntThreads,
+                name: widestring::U16Str::from_slice(name).to_os_string(),
+            });
+        }
+        None
+    }
+}
+
+impl Iterator for ProcessIter {
+    type Item = ProcessEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_matching(|_, _| true)
+    }
+}
+
+impl FusedIterator for ProcessIter {}
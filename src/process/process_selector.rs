@@ -0,0 +1,142 @@
+use std::{fmt, path::Path};
+
+use crate::process::ProcessEntry;
+
+/// Selects running processes by the properties of their [`ProcessEntry`], e.g. for [`Syringe::inject_into_all`](crate::Syringe::inject_into_all)
+/// [`OwnedProcess::find_all_by_selector`](crate::process::OwnedProcess::find_all_by_selector)
+/// or [`ProcessWatcher`](crate::process::ProcessWatcher).
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::process::{OwnedProcess, ProcessSelector};
+///
+/// // e.g. read from a config file.
+/// let selector = ProcessSelector::glob("game*.exe");
+/// let processes = OwnedProcess::find_all_by_selector(&selector);
+/// ```
+pub enum ProcessSelector {
+    /// Selects processes whose executable has the given file name, ignoring case (e.g. `chrome.exe`).
+    Name(String),
+    /// Selects processes whose executable name contains the given string, like [`OwnedProcess::find_all_by_name`](crate::process::OwnedProcess::find_all_by_name).
+    NameContains(String),
+    /// Selects processes whose executable name matches the given glob pattern, ignoring case (e.g. `game*.exe`).
+    /// The pattern supports `*` to match any sequence of characters and `?` to match any single character.
+    Glob(String),
+    /// Selects processes whose executable name matches the given regular expression.
+    /// The expression is not anchored, so it has to start with `^` and end with `$` to match whole names.
+    #[cfg(feature = "regex")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "regex")))]
+    Regex(regex::Regex),
+    /// Selects the processes with the given ids.
+    Pids(Vec<u32>),
+    /// Selects the processes for which the given predicate returns `true`.
+    Filter(Box<dyn Fn(&ProcessEntry) -> bool + Send + Sync>),
+}
+
+impl ProcessSelector {
+    /// Creates a selector for the processes whose executable has the given file name, ignoring case.
+    #[must_use]
+    pub fn name(name: impl Into<String>) -> Self {
+        Self::Name(name.into())
+    }
+
+    /// Creates a selector for the processes whose executable name matches the given glob pattern, ignoring case.
+    /// See [`ProcessSelector::Glob`] for the supported syntax.
+    #[must_use]
+    pub fn glob(pattern: impl Into<String>) -> Self {
+        Self::Glob(pattern.into())
+    }
+
+    /// Creates a selector for the processes whose executable name matches the given regular expression.
+    #[cfg(feature = "regex")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "regex")))]
+    pub fn regex(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self::Regex(regex::Regex::new(pattern)?))
+    }
+
+    /// Creates a selector for the processes for which the given predicate returns `true`.
+    #[must_use]
+    pub fn filter(predicate: impl Fn(&ProcessEntry) -> bool + Send + Sync + 'static) -> Self {
+        Self::Filter(Box::new(predicate))
+    }
+
+    /// Returns whether the given process is selected.
+    #[must_use]
+    pub fn matches(&self, entry: &ProcessEntry) -> bool {
+        match self {
+            Self::Name(name) => Path::new(entry.name())
+                .file_name()
+                .is_some_and(|file_name| file_name.eq_ignore_ascii_case(name)),
+            Self::NameContains(name) => entry.name().to_string_lossy().contains(name.as_str()),
+            Self::Glob(pattern) => glob_matches(pattern, &entry.name().to_string_lossy()),
+            #[cfg(feature = "regex")]
+            Self::Regex(regex) => regex.is_match(&entry.name().to_string_lossy()),
+            Self::Pids(pids) => pids.contains(&entry.pid()),
+            Self::Filter(predicate) => predicate(entry),
+        }
+    }
+}
+
+impl fmt::Debug for ProcessSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(name) => f.debug_tuple("Name").field(name).finish(),
+            Self::NameContains(name) => f.debug_tuple("NameContains").field(name).finish(),
+            Self::Glob(pattern) => f.debug_tuple("Glob").field(pattern).finish(),
+            #[cfg(feature = "regex")]
+            Self::Regex(regex) => f.debug_tuple("Regex").field(&regex.as_str()).finish(),
+            Self::Pids(pids) => f.debug_tuple("Pids").field(pids).finish(),
+            Self::Filter(_) => f.debug_tuple("Filter").finish_non_exhaustive(),
+        }
+    }
+}
+
+/// Returns whether the given name matches the given glob pattern, ignoring case.
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    let eq = |a: char, b: char| a == b || a.to_lowercase().eq(b.to_lowercase());
+
+    let (mut p, mut n) = (0, 0);
+    // the position after the last `*` and the position in the name it currently matches up to.
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, n));
+            }
+            Some(&c) if c == '?' || eq(c, name[n]) => {
+                p += 1;
+                n += 1;
+            }
+            // let the last `*` match one more character.
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    p = star_p;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matches_wildcards() {
+        assert!(glob_matches("game*.exe", "game.exe"));
+        assert!(glob_matches("game*.exe", "Game-Win64-Shipping.EXE"));
+        assert!(glob_matches("g?me.exe", "game.exe"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("*a*b*", "xxaxxbxx"));
+        assert!(!glob_matches("game*.exe", "game.dll"));
+        assert!(!glob_matches("g?me.exe", "gme.exe"));
+        assert!(!glob_matches("game.exe", "my-game.exe"));
+    }
+}
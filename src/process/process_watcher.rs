@@ -0,0 +1,135 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    io, thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    process::{
+        owned::PROCESS_POLL_INTERVAL, JobObject, OwnedProcess, ProcessIter, ProcessSelector,
+    },
+    utils::trace_event,
+};
+
+/// Watches for processes selected by a [`ProcessSelector`] that are started while the watcher exists,
+/// e.g. to inject into every instance of a program as it is launched.
+///
+/// New processes are detected by polling a snapshot of the running processes,
+/// so a process that starts and exits between two polls is missed.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::process::{ProcessSelector, ProcessWatcher};
+/// use std::time::Duration;
+///
+/// let mut watcher = ProcessWatcher::new(ProcessSelector::glob("game*.exe")).unwrap();
+/// while let Some(process) = watcher.wait_for_next(Duration::from_secs(60)).unwrap() {
+///     println!("started: {process:?}");
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ProcessWatcher {
+    selector: ProcessSelector,
+    known_pids: HashSet<u32>,
+    pending: VecDeque<OwnedProcess>,
+    job: Option<JobObject>,
+}
+
+impl ProcessWatcher {
+    /// Creates a watcher for the processes selected by the given selector that are started from now on.
+    pub fn new(selector: ProcessSelector) -> Result<Self, io::Error> {
+        let known_pids = ProcessIter::new()?.map(|entry| entry.pid()).collect();
+        Ok(Self {
+            selector,
+            known_pids,
+            pending: VecDeque::new(),
+            job: None,
+        })
+    }
+
+    /// Creates a watcher for the processes selected by the given selector,
+    /// which also reports the selected processes that are already running on the first poll.
+    #[must_use]
+    pub fn including_running(selector: ProcessSelector) -> Self {
+        Self {
+            selector,
+            known_pids: HashSet::new(),
+            pending: VecDeque::new(),
+            job: None,
+        }
+    }
+
+    /// Sets a job object every reported process is assigned to as soon as it is found.
+    ///
+    /// With [kill on close](JobObject::set_kill_on_close) the reported processes (and the children they spawn afterwards)
+    /// are terminated once the job is closed, so they are not left behind if the watcher exits or crashes.
+    /// Children a process spawned before it was found are not part of the job.
+    /// Processes that cannot be assigned to the job are not reported.
+    #[must_use]
+    pub fn with_job(mut self, job: JobObject) -> Self {
+        self.job = Some(job);
+        self
+    }
+
+    /// Returns the job object reported processes are assigned to, if any.
+    #[must_use]
+    pub fn job(&self) -> Option<&JobObject> {
+        self.job.as_ref()
+    }
+
+    /// Returns the selector of this watcher.
+    #[must_use]
+    pub fn selector(&self) -> &ProcessSelector {
+        &self.selector
+    }
+
+    /// Returns the selected processes that were started since the last poll and could be opened
+    /// (and assigned to the [job](Self::with_job) of this watcher).
+    pub fn poll(&mut self) -> Result<Vec<OwnedProcess>, io::Error> {
+        self.refresh()?;
+        Ok(self.pending.drain(..).collect())
+    }
+
+    /// Waits for the next selected process to be started, polling until one is found or the given timeout elapses.
+    pub fn wait_for_next(&mut self, timeout: Duration) -> Result<Option<OwnedProcess>, io::Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(process) = self.pending.pop_front() {
+                return Ok(Some(process));
+            }
+            self.refresh()?;
+            if !self.pending.is_empty() {
+                continue;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            // taking a process snapshot is comparatively expensive, so do not poll in a tight loop.
+            thread::sleep(PROCESS_POLL_INTERVAL.min(deadline - now));
+        }
+    }
+
+    fn refresh(&mut self) -> Result<(), io::Error> {
+        let mut running_pids = HashSet::with_capacity(self.known_pids.len());
+        for entry in ProcessIter::new()? {
+            running_pids.insert(entry.pid());
+            if self.known_pids.contains(&entry.pid()) || !self.selector.matches(&entry) {
+                continue;
+            }
+            let Ok(process) = entry.open() else {
+                continue;
+            };
+            if let Some(job) = &self.job {
+                if let Err(_err) = job.assign(&process) {
+                    trace_event!(warn, pid = entry.pid(), error = %_err, "failed to assign watched process to job");
+                    continue;
+                }
+            }
+            self.pending.push_back(process);
+        }
+        // forget exited processes, so a process reusing their id is reported.
+        self.known_pids = running_pids;
+        Ok(())
+    }
+}
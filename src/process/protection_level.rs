@@ -0,0 +1,138 @@
+use std::{fmt, io};
+
+use winapi::shared::{minwindef::ULONG, winerror::ERROR_INVALID_PARAMETER};
+
+use crate::process::{ntdll::query_process_information, BorrowedProcess};
+
+const PROCESS_PROTECTION_INFORMATION_CLASS: ULONG = 61;
+
+/// The kind of protection of a [protected process](https://docs.microsoft.com/en-us/windows/win32/services/protecting-anti-malware-services-).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProtectionKind {
+    /// A protected process light (PPL).
+    ProtectedLight,
+    /// A fully protected process.
+    Protected,
+}
+
+/// The signer that determines which protected processes may access a protected process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProtectionSigner {
+    /// No signer.
+    None,
+    /// Signed with an authenticode signature.
+    Authenticode,
+    /// Signed for dynamic code generation (e.g. .NET native images).
+    CodeGen,
+    /// Signed as an anti-malware service.
+    Antimalware,
+    /// Signed as the local security authority.
+    Lsa,
+    /// Signed as a windows component.
+    Windows,
+    /// Signed as a component of the windows trusted computing base.
+    WinTcb,
+    /// Signed as a windows system component.
+    WinSystem,
+    /// Signed as a store app.
+    App,
+    /// A signer unknown to this crate.
+    Other(u8),
+}
+
+/// The protection level of a protected process, as set by the kernel when the process was created.
+///
+/// Protected processes can not be injected into or have their memory accessed from user mode, regardless of privileges.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProtectionLevel(u8);
+
+impl ProtectionLevel {
+    /// Returns the kind of protection.
+    #[must_use]
+    pub const fn kind(&self) -> ProtectionKind {
+        if self.0 & 0b111 == 1 {
+            ProtectionKind::ProtectedLight
+        } else {
+            ProtectionKind::Protected
+        }
+    }
+
+    /// Returns the signer of the protected process.
+    #[must_use]
+    pub const fn signer(&self) -> ProtectionSigner {
+        match self.0 >> 4 {
+            0 => ProtectionSigner::None,
+            1 => ProtectionSigner::Authenticode,
+            2 => ProtectionSigner::CodeGen,
+            3 => ProtectionSigner::Antimalware,
+            4 => ProtectionSigner::Lsa,
+            5 => ProtectionSigner::Windows,
+            6 => ProtectionSigner::WinTcb,
+            7 => ProtectionSigner::WinSystem,
+            8 => ProtectionSigner::App,
+            signer => ProtectionSigner::Other(signer),
+        }
+    }
+
+    /// Returns the raw `PS_PROTECTION` value.
+    #[must_use]
+    pub const fn raw(&self) -> u8 {
+        self.0
+    }
+}
+
+impl fmt::Debug for ProtectionLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProtectionLevel")
+            .field("kind", &self.kind())
+            .field("signer", &self.signer())
+            .finish()
+    }
+}
+
+impl fmt::Display for ProtectionLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind() {
+            ProtectionKind::ProtectedLight => "PPL",
+            ProtectionKind::Protected => "PP",
+        };
+        write!(f, "{kind} ({:?})", self.signer())
+    }
+}
+
+pub(crate) fn protection_level(
+    process: BorrowedProcess<'_>,
+) -> Result<Option<ProtectionLevel>, io::Error> {
+    match query_process_information::<u8>(process, PROCESS_PROTECTION_INFORMATION_CLASS) {
+        Ok(0) => Ok(None),
+        Ok(protection) => Ok(Some(ProtectionLevel(protection))),
+        // the information class is not supported before windows 8.1, which also does not support protected processes light.
+        Err(err) if err.raw_os_error() == Some(ERROR_INVALID_PARAMETER as i32) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::Process;
+
+    #[test]
+    fn protection_level_decodes_kind_and_signer() {
+        // PsProtectedSignerAntimalware-Light
+        let level = ProtectionLevel(0x31);
+        assert_eq!(level.kind(), ProtectionKind::ProtectedLight);
+        assert_eq!(level.signer(), ProtectionSigner::Antimalware);
+        assert_eq!(level.to_string(), "PPL (Antimalware)");
+
+        // PsProtectedSignerWinTcb
+        let level = ProtectionLevel(0x62);
+        assert_eq!(level.kind(), ProtectionKind::Protected);
+        assert_eq!(level.signer(), ProtectionSigner::WinTcb);
+    }
+
+    #[test]
+    fn current_process_is_not_protected() {
+        assert_eq!(protection_level(BorrowedProcess::current()).unwrap(), None);
+    }
+}
@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+/// A policy for retrying an operation that may not succeed immediately,
+/// e.g. waiting for a module to be loaded by a freshly started process.
+///
+/// An operation is retried until it succeeds, the maximum number of attempts is reached or the timeout elapses,
+/// whichever happens first. The operation is always attempted at least once.
+///
+/// # Note
+/// A `Syringe` only uses its module retry policy (see `Syringe::set_module_retry_policy`) when injecting
+/// from a 64-bit process into a 32-bit (WOW64) process. Otherwise, the required functions are taken from the `kernel32.dll`
+/// of the current process and nothing is waited for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RetryPolicy {
+    max_attempts: Option<u32>,
+    interval: Duration,
+    timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a new policy that retries every 10 milliseconds for up to one second.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            max_attempts: None,
+            interval: Duration::from_millis(10),
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    /// Creates a new policy that attempts the operation only once.
+    #[must_use]
+    pub const fn no_retry() -> Self {
+        Self {
+            max_attempts: Some(1),
+            interval: Duration::ZERO,
+            timeout: Duration::ZERO,
+        }
+    }
+
+    /// Sets the maximum number of attempts or [`None`] to only limit the attempts by the timeout.
+    /// A maximum of zero attempts is treated as a single attempt.
+    #[must_use]
+    pub const fn with_max_attempts(mut self, max_attempts: Option<u32>) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Sets the time to wait between two attempts.
+    #[must_use]
+    pub const fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the total time after which no further attempts are made.
+    #[must_use]
+    pub const fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns the maximum number of attempts or [`None`] if the attempts are only limited by the timeout.
+    #[must_use]
+    pub const fn max_attempts(&self) -> Option<u32> {
+        self.max_attempts
+    }
+
+    /// Returns the time to wait between two attempts.
+    #[must_use]
+    pub const fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns the total time after which no further attempts are made.
+    #[must_use]
+    pub const fn timeout(&self) -> Duration {
+        self.timeout
+    }
+}
@@ -0,0 +1,306 @@
+use std::{
+    ffi::{OsStr, OsString},
+    io, mem,
+    os::windows::{
+        ffi::OsStrExt,
+        prelude::{AsHandle, AsRawHandle, BorrowedHandle, FromRawHandle, OwnedHandle},
+    },
+    path::{Path, PathBuf},
+    ptr,
+};
+
+use winapi::{
+    shared::minwindef::{DWORD, FALSE},
+    um::{
+        processthreadsapi::{CreateProcessW, ResumeThread, PROCESS_INFORMATION, STARTUPINFOW},
+        winbase::{CREATE_SUSPENDED, DEBUG_ONLY_THIS_PROCESS},
+    },
+};
+
+use crate::process::{JobObject, OwnedProcess, Process};
+
+/// A builder for spawning a new process (see [`OwnedProcess::spawn`]).
+///
+/// Unlike [`std::process::Command`] this builder can start the process suspended and gives access to its main thread,
+/// so a module can be injected before any code of the target runs.
+#[derive(Debug, Clone)]
+pub struct ProcessBuilder {
+    program: OsString,
+    args: Vec<OsString>,
+    current_dir: Option<PathBuf>,
+    suspended: bool,
+    debug: bool,
+    job: Option<JobObject>,
+}
+
+impl ProcessBuilder {
+    /// Creates a new builder for spawning the given program.
+    pub fn new(program: impl AsRef<OsStr>) -> Self {
+        Self {
+            program: program.as_ref().to_os_string(),
+            args: Vec::new(),
+            current_dir: None,
+            suspended: false,
+            debug: false,
+            job: None,
+        }
+    }
+
+    /// Adds an argument to pass to the program.
+    pub fn arg(&mut self, arg: impl AsRef<OsStr>) -> &mut Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// Adds multiple arguments to pass to the program.
+    pub fn args(&mut self, args: impl IntoIterator<Item = impl AsRef<OsStr>>) -> &mut Self {
+        self.args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+        self
+    }
+
+    /// Sets the working directory of the spawned process.
+    pub fn current_dir(&mut self, dir: impl AsRef<Path>) -> &mut Self {
+        self.current_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets whether the main thread of the spawned process should start suspended.
+    /// A suspended process does not run until [`SpawnedProcess::resume`] is called.
+    pub fn suspended(&mut self, suspended: bool) -> &mut Self {
+        self.suspended = suspended;
+        self
+    }
+
+    /// Sets whether the spawned process should be debugged by the calling thread.
+    /// The calling thread then has to handle the debug events of the process, e.g. using `WaitForDebugEvent`.
+    pub fn debug(&mut self, debug: bool) -> &mut Self {
+        self.debug = debug;
+        self
+    }
+
+    /// Sets the job object the spawned process is assigned to before it runs any code.
+    /// With [kill on close](JobObject::set_kill_on_close) the process is terminated once the job is closed,
+    /// even if the calling process crashes.
+    pub fn job(&mut self, job: &JobObject) -> &mut Self {
+        self.job = Some(job.clone());
+        self
+    }
+
+    /// Spawns the process.
+    pub fn spawn(&self) -> Result<SpawnedProcess, io::Error> {
+        let mut command_line = self.command_line()?;
+        let current_dir = self
+            .current_dir
+            .as_deref()
+            .map(|dir| to_wide_nul(dir.as_os_str()))
+            .transpose()?;
+
+        let mut startup_info: STARTUPINFOW = unsafe { mem::zeroed() };
+        startup_info.cb = mem::size_of::<STARTUPINFOW>() as DWORD;
+        let mut process_info: PROCESS_INFORMATION = unsafe { mem::zeroed() };
+
+        // the process is started suspended when it is placed in a job, so it cannot create children outside of the job.
+        let mut creation_flags = if self.suspended || self.job.is_some() {
+            CREATE_SUSPENDED
+        } else {
+            0
+        };
+        if self.debug {
+            creation_flags |= DEBUG_ONLY_THIS_PROCESS;
+        }
+        let result = unsafe {
+            CreateProcessW(
+                ptr::null(),
+                command_line.as_mut_ptr(),
+                ptr::null_mut(),
+                ptr::null_mut(),
+                FALSE,
+                creation_flags,
+                ptr::null_mut(),
+                current_dir.as_ref().map_or(ptr::null(), |dir| dir.as_ptr()),
+                &mut startup_info,
+                &mut process_info,
+            )
+        };
+        if result == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let spawned = SpawnedProcess {
+            process: unsafe { OwnedProcess::from_raw_handle(process_info.hProcess) },
+            main_thread: unsafe { OwnedHandle::from_raw_handle(process_info.hThread) },
+            main_thread_id: process_info.dwThreadId,
+        };
+        if let Some(job) = &self.job {
+            if let Err(err) = job.assign(spawned.process()) {
+                let _ = spawned.process().kill();
+                return Err(err);
+            }
+            if !self.suspended {
+                if let Err(err) = spawned.resume() {
+                    let _ = spawned.process().kill();
+                    return Err(err);
+                }
+            }
+        }
+        Ok(spawned)
+    }
+
+    fn command_line(&self) -> Result<Vec<u16>, io::Error> {
+        let mut command_line = Vec::new();
+        append_quoted_arg(&mut command_line, &self.program, true)?;
+        for arg in &self.args {
+            command_line.push(u16::from(b' '));
+            append_quoted_arg(&mut command_line, arg, false)?;
+        }
+        command_line.push(0);
+        Ok(command_line)
+    }
+}
+
+/// A process spawned using a [`ProcessBuilder`] together with its main thread.
+#[derive(Debug)]
+pub struct SpawnedProcess {
+    process: OwnedProcess,
+    main_thread: OwnedHandle,
+    main_thread_id: u32,
+}
+
+impl SpawnedProcess {
+    /// Returns the spawned process.
+    #[must_use]
+    pub fn process(&self) -> &OwnedProcess {
+        &self.process
+    }
+
+    /// Returns a handle to the main thread of the spawned process.
+    #[must_use]
+    pub fn main_thread(&self) -> BorrowedHandle<'_> {
+        self.main_thread.as_handle()
+    }
+
+    /// Returns the id of the main thread of the spawned process.
+    #[must_use]
+    pub fn main_thread_id(&self) -> u32 {
+        self.main_thread_id
+    }
+
+    /// Resumes the main thread of the spawned process, if it was started suspended.
+    pub fn resume(&self) -> Result<(), io::Error> {
+        let result = unsafe { ResumeThread(self.main_thread.as_raw_handle()) };
+        if result == DWORD::MAX {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Returns the spawned process, closing the handle to its main thread.
+    #[must_use]
+    pub fn into_process(self) -> OwnedProcess {
+        self.process
+    }
+
+    /// Splits this instance into the spawned process and the handle to its main thread.
+    #[must_use]
+    pub fn into_parts(self) -> (OwnedProcess, OwnedHandle) {
+        (self.process, self.main_thread)
+    }
+}
+
+fn to_wide_nul(s: &OsStr) -> Result<Vec<u16>, io::Error> {
+    let mut wide = s.encode_wide().collect::<Vec<_>>();
+    if wide.contains(&0) {
+        return Err(nul_error());
+    }
+    wide.push(0);
+    Ok(wide)
+}
+
+fn nul_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "command line arguments cannot contain nul characters",
+    )
+}
+
+// Quotes an argument so that it is parsed back by `CommandLineToArgvW` and the msvc runtime as-is.
+fn append_quoted_arg(command_line: &mut Vec<u16>, arg: &OsStr, is_program: bool) -> io::Result<()> {
+    const QUOTE: u16 = b'"' as u16;
+    const BACKSLASH: u16 = b'\\' as u16;
+
+    let arg = arg.encode_wide().collect::<Vec<_>>();
+    if arg.contains(&0) {
+        return Err(nul_error());
+    }
+
+    let needs_quotes = arg.is_empty()
+        || arg
+            .iter()
+            .any(|&c| c == u16::from(b' ') || c == u16::from(b'\t') || c == QUOTE);
+    if !needs_quotes {
+        command_line.extend_from_slice(&arg);
+        return Ok(());
+    }
+
+    // the program name is parsed without escape sequences, so it cannot contain quotes itself.
+    if is_program {
+        if arg.contains(&QUOTE) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "program path cannot contain quotes",
+            ));
+        }
+        command_line.push(QUOTE);
+        command_line.extend_from_slice(&arg);
+        command_line.push(QUOTE);
+        return Ok(());
+    }
+
+    command_line.push(QUOTE);
+    let mut backslashes = 0;
+    for &c in &arg {
+        if c == BACKSLASH {
+            backslashes += 1;
+        } else {
+            if c == QUOTE {
+                // escape preceding backslashes and the quote itself
+                command_line.extend(std::iter::repeat_n(BACKSLASH, backslashes + 1));
+            }
+            backslashes = 0;
+        }
+        command_line.push(c);
+    }
+    // escape trailing backslashes so they don't escape the closing quote
+    command_line.extend(std::iter::repeat_n(BACKSLASH, backslashes));
+    command_line.push(QUOTE);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command_line(builder: &ProcessBuilder) -> String {
+        let mut command_line = builder.command_line().unwrap();
+        assert_eq!(command_line.pop(), Some(0));
+        String::from_utf16(&command_line).unwrap()
+    }
+
+    #[test]
+    fn command_line_quotes_arguments() {
+        let mut builder = ProcessBuilder::new(r"C:\Program Files\app.exe");
+        builder.args(["plain", "with space", "", r#"say "hi""#, r"trailing\"]);
+        assert_eq!(
+            command_line(&builder),
+            r#""C:\Program Files\app.exe" plain "with space" "" "say \"hi\"" trailing\"#
+        );
+    }
+
+    #[test]
+    fn command_line_escapes_backslashes_before_quotes() {
+        let mut builder = ProcessBuilder::new("app.exe");
+        builder.arg(r#"a\"b"#).arg(r"c d\");
+        assert_eq!(command_line(&builder), r#"app.exe "a\\\"b" "c d\\""#);
+    }
+}
@@ -0,0 +1,186 @@
+use std::{
+    ffi::c_void,
+    fmt, io,
+    mem::{self, MaybeUninit},
+    ops::Range,
+    os::windows::prelude::AsRawHandle,
+    ptr,
+};
+
+use winapi::shared::{
+    minwindef::ULONG,
+    ntdef::{HANDLE, NTSTATUS},
+};
+
+use crate::process::{
+    memory::{PointerWidth, ProcessMemorySlice},
+    ntdll::{check_status, ClientId, NtQueryInformationThread},
+    BorrowedProcess, Process, ProcessThread,
+};
+
+const THREAD_BASIC_INFORMATION_CLASS: ULONG = 0;
+
+/// The offset of the 32-bit `TEB` of a WOW64 thread from its native `TEB`.
+#[cfg(target_pointer_width = "64")]
+const WOW64_TEB_OFFSET: usize = 0x2000;
+
+#[repr(C)]
+struct ThreadBasicInformation {
+    exit_status: NTSTATUS,
+    teb_base_address: *mut c_void,
+    client_id: ClientId,
+    affinity_mask: usize,
+    priority: i32,
+    base_priority: i32,
+}
+
+/// Returns the address of the `TEB` of the thread with the given id, which has to belong to the given process.
+/// For WOW64 processes this is the 32-bit `TEB`.
+pub(crate) fn thread_teb(process: BorrowedProcess<'_>, tid: u32) -> Result<usize, io::Error> {
+    let thread = ProcessThread::from_tid(tid)?;
+    let mut info = MaybeUninit::<ThreadBasicInformation>::uninit();
+    let status = unsafe {
+        NtQueryInformationThread(
+            thread.as_raw_handle(),
+            THREAD_BASIC_INFORMATION_CLASS,
+            info.as_mut_ptr().cast(),
+            mem::size_of::<ThreadBasicInformation>() as ULONG,
+            ptr::null_mut(),
+        )
+    };
+    check_status(status)?;
+    let info = unsafe { info.assume_init() };
+
+    if info.client_id.unique_process != process.pid()?.get() as usize as HANDLE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("thread {tid} does not belong to the process"),
+        ));
+    }
+
+    let teb = info.teb_base_address as usize;
+    #[cfg(target_pointer_width = "64")]
+    if process.runs_under_wow64()? {
+        return Ok(teb + WOW64_TEB_OFFSET);
+    }
+    Ok(teb)
+}
+
+/// Returns the committed range of the stack of the thread with the given id, as recorded in the `NT_TIB` at the start of its `TEB`.
+pub(crate) fn thread_stack_range(
+    process: BorrowedProcess<'_>,
+    tid: u32,
+) -> Result<Range<usize>, io::Error> {
+    let teb = thread_teb(process, tid)?;
+    let width = PointerWidth::of(process)?;
+    // `NT_TIB` starts with `ExceptionList`, followed by `StackBase` and `StackLimit`.
+    let base = width.read_address(process, teb + width.size())?;
+    let limit = width.read_address(process, teb + 2 * width.size())?;
+    Ok(limit as usize..base as usize)
+}
+
+/// A copy of the stack of a thread of a (remote) process, see [`Process::capture_thread_stack`].
+///
+/// The copy is taken without suspending the thread, so a running thread may change its stack while it is read.
+#[derive(Clone, PartialEq, Eq)]
+pub struct StackSnapshot {
+    tid: u32,
+    address: usize,
+    bytes: Vec<u8>,
+    pointer_width: PointerWidth,
+}
+
+impl fmt::Debug for StackSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StackSnapshot")
+            .field("tid", &self.tid)
+            .field("range", &self.range())
+            .finish_non_exhaustive()
+    }
+}
+
+impl StackSnapshot {
+    pub(crate) fn capture(
+        process: BorrowedProcess<'_>,
+        tid: u32,
+        max_len: usize,
+    ) -> Result<Self, io::Error> {
+        let stack = thread_stack_range(process, tid)?;
+        let pointer_width = PointerWidth::of(process)?;
+        // the stack grows downwards, so the top of the stack is closest to its limit.
+        // the length is kept a multiple of the pointer size, so the slots stay aligned.
+        let max_len = max_len - max_len % pointer_width.size();
+        let address = stack.start.max(stack.end.saturating_sub(max_len));
+        let bytes = unsafe {
+            ProcessMemorySlice::from_raw_parts(address as *mut u8, stack.end - address, process)
+        }
+        .read_vec(0, stack.end - address)?;
+        Ok(Self {
+            tid,
+            address,
+            bytes,
+            pointer_width,
+        })
+    }
+
+    /// Returns the id of the thread the stack belongs to.
+    #[must_use]
+    pub const fn tid(&self) -> u32 {
+        self.tid
+    }
+
+    /// Returns the range of addresses the copy was taken from, which ends at the base of the stack.
+    #[must_use]
+    pub fn range(&self) -> Range<usize> {
+        self.address..self.address + self.bytes.len()
+    }
+
+    /// Returns the copied bytes.
+    #[must_use]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the pointer sized values on the stack that lie in the given range together with the address of their slot,
+    /// e.g. to find return addresses into a module by passing the range of its image.
+    ///
+    /// This is a heuristic, as stale values and data that happens to look like an address are reported as well.
+    pub fn values_in(&self, range: Range<usize>) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let size = self.pointer_width.size();
+        self.bytes
+            .chunks_exact(size)
+            .enumerate()
+            .filter_map(move |(i, slot)| {
+                let value = self.pointer_width.decode_address(slot);
+                let value = usize::try_from(value).ok()?;
+                range
+                    .contains(&value)
+                    .then_some((self.address + i * size, value))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winapi::um::processthreadsapi::GetCurrentThreadId;
+
+    #[test]
+    fn stack_of_current_thread_contains_locals() {
+        let process = BorrowedProcess::current();
+        let tid = unsafe { GetCurrentThreadId() };
+        let local = 0u64;
+        let local_address = &local as *const u64 as usize;
+        let slot = std::hint::black_box(&local_address as *const usize as usize);
+
+        let stack = thread_stack_range(process, tid).unwrap();
+        assert!(stack.contains(&local_address));
+
+        let snapshot = StackSnapshot::capture(process, tid, usize::MAX).unwrap();
+        assert_eq!(snapshot.tid(), tid);
+        assert_eq!(snapshot.range(), stack);
+        assert!(snapshot
+            .values_in(local_address..local_address + 1)
+            .any(|(address, value)| address == slot && value == local_address));
+    }
+}
@@ -0,0 +1,605 @@
+use std::{
+    ffi::c_void,
+    fmt, io,
+    iter::FusedIterator,
+    mem::{self, MaybeUninit},
+    os::windows::prelude::{
+        AsHandle, AsRawHandle, BorrowedHandle, FromRawHandle, OwnedHandle, RawHandle,
+    },
+    ptr,
+    sync::Arc,
+    time::SystemTime,
+};
+
+use widestring::U16CString;
+
+use winapi::{
+    shared::{
+        minwindef::{DWORD, FALSE, ULONG},
+        sddl::{ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1},
+        winerror::ERROR_INVALID_PARAMETER,
+    },
+    um::{
+        handleapi::INVALID_HANDLE_VALUE,
+        processthreadsapi::{
+            GetThreadTimes, OpenThread, ResumeThread, SetThreadPriority, SuspendThread,
+            TerminateThread,
+        },
+        tlhelp32::{
+            CreateToolhelp32Snapshot, Thread32First, Thread32Next, TH32CS_SNAPTHREAD, THREADENTRY32,
+        },
+        winbase::LocalFree,
+        winbase::{
+            SetThreadAffinityMask, THREAD_PRIORITY_ABOVE_NORMAL, THREAD_PRIORITY_BELOW_NORMAL,
+            THREAD_PRIORITY_HIGHEST, THREAD_PRIORITY_IDLE, THREAD_PRIORITY_LOWEST,
+            THREAD_PRIORITY_NORMAL, THREAD_PRIORITY_TIME_CRITICAL,
+        },
+        winnt::{
+            PSECURITY_DESCRIPTOR, SYNCHRONIZE, THREAD_GET_CONTEXT, THREAD_QUERY_INFORMATION,
+            THREAD_SET_CONTEXT, THREAD_SUSPEND_RESUME,
+        },
+    },
+};
+
+use crate::{
+    error::{ExceptionCode, ExceptionOrIoError, NtStatus},
+    process::{
+        ntdll::{check_status, NtQueryInformationThread},
+        process_id::{filetime_to_system_time, filetime_to_u64},
+    },
+};
+
+/// The [privileges](https://docs.microsoft.com/en-us/windows/win32/procthread/thread-security-and-access-rights) of the thread handles returned by [`Process::threads`](crate::process::Process::threads).
+pub const THREAD_ACCESS: DWORD = THREAD_QUERY_INFORMATION
+    | THREAD_SUSPEND_RESUME
+    | THREAD_GET_CONTEXT
+    | THREAD_SET_CONTEXT
+    | SYNCHRONIZE;
+
+const THREAD_QUERY_SET_WIN32_START_ADDRESS: ULONG = 9;
+
+/// A thread of a running process.
+/// This struct owns the underlying thread handle.
+#[derive(Debug)]
+pub struct ProcessThread {
+    handle: OwnedHandle,
+    tid: u32,
+    creation_method: Option<RemoteThreadCreationMethod>,
+}
+
+/// The mechanism used to create a thread in another process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RemoteThreadCreationMethod {
+    /// The thread was created using `CreateRemoteThread`.
+    CreateRemoteThread,
+    /// The thread was created using `RtlCreateUserThread` after `CreateRemoteThread` failed.
+    RtlCreateUserThread,
+}
+
+/// How a thread run in a (possibly remote) process ended, derived from its exit code
+/// (see [`Process::run_remote_thread_with_options`](crate::process::Process::run_remote_thread_with_options)).
+///
+/// # Note
+/// Windows reports the value returned by the thread function and the status of a terminated thread through the same exit code,
+/// so a thread function returning a known exception code or an `NTSTATUS` error is reported as if it was terminated.
+/// The stubs of this crate always return 0, so this only matters for functions that are run directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum RemoteThreadResult {
+    /// The thread function returned the given value.
+    Returned(u32),
+    /// The thread was terminated with the given `NTSTATUS` error, e.g. because the loader failed to initialize it.
+    Terminated(NtStatus),
+    /// The thread was terminated by the given unhandled exception.
+    Exception(ExceptionCode),
+    /// The thread was still running when the wait for it timed out.
+    StillActive,
+}
+
+impl RemoteThreadResult {
+    /// Classifies the given exit code of a thread that exited.
+    /// Exit codes are only considered a termination status if they are an `NTSTATUS` error known to the system, so returned
+    /// values like `-1` are not mistaken for one.
+    #[must_use]
+    pub fn from_exit_code(exit_code: u32) -> Self {
+        if let Ok(exception) = ExceptionCode::try_from_code(exit_code) {
+            Self::Exception(exception)
+        } else if NtStatus(exit_code).is_error() && NtStatus(exit_code).to_win32_error().is_some() {
+            Self::Terminated(NtStatus(exit_code))
+        } else {
+            Self::Returned(exit_code)
+        }
+    }
+
+    /// Returns the value returned by the thread function, if it returned.
+    #[must_use]
+    pub const fn returned(self) -> Option<u32> {
+        match self {
+            Self::Returned(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value returned by the thread function or an error describing why it did not return.
+    /// A terminated thread is reported as the io error equivalent to its status.
+    pub fn into_result(self) -> Result<u32, ExceptionOrIoError> {
+        match self {
+            Self::Returned(value) => Ok(value),
+            Self::Exception(exception) => Err(ExceptionOrIoError::Exception(exception)),
+            Self::Terminated(status) => Err(ExceptionOrIoError::Io(io::Error::from_raw_os_error(
+                status.to_win32_error().unwrap_or(status.0) as _,
+            ))),
+            Self::StillActive => Err(ExceptionOrIoError::Io(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "the remote thread is still running",
+            ))),
+        }
+    }
+}
+
+unsafe impl Send for ProcessThread {}
+unsafe impl Sync for ProcessThread {}
+
+impl AsRawHandle for ProcessThread {
+    fn as_raw_handle(&self) -> RawHandle {
+        self.handle.as_raw_handle()
+    }
+}
+
+impl AsHandle for ProcessThread {
+    fn as_handle(&self) -> BorrowedHandle<'_> {
+        self.handle.as_handle()
+    }
+}
+
+impl From<ProcessThread> for OwnedHandle {
+    fn from(thread: ProcessThread) -> Self {
+        thread.handle
+    }
+}
+
+impl ProcessThread {
+    /// Opens the thread with the given id using the [`THREAD_ACCESS`] access rights.
+    pub fn from_tid(tid: u32) -> Result<Self, io::Error> {
+        let handle = unsafe { OpenThread(THREAD_ACCESS, FALSE, tid) };
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            handle: unsafe { OwnedHandle::from_raw_handle(handle) },
+            tid,
+            creation_method: None,
+        })
+    }
+
+    /// Creates a new instance from the given handle and id of a thread created using the given method.
+    ///
+    /// # Safety
+    /// The caller must ensure that the handle is a valid handle for the thread with the given id.
+    pub(crate) unsafe fn from_remote_parts(
+        handle: OwnedHandle,
+        tid: u32,
+        creation_method: RemoteThreadCreationMethod,
+    ) -> Self {
+        Self {
+            handle,
+            tid,
+            creation_method: Some(creation_method),
+        }
+    }
+
+    /// Returns the id of this thread.
+    #[must_use]
+    pub const fn tid(&self) -> u32 {
+        self.tid
+    }
+
+    /// Returns the mechanism used to create this thread, if it was created by this crate in another process.
+    #[must_use]
+    pub const fn creation_method(&self) -> Option<RemoteThreadCreationMethod> {
+        self.creation_method
+    }
+
+    /// Returns the address of the function the thread was started with.
+    pub fn start_address(&self) -> Result<usize, io::Error> {
+        let mut start_address = MaybeUninit::<usize>::uninit();
+        let status = unsafe {
+            NtQueryInformationThread(
+                self.as_raw_handle(),
+                THREAD_QUERY_SET_WIN32_START_ADDRESS,
+                start_address.as_mut_ptr().cast::<c_void>(),
+                mem::size_of::<usize>() as ULONG,
+                ptr::null_mut(),
+            )
+        };
+        check_status(status)?;
+        Ok(unsafe { start_address.assume_init() })
+    }
+
+    /// Returns the time at which this thread was created.
+    pub fn creation_time(&self) -> Result<SystemTime, io::Error> {
+        Ok(filetime_to_system_time(self.raw_creation_time()?))
+    }
+
+    fn raw_creation_time(&self) -> Result<u64, io::Error> {
+        let mut creation_time = MaybeUninit::uninit();
+        let mut exit_time = MaybeUninit::uninit();
+        let mut kernel_time = MaybeUninit::uninit();
+        let mut user_time = MaybeUninit::uninit();
+        let result = unsafe {
+            GetThreadTimes(
+                self.as_raw_handle(),
+                creation_time.as_mut_ptr(),
+                exit_time.as_mut_ptr(),
+                kernel_time.as_mut_ptr(),
+                user_time.as_mut_ptr(),
+            )
+        };
+        if result == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(filetime_to_u64(unsafe { creation_time.assume_init() }))
+    }
+
+    /// Suspends this thread and returns its previous suspend count.
+    pub fn suspend(&self) -> Result<u32, io::Error> {
+        let result = unsafe { SuspendThread(self.as_raw_handle()) };
+        if result == DWORD::MAX {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(result)
+    }
+
+    /// Decrements the suspend count of this thread and returns its previous suspend count.
+    /// The thread continues running once its suspend count reaches zero.
+    pub fn resume(&self) -> Result<u32, io::Error> {
+        let result = unsafe { ResumeThread(self.as_raw_handle()) };
+        if result == DWORD::MAX {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(result)
+    }
+
+    /// Sets the priority of this thread relative to the priority class of its process.
+    ///
+    /// # Note
+    /// This requires the `THREAD_SET_INFORMATION` access right, which threads created by this crate have,
+    /// but threads opened using [`ProcessThread::from_tid`] do not.
+    pub fn set_priority(&self, priority: ThreadPriority) -> Result<(), io::Error> {
+        if unsafe { SetThreadPriority(self.as_raw_handle(), priority.to_raw()) } == FALSE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Restricts this thread to the processors in the given mask, which must be a subset of the affinity mask of its process,
+    /// and returns the previous mask.
+    ///
+    /// # Note
+    /// This requires the `THREAD_SET_INFORMATION` access right, which threads created by this crate have,
+    /// but threads opened using [`ProcessThread::from_tid`] do not.
+    pub fn set_affinity_mask(&self, affinity_mask: usize) -> Result<usize, io::Error> {
+        let previous = unsafe { SetThreadAffinityMask(self.as_raw_handle(), affinity_mask) };
+        if previous == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(previous)
+    }
+
+    /// Applies the priority and affinity of the given options to this newly created, suspended thread and resumes it unless
+    /// the options request a suspended thread. The thread is terminated if the options can not be applied, as it never ran.
+    pub(crate) fn start_with_options(
+        &self,
+        options: &RemoteThreadOptions,
+    ) -> Result<(), io::Error> {
+        let result = (|| {
+            if let Some(priority) = options.priority() {
+                self.set_priority(priority)?;
+            }
+            if let Some(affinity_mask) = options.affinity_mask() {
+                self.set_affinity_mask(affinity_mask)?;
+            }
+            if !options.suspended() {
+                self.resume()?;
+            }
+            Ok(())
+        })();
+        if result.is_err() {
+            unsafe { TerminateThread(self.as_raw_handle(), 0) };
+        }
+        result
+    }
+}
+
+/// The priority of a thread relative to the priority class of its process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ThreadPriority {
+    /// Only runs when the system is idle (`THREAD_PRIORITY_IDLE`).
+    Idle,
+    /// Two levels below normal (`THREAD_PRIORITY_LOWEST`).
+    Lowest,
+    /// One level below normal (`THREAD_PRIORITY_BELOW_NORMAL`), e.g. for helper threads that must not preempt a render thread.
+    BelowNormal,
+    /// The default priority (`THREAD_PRIORITY_NORMAL`).
+    Normal,
+    /// One level above normal (`THREAD_PRIORITY_ABOVE_NORMAL`).
+    AboveNormal,
+    /// Two levels above normal (`THREAD_PRIORITY_HIGHEST`).
+    Highest,
+    /// The highest priority of the priority class (`THREAD_PRIORITY_TIME_CRITICAL`).
+    TimeCritical,
+}
+
+impl ThreadPriority {
+    /// Returns the value of this priority as passed to `SetThreadPriority`.
+    #[must_use]
+    pub const fn to_raw(self) -> i32 {
+        (match self {
+            Self::Idle => THREAD_PRIORITY_IDLE,
+            Self::Lowest => THREAD_PRIORITY_LOWEST,
+            Self::BelowNormal => THREAD_PRIORITY_BELOW_NORMAL,
+            Self::Normal => THREAD_PRIORITY_NORMAL,
+            Self::AboveNormal => THREAD_PRIORITY_ABOVE_NORMAL,
+            Self::Highest => THREAD_PRIORITY_HIGHEST,
+            Self::TimeCritical => THREAD_PRIORITY_TIME_CRITICAL,
+        }) as i32
+    }
+}
+
+/// A security descriptor for the threads created using [`RemoteThreadOptions`], which controls who may access them.
+///
+/// Clones share the same descriptor, which is freed once the last clone is dropped.
+#[derive(Debug, Clone)]
+pub struct SecurityDescriptor(Arc<LocalSecurityDescriptor>);
+
+/// A self-relative security descriptor allocated by the system using `LocalAlloc`.
+#[derive(Debug)]
+struct LocalSecurityDescriptor(PSECURITY_DESCRIPTOR);
+
+// SAFETY: the descriptor is exclusively owned by this struct and never modified after it was created,
+// so it can be read from and freed on any thread.
+unsafe impl Send for LocalSecurityDescriptor {}
+unsafe impl Sync for LocalSecurityDescriptor {}
+
+impl Drop for LocalSecurityDescriptor {
+    fn drop(&mut self) {
+        unsafe { LocalFree(self.0) };
+    }
+}
+
+impl SecurityDescriptor {
+    /// Creates a security descriptor from its [string representation](https://docs.microsoft.com/en-us/windows/win32/secauthz/security-descriptor-string-format),
+    /// e.g. `D:(A;;GA;;;SY)(A;;GA;;;BA)` to only grant access to the system and administrators.
+    pub fn from_sddl(sddl: &str) -> Result<Self, io::Error> {
+        let sddl = U16CString::from_str(sddl)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        let mut descriptor = ptr::null_mut();
+        let result = unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                sddl.as_ptr(),
+                DWORD::from(SDDL_REVISION_1),
+                &mut descriptor,
+                ptr::null_mut(),
+            )
+        };
+        if result == FALSE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self(Arc::new(LocalSecurityDescriptor(descriptor))))
+    }
+
+    /// Returns a pointer to the underlying self-relative `SECURITY_DESCRIPTOR`, which is valid as long as this instance is alive.
+    #[must_use]
+    pub fn as_ptr(&self) -> PSECURITY_DESCRIPTOR {
+        self.0 .0
+    }
+}
+
+/// Options for creating a thread in another process (see [`Process::start_remote_thread_with_options`](crate::process::Process::start_remote_thread_with_options)).
+#[derive(Debug, Clone)]
+pub struct RemoteThreadOptions {
+    stack_size: usize,
+    suspended: bool,
+    security_descriptor: Option<SecurityDescriptor>,
+    priority: Option<ThreadPriority>,
+    affinity_mask: Option<usize>,
+}
+
+impl Default for RemoteThreadOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RemoteThreadOptions {
+    /// Creates a new set of options using the defaults of `CreateRemoteThread`.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            stack_size: 0,
+            suspended: false,
+            security_descriptor: None,
+            priority: None,
+            affinity_mask: None,
+        }
+    }
+
+    /// Sets the initial size of the stack of the thread in bytes.
+    /// A size of zero uses the default stack size of the executable of the target process.
+    #[must_use]
+    pub const fn with_stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = stack_size;
+        self
+    }
+
+    /// Sets whether the thread is created in a suspended state.
+    #[must_use]
+    pub const fn with_suspended(mut self, suspended: bool) -> Self {
+        self.suspended = suspended;
+        self
+    }
+
+    /// Sets the security descriptor of the thread. If [`None`], the thread gets the default security descriptor.
+    #[must_use]
+    pub fn with_security_descriptor(
+        mut self,
+        security_descriptor: Option<SecurityDescriptor>,
+    ) -> Self {
+        self.security_descriptor = security_descriptor;
+        self
+    }
+
+    /// Sets the priority of the thread, e.g. [`ThreadPriority::BelowNormal`] so that remote calls do not preempt
+    /// the threads of the target process. If [`None`], the thread gets the default priority.
+    ///
+    /// The thread is created suspended and resumed once the priority is set, so it never runs with the default priority.
+    #[must_use]
+    pub const fn with_priority(mut self, priority: Option<ThreadPriority>) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Sets the mask of the processors the thread may run on, which must be a subset of the affinity mask of the target process.
+    /// If [`None`], the thread may run on any processor of the target process.
+    ///
+    /// The thread is created suspended and resumed once the affinity is set, so it never runs on other processors.
+    #[must_use]
+    pub const fn with_affinity_mask(mut self, affinity_mask: Option<usize>) -> Self {
+        self.affinity_mask = affinity_mask;
+        self
+    }
+
+    /// Returns the initial size of the stack of the thread in bytes.
+    #[must_use]
+    pub const fn stack_size(&self) -> usize {
+        self.stack_size
+    }
+
+    /// Returns whether the thread is created in a suspended state.
+    #[must_use]
+    pub const fn suspended(&self) -> bool {
+        self.suspended
+    }
+
+    /// Returns the security descriptor of the thread or [`None`] if it gets the default security descriptor.
+    #[must_use]
+    pub const fn security_descriptor(&self) -> Option<&SecurityDescriptor> {
+        self.security_descriptor.as_ref()
+    }
+
+    /// Returns the priority of the thread or [`None`] if it gets the default priority.
+    #[must_use]
+    pub const fn priority(&self) -> Option<ThreadPriority> {
+        self.priority
+    }
+
+    /// Returns the mask of the processors the thread may run on or [`None`] if it may run on any processor.
+    #[must_use]
+    pub const fn affinity_mask(&self) -> Option<usize> {
+        self.affinity_mask
+    }
+
+    /// Returns whether the thread has to be configured after it was created, before it may run.
+    pub(crate) const fn needs_configuration(&self) -> bool {
+        self.priority.is_some() || self.affinity_mask.is_some()
+    }
+}
+
+/// Opens all threads of the process with the given id.
+pub(crate) fn threads_of(pid: u32) -> Result<Vec<ProcessThread>, io::Error> {
+    let mut threads = Vec::new();
+    for tid in ThreadIdIter::new(pid)? {
+        match ProcessThread::from_tid(tid) {
+            Ok(thread) => threads.push(thread),
+            // the thread exited since the snapshot was taken
+            Err(err) if err.raw_os_error() == Some(ERROR_INVALID_PARAMETER as i32) => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(threads)
+}
+
+/// Returns the thread of the given threads that was created first.
+pub(crate) fn first_created(
+    threads: Vec<ProcessThread>,
+) -> Result<Option<ProcessThread>, io::Error> {
+    let mut first = None;
+    for thread in threads {
+        let creation_time = thread.raw_creation_time()?;
+        if first
+            .as_ref()
+            .is_none_or(|(first_creation_time, _)| creation_time < *first_creation_time)
+        {
+            first = Some((creation_time, thread));
+        }
+    }
+    Ok(first.map(|(_, thread)| thread))
+}
+
+/// An iterator over the ids of the threads of a single process in a thread snapshot.
+struct ThreadIdIter {
+    snapshot: OwnedHandle,
+    entry: THREADENTRY32,
+    pid: u32,
+    started: bool,
+    done: bool,
+}
+
+impl fmt::Debug for ThreadIdIter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThreadIdIter")
+            .field("snapshot", &self.snapshot)
+            .field("pid", &self.pid)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ThreadIdIter {
+    fn new(pid: u32) -> Result<Self, io::Error> {
+        // the snapshot always contains the threads of all processes.
+        let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0) };
+        if snapshot == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+        let snapshot = unsafe { OwnedHandle::from_raw_handle(snapshot) };
+
+        let mut entry: THREADENTRY32 = unsafe { mem::zeroed() };
+        entry.dwSize = mem::size_of::<THREADENTRY32>() as u32;
+
+        Ok(Self {
+            snapshot,
+            entry,
+            pid,
+            started: false,
+            done: false,
+        })
+    }
+}
+
+impl Iterator for ThreadIdIter {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let result = unsafe {
+                if self.started {
+                    Thread32Next(self.snapshot.as_raw_handle(), &mut self.entry)
+                } else {
+                    self.started = true;
+                    Thread32First(self.snapshot.as_raw_handle(), &mut self.entry)
+                }
+            };
+            // an error while walking the snapshot is treated like its end.
+            if result == 0 {
+                self.done = true;
+                return None;
+            }
+            if self.entry.th32OwnerProcessID == self.pid {
+                return Some(self.entry.th32ThreadID);
+            }
+        }
+        None
+    }
+}
+
+impl FusedIterator for ThreadIdIter {}
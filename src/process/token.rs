@@ -0,0 +1,220 @@
+use std::{
+    ffi::c_void,
+    fmt, io,
+    mem::{self, MaybeUninit},
+    os::windows::prelude::{AsRawHandle, FromRawHandle, OwnedHandle},
+    ptr,
+    sync::OnceLock,
+};
+
+use winapi::{
+    shared::{
+        minwindef::{DWORD, FALSE},
+        winerror::{ERROR_INSUFFICIENT_BUFFER, ERROR_NOT_ALL_ASSIGNED},
+    },
+    um::{
+        processthreadsapi::OpenProcessToken,
+        securitybaseapi::{
+            AdjustTokenPrivileges, GetSidSubAuthority, GetSidSubAuthorityCount, GetTokenInformation,
+        },
+        winbase::LookupPrivilegeValueW,
+        winnt::{
+            TokenElevation, TokenIntegrityLevel, TokenIsAppContainer, LUID_AND_ATTRIBUTES,
+            SECURITY_MANDATORY_HIGH_RID, SECURITY_MANDATORY_LOW_RID,
+            SECURITY_MANDATORY_MEDIUM_PLUS_RID, SECURITY_MANDATORY_MEDIUM_RID,
+            SECURITY_MANDATORY_SYSTEM_RID, SECURITY_MANDATORY_UNTRUSTED_RID, SE_PRIVILEGE_ENABLED,
+            TOKEN_ADJUST_PRIVILEGES, TOKEN_ELEVATION, TOKEN_INFORMATION_CLASS,
+            TOKEN_MANDATORY_LABEL, TOKEN_PRIVILEGES, TOKEN_QUERY,
+        },
+    },
+};
+
+use widestring::{u16cstr, U16CStr};
+
+use crate::process::{windows_version, BorrowedProcess, Process, WindowsVersion};
+
+const SE_DEBUG_NAME: &U16CStr = u16cstr!("SeDebugPrivilege");
+
+/// The [mandatory integrity level](https://docs.microsoft.com/en-us/windows/win32/secauthz/mandatory-integrity-control) of a process.
+///
+/// A process can generally not open or inject into a process with a higher integrity level.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IntegrityLevel(pub u32);
+
+impl IntegrityLevel {
+    /// The integrity level of anonymous processes.
+    pub const UNTRUSTED: Self = Self(SECURITY_MANDATORY_UNTRUSTED_RID);
+    /// The integrity level of sandboxed processes such as browser renderers.
+    pub const LOW: Self = Self(SECURITY_MANDATORY_LOW_RID);
+    /// The integrity level of processes started by a standard user.
+    pub const MEDIUM: Self = Self(SECURITY_MANDATORY_MEDIUM_RID);
+    /// The integrity level of processes started by a standard user with UI access.
+    pub const MEDIUM_PLUS: Self = Self(SECURITY_MANDATORY_MEDIUM_PLUS_RID);
+    /// The integrity level of elevated processes.
+    pub const HIGH: Self = Self(SECURITY_MANDATORY_HIGH_RID);
+    /// The integrity level of services and system processes.
+    pub const SYSTEM: Self = Self(SECURITY_MANDATORY_SYSTEM_RID);
+    /// The integrity level of protected processes.
+    // SECURITY_MANDATORY_PROTECTED_PROCESS_RID, which is missing from winapi.
+    pub const PROTECTED: Self = Self(0x5000);
+
+    fn name(self) -> Option<&'static str> {
+        Some(match self {
+            Self::UNTRUSTED => "Untrusted",
+            Self::LOW => "Low",
+            Self::MEDIUM => "Medium",
+            Self::MEDIUM_PLUS => "MediumPlus",
+            Self::HIGH => "High",
+            Self::SYSTEM => "System",
+            Self::PROTECTED => "Protected",
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Debug for IntegrityLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "IntegrityLevel::{name}"),
+            None => write!(f, "IntegrityLevel({:#x})", self.0),
+        }
+    }
+}
+
+fn open_token(process: BorrowedProcess<'_>, access: DWORD) -> Result<OwnedHandle, io::Error> {
+    let mut token = ptr::null_mut();
+    let result = unsafe { OpenProcessToken(process.as_raw_handle(), access, &mut token) };
+    if result == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedHandle::from_raw_handle(token) })
+}
+
+fn token_information(
+    token: &OwnedHandle,
+    class: TOKEN_INFORMATION_CLASS,
+) -> Result<Vec<u64>, io::Error> {
+    // u64 elements keep the buffer suitably aligned for the returned structures.
+    let mut buf = Vec::<u64>::new();
+    loop {
+        let mut len = 0;
+        let result = unsafe {
+            GetTokenInformation(
+                token.as_raw_handle(),
+                class,
+                buf.as_mut_ptr().cast::<c_void>(),
+                (buf.len() * mem::size_of::<u64>()) as DWORD,
+                &mut len,
+            )
+        };
+        if result != 0 {
+            return Ok(buf);
+        }
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(ERROR_INSUFFICIENT_BUFFER as i32) {
+            return Err(err);
+        }
+        buf.resize((len as usize).div_ceil(mem::size_of::<u64>()), 0);
+    }
+}
+
+pub(crate) fn is_elevated(process: BorrowedProcess<'_>) -> Result<bool, io::Error> {
+    let token = open_token(process, TOKEN_QUERY)?;
+    let buf = token_information(&token, TokenElevation)?;
+    let elevation = unsafe { &*buf.as_ptr().cast::<TOKEN_ELEVATION>() };
+    Ok(elevation.TokenIsElevated != 0)
+}
+
+pub(crate) fn is_app_container(process: BorrowedProcess<'_>) -> Result<bool, io::Error> {
+    // app containers were added in windows 8, older versions reject the information class.
+    if !windows_version().is_at_least(WindowsVersion::WINDOWS_8) {
+        return Ok(false);
+    }
+    let token = open_token(process, TOKEN_QUERY)?;
+    let buf = token_information(&token, TokenIsAppContainer)?;
+    Ok(unsafe { *buf.as_ptr().cast::<DWORD>() } != 0)
+}
+
+pub(crate) fn integrity_level(process: BorrowedProcess<'_>) -> Result<IntegrityLevel, io::Error> {
+    let token = open_token(process, TOKEN_QUERY)?;
+    let buf = token_information(&token, TokenIntegrityLevel)?;
+    let label = unsafe { &*buf.as_ptr().cast::<TOKEN_MANDATORY_LABEL>() };
+    let sid = label.Label.Sid;
+    // the integrity level is the last sub authority of the label sid.
+    let rid = unsafe {
+        let count = *GetSidSubAuthorityCount(sid);
+        *GetSidSubAuthority(sid, DWORD::from(count) - 1)
+    };
+    Ok(IntegrityLevel(rid))
+}
+
+/// Enables the `SeDebugPrivilege` privilege for the current process.
+///
+/// With this privilege enabled, processes of other users and services can be opened,
+/// as long as the current process is running elevated (the privilege is only held by administrators).
+///
+/// # Note
+/// [`OwnedProcess::from_pid`](crate::process::OwnedProcess::from_pid) automatically tries to enable this privilege if opening a process is denied.
+pub fn enable_debug_privilege() -> Result<(), io::Error> {
+    let token = open_token(
+        BorrowedProcess::current(),
+        TOKEN_ADJUST_PRIVILEGES | TOKEN_QUERY,
+    )?;
+
+    let mut luid = MaybeUninit::uninit();
+    let result =
+        unsafe { LookupPrivilegeValueW(ptr::null(), SE_DEBUG_NAME.as_ptr(), luid.as_mut_ptr()) };
+    if result == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut privileges = TOKEN_PRIVILEGES {
+        PrivilegeCount: 1,
+        Privileges: [LUID_AND_ATTRIBUTES {
+            Luid: unsafe { luid.assume_init() },
+            Attributes: SE_PRIVILEGE_ENABLED,
+        }],
+    };
+    let result = unsafe {
+        AdjustTokenPrivileges(
+            token.as_raw_handle(),
+            FALSE,
+            &mut privileges,
+            0,
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+    };
+    // AdjustTokenPrivileges succeeds even if the privilege is not held, but reports it through the last error.
+    let err = io::Error::last_os_error();
+    if result == 0 || err.raw_os_error() == Some(ERROR_NOT_ALL_ASSIGNED as i32) {
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Tries to enable the `SeDebugPrivilege` privilege once per process lifetime and returns whether it is enabled.
+pub(crate) fn try_enable_debug_privilege_once() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| enable_debug_privilege().is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integrity_levels_are_ordered() {
+        assert!(IntegrityLevel::LOW < IntegrityLevel::MEDIUM);
+        assert!(IntegrityLevel::MEDIUM < IntegrityLevel::HIGH);
+        assert!(IntegrityLevel::HIGH < IntegrityLevel::SYSTEM);
+        assert_eq!(
+            format!("{:?}", IntegrityLevel::HIGH),
+            "IntegrityLevel::High"
+        );
+        assert_eq!(
+            format!("{:?}", IntegrityLevel(0x1234)),
+            "IntegrityLevel(0x1234)"
+        );
+    }
+}
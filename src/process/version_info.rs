@@ -0,0 +1,217 @@
+use std::{fmt, io, mem, path::Path, ptr, slice};
+
+use widestring::{U16CString, U16Str};
+use winapi::{
+    shared::{
+        minwindef::{DWORD, LPVOID},
+        winerror::{ERROR_RESOURCE_DATA_NOT_FOUND, ERROR_RESOURCE_TYPE_NOT_FOUND},
+    },
+    um::winver::{GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW},
+};
+
+/// A version number as stored in the fixed part of a version resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ModuleVersion {
+    /// The major version.
+    pub major: u16,
+    /// The minor version.
+    pub minor: u16,
+    /// The build number.
+    pub build: u16,
+    /// The revision number.
+    pub revision: u16,
+}
+
+impl fmt::Display for ModuleVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}.{}",
+            self.major, self.minor, self.build, self.revision
+        )
+    }
+}
+
+/// The version information of a module, see [`ProcessModule::version_info`](crate::process::ProcessModule::version_info).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleVersionInfo {
+    file_version: Option<ModuleVersion>,
+    product_version: Option<ModuleVersion>,
+    file_version_string: Option<String>,
+    product_version_string: Option<String>,
+    company_name: Option<String>,
+}
+
+impl ModuleVersionInfo {
+    /// Returns the binary file version of the module.
+    #[must_use]
+    pub fn file_version(&self) -> Option<ModuleVersion> {
+        self.file_version
+    }
+
+    /// Returns the binary product version of the module.
+    #[must_use]
+    pub fn product_version(&self) -> Option<ModuleVersion> {
+        self.product_version
+    }
+
+    /// Returns the `FileVersion` string of the module, which may contain more than just the version number.
+    #[must_use]
+    pub fn file_version_string(&self) -> Option<&str> {
+        self.file_version_string.as_deref()
+    }
+
+    /// Returns the `ProductVersion` string of the module, which may contain more than just the version number.
+    #[must_use]
+    pub fn product_version_string(&self) -> Option<&str> {
+        self.product_version_string.as_deref()
+    }
+
+    /// Returns the `CompanyName` string of the module.
+    #[must_use]
+    pub fn company_name(&self) -> Option<&str> {
+        self.company_name.as_deref()
+    }
+
+    /// Reads the version resource of the module file at the given path.
+    /// Returns [`None`] if the file has no version resource.
+    pub(crate) fn read_from_file(path: &Path) -> Result<Option<Self>, io::Error> {
+        let path = U16CString::from_os_str(path.as_os_str())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let size = unsafe { GetFileVersionInfoSizeW(path.as_ptr(), ptr::null_mut()) };
+        if size == 0 {
+            let err = io::Error::last_os_error();
+            return match err.raw_os_error().map(|code| code as DWORD) {
+                Some(ERROR_RESOURCE_DATA_NOT_FOUND | ERROR_RESOURCE_TYPE_NOT_FOUND) => Ok(None),
+                _ => Err(err),
+            };
+        }
+
+        let mut data = vec![0u8; size as usize];
+        if unsafe { GetFileVersionInfoW(path.as_ptr(), 0, size, data.as_mut_ptr().cast()) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let fixed_info = query_bytes(&data, "\\")
+            .filter(|value| value.len() >= mem::size_of::<FixedFileInfo>())
+            .map(|value| unsafe { value.as_ptr().cast::<FixedFileInfo>().read_unaligned() })
+            .filter(|info| info.signature == FixedFileInfo::SIGNATURE);
+
+        // use the first language listed, falling back to US English with the unicode code page.
+        let translation = query_bytes(&data, "\\VarFileInfo\\Translation")
+            .filter(|value| value.len() >= 4)
+            .map_or((0x0409, 0x04B0), |value| {
+                (
+                    u16::from_le_bytes([value[0], value[1]]),
+                    u16::from_le_bytes([value[2], value[3]]),
+                )
+            });
+        let query_string = |name: &str| {
+            query_string(
+                &data,
+                &format!(
+                    "\\StringFileInfo\\{:04x}{:04x}\\{name}",
+                    translation.0, translation.1
+                ),
+            )
+        };
+
+        Ok(Some(Self {
+            file_version: fixed_info
+                .map(|info| ModuleVersion::from_parts(info.file_version_ms, info.file_version_ls)),
+            product_version: fixed_info.map(|info| {
+                ModuleVersion::from_parts(info.product_version_ms, info.product_version_ls)
+            }),
+            file_version_string: query_string("FileVersion"),
+            product_version_string: query_string("ProductVersion"),
+            company_name: query_string("CompanyName"),
+        }))
+    }
+}
+
+impl ModuleVersion {
+    fn from_parts(most_significant: u32, least_significant: u32) -> Self {
+        Self {
+            major: (most_significant >> 16) as u16,
+            minor: most_significant as u16,
+            build: (least_significant >> 16) as u16,
+            revision: least_significant as u16,
+        }
+    }
+}
+
+// VS_FIXEDFILEINFO, which is missing from winapi.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+struct FixedFileInfo {
+    signature: u32,
+    struc_version: u32,
+    file_version_ms: u32,
+    file_version_ls: u32,
+    product_version_ms: u32,
+    product_version_ls: u32,
+    file_flags_mask: u32,
+    file_flags: u32,
+    file_os: u32,
+    file_type: u32,
+    file_subtype: u32,
+    file_date_ms: u32,
+    file_date_ls: u32,
+}
+
+impl FixedFileInfo {
+    const SIGNATURE: u32 = 0xFEEF_04BD;
+}
+
+/// Queries the value at the given path from the given version resource.
+/// Returns a pointer to the value and its length, which is in characters for string values and in bytes otherwise.
+fn query_value(data: &[u8], sub_block: &str) -> Option<(*const u8, usize)> {
+    let sub_block = U16CString::from_str(sub_block).ok()?;
+    let mut value: LPVOID = ptr::null_mut();
+    let mut len = 0;
+    let result = unsafe {
+        VerQueryValueW(
+            data.as_ptr().cast(),
+            sub_block.as_ptr(),
+            &mut value,
+            &mut len,
+        )
+    };
+    if result == 0 || value.is_null() {
+        None
+    } else {
+        Some((value.cast_const().cast(), len as usize))
+    }
+}
+
+fn query_bytes<'a>(data: &'a [u8], sub_block: &str) -> Option<&'a [u8]> {
+    query_value(data, sub_block).map(|(value, len)| unsafe { slice::from_raw_parts(value, len) })
+}
+
+fn query_string(data: &[u8], sub_block: &str) -> Option<String> {
+    let (value, len) = query_value(data, sub_block)?;
+    let value = unsafe { slice::from_raw_parts(value.cast::<u16>(), len) };
+    // the reported length may or may not include the terminator.
+    Some(
+        U16Str::from_slice(value)
+            .to_string_lossy()
+            .trim_end_matches('\0')
+            .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_kernel32_version_info() {
+        let path = Path::new(&std::env::var_os("SystemRoot").unwrap())
+            .join("System32")
+            .join("kernel32.dll");
+        let info = ModuleVersionInfo::read_from_file(&path).unwrap().unwrap();
+        assert!(info.file_version().unwrap().major >= 6);
+        assert_eq!(info.company_name(), Some("Microsoft Corporation"));
+    }
+}
@@ -0,0 +1,64 @@
+use std::io;
+
+use winapi::{
+    shared::{
+        minwindef::{BOOL, LPARAM, TRUE},
+        windef::HWND,
+    },
+    um::winuser::{
+        EnumWindows, GetClassNameW, GetWindowTextLengthW, GetWindowTextW, GetWindowThreadProcessId,
+    },
+};
+
+/// A top-level window as seen during window enumeration.
+#[derive(Debug, Clone)]
+pub(crate) struct TopLevelWindow {
+    pub pid: u32,
+    pub title: String,
+    pub class: String,
+}
+
+/// Returns all top-level windows on the current desktop.
+pub(crate) fn top_level_windows() -> Result<Vec<TopLevelWindow>, io::Error> {
+    unsafe extern "system" fn callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let windows = unsafe { &mut *(lparam as *mut Vec<HWND>) };
+        windows.push(hwnd);
+        TRUE
+    }
+
+    let mut handles = Vec::<HWND>::new();
+    let result = unsafe { EnumWindows(Some(callback), &mut handles as *mut _ as LPARAM) };
+    if result == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(handles
+        .into_iter()
+        .filter_map(|hwnd| {
+            let mut pid = 0;
+            if unsafe { GetWindowThreadProcessId(hwnd, &mut pid) } == 0 {
+                // the window was destroyed in the meantime.
+                return None;
+            }
+            Some(TopLevelWindow {
+                pid,
+                title: window_title(hwnd),
+                class: window_class(hwnd),
+            })
+        })
+        .collect())
+}
+
+fn window_title(hwnd: HWND) -> String {
+    let len = unsafe { GetWindowTextLengthW(hwnd) };
+    let mut buf = vec![0u16; len as usize + 1];
+    let len = unsafe { GetWindowTextW(hwnd, buf.as_mut_ptr(), buf.len() as i32) };
+    String::from_utf16_lossy(&buf[..len.max(0) as usize])
+}
+
+fn window_class(hwnd: HWND) -> String {
+    // class names are limited to 256 characters.
+    let mut buf = [0u16; 257];
+    let len = unsafe { GetClassNameW(hwnd, buf.as_mut_ptr(), buf.len() as i32) };
+    String::from_utf16_lossy(&buf[..len.max(0) as usize])
+}
@@ -0,0 +1,78 @@
+use std::{fmt, mem, sync::OnceLock};
+
+use winapi::um::winnt::OSVERSIONINFOW;
+
+use crate::process::ntdll::RtlGetVersion;
+
+/// The version of the running Windows, see [`windows_version`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WindowsVersion {
+    /// The major version, e.g. `10` for Windows 10 and 11.
+    pub major: u32,
+    /// The minor version.
+    pub minor: u32,
+    /// The build number, e.g. `22000` or higher for Windows 11.
+    pub build: u32,
+}
+
+impl WindowsVersion {
+    /// Windows 7.
+    pub const WINDOWS_7: Self = Self::new(6, 1, 7600);
+    /// Windows 8.
+    pub const WINDOWS_8: Self = Self::new(6, 2, 9200);
+    /// Windows 8.1.
+    pub const WINDOWS_8_1: Self = Self::new(6, 3, 9600);
+    /// The first release of Windows 10.
+    pub const WINDOWS_10: Self = Self::new(10, 0, 10240);
+    /// The first release of Windows 11.
+    pub const WINDOWS_11: Self = Self::new(10, 0, 22000);
+
+    /// Creates a new version from its parts.
+    #[must_use]
+    pub const fn new(major: u32, minor: u32, build: u32) -> Self {
+        Self {
+            major,
+            minor,
+            build,
+        }
+    }
+
+    /// Returns whether this version is the given version or a later one.
+    #[must_use]
+    pub fn is_at_least(self, version: WindowsVersion) -> bool {
+        self >= version
+    }
+}
+
+impl fmt::Display for WindowsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.build)
+    }
+}
+
+/// Returns the version of the running Windows.
+///
+/// Unlike `GetVersionEx`, this reports the real version regardless of the compatibility manifest of the executable.
+#[must_use]
+pub fn windows_version() -> WindowsVersion {
+    static VERSION: OnceLock<WindowsVersion> = OnceLock::new();
+    *VERSION.get_or_init(|| {
+        let mut info: OSVERSIONINFOW = unsafe { mem::zeroed() };
+        info.dwOSVersionInfoSize = mem::size_of::<OSVERSIONINFOW>() as u32;
+        // RtlGetVersion always succeeds.
+        unsafe { RtlGetVersion(&mut info) };
+        WindowsVersion::new(info.dwMajorVersion, info.dwMinorVersion, info.dwBuildNumber)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_version_is_at_least_windows_7() {
+        let version = windows_version();
+        assert!(version.is_at_least(WindowsVersion::WINDOWS_7), "{version}");
+        assert!(WindowsVersion::WINDOWS_11 > WindowsVersion::WINDOWS_10);
+    }
+}
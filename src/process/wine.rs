@@ -0,0 +1,65 @@
+use std::{ffi::CStr, mem, os::raw::c_char, sync::OnceLock};
+
+use cstr::cstr;
+use widestring::u16cstr;
+use winapi::{
+    shared::minwindef::FARPROC,
+    um::libloaderapi::{GetModuleHandleW, GetProcAddress},
+};
+
+type WineGetVersionFn = unsafe extern "C" fn() -> *const c_char;
+
+/// Returns the version of [Wine](https://www.winehq.org/) the current process is running under
+/// or [`None`] if it is running on Windows.
+///
+/// Wine, and Proton which is based on it, is detected through the `wine_get_version` export of its `ntdll.dll`.
+/// Processes running under Wine can only be accessed by processes running in the same Wine instance,
+/// so this also tells whether target processes run under Wine.
+#[must_use]
+pub fn wine_version() -> Option<&'static str> {
+    static WINE_VERSION: OnceLock<Option<String>> = OnceLock::new();
+    WINE_VERSION.get_or_init(query_wine_version).as_deref()
+}
+
+/// Returns whether the current process is running under Wine, see [`wine_version`].
+#[must_use]
+pub fn is_wine() -> bool {
+    wine_version().is_some()
+}
+
+fn query_wine_version() -> Option<String> {
+    let ntdll = unsafe { GetModuleHandleW(u16cstr!("ntdll.dll").as_ptr()) };
+    if ntdll.is_null() {
+        return None;
+    }
+    let wine_get_version = unsafe { GetProcAddress(ntdll, cstr!("wine_get_version").as_ptr()) };
+    if wine_get_version.is_null() {
+        return None;
+    }
+
+    let wine_get_version: WineGetVersionFn =
+        unsafe { mem::transmute::<FARPROC, WineGetVersionFn>(wine_get_version) };
+    let version = unsafe { wine_get_version() };
+    if version.is_null() {
+        return Some(String::new());
+    }
+    Some(
+        unsafe { CStr::from_ptr(version) }
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wine_version_matches_ntdll_exports() {
+        let ntdll = unsafe { GetModuleHandleW(u16cstr!("ntdll.dll").as_ptr()) };
+        let has_export =
+            !unsafe { GetProcAddress(ntdll, cstr!("wine_get_version").as_ptr()) }.is_null();
+        assert_eq!(is_wine(), has_export);
+        assert_eq!(wine_version().is_some(), has_export);
+    }
+}
@@ -0,0 +1,396 @@
+use std::{
+    mem,
+    path::{Path, PathBuf},
+    ptr,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{ExceptionOrIoError, ProfileError},
+    process::{BorrowedProcessModule, OwnedProcess, Process, ProcessSelector},
+    utils::trace_event,
+    InjectOptions, Syringe,
+};
+
+/// The processes an [`InjectionProfile`] applies to, a serializable subset of [`ProcessSelector`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+#[cfg_attr(
+    feature = "doc-cfg",
+    doc(cfg(all(feature = "syringe", feature = "serde")))
+)]
+pub enum ProfileTarget {
+    /// See [`ProcessSelector::Name`].
+    Name(String),
+    /// See [`ProcessSelector::NameContains`].
+    NameContains(String),
+    /// See [`ProcessSelector::Glob`].
+    Glob(String),
+    /// See [`ProcessSelector::Pids`].
+    Pids(Vec<u32>),
+}
+
+impl ProfileTarget {
+    /// Returns the selector for the processes this target describes.
+    #[must_use]
+    pub fn to_selector(&self) -> ProcessSelector {
+        match self {
+            Self::Name(name) => ProcessSelector::Name(name.clone()),
+            Self::NameContains(name) => ProcessSelector::NameContains(name.clone()),
+            Self::Glob(pattern) => ProcessSelector::Glob(pattern.clone()),
+            Self::Pids(pids) => ProcessSelector::Pids(pids.clone()),
+        }
+    }
+}
+
+/// How the payload of an [`InjectionProfile`] is injected.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+#[cfg_attr(
+    feature = "doc-cfg",
+    doc(cfg(all(feature = "syringe", feature = "serde")))
+)]
+pub enum InjectionMethod {
+    /// The payload is injected right away, see [`Syringe::inject`].
+    #[default]
+    RemoteThread,
+    /// The payload is injected and the post-inject calls are made on a worker thread, see [`Syringe::start_worker_thread`].
+    WorkerThread,
+    /// The payload is injected once the target process loaded the given module, see [`Syringe::inject_when_module_loaded`].
+    WhenModuleLoaded {
+        /// The name of the module to wait for.
+        module_name: PathBuf,
+        /// How long to wait for the module in milliseconds.
+        timeout_ms: u64,
+    },
+}
+
+/// A procedure exported by the payload of an [`InjectionProfile`] that is called after the payload was injected.
+///
+/// The procedure is called as `u32 __stdcall procedure(uintptr_t parameter)`, i.e. like the start routine of a thread.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "doc-cfg",
+    doc(cfg(all(feature = "syringe", feature = "serde")))
+)]
+pub struct PostInjectCall {
+    procedure: String,
+    #[serde(default)]
+    parameter: u64,
+}
+
+impl PostInjectCall {
+    /// Creates a new call of the procedure with the given name with the given parameter.
+    #[must_use]
+    pub fn new(procedure: impl Into<String>, parameter: u64) -> Self {
+        Self {
+            procedure: procedure.into(),
+            parameter,
+        }
+    }
+
+    /// Returns the name of the called procedure.
+    #[must_use]
+    pub fn procedure(&self) -> &str {
+        &self.procedure
+    }
+
+    /// Returns the parameter the procedure is called with.
+    #[must_use]
+    pub const fn parameter(&self) -> u64 {
+        self.parameter
+    }
+}
+
+/// A description of how to inject into a kind of target process, which can be deserialized from a configuration file using
+/// any serde format and applied using [`Syringe::apply_profile`].
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::{InjectionProfile, PostInjectCall, ProfileTarget, Syringe};
+///
+/// // e.g. deserialized from a config file.
+/// let profile = InjectionProfile::new(ProfileTarget::Glob("game*.exe".to_string()))
+///     .with_payload_x64("payload_x64.dll")
+///     .with_payload_x86("payload_x86.dll")
+///     .with_post_inject_call(PostInjectCall::new("initialize", 0));
+/// for process in profile.find_targets() {
+///     let syringe = Syringe::for_process(process);
+///     let injection = syringe.apply_profile(&profile).unwrap();
+///     println!("initialize returned {}", injection.call_results()[0]);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "doc-cfg",
+    doc(cfg(all(feature = "syringe", feature = "serde")))
+)]
+pub struct InjectionProfile {
+    target: ProfileTarget,
+    #[serde(default)]
+    payload_x86: Option<PathBuf>,
+    #[serde(default)]
+    payload_x64: Option<PathBuf>,
+    #[serde(default)]
+    method: InjectionMethod,
+    #[serde(default)]
+    copy_to_temp: bool,
+    #[serde(default)]
+    call_timeout_ms: Option<u64>,
+    #[serde(default)]
+    post_inject_calls: Vec<PostInjectCall>,
+}
+
+impl InjectionProfile {
+    /// Creates a new profile for the given target without payloads.
+    #[must_use]
+    pub fn new(target: ProfileTarget) -> Self {
+        Self {
+            target,
+            payload_x86: None,
+            payload_x64: None,
+            method: InjectionMethod::default(),
+            copy_to_temp: false,
+            call_timeout_ms: None,
+            post_inject_calls: Vec::new(),
+        }
+    }
+
+    /// Sets the payload injected into x86 target processes.
+    #[must_use]
+    pub fn with_payload_x86(mut self, payload_path: impl Into<PathBuf>) -> Self {
+        self.payload_x86 = Some(payload_path.into());
+        self
+    }
+
+    /// Sets the payload injected into x64 target processes.
+    #[must_use]
+    pub fn with_payload_x64(mut self, payload_path: impl Into<PathBuf>) -> Self {
+        self.payload_x64 = Some(payload_path.into());
+        self
+    }
+
+    /// Sets how the payload is injected.
+    #[must_use]
+    pub fn with_method(mut self, method: InjectionMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Sets whether the payload is injected from a temporary copy, see [`InjectOptions::with_copy_to_temp`].
+    #[must_use]
+    pub fn with_copy_to_temp(mut self, copy_to_temp: bool) -> Self {
+        self.copy_to_temp = copy_to_temp;
+        self
+    }
+
+    /// Sets how long each post-inject call is waited for, or removes the limit if [`None`] is given.
+    /// Timeouts whose milliseconds do not fit into a [`u64`] are clamped to the longest representable timeout.
+    #[must_use]
+    pub fn with_call_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.call_timeout_ms =
+            timeout.map(|timeout| u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX));
+        self
+    }
+
+    /// Adds a call made after the payload was injected. Calls are made in the order they were added.
+    #[must_use]
+    pub fn with_post_inject_call(mut self, call: PostInjectCall) -> Self {
+        self.post_inject_calls.push(call);
+        self
+    }
+
+    /// Returns the processes this profile applies to.
+    #[must_use]
+    pub const fn target(&self) -> &ProfileTarget {
+        &self.target
+    }
+
+    /// Returns the payload injected into target processes of the given architecture, if any.
+    #[must_use]
+    pub fn payload_path(&self, is_x86: bool) -> Option<&Path> {
+        if is_x86 {
+            self.payload_x86.as_deref()
+        } else {
+            self.payload_x64.as_deref()
+        }
+    }
+
+    /// Returns how the payload is injected.
+    #[must_use]
+    pub const fn method(&self) -> &InjectionMethod {
+        &self.method
+    }
+
+    /// Returns whether the payload is injected from a temporary copy.
+    #[must_use]
+    pub const fn copy_to_temp(&self) -> bool {
+        self.copy_to_temp
+    }
+
+    /// Returns how long each post-inject call is waited for, if limited.
+    #[must_use]
+    pub fn call_timeout(&self) -> Option<Duration> {
+        self.call_timeout_ms.map(Duration::from_millis)
+    }
+
+    /// Returns the calls made after the payload was injected.
+    #[must_use]
+    pub fn post_inject_calls(&self) -> &[PostInjectCall] {
+        &self.post_inject_calls
+    }
+
+    /// Opens the running processes this profile applies to.
+    #[must_use]
+    pub fn find_targets(&self) -> Vec<OwnedProcess> {
+        OwnedProcess::find_all_by_selector(&self.target.to_selector())
+    }
+}
+
+/// The result of applying an [`InjectionProfile`] using [`Syringe::apply_profile`].
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "doc-cfg",
+    doc(cfg(all(feature = "syringe", feature = "serde")))
+)]
+pub struct ProfileInjection<'a> {
+    module: BorrowedProcessModule<'a>,
+    call_results: Vec<u32>,
+}
+
+impl<'a> ProfileInjection<'a> {
+    /// Returns the injected payload.
+    #[must_use]
+    pub const fn module(&self) -> BorrowedProcessModule<'a> {
+        self.module
+    }
+
+    /// Returns the values returned by the post-inject calls in the order they were made.
+    #[must_use]
+    pub fn call_results(&self) -> &[u32] {
+        &self.call_results
+    }
+}
+
+impl Syringe {
+    /// Injects the payload of the given profile for the architecture of the target process using the method of the profile
+    /// and makes its post-inject calls afterwards.
+    ///
+    /// The target of the profile is not checked, see [`InjectionProfile::find_targets`] to find the processes it applies to.
+    /// If a post-inject call fails, the payload stays injected.
+    pub fn apply_profile(
+        &self,
+        profile: &InjectionProfile,
+    ) -> Result<ProfileInjection<'_>, ProfileError> {
+        let is_x86 = self.process().is_x86()?;
+        let payload_path = profile
+            .payload_path(is_x86)
+            .ok_or(ProfileError::NoPayloadForArchitecture { is_x86 })?;
+        trace_event!(info, pid = ?self.process().pid().ok(), payload = %payload_path.display(), method = ?profile.method, "applying injection profile");
+
+        if profile.method == InjectionMethod::WorkerThread {
+            self.start_worker()?;
+            self.remote_allocator
+                .set_worker_call_timeout(profile.call_timeout());
+        }
+        if let InjectionMethod::WhenModuleLoaded {
+            module_name,
+            timeout_ms,
+        } = &profile.method
+        {
+            self.wait_for_module_loaded(module_name, Duration::from_millis(*timeout_ms))?;
+        }
+        let options = InjectOptions::new().with_copy_to_temp(profile.copy_to_temp);
+        let module = self.inject_with_options(payload_path, &options)?.module();
+
+        let mut call_results = Vec::with_capacity(profile.post_inject_calls.len());
+        for call in &profile.post_inject_calls {
+            call_results.push(self.make_post_inject_call(module, call, profile.call_timeout())?);
+        }
+        Ok(ProfileInjection {
+            module,
+            call_results,
+        })
+    }
+
+    fn make_post_inject_call(
+        &self,
+        module: BorrowedProcessModule<'_>,
+        call: &PostInjectCall,
+        timeout: Option<Duration>,
+    ) -> Result<u32, ProfileError> {
+        let procedure = module
+            .get_procedure_address_from_exports(&call.procedure)?
+            .ok_or_else(|| ProfileError::MissingProcedure {
+                procedure: call.procedure.clone(),
+            })?;
+        let call_error = |source| ProfileError::PostInjectCall {
+            procedure: call.procedure.clone(),
+            source,
+        };
+
+        let result = if self.worker_thread_id().is_some() {
+            // the procedure returns a 32 bit value, so the upper half of the returned word is undefined.
+            unsafe { self.call_on_worker_thread(procedure as usize, call.parameter as usize) }
+                .map(|result| result as u32)
+                .map_err(|e| call_error(ExceptionOrIoError::Io(e)))?
+        } else {
+            self.process()
+                .run_remote_thread_with_timeout(
+                    unsafe {
+                        mem::transmute::<*const (), extern "system" fn(*mut u8) -> u32>(
+                            procedure.cast(),
+                        )
+                    },
+                    ptr::null_mut::<u8>().wrapping_add(call.parameter as usize),
+                    &self.remote_thread_options,
+                    timeout,
+                )?
+                .into_result()
+                .map_err(call_error)?
+        };
+        trace_event!(debug, procedure = %call.procedure, result, "made post-inject call");
+        Ok(result)
+    }
+}
+
+#[cfg(all(test, feature = "bincode"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_roundtrips_through_serde() {
+        let profile = InjectionProfile::new(ProfileTarget::Pids(vec![4, 8]))
+            .with_payload_x64("payload_x64.dll")
+            .with_method(InjectionMethod::WhenModuleLoaded {
+                module_name: PathBuf::from("d3d11.dll"),
+                timeout_ms: 5000,
+            })
+            .with_call_timeout(Some(Duration::from_secs(2)))
+            .with_post_inject_call(PostInjectCall::new("initialize", 42));
+
+        let bytes = bincode::serialize(&profile).unwrap();
+        let deserialized: InjectionProfile = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(deserialized, profile);
+        assert_eq!(deserialized.payload_path(true), None);
+        assert_eq!(
+            deserialized.payload_path(false),
+            Some(Path::new("payload_x64.dll"))
+        );
+        assert_eq!(deserialized.call_timeout(), Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn call_timeout_is_clamped() {
+        let profile = InjectionProfile::new(ProfileTarget::Pids(Vec::new()))
+            .with_call_timeout(Some(Duration::MAX));
+        assert_eq!(
+            profile.call_timeout(),
+            Some(Duration::from_millis(u64::MAX))
+        );
+    }
+}
@@ -0,0 +1,598 @@
+#[cfg(feature = "assembler")]
+use iced_x86::{code_asm::*, IcedError};
+#[cfg(not(feature = "assembler"))]
+use {crate::stub_templates, std::convert::Infallible};
+
+use std::{
+    cell::Cell,
+    io,
+    mem::{self, MaybeUninit},
+    os::windows::io::{AsRawHandle, FromRawHandle, OwnedHandle},
+    ptr,
+    time::Duration,
+};
+
+use winapi::{
+    shared::{minwindef::FALSE, winerror::WAIT_TIMEOUT},
+    um::{
+        handleapi::DuplicateHandle,
+        processthreadsapi::{GetCurrentProcess, GetExitCodeThread},
+        synchapi::{CreateEventW, SetEvent, WaitForMultipleObjects, WaitForSingleObject},
+        winbase::{INFINITE, WAIT_FAILED, WAIT_OBJECT_0},
+        winnt::{DUPLICATE_CLOSE_SOURCE, DUPLICATE_SAME_ACCESS, HANDLE, PROCESS_DUP_HANDLE},
+    },
+};
+
+use crate::{
+    process::{
+        memory::{Allocation, DynamicMultiBufferAllocator, ProcessMemorySlice, RawAllocator},
+        BorrowedProcess, Process, ProcessThread, RemoteThreadOptions, RemoteThreadResult,
+    },
+    utils::trace_event,
+};
+
+/// How long stopping a worker waits for its thread to exit before its memory is leaked instead of freed.
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A long-lived thread in a (remote) process that calls functions on request, see [`Syringe::start_worker_thread`](crate::Syringe::start_worker_thread).
+///
+/// The thread runs a small stub that waits for a request event, calls the function in its call block with the parameter
+/// in the block, stores the result and signals a done event, until it is asked to call a null function.
+/// Completion is signaled through the event and a status word in the block rather than the exit code of the thread,
+/// so results are not truncated to 32 bits and a call can be given up on without guessing the state of the thread.
+#[derive(Debug)]
+pub(crate) struct RemoteWorker {
+    process: BorrowedProcess<'static>,
+    thread: ProcessThread,
+    request_event: OwnedHandle,
+    done_event: OwnedHandle,
+    // the handles of the events in the target, closed once the thread exited.
+    remote_events: [usize; 2],
+    word_len: usize,
+    stub: Allocation,
+    call_block: Allocation,
+    exited: Cell<bool>,
+    stopped: Cell<bool>,
+    // set once the thread was asked to exit, so a stop that timed out is not waited for again.
+    stop_requested: Cell<bool>,
+    // set while a call that timed out may still be running, until its done event is consumed.
+    busy: Cell<bool>,
+}
+
+impl RemoteWorker {
+    /// The number of words in the block passed to the stub: `WaitForSingleObject`, `SetEvent`, the request and done events,
+    /// the function to call, its parameter, its return value and the status of the call.
+    const CALL_BLOCK_WORDS: usize = 8;
+    const FUNCTION_WORD: usize = 4;
+    const RESULT_WORD: usize = 6;
+    const STATUS_WORD: usize = 7;
+
+    /// The status of a call that was requested but not yet picked up by the stub.
+    const STATUS_REQUESTED: u32 = 0;
+    /// The status of a call the stub is currently making.
+    const STATUS_RUNNING: u32 = 1;
+    /// The status of a call that returned and whose result was stored.
+    const STATUS_DONE: u32 = 2;
+
+    /// Starts a new worker thread in the given process, placing its stub and call block in memory of the given page allocator.
+    /// The given functions have to be the addresses of `WaitForSingleObject` and `SetEvent` in the process.
+    pub fn start(
+        process: BorrowedProcess<'static>,
+        pages: &mut DynamicMultiBufferAllocator<'static>,
+        wait_for_single_object: usize,
+        set_event: usize,
+        options: &RemoteThreadOptions,
+    ) -> Result<Self, io::Error> {
+        let is_x86 = process.is_x86()?;
+        let request_event = create_event()?;
+        let done_event = create_event()?;
+
+        // the handle of the target may lack the right to duplicate handles into it.
+        let dup_process = process.duplicate_with_access(PROCESS_DUP_HANDLE)?;
+        let remote_request_event = duplicate_into(&request_event, dup_process.as_raw_handle())?;
+        let remote_done_event = match duplicate_into(&done_event, dup_process.as_raw_handle()) {
+            Ok(handle) => handle,
+            Err(err) => {
+                close_remote_handle(dup_process.as_raw_handle(), remote_request_event);
+                return Err(err);
+            }
+        };
+        let remote_events = [remote_request_event, remote_done_event];
+
+        let result = Self::place(process, pages, is_x86).and_then(|(stub, call_block)| {
+            let word_len = if is_x86 { 4 } else { 8 };
+            let words = [
+                wait_for_single_object,
+                set_event,
+                remote_request_event,
+                remote_done_event,
+                0,
+                0,
+                0,
+                0,
+            ];
+            let mut block = Vec::with_capacity(call_block.len);
+            for word in words {
+                block.extend_from_slice(&(word as u64).to_le_bytes()[..word_len]);
+            }
+            if let Err(err) = slice_of(process, &call_block).write(0, &block) {
+                pages.free(&stub);
+                pages.free(&call_block);
+                return Err(err);
+            }
+
+            let thread = process.start_remote_thread_with_options(
+                unsafe {
+                    mem::transmute::<*mut u8, unsafe extern "system" fn(*mut u8) -> u32>(
+                        stub.as_raw_ptr(),
+                    )
+                },
+                call_block.as_raw_ptr(),
+                &options.clone().with_suspended(false),
+            );
+            match thread {
+                Ok(thread) => Ok((thread, stub, call_block, word_len)),
+                Err(err) => {
+                    pages.free(&stub);
+                    pages.free(&call_block);
+                    Err(err)
+                }
+            }
+        });
+        let (thread, stub, call_block, word_len) = match result {
+            Ok(parts) => parts,
+            Err(err) => {
+                for handle in remote_events {
+                    close_remote_handle(dup_process.as_raw_handle(), handle);
+                }
+                return Err(err);
+            }
+        };
+        trace_event!(debug, tid = thread.tid(), stub = ?stub.as_raw_ptr(), "started remote worker thread");
+
+        Ok(Self {
+            process,
+            thread,
+            request_event,
+            done_event,
+            remote_events,
+            word_len,
+            stub,
+            call_block,
+            exited: Cell::new(false),
+            stopped: Cell::new(false),
+            stop_requested: Cell::new(false),
+            busy: Cell::new(false),
+        })
+    }
+
+    fn place(
+        process: BorrowedProcess<'static>,
+        pages: &mut DynamicMultiBufferAllocator<'static>,
+        is_x86: bool,
+    ) -> Result<(Allocation, Allocation), io::Error> {
+        let code = if is_x86 {
+            Self::build_code_x86()
+        } else {
+            Self::build_code_x64()
+        }
+        .unwrap();
+        let word_len = if is_x86 { 4 } else { 8 };
+
+        let stub = pages.alloc(code.len())?;
+        let call_block = match pages.alloc(Self::CALL_BLOCK_WORDS * word_len) {
+            Ok(call_block) => call_block,
+            Err(err) => {
+                pages.free(&stub);
+                return Err(err);
+            }
+        };
+        let stub_memory = slice_of(process, &stub);
+        if let Err(err) = stub_memory
+            .write(0, &code)
+            .and_then(|()| stub_memory.flush_instruction_cache())
+        {
+            pages.free(&stub);
+            pages.free(&call_block);
+            return Err(err);
+        }
+        Ok((stub, call_block))
+    }
+
+    /// Returns the id of the worker thread.
+    pub const fn tid(&self) -> u32 {
+        self.thread.tid()
+    }
+
+    /// Returns whether the worker thread has exited, after which it cannot be used anymore.
+    pub fn has_exited(&self) -> bool {
+        self.exited.get()
+    }
+
+    /// Returns whether the worker thread is still making a call that timed out, during which it cannot take further calls.
+    pub fn is_busy(&self) -> bool {
+        if !self.busy.get() {
+            return false;
+        }
+        // the done event is the authority on completion, the status word is only set before it is signaled.
+        let reason = unsafe { WaitForSingleObject(self.done_event.as_raw_handle().cast(), 0) };
+        if reason == WAIT_OBJECT_0 {
+            trace_event!(
+                debug,
+                tid = self.tid(),
+                "timed out call on remote worker thread completed"
+            );
+            self.busy.set(false);
+        }
+        self.busy.get()
+    }
+
+    /// Waits for at most the given timeout for a call that timed out to return and returns whether the worker thread is no longer
+    /// making it, which is also the case if the thread exited in the meantime.
+    pub fn wait_for_call(&self, timeout: Option<Duration>) -> Result<bool, io::Error> {
+        if !self.busy.get() || self.has_exited() {
+            return Ok(true);
+        }
+        let handles = [
+            self.done_event.as_raw_handle().cast(),
+            self.thread.as_raw_handle().cast(),
+        ];
+        let timeout_ms = timeout.map_or(INFINITE, |timeout| {
+            u32::try_from(timeout.as_millis()).unwrap_or(INFINITE)
+        });
+        let reason = unsafe { WaitForMultipleObjects(2, handles.as_ptr(), FALSE, timeout_ms) };
+        match reason {
+            WAIT_OBJECT_0 => {
+                self.busy.set(false);
+                Ok(true)
+            }
+            WAIT_TIMEOUT => Ok(false),
+            WAIT_FAILED => Err(io::Error::last_os_error()),
+            _ => {
+                self.exited.set(true);
+                Ok(true)
+            }
+        }
+    }
+
+    /// Calls the given function with the given parameter on the worker thread, waits for it to return and returns the full word
+    /// it returned.
+    ///
+    /// If the thread exits during the call, e.g. because the function raised an exception or exited the thread,
+    /// the exit code of the thread is returned like the exit code of a thread created for the call.
+    /// If the given timeout elapses, an error with kind [`io::ErrorKind::TimedOut`] is returned and the worker stays busy until
+    /// the call returns, see [`RemoteWorker::is_busy`].
+    pub fn call(
+        &self,
+        function: usize,
+        parameter: usize,
+        timeout: Option<Duration>,
+    ) -> Result<u64, io::Error> {
+        if self.has_exited() {
+            return Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "the remote worker thread has exited",
+            ));
+        }
+        if self.is_busy() {
+            return Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "the remote worker thread is still making a call that timed out",
+            ));
+        }
+
+        let mut request = Vec::with_capacity(4 * self.word_len);
+        for word in [function, parameter, 0, Self::STATUS_REQUESTED as usize] {
+            request.extend_from_slice(&(word as u64).to_le_bytes()[..self.word_len]);
+        }
+        slice_of(self.process, &self.call_block)
+            .write(Self::FUNCTION_WORD * self.word_len, &request)?;
+
+        if unsafe { SetEvent(self.request_event.as_raw_handle().cast()) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let handles = [
+            self.done_event.as_raw_handle().cast(),
+            self.thread.as_raw_handle().cast(),
+        ];
+        let timeout_ms = timeout.map_or(INFINITE, |timeout| {
+            u32::try_from(timeout.as_millis()).unwrap_or(INFINITE)
+        });
+        let reason = unsafe { WaitForMultipleObjects(2, handles.as_ptr(), FALSE, timeout_ms) };
+        match reason {
+            WAIT_OBJECT_0 => self.read_word(Self::RESULT_WORD),
+            WAIT_TIMEOUT => {
+                self.busy.set(true);
+                let status = self.read_word(Self::STATUS_WORD)? as u32;
+                trace_event!(
+                    warn,
+                    tid = self.tid(),
+                    status,
+                    "call on remote worker thread timed out"
+                );
+                let message = match status {
+                    Self::STATUS_REQUESTED => {
+                        "the remote worker thread did not pick up the call in time"
+                    }
+                    Self::STATUS_RUNNING => {
+                        "the call on the remote worker thread did not return in time"
+                    }
+                    _ => "the call on the remote worker thread did not complete in time",
+                };
+                Err(io::Error::new(io::ErrorKind::TimedOut, message))
+            }
+            WAIT_FAILED => Err(io::Error::last_os_error()),
+            _ => {
+                self.exited.set(true);
+                let exit_code = self.exit_code()?;
+                trace_event!(
+                    warn,
+                    tid = self.tid(),
+                    exit_code = format_args!("{exit_code:#x}"),
+                    "remote worker thread exited during a call"
+                );
+                if self.read_word(Self::STATUS_WORD)? as u32 == Self::STATUS_DONE {
+                    // the call returned before the thread was terminated, so its result is complete.
+                    self.read_word(Self::RESULT_WORD)
+                } else if let RemoteThreadResult::Exception(_) =
+                    RemoteThreadResult::from_exit_code(exit_code)
+                {
+                    Ok(exit_code.into())
+                } else {
+                    Err(io::Error::new(
+                        io::ErrorKind::BrokenPipe,
+                        format!("the remote worker thread exited with code {exit_code:#x}"),
+                    ))
+                }
+            }
+        }
+    }
+
+    fn read_word(&self, index: usize) -> Result<u64, io::Error> {
+        let mut word = [0; 8];
+        slice_of(self.process, &self.call_block)
+            .read(index * self.word_len, &mut word[..self.word_len])?;
+        Ok(u64::from_le_bytes(word))
+    }
+
+    /// Returns the number of bytes of the memory used by the worker in the target.
+    pub fn memory_len(&self) -> usize {
+        self.stub.len + self.call_block.len
+    }
+
+    /// Asks the worker thread to exit and waits for it, then frees its memory in the given page allocator.
+    ///
+    /// If the thread does not exit in time, e.g. because the target is suspended, an error with kind [`io::ErrorKind::TimedOut`]
+    /// is returned and the memory is leaked, as the thread may still access it.
+    pub fn stop(&self, pages: &mut DynamicMultiBufferAllocator<'static>) -> Result<(), io::Error> {
+        self.request_stop()?;
+        pages.free(&self.stub);
+        pages.free(&self.call_block);
+        Ok(())
+    }
+
+    fn request_stop(&self) -> Result<(), io::Error> {
+        if !self.has_exited() && self.process.is_alive() {
+            let timeout = if self.stop_requested.get() {
+                // the stop already timed out once, only check whether the thread exited since.
+                Duration::ZERO
+            } else {
+                slice_of(self.process, &self.call_block).write(
+                    Self::FUNCTION_WORD * self.word_len,
+                    &[0; 8][..self.word_len],
+                )?;
+                if unsafe { SetEvent(self.request_event.as_raw_handle().cast()) } == 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                self.stop_requested.set(true);
+                STOP_TIMEOUT
+            };
+            let reason = unsafe {
+                WaitForSingleObject(
+                    self.thread.as_raw_handle().cast(),
+                    timeout.as_millis() as u32,
+                )
+            };
+            match reason {
+                WAIT_OBJECT_0 => {}
+                WAIT_FAILED => return Err(io::Error::last_os_error()),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "the remote worker thread did not exit in time",
+                    ))
+                }
+            }
+        }
+        self.exited.set(true);
+        if !self.stopped.replace(true) && self.process.is_alive() {
+            if let Ok(dup_process) = self.process.duplicate_with_access(PROCESS_DUP_HANDLE) {
+                for handle in self.remote_events {
+                    close_remote_handle(dup_process.as_raw_handle(), handle);
+                }
+            }
+            trace_event!(debug, tid = self.tid(), "stopped remote worker thread");
+        }
+        Ok(())
+    }
+
+    fn exit_code(&self) -> Result<u32, io::Error> {
+        let mut exit_code = MaybeUninit::uninit();
+        if unsafe { GetExitCodeThread(self.thread.as_raw_handle().cast(), exit_code.as_mut_ptr()) }
+            == 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(unsafe { exit_code.assume_init() })
+    }
+
+    #[cfg(feature = "assembler")]
+    fn build_code_x86() -> Result<Vec<u8>, IcedError> {
+        let mut asm = CodeAssembler::new(32)?;
+        let mut wait = asm.create_label();
+        let mut exit = asm.create_label();
+
+        asm.push(ebx)?;
+        asm.mov(ebx, dword_ptr(esp + 8))?; // CreateRemoteThread lpParameter
+        asm.set_label(&mut wait)?;
+        asm.push(-1)?; // INFINITE
+        asm.push(dword_ptr(ebx + 8))?; // request event
+        asm.call(dword_ptr(ebx))?; // WaitForSingleObject
+        asm.test(eax, eax)?;
+        asm.jnz(exit)?; // exit if the wait failed
+        asm.mov(eax, dword_ptr(ebx + 16))?; // function
+        asm.test(eax, eax)?;
+        asm.jz(exit)?; // a null function asks the worker to exit
+        asm.mov(dword_ptr(ebx + 28), Self::STATUS_RUNNING)?;
+        asm.push(dword_ptr(ebx + 20))?; // parameter
+        asm.call(eax)?; // stdcall, so the callee cleans up the argument
+        asm.mov(dword_ptr(ebx + 24), eax)?; // result
+        asm.mov(dword_ptr(ebx + 28), Self::STATUS_DONE)?;
+        asm.push(dword_ptr(ebx + 12))?; // done event
+        asm.call(dword_ptr(ebx + 4))?; // SetEvent
+        asm.jmp(wait)?;
+        asm.set_label(&mut exit)?;
+        asm.pop(ebx)?;
+        asm.ret_1(4)?; // Restore stack ptr. (Callee cleanup)
+
+        let code = asm.assemble(0x1234_5678)?;
+        debug_assert_eq!(
+            code,
+            asm.assemble(0x1111_2222)?,
+            "worker x86 stub is not location independent"
+        );
+
+        Ok(code)
+    }
+
+    #[cfg(feature = "assembler")]
+    fn build_code_x64() -> Result<Vec<u8>, IcedError> {
+        let mut asm = CodeAssembler::new(64)?;
+        let mut wait = asm.create_label();
+        let mut exit = asm.create_label();
+
+        asm.push(rbx)?; // Re-align stack to 16 byte boundary
+        asm.sub(rsp, 32)?; // shadow space
+        asm.mov(rbx, rcx)?; // CreateRemoteThread lpParameter
+        asm.set_label(&mut wait)?;
+        asm.mov(rcx, qword_ptr(rbx + 16))?; // request event
+        asm.mov(edx, INFINITE)?;
+        asm.call(qword_ptr(rbx))?; // WaitForSingleObject
+        asm.test(eax, eax)?;
+        asm.jnz(exit)?; // exit if the wait failed
+        asm.mov(rax, qword_ptr(rbx + 32))?; // function
+        asm.test(rax, rax)?;
+        asm.jz(exit)?; // a null function asks the worker to exit
+        asm.mov(dword_ptr(rbx + 56), Self::STATUS_RUNNING)?;
+        asm.mov(rcx, qword_ptr(rbx + 40))?; // parameter
+        asm.call(rax)?;
+        asm.mov(qword_ptr(rbx + 48), rax)?; // result
+        asm.mov(dword_ptr(rbx + 56), Self::STATUS_DONE)?;
+        asm.mov(rcx, qword_ptr(rbx + 24))?; // done event
+        asm.call(qword_ptr(rbx + 8))?; // SetEvent
+        asm.jmp(wait)?;
+        asm.set_label(&mut exit)?;
+        asm.add(rsp, 32)?;
+        asm.pop(rbx)?;
+        asm.ret()?;
+
+        let code = asm.assemble(0x1234_5678)?;
+        debug_assert_eq!(
+            code,
+            asm.assemble(0x1111_2222)?,
+            "worker x64 stub is not location independent"
+        );
+
+        Ok(code)
+    }
+
+    #[cfg(not(feature = "assembler"))]
+    fn build_code_x86() -> Result<Vec<u8>, Infallible> {
+        Ok(stub_templates::WORKER_X86.to_vec())
+    }
+
+    #[cfg(not(feature = "assembler"))]
+    fn build_code_x64() -> Result<Vec<u8>, Infallible> {
+        Ok(stub_templates::WORKER_X64.to_vec())
+    }
+}
+
+impl Drop for RemoteWorker {
+    fn drop(&mut self) {
+        // the memory of the worker is freed with its page allocator, the thread only has to stop using it.
+        if let Err(_err) = self.request_stop() {
+            trace_event!(warn, tid = self.tid(), error = %_err, "failed to stop remote worker thread");
+        }
+    }
+}
+
+fn slice_of<'a>(process: BorrowedProcess<'a>, allocation: &Allocation) -> ProcessMemorySlice<'a> {
+    unsafe { ProcessMemorySlice::from_raw_parts(allocation.as_raw_ptr(), allocation.len, process) }
+}
+
+/// Creates an unnamed auto-reset event.
+fn create_event() -> Result<OwnedHandle, io::Error> {
+    let handle = unsafe { CreateEventW(ptr::null_mut(), FALSE, FALSE, ptr::null()) };
+    if handle.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedHandle::from_raw_handle(handle.cast()) })
+}
+
+/// Duplicates the given handle into the given process and returns the value of the handle in that process.
+pub(crate) fn duplicate_into(
+    handle: &OwnedHandle,
+    process: std::os::windows::io::RawHandle,
+) -> Result<usize, io::Error> {
+    let mut remote_handle: HANDLE = ptr::null_mut();
+    let result = unsafe {
+        DuplicateHandle(
+            GetCurrentProcess(),
+            handle.as_raw_handle().cast(),
+            process.cast(),
+            &mut remote_handle,
+            0,
+            FALSE,
+            DUPLICATE_SAME_ACCESS,
+        )
+    };
+    if result == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(remote_handle as usize)
+}
+
+/// Closes the handle with the given value in the given process.
+pub(crate) fn close_remote_handle(process: std::os::windows::io::RawHandle, handle: usize) {
+    let result = unsafe {
+        DuplicateHandle(
+            process.cast(),
+            handle as HANDLE,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            0,
+            FALSE,
+            DUPLICATE_CLOSE_SOURCE,
+        )
+    };
+    if result == 0 {
+        trace_event!(warn, handle, error = %io::Error::last_os_error(), "failed to close remote handle");
+    }
+}
+
+#[cfg(all(test, feature = "assembler"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stub_templates_match_the_assembler() {
+        assert_eq!(
+            RemoteWorker::build_code_x86().unwrap(),
+            crate::stub_templates::WORKER_X86
+        );
+        assert_eq!(
+            RemoteWorker::build_code_x64().unwrap(),
+            crate::stub_templates::WORKER_X64
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{process::Process, utils::trace_event, Syringe};
+
+/// A snapshot of the resources a [`Syringe`] holds in its target process, see [`Syringe::resource_usage`].
+///
+/// Comparing snapshots taken over time helps to find the component that holds on to remote memory,
+/// e.g. procedures or boxes that are never dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub struct ResourceUsage {
+    thread_handles: usize,
+    live_allocations: usize,
+    live_allocation_bytes: usize,
+    committed_bytes: usize,
+    leaked_bytes: usize,
+    stubs: usize,
+    shared_code: usize,
+}
+
+impl ResourceUsage {
+    /// Returns the number of handles to threads in the target that are held open, i.e. the worker thread if one is running
+    /// (see [`Syringe::start_worker_thread`]).
+    #[must_use]
+    pub const fn thread_handles(&self) -> usize {
+        self.thread_handles
+    }
+
+    /// Returns the number of remote allocations that are still alive, including the ones of stubs and remote procedures.
+    #[must_use]
+    pub const fn live_allocations(&self) -> usize {
+        self.live_allocations
+    }
+
+    /// Returns the total size of the remote allocations that are still alive in bytes.
+    #[must_use]
+    pub const fn live_allocation_bytes(&self) -> usize {
+        self.live_allocation_bytes
+    }
+
+    /// Returns the number of bytes committed in the target, see [`Syringe::committed_remote_bytes`].
+    #[must_use]
+    pub const fn committed_bytes(&self) -> usize {
+        self.committed_bytes
+    }
+
+    /// Returns the number of bytes that can not be reused until the syringe is dropped, because a remote thread
+    /// that may still access them did not stop in time.
+    #[must_use]
+    pub const fn leaked_bytes(&self) -> usize {
+        self.leaked_bytes
+    }
+
+    /// Returns the number of stubs the syringe placed in the target for itself, see [`Syringe::stubs`].
+    #[must_use]
+    pub const fn stubs(&self) -> usize {
+        self.stubs
+    }
+
+    /// Returns the number of blocks of code that are shared between remote procedures and still in use.
+    #[must_use]
+    pub const fn shared_code(&self) -> usize {
+        self.shared_code
+    }
+}
+
+impl Syringe {
+    /// Returns the resources this syringe currently holds in the target process.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dll_syringe::{process::OwnedProcess, Syringe};
+    ///
+    /// let syringe = Syringe::for_process(OwnedProcess::find_first_by_name("target_process").unwrap());
+    /// let before = syringe.resource_usage();
+    /// // ...
+    /// let after = syringe.resource_usage();
+    /// if after.live_allocations() > before.live_allocations() {
+    ///     println!("{} remote allocations were not dropped", after.live_allocations() - before.live_allocations());
+    /// }
+    /// ```
+    #[must_use]
+    pub fn resource_usage(&self) -> ResourceUsage {
+        let allocator = &self.remote_allocator;
+        ResourceUsage {
+            thread_handles: usize::from(allocator.worker_tid().is_some()),
+            live_allocations: allocator.count_live_allocations(),
+            live_allocation_bytes: allocator.count_live_allocation_bytes(),
+            committed_bytes: allocator.count_committed_bytes(),
+            leaked_bytes: allocator.count_leaked_bytes(),
+            stubs: self.count_stubs(),
+            shared_code: allocator.count_shared_code(),
+        }
+    }
+
+    /// Warns about remote resources that outlive this syringe in a live target.
+    /// The stubs of the syringe have to be dropped before, as they are freed along with it.
+    pub(crate) fn warn_about_remaining_resources(&self) {
+        let allocator = &self.remote_allocator;
+        if allocator.is_dead() || !self.process().is_alive() {
+            return;
+        }
+        let usage = self.resource_usage();
+        if usage.live_allocations() != 0 {
+            trace_event!(
+                warn,
+                allocations = usage.live_allocations(),
+                bytes = usage.live_allocation_bytes(),
+                "remote allocations outlive the syringe and stay in the target until they are dropped"
+            );
+        }
+        if usage.leaked_bytes() != 0 {
+            trace_event!(
+                warn,
+                bytes = usage.leaked_bytes(),
+                "remote memory was leaked by the syringe"
+            );
+        }
+    }
+}
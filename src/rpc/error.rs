@@ -1,36 +1,68 @@
+#[cfg(feature = "rpc-raw")]
+use std::fmt;
 use std::io;
 
 use thiserror::Error;
 use winapi::shared::winerror::ERROR_PARTIAL_COPY;
 
-use crate::error::ExceptionCode;
+use crate::error::{ErrorKind, ExceptionCode, ExceptionOrIoError, RemoteAllocationBudgetExceeded};
 
 #[derive(Debug, Error)]
 #[cfg(feature = "rpc-core")]
 #[cfg_attr(all(feature = "rpc-core", not(feature = "rpc-raw")), doc(hidden))]
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-raw")))]
 /// An enum repsenting possible errors during remote procedure calls without serialization, deserialization or remote panics.
+#[non_exhaustive]
 pub enum RawRpcError {
     /// Variant representing an io error.
-    #[error("io error: {}", _0)]
-    Io(io::Error),
+    #[error("io error")]
+    Io(#[source] io::Error),
     /// Variant representing an unhandled exception inside the target process.
-    #[error("remote exception: {}", _0)]
-    RemoteException(ExceptionCode),
+    #[error("remote exception")]
+    RemoteException(#[source] ExceptionCode),
     /// Variant representing an inaccessible target process.
     /// This can occur if it crashed or was terminated.
     #[error("inaccessible target process")]
     ProcessInaccessible,
+    /// Variant representing a remote allocation that would exceed the allocation budget of the syringe.
+    /// See [`Syringe::set_allocation_budget`](crate::Syringe::set_allocation_budget).
+    #[error(transparent)]
+    AllocationBudgetExceeded(RemoteAllocationBudgetExceeded),
     /// Variant representing an inaccessible target module.
-    /// This can occur if the target module was ejected or unloaded.
+    /// This can occur if the target module was ejected or unloaded, or if the procedure is no longer exported by it.
     #[error("inaccessible target module")]
     ModuleInaccessible,
+    /// Variant representing a signature that can not be used to call the procedure, e.g. because of its return type.
+    #[cfg(feature = "rpc-raw")]
+    #[error("invalid signature")]
+    InvalidSignature(#[source] SignatureError),
+}
+
+#[cfg(feature = "rpc-core")]
+#[cfg_attr(all(feature = "rpc-core", not(feature = "rpc-raw")), doc(hidden))]
+impl RawRpcError {
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(e) => ErrorKind::Io.refine(e),
+            Self::RemoteException(_) => ErrorKind::RemoteException,
+            Self::ProcessInaccessible => ErrorKind::ProcessInaccessible,
+            Self::AllocationBudgetExceeded(_) => ErrorKind::AllocationBudgetExceeded,
+            Self::ModuleInaccessible => ErrorKind::ModuleInaccessible,
+            #[cfg(feature = "rpc-raw")]
+            Self::InvalidSignature(_) => ErrorKind::InvalidInput,
+        }
+    }
 }
 
 #[cfg(feature = "rpc-core")]
 #[cfg_attr(all(feature = "rpc-core", not(feature = "rpc-raw")), doc(hidden))]
 impl From<io::Error> for RawRpcError {
     fn from(err: io::Error) -> Self {
+        if let Some(err) = RemoteAllocationBudgetExceeded::from_io_error(&err) {
+            return Self::AllocationBudgetExceeded(err);
+        }
         if err.raw_os_error() == Some(ERROR_PARTIAL_COPY as _)
             || err.kind() == io::ErrorKind::PermissionDenied
         {
@@ -49,36 +81,157 @@ impl From<ExceptionCode> for RawRpcError {
     }
 }
 
+#[cfg(feature = "rpc-core")]
+#[cfg_attr(all(feature = "rpc-core", not(feature = "rpc-raw")), doc(hidden))]
+impl From<ExceptionOrIoError> for RawRpcError {
+    fn from(err: ExceptionOrIoError) -> Self {
+        match err {
+            ExceptionOrIoError::Io(e) => Self::from(e),
+            ExceptionOrIoError::Exception(e) => Self::RemoteException(e),
+        }
+    }
+}
+
+/// The position of a type in the signature of a remote procedure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg(feature = "rpc-raw")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-raw")))]
+pub enum SignaturePosition {
+    /// The argument at the given zero-based index.
+    Argument(usize),
+    /// The return value.
+    Return,
+}
+
+#[cfg(feature = "rpc-raw")]
+impl fmt::Display for SignaturePosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Argument(index) => write!(f, "argument {index}"),
+            Self::Return => f.write_str("return value"),
+        }
+    }
+}
+
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+#[cfg(feature = "rpc-raw")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-raw")))]
+/// An enum representing reasons why a signature can not be used to call a remote procedure in a target process,
+/// see [`RemoteRawProcedure::validate_signature`](crate::rpc::RemoteRawProcedure::validate_signature).
+#[non_exhaustive]
+pub enum SignatureError {
+    /// Variant representing a reference, which points into the current process and is meaningless in the target process.
+    #[error("{position} of type {type_name} is a reference into the current process, pass a raw pointer to remote memory instead")]
+    Reference {
+        /// The position of the type in the signature.
+        position: SignaturePosition,
+        /// The name of the type.
+        type_name: &'static str,
+    },
+    /// Variant representing a type that is known to have no C-compatible layout, e.g. a `String`, `Vec`, `char` or tuple.
+    #[error("{position} of type {type_name} has no C-compatible layout")]
+    NotFfiSafe {
+        /// The position of the type in the signature.
+        position: SignaturePosition,
+        /// The name of the type.
+        type_name: &'static str,
+    },
+    /// Variant representing a type that does not fit into a word of the target process, e.g. a `usize` or a pointer
+    /// when calling into a 32-bit target from a 64-bit process.
+    #[error(
+        "{position} of type {type_name} ({} bit) does not fit into a {} bit word of the target process",
+        .size * 8,
+        .word_size * 8
+    )]
+    TooLarge {
+        /// The position of the type in the signature.
+        position: SignaturePosition,
+        /// The name of the type.
+        type_name: &'static str,
+        /// The size of the type in bytes.
+        size: usize,
+        /// The size of a word in the target process in bytes.
+        word_size: usize,
+    },
+    /// Variant representing a return type that is not returned in registers, but through a hidden pointer passed by the caller,
+    /// e.g. a struct larger than 8 bytes. Such procedures can not be called, return a pointer to the value instead.
+    #[error("return type {type_name} ({size} bytes) is returned through a hidden pointer, which is not supported")]
+    ReturnedByPointer {
+        /// The name of the type.
+        type_name: &'static str,
+        /// The size of the type in bytes.
+        size: usize,
+    },
+}
+
 #[derive(Debug, Error)]
 #[cfg(feature = "rpc-payload")]
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-payload")))]
 /// An enum repsenting possible errors during remote procedure calls.
+#[non_exhaustive]
 pub enum PayloadRpcError {
     /// Variant representing an io error.
-    #[error("io error: {}", _0)]
-    Io(io::Error),
+    #[error("io error")]
+    Io(#[source] io::Error),
     /// Variant representing an unhandled exception inside the target process.
-    #[error("remote exception: {}", _0)]
-    RemoteException(ExceptionCode),
+    #[error("remote exception")]
+    RemoteException(#[source] ExceptionCode),
     /// Variant representing an inaccessible target process.
     /// This can occur if it crashed or was terminated.
     #[error("inaccessible target process")]
     ProcessInaccessible,
+    /// Variant representing a remote allocation that would exceed the allocation budget of the syringe.
+    /// See [`Syringe::set_allocation_budget`](crate::Syringe::set_allocation_budget).
+    #[error(transparent)]
+    AllocationBudgetExceeded(RemoteAllocationBudgetExceeded),
     /// Variant representing an inaccessible target module.
-    /// This can occur if the target module was ejected or unloaded.
+    /// This can occur if the target module was ejected or unloaded, or if the procedure is no longer exported by it.
     #[error("inaccessible target module")]
     ModuleInaccessible,
     /// Variant representing an error in the remote procedure.
     #[error("remote procedure error: {}", _0)]
     RemoteProcedure(String),
+    /// Variant representing a panic in the remote procedure.
+    #[error(
+        "remote procedure panicked{}: {message}",
+        .location.as_ref().map_or(String::new(), |location| format!(" at {location}"))
+    )]
+    RemoteProcedurePanicked {
+        /// The panic message.
+        message: String,
+        /// The source location of the panic, if it could be determined.
+        location: Option<String>,
+    },
     /// Variant representing an error while serializing or deserializing.
     #[error("serde error: {}", _0)]
     Serde(#[from] Box<bincode::ErrorKind>),
 }
 
+#[cfg(feature = "rpc-payload")]
+impl PayloadRpcError {
+    /// Returns the category of this error.
+    #[must_use]
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(e) => ErrorKind::Io.refine(e),
+            Self::RemoteException(_) => ErrorKind::RemoteException,
+            Self::ProcessInaccessible => ErrorKind::ProcessInaccessible,
+            Self::AllocationBudgetExceeded(_) => ErrorKind::AllocationBudgetExceeded,
+            Self::ModuleInaccessible => ErrorKind::ModuleInaccessible,
+            Self::RemoteProcedure(_) | Self::RemoteProcedurePanicked { .. } => {
+                ErrorKind::RemoteProcedure
+            }
+            Self::Serde(_) => ErrorKind::Serialization,
+        }
+    }
+}
+
 #[cfg(feature = "rpc-payload")]
 impl From<io::Error> for PayloadRpcError {
     fn from(err: io::Error) -> Self {
+        if let Some(err) = RemoteAllocationBudgetExceeded::from_io_error(&err) {
+            return Self::AllocationBudgetExceeded(err);
+        }
         if err.raw_os_error() == Some(ERROR_PARTIAL_COPY as _)
             || err.kind() == io::ErrorKind::PermissionDenied
         {
@@ -96,7 +249,11 @@ impl From<RawRpcError> for PayloadRpcError {
             RawRpcError::Io(err) => Self::Io(err),
             RawRpcError::RemoteException(code) => Self::RemoteException(code),
             RawRpcError::ProcessInaccessible => Self::ProcessInaccessible,
+            RawRpcError::AllocationBudgetExceeded(err) => Self::AllocationBudgetExceeded(err),
             RawRpcError::ModuleInaccessible => Self::ModuleInaccessible,
+            RawRpcError::InvalidSignature(err) => {
+                Self::Io(io::Error::new(io::ErrorKind::InvalidInput, err))
+            }
         }
     }
 }
@@ -1,4 +1,5 @@
 mod rpc_core;
+pub use rpc_core::ProcedureLookupMethod;
 pub(crate) use rpc_core::*;
 
 mod error;
@@ -1,13 +1,13 @@
 use serde::{de::DeserializeOwned, Serialize};
 
-use std::marker::PhantomData;
+use std::{io, marker::PhantomData, rc::Rc};
 
 use crate::{
     error::LoadProcedureError,
     function::{FunctionPtr, RawFunctionPtr},
     process::{
-        memory::{ProcessMemoryBuffer, RemoteBoxAllocator},
-        BorrowedProcess, BorrowedProcessModule, ModuleHandle,
+        memory::{ProcessMemoryBuffer, RemoteAllocation, RemoteBoxAllocator},
+        BorrowedProcess, BorrowedProcessModule, ModuleHandle, RemoteThreadOptions,
     },
     rpc::{error::PayloadRpcError, RemoteRawProcedure, Truncate},
     utils::ArrayOrVecBuf,
@@ -22,20 +22,29 @@ impl Syringe {
     /// # Note
     /// The function does not have to be from an injected module.
     /// If the module is not loaded in the target process `Ok(None)` is returned.
+    /// If the export is redirected later on, the procedure calls the new target, see [`Syringe::get_procedure_address`].
     ///
     /// # Safety
     /// The target function must abide by the given signature and has to be declared using the [`payload_procedure!`](crate::payload_procedure) macro.
-    pub unsafe fn get_payload_procedure<F: PayloadRpcFunctionPtr>(
+    pub unsafe fn get_payload_procedure<'a, F: PayloadRpcFunctionPtr>(
         &self,
-        module: BorrowedProcessModule<'_>,
+        module: impl Into<BorrowedProcessModule<'a>>,
         name: &str,
     ) -> Result<Option<RemotePayloadProcedure<F>>, LoadProcedureError> {
+        let module = module.into();
         match self.get_procedure_address(module, name) {
-            Ok(Some(procedure)) => Ok(Some(RemotePayloadProcedure::new(
-                unsafe { RealPayloadRpcFunctionPtr::from_ptr(procedure) },
-                self.remote_allocator.clone(),
-                module.handle(),
-            ))),
+            Ok(Some(procedure)) => {
+                let mut procedure = RemotePayloadProcedure::new(
+                    unsafe { RealPayloadRpcFunctionPtr::from_ptr(procedure) },
+                    self.remote_allocator.clone(),
+                    module.handle(),
+                    self.remote_thread_options.clone(),
+                );
+                if let Some(slot) = self.procedure_cache.get(module.handle(), name) {
+                    procedure.f = procedure.f.following(slot);
+                }
+                Ok(Some(procedure))
+            }
             Ok(None) => Ok(None),
             Err(e) => Err(e),
         }
@@ -64,13 +73,25 @@ where
         ptr: RealPayloadRpcFunctionPtr,
         remote_allocator: RemoteBoxAllocator,
         module_handle: ModuleHandle,
+        thread_options: RemoteThreadOptions,
     ) -> Self {
         Self {
-            f: RemoteRawProcedure::new(ptr, remote_allocator, module_handle),
+            f: RemoteRawProcedure::new(ptr, remote_allocator, module_handle, thread_options),
             phantom: PhantomData,
         }
     }
 
+    /// Returns the options used for the threads that execute this procedure.
+    #[must_use]
+    pub fn thread_options(&self) -> &RemoteThreadOptions {
+        self.f.thread_options()
+    }
+
+    /// Sets the options used for the threads that execute this procedure.
+    pub fn set_thread_options(&mut self, options: RemoteThreadOptions) {
+        self.f.set_thread_options(options);
+    }
+
     /// Returns the process that this remote procedure is from.
     #[must_use]
     pub fn process(&self) -> BorrowedProcess<'_> {
@@ -90,16 +111,59 @@ where
     for<'r> F::RefArgs<'r>: Serialize,
     F::Output: DeserializeOwned,
 {
-    fn call_with_args(&self, args: F::RefArgs<'_>) -> Result<F::Output, PayloadRpcError> {
+    fn serialize_args(args: F::RefArgs<'_>) -> Result<ArrayOrVecBuf<u8, 512>, PayloadRpcError> {
         let arg_bytes = bincode::serialized_size(&args)? as usize;
         let mut local_arg_buf = ArrayOrVecBuf::<_, 512>::with_capacity(arg_bytes);
         bincode::serialize_into(local_arg_buf.spare_writer(), &args)?;
         unsafe { local_arg_buf.set_len(arg_bytes) };
+        Ok(local_arg_buf)
+    }
+
+    fn stage_args_with(
+        &self,
+        args: F::RefArgs<'_>,
+    ) -> Result<StagedPayloadArgs<F>, PayloadRpcError> {
+        let local_arg_buf = Self::serialize_args(args)?;
+        let buf = self.f.remote_allocator.alloc_raw(local_arg_buf.len())?;
+        buf.write_bytes(&local_arg_buf)?;
+        Ok(StagedPayloadArgs {
+            buf,
+            phantom: PhantomData,
+        })
+    }
+
+    fn call_with_staged_args(
+        &self,
+        args: &StagedPayloadArgs<F>,
+    ) -> Result<F::Output, PayloadRpcError> {
+        if !Rc::ptr_eq(&args.buf.allocator().0, &self.f.remote_allocator.0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the arguments were staged in another process or by another syringe",
+            )
+            .into());
+        }
+        self.call_with_arg_buf(&args.buf, true, ArrayOrVecBuf::default())
+    }
+
+    fn call_with_args(&self, args: F::RefArgs<'_>) -> Result<F::Output, PayloadRpcError> {
+        let local_arg_buf = Self::serialize_args(args)?;
 
         // Allocate a buffer in the remote process to hold the argument.
         let remote_arg_buf = self.f.remote_allocator.alloc_raw(local_arg_buf.len())?;
         remote_arg_buf.write_bytes(&local_arg_buf)?;
 
+        self.call_with_arg_buf(&remote_arg_buf, false, local_arg_buf)
+    }
+
+    /// Calls the procedure with the serialized arguments in the given buffer.
+    /// The result is read into the given local buffer, which can be the buffer the arguments were serialized into.
+    fn call_with_arg_buf(
+        &self,
+        remote_arg_buf: &RemoteAllocation,
+        preserve_args: bool,
+        local_arg_buf: ArrayOrVecBuf<u8, 512>,
+    ) -> Result<F::Output, PayloadRpcError> {
         let parameter_buf = self
             .f
             .remote_allocator
@@ -107,6 +171,8 @@ where
                 data: remote_arg_buf.as_ptr().as_ptr() as u64,
                 len: remote_arg_buf.len() as u64,
                 is_error: false,
+                is_panic: false,
+                preserve_data: preserve_args,
             })?;
 
         // Call the remote procedure stub.
@@ -138,7 +204,10 @@ where
             result_memory.read(0, &mut local_result_buf)?;
         };
 
-        if result_buf_info.is_error {
+        if result_buf_info.is_panic {
+            let (message, location) = bincode::deserialize(&local_result_buf)?;
+            Err(PayloadRpcError::RemoteProcedurePanicked { message, location })
+        } else if result_buf_info.is_error {
             Err(PayloadRpcError::RemoteProcedure(unsafe {
                 String::from_utf8_unchecked(local_result_buf.into_vec())
             }))
@@ -148,6 +217,52 @@ where
     }
 }
 
+/// The arguments of a [`RemotePayloadProcedure`] that were serialized and copied into the target process once,
+/// so they can be passed to any number of calls without being serialized and written again.
+///
+/// The procedure receives the same arguments on every call, as its result is never written over them.
+/// The arguments are freed when this struct is dropped.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::{Syringe, process::OwnedProcess};
+///
+/// let syringe = Syringe::for_process(OwnedProcess::find_first_by_name("target_process").unwrap());
+/// let payload = syringe.inject("injection_payload.dll").unwrap();
+/// let sum = unsafe { syringe.get_payload_procedure::<fn(Vec<u64>) -> u64>(payload, "sum") }.unwrap().unwrap();
+///
+/// let nums = sum.stage_args(&(0..100_000).collect()).unwrap();
+/// for _ in 0..10 {
+///     println!("{}", sum.call_staged(&nums).unwrap());
+/// }
+/// ```
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-payload")))]
+#[derive(Debug)]
+pub struct StagedPayloadArgs<F> {
+    buf: RemoteAllocation,
+    phantom: PhantomData<fn() -> F>,
+}
+
+impl<F> StagedPayloadArgs<F> {
+    /// Returns the number of bytes the serialized arguments occupy in the target process.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Returns whether the serialized arguments are empty, e.g. for a procedure without arguments.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the address of the serialized arguments in the target process.
+    #[must_use]
+    pub const fn as_raw_ptr(&self) -> *mut u8 {
+        self.buf.as_raw_ptr()
+    }
+}
+
 macro_rules! impl_call {
     (@recurse () ($($nm:ident : $ty:ident),*)) => {
         impl_call!(@impl_all ($($nm : $ty),*));
@@ -168,6 +283,19 @@ macro_rules! impl_call {
             pub fn call(&self, $($nm: &$ty),*) -> Result<Output, PayloadRpcError> {
                 self.call_with_args(($($nm,)*))
             }
+
+            /// Serializes the given arguments and copies them into the target process once, so they can be passed to
+            /// many calls using [`call_staged`](Self::call_staged).
+            #[allow(clippy::too_many_arguments)]
+            pub fn stage_args(&self, $($nm: &$ty),*) -> Result<StagedPayloadArgs<fn($($ty),*) -> Output>, PayloadRpcError> {
+                self.stage_args_with(($($nm,)*))
+            }
+
+            /// Calls the remote procedure with arguments that were staged using [`stage_args`](Self::stage_args),
+            /// without serializing or writing them again. Only the return value is copied.
+            pub fn call_staged(&self, args: &StagedPayloadArgs<fn($($ty),*) -> Output>) -> Result<Output, PayloadRpcError> {
+                self.call_with_staged_args(args)
+            }
         }
 
         impl <$($ty,)* Output> RemotePayloadProcedure<unsafe fn($($ty),*) -> Output> where $($ty: 'static + Serialize,)* Output: 'static + DeserializeOwned,  {
@@ -180,6 +308,22 @@ macro_rules! impl_call {
             pub unsafe fn call(&self, $($nm: &$ty),*) -> Result<Output, PayloadRpcError> {
                 self.call_with_args(($($nm,)*))
             }
+
+            /// Serializes the given arguments and copies them into the target process once, so they can be passed to
+            /// many calls using [`call_staged`](Self::call_staged).
+            #[allow(clippy::too_many_arguments)]
+            pub fn stage_args(&self, $($nm: &$ty),*) -> Result<StagedPayloadArgs<unsafe fn($($ty),*) -> Output>, PayloadRpcError> {
+                self.stage_args_with(($($nm,)*))
+            }
+
+            /// Calls the remote procedure with arguments that were staged using [`stage_args`](Self::stage_args),
+            /// without serializing or writing them again. Only the return value is copied.
+            ///
+            /// # Safety
+            /// The caller must ensure whatever the requirements of the underlying remote procedure are.
+            pub unsafe fn call_staged(&self, args: &StagedPayloadArgs<unsafe fn($($ty),*) -> Output>) -> Result<Output, PayloadRpcError> {
+                self.call_with_staged_args(args)
+            }
         }
     };
 
@@ -1,22 +1,35 @@
+#[cfg(feature = "assembler")]
 use iced_x86::{code_asm::*, IcedError};
+#[cfg(not(feature = "assembler"))]
+use {crate::stub_templates, std::convert::Infallible};
 
 use std::{
     any::{self, TypeId},
-    cell::OnceCell,
-    cmp, fmt, io, mem, slice,
+    cell::{Cell, OnceCell},
+    cmp, fmt, io, mem,
+    rc::Rc,
+    slice,
 };
 
 use crate::{
     error::LoadProcedureError,
     function::{Abi, FunctionPtr, RawFunctionPtr},
     process::{
-        memory::{RemoteAllocation, RemoteBox, RemoteBoxAllocator},
-        BorrowedProcess, BorrowedProcessModule, ModuleHandle, Process, ProcessModule,
+        memory::{RemoteAllocation, RemoteBoxAllocator, RemoteResultBuf},
+        BorrowedProcess, BorrowedProcessModule, ModuleHandle, Process, ProcessModule, RemoteSymbol,
+        RemoteThreadOptions,
     },
-    rpc::error::RawRpcError,
-    Syringe,
+    rpc::{
+        error::{RawRpcError, SignatureError, SignaturePosition},
+        ProcedureSlot,
+    },
+    utils::trace_event,
+    StubInfo, StubKind, Syringe,
 };
 
+#[cfg(feature = "assembler")]
+use crate::process::memory::RemoteResultStatus;
+
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-raw")))]
 impl Syringe {
     /// Loads an exported function from the given module from the target process.
@@ -27,26 +40,69 @@ impl Syringe {
     /// # Note
     /// The function does not have to be from an injected module.
     /// If the module is not loaded in the target process `Ok(None)` is returned.
+    /// If the export is redirected later on, the procedure calls the new target, see [`Syringe::get_procedure_address`].
+    ///
+    /// # Errors
+    /// Fails with [`LoadProcedureError::InvalidSignature`] if the signature can not be used to call the function in the
+    /// target process, see [`RemoteRawProcedure::validate_signature`].
     ///
     /// # Safety
     /// The target function must abide by the given signature.
-    pub unsafe fn get_raw_procedure<F: RawRpcFunctionPtr>(
+    pub unsafe fn get_raw_procedure<'a, F: RawRpcFunctionPtr>(
         &self,
-        module: BorrowedProcessModule<'_>,
+        module: impl Into<BorrowedProcessModule<'a>>,
         name: &str,
     ) -> Result<Option<RemoteRawProcedure<F>>, LoadProcedureError> {
+        let module = module.into();
         match self.get_procedure_address(module, name) {
-            Ok(Some(procedure)) => Ok(Some(RemoteRawProcedure::new(
-                unsafe { F::from_ptr(procedure) },
-                self.remote_allocator.clone(),
-                module.handle(),
-            ))),
+            Ok(Some(procedure)) => {
+                let mut procedure = RemoteRawProcedure::new(
+                    unsafe { F::from_ptr(procedure) },
+                    self.remote_allocator.clone(),
+                    module.handle(),
+                    self.remote_thread_options.clone(),
+                );
+                if let Some(slot) = self.procedure_cache.get(module.handle(), name) {
+                    procedure = procedure.following(slot);
+                }
+                procedure
+                    .validate_signature()
+                    .map_err(LoadProcedureError::InvalidSignature)?;
+                Ok(Some(procedure))
+            }
             Ok(None) => Ok(None),
             Err(e) => Err(e),
         }
     }
 }
 
+impl<F: RawRpcFunctionPtr> RemoteSymbol<'_, F> {
+    /// Returns a procedure that calls this symbol in its process using the given syringe.
+    ///
+    /// # Errors
+    /// Fails with [`io::ErrorKind::InvalidInput`] if the syringe targets a different process than the one of the module of this symbol
+    /// or if the signature can not be used to call the symbol in its process, see [`RemoteRawProcedure::validate_signature`].
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-raw")))]
+    pub fn to_raw_procedure(&self, syringe: &Syringe) -> Result<RemoteRawProcedure<F>, io::Error> {
+        if self.module().process() != &syringe.process() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "symbol is not from the target process of the syringe",
+            ));
+        }
+        let procedure = RemoteRawProcedure::new(
+            self.as_ptr(),
+            syringe.remote_allocator.clone(),
+            self.module().handle(),
+            syringe.remote_thread_options.clone(),
+        );
+        procedure
+            .validate_signature()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        Ok(procedure)
+    }
+}
+
 /// A function pointer that can be used with [`RemoteRawProcedure`].
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-raw")))]
 pub trait RawRpcFunctionPtr: FunctionPtr {}
@@ -54,28 +110,44 @@ pub trait RawRpcFunctionPtr: FunctionPtr {}
 /// A struct representing a procedure from a module of a remote process.
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-raw")))]
 pub struct RemoteRawProcedure<F> {
-    ptr: F,
+    ptr: Cell<F>,
     pub(crate) remote_allocator: RemoteBoxAllocator,
     stub: OnceCell<RemoteRawProcedureStub>,
     module_handle: ModuleHandle,
+    thread_options: RemoteThreadOptions,
+    // the cached lookup this procedure was created from, which is checked for redirected exports before each call.
+    slot: Option<Rc<ProcedureSlot>>,
 }
 
 impl<F: FunctionPtr> fmt::Debug for RemoteRawProcedure<F> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("RemoteRawProcedure")
-            .field("ptr", &self.ptr.as_ptr())
+            .field("ptr", &self.as_raw_ptr())
             .field("remote_allocator", &self.remote_allocator)
             .field("stub", &self.stub)
             .field("module_handle", &self.module_handle)
+            .field("thread_options", &self.thread_options)
+            .field("slot", &self.slot)
             .finish()
     }
 }
 
+/// The remote memory used to call a [`RemoteRawProcedure`].
+///
+/// The code is a template shared by all procedures of the same signature, which reads the address of the procedure
+/// and of its result buffer from the parameter block, followed by the arguments.
+/// Procedures without parameters and without a return value get the address of the procedure as the thread parameter
+/// instead of a parameter block.
 #[derive(Debug)]
 pub(crate) struct RemoteRawProcedureStub {
-    pub code: RemoteAllocation,
-    pub parameter: RemoteAllocation,
-    pub result: RemoteBox<usize>,
+    pub code: Rc<RemoteAllocation>,
+    pub parameter: Option<RemoteAllocation>,
+    pub result: Option<RemoteResultBuf>,
+}
+
+impl RemoteRawProcedureStub {
+    /// The number of words in the parameter block preceding the arguments.
+    const HEADER_WORDS: usize = 2;
 }
 
 impl<F> RemoteRawProcedure<F>
@@ -86,15 +158,35 @@ where
         ptr: F,
         remote_allocator: RemoteBoxAllocator,
         module_handle: ModuleHandle,
+        thread_options: RemoteThreadOptions,
     ) -> Self {
         Self {
-            ptr,
+            ptr: Cell::new(ptr),
             remote_allocator,
             stub: OnceCell::new(),
             module_handle,
+            thread_options,
+            slot: None,
         }
     }
 
+    /// Makes this procedure follow the given cached lookup, so it calls the new target if the export is redirected.
+    pub(crate) fn following(mut self, slot: Rc<ProcedureSlot>) -> Self {
+        self.slot = Some(slot);
+        self
+    }
+
+    /// Returns the options used for the threads that execute this procedure.
+    #[must_use]
+    pub fn thread_options(&self) -> &RemoteThreadOptions {
+        &self.thread_options
+    }
+
+    /// Sets the options used for the threads that execute this procedure.
+    pub fn set_thread_options(&mut self, options: RemoteThreadOptions) {
+        self.thread_options = options;
+    }
+
     /// Returns the process that this remote procedure is from.
     #[must_use]
     pub fn process(&self) -> BorrowedProcess<'_> {
@@ -104,7 +196,7 @@ where
     /// Returns the underlying pointer to the remote procedure.
     #[must_use]
     pub fn as_ptr(&self) -> F {
-        self.ptr
+        self.ptr.get()
     }
 
     /// Returns the raw underlying pointer to the remote procedure.
@@ -127,45 +219,196 @@ where
         {
             return Err(RawRpcError::ModuleInaccessible);
         }
+        let redirected = self.follow_slot()?;
 
+        Self::check_return_type().map_err(RawRpcError::InvalidSignature)?;
         let stub = self.build_call_stub()?;
 
-        stub.parameter.memory().write_struct(0, args)?;
+        if let Some(parameter) = &stub.parameter {
+            if redirected {
+                parameter
+                    .memory()
+                    .write_struct(0, &(self.as_raw_ptr() as usize))?;
+            }
+            parameter.memory().write_struct(
+                RemoteRawProcedureStub::HEADER_WORDS * mem::size_of::<usize>(),
+                args,
+            )?;
+        }
+        if let Some(result) = &stub.result {
+            result.reset()?;
+        }
+
+        self.remote_allocator
+            .run_remote_thread(
+                unsafe {
+                    mem::transmute::<*mut u8, extern "system" fn(*mut u8) -> u32>(
+                        stub.code.as_raw_ptr(),
+                    )
+                },
+                stub.parameter
+                    .as_ref()
+                    .map_or(self.as_raw_ptr().cast(), RemoteAllocation::as_raw_ptr),
+                &self.thread_options,
+            )?
+            .into_result()?;
+
+        // a procedure without a return value has completed once its thread exited.
+        let Some(result) = &stub.result else {
+            return Ok(unsafe { mem::zeroed() });
+        };
+        let result = result.read_word()?.into_io_result("call")?.to_le_bytes();
+        Ok(unsafe { result.as_ptr().cast::<F::Output>().read_unaligned() })
+    }
 
-        let exit_code = stub.code.process().run_remote_thread(
-            unsafe { mem::transmute(stub.code.as_raw_ptr()) },
-            stub.parameter.as_raw_ptr(),
-        )?;
-        Syringe::remote_exit_code_to_exception(exit_code)?;
+    /// Updates the address of this procedure if the export it was resolved from was redirected and returns whether it changed.
+    fn follow_slot(&self) -> Result<bool, RawRpcError> {
+        let Some(slot) = &self.slot else {
+            return Ok(false);
+        };
+        let module = unsafe { ProcessModule::new_unchecked(self.module_handle, self.process()) };
+        let Some(address) = slot.address(module)? else {
+            return Err(RawRpcError::ModuleInaccessible);
+        };
+        if address == self.as_raw_ptr() {
+            return Ok(false);
+        }
+        // SAFETY: the export still has the same name, so it is expected to abide by the signature the procedure was created with.
+        self.ptr.set(unsafe { F::from_ptr(address) });
+        Ok(true)
+    }
 
-        if mem::size_of::<F::Output>() == 0 {
-            Ok(unsafe { mem::zeroed() })
+    /// Checks that the arguments and the return value of the signature of this procedure can be passed to and from the
+    /// target process, which is done automatically when the procedure is created.
+    ///
+    /// This rejects references, types that are known to have no C-compatible layout (e.g. `String`, `Vec`, `char` or tuples)
+    /// and arguments that do not fit into a word of the target process, e.g. a `usize` or a pointer when calling into a
+    /// 32-bit target from a 64-bit process. Use `u32` or [`Truncate`] for those instead.
+    /// Return types that are not returned in registers (e.g. structs larger than 8 bytes) are rejected as well,
+    /// calling a procedure with such a signature fails with [`RawRpcError::InvalidSignature`].
+    ///
+    /// # Note
+    /// The layout of user-defined types can not be inspected, so they must be `#[repr(C)]` or `#[repr(transparent)]`
+    /// to be passed safely.
+    /// The trailing `*mut u8` and `usize` arguments of a signature usable with `call_with_out_buf` are not checked against
+    /// the word size, as they are passed by this crate in the width of the target process.
+    pub fn validate_signature(&self) -> Result<(), SignatureError> {
+        let word_size = match self.remote_allocator.is_x86() {
+            Ok(true) => Some(mem::size_of::<u32>()),
+            Ok(false) => Some(mem::size_of::<u64>()),
+            // the sizes can not be checked if the target is gone, which the first call reports anyway.
+            Err(_) => None,
+        };
+        let arguments = <F::NonExtern>::argument_layouts();
+        let out_buf_start = if is_out_buf_signature::<F>(&arguments) {
+            arguments.len() - 2
         } else {
-            let offset = if cfg!(target_endian = "little") {
-                0
-            } else if cfg!(target_endian = "big") {
-                mem::size_of::<usize>() - mem::size_of::<F::Output>()
+            arguments.len()
+        };
+        for (index, layout) in arguments.into_iter().enumerate() {
+            let word_size = if index < out_buf_start {
+                word_size
             } else {
-                unreachable!()
+                None
             };
-            let result = unsafe { stub.result.memory().read_struct::<F::Output>(offset) }?;
-            Ok(result)
+            validate_layout(SignaturePosition::Argument(index), layout, word_size)?;
         }
+        Self::check_return_type()?;
+        validate_layout(
+            SignaturePosition::Return,
+            TypeLayout::of::<F::Output>(),
+            Some(RemoteResultBuf::WORD_LEN),
+        )
+    }
+
+    /// Checks that the return type is returned in registers, as the stubs do not pass a hidden pointer for larger results.
+    pub(crate) fn check_return_type() -> Result<(), SignatureError> {
+        // both the x86 and the x64 calling conventions return values of other sizes (e.g. larger structs) through a hidden pointer.
+        let size = mem::size_of::<F::Output>();
+        if matches!(size, 0 | 1 | 2 | 4 | 8) {
+            Ok(())
+        } else {
+            Err(SignatureError::ReturnedByPointer {
+                type_name: any::type_name::<F::Output>(),
+                size,
+            })
+        }
+    }
+
+    /// Places the call stub of this procedure in the target process, which otherwise happens on the first call.
+    /// Preparing many procedures inside [`Syringe::batch_stub_writes`] writes their stubs together.
+    pub fn prepare(&self) -> Result<(), io::Error> {
+        self.build_call_stub().map(|_| ())
+    }
+
+    /// Returns the call stub of this procedure in the target process or [`None`] if the procedure was not called or prepared yet.
+    /// The stub is shared with all other procedures of the same signature.
+    pub fn stub_info(&self) -> Result<Option<StubInfo>, io::Error> {
+        self.remote_allocator.write_pending_code()?;
+        self.stub
+            .get()
+            .map(|stub| StubInfo::read(StubKind::CallProcedure, &stub.code))
+            .transpose()
+    }
+
+    fn call_with_out_buf_args(
+        &self,
+        args: &[usize],
+        capacity: usize,
+    ) -> Result<RawOutBuf, RawRpcError>
+    where
+        F: RawRpcFunctionPtr<Output = usize>,
+    {
+        let buf = self.remote_allocator.alloc_raw(cmp::max(capacity, 1))?;
+
+        // at most 10 arguments precede the buffer and its capacity, see `impl_call_with_out_buf`.
+        let mut args_buf = [0usize; 12];
+        args_buf[..args.len()].copy_from_slice(args);
+        args_buf[args.len()] = buf.as_raw_ptr() as usize;
+        args_buf[args.len() + 1] = capacity;
+
+        let mut len = self.call_with_args(&args_buf[..args.len() + 2])?;
+        if self.remote_allocator.is_x86()? {
+            // the upper half of the returned word is undefined for 32-bit targets.
+            len = len as u32 as usize;
+        }
+
+        let mut data = vec![0u8; cmp::min(len, capacity)];
+        buf.read_bytes(&mut data)?;
+        Ok(RawOutBuf { data, len })
     }
 
     fn build_call_stub(&self) -> Result<&RemoteRawProcedureStub, io::Error> {
         self.stub.get_or_try_init(|| {
-            let parameter = self.remote_allocator.alloc_buf::<usize>(F::ARITY)?;
-            let result = self.remote_allocator.alloc_uninit::<usize>()?;
-
-            let float_mask = <F::NonExtern>::build_float_mask();
-            let code = if self.process().is_x86()? {
-                Self::build_call_stub_x86(self.ptr, result.as_ptr().as_ptr(), float_mask).unwrap()
+            Self::check_return_type()
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+            let result = if mem::size_of::<F::Output>() == 0 {
+                None
+            } else {
+                Some(RemoteResultBuf::for_word(&self.remote_allocator)?)
+            };
+            let parameter = if F::ARITY == 0 && result.is_none() {
+                None
             } else {
-                Self::build_call_stub_x64(self.ptr, result.as_ptr().as_ptr(), float_mask).unwrap()
+                let parameter = self
+                    .remote_allocator
+                    .alloc_buf::<usize>(RemoteRawProcedureStub::HEADER_WORDS + F::ARITY)?;
+                let header: [usize; RemoteRawProcedureStub::HEADER_WORDS] = [
+                    self.as_raw_ptr() as usize,
+                    result.as_ref().map_or(0, |result| result.as_raw_ptr() as usize),
+                ];
+                parameter.memory().write_struct(0, &header)?;
+                Some(parameter)
             };
-            let code = self.remote_allocator.alloc_and_copy_buf(code.as_slice())?;
-            code.memory().flush_instruction_cache()?;
+
+            let code = Self::build_call_stub_code(
+                self.remote_allocator.is_x86()?,
+                parameter.is_some(),
+                result.is_some(),
+            );
+            trace_event!(trace, bytes = %crate::utils::HexBytes(code.as_slice()), "assembled call stub");
+            let code = self.remote_allocator.alloc_shared_code(code.as_slice())?;
+            trace_event!(debug, address = ?code.as_ptr(), len = code.len(), target = ?self.as_raw_ptr(), "using call stub");
 
             Ok(RemoteRawProcedureStub {
                 code,
@@ -175,38 +418,72 @@ where
         })
     }
 
-    #[allow(clippy::fn_to_numeric_cast, clippy::fn_to_numeric_cast_with_truncation)]
-    fn build_call_stub_x86(
-        procedure: F,
-        result_buf: *mut usize,
-        _float_mask: u32,
-    ) -> Result<Vec<u8>, IcedError> {
-        assert!(!result_buf.is_null());
-        assert_eq!(
-            procedure.as_ptr() as u32 as usize,
-            procedure.as_ptr() as usize
-        );
-        assert_eq!(result_buf as u32 as usize, result_buf as usize);
+    /// Builds the code of the call stub for an x86 or x64 target.
+    ///
+    /// With a parameter block, the stub reads the procedure, the address of the result buffer and the arguments from it,
+    /// otherwise its parameter is the procedure itself.
+    pub(crate) fn build_call_stub_code(
+        is_x86: bool,
+        has_parameter: bool,
+        has_result: bool,
+    ) -> Vec<u8> {
+        if is_x86 {
+            Self::build_call_stub_x86(has_parameter, has_result).unwrap()
+        } else {
+            let float_mask = <F::NonExtern>::build_float_mask();
+            Self::build_call_stub_x64(has_parameter, has_result, float_mask).unwrap()
+        }
+    }
+
+    /// Returns the number of bytes of the return value in the target process.
+    fn result_len(is_x86: bool) -> u32 {
+        // pointer sized values only occupy `eax` in 32-bit targets, `edx` is undefined, so they are zero-extended instead.
+        if is_x86 && <F::Output as PointerSized>::is_pointer_sized() {
+            mem::size_of::<u32>() as u32
+        } else {
+            mem::size_of::<F::Output>() as u32
+        }
+    }
+
+    #[cfg(feature = "assembler")]
+    fn build_call_stub_x86(has_parameter: bool, has_result: bool) -> Result<Vec<u8>, IcedError> {
+        const WORD: usize = mem::size_of::<usize>();
+        const ARGS_OFFSET: usize = RemoteRawProcedureStub::HEADER_WORDS * WORD;
 
         let mut asm = CodeAssembler::new(32)?;
 
-        asm.mov(eax, esp + 4)?; // load arg ptr (lpParameter) from stack
-        for i in (0..F::ARITY).rev() {
-            asm.push(dword_ptr(eax + (i * mem::size_of::<usize>())))?;
+        asm.mov(eax, esp + 4)?; // load parameter block ptr (lpParameter) from stack
+        if has_parameter {
+            for i in (0..F::ARITY).rev() {
+                asm.push(dword_ptr(eax + (ARGS_OFFSET + i * WORD)))?;
+            }
+            asm.call(dword_ptr(eax))?; // call the procedure from the parameter block
+        } else {
+            asm.call(eax)?; // lpParameter is the procedure itself
         }
-        asm.mov(eax, procedure.as_ptr() as u32)?; // load address of target function
-        asm.call(eax)?; // call real_address
-        asm.mov(dword_ptr(result_buf as u32), eax)?; // write result to result buf
-        asm.mov(eax, 0)?; // return 0
 
         match F::ABI {
             Abi::C => {
-                asm.add(esp, (mem::size_of::<u32>() * F::ARITY) as u32)?;
+                if F::ARITY > 0 {
+                    asm.add(esp, (mem::size_of::<u32>() * F::ARITY) as u32)?;
+                }
             }
-            Abi::System => {}
+            Abi::System => {} // callee cleanup
             _ => unreachable!(),
         }
 
+        // write result to result buf, 64-bit results are returned in edx:eax.
+        if has_result {
+            asm.mov(ecx, dword_ptr(esp + 4))?;
+            asm.mov(ecx, dword_ptr(ecx + WORD))?;
+            RemoteResultBuf::emit_write_x86_at_ecx(
+                &mut asm,
+                RemoteResultStatus::Ok,
+                Self::result_len(true),
+            )?;
+        }
+        asm.mov(eax, 0)?; // return 0
+
         // Restore stack ptr. (Callee cleanup)
         asm.ret_1(4)?;
 
@@ -221,69 +498,60 @@ where
         Ok(code)
     }
 
-    #[allow(
-        clippy::fn_to_numeric_cast,
-        clippy::fn_to_numeric_cast_with_truncation,
-        clippy::identity_op,
-        clippy::erasing_op
-    )]
+    #[cfg(feature = "assembler")]
     fn build_call_stub_x64(
-        procedure: F,
-        result_buf: *mut usize,
+        has_parameter: bool,
+        has_result: bool,
         float_mask: u32,
     ) -> Result<Vec<u8>, IcedError> {
+        const WORD: usize = mem::size_of::<u64>();
+        const ARGS_OFFSET: usize = RemoteRawProcedureStub::HEADER_WORDS * WORD;
+        const SHADOW_SPACE: usize = 32;
+
+        // shadow space and stack arguments, keeping the stack aligned to 16 bytes at the call.
+        let stack_args = F::ARITY.saturating_sub(4);
+        let frame_size = (SHADOW_SPACE + stack_args * WORD + WORD).next_multiple_of(16) - WORD;
+
         let mut asm = CodeAssembler::new(64)?;
 
-        asm.sub(rsp, 8)?; // align stack to 16 bytes
-        asm.mov(rax, rcx)?; // arg base ptr
-        if F::ARITY > 0 {
-            asm.mov(rcx, qword_ptr(rax + (0 * mem::size_of::<usize>())))?;
-            if float_mask & (1 << 0) != 0 {
-                asm.movq(xmm0, rcx)?;
-            }
-        }
-        if F::ARITY > 1 {
-            asm.mov(rdx, qword_ptr(rax + (1 * mem::size_of::<usize>())))?;
-            if float_mask & (1 << 1) != 0 {
-                asm.movq(xmm1, rdx)?;
+        asm.mov(qword_ptr(rsp + 8), rcx)?; // keep parameter block ptr in our home space
+        asm.sub(rsp, frame_size as i32)?;
+        asm.mov(rax, rcx)?;
+        if has_parameter {
+            let registers = [(rcx, xmm0), (rdx, xmm1), (r8, xmm2), (r9, xmm3)];
+            for (i, (register, float_register)) in registers.into_iter().enumerate().take(F::ARITY)
+            {
+                asm.mov(register, qword_ptr(rax + (ARGS_OFFSET + i * WORD)))?;
+                if float_mask & (1 << i) != 0 {
+                    asm.movq(float_register, register)?;
+                }
             }
-        }
-        if F::ARITY > 2 {
-            asm.mov(r8, qword_ptr(rax + (2 * mem::size_of::<usize>())))?;
-            if float_mask & (1 << 2) != 0 {
-                asm.movq(xmm2, r8)?;
-            }
-        }
-        if F::ARITY > 3 {
-            asm.mov(r9, qword_ptr(rax + (3 * mem::size_of::<usize>())))?;
-            if float_mask & (1 << 3) != 0 {
-                asm.movq(xmm3, r9)?;
+            for i in 4..F::ARITY {
+                asm.mov(r10, qword_ptr(rax + (ARGS_OFFSET + i * WORD)))?;
+                asm.mov(qword_ptr(rsp + (SHADOW_SPACE + (i - 4) * WORD)), r10)?;
             }
+            asm.call(qword_ptr(rax))?; // call the procedure from the parameter block
+        } else {
+            asm.call(rax)?; // lpParameter is the procedure itself
         }
-        for i in (4..F::ARITY).rev() {
-            asm.push(qword_ptr(rax + (i * mem::size_of::<usize>())))?;
-        }
-
-        asm.mov(rax, procedure.as_ptr() as u64)?;
-
-        asm.sub(rsp, 32)?; // push shadow space
-        asm.call(rax)?;
-        asm.add(rsp, 32)?; // pop shadow space
+        asm.add(rsp, frame_size as i32)?;
 
         // write result to result buf
-        if float_mask & 0x8000_0000u32 != 0 {
-            asm.movq(rax, xmm0)?;
+        if has_result {
+            if float_mask & 0x8000_0000u32 != 0 {
+                asm.movq(rax, xmm0)?;
+            }
+            asm.mov(r11, qword_ptr(rsp + 8))?;
+            asm.mov(r11, qword_ptr(r11 + WORD))?;
+            RemoteResultBuf::emit_write_x64_at_r11(
+                &mut asm,
+                RemoteResultStatus::Ok,
+                Self::result_len(false),
+            )?;
         }
-        asm.mov(qword_ptr(result_buf as u64), rax)?;
 
         asm.mov(rax, 0u64)?; // return 0
-
-        if F::ARITY > 4 {
-            asm.add(rsp, ((F::ARITY - 4) * mem::size_of::<usize>()) as i32)?;
-        }
-
-        asm.add(rsp, 8)?; // remove stack alignment
-        asm.ret()?; // Restore stack ptr.
+        asm.ret()?;
 
         let code = asm.assemble(0x1234_5678)?;
         debug_assert_eq!(
@@ -295,12 +563,252 @@ where
 
         Ok(code)
     }
+
+    #[cfg(not(feature = "assembler"))]
+    fn build_call_stub_x86(has_parameter: bool, has_result: bool) -> Result<Vec<u8>, Infallible> {
+        let callee_cleanup = match F::ABI {
+            Abi::C => false,
+            Abi::System => true,
+            _ => unreachable!(),
+        };
+        Ok(stub_templates::call_x86(
+            F::ARITY,
+            callee_cleanup,
+            has_parameter,
+            has_result.then(|| Self::result_len(true)),
+            RemoteRawProcedureStub::HEADER_WORDS * mem::size_of::<usize>(),
+        ))
+    }
+
+    #[cfg(not(feature = "assembler"))]
+    fn build_call_stub_x64(
+        has_parameter: bool,
+        has_result: bool,
+        float_mask: u32,
+    ) -> Result<Vec<u8>, Infallible> {
+        Ok(stub_templates::call_x64(
+            F::ARITY,
+            has_parameter,
+            has_result.then(|| Self::result_len(false)),
+            float_mask,
+            RemoteRawProcedureStub::HEADER_WORDS * mem::size_of::<u64>(),
+        ))
+    }
 }
 
 fn type_eq<T: ?Sized + 'static, U: ?Sized + 'static>() -> bool {
     TypeId::of::<T>() == TypeId::of::<U>()
 }
 
+/// The name, size and kind of a type in the signature of a remote procedure.
+#[derive(Debug, Clone, Copy)]
+struct TypeLayout {
+    name: &'static str,
+    id: TypeId,
+    size: usize,
+    kind: TypeKind,
+}
+
+impl TypeLayout {
+    fn of<T: 'static>() -> Self {
+        Self {
+            name: any::type_name::<T>(),
+            id: TypeId::of::<T>(),
+            size: mem::size_of::<T>(),
+            kind: T::kind(),
+        }
+    }
+}
+
+/// How a type in the signature of a remote procedure is passed to the target process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TypeKind {
+    /// A type that is copied into a word of the target process.
+    Plain,
+    /// A [`Truncate`], which is truncated to a word of the target process.
+    Truncated,
+    /// A reference, which points into the current process.
+    Reference,
+    /// A type that is known to have no C-compatible layout.
+    NotFfiSafe,
+}
+
+/// Helper trait for classifying the types in the signature of a remote procedure.
+trait SignatureType {
+    /// Returns how the type is passed to the target process.
+    fn kind() -> TypeKind;
+}
+
+impl<T> SignatureType for T {
+    default fn kind() -> TypeKind {
+        TypeKind::Plain
+    }
+}
+
+// options of types other than non-nullable pointers and integers have no guaranteed layout.
+impl<T> SignatureType for Option<T> {
+    default fn kind() -> TypeKind {
+        TypeKind::NotFfiSafe
+    }
+}
+
+macro_rules! impl_signature_type {
+    ($($kind:ident => { $(impl$(<$($param:ident $(: ?$relaxed:ident)?),*>)? for $ty:ty;)* })*) => {
+        $($(
+            impl$(<$($param $(: ?$relaxed)?),*>)? SignatureType for $ty {
+                fn kind() -> TypeKind {
+                    TypeKind::$kind
+                }
+            }
+        )*)*
+    };
+}
+
+impl_signature_type! {
+    Truncated => {
+        impl<T> for Truncate<T>;
+    }
+    Reference => {
+        impl<T: ?Sized> for &T;
+        impl<T: ?Sized> for &mut T;
+        impl<T: ?Sized> for Option<&T>;
+        impl<T: ?Sized> for Option<&mut T>;
+    }
+    Plain => {
+        impl<T> for Option<std::ptr::NonNull<T>>;
+        impl for Option<std::num::NonZeroU8>;
+        impl for Option<std::num::NonZeroU16>;
+        impl for Option<std::num::NonZeroU32>;
+        impl for Option<std::num::NonZeroU64>;
+        impl for Option<std::num::NonZeroUsize>;
+        impl for Option<std::num::NonZeroI8>;
+        impl for Option<std::num::NonZeroI16>;
+        impl for Option<std::num::NonZeroI32>;
+        impl for Option<std::num::NonZeroI64>;
+        impl for Option<std::num::NonZeroIsize>;
+    }
+    NotFfiSafe => {
+        impl for char;
+        impl for i128;
+        impl for u128;
+        impl for String;
+        impl for std::ffi::CString;
+        impl for std::ffi::OsString;
+        impl for std::path::PathBuf;
+        impl<T> for Vec<T>;
+        impl<T: ?Sized> for Box<T>;
+        impl<T: ?Sized> for Rc<T>;
+        impl<T: ?Sized> for std::sync::Arc<T>;
+        impl<T> for std::cell::Cell<T>;
+        impl<T> for std::cell::RefCell<T>;
+        impl<T, E> for Result<T, E>;
+        impl<A> for (A,);
+        impl<A, B> for (A, B);
+        impl<A, B, C> for (A, B, C);
+        impl<A, B, C, D> for (A, B, C, D);
+        impl<A, B, C, D, E> for (A, B, C, D, E);
+        impl<A, B, C, D, E, F> for (A, B, C, D, E, F);
+    }
+}
+
+// C passes arrays by pointer.
+impl<T, const N: usize> SignatureType for [T; N] {
+    fn kind() -> TypeKind {
+        TypeKind::NotFfiSafe
+    }
+}
+
+/// Helper trait for listing the layouts of the arguments of a function pointer.
+trait ArgumentLayouts {
+    /// Returns the layouts of the arguments in order.
+    fn argument_layouts() -> Vec<TypeLayout>;
+}
+
+impl<F: FunctionPtr> ArgumentLayouts for F {
+    default fn argument_layouts() -> Vec<TypeLayout> {
+        // This default implementation will never be called as there exists a specialization for every valid function pointer (defined in the macro below).
+        unreachable!()
+    }
+}
+
+/// Checks that a value of the given type can be passed between processes in a word of the given size, if known.
+fn validate_layout(
+    position: SignaturePosition,
+    layout: TypeLayout,
+    word_size: Option<usize>,
+) -> Result<(), SignatureError> {
+    let type_name = layout.name;
+    match layout.kind {
+        TypeKind::Reference => {
+            return Err(SignatureError::Reference {
+                position,
+                type_name,
+            })
+        }
+        TypeKind::NotFfiSafe => {
+            return Err(SignatureError::NotFfiSafe {
+                position,
+                type_name,
+            })
+        }
+        TypeKind::Plain | TypeKind::Truncated => {}
+    }
+    match word_size {
+        Some(word_size) if layout.size > word_size && layout.kind != TypeKind::Truncated => {
+            Err(SignatureError::TooLarge {
+                position,
+                type_name,
+                size: layout.size,
+                word_size,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns whether the given function ends with the out buffer arguments and return value of `call_with_out_buf`.
+fn is_out_buf_signature<F: FunctionPtr>(arguments: &[TypeLayout]) -> bool {
+    type_eq::<F::Output, usize>()
+        && matches!(
+            arguments,
+            [.., buf, capacity] if buf.id == TypeId::of::<*mut u8>()
+                && capacity.id == TypeId::of::<usize>()
+        )
+}
+
+/// Helper trait for types that are as wide as a pointer of the process they are used in.
+trait PointerSized {
+    /// Returns whether the type is as wide as a pointer.
+    fn is_pointer_sized() -> bool;
+}
+
+impl<T> PointerSized for T {
+    default fn is_pointer_sized() -> bool {
+        false
+    }
+}
+
+macro_rules! impl_pointer_sized {
+    ($(impl$(<$param:ident>)? for $ty:ty;)*) => {
+        $(
+            impl$(<$param>)? PointerSized for $ty {
+                fn is_pointer_sized() -> bool {
+                    true
+                }
+            }
+        )*
+    };
+}
+
+impl_pointer_sized! {
+    impl for usize;
+    impl for isize;
+    impl<T> for *const T;
+    impl<T> for *mut T;
+    impl<T> for std::ptr::NonNull<T>;
+    impl<T> for Option<std::ptr::NonNull<T>>;
+}
+
 /// Helper trait for building a mask of which arguments and results are passed in floating point registers.
 trait BuildFloatMask {
     /// Returns a mask of which arguments and results are passed in floating point registers.
@@ -310,6 +818,47 @@ trait BuildFloatMask {
     fn build_float_mask() -> u32;
 }
 
+/// The contents of the out buffer filled by a call to a [`RemoteRawProcedure`] through `call_with_out_buf`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-raw")))]
+pub struct RawOutBuf {
+    data: Vec<u8>,
+    len: usize,
+}
+
+impl RawOutBuf {
+    /// Returns the bytes written to the out buffer, truncated to its capacity.
+    #[must_use]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the length reported by the remote procedure.
+    /// If the out buffer was too small this may be larger than the length of [`data`](Self::data).
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the remote procedure reported an empty result.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns whether the reported length exceeded the capacity of the out buffer, i.e. [`data`](Self::data) is incomplete.
+    #[must_use]
+    pub fn is_truncated(&self) -> bool {
+        self.len > self.data.len()
+    }
+
+    /// Consumes the out buffer and returns the bytes written to it.
+    #[must_use]
+    pub fn into_vec(self) -> Vec<u8> {
+        self.data
+    }
+}
+
 #[derive(shrinkwraprs::Shrinkwrap, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-raw")))]
 #[repr(transparent)]
@@ -347,12 +896,9 @@ macro_rules! impl_call {
                     mem::size_of::<u64>()
                 };
 
-                let truncate_prefix = any::type_name::<Truncate<()>>();
-                let truncate_prefix = &truncate_prefix[..(truncate_prefix.len() - "()>".len())];
-
                 // store arguments as a usize buffer (avoids handling different sized arguments explicitly)
                 let args_buf = [$({
-                    let is_truncate = any::type_name::<$ty>().starts_with(truncate_prefix);
+                    let is_truncate = <$ty as SignatureType>::kind() == TypeKind::Truncated;
 
                     assert!(
                         is_truncate || mem::size_of::<$ty>() <= target_pointer_size,
@@ -377,12 +923,28 @@ macro_rules! impl_call {
 
                 // use vars to avoid dead_code warning
                 let _ = target_pointer_size;
-                let _ = truncate_prefix;
 
                 Ok(args_buf)
             }
         }
 
+        impl_signature_type! {
+            Plain => {
+                impl<$($ty,)* Output> for Option<fn($($ty),*) -> Output>;
+                impl<$($ty,)* Output> for Option<unsafe fn($($ty),*) -> Output>;
+                impl<$($ty,)* Output> for Option<extern "system" fn($($ty),*) -> Output>;
+                impl<$($ty,)* Output> for Option<unsafe extern "system" fn($($ty),*) -> Output>;
+                impl<$($ty,)* Output> for Option<extern "C" fn($($ty),*) -> Output>;
+                impl<$($ty,)* Output> for Option<unsafe extern "C" fn($($ty),*) -> Output>;
+            }
+        }
+
+        impl <$($ty,)* Output> ArgumentLayouts for fn($($ty),*) -> Output where $($ty : 'static,)* Output: 'static {
+            fn argument_layouts() -> Vec<TypeLayout> {
+                vec![$(TypeLayout::of::<$ty>()),*]
+            }
+        }
+
         impl <$($ty,)* Output> BuildFloatMask for fn($($ty),*) -> Output where $($ty : 'static,)* Output: 'static {
             fn build_float_mask() -> u32 {
                 // calculate a mask denoting which arguments are floats
@@ -454,3 +1016,296 @@ impl_call! {
     arg0:  A, arg1:  B, arg2:  C, arg3:  D, arg4:  E, arg5:  F,arg6:  G,
     arg7:  H, arg8:  I, arg9:  J, arg10: K, arg11: L
 }
+
+macro_rules! impl_call_with_out_buf {
+    (@recurse () ($($nm:ident : $ty:ident),*)) => {
+        impl_call_with_out_buf!(@impl_all ($($nm : $ty),*));
+    };
+    (@recurse ($hd_nm:ident : $hd_ty:ident $(, $tl_nm:ident : $tl_ty:ident)*) ($($nm:ident : $ty:ident),*)) => {
+        impl_call_with_out_buf!(@impl_all ($($nm : $ty),*));
+        impl_call_with_out_buf!(@recurse ($($tl_nm : $tl_ty),*) ($($nm : $ty,)* $hd_nm : $hd_ty));
+    };
+
+    (@impl_all ($($nm:ident : $ty:ident),*)) => {
+        impl_call_with_out_buf!(@impl_one (extern "system" fn($($ty,)* *mut u8, usize) -> usize) ($($nm : $ty),*));
+        impl_call_with_out_buf!(@impl_one (extern "C" fn($($ty,)* *mut u8, usize) -> usize) ($($nm : $ty),*));
+        impl_call_with_out_buf!(@impl_unsafe_one (unsafe extern "system" fn($($ty,)* *mut u8, usize) -> usize) ($($nm : $ty),*));
+        impl_call_with_out_buf!(@impl_unsafe_one (unsafe extern "C" fn($($ty,)* *mut u8, usize) -> usize) ($($nm : $ty),*));
+    };
+
+    (@impl_one ($fn_ty:ty) ($($nm:ident : $ty:ident),*)) => {
+        impl <$($ty,)*> RemoteRawProcedure<$fn_ty> where $($ty : 'static + Copy,)* {
+            /// Calls the remote procedure with the given arguments followed by a pointer to an out buffer of the given capacity and the capacity itself.
+            /// The remote procedure is expected to return the number of bytes it wrote (or would have written) to the out buffer.
+            #[allow(clippy::too_many_arguments)]
+            pub fn call_with_out_buf(&self, $($nm: $ty,)* capacity: usize) -> Result<RawOutBuf, RawRpcError> {
+                let args_buf = RemoteRawProcedure::<fn($($ty),*) -> usize>::build_args_buf(self.process(), $($nm),*)?;
+                self.call_with_out_buf_args(&args_buf, capacity)
+            }
+        }
+    };
+    (@impl_unsafe_one ($fn_ty:ty) ($($nm:ident : $ty:ident),*)) => {
+        impl <$($ty,)*> RemoteRawProcedure<$fn_ty> where $($ty : 'static + Copy,)* {
+            /// Calls the remote procedure with the given arguments followed by a pointer to an out buffer of the given capacity and the capacity itself.
+            /// The remote procedure is expected to return the number of bytes it wrote (or would have written) to the out buffer.
+            ///
+            /// # Safety
+            /// The caller must ensure whatever the requirements of the underlying remote procedure are.
+            #[allow(clippy::too_many_arguments)]
+            pub unsafe fn call_with_out_buf(&self, $($nm: $ty,)* capacity: usize) -> Result<RawOutBuf, RawRpcError> {
+                let args_buf = RemoteRawProcedure::<fn($($ty),*) -> usize>::build_args_buf(self.process(), $($nm),*)?;
+                self.call_with_out_buf_args(&args_buf, capacity)
+            }
+        }
+    };
+
+    ($($nm:ident : $ty:ident),*) => {
+        impl_call_with_out_buf!(@recurse ($($nm : $ty),*) ());
+    };
+}
+
+impl_call_with_out_buf! {
+    arg0:  A, arg1:  B, arg2:  C, arg3:  D, arg4:  E, arg5:  F,arg6:  G,
+    arg7:  H, arg8:  I, arg9:  J
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::OwnedProcess;
+
+    #[test]
+    fn procedures_of_the_same_signature_share_their_stub() {
+        let syringe = Syringe::for_process(OwnedProcess::current());
+        let kernel32 = syringe
+            .process()
+            .find_module_by_name("kernel32.dll")
+            .unwrap()
+            .unwrap();
+
+        let get_current_process_id = unsafe {
+            syringe
+                .get_raw_procedure::<extern "system" fn() -> u32>(kernel32, "GetCurrentProcessId")
+        }
+        .unwrap()
+        .unwrap();
+        let get_current_thread_id = unsafe {
+            syringe.get_raw_procedure::<extern "system" fn() -> u32>(kernel32, "GetCurrentThreadId")
+        }
+        .unwrap()
+        .unwrap();
+        assert_eq!(get_current_process_id.call().unwrap(), std::process::id());
+        assert_ne!(get_current_thread_id.call().unwrap(), 0);
+
+        let process_id_stub = get_current_process_id.stub.get().unwrap();
+        let thread_id_stub = get_current_thread_id.stub.get().unwrap();
+        assert!(Rc::ptr_eq(&process_id_stub.code, &thread_id_stub.code));
+        assert_ne!(
+            process_id_stub.parameter.as_ref().unwrap().as_raw_ptr(),
+            thread_id_stub.parameter.as_ref().unwrap().as_raw_ptr()
+        );
+    }
+
+    #[test]
+    fn procedures_follow_redirected_exports() {
+        let syringe = Syringe::for_process(OwnedProcess::current());
+        let kernel32 = syringe
+            .process()
+            .find_module_by_name("kernel32.dll")
+            .unwrap()
+            .unwrap();
+        let get_current_thread_id = syringe
+            .get_procedure_address(kernel32, "GetCurrentThreadId")
+            .unwrap()
+            .unwrap();
+
+        // pretend that `GetCurrentProcessId` was resolved while its export was redirected to `GetCurrentThreadId`.
+        let slot = syringe.procedure_cache.insert(
+            kernel32.borrowed(),
+            "GetCurrentProcessId".to_string(),
+            Some(get_current_thread_id),
+        );
+        let entry = Box::new(std::sync::atomic::AtomicU32::new(0));
+        slot.watch_export_entry(entry.as_ptr() as usize, 0);
+        let procedure = RemoteRawProcedure::<extern "system" fn() -> u32>::new(
+            unsafe { FunctionPtr::from_ptr(get_current_thread_id) },
+            syringe.remote_allocator.clone(),
+            kernel32.handle(),
+            syringe.remote_thread_options.clone(),
+        )
+        .following(slot);
+        assert_ne!(procedure.call().unwrap(), std::process::id());
+
+        entry.store(1, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(procedure.call().unwrap(), std::process::id());
+    }
+
+    #[test]
+    fn validate_layout_rejects_types_that_can_not_cross_processes() {
+        fn check<T: 'static>(word_size: usize) -> Result<(), SignatureError> {
+            validate_layout(
+                SignaturePosition::Argument(0),
+                TypeLayout::of::<T>(),
+                Some(word_size),
+            )
+        }
+
+        assert!(check::<u32>(4).is_ok());
+        assert!(check::<*mut u8>(8).is_ok());
+        assert!(check::<Truncate<usize>>(4).is_ok());
+        assert!(check::<Option<std::ptr::NonNull<u8>>>(8).is_ok());
+        assert!(check::<Option<extern "system" fn()>>(8).is_ok());
+        assert!(check::<Option<std::num::NonZeroU32>>(4).is_ok());
+        assert!(matches!(
+            check::<&str>(8),
+            Err(SignatureError::Reference { .. })
+        ));
+        assert!(matches!(
+            check::<Option<std::num::Wrapping<u32>>>(8),
+            Err(SignatureError::NotFfiSafe { .. })
+        ));
+        assert!(matches!(
+            check::<&u32>(8),
+            Err(SignatureError::Reference { .. })
+        ));
+        assert!(matches!(
+            check::<String>(8),
+            Err(SignatureError::NotFfiSafe { .. })
+        ));
+        assert!(matches!(
+            check::<Option<u32>>(8),
+            Err(SignatureError::NotFfiSafe { .. })
+        ));
+        assert!(matches!(
+            check::<(u8, u8)>(8),
+            Err(SignatureError::NotFfiSafe { .. })
+        ));
+        assert!(matches!(
+            check::<char>(8),
+            Err(SignatureError::NotFfiSafe { .. })
+        ));
+        assert_eq!(
+            check::<u64>(4),
+            Err(SignatureError::TooLarge {
+                position: SignaturePosition::Argument(0),
+                type_name: "u64",
+                size: 8,
+                word_size: 4,
+            })
+        );
+        assert!(
+            validate_layout(SignaturePosition::Return, TypeLayout::of::<()>(), Some(8)).is_ok()
+        );
+    }
+
+    #[test]
+    fn get_raw_procedure_rejects_invalid_signature() {
+        let syringe = Syringe::for_process(OwnedProcess::current());
+        let kernel32 = syringe
+            .process()
+            .find_module_by_name("kernel32.dll")
+            .unwrap()
+            .unwrap();
+
+        let result = unsafe {
+            syringe.get_raw_procedure::<extern "system" fn(char) -> u32>(
+                kernel32,
+                "GetCurrentProcessId",
+            )
+        };
+        assert!(matches!(
+            result,
+            Err(LoadProcedureError::InvalidSignature(
+                SignatureError::NotFfiSafe { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn return_types_must_be_returned_in_registers() {
+        #[derive(Clone, Copy)]
+        #[repr(C)]
+        struct Large([u32; 3]);
+        #[derive(Clone, Copy)]
+        #[repr(C)]
+        struct Odd([u8; 3]);
+
+        assert!(RemoteRawProcedure::<extern "system" fn() -> u64>::check_return_type().is_ok());
+        assert!(matches!(
+            RemoteRawProcedure::<extern "system" fn() -> Large>::check_return_type(),
+            Err(SignatureError::ReturnedByPointer { size: 12, .. })
+        ));
+        assert!(matches!(
+            RemoteRawProcedure::<extern "system" fn() -> Odd>::check_return_type(),
+            Err(SignatureError::ReturnedByPointer { size: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn pointer_sized_results_of_x86_targets_are_truncated() {
+        assert_eq!(
+            RemoteRawProcedure::<extern "C" fn() -> usize>::result_len(true),
+            4
+        );
+        assert_eq!(
+            RemoteRawProcedure::<extern "C" fn() -> *mut u8>::result_len(true),
+            4
+        );
+        assert_eq!(
+            RemoteRawProcedure::<extern "C" fn() -> u64>::result_len(true),
+            8
+        );
+        assert_eq!(
+            RemoteRawProcedure::<extern "C" fn() -> usize>::result_len(false),
+            mem::size_of::<usize>() as u32
+        );
+    }
+
+    #[cfg(feature = "assembler")]
+    fn assert_call_stubs_match_templates<F: RawRpcFunctionPtr>() {
+        let float_mask = <F::NonExtern>::build_float_mask();
+        let mut variants = vec![(true, false), (true, true)];
+        if F::ARITY == 0 {
+            variants.push((false, false));
+        }
+
+        for (has_parameter, has_result) in variants {
+            assert_eq!(
+                RemoteRawProcedure::<F>::build_call_stub_x86(has_parameter, has_result).unwrap(),
+                crate::stub_templates::call_x86(
+                    F::ARITY,
+                    F::ABI == Abi::System,
+                    has_parameter,
+                    has_result.then(|| RemoteRawProcedure::<F>::result_len(true)),
+                    RemoteRawProcedureStub::HEADER_WORDS * mem::size_of::<usize>(),
+                ),
+                "x86 call stub of {}",
+                any::type_name::<F>()
+            );
+            assert_eq!(
+                RemoteRawProcedure::<F>::build_call_stub_x64(has_parameter, has_result, float_mask)
+                    .unwrap(),
+                crate::stub_templates::call_x64(
+                    F::ARITY,
+                    has_parameter,
+                    has_result.then(|| RemoteRawProcedure::<F>::result_len(false)),
+                    float_mask,
+                    RemoteRawProcedureStub::HEADER_WORDS * mem::size_of::<u64>(),
+                ),
+                "x64 call stub of {}",
+                any::type_name::<F>()
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "assembler")]
+    fn call_stub_templates_match_the_assembler() {
+        assert_call_stubs_match_templates::<extern "system" fn()>();
+        assert_call_stubs_match_templates::<extern "C" fn(u32) -> u64>();
+        assert_call_stubs_match_templates::<extern "system" fn(f64, u32, f32, usize, u8) -> f64>();
+        assert_call_stubs_match_templates::<
+            unsafe extern "C" fn(u8, u16, u32, u64, f32, f64, usize, isize, u8, u16) -> u32,
+        >();
+    }
+}
@@ -1,89 +1,584 @@
+#[cfg(feature = "assembler")]
 use iced_x86::{code_asm::*, IcedError};
+#[cfg(not(feature = "assembler"))]
+use {crate::stub_templates, std::convert::Infallible};
 
 use std::{
-    ffi::CString,
-    mem,
-    ptr::{self, NonNull},
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ffi::{c_char, c_void},
+    io, mem,
+    rc::Rc,
+    time::Instant,
 };
 
+use winapi::shared::winerror::{ERROR_MOD_NOT_FOUND, ERROR_PROC_NOT_FOUND};
+
 use crate::{
-    error::LoadProcedureError,
-    function::{FunctionPtr, RawFunctionPtr},
+    error::{LoadProcedureError, Operation},
+    function::RawFunctionPtr,
     process::{
-        memory::{RemoteAllocation, RemoteBox},
-        BorrowedProcessModule, Process,
+        memory::{
+            ProcessMemorySlice, Ptr32, Ptr64, RemoteAllocation, RemoteImage, RemotePtr,
+            RemotePtrWidth, RemoteResult, RemoteResultBuf,
+        },
+        BorrowedProcessModule, ExportSnapshotDiff, ModuleHandle, Process, RemoteThreadOptions,
     },
     rpc::error::RawRpcError,
-    GetProcAddressFn, Syringe,
+    utils::{to_ansi_cstring, trace_event},
+    Syringe,
+};
+
+#[cfg(feature = "assembler")]
+use crate::{
+    function::FunctionPtr, process::memory::RemoteResultStatus, GetLastErrorFn, GetProcAddressFn,
 };
 
 #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-core")))]
 impl Syringe {
     /// Load the address of the given function from the given module in the remote process.
-    pub fn get_procedure_address(
+    ///
+    /// # Note
+    /// Results are cached per module and name, so repeated lookups do not execute code in the target process.
+    /// The cache entries of a module are discarded once it is found to be unloaded or ejected through this syringe.
+    /// If a module might have been reloaded at the same address in the meantime, use [`Syringe::clear_procedure_cache`].
+    ///
+    /// Before a cached result or a procedure created from it (e.g. by [`Syringe::get_raw_procedure`](crate::Syringe::get_raw_procedure))
+    /// is used, the entry of the export address table it was resolved from is checked. If the export was redirected in the
+    /// meantime, e.g. by a payload patching its exports at runtime, the procedure is looked up again in the export directory.
+    ///
+    /// Names containing non-ASCII characters are converted to the ANSI code page of the system, like `GetProcAddress` expects them.
+    pub fn get_procedure_address<'a>(
         &self,
-        module: BorrowedProcessModule<'_>,
+        module: impl Into<BorrowedProcessModule<'a>>,
         name: impl AsRef<str>,
     ) -> Result<Option<RawFunctionPtr>, LoadProcedureError> {
+        let module = module.into();
+        self.get_procedure_address_cached(module, ProcedureName::Name(name.as_ref()))
+    }
+
+    /// Load the address of the function with the given ordinal from the given module in the remote process.
+    ///
+    /// # Note
+    /// Results are cached like the ones of [`Syringe::get_procedure_address`].
+    pub fn get_procedure_address_by_ordinal<'a>(
+        &self,
+        module: impl Into<BorrowedProcessModule<'a>>,
+        ordinal: u16,
+    ) -> Result<Option<RawFunctionPtr>, LoadProcedureError> {
+        let module = module.into();
+        self.get_procedure_address_cached(module, ProcedureName::Ordinal(ordinal))
+    }
+
+    /// Load the addresses of the functions with the given names from the given module in the remote process.
+    /// The results are returned in the order of the names.
+    ///
+    /// Unlike repeated calls to [`Syringe::get_procedure_address`], all names that are not cached yet are resolved by
+    /// a single execution in the target process, which makes resolving many procedures at once considerably faster.
+    ///
+    /// # Note
+    /// Results are cached like the ones of [`Syringe::get_procedure_address`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dll_syringe::{process::{OwnedProcess, Process}, Syringe};
+    ///
+    /// let syringe = Syringe::for_process(OwnedProcess::find_first_by_name("target_process").unwrap());
+    /// let kernel32 = syringe.process().find_module_by_name("kernel32.dll").unwrap().unwrap();
+    /// let procedures = syringe
+    ///     .get_procedures(kernel32, &["OpenProcess", "CloseHandle", "ExitProcess"])
+    ///     .unwrap();
+    /// assert!(procedures.iter().all(Option::is_some));
+    /// ```
+    pub fn get_procedures<'a>(
+        &self,
+        module: impl Into<BorrowedProcessModule<'a>>,
+        names: &[impl AsRef<str>],
+    ) -> Result<Vec<Option<RawFunctionPtr>>, LoadProcedureError> {
+        let module = module.into();
+        assert!(
+            module.process() == &self.process(),
+            "trying to get a procedure from a module from a different process"
+        );
+
+        if self.has_target_exited() {
+            return Err(LoadProcedureError::ProcessInaccessible);
+        }
+
+        if !module.guess_is_loaded() {
+            self.procedure_cache.remove_module(module.handle());
+        }
+        let mut procedures = vec![None; names.len()];
+        let mut uncached = Vec::new();
+        for (i, name) in names.iter().enumerate() {
+            match self.procedure_cache.get(module.handle(), name.as_ref()) {
+                Some(slot) => {
+                    procedures[i] = slot.address(module).map_err(|err| {
+                        self.error_unless_exited(
+                            err.into(),
+                            LoadProcedureError::ProcessInaccessible,
+                        )
+                    })?;
+                }
+                None => uncached.push(i),
+            }
+        }
+        if uncached.is_empty() {
+            return Ok(procedures);
+        }
+
+        let uncached_names = uncached
+            .iter()
+            .map(|&i| names[i].as_ref())
+            .collect::<Vec<_>>();
+        let resolved = match self.procedure_lookup_method {
+            ProcedureLookupMethod::RemoteThread => {
+                self.get_procedures_remote(module, &uncached_names)
+            }
+            ProcedureLookupMethod::ExportTable => uncached_names
+                .iter()
+                .map(|&name| self.get_procedure_address_uncached(module, ProcedureName::Name(name)))
+                .collect(),
+        }
+        .map_err(|err| self.error_unless_exited(err, LoadProcedureError::ProcessInaccessible))?;
+        trace_event!(
+            debug,
+            module = ?module.handle(),
+            names = names.len(),
+            resolved = resolved.len(),
+            "resolved procedures"
+        );
+
+        for (i, procedure) in uncached.into_iter().zip(resolved) {
+            self.procedure_cache
+                .insert(module, names[i].as_ref().to_string(), procedure);
+            procedures[i] = procedure;
+        }
+        Ok(procedures)
+    }
+
+    fn get_procedure_address_cached(
+        &self,
+        module: BorrowedProcessModule<'_>,
+        name: ProcedureName<'_>,
+    ) -> Result<Option<RawFunctionPtr>, LoadProcedureError> {
+        self.get_procedure_slot(module, name)?
+            .address(module)
+            .map_err(|err| {
+                self.error_unless_exited(err.into(), LoadProcedureError::ProcessInaccessible)
+            })
+    }
+
+    /// Returns the cached result of looking up the given procedure, looking it up first if it is not cached yet.
+    pub(crate) fn get_procedure_slot(
+        &self,
+        module: BorrowedProcessModule<'_>,
+        name: ProcedureName<'_>,
+    ) -> Result<Rc<ProcedureSlot>, LoadProcedureError> {
         assert!(
             module.process() == &self.process(),
             "trying to get a procedure from a module from a different process"
         );
 
+        if self.has_target_exited() {
+            return Err(LoadProcedureError::ProcessInaccessible);
+        }
+
+        let cache_key = name.cache_key();
+        if let Some(slot) = self.procedure_cache.get(module.handle(), &cache_key) {
+            if module.guess_is_loaded() {
+                return Ok(slot);
+            }
+            self.procedure_cache.remove_module(module.handle());
+        }
+
+        let procedure = self
+            .get_procedure_address_uncached(module, name)
+            .map_err(|err| {
+                self.error_unless_exited(err, LoadProcedureError::ProcessInaccessible)
+            })?;
+        Ok(self
+            .procedure_cache
+            .insert(module, cache_key.into_owned(), procedure))
+    }
+
+    /// Returns the method used by [`Syringe::get_procedure_address`] to look up procedures.
+    #[must_use]
+    pub fn procedure_lookup_method(&self) -> ProcedureLookupMethod {
+        self.procedure_lookup_method
+    }
+
+    /// Sets the method used by [`Syringe::get_procedure_address`] to look up procedures.
+    pub fn set_procedure_lookup_method(&mut self, method: ProcedureLookupMethod) {
+        self.procedure_lookup_method = method;
+    }
+
+    /// Discards all cached results of [`Syringe::get_procedure_address`].
+    pub fn clear_procedure_cache(&self) {
+        self.procedure_cache.clear();
+    }
+
+    /// Discards the cached results of [`Syringe::get_procedure_address`] for the exports that were removed or changed
+    /// according to the given diff of export snapshots and returns the number of discarded results.
+    /// The procedures created from the discarded results look up their address again before their next call.
+    ///
+    /// Exports that are redirected by changing their entry in the export address table are detected automatically, see
+    /// [`Syringe::get_procedure_address`]. This is only needed for other changes, e.g. if the export directory was replaced.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dll_syringe::{process::OwnedProcess, Syringe};
+    ///
+    /// let syringe = Syringe::for_process(OwnedProcess::find_first_by_name("target_process").unwrap());
+    /// let module = syringe.inject("injection_payload.dll").unwrap();
+    /// let before = module.export_snapshot().unwrap();
+    /// // ... the payload patches its exports ...
+    /// let diff = before.diff(&module.export_snapshot().unwrap());
+    /// syringe.invalidate_stale_procedures(&diff);
+    /// ```
+    pub fn invalidate_stale_procedures(&self, diff: &ExportSnapshotDiff) -> usize {
+        let mut removed = 0;
+        for export in diff.stale() {
+            if let Some(name) = export.name() {
+                removed += usize::from(self.procedure_cache.remove(diff.module, name));
+            }
+            let ordinal = ProcedureName::Ordinal(export.ordinal());
+            removed += usize::from(
+                self.procedure_cache
+                    .remove(diff.module, &ordinal.cache_key()),
+            );
+        }
+        trace_event!(debug, module = ?diff.module, removed, "invalidated stale procedures");
+        removed
+    }
+
+    fn get_procedure_address_uncached(
+        &self,
+        module: BorrowedProcessModule<'_>,
+        name: ProcedureName<'_>,
+    ) -> Result<Option<RawFunctionPtr>, LoadProcedureError> {
+        match self.procedure_lookup_method {
+            ProcedureLookupMethod::RemoteThread => self.get_procedure_address_remote(module, name),
+            ProcedureLookupMethod::ExportTable => {
+                if !module.guess_is_loaded() {
+                    return Err(if self.process().is_alive() {
+                        LoadProcedureError::ModuleInaccessible
+                    } else {
+                        LoadProcedureError::ProcessInaccessible
+                    });
+                }
+                Ok(match name {
+                    ProcedureName::Name(name) => module.get_procedure_address_from_exports(name)?,
+                    ProcedureName::Ordinal(ordinal) => {
+                        module.get_procedure_address_from_exports_by_ordinal(ordinal)?
+                    }
+                })
+            }
+        }
+    }
+
+    fn get_procedure_address_remote(
+        &self,
+        module: BorrowedProcessModule<'_>,
+        name: ProcedureName<'_>,
+    ) -> Result<Option<RawFunctionPtr>, LoadProcedureError> {
         let stub = self.build_get_proc_address_stub()?;
-        let name = name.as_ref();
-        let name = self
-            .remote_allocator
-            .alloc_and_copy_buf(CString::new(name).unwrap().as_bytes_with_nul())?;
-        stub.parameter.write(&GetProcAddressParams {
-            module_handle: module.handle() as u64,
-            name: name.as_raw_ptr() as u64,
-        })?;
+        // kept alive until the stub returned.
+        let mut remote_name = None;
+        let name = match name {
+            ProcedureName::Name(name) => {
+                let name = self
+                    .remote_allocator
+                    .alloc_and_copy_buf(to_ansi_cstring(name)?.as_bytes_with_nul())?;
+                remote_name.insert(name).as_raw_ptr() as u64
+            }
+            // GetProcAddress treats values below 0x10000 as ordinals (MAKEINTRESOURCEA).
+            ProcedureName::Ordinal(ordinal) => u64::from(ordinal),
+        };
+        let parameter = stub.parameter.memory();
+        let module_handle = module.handle() as u64;
+        if self.remote_allocator.is_x86()? {
+            parameter.write_struct(0, &GetProcAddressParams::<Ptr32>::new(module_handle, name)?)?;
+        } else {
+            parameter.write_struct(0, &GetProcAddressParams::<Ptr64>::new(module_handle, name)?)?;
+        }
 
-        // clear the result
-        stub.result.write(&ptr::null_mut())?;
+        stub.result.reset()?;
 
-        let exit_code = self.remote_allocator.process().run_remote_thread(
-            unsafe { mem::transmute(stub.code.as_raw_ptr()) },
-            stub.parameter.as_raw_ptr(),
-        )?;
-        Syringe::remote_exit_code_to_exception(exit_code)?;
+        let start = Instant::now();
+        self.remote_allocator
+            .run_remote_thread(
+                unsafe {
+                    mem::transmute::<*mut u8, extern "system" fn(*mut u8) -> u32>(
+                        stub.code.as_raw_ptr(),
+                    )
+                },
+                stub.parameter.as_raw_ptr(),
+                &self.remote_thread_options,
+            )
+            .map_err(LoadProcedureError::from)
+            .and_then(|result| result.into_result().map_err(LoadProcedureError::from))
+            .inspect_err(|e| {
+                self.emit_remote_call_failed(Operation::LoadProcedure, e, start);
+            })?;
 
-        Ok(NonNull::new(stub.result.read()?).map(|p| p.as_ptr()))
+        match stub.result.read_word()? {
+            RemoteResult::Ok(procedure) => Ok(Some(procedure as usize as RawFunctionPtr)),
+            RemoteResult::Err(ERROR_PROC_NOT_FOUND) => Ok(None),
+            RemoteResult::Err(ERROR_MOD_NOT_FOUND) => Err(LoadProcedureError::ModuleInaccessible),
+            result => Err(LoadProcedureError::RemoteIo(
+                result.into_io_result("GetProcAddress").unwrap_err(),
+            )),
+        }
     }
 
-    fn build_get_proc_address_stub(
+    /// Resolves the given names using the batch stub, which calls `GetProcAddress` for each entry of a name table.
+    #[cfg(feature = "assembler")]
+    fn get_procedures_remote(
         &self,
-    ) -> Result<&RemoteProcedureStub<GetProcAddressParams, RawFunctionPtr>, LoadProcedureError>
-    {
-        self.get_proc_address_stub.get_or_try_init(|| {
-            let inject_data = self.inject_help_data.get_or_try_init(|| {
-                Self::load_inject_help_data_for_process(self.remote_allocator.process())
+        module: BorrowedProcessModule<'_>,
+        names: &[&str],
+    ) -> Result<Vec<Option<RawFunctionPtr>>, LoadProcedureError> {
+        let stub = self.build_get_procs_stub()?;
+
+        // the table consists of a header (module handle, entry count) and one entry (name, procedure, error) per name,
+        // followed by the names. Every field is 8 bytes wide, so the x86 stub uses the lower half of each field.
+        let ansi_names = names
+            .iter()
+            .map(|&name| to_ansi_cstring(name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let entries_offset = GetProcsHeader::SIZE;
+        let names_offset = entries_offset + names.len() * GetProcsEntry::SIZE;
+        let table_len = names_offset
+            + ansi_names
+                .iter()
+                .map(|name| name.as_bytes_with_nul().len())
+                .sum::<usize>();
+        let table = self.remote_allocator.alloc_buf::<u8>(table_len)?;
+        let table_address = table.as_raw_ptr() as u64;
+
+        let mut buf = Vec::with_capacity(table_len);
+        buf.extend_from_slice(&(module.handle() as usize as u64).to_le_bytes());
+        buf.extend_from_slice(&(names.len() as u64).to_le_bytes());
+        let mut name_address = table_address + names_offset as u64;
+        for name in &ansi_names {
+            buf.extend_from_slice(&name_address.to_le_bytes());
+            buf.extend_from_slice(&[0; GetProcsEntry::SIZE - 8]);
+            name_address += name.as_bytes_with_nul().len() as u64;
+        }
+        for name in &ansi_names {
+            buf.extend_from_slice(name.as_bytes_with_nul());
+        }
+        table.write_bytes(&buf)?;
+
+        let start = Instant::now();
+        self.remote_allocator
+            .run_remote_thread(
+                unsafe {
+                    mem::transmute::<*mut u8, extern "system" fn(*mut u8) -> u32>(stub.as_raw_ptr())
+                },
+                table.as_raw_ptr(),
+                &self.remote_thread_options,
+            )
+            .map_err(LoadProcedureError::from)
+            .and_then(|result| result.into_result().map_err(LoadProcedureError::from))
+            .inspect_err(|e| {
+                self.emit_remote_call_failed(Operation::LoadProcedure, e, start);
             })?;
 
+        let entries = table
+            .memory()
+            .read_vec(entries_offset, names.len() * GetProcsEntry::SIZE)?;
+        entries
+            .chunks_exact(GetProcsEntry::SIZE)
+            .map(|entry| match GetProcsEntry::decode(entry) {
+                GetProcsEntry {
+                    procedure: 0,
+                    error: ERROR_PROC_NOT_FOUND,
+                } => Ok(None),
+                GetProcsEntry {
+                    procedure: 0,
+                    error: ERROR_MOD_NOT_FOUND,
+                } => Err(LoadProcedureError::ModuleInaccessible),
+                GetProcsEntry {
+                    procedure: 0,
+                    error,
+                } => Err(LoadProcedureError::RemoteIo(io::Error::from_raw_os_error(
+                    error as i32,
+                ))),
+                GetProcsEntry { procedure, .. } => Ok(Some(procedure as usize as RawFunctionPtr)),
+            })
+            .collect()
+    }
+
+    /// Resolves the given names one by one, as the batch stub can only be built with the `assembler` feature.
+    #[cfg(not(feature = "assembler"))]
+    fn get_procedures_remote(
+        &self,
+        module: BorrowedProcessModule<'_>,
+        names: &[&str],
+    ) -> Result<Vec<Option<RawFunctionPtr>>, LoadProcedureError> {
+        names
+            .iter()
+            .map(|&name| self.get_procedure_address_remote(module, ProcedureName::Name(name)))
+            .collect()
+    }
+
+    #[cfg(feature = "assembler")]
+    fn build_get_procs_stub(&self) -> Result<&RemoteAllocation, LoadProcedureError> {
+        self.get_procs_stub.get_or_try_init(|| {
+            let inject_data = self.inject_help_data()?;
+
             let remote_get_proc_address = inject_data.get_proc_address_fn_ptr();
+            let get_last_error = inject_data.get_get_last_error();
 
-            let parameter = self
-                .remote_allocator
-                .alloc_uninit::<GetProcAddressParams>()?;
-            let result = self.remote_allocator.alloc_uninit::<RawFunctionPtr>()?;
+            let code = if self.remote_allocator.is_x86()? {
+                Syringe::build_get_procs_x86(remote_get_proc_address, get_last_error).unwrap()
+            } else {
+                Syringe::build_get_procs_x64(remote_get_proc_address, get_last_error).unwrap()
+            };
+            trace_event!(trace, bytes = %crate::utils::HexBytes(code.as_slice()), "assembled GetProcAddress batch stub");
+            let function_stub = self.remote_allocator.alloc_and_copy_code(code.as_slice())?;
+            trace_event!(debug, address = ?function_stub.as_ptr(), len = function_stub.len(), "wrote GetProcAddress batch stub");
+            Ok(function_stub)
+        })
+    }
+
+    #[cfg(feature = "assembler")]
+    #[allow(clippy::fn_to_numeric_cast, clippy::fn_to_numeric_cast_with_truncation)]
+    fn build_get_procs_x86(
+        get_proc_address: GetProcAddressFn,
+        get_last_error: GetLastErrorFn,
+    ) -> Result<Vec<u8>, IcedError> {
+        assert_eq!(get_proc_address as u32 as usize, get_proc_address as usize);
+        assert_eq!(get_last_error as u32 as usize, get_last_error as usize);
+
+        let mut asm = CodeAssembler::new(32)?;
+        let mut next_entry = asm.create_label();
+        let mut resolved = asm.create_label();
+        let mut done = asm.create_label();
+
+        asm.push(ebx)?;
+        asm.push(esi)?;
+        asm.push(edi)?;
+        asm.mov(ebx, dword_ptr(esp + 16))?; // CreateRemoteThread lpParameter
+        asm.mov(esi, dword_ptr(ebx + 8))?; // entry count
+        asm.lea(edi, dword_ptr(ebx + GetProcsHeader::SIZE as i32))?; // first entry
+        asm.set_label(&mut next_entry)?;
+        asm.test(esi, esi)?;
+        asm.jz(done)?;
+        asm.push(dword_ptr(edi))?; // lpProcName
+        asm.push(dword_ptr(ebx))?; // hModule
+        asm.mov(eax, get_proc_address.as_ptr() as u32)?;
+        asm.call(eax)?;
+        asm.mov(dword_ptr(edi + 8), eax)?; // procedure
+        asm.test(eax, eax)?;
+        asm.jnz(resolved)?;
+        asm.mov(eax, get_last_error as u32)?;
+        asm.call(eax)?;
+        asm.mov(dword_ptr(edi + 16), eax)?; // error
+        asm.set_label(&mut resolved)?;
+        asm.add(edi, GetProcsEntry::SIZE as i32)?;
+        asm.dec(esi)?;
+        asm.jmp(next_entry)?;
+        asm.set_label(&mut done)?;
+        asm.pop(edi)?;
+        asm.pop(esi)?;
+        asm.pop(ebx)?;
+        asm.mov(eax, 0)?; // return 0
+        asm.ret_1(4)?; // Restore stack ptr. (Callee cleanup)
+
+        let code = asm.assemble(0x1234_5678)?;
+        debug_assert_eq!(
+            code,
+            asm.assemble(0x1111_2222)?,
+            "GetProcAddress batch x86 stub is not location independent"
+        );
+
+        Ok(code)
+    }
+
+    #[cfg(feature = "assembler")]
+    #[allow(clippy::fn_to_numeric_cast, clippy::fn_to_numeric_cast_with_truncation)]
+    fn build_get_procs_x64(
+        get_proc_address: GetProcAddressFn,
+        get_last_error: GetLastErrorFn,
+    ) -> Result<Vec<u8>, IcedError> {
+        let mut asm = CodeAssembler::new(64)?;
+        let mut next_entry = asm.create_label();
+        let mut resolved = asm.create_label();
+        let mut done = asm.create_label();
+
+        // the pushes re-align the stack to a 16 byte boundary.
+        asm.push(rbx)?;
+        asm.push(rsi)?;
+        asm.push(rdi)?;
+        asm.sub(rsp, 32)?; // shadow space
+        asm.mov(rbx, rcx)?; // CreateRemoteThread lpParameter
+        asm.mov(rsi, qword_ptr(rbx + 8))?; // entry count
+        asm.lea(rdi, qword_ptr(rbx + GetProcsHeader::SIZE as i32))?; // first entry
+        asm.set_label(&mut next_entry)?;
+        asm.test(rsi, rsi)?;
+        asm.jz(done)?;
+        asm.mov(rdx, qword_ptr(rdi))?; // lpProcName
+        asm.mov(rcx, qword_ptr(rbx))?; // hModule
+        asm.mov(rax, get_proc_address.as_ptr() as u64)?;
+        asm.call(rax)?;
+        asm.mov(qword_ptr(rdi + 8), rax)?; // procedure
+        asm.test(rax, rax)?;
+        asm.jnz(resolved)?;
+        asm.mov(rax, get_last_error as u64)?;
+        asm.call(rax)?;
+        asm.mov(dword_ptr(rdi + 16), eax)?; // error
+        asm.set_label(&mut resolved)?;
+        asm.add(rdi, GetProcsEntry::SIZE as i32)?;
+        asm.dec(rsi)?;
+        asm.jmp(next_entry)?;
+        asm.set_label(&mut done)?;
+        asm.add(rsp, 32)?;
+        asm.pop(rdi)?;
+        asm.pop(rsi)?;
+        asm.pop(rbx)?;
+        asm.mov(rax, 0u64)?; // return 0
+        asm.ret()?;
+
+        let code = asm.assemble(0x1234_5678)?;
+        debug_assert_eq!(
+            code,
+            asm.assemble(0x1111_2222)?,
+            "GetProcAddress batch x64 stub is not location independent"
+        );
+
+        Ok(code)
+    }
+
+    fn build_get_proc_address_stub(&self) -> Result<&RemoteProcedureStub, LoadProcedureError> {
+        self.get_proc_address_stub.get_or_try_init(|| {
+            let inject_data = self.inject_help_data()?;
+
+            let remote_get_proc_address = inject_data.get_proc_address_fn_ptr() as usize;
+            let get_last_error = inject_data.get_get_last_error() as usize;
+
+            // large enough for the parameters of targets of either bitness.
+            let parameter = self.remote_allocator.alloc_raw_aligned(
+                mem::size_of::<GetProcAddressParams<Ptr64>>(),
+                mem::align_of::<GetProcAddressParams<Ptr64>>(),
+            )?;
+            let result = RemoteResultBuf::for_word(&self.remote_allocator)?;
 
             // Allocate memory in remote process and build a method stub.
-            let code = if self.remote_allocator.process().is_x86()? {
-                Syringe::build_get_proc_address_x86(
-                    remote_get_proc_address,
-                    result.as_ptr().as_ptr(),
-                )
+            let result_address = result.as_raw_ptr() as usize;
+            let code = if self.remote_allocator.is_x86()? {
+                Syringe::build_get_proc_address_x86(remote_get_proc_address, result_address, get_last_error)
                 .unwrap()
             } else {
-                Syringe::build_get_proc_address_x64(
-                    remote_get_proc_address,
-                    result.as_ptr().as_ptr(),
-                )
+                Syringe::build_get_proc_address_x64(remote_get_proc_address, result_address, get_last_error)
                 .unwrap()
             };
-            let function_stub = self.remote_allocator.alloc_and_copy_buf(code.as_slice())?;
-            function_stub.memory().flush_instruction_cache()?;
+            trace_event!(trace, bytes = %crate::utils::HexBytes(code.as_slice()), "assembled GetProcAddress stub");
+            let function_stub = self.remote_allocator.alloc_and_copy_code(code.as_slice())?;
+            trace_event!(debug, address = ?function_stub.as_ptr(), len = function_stub.len(), "wrote GetProcAddress stub");
 
             Ok(RemoteProcedureStub {
                 code: function_stub,
@@ -93,30 +588,43 @@ impl Syringe {
         })
     }
 
-    #[allow(clippy::fn_to_numeric_cast, clippy::fn_to_numeric_cast_with_truncation)]
-    fn build_get_proc_address_x86(
-        get_proc_address: GetProcAddressFn,
-        return_buffer: *mut RawFunctionPtr,
+    /// Builds the code of the `GetProcAddress` stub for an x86 target from the addresses of the functions it calls
+    /// and of its result buffer.
+    #[cfg(feature = "assembler")]
+    pub(crate) fn build_get_proc_address_x86(
+        get_proc_address: usize,
+        result: usize,
+        get_last_error: usize,
     ) -> Result<Vec<u8>, IcedError> {
-        assert!(!return_buffer.is_null());
-        assert_eq!(get_proc_address as u32 as usize, get_proc_address as usize);
-        assert_eq!(return_buffer as u32 as usize, return_buffer as usize);
+        assert_eq!(get_proc_address as u32 as usize, get_proc_address);
+        assert_eq!(get_last_error as u32 as usize, get_last_error);
 
         // assembly code from https://github.com/Reloaded-Project/Reloaded.Injector/blob/77a9a87392cc75fa087d7004e8cdef054e880428/Source/Reloaded.Injector/Shellcode.cs#L159
         // mov eax, dword [esp + 4]         // CreateRemoteThread lpParameter
-        // push dword [eax + 8]             // lpProcName
+        // push dword [eax + 4]             // lpProcName
         // push dword [eax + 0]             // hModule
         // call dword [dword GetProcAddress]
         // mov dword [dword ReturnAddress], eax
         // ret 4                           // Restore stack ptr. (Callee cleanup)
+        // extended to report the error of GetProcAddress through the result buffer.
         let mut asm = CodeAssembler::new(32)?;
+        let mut failed = asm.create_label();
+        let mut done = asm.create_label();
 
         asm.mov(eax, esp + 4)?; // CreateRemoteThread lpParameter
-        asm.push(dword_ptr(eax + 8))?; // lpProcName
+        asm.push(dword_ptr(eax + 4))?; // lpProcName
         asm.push(dword_ptr(eax + 0))?; // hModule
-        asm.mov(eax, get_proc_address.as_ptr() as u32)?;
+        asm.mov(eax, get_proc_address as u32)?;
+        asm.call(eax)?;
+        asm.test(eax, eax)?;
+        asm.jz(failed)?;
+        RemoteResultBuf::emit_write_x86_to(&mut asm, result, RemoteResultStatus::Ok, 4)?;
+        asm.jmp(done)?;
+        asm.set_label(&mut failed)?;
+        asm.mov(eax, get_last_error as u32)?;
         asm.call(eax)?;
-        asm.mov(dword_ptr(return_buffer as u32), eax)?;
+        RemoteResultBuf::emit_write_x86_to(&mut asm, result, RemoteResultStatus::Error, 4)?;
+        asm.set_label(&mut done)?;
         asm.mov(eax, 0)?; // return 0
         asm.ret_1(4)?; // Restore stack ptr. (Callee cleanup)
 
@@ -130,13 +638,14 @@ impl Syringe {
         Ok(code)
     }
 
-    #[allow(clippy::fn_to_numeric_cast, clippy::fn_to_numeric_cast_with_truncation)]
-    fn build_get_proc_address_x64(
-        get_proc_address: GetProcAddressFn,
-        return_buffer: *mut RawFunctionPtr,
+    /// Builds the code of the `GetProcAddress` stub for an x64 target from the addresses of the functions it calls
+    /// and of its result buffer.
+    #[cfg(feature = "assembler")]
+    pub(crate) fn build_get_proc_address_x64(
+        get_proc_address: usize,
+        result: usize,
+        get_last_error: usize,
     ) -> Result<Vec<u8>, IcedError> {
-        assert!(!return_buffer.is_null());
-
         // assembly code from https://github.com/Reloaded-Project/Reloaded.Injector/blob/77a9a87392cc75fa087d7004e8cdef054e880428/Source/Reloaded.Injector/Shellcode.cs#L188
         //                                      // CreateRemoteThread lpParameter @ ECX
         // sub rsp, 40                          // Re-align stack to 16 byte boundary +32 shadow space
@@ -146,14 +655,25 @@ impl Syringe {
         // mov qword [qword ReturnAddress], rax
         // add rsp, 40                          // Re-align stack to 16 byte boundary + shadow space.
         // ret
+        // extended to report the error of GetProcAddress through the result buffer.
         let mut asm = CodeAssembler::new(64).unwrap();
+        let mut failed = asm.create_label();
+        let mut done = asm.create_label();
 
         asm.sub(rsp, 40)?; // Re-align stack to 16 byte boundary +32 shadow space
         asm.mov(rdx, qword_ptr(rcx + 8))?; // lpProcName
         asm.mov(rcx, qword_ptr(rcx + 0))?; // hModule
-        asm.mov(rax, get_proc_address.as_ptr() as u64)?;
+        asm.mov(rax, get_proc_address as u64)?;
+        asm.call(rax)?;
+        asm.test(rax, rax)?;
+        asm.jz(failed)?;
+        RemoteResultBuf::emit_write_x64_to(&mut asm, result, RemoteResultStatus::Ok, 8)?;
+        asm.jmp(done)?;
+        asm.set_label(&mut failed)?;
+        asm.mov(rax, get_last_error as u64)?;
         asm.call(rax)?;
-        asm.mov(qword_ptr(return_buffer as u64), rax)?;
+        RemoteResultBuf::emit_write_x64_to(&mut asm, result, RemoteResultStatus::Error, 4)?;
+        asm.set_label(&mut done)?;
         asm.mov(rax, 0u64)?; // return 0
         asm.add(rsp, 40)?; // Re-align stack to 16 byte boundary + shadow space.
         asm.ret()?; // Restore stack ptr. (Callee cleanup)
@@ -167,36 +687,329 @@ impl Syringe {
 
         Ok(code)
     }
+
+    #[cfg(not(feature = "assembler"))]
+    pub(crate) fn build_get_proc_address_x86(
+        get_proc_address: usize,
+        result: usize,
+        get_last_error: usize,
+    ) -> Result<Vec<u8>, Infallible> {
+        assert_eq!(get_proc_address as u32 as usize, get_proc_address);
+        assert_eq!(get_last_error as u32 as usize, get_last_error);
+        assert_eq!(result as u32 as usize, result);
+
+        Ok(stub_templates::get_proc_address_x86(
+            get_proc_address as u32,
+            result as u32,
+            get_last_error as u32,
+        ))
+    }
+
+    #[cfg(not(feature = "assembler"))]
+    pub(crate) fn build_get_proc_address_x64(
+        get_proc_address: usize,
+        result: usize,
+        get_last_error: usize,
+    ) -> Result<Vec<u8>, Infallible> {
+        Ok(stub_templates::get_proc_address_x64(
+            get_proc_address as u64,
+            result as u64,
+            get_last_error as u64,
+        ))
+    }
+}
+
+/// The method used by [`Syringe::get_procedure_address`] to look up procedures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-core")))]
+pub enum ProcedureLookupMethod {
+    /// Calls `GetProcAddress` in a remote thread, which resolves procedures exactly like the target process would.
+    #[default]
+    RemoteThread,
+    /// Reads the export directory of the module from the memory of the target process, without executing any code in it.
+    /// This is faster and works for targets that block the creation of remote threads,
+    /// see [`ProcessModule::get_procedure_address_from_exports`](crate::process::ProcessModule::get_procedure_address_from_exports).
+    ExportTable,
+}
+
+/// The results of previous procedure lookups by module and name.
+#[derive(Debug, Default)]
+pub(crate) struct ProcedureCache(RefCell<HashMap<(usize, String), Rc<ProcedureSlot>>>);
+
+impl ProcedureCache {
+    pub fn get(&self, module: ModuleHandle, name: &str) -> Option<Rc<ProcedureSlot>> {
+        self.0
+            .borrow()
+            .get(&(module as usize, name.to_string()))
+            .cloned()
+    }
+
+    pub fn insert(
+        &self,
+        module: BorrowedProcessModule<'_>,
+        name: String,
+        procedure: Option<RawFunctionPtr>,
+    ) -> Rc<ProcedureSlot> {
+        let slot = Rc::new(ProcedureSlot::new(module, name.clone(), procedure));
+        self.0
+            .borrow_mut()
+            .insert((module.handle() as usize, name), slot.clone());
+        slot
+    }
+
+    /// Discards the cached result for the given procedure, which the procedures created from it look up again before their next call.
+    pub fn remove(&self, module: ModuleHandle, name: &str) -> bool {
+        let slot = self
+            .0
+            .borrow_mut()
+            .remove(&(module as usize, name.to_string()));
+        if let Some(slot) = &slot {
+            slot.stale.set(true);
+        }
+        slot.is_some()
+    }
+
+    pub fn remove_module(&self, module: ModuleHandle) {
+        self.0
+            .borrow_mut()
+            .retain(|(cached_module, _), _| *cached_module != module as usize);
+    }
+
+    pub fn clear(&self) {
+        self.0.borrow_mut().clear();
+    }
 }
 
+/// The result of a procedure lookup, which is shared by the [`ProcedureCache`] and the procedures created from it.
+#[derive(Debug)]
+pub(crate) struct ProcedureSlot {
+    // the key of the procedure in the cache.
+    name: String,
+    address: Cell<Option<usize>>,
+    // the address of the entry of the export address table the procedure was resolved from and the RVA it held then.
+    export_entry: Cell<Option<(usize, u32)>>,
+    stale: Cell<bool>,
+}
+
+impl ProcedureSlot {
+    fn new(
+        module: BorrowedProcessModule<'_>,
+        name: String,
+        procedure: Option<RawFunctionPtr>,
+    ) -> Self {
+        let slot = Self {
+            name,
+            address: Cell::new(procedure.map(|procedure| procedure as usize)),
+            export_entry: Cell::new(None),
+            stale: Cell::new(false),
+        };
+        // without the entry, redirected exports are only noticed through `Syringe::invalidate_stale_procedures`.
+        slot.export_entry
+            .set(slot.read_export_entry(module).ok().flatten());
+        slot
+    }
+
+    /// Returns the address of the procedure in the given module, which is looked up again in the export directory if the
+    /// entry of the export address table it was resolved from changed or the slot was discarded from the cache since.
+    pub fn address(
+        &self,
+        module: BorrowedProcessModule<'_>,
+    ) -> Result<Option<RawFunctionPtr>, io::Error> {
+        let redirected = match self.export_entry.get() {
+            Some((entry, rva)) => {
+                let mut current = [0u8; 4];
+                unsafe {
+                    ProcessMemorySlice::from_raw_parts(entry as *mut u8, 4, *module.process())
+                }
+                .read(0, &mut current)?;
+                u32::from_le_bytes(current) != rva
+            }
+            None => false,
+        };
+        if redirected || self.stale.get() {
+            let address = match self.ordinal() {
+                Some(ordinal) => module.get_procedure_address_from_exports_by_ordinal(ordinal)?,
+                None => module.get_procedure_address_from_exports(&self.name)?,
+            };
+            trace_event!(debug, module = ?module.handle(), name = %self.name, previous = ?self.address.get(), ?address, "looked up stale procedure again");
+            self.address.set(address.map(|address| address as usize));
+            self.export_entry.set(self.read_export_entry(module)?);
+            self.stale.set(false);
+        }
+        Ok(self.address.get().map(|address| address as RawFunctionPtr))
+    }
+
+    fn ordinal(&self) -> Option<u16> {
+        self.name.strip_prefix('#')?.parse().ok()
+    }
+
+    /// Makes this slot treat the `u32` at the given address as the entry of the export address table it was resolved from.
+    #[cfg(test)]
+    pub(crate) fn watch_export_entry(&self, entry: usize, rva: u32) {
+        self.export_entry.set(Some((entry, rva)));
+    }
+
+    fn read_export_entry(
+        &self,
+        module: BorrowedProcessModule<'_>,
+    ) -> Result<Option<(usize, u32)>, io::Error> {
+        let image = RemoteImage::new(module)?;
+        let entry = match self.ordinal() {
+            Some(ordinal) => image.export_entry_rva_by_ordinal(ordinal)?,
+            None => image.export_entry_rva(&self.name)?,
+        };
+        entry
+            .map(|entry| Ok((image.base() + entry, image.read_u32(entry)?)))
+            .transpose()
+    }
+}
+
+/// The procedure looked up by [`Syringe::get_procedure_address_cached`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ProcedureName<'a> {
+    Name(&'a str),
+    Ordinal(u16),
+}
+
+impl ProcedureName<'_> {
+    /// Returns the key of the procedure in the [`ProcedureCache`].
+    /// Ordinals are prefixed with `#` like in export forwarders.
+    fn cache_key(&self) -> Cow<'_, str> {
+        match self {
+            ProcedureName::Name(name) => Cow::Borrowed(name),
+            ProcedureName::Ordinal(ordinal) => Cow::Owned(format!("#{ordinal}")),
+        }
+    }
+}
+
+/// The parameter of the `GetProcAddress` stub, laid out with the pointer width of the target.
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
-pub(crate) struct GetProcAddressParams {
-    module_handle: u64,
-    name: u64,
+pub(crate) struct GetProcAddressParams<W: RemotePtrWidth> {
+    module_handle: RemotePtr<c_void, W>,
+    /// The address of the nul terminated ANSI name or an ordinal below `0x10000`.
+    name: RemotePtr<c_char, W>,
+}
+
+impl<W: RemotePtrWidth> GetProcAddressParams<W> {
+    fn new(module_handle: u64, name: u64) -> Result<Self, io::Error> {
+        Ok(Self {
+            module_handle: RemotePtr::try_new(module_handle)?,
+            name: RemotePtr::try_new(name)?,
+        })
+    }
+}
+
+/// The header of the name table passed to the `GetProcAddress` batch stub.
+#[cfg(feature = "assembler")]
+struct GetProcsHeader;
+
+#[cfg(feature = "assembler")]
+impl GetProcsHeader {
+    /// The module handle and the number of entries.
+    const SIZE: usize = 16;
+}
+
+/// An entry of the name table passed to the `GetProcAddress` batch stub, which stores its result in place.
+#[cfg(feature = "assembler")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct GetProcsEntry {
+    procedure: u64,
+    /// The result of `GetLastError` if the procedure could not be resolved.
+    error: u32,
+}
+
+#[cfg(feature = "assembler")]
+impl GetProcsEntry {
+    /// The name, the procedure and the error.
+    const SIZE: usize = 24;
+
+    fn decode(bytes: &[u8]) -> Self {
+        Self {
+            procedure: u64::from_le_bytes(bytes[8..16].try_into().unwrap()),
+            error: u32::from_le_bytes(bytes[16..20].try_into().unwrap()),
+        }
+    }
 }
 
 #[derive(Debug)]
-pub(crate) struct RemoteProcedureStub<A: ?Sized + Copy, R: Copy> {
+pub(crate) struct RemoteProcedureStub {
     pub code: RemoteAllocation,
-    pub parameter: RemoteBox<A>,
-    pub result: RemoteBox<R>,
+    // the layout of the parameters depends on the pointer width of the target.
+    pub parameter: RemoteAllocation,
+    pub result: RemoteResultBuf,
 }
 
-impl<A: ?Sized + Copy, R: Copy> RemoteProcedureStub<A, R> {
+impl RemoteProcedureStub {
     #[allow(dead_code)]
-    pub(crate) fn call(&self, args: &A) -> Result<R, RawRpcError> {
-        self.parameter.write(args)?;
-        let exit_code = self.code.process().run_remote_thread(
-            unsafe { mem::transmute(self.code.as_raw_ptr()) },
-            self.parameter.as_raw_ptr(),
-        )?;
-        Syringe::remote_exit_code_to_exception(exit_code)?;
-
-        if mem::size_of::<R>() == 0 {
-            Ok(unsafe { mem::zeroed() })
+    pub(crate) fn call<A: Copy>(&self, args: &A) -> Result<u64, RawRpcError> {
+        self.parameter.memory().write_struct(0, args)?;
+        self.result.reset()?;
+        self.code
+            .allocator()
+            .run_remote_thread(
+                unsafe {
+                    mem::transmute::<*mut u8, extern "system" fn(*mut u8) -> u32>(
+                        self.code.as_raw_ptr(),
+                    )
+                },
+                self.parameter.as_raw_ptr(),
+                &RemoteThreadOptions::new(),
+            )?
+            .into_result()?;
+
+        Ok(self
+            .result
+            .read_word()?
+            .into_io_result("remote procedure")?)
+    }
+}
+
+#[cfg(all(test, feature = "assembler"))]
+mod tests {
+    use super::*;
+    use crate::{
+        process::{memory::RemoteBoxAllocator, OwnedProcess},
+        stub_templates,
+    };
+    use winapi::um::{errhandlingapi::GetLastError, libloaderapi::GetProcAddress};
+
+    #[test]
+    #[allow(clippy::fn_to_numeric_cast, clippy::fn_to_numeric_cast_with_truncation)]
+    fn get_proc_address_stub_template_matches_the_assembler() {
+        let allocator = RemoteBoxAllocator::new(OwnedProcess::current());
+        let result = RemoteResultBuf::for_word(&allocator).unwrap();
+        let get_proc_address: GetProcAddressFn = GetProcAddress;
+        let get_last_error: GetLastErrorFn = GetLastError;
+
+        if cfg!(target_pointer_width = "32") {
+            assert_eq!(
+                Syringe::build_get_proc_address_x86(
+                    get_proc_address as usize,
+                    result.as_raw_ptr() as usize,
+                    get_last_error as usize,
+                )
+                .unwrap(),
+                stub_templates::get_proc_address_x86(
+                    get_proc_address as u32,
+                    result.as_raw_ptr() as u32,
+                    get_last_error as u32
+                )
+            );
         } else {
-            Ok(self.result.read()?)
+            assert_eq!(
+                Syringe::build_get_proc_address_x64(
+                    get_proc_address as usize,
+                    result.as_raw_ptr() as usize,
+                    get_last_error as usize,
+                )
+                .unwrap(),
+                stub_templates::get_proc_address_x64(
+                    get_proc_address as u64,
+                    result.as_raw_ptr() as u64,
+                    get_last_error as u64
+                )
+            );
         }
     }
 }
@@ -0,0 +1,243 @@
+use std::{io, mem, ptr::NonNull};
+
+#[cfg(feature = "rpc-raw")]
+use crate::rpc::{RawRpcFunctionPtr, RemoteRawProcedure, SignatureError};
+#[cfg(feature = "rpc-core")]
+use crate::Syringe;
+use crate::{
+    process::{
+        memory::{RemoteAllocation, RemoteResultBuf, RemoteResultStatus},
+        RemoteThreadOptions, RemoteThreadResult,
+    },
+    syringe::LoadLibraryWStub,
+};
+
+/// The size of the buffer the stubs of this module write their result into.
+///
+/// The buffer starts with a 32-bit status (`0` if no result was written, `1` for a value and `2` for an error code)
+/// and the 32-bit length of the payload, followed by an 8-byte payload. The payload and its length are written
+/// before the status, so a stub that is interrupted leaves the status at `0`.
+/// The buffer has to be zeroed before a stub runs, see [`StubResult::from_bytes`] for reading it.
+pub const RESULT_LEN: usize = RemoteResultBuf::PAYLOAD_OFFSET + RemoteResultBuf::WORD_LEN;
+
+/// A result written by a stub of this module into a buffer of [`RESULT_LEN`] bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StubResult {
+    /// The stub produced a value, zero-extended to 64 bits.
+    Ok(u64),
+    /// The stub reported an error code, usually the result of `GetLastError`.
+    Err(u32),
+    /// The stub did not write a result, e.g. because the thread was terminated.
+    Missing,
+}
+
+impl StubResult {
+    /// Parses the contents of a result buffer that was read from the target process.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8; RESULT_LEN]) -> Self {
+        let status = u32::from_ne_bytes(bytes[0..4].try_into().unwrap());
+        let len = u32::from_ne_bytes(bytes[4..8].try_into().unwrap()) as usize;
+        let mut payload = [0u8; RemoteResultBuf::WORD_LEN];
+        let len = len.min(payload.len());
+        payload[..len].copy_from_slice(&bytes[RemoteResultBuf::PAYLOAD_OFFSET..][..len]);
+        let payload = u64::from_ne_bytes(payload);
+
+        if status == RemoteResultStatus::Ok as u32 {
+            Self::Ok(payload)
+        } else if status == RemoteResultStatus::Error as u32 {
+            Self::Err(payload as u32)
+        } else {
+            Self::Missing
+        }
+    }
+}
+
+/// Builds the stub the syringe uses to load a module, which calls `LoadLibraryW` with its parameter as the path.
+///
+/// The stub is a thread procedure returning `0` that writes the module handle or the error code from `GetLastError`
+/// into the result buffer of [`RESULT_LEN`] bytes at the given address.
+/// All addresses are addresses in the target process.
+///
+/// # Panics
+/// Panics if an address does not fit into a pointer of an x86 target.
+#[must_use]
+pub fn load_library_w_stub(
+    is_x86: bool,
+    load_library_w: usize,
+    get_last_error: usize,
+    result: usize,
+) -> Vec<u8> {
+    if is_x86 {
+        LoadLibraryWStub::build_code_x86(load_library_w, result, get_last_error).unwrap()
+    } else {
+        LoadLibraryWStub::build_code_x64(load_library_w, result, get_last_error).unwrap()
+    }
+}
+
+/// Builds the stub the syringe uses to look up procedures, which calls `GetProcAddress`.
+///
+/// The parameter of the stub points to two pointers of the width of the target, the module handle followed by the pointer
+/// to the name or ordinal of the procedure. The stub is a thread procedure returning `0` that writes the address of the procedure
+/// or the error code from `GetLastError` into the result buffer of [`RESULT_LEN`] bytes at the given address.
+/// All addresses are addresses in the target process.
+///
+/// # Panics
+/// Panics if an address does not fit into a pointer of an x86 target.
+#[cfg(feature = "rpc-core")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-core")))]
+#[must_use]
+pub fn get_proc_address_stub(
+    is_x86: bool,
+    get_proc_address: usize,
+    get_last_error: usize,
+    result: usize,
+) -> Vec<u8> {
+    if is_x86 {
+        Syringe::build_get_proc_address_x86(get_proc_address, result, get_last_error).unwrap()
+    } else {
+        Syringe::build_get_proc_address_x64(get_proc_address, result, get_last_error).unwrap()
+    }
+}
+
+/// Builds the stub the syringe uses to call a procedure with the signature `F`, see [`RemoteRawProcedure`].
+///
+/// The parameter of the stub points to a block of `usize`s holding the address of the procedure, the address of a
+/// result buffer of [`RESULT_LEN`] bytes and the arguments in order. The stub is a thread procedure returning `0` that
+/// writes the return value of the procedure into the result buffer, unless `F` returns `()`.
+///
+/// # Errors
+/// Fails if the return type of `F` is not returned in registers.
+#[cfg(feature = "rpc-raw")]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-raw")))]
+pub fn call_stub<F: RawRpcFunctionPtr>(is_x86: bool) -> Result<Vec<u8>, SignatureError> {
+    RemoteRawProcedure::<F>::check_return_type()?;
+    let has_result = mem::size_of::<F::Output>() != 0;
+    Ok(RemoteRawProcedure::<F>::build_call_stub_code(
+        is_x86, true, has_result,
+    ))
+}
+
+/// Code placed in executable memory of a target process by [`Syringe::alloc_shellcode`], which is freed once this is dropped.
+#[derive(Debug)]
+pub struct RemoteShellcode {
+    allocation: RemoteAllocation,
+}
+
+impl RemoteShellcode {
+    pub(crate) fn new(allocation: RemoteAllocation) -> Self {
+        Self { allocation }
+    }
+
+    /// Returns the address of the code in the target process.
+    #[must_use]
+    pub const fn as_ptr(&self) -> NonNull<u8> {
+        self.allocation.as_ptr()
+    }
+
+    /// Returns the length of the code in bytes.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.allocation.len()
+    }
+
+    /// Returns whether the code is empty, which is never the case.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Runs the code as a thread procedure with the given parameter and returns how it ended, see
+    /// [`Process::run_remote_thread_with_options`](crate::process::Process::run_remote_thread_with_options).
+    ///
+    /// The code runs on the worker thread of the syringe that placed it if one is running and idle,
+    /// in which case the options are ignored, and on a new thread otherwise.
+    ///
+    /// # Safety
+    /// The code must be a valid thread procedure for the target process and the parameter has to be what it expects.
+    pub unsafe fn run(
+        &self,
+        parameter: *mut u8,
+        options: &RemoteThreadOptions,
+    ) -> Result<RemoteThreadResult, io::Error> {
+        self.allocation.allocator().run_remote_thread(
+            unsafe {
+                mem::transmute::<*mut u8, extern "system" fn(*mut u8) -> u32>(
+                    self.allocation.as_raw_ptr(),
+                )
+            },
+            parameter,
+            options,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        process::{BorrowedProcess, Process},
+        Syringe,
+    };
+    use widestring::U16CString;
+    use winapi::um::{
+        errhandlingapi::GetLastError,
+        libloaderapi::{GetModuleHandleW, LoadLibraryW},
+    };
+
+    #[test]
+    fn load_library_w_stub_loads_module() {
+        let syringe = Syringe::for_current_process();
+        let result = syringe.alloc_box(&[0u8; RESULT_LEN]).unwrap();
+        let code = load_library_w_stub(
+            BorrowedProcess::current().is_x86().unwrap(),
+            LoadLibraryW as *const () as usize,
+            GetLastError as *const () as usize,
+            result.as_raw_ptr() as usize,
+        );
+        let code = syringe.alloc_shellcode(&code).unwrap();
+
+        let mut path = U16CString::from_str("kernel32.dll")
+            .unwrap()
+            .into_vec_with_nul();
+        let exit_code =
+            unsafe { code.run(path.as_mut_ptr().cast(), syringe.remote_thread_options()) }.unwrap();
+
+        assert_eq!(exit_code, RemoteThreadResult::Returned(0));
+        let kernel32 = unsafe { GetModuleHandleW(path.as_ptr()) };
+        assert_eq!(
+            StubResult::from_bytes(&result.read().unwrap()),
+            StubResult::Ok(kernel32 as u64)
+        );
+    }
+
+    #[test]
+    fn load_library_w_stub_reports_error() {
+        let syringe = Syringe::for_current_process();
+        let result = syringe.alloc_box(&[0u8; RESULT_LEN]).unwrap();
+        let code = load_library_w_stub(
+            BorrowedProcess::current().is_x86().unwrap(),
+            LoadLibraryW as *const () as usize,
+            GetLastError as *const () as usize,
+            result.as_raw_ptr() as usize,
+        );
+        let code = syringe.alloc_shellcode(&code).unwrap();
+
+        let mut path = U16CString::from_str("does_not_exist_7f3c.dll")
+            .unwrap()
+            .into_vec_with_nul();
+        unsafe { code.run(path.as_mut_ptr().cast(), syringe.remote_thread_options()) }.unwrap();
+
+        assert!(matches!(
+            StubResult::from_bytes(&result.read().unwrap()),
+            StubResult::Err(code) if code != 0
+        ));
+    }
+
+    #[test]
+    fn result_without_status_is_missing() {
+        assert_eq!(
+            StubResult::from_bytes(&[0u8; RESULT_LEN]),
+            StubResult::Missing
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::{io, ops::Range};
+
+#[cfg(feature = "assembler")]
+use {
+    iced_x86::{Decoder, DecoderOptions, Formatter, Instruction, IntelFormatter, Mnemonic},
+    std::fmt::Write,
+};
+
+use crate::process::{memory::RemoteAllocation, Process};
+
+/// The kind of a stub a [`Syringe`](crate::Syringe) placed in the target process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub enum StubKind {
+    /// The stub calling `LoadLibraryW` to inject a module.
+    LoadLibraryW,
+    /// The stub calling `GetProcAddress` to look up remote procedures.
+    GetProcAddress,
+    /// The stub calling `GetProcAddress` for each name of a table, see [`Syringe::get_procedures`](crate::Syringe::get_procedures).
+    GetProcAddressBatch,
+    /// The stub calling a remote procedure with the arguments from its parameter block.
+    CallProcedure,
+    /// The stub calling a function with a context and a data pointer, see [`Syringe::run_remote_thread_with_context`](crate::Syringe::run_remote_thread_with_context).
+    ContextThread,
+}
+
+/// The code of a stub in the target process, which can be used to correlate the address of a crash in the target with the stub.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::{process::OwnedProcess, Syringe};
+///
+/// let syringe = Syringe::for_process(OwnedProcess::find_first_by_name("target_process").unwrap());
+/// syringe.inject("injection_payload.dll").unwrap();
+/// for stub in syringe.stubs().unwrap() {
+///     println!("{:?} stub:\n{}", stub.kind(), stub.disassembly());
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub struct StubInfo {
+    kind: StubKind,
+    address: usize,
+    bytes: Vec<u8>,
+    #[cfg_attr(not(feature = "assembler"), allow(dead_code))]
+    bitness: u32,
+}
+
+impl StubInfo {
+    /// Reads the code of the stub in the given allocation from the target process.
+    /// The allocation may be padded, so the code is cut off after the `ret` that ends every stub if it can be decoded.
+    pub(crate) fn read(kind: StubKind, code: &RemoteAllocation) -> Result<Self, io::Error> {
+        let bitness = if code.process().is_x86()? { 32 } else { 64 };
+        #[allow(unused_mut)]
+        let mut bytes = code.memory().read_vec(0, code.len())?;
+
+        #[cfg(feature = "assembler")]
+        Self::truncate_after_ret(&mut bytes, bitness, code.as_raw_ptr() as u64);
+
+        Ok(Self {
+            kind,
+            address: code.as_raw_ptr() as usize,
+            bytes,
+            bitness,
+        })
+    }
+
+    #[cfg(feature = "assembler")]
+    fn truncate_after_ret(bytes: &mut Vec<u8>, bitness: u32, address: u64) {
+        let mut decoder = Decoder::with_ip(bitness, bytes, address, DecoderOptions::NONE);
+        let mut instruction = Instruction::default();
+        let mut len = bytes.len();
+        while decoder.can_decode() {
+            decoder.decode_out(&mut instruction);
+            if instruction.mnemonic() == Mnemonic::Ret {
+                len = decoder.position();
+                break;
+            }
+        }
+        bytes.truncate(len);
+    }
+
+    /// Returns the kind of the stub.
+    #[must_use]
+    pub const fn kind(&self) -> StubKind {
+        self.kind
+    }
+
+    /// Returns the address of the stub in the target process.
+    #[must_use]
+    pub const fn address(&self) -> usize {
+        self.address
+    }
+
+    /// Returns the machine code of the stub.
+    ///
+    /// Without the `assembler` feature the code cannot be decoded, so it includes the padding of its allocation.
+    #[must_use]
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the range of addresses occupied by the stub in the target process.
+    #[must_use]
+    pub fn address_range(&self) -> Range<usize> {
+        self.address..self.address + self.bytes.len()
+    }
+
+    /// Returns whether the given address in the target process lies inside the stub, e.g. the address of a crash.
+    #[must_use]
+    pub fn contains(&self, address: usize) -> bool {
+        self.address_range().contains(&address)
+    }
+
+    /// Returns a listing of the instructions of the stub with their addresses and bytes, one instruction per line.
+    #[must_use]
+    #[cfg(feature = "assembler")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "assembler")))]
+    pub fn disassembly(&self) -> String {
+        let mut decoder = Decoder::with_ip(
+            self.bitness,
+            &self.bytes,
+            self.address as u64,
+            DecoderOptions::NONE,
+        );
+        let mut formatter = IntelFormatter::new();
+        let mut formatted = String::new();
+        let mut listing = String::new();
+        let mut instruction = Instruction::default();
+        while decoder.can_decode() {
+            let start = decoder.position();
+            decoder.decode_out(&mut instruction);
+            formatted.clear();
+            formatter.format(&instruction, &mut formatted);
+
+            let bytes = self.bytes[start..decoder.position()].iter().fold(
+                String::new(),
+                |mut hex, byte| {
+                    let _ = write!(hex, "{byte:02X}");
+                    hex
+                },
+            );
+            let _ = writeln!(
+                listing,
+                "{:#010x}  {bytes:<24}{formatted}",
+                instruction.ip()
+            );
+        }
+        listing
+    }
+}
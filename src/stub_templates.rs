@@ -0,0 +1,345 @@
+//! Precompiled stubs used in place of the assembler if the `assembler` feature is disabled.
+//!
+//! The fixed stubs are the output of the assembler based builders with placeholders for the absolute
+//! addresses, which are patched in when a stub is built. The call stubs of raw procedures depend on the
+//! signature of the procedure and are instead put together from the encodings of their few instructions.
+
+use std::mem;
+
+#[cfg(feature = "rpc-raw")]
+use crate::process::memory::{RemoteResultBuf, RemoteResultStatus};
+
+const PROCEDURE_X86: u32 = 0x5EED_0001;
+const RESULT_X86: u32 = 0x5EED_0002;
+const GET_LAST_ERROR_X86: u32 = 0x5EED_0003;
+const PROCEDURE_X64: u64 = 0x5EED_5EED_0000_0001;
+const RESULT_X64: u64 = 0x5EED_5EED_0000_0002;
+const GET_LAST_ERROR_X64: u64 = 0x5EED_5EED_0000_0003;
+
+#[rustfmt::skip]
+const LOAD_LIBRARY_W_X86: [u8; 75] = [
+    0x8B, 0x44, 0x24, 0x04, 0x50, 0xB8, 0x01, 0x00, 0xED, 0x5E, 0xFF, 0xD0,
+    0x85, 0xC0, 0x74, 0x17, 0xB9, 0x02, 0x00, 0xED, 0x5E, 0x89, 0x41, 0x08,
+    0xC7, 0x41, 0x04, 0x04, 0x00, 0x00, 0x00, 0xC7, 0x01, 0x01, 0x00, 0x00,
+    0x00, 0xEB, 0x1C, 0xB8, 0x03, 0x00, 0xED, 0x5E, 0xFF, 0xD0, 0xB9, 0x02,
+    0x00, 0xED, 0x5E, 0x89, 0x41, 0x08, 0xC7, 0x41, 0x04, 0x04, 0x00, 0x00,
+    0x00, 0xC7, 0x01, 0x02, 0x00, 0x00, 0x00, 0xB8, 0x00, 0x00, 0x00, 0x00,
+    0xC2, 0x04, 0x00,
+];
+
+#[rustfmt::skip]
+const LOAD_LIBRARY_W_X64: [u8; 108] = [
+    0x48, 0x83, 0xEC, 0x28, 0x48, 0xB8, 0x01, 0x00, 0x00, 0x00, 0xED, 0x5E,
+    0xED, 0x5E, 0xFF, 0xD0, 0x48, 0x85, 0xC0, 0x74, 0x1F, 0x49, 0xBB, 0x02,
+    0x00, 0x00, 0x00, 0xED, 0x5E, 0xED, 0x5E, 0x49, 0x89, 0x43, 0x08, 0x41,
+    0xC7, 0x43, 0x04, 0x08, 0x00, 0x00, 0x00, 0x41, 0xC7, 0x03, 0x01, 0x00,
+    0x00, 0x00, 0xEB, 0x29, 0x48, 0xB8, 0x03, 0x00, 0x00, 0x00, 0xED, 0x5E,
+    0xED, 0x5E, 0xFF, 0xD0, 0x49, 0xBB, 0x02, 0x00, 0x00, 0x00, 0xED, 0x5E,
+    0xED, 0x5E, 0x49, 0x89, 0x43, 0x08, 0x41, 0xC7, 0x43, 0x04, 0x04, 0x00,
+    0x00, 0x00, 0x41, 0xC7, 0x03, 0x02, 0x00, 0x00, 0x00, 0x48, 0xB8, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x48, 0x83, 0xC4, 0x28, 0xC3,
+];
+
+#[cfg(feature = "rpc-core")]
+#[rustfmt::skip]
+const GET_PROC_ADDRESS_X86: [u8; 79] = [
+    0x8B, 0x44, 0x24, 0x04, 0xFF, 0x70, 0x04, 0xFF, 0x30, 0xB8, 0x01, 0x00,
+    0xED, 0x5E, 0xFF, 0xD0, 0x85, 0xC0, 0x74, 0x17, 0xB9, 0x02, 0x00, 0xED,
+    0x5E, 0x89, 0x41, 0x08, 0xC7, 0x41, 0x04, 0x04, 0x00, 0x00, 0x00, 0xC7,
+    0x01, 0x01, 0x00, 0x00, 0x00, 0xEB, 0x1C, 0xB8, 0x03, 0x00, 0xED, 0x5E,
+    0xFF, 0xD0, 0xB9, 0x02, 0x00, 0xED, 0x5E, 0x89, 0x41, 0x08, 0xC7, 0x41,
+    0x04, 0x04, 0x00, 0x00, 0x00, 0xC7, 0x01, 0x02, 0x00, 0x00, 0x00, 0xB8,
+    0x00, 0x00, 0x00, 0x00, 0xC2, 0x04, 0x00,
+];
+
+#[cfg(feature = "rpc-core")]
+#[rustfmt::skip]
+const GET_PROC_ADDRESS_X64: [u8; 115] = [
+    0x48, 0x83, 0xEC, 0x28, 0x48, 0x8B, 0x51, 0x08, 0x48, 0x8B, 0x09, 0x48,
+    0xB8, 0x01, 0x00, 0x00, 0x00, 0xED, 0x5E, 0xED, 0x5E, 0xFF, 0xD0, 0x48,
+    0x85, 0xC0, 0x74, 0x1F, 0x49, 0xBB, 0x02, 0x00, 0x00, 0x00, 0xED, 0x5E,
+    0xED, 0x5E, 0x49, 0x89, 0x43, 0x08, 0x41, 0xC7, 0x43, 0x04, 0x08, 0x00,
+    0x00, 0x00, 0x41, 0xC7, 0x03, 0x01, 0x00, 0x00, 0x00, 0xEB, 0x29, 0x48,
+    0xB8, 0x03, 0x00, 0x00, 0x00, 0xED, 0x5E, 0xED, 0x5E, 0xFF, 0xD0, 0x49,
+    0xBB, 0x02, 0x00, 0x00, 0x00, 0xED, 0x5E, 0xED, 0x5E, 0x49, 0x89, 0x43,
+    0x08, 0x41, 0xC7, 0x43, 0x04, 0x04, 0x00, 0x00, 0x00, 0x41, 0xC7, 0x03,
+    0x02, 0x00, 0x00, 0x00, 0x48, 0xB8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x48, 0x83, 0xC4, 0x28, 0xC3,
+];
+
+#[rustfmt::skip]
+pub(crate) const HEAP_X86: [u8; 25] = [
+    0x53, 0x8B, 0x5C, 0x24, 0x08, 0xFF, 0x73, 0x0C, 0xFF, 0x73, 0x08, 0xFF,
+    0x73, 0x04, 0xFF, 0x13, 0x89, 0x43, 0x10, 0x5B, 0x31, 0xC0, 0xC2, 0x04,
+    0x00,
+];
+
+#[rustfmt::skip]
+pub(crate) const HEAP_X64: [u8; 34] = [
+    0x53, 0x48, 0x83, 0xEC, 0x20, 0x48, 0x89, 0xCB, 0x48, 0x8B, 0x4B, 0x08,
+    0x48, 0x8B, 0x53, 0x10, 0x4C, 0x8B, 0x43, 0x18, 0xFF, 0x13, 0x48, 0x89,
+    0x43, 0x20, 0x48, 0x83, 0xC4, 0x20, 0x5B, 0x31, 0xC0, 0xC3,
+];
+
+#[rustfmt::skip]
+pub(crate) const CONTEXT_THREAD_X64: [u8; 22] = [
+    0x48, 0x83, 0xEC, 0x28, 0x48, 0x89, 0xC8, 0x48, 0x8B, 0x48, 0x08, 0x48,
+    0x8B, 0x50, 0x10, 0xFF, 0x10, 0x48, 0x83, 0xC4, 0x28, 0xC3,
+];
+
+#[rustfmt::skip]
+pub(crate) const WORKER_X86: [u8; 57] = [
+    0x53, 0x8B, 0x5C, 0x24, 0x08, 0x6A, 0xFF, 0xFF, 0x73, 0x08, 0xFF, 0x13,
+    0x85, 0xC0, 0x75, 0x25, 0x8B, 0x43, 0x10, 0x85, 0xC0, 0x74, 0x1E, 0xC7,
+    0x43, 0x1C, 0x01, 0x00, 0x00, 0x00, 0xFF, 0x73, 0x14, 0xFF, 0xD0, 0x89,
+    0x43, 0x18, 0xC7, 0x43, 0x1C, 0x02, 0x00, 0x00, 0x00, 0xFF, 0x73, 0x0C,
+    0xFF, 0x53, 0x04, 0xEB, 0xD0, 0x5B, 0xC2, 0x04, 0x00,
+];
+
+#[rustfmt::skip]
+pub(crate) const WORKER_X64: [u8; 71] = [
+    0x53, 0x48, 0x83, 0xEC, 0x20, 0x48, 0x89, 0xCB, 0x48, 0x8B, 0x4B, 0x10,
+    0xBA, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x13, 0x85, 0xC0, 0x75, 0x2A, 0x48,
+    0x8B, 0x43, 0x20, 0x48, 0x85, 0xC0, 0x74, 0x21, 0xC7, 0x43, 0x38, 0x01,
+    0x00, 0x00, 0x00, 0x48, 0x8B, 0x4B, 0x28, 0xFF, 0xD0, 0x48, 0x89, 0x43,
+    0x30, 0xC7, 0x43, 0x38, 0x02, 0x00, 0x00, 0x00, 0x48, 0x8B, 0x4B, 0x18,
+    0xFF, 0x53, 0x08, 0xEB, 0xC7, 0x48, 0x83, 0xC4, 0x20, 0x5B, 0xC3,
+];
+
+/// Copies the template and replaces every occurrence of each placeholder with its value.
+/// The placeholders are looked up in the unpatched template, so a patched address can never be mistaken for one.
+fn patch<const N: usize>(template: &[u8], replacements: [([u8; N], [u8; N]); 3]) -> Vec<u8> {
+    let mut code = template.to_vec();
+    for (placeholder, value) in replacements {
+        let offsets = template
+            .windows(N)
+            .enumerate()
+            .filter(|(_, window)| *window == placeholder)
+            .map(|(offset, _)| offset)
+            .collect::<Vec<_>>();
+        debug_assert!(
+            !offsets.is_empty(),
+            "placeholder not found in stub template"
+        );
+        for offset in offsets {
+            code[offset..offset + N].copy_from_slice(&value);
+        }
+    }
+    code
+}
+
+fn patch_x86(template: &[u8], procedure: u32, result: u32, get_last_error: u32) -> Vec<u8> {
+    patch(
+        template,
+        [
+            (PROCEDURE_X86.to_le_bytes(), procedure.to_le_bytes()),
+            (RESULT_X86.to_le_bytes(), result.to_le_bytes()),
+            (
+                GET_LAST_ERROR_X86.to_le_bytes(),
+                get_last_error.to_le_bytes(),
+            ),
+        ],
+    )
+}
+
+fn patch_x64(template: &[u8], procedure: u64, result: u64, get_last_error: u64) -> Vec<u8> {
+    patch(
+        template,
+        [
+            (PROCEDURE_X64.to_le_bytes(), procedure.to_le_bytes()),
+            (RESULT_X64.to_le_bytes(), result.to_le_bytes()),
+            (
+                GET_LAST_ERROR_X64.to_le_bytes(),
+                get_last_error.to_le_bytes(),
+            ),
+        ],
+    )
+}
+
+pub(crate) fn load_library_w_x86(load_library_w: u32, result: u32, get_last_error: u32) -> Vec<u8> {
+    patch_x86(&LOAD_LIBRARY_W_X86, load_library_w, result, get_last_error)
+}
+
+pub(crate) fn load_library_w_x64(load_library_w: u64, result: u64, get_last_error: u64) -> Vec<u8> {
+    patch_x64(&LOAD_LIBRARY_W_X64, load_library_w, result, get_last_error)
+}
+
+#[cfg(feature = "rpc-core")]
+pub(crate) fn get_proc_address_x86(
+    get_proc_address: u32,
+    result: u32,
+    get_last_error: u32,
+) -> Vec<u8> {
+    patch_x86(
+        &GET_PROC_ADDRESS_X86,
+        get_proc_address,
+        result,
+        get_last_error,
+    )
+}
+
+#[cfg(feature = "rpc-core")]
+pub(crate) fn get_proc_address_x64(
+    get_proc_address: u64,
+    result: u64,
+    get_last_error: u64,
+) -> Vec<u8> {
+    patch_x64(
+        &GET_PROC_ADDRESS_X64,
+        get_proc_address,
+        result,
+        get_last_error,
+    )
+}
+
+/// Builds the same code as `ContextThreadStub::build_code_x86`, whose offsets depend on the word size of the parameter block.
+pub(crate) fn context_thread_x86() -> Vec<u8> {
+    const WORD: u8 = mem::size_of::<usize>() as u8;
+
+    #[rustfmt::skip]
+    let code = vec![
+        0x8B, 0x44, 0x24, 0x04, // mov eax, [esp+4]
+        0xFF, 0x70, 2 * WORD,   // push dword [eax+2*WORD]
+        0xFF, 0x70, WORD,       // push dword [eax+WORD]
+        0xFF, 0x10,             // call dword [eax]
+        0xC2, 0x04, 0x00,       // ret 4
+    ];
+    code
+}
+
+/// Builds the same code as `RemoteRawProcedure::build_call_stub_x86`.
+#[cfg(feature = "rpc-raw")]
+pub(crate) fn call_x86(
+    arity: usize,
+    callee_cleanup: bool,
+    has_parameter: bool,
+    result_len: Option<u32>,
+    args_offset: usize,
+) -> Vec<u8> {
+    const WORD: usize = mem::size_of::<usize>();
+
+    let mut code = Vec::new();
+    code.extend_from_slice(&[0x8B, 0x44, 0x24, 0x04]); // mov eax, [esp+4]
+    if has_parameter {
+        for i in (0..arity).rev() {
+            // push dword [eax+disp8]
+            code.extend_from_slice(&[0xFF, 0x70, disp8(args_offset + i * WORD)]);
+        }
+        code.extend_from_slice(&[0xFF, 0x10]); // call dword [eax]
+    } else {
+        code.extend_from_slice(&[0xFF, 0xD0]); // call eax
+    }
+    if !callee_cleanup && arity > 0 {
+        // add esp, imm8
+        code.extend_from_slice(&[0x83, 0xC4, disp8(mem::size_of::<u32>() * arity)]);
+    }
+    if let Some(len) = result_len {
+        code.extend_from_slice(&[0x8B, 0x4C, 0x24, 0x04]); // mov ecx, [esp+4]
+        code.extend_from_slice(&[0x8B, 0x49, disp8(WORD)]); // mov ecx, [ecx+disp8]
+        emit_write_x86_at_ecx(&mut code, RemoteResultStatus::Ok, len);
+    }
+    code.extend_from_slice(&[0xB8, 0x00, 0x00, 0x00, 0x00]); // mov eax, 0
+    code.extend_from_slice(&[0xC2, 0x04, 0x00]); // ret 4
+    code
+}
+
+/// Builds the same code as `RemoteRawProcedure::build_call_stub_x64`.
+#[cfg(feature = "rpc-raw")]
+pub(crate) fn call_x64(
+    arity: usize,
+    has_parameter: bool,
+    result_len: Option<u32>,
+    float_mask: u32,
+    args_offset: usize,
+) -> Vec<u8> {
+    const WORD: usize = mem::size_of::<u64>();
+    const SHADOW_SPACE: usize = 32;
+
+    let stack_args = arity.saturating_sub(4);
+    let frame_size = (SHADOW_SPACE + stack_args * WORD + WORD).next_multiple_of(16) - WORD;
+
+    let mut code = Vec::new();
+    code.extend_from_slice(&[0x48, 0x89, 0x4C, 0x24, 0x08]); // mov [rsp+8], rcx
+    code.extend_from_slice(&[0x48, 0x83, 0xEC, disp8(frame_size)]); // sub rsp, imm8
+    code.extend_from_slice(&[0x48, 0x89, 0xC8]); // mov rax, rcx
+    if has_parameter {
+        // mov reg, [rax+disp8] and movq xmm, reg for rcx/xmm0, rdx/xmm1, r8/xmm2 and r9/xmm3.
+        let loads = [
+            [0x48, 0x8B, 0x48],
+            [0x48, 0x8B, 0x50],
+            [0x4C, 0x8B, 0x40],
+            [0x4C, 0x8B, 0x48],
+        ];
+        let float_moves = [
+            [0x48, 0x0F, 0x6E, 0xC1],
+            [0x48, 0x0F, 0x6E, 0xCA],
+            [0x49, 0x0F, 0x6E, 0xD0],
+            [0x49, 0x0F, 0x6E, 0xD9],
+        ];
+        for (i, (load, float_move)) in loads.iter().zip(&float_moves).enumerate().take(arity) {
+            code.extend_from_slice(load);
+            code.push(disp8(args_offset + i * WORD));
+            if float_mask & (1 << i) != 0 {
+                code.push(0x66);
+                code.extend_from_slice(float_move);
+            }
+        }
+        for i in 4..arity {
+            // mov r10, [rax+disp8]
+            code.extend_from_slice(&[0x4C, 0x8B, 0x50, disp8(args_offset + i * WORD)]);
+            // mov [rsp+disp8], r10
+            let stack_slot = disp8(SHADOW_SPACE + (i - 4) * WORD);
+            code.extend_from_slice(&[0x4C, 0x89, 0x54, 0x24, stack_slot]);
+        }
+        code.extend_from_slice(&[0xFF, 0x10]); // call qword [rax]
+    } else {
+        code.extend_from_slice(&[0xFF, 0xD0]); // call rax
+    }
+    code.extend_from_slice(&[0x48, 0x83, 0xC4, disp8(frame_size)]); // add rsp, imm8
+    if let Some(len) = result_len {
+        if float_mask & 0x8000_0000u32 != 0 {
+            code.extend_from_slice(&[0x66, 0x48, 0x0F, 0x7E, 0xC0]); // movq rax, xmm0
+        }
+        code.extend_from_slice(&[0x4C, 0x8B, 0x5C, 0x24, 0x08]); // mov r11, [rsp+8]
+        code.extend_from_slice(&[0x4D, 0x8B, 0x5B, disp8(WORD)]); // mov r11, [r11+disp8]
+        emit_write_x64_at_r11(&mut code, RemoteResultStatus::Ok, len);
+    }
+    code.extend_from_slice(&[0x48, 0xB8, 0, 0, 0, 0, 0, 0, 0, 0]); // mov rax, 0
+    code.push(0xC3); // ret
+    code
+}
+
+/// Same as `RemoteResultBuf::emit_write_x86_at_ecx`.
+#[cfg(feature = "rpc-raw")]
+fn emit_write_x86_at_ecx(code: &mut Vec<u8>, status: RemoteResultStatus, len: u32) {
+    let payload = disp8(RemoteResultBuf::PAYLOAD_OFFSET);
+    code.extend_from_slice(&[0x89, 0x41, payload]); // mov [ecx+payload], eax
+    if len > 4 {
+        code.extend_from_slice(&[0x89, 0x51, payload + 4]); // mov [ecx+payload+4], edx
+    }
+    code.extend_from_slice(&[0xC7, 0x41, 0x04]); // mov dword [ecx+4], len
+    code.extend_from_slice(&len.to_le_bytes());
+    code.extend_from_slice(&[0xC7, 0x01]); // mov dword [ecx], status
+    code.extend_from_slice(&(status as u32).to_le_bytes());
+}
+
+/// Same as `RemoteResultBuf::emit_write_x64_at_r11`.
+#[cfg(feature = "rpc-raw")]
+fn emit_write_x64_at_r11(code: &mut Vec<u8>, status: RemoteResultStatus, len: u32) {
+    let payload = disp8(RemoteResultBuf::PAYLOAD_OFFSET);
+    code.extend_from_slice(&[0x49, 0x89, 0x43, payload]); // mov [r11+payload], rax
+    code.extend_from_slice(&[0x41, 0xC7, 0x43, 0x04]); // mov dword [r11+4], len
+    code.extend_from_slice(&len.to_le_bytes());
+    code.extend_from_slice(&[0x41, 0xC7, 0x03]); // mov dword [r11], status
+    code.extend_from_slice(&(status as u32).to_le_bytes());
+}
+
+/// Returns the given value as an 8-bit displacement or immediate, which all offsets in the stubs fit into.
+#[cfg(feature = "rpc-raw")]
+fn disp8(value: usize) -> u8 {
+    assert!(
+        value <= i8::MAX as usize,
+        "stub offset does not fit into 8 bits"
+    );
+    value as u8
+}
@@ -1,55 +1,121 @@
 use cstr::cstr;
+#[cfg(feature = "assembler")]
 use iced_x86::{
     code_asm::{
-        dword_ptr,
         registers::{gpr32::*, gpr64::*},
         CodeAssembler,
     },
     IcedError,
 };
-use num_enum::TryFromPrimitive;
-use path_absolutize::Absolutize;
-use std::{cell::OnceCell, io, mem, path::Path};
+use std::{
+    cell::{OnceCell, RefCell},
+    collections::HashMap,
+    ffi::CStr,
+    io, mem,
+    path::{Path, PathBuf},
+    ptr, thread,
+    time::{Duration, Instant},
+};
 use widestring::{u16cstr, U16CString};
-use winapi::shared::{
-    minwindef::{BOOL, DWORD, FALSE, HMODULE},
-    ntdef::LPCWSTR,
+use winapi::{
+    shared::{
+        minwindef::{BOOL, DWORD, FALSE, HMODULE},
+        ntdef::LPCWSTR,
+        winerror::{
+            ERROR_ACCESS_DENIED, ERROR_FILENAME_EXCED_RANGE, ERROR_INVALID_IMAGE_HASH,
+            ERROR_INVALID_PARAMETER, ERROR_MOD_NOT_FOUND, ERROR_PROC_NOT_FOUND,
+        },
+    },
+    um::{
+        libloaderapi::{FreeLibrary, LoadLibraryW},
+        winnt::{
+            PAGE_EXECUTE_READ, PROCESS_CREATE_THREAD, PROCESS_DUP_HANDLE,
+            PROCESS_QUERY_INFORMATION, PROCESS_VM_OPERATION, PROCESS_VM_READ, PROCESS_VM_WRITE,
+        },
+    },
 };
 
 use crate::{
-    error::{EjectError, ExceptionCode, ExceptionOrIoError, InjectError, LoadInjectHelpDataError},
+    context_thread::ContextThreadStub,
+    error::{
+        EjectError, InjectError, LoadInjectHelpDataError, MissingAccessError,
+        MissingDependencyError, MissingExportError, OpenProcessError, Operation,
+    },
+    function::RawFunctionPtr,
+    inject_options::remove_staged_payload,
+    missing_dependency::{find_missing_dependency, MissingDependency},
+    payload_policy::check_mitigation_policies,
     process::{
-        memory::{RemoteAllocation, RemoteBox, RemoteBoxAllocator},
-        BorrowedProcess, BorrowedProcessModule, ModuleHandle, OwnedProcess, Process, ProcessModule,
+        is_wine,
+        memory::{
+            with_image_file, AllocationPlacement, MemoryProtection, ProcessMemoryBuffer,
+            RemoteAllocation, RemoteAllocationBackend, RemoteBox, RemoteBoxAllocator, RemoteImage,
+            RemoteResult, RemoteResultBuf,
+        },
+        BorrowedProcess, BorrowedProcessModule, Capabilities, ModuleHandle, ModulePathMatcher,
+        NameMatchOptions, OwnedProcess, Process, ProcessExitWatch, ProcessIter, ProcessModule,
+        RemoteThreadOptions, RemoteThreadResult, RetryPolicy, PROCESS_MEMORY_ACCESS,
+        PROCESS_MEMORY_READ_ACCESS, PROCESS_QUERY_ACCESS,
     },
+    shellcode::RemoteShellcode,
+    syringe_events::EventListeners,
+    utils::{is_long_path, short_path_name, to_extended_length_path, trace_event, trace_span},
+    InjectOptions, InjectedModule, PayloadMarkerData, StubInfo, StubKind, SyringeEvent,
+    PAYLOAD_MARKER_EXPORT_NAME, SELF_UNLOAD_EXPORT_NAME,
 };
 
+#[cfg(feature = "assembler")]
+use crate::process::memory::RemoteResultStatus;
+#[cfg(not(feature = "assembler"))]
+use {crate::stub_templates, std::convert::Infallible};
+
 #[cfg(all(target_arch = "x86_64", feature = "into-x86-from-x64"))]
 use {
+    crate::process::{ModuleVersion, ModuleVersionInfo},
+    crate::utils::retry_faillable_until_some_with_policy,
     goblin::pe::PE,
-    std::{convert::TryInto, fs, mem::MaybeUninit, path::PathBuf, time::Duration},
+    std::{
+        convert::TryInto,
+        fs,
+        mem::MaybeUninit,
+        sync::{Mutex, PoisonError},
+    },
     widestring::U16Str,
     winapi::{shared::minwindef::MAX_PATH, um::wow64apiset::GetSystemWow64DirectoryW},
 };
 
 #[cfg(feature = "rpc-core")]
-use {
-    crate::function::RawFunctionPtr,
-    winapi::shared::{minwindef::FARPROC, ntdef::LPCSTR},
-};
+use winapi::shared::{minwindef::FARPROC, ntdef::LPCSTR};
+
+/// The access rights needed to list the modules of the target process.
+const MODULE_QUERY_ACCESS: DWORD = PROCESS_QUERY_INFORMATION | PROCESS_VM_READ;
+
+/// The access rights needed to run code in the target process, e.g. to load or unload a module.
+/// Unlike [`PROCESS_INJECTION_ACCESS`](crate::process::PROCESS_INJECTION_ACCESS), this does not include `SYNCHRONIZE`,
+/// which is only used to be notified about the exit of the target.
+const REMOTE_CODE_ACCESS: DWORD =
+    MODULE_QUERY_ACCESS | PROCESS_CREATE_THREAD | PROCESS_VM_OPERATION | PROCESS_VM_WRITE;
 
 type LoadLibraryWFn = unsafe extern "system" fn(LPCWSTR) -> HMODULE;
 type FreeLibraryFn = unsafe extern "system" fn(HMODULE) -> BOOL;
-type GetLastErrorFn = unsafe extern "system" fn() -> DWORD;
+pub(crate) type GetLastErrorFn = unsafe extern "system" fn() -> DWORD;
 #[cfg(feature = "rpc-core")]
 pub(crate) type GetProcAddressFn = unsafe extern "system" fn(HMODULE, LPCSTR) -> FARPROC;
 
+/// The offsets of the kernel32 exports used for injection into WOW64 processes, keyed by the path and file version of the
+/// kernel32 module they were read from.
+#[cfg(all(target_arch = "x86_64", feature = "into-x86-from-x64"))]
+static KERNEL32_OFFSET_CACHE: Mutex<Option<HashMap<(PathBuf, ModuleVersion), InjectHelpData>>> =
+    Mutex::new(None);
+
 #[derive(Debug, Clone)]
 pub(crate) struct InjectHelpData {
     kernel32_module: ModuleHandle,
     load_library_offset: usize,
     free_library_offset: usize,
     get_last_error_offset: usize,
+    wait_for_single_object_offset: usize,
+    set_event_offset: usize,
     #[cfg(feature = "rpc-core")]
     get_proc_address_offset: usize,
 }
@@ -58,20 +124,60 @@ unsafe impl Send for InjectHelpData {}
 
 impl InjectHelpData {
     pub fn get_load_library_fn_ptr(&self) -> LoadLibraryWFn {
-        unsafe { mem::transmute(self.kernel32_module as usize + self.load_library_offset) }
+        unsafe {
+            mem::transmute::<usize, LoadLibraryWFn>(
+                self.kernel32_module as usize + self.load_library_offset,
+            )
+        }
     }
     pub fn get_free_library_fn_ptr(&self) -> FreeLibraryFn {
-        unsafe { mem::transmute(self.kernel32_module as usize + self.free_library_offset) }
+        unsafe {
+            mem::transmute::<usize, FreeLibraryFn>(
+                self.kernel32_module as usize + self.free_library_offset,
+            )
+        }
     }
     pub fn get_get_last_error(&self) -> GetLastErrorFn {
-        unsafe { mem::transmute(self.kernel32_module as usize + self.get_last_error_offset) }
+        unsafe {
+            mem::transmute::<usize, GetLastErrorFn>(
+                self.kernel32_module as usize + self.get_last_error_offset,
+            )
+        }
+    }
+    pub fn get_wait_for_single_object_fn_ptr(&self) -> usize {
+        self.kernel32_module as usize + self.wait_for_single_object_offset
+    }
+    pub fn get_set_event_fn_ptr(&self) -> usize {
+        self.kernel32_module as usize + self.set_event_offset
     }
     #[cfg(feature = "rpc-core")]
     pub fn get_proc_address_fn_ptr(&self) -> GetProcAddressFn {
-        unsafe { mem::transmute(self.kernel32_module as usize + self.get_proc_address_offset) }
+        unsafe {
+            mem::transmute::<usize, GetProcAddressFn>(
+                self.kernel32_module as usize + self.get_proc_address_offset,
+            )
+        }
     }
 }
 
+/// The way a module is ejected from the target process, see [`Syringe::eject_with_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub enum EjectMode {
+    /// `FreeLibrary` is called for the module from a thread created by the syringe.
+    ///
+    /// This works for every module, but unloads it even if its own threads are still executing its code, which crashes the target.
+    #[default]
+    FreeLibrary,
+    /// The module unloads itself using `FreeLibraryAndExitThread` from a thread created by the syringe, which never returns
+    /// into the unloaded module. The module can stop its own threads beforehand.
+    ///
+    /// The module has to define the required export using the `payload_self_unload!` macro, otherwise ejecting it fails with
+    /// [`EjectError::SelfUnloadUnsupported`].
+    SelfUnload,
+}
+
 /// An injector that can inject modules (.dll's) into a target process.
 ///
 /// # Example
@@ -98,22 +204,121 @@ pub struct Syringe {
     pub(crate) inject_help_data: OnceCell<InjectHelpData>,
     pub(crate) remote_allocator: RemoteBoxAllocator,
     load_library_w_stub: OnceCell<LoadLibraryWStub>,
+    pub(crate) context_thread_stub: OnceCell<ContextThreadStub>,
+    pub(crate) remote_thread_options: RemoteThreadOptions,
+    // staged payload copies by the address of the module loaded from them.
+    staged_payloads: RefCell<HashMap<usize, PathBuf>>,
+    module_retry_policy: RetryPolicy,
+    wine_compatibility: bool,
+    event_listeners: EventListeners,
+    // registered on first use, as most syringes never check for the exit of their target.
+    exit_watch: OnceCell<Option<ProcessExitWatch>>,
+    // `None` if the access rights of the handle could not be determined, in which case operations are attempted regardless.
+    granted_access: Option<DWORD>,
+    #[cfg(feature = "process-memory")]
+    pub(crate) journal: std::rc::Rc<RefCell<crate::journal::ModificationJournal>>,
+    #[cfg(feature = "rpc-core")]
+    pub(crate) get_proc_address_stub: OnceCell<crate::rpc::RemoteProcedureStub>,
+    #[cfg(all(feature = "rpc-core", feature = "assembler"))]
+    pub(crate) get_procs_stub: OnceCell<crate::process::memory::RemoteAllocation>,
     #[cfg(feature = "rpc-core")]
-    pub(crate) get_proc_address_stub:
-        OnceCell<crate::rpc::RemoteProcedureStub<crate::rpc::GetProcAddressParams, RawFunctionPtr>>,
+    pub(crate) procedure_cache: crate::rpc::ProcedureCache,
+    #[cfg(feature = "rpc-core")]
+    pub(crate) procedure_lookup_method: crate::rpc::ProcedureLookupMethod,
+    #[cfg(feature = "rpc-core")]
+    pub(crate) payload_log_handler: Option<crate::payload_log::PayloadLogHandler>,
 }
 
 impl Syringe {
     /// Creates a new syringe for the given target process.
+    ///
+    /// Each operation only requires the access rights it uses, e.g. injecting and ejecting requires `PROCESS_CREATE_THREAD`,
+    /// `PROCESS_QUERY_INFORMATION`, `PROCESS_VM_OPERATION`, `PROCESS_VM_READ` and `PROCESS_VM_WRITE`,
+    /// while [`find_or_inject`](Self::find_or_inject) for an already loaded module only requires `PROCESS_QUERY_INFORMATION` and `PROCESS_VM_READ`.
+    /// Operations requiring access rights the handle lacks fail with a [`MissingAccessError`] before touching the target.
+    /// A handle with the [`PROCESS_INJECTION_ACCESS`](crate::process::PROCESS_INJECTION_ACCESS) access rights supports all operations.
     #[must_use]
     pub fn for_process(process: OwnedProcess) -> Self {
+        let granted_access = process.granted_access().ok();
         Self {
             remote_allocator: RemoteBoxAllocator::new(process),
+            exit_watch: OnceCell::new(),
+            granted_access,
             inject_help_data: OnceCell::new(),
             load_library_w_stub: OnceCell::new(),
+            context_thread_stub: OnceCell::new(),
+            remote_thread_options: RemoteThreadOptions::new(),
+            staged_payloads: RefCell::new(HashMap::new()),
+            module_retry_policy: RetryPolicy::new(),
+            wine_compatibility: is_wine(),
+            event_listeners: EventListeners::default(),
+            #[cfg(feature = "process-memory")]
+            journal: std::rc::Rc::default(),
             #[cfg(feature = "rpc-core")]
             get_proc_address_stub: OnceCell::new(),
+            #[cfg(all(feature = "rpc-core", feature = "assembler"))]
+            get_procs_stub: OnceCell::new(),
+            #[cfg(feature = "rpc-core")]
+            procedure_cache: crate::rpc::ProcedureCache::default(),
+            #[cfg(feature = "rpc-core")]
+            procedure_lookup_method: crate::rpc::ProcedureLookupMethod::default(),
+            #[cfg(feature = "rpc-core")]
+            payload_log_handler: None,
+        }
+    }
+
+    /// Opens the process with the given id and creates a new syringe for it.
+    ///
+    /// Unlike opening the process using [`OwnedProcess::from_pid`], the returned error distinguishes ids without a running process,
+    /// processes that deny access and processes that exited while they were opened.
+    ///
+    /// If the process denies the [`PROCESS_INJECTION_ACCESS`](crate::process::PROCESS_INJECTION_ACCESS) access rights,
+    /// it is opened with the most extensive of the more limited access rights it grants, see [`Syringe::for_process`].
+    pub fn for_process_by_pid(pid: u32) -> Result<Self, OpenProcessError> {
+        Ok(Self::for_process(open_target_process(pid)?))
+    }
+
+    /// Opens the first running process with the given executable name and creates a new syringe for it.
+    ///
+    /// The comparison of names is case-insensitive and the extension `.exe` may be omitted, e.g. `notepad` selects `Notepad.exe`.
+    /// Processes that exit before they are opened are skipped.
+    pub fn for_process_by_name(name: impl AsRef<str>) -> Result<Self, OpenProcessError> {
+        let name = name.as_ref();
+        let options = NameMatchOptions::new()
+            .with_exact(true)
+            .with_case_sensitive(false)
+            .with_optional_extension(true);
+        let wide_name = name.encode_utf16().collect::<Vec<_>>();
+        let mut path_buf = Vec::new();
+        let mut iter = ProcessIter::new()?;
+
+        let mut exited = None;
+        while let Some(entry) = iter.next_matching(|pid, file_name| {
+            options.matches_process(&wide_name, pid, file_name, &mut path_buf)
+        }) {
+            match open_target_process(entry.pid()) {
+                Ok(process) => return Ok(Self::for_process(process)),
+                // the process was part of the snapshot, so it exited since.
+                Err(OpenProcessError::NotFound { pid } | OpenProcessError::Exited { pid }) => {
+                    exited = Some(OpenProcessError::Exited { pid });
+                }
+                Err(err) => return Err(err),
+            }
         }
+        Err(
+            exited.unwrap_or_else(|| OpenProcessError::NoMatchingProcess {
+                name: name.to_string(),
+            }),
+        )
+    }
+
+    /// Creates a new syringe targeting the current process.
+    ///
+    /// Payloads are loaded and ejected by calling `LoadLibraryW` and `FreeLibrary` directly instead of through a remote thread.
+    /// The same applies to any other syringe whose target is the current process, e.g. one opened by the id of the current process.
+    #[must_use]
+    pub fn for_current_process() -> Self {
+        Self::for_process(OwnedProcess::current())
     }
 
     /// Returns the target process for this syringe.
@@ -121,48 +326,614 @@ impl Syringe {
         self.remote_allocator.process()
     }
 
+    /// Returns whether the target process has exited.
+    ///
+    /// The exit is detected through a wait registered the first time this is called, so later calls do not query the target process.
+    /// Once the target exited, all operations of this syringe fail with a `ProcessInaccessible` error without calling into it.
+    #[must_use]
+    pub fn has_target_exited(&self) -> bool {
+        if self.remote_allocator.is_dead() {
+            return true;
+        }
+        let mut registered = false;
+        let exit_watch = self.exit_watch.get_or_init(|| {
+            registered = true;
+            // without a registration, the exit of the target is detected by querying it instead.
+            self.process().watch_exit().ok()
+        });
+        match exit_watch {
+            // a new registration is only signaled asynchronously, so the target is queried once.
+            Some(exit_watch) if !registered => exit_watch.has_exited(),
+            _ => !self.process().is_alive(),
+        }
+    }
+
+    /// Returns an error if the handle of the target process lacks any of the given access rights.
+    pub(crate) fn require_access(&self, required: DWORD) -> Result<(), MissingAccessError> {
+        match self.granted_access {
+            Some(granted) if granted & required != required => {
+                Err(MissingAccessError { required, granted })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the given `ProcessInaccessible` error instead of the given error if the target process exited,
+    /// as a dead target otherwise surfaces as arbitrary errors from inside remote calls.
+    pub(crate) fn error_unless_exited<E>(&self, err: E, process_inaccessible: E) -> E {
+        // the exit notification may not have arrived yet, so the process is queried as well.
+        if self.has_target_exited() || !self.process().is_alive() {
+            process_inaccessible
+        } else {
+            err
+        }
+    }
+
+    /// Returns the policy used to wait for the modules required for injection to be loaded by the target process.
+    /// It only applies to 32-bit (WOW64) targets of a 64-bit process, see [`Syringe::set_module_retry_policy`].
+    #[must_use]
+    pub fn module_retry_policy(&self) -> &RetryPolicy {
+        &self.module_retry_policy
+    }
+
+    /// Sets the policy used to wait for the modules required for injection to be loaded by the target process.
+    ///
+    /// Freshly started targets may not have loaded them yet. The default policy waits for up to one second,
+    /// which may be too short for slow-loading (e.g. packed) executables, while [`RetryPolicy::no_retry`] fails immediately.
+    /// The modules are only looked up once, before the first injection, ejection or procedure load.
+    ///
+    /// # Note
+    /// The policy only applies when injecting from a 64-bit process into a 32-bit (WOW64) process. In all other cases the
+    /// target uses the same `kernel32.dll` as the current process, so the required functions are resolved locally without waiting.
+    pub fn set_module_retry_policy(&mut self, policy: RetryPolicy) {
+        self.module_retry_policy = policy;
+    }
+
+    /// Returns whether this syringe works around behaviors that differ under Wine, see [`Syringe::set_wine_compatibility`].
+    #[must_use]
+    pub fn wine_compatibility(&self) -> bool {
+        self.wine_compatibility
+    }
+
+    /// Sets whether this syringe works around behaviors that differ under Wine.
+    /// This is enabled by default if the current process runs under Wine or Proton, see [`is_wine`].
+    ///
+    /// In compatibility mode
+    /// - the protection level and mitigation policies of the target are not checked before injecting, as Wine supports neither,
+    /// - the `kernel32.dll` exports used to inject into x86 targets are read from the module loaded by the target
+    ///   instead of the file in the `SysWOW64` directory, whose location and contents depend on the Wine prefix.
+    ///
+    /// The setting only affects modules that are looked up after it is changed.
+    pub fn set_wine_compatibility(&mut self, enabled: bool) {
+        self.wine_compatibility = enabled;
+    }
+
+    /// Returns a report of what is supported for the target process from the current process,
+    /// e.g. to tell users why injection will fail before trying, see [`Process::capabilities`].
+    ///
+    /// Unlike [`Process::capabilities`], this respects the [Wine compatibility mode](Syringe::set_wine_compatibility) of this syringe.
+    #[must_use]
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::probe(self.process(), self.wine_compatibility)
+    }
+
+    /// Returns whether the target process is a 32-bit process.
+    ///
+    /// Unlike [`Process::is_x86`], the result is queried once and cached, as the architecture of a process never changes.
+    /// The syringe uses the cached value itself whenever it builds a stub or calls a procedure.
+    pub fn is_target_x86(&self) -> Result<bool, io::Error> {
+        self.remote_allocator.is_x86()
+    }
+
+    /// Returns the backend used for the data this syringe allocates in the target process.
+    #[must_use]
+    pub fn allocation_backend(&self) -> RemoteAllocationBackend {
+        self.remote_allocator.backend()
+    }
+
+    /// Sets the backend used for the data this syringe allocates in the target process, e.g. module paths and procedure arguments.
+    ///
+    /// With [`RemoteAllocationBackend::ProcessHeap`], small allocations are placed in the default heap of the target
+    /// instead of occupying whole pages. Stub code is always placed in pages, as heap memory is not executable.
+    /// Existing allocations are not moved.
+    pub fn set_allocation_backend(&mut self, backend: RemoteAllocationBackend) {
+        self.remote_allocator.set_backend(backend);
+    }
+
+    /// Returns where the pages this syringe allocates in the target process are placed.
+    #[must_use]
+    pub fn allocation_placement(&self) -> AllocationPlacement {
+        self.remote_allocator.placement()
+    }
+
+    /// Sets where the pages this syringe allocates in the target process are placed, e.g. [below 4 GiB](AllocationPlacement::Below4Gb)
+    /// or [within `rel32` reach](AllocationPlacement::near) of a function a stub is called from.
+    ///
+    /// The placement applies to pages allocated afterwards, existing pages keep being used for allocations that fit into them,
+    /// so it should be set before the syringe is used or after [trimming](Syringe::trim_allocations) unused pages.
+    /// Data placed in the heap of the target by [`RemoteAllocationBackend::ProcessHeap`] is not affected.
+    pub fn set_allocation_placement(&mut self, placement: AllocationPlacement) {
+        self.remote_allocator.set_placement(placement);
+    }
+
+    /// Returns the maximum number of bytes this syringe may commit in the target process, if limited.
+    #[must_use]
+    pub fn allocation_budget(&self) -> Option<usize> {
+        self.remote_allocator.budget()
+    }
+
+    /// Sets the maximum number of bytes this syringe may commit in the target process, or removes the limit if [`None`].
+    ///
+    /// Allocations that would exceed the budget, e.g. for stubs, module paths or procedure arguments, fail with
+    /// [`RemoteAllocationBudgetExceeded`](crate::error::RemoteAllocationBudgetExceeded) instead of exhausting the address space of the target,
+    /// which is easily done for 32-bit targets by a loop calling remote procedures with large arguments.
+    /// Memory is committed in whole pages, so the budget is exceeded by the first allocation that requires a new page.
+    /// Existing allocations are kept even if they exceed a new budget.
+    pub fn set_allocation_budget(&mut self, budget: Option<usize>) {
+        self.remote_allocator.set_budget(budget);
+    }
+
+    /// Returns the number of bytes this syringe has currently committed in the target process, which is what the [allocation budget](Syringe::set_allocation_budget) limits.
+    #[must_use]
+    pub fn committed_remote_bytes(&self) -> usize {
+        self.remote_allocator.count_committed_bytes()
+    }
+
+    /// Allocates a copy of the given value in the target process using the allocator of this syringe.
+    ///
+    /// The returned box shares the process handle of the syringe instead of borrowing it, so it can be kept around
+    /// independently of the syringe and is freed once it is dropped.
+    pub fn alloc_box<T: Copy>(&self, value: &T) -> Result<RemoteBox<T>, io::Error> {
+        self.remote_allocator.alloc_and_copy(value)
+    }
+
+    /// Places the given machine code in executable memory of the target process using the allocator of this syringe,
+    /// e.g. a stub built using the [`shellcode`](crate::shellcode) module, so it can be run repeatedly.
+    ///
+    /// Like boxes allocated by [`Syringe::alloc_box`], the code is freed once it is dropped.
+    pub fn alloc_shellcode(&self, code: &[u8]) -> Result<RemoteShellcode, io::Error> {
+        if code.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "shellcode must not be empty",
+            ));
+        }
+        let code = self.remote_allocator.alloc_and_copy_code(code)?;
+        trace_event!(debug, address = ?code.as_ptr(), len = code.len(), "wrote shellcode");
+        Ok(RemoteShellcode::new(code))
+    }
+
+    /// Releases the pages this syringe allocated in the target process that no longer hold any allocations,
+    /// e.g. after many procedures with large stubs were dropped, and returns the number of bytes released.
+    ///
+    /// Freed allocations are merged with their free neighbors, so this is only needed to return memory to the target.
+    pub fn trim_allocations(&self) -> usize {
+        self.remote_allocator.trim()
+    }
+
+    /// Returns the stubs this syringe has placed in the target process so far, e.g. to find out whether a crash in the target
+    /// occurred inside one of them. Stubs are created on first use, so a fresh syringe has none.
+    ///
+    /// The stubs of remote procedures can be retrieved using [`RemoteRawProcedure::stub_info`](crate::rpc::RemoteRawProcedure::stub_info).
+    pub fn stubs(&self) -> Result<Vec<StubInfo>, io::Error> {
+        self.remote_allocator.write_pending_code()?;
+        let mut stubs = Vec::new();
+        if let Some(stub) = self.load_library_w_stub.get() {
+            stubs.push(StubInfo::read(StubKind::LoadLibraryW, &stub.code)?);
+        }
+        if let Some(stub) = self.context_thread_stub.get() {
+            stubs.push(StubInfo::read(StubKind::ContextThread, &stub.code)?);
+        }
+        #[cfg(feature = "rpc-core")]
+        if let Some(stub) = self.get_proc_address_stub.get() {
+            stubs.push(StubInfo::read(StubKind::GetProcAddress, &stub.code)?);
+        }
+        #[cfg(all(feature = "rpc-core", feature = "assembler"))]
+        if let Some(stub) = self.get_procs_stub.get() {
+            stubs.push(StubInfo::read(StubKind::GetProcAddressBatch, stub)?);
+        }
+        Ok(stubs)
+    }
+
+    /// Returns the number of stubs this syringe placed in the target, without reading them like [`Syringe::stubs`].
+    pub(crate) fn count_stubs(&self) -> usize {
+        [
+            self.load_library_w_stub.get().is_some(),
+            self.context_thread_stub.get().is_some(),
+            #[cfg(feature = "rpc-core")]
+            self.get_proc_address_stub.get().is_some(),
+            #[cfg(all(feature = "rpc-core", feature = "assembler"))]
+            self.get_procs_stub.get().is_some(),
+        ]
+        .into_iter()
+        .filter(|&placed| placed)
+        .count()
+    }
+
+    /// Calls the given function with the writes of the stubs it places in the target process batched, so stubs placed next to
+    /// each other are written and their instruction cache flushed with a single call each instead of one per stub.
+    /// This reduces the cost of preparing many remote procedures up front, e.g. using
+    /// [`RemoteRawProcedure::prepare`](crate::rpc::RemoteRawProcedure::prepare).
+    ///
+    /// Pending stubs are written before any call is made in the target, so calls inside the function work as usual.
+    /// Nested batches join the outermost one, which writes the stubs once it ends.
+    pub fn batch_stub_writes<R>(&self, f: impl FnOnce(&Self) -> R) -> Result<R, io::Error> {
+        let batch = self.remote_allocator.code_batch();
+        let result = f(self);
+        batch.finish()?;
+        Ok(result)
+    }
+
+    /// Returns the options used for the threads this syringe creates in the target process.
+    #[must_use]
+    pub fn remote_thread_options(&self) -> &RemoteThreadOptions {
+        &self.remote_thread_options
+    }
+
+    /// Sets the options used for the threads this syringe creates in the target process,
+    /// e.g. to increase the stack size for targets with a small default stack.
+    ///
+    /// Procedures loaded afterwards inherit these options.
+    pub fn set_remote_thread_options(&mut self, options: RemoteThreadOptions) {
+        self.remote_thread_options = options;
+    }
+
+    /// Starts a long-lived worker thread in the target process that performs the remote calls of this syringe and of the procedures
+    /// loaded from it, instead of creating a new thread for every call, which is the dominant cost of a call to a cheap procedure.
+    ///
+    /// The worker waits for requests on an event, so it does not use any cpu time while idle. Calls are made one after another,
+    /// so procedures called on the worker share its thread-local state and [remote thread options](Syringe::set_remote_thread_options)
+    /// only apply when the worker is started. If the worker exits, e.g. because a procedure called `ExitThread`, calls fall back to
+    /// creating a thread each. A running worker is replaced.
+    ///
+    /// The events of the worker are duplicated into the target, so the target has to be accessible with `PROCESS_DUP_HANDLE` rights.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dll_syringe::{Syringe, process::OwnedProcess};
+    ///
+    /// let mut syringe = Syringe::for_process(OwnedProcess::find_first_by_name("target_process").unwrap());
+    /// syringe.start_worker_thread().unwrap();
+    /// // every call below runs on the same thread in the target.
+    /// let payload = syringe.inject("injection_payload.dll").unwrap();
+    /// syringe.eject(payload).unwrap();
+    /// syringe.stop_worker_thread().unwrap();
+    /// ```
+    pub fn start_worker_thread(&mut self) -> Result<(), InjectError> {
+        self.start_worker()
+    }
+
+    pub(crate) fn start_worker(&self) -> Result<(), InjectError> {
+        self.require_access(REMOTE_CODE_ACCESS | PROCESS_DUP_HANDLE)?;
+        let inject_data = self.inject_help_data()?;
+        self.remote_allocator.start_worker(
+            inject_data.get_wait_for_single_object_fn_ptr(),
+            inject_data.get_set_event_fn_ptr(),
+            &self.remote_thread_options,
+        )?;
+        Ok(())
+    }
+
+    /// Stops the worker thread started by [`Syringe::start_worker_thread`] and returns whether one was running.
+    /// Later calls create a new thread each again.
+    pub fn stop_worker_thread(&mut self) -> Result<bool, io::Error> {
+        self.remote_allocator.stop_worker()
+    }
+
+    /// Returns the id of the worker thread started by [`Syringe::start_worker_thread`], if it is running.
+    #[must_use]
+    pub fn worker_thread_id(&self) -> Option<u32> {
+        self.remote_allocator.worker_tid()
+    }
+
+    /// Returns how long calls on the worker thread are waited for, see [`Syringe::set_worker_call_timeout`].
+    #[must_use]
+    pub fn worker_call_timeout(&self) -> Option<Duration> {
+        self.remote_allocator.worker_call_timeout()
+    }
+
+    /// Sets how long calls on the worker thread started by [`Syringe::start_worker_thread`] are waited for before they fail
+    /// with an error of kind [`io::ErrorKind::TimedOut`]. By default calls are waited for indefinitely.
+    ///
+    /// The worker signals completion through an event and a status word in its memory, so a call can be given up on without
+    /// guessing the state of the thread. The call keeps running in the target though and while it does, calls fall back to
+    /// creating a thread each.
+    pub fn set_worker_call_timeout(&mut self, timeout: Option<Duration>) {
+        self.remote_allocator.set_worker_call_timeout(timeout);
+    }
+
+    /// Calls the function at the given address in the target process with the given parameter on the worker thread and
+    /// returns the full word it returned, which unlike the exit code of a thread is not truncated to 32 bits.
+    ///
+    /// The function is called as `extern "system" fn(parameter: usize) -> usize`.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`io::ErrorKind::NotConnected`] if no worker thread is running or of kind
+    /// [`io::ErrorKind::WouldBlock`] if it is still busy with a call that timed out, see [`Syringe::set_worker_call_timeout`].
+    ///
+    /// # Safety
+    /// The function must abide by the given signature and must not corrupt the target process.
+    pub unsafe fn call_on_worker_thread(
+        &self,
+        function: usize,
+        parameter: usize,
+    ) -> Result<u64, io::Error> {
+        if self.worker_thread_id().is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "no worker thread is running",
+            ));
+        }
+        self.remote_allocator
+            .call_on_worker(function, parameter)
+            .unwrap_or_else(|| {
+                Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    "the worker thread is still busy with a call that timed out",
+                ))
+            })
+    }
+
+    /// Registers a listener that is called for every [`SyringeEvent`] of this syringe,
+    /// e.g. to collect telemetry about injections without wrapping every call.
+    ///
+    /// Listeners are called synchronously on the thread performing the operation in the order they were added.
+    pub fn add_event_listener(&mut self, listener: impl Fn(&SyringeEvent<'_>) + Send + 'static) {
+        self.event_listeners.add(listener);
+    }
+
+    pub(crate) fn emit_remote_call_failed(
+        &self,
+        operation: Operation,
+        error: &(dyn std::error::Error + 'static),
+        start: Instant,
+    ) {
+        if !self.event_listeners.is_empty() {
+            self.event_listeners.emit(&SyringeEvent::RemoteCallFailed {
+                process: self.process(),
+                operation,
+                error,
+                elapsed: start.elapsed(),
+            });
+        }
+    }
+
     /// Injects the module from the given path into the target process.
     ///
+    /// Relative paths are rejected if they are ambiguous, see [`InjectOptions::with_resolve_relative_to`] for details.
+    ///
     /// # Limitations
     /// - The target process and the given module need to be of the same bitness.
     /// - If the current process is `x64` the target process can be either `x64` (always available) or `x86` (with the `into_x86_from_x64` feature enabled).
     /// - If the current process is `x86` the target process can only be `x86`.
+    /// - Payload paths longer than `MAX_PATH` are passed in their extended-length form, which requires the target process to be long path aware.
+    ///   Otherwise the short (8.3) name of the payload is used if available.
+    ///
+    /// If the target is the current process, the payload is loaded by calling `LoadLibraryW` directly.
+    /// If the injection fails after the payload was loaded, e.g. because the remote thread timed out, the payload is unloaded again
+    /// unless it was already loaded before.
     pub fn inject(
         &self,
         payload_path: impl AsRef<Path>,
+    ) -> Result<InjectedModule<'_>, InjectError> {
+        self.inject_with_options(payload_path, &InjectOptions::new())
+    }
+
+    /// Injects the module from the given path into the target process using the given options.
+    ///
+    /// # Limitations
+    /// See [`inject`](Self::inject).
+    pub fn inject_with_options(
+        &self,
+        payload_path: impl AsRef<Path>,
+        options: &InjectOptions,
+    ) -> Result<InjectedModule<'_>, InjectError> {
+        self.inject_module_with_options(payload_path.as_ref(), options)
+            .map(|module| InjectedModule::new(self, module))
+    }
+
+    pub(crate) fn inject_module_with_options(
+        &self,
+        payload_path: &Path,
+        options: &InjectOptions,
+    ) -> Result<BorrowedProcessModule<'_>, InjectError> {
+        trace_span!(DEBUG, "inject", pid = ?self.process().pid().ok(), payload = %payload_path.display());
+        if self.event_listeners.is_empty() {
+            return self.inject_with_options_inner(payload_path, options);
+        }
+
+        self.event_listeners.emit(&SyringeEvent::BeforeInject {
+            process: self.process(),
+            payload_path,
+        });
+        let start = Instant::now();
+        let result = self.inject_with_options_inner(payload_path, options);
+        self.event_listeners.emit(&SyringeEvent::AfterInject {
+            process: self.process(),
+            payload_path,
+            result: result.as_ref().copied(),
+            elapsed: start.elapsed(),
+        });
+        result
+    }
+
+    fn inject_with_options_inner(
+        &self,
+        payload_path: &Path,
+        options: &InjectOptions,
     ) -> Result<BorrowedProcessModule<'_>, InjectError> {
+        if self.has_target_exited() {
+            return Err(InjectError::ProcessInaccessible);
+        }
+        let module = self
+            .inject_from_options(payload_path, options)
+            .map_err(|err| self.error_unless_exited(err, InjectError::ProcessInaccessible))?;
+        #[cfg(feature = "rpc-core")]
+        self.attach_payload_log_handler(module);
+        Ok(module)
+    }
+
+    fn inject_from_options(
+        &self,
+        payload_path: &Path,
+        options: &InjectOptions,
+    ) -> Result<BorrowedProcessModule<'_>, InjectError> {
+        let payload_path = options.resolve_payload_path(payload_path, self.process())?;
+        match self.inject_from_resolved_path(&payload_path, options) {
+            Err(InjectError::RemoteIo(err))
+                if options.diagnose_missing_dependencies()
+                    && err.raw_os_error() == Some(ERROR_MOD_NOT_FOUND as i32) =>
+            {
+                match find_missing_dependency(self.process(), &payload_path) {
+                    Ok(Some(MissingDependency { name, importer })) => {
+                        Err(MissingDependencyError::NotFound { name, importer }.into())
+                    }
+                    Ok(None) => Err(InjectError::RemoteIo(err)),
+                    Err(err) => Err(MissingDependencyError::DiagnosisFailed(err).into()),
+                }
+            }
+            result => result,
+        }
+    }
+
+    fn inject_from_resolved_path(
+        &self,
+        payload_path: &Path,
+        options: &InjectOptions,
+    ) -> Result<BorrowedProcessModule<'_>, InjectError> {
+        if !options.copy_to_temp() {
+            return self.inject_from_path(payload_path);
+        }
+
+        let staged_path = options.stage_payload(payload_path, self.process())?;
+        match self.inject_from_path(&staged_path) {
+            Ok(module) => {
+                self.staged_payloads
+                    .borrow_mut()
+                    .insert(module.handle() as usize, staged_path);
+                Ok(module)
+            }
+            Err(err) => {
+                let _ = remove_staged_payload(&staged_path);
+                Err(err)
+            }
+        }
+    }
+
+    fn inject_from_path(
+        &self,
+        module_path: &Path,
+    ) -> Result<BorrowedProcessModule<'_>, InjectError> {
+        if self.process().is_current() {
+            return self.inject_locally(module_path);
+        }
+        self.require_access(REMOTE_CODE_ACCESS)?;
+
+        // wine has neither protected processes nor mitigation policies.
+        if !self.wine_compatibility {
+            if let Some(level) = self.process().protection_level()? {
+                return Err(InjectError::ProtectedProcess { level });
+            }
+            check_mitigation_policies(&self.process().mitigation_policies()?, module_path)?;
+        }
+
         let load_library_w = self.load_library_w_stub.get_or_try_init(|| {
-            let inject_data = self
-                .inject_help_data
-                .get_or_try_init(|| Self::load_inject_help_data_for_process(self.process()))?;
+            let inject_data = self.inject_help_data()?;
             LoadLibraryWStub::build(inject_data, &self.remote_allocator)
         })?;
 
-        let module_path = payload_path.as_ref().absolutize()?;
+        // the remote LoadLibraryW may see a different System32 than we do.
+        let remote_module_path = self.process().translate_path(module_path)?;
+
+        let transaction = InjectTransaction::begin(self, load_library_w, &remote_module_path)?;
+        let injected_module_handle = transaction.finish(if is_long_path(&remote_module_path) {
+            match self.load_module(
+                load_library_w,
+                &to_extended_length_path(&remote_module_path),
+            ) {
+                // the target process is not long path aware, fall back to the short name of the payload if there is one.
+                Err(InjectError::RemoteIo(io))
+                    if io.raw_os_error() == Some(ERROR_FILENAME_EXCED_RANGE as i32) =>
+                {
+                    match short_path_name(module_path) {
+                        Ok(short_path) if !is_long_path(&short_path) => self.load_module(
+                            load_library_w,
+                            &self.process().translate_path(short_path)?,
+                        ),
+                        _ => Err(InjectError::PayloadPathTooLong),
+                    }
+                }
+                result => result,
+            }
+        } else {
+            self.load_module(load_library_w, &remote_module_path)
+        })?;
+
+        // the stub returns the full handle, so it does not need to be matched against the modules of the target,
+        // which could already have been unloaded again by the target.
+        Ok(unsafe { ProcessModule::new_unchecked(injected_module_handle, self.process()) })
+    }
+
+    /// Loads the module from the given path into the current process, which needs neither a stub nor a remote thread.
+    fn inject_locally(&self, module_path: &Path) -> Result<BorrowedProcessModule<'_>, InjectError> {
+        let module_path = if is_long_path(module_path) {
+            to_extended_length_path(module_path)
+        } else {
+            module_path.to_path_buf()
+        };
+        let wide_module_path = U16CString::from_os_str(module_path.as_os_str())?;
+        let handle = unsafe { LoadLibraryW(wide_module_path.as_ptr()) };
+        if handle.is_null() {
+            // classified like a failure of the stub, so e.g. missing dependencies are diagnosed in the same way.
+            return Err(self.classify_load_error(InjectError::RemoteIo(io::Error::last_os_error())));
+        }
+        trace_event!(debug, module = ?handle, "loaded payload into the current process");
+        Ok(unsafe { ProcessModule::new_unchecked(handle, self.process()) })
+    }
+
+    fn load_module(
+        &self,
+        load_library_w: &LoadLibraryWStub,
+        remote_module_path: &Path,
+    ) -> Result<ModuleHandle, InjectError> {
         let wide_module_path =
-            U16CString::from_os_str(module_path.as_os_str())?.into_vec_with_nul();
+            U16CString::from_os_str(remote_module_path.as_os_str())?.into_vec_with_nul();
         let remote_wide_module_path = self
             .remote_allocator
             .alloc_and_copy_buf(wide_module_path.as_slice())?;
 
-        let injected_module_handle = load_library_w
-            .call(remote_wide_module_path.as_raw_ptr().cast())
-            .map_err(|e| match e {
-                InjectError::RemoteIo(io) if io.raw_os_error() == Some(193) => {
-                    InjectError::ArchitectureMismatch
-                }
-                _ => e,
-            })?;
-
-        let injected_module =
-            unsafe { ProcessModule::new_unchecked(injected_module_handle, self.process()) };
-
-        debug_assert_eq!(
-            Some(injected_module),
-            self.process().find_module_by_path(module_path)?
-        );
+        let start = Instant::now();
+        load_library_w
+            .call(
+                remote_wide_module_path.as_raw_ptr().cast(),
+                &self.remote_thread_options,
+            )
+            .inspect_err(|e| {
+                self.emit_remote_call_failed(Operation::Inject, e, start);
+            })
+            .map_err(|e| self.classify_load_error(e))
+    }
 
-        Ok(injected_module)
+    /// Maps the error codes of `LoadLibraryW` that have a more specific meaning to their variants.
+    fn classify_load_error(&self, err: InjectError) -> InjectError {
+        match err {
+            InjectError::RemoteIo(io) if io.raw_os_error() == Some(193) => {
+                InjectError::ArchitectureMismatch
+            }
+            // a signature mitigation policy rejected the module.
+            InjectError::RemoteIo(io)
+                if io.raw_os_error() == Some(ERROR_INVALID_IMAGE_HASH as i32) =>
+            {
+                InjectError::UnsignedModuleRejected
+            }
+            InjectError::RemoteIo(io)
+                if io.raw_os_error() == Some(ERROR_ACCESS_DENIED as i32)
+                    && self.process().is_app_container().unwrap_or(false) =>
+            {
+                InjectError::AppContainerAccessDenied
+            }
+            _ => err,
+        }
     }
 
     /// Injects the module from the given path into the target process, if it is not already loaded.
@@ -174,28 +945,370 @@ impl Syringe {
     pub fn find_or_inject(
         &self,
         payload_path: impl AsRef<Path>,
-    ) -> Result<BorrowedProcessModule<'_>, InjectError> {
+    ) -> Result<InjectedModule<'_>, InjectError> {
         let payload_path = payload_path.as_ref();
+        self.require_access(MODULE_QUERY_ACCESS)?;
         match self.process().find_module_by_path(payload_path) {
-            Ok(Some(module)) => Ok(module),
+            Ok(Some(module)) => Ok(InjectedModule::new(self, module)),
             Ok(None) => self.inject(payload_path),
             Err(err) => Err(err.into()),
         }
     }
 
+    /// Waits for the module with the given name to be loaded by the target process and injects the module from the given path afterwards.
+    /// This is useful for payloads that depend on a module the target process loads lazily, e.g. a rendering library.
+    ///
+    /// The comparison of module names is case-insensitive and the default library extension `.dll` is appended if the extension is omitted.
+    /// If the module is already loaded, the payload is injected immediately.
+    ///
+    /// # Errors
+    /// Returns [`InjectError::ModuleLoadTimedOut`] if the module was not loaded within the given timeout
+    /// and [`InjectError::ProcessInaccessible`] if the target process exits while waiting.
+    ///
+    /// # Limitations
+    /// See [`inject`](Self::inject).
+    pub fn inject_when_module_loaded(
+        &self,
+        module_name: impl AsRef<Path>,
+        payload_path: impl AsRef<Path>,
+        timeout: Duration,
+    ) -> Result<InjectedModule<'_>, InjectError> {
+        self.wait_for_module_loaded(module_name.as_ref(), timeout)?;
+        self.inject(payload_path)
+    }
+
+    /// Waits for the module with the given name to be loaded by the target process, see [`Syringe::inject_when_module_loaded`].
+    pub(crate) fn wait_for_module_loaded(
+        &self,
+        module_name: &Path,
+        timeout: Duration,
+    ) -> Result<(), InjectError> {
+        const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+        let start = Instant::now();
+        while self.process().find_module_by_name(module_name)?.is_none() {
+            if !self.process().is_alive() {
+                return Err(InjectError::ProcessInaccessible);
+            }
+            if start.elapsed() >= timeout {
+                return Err(InjectError::ModuleLoadTimedOut {
+                    module_name: module_name.to_path_buf(),
+                });
+            }
+            thread::sleep(POLL_INTERVAL.min(timeout.saturating_sub(start.elapsed())));
+        }
+        trace_event!(debug, module = %module_name.display(), waited = ?start.elapsed(), "awaited module loaded");
+        Ok(())
+    }
+
+    /// Copies the given machine code into the target process, runs it on a new remote thread and returns how the thread ended.
+    ///
+    /// The code is placed in read-only executable memory and called as a thread procedure, i.e. as
+    /// `extern "system" fn(parameter: *mut c_void) -> u32`. If a parameter is given, it is copied into the target process
+    /// and a pointer to the copy is passed to the code, otherwise the parameter is null.
+    /// Both copies are freed once the thread has exited.
+    ///
+    /// # Safety
+    /// The code must be valid for the architecture of the target process, must not keep using its memory or the parameter
+    /// after returning and must not corrupt the target process.
+    pub unsafe fn run_shellcode(
+        &self,
+        code: &[u8],
+        parameter: Option<&[u8]>,
+    ) -> Result<RemoteThreadResult, io::Error> {
+        if code.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "shellcode must not be empty",
+            ));
+        }
+        // an exited target is not checked for upfront, so the error of the failed operation is reported as is.
+        self.require_access(PROCESS_CREATE_THREAD | PROCESS_VM_OPERATION | PROCESS_VM_WRITE)
+            .map_err(|err| io::Error::new(io::ErrorKind::PermissionDenied, err))?;
+
+        let code_buffer = ProcessMemoryBuffer::allocate_data(self.process(), code.len())?;
+        code_buffer.write(0, code)?;
+        let code_memory = code_buffer.as_slice();
+        let _protection = unsafe { code_memory.protect(MemoryProtection(PAGE_EXECUTE_READ)) }?;
+        code_memory.flush_instruction_cache()?;
+
+        let parameter_buffer = match parameter {
+            Some(parameter) if !parameter.is_empty() => {
+                let buffer = ProcessMemoryBuffer::allocate_data(self.process(), parameter.len())?;
+                buffer.write(0, parameter)?;
+                Some(buffer)
+            }
+            _ => None,
+        };
+        let parameter_ptr = parameter_buffer
+            .as_ref()
+            .map_or(ptr::null_mut(), |buffer| buffer.as_ptr());
+
+        trace_span!(DEBUG, "run_shellcode", pid = ?self.process().pid().ok(), len = code.len());
+        self.process().run_remote_thread_with_options(
+            unsafe {
+                mem::transmute::<*mut u8, extern "system" fn(*mut u8) -> u32>(code_memory.as_ptr())
+            },
+            parameter_ptr,
+            &self.remote_thread_options,
+        )
+    }
+
+    /// Calls `FreeLibrary` for the given module in the target process, directly if the target is the current process.
+    fn free_library(&self, module: BorrowedProcessModule<'_>) -> Result<(), EjectError> {
+        if self.process().is_current() {
+            if unsafe { FreeLibrary(module.handle()) } == FALSE {
+                return Err(EjectError::RemoteIo(io::Error::last_os_error()));
+            }
+            return Ok(());
+        }
+        let inject_data = self.inject_help_data()?;
+
+        let result = self.remote_allocator.run_remote_thread(
+            unsafe {
+                mem::transmute::<FreeLibraryFn, extern "system" fn(HMODULE) -> u32>(
+                    inject_data.get_free_library_fn_ptr(),
+                )
+            },
+            module.handle(),
+            &self.remote_thread_options,
+        )?;
+        trace_event!(debug, result = ?result, "FreeLibrary returned");
+
+        if result.into_result()? as BOOL == FALSE {
+            return Err(EjectError::RemoteIo(io::Error::new(
+                io::ErrorKind::Other,
+                "failed to eject module from process",
+            )));
+        }
+        Ok(())
+    }
+
+    /// Asks the given module to unload itself from a new thread using the export defined by the `payload_self_unload!` macro.
+    fn self_unload(&self, module: BorrowedProcessModule<'_>) -> Result<(), EjectError> {
+        let Some(self_unload) =
+            module.get_procedure_address_from_exports(SELF_UNLOAD_EXPORT_NAME)?
+        else {
+            return Err(EjectError::SelfUnloadUnsupported);
+        };
+
+        // the worker thread must not be used, as the procedure exits the thread it runs on.
+        let result = self.process().run_remote_thread_with_options(
+            unsafe {
+                mem::transmute::<RawFunctionPtr, extern "system" fn(*mut u8) -> u32>(self_unload)
+            },
+            ptr::null_mut(),
+            &self.remote_thread_options,
+        )?;
+        trace_event!(debug, result = ?result, "self unload returned");
+
+        let exit_code = result.into_result()?;
+        if exit_code != 0 {
+            return Err(EjectError::RemoteIo(io::Error::from_raw_os_error(
+                exit_code as i32,
+            )));
+        }
+        Ok(())
+    }
+
     /// Ejects a module from the target process.
     ///
     /// # Panics
     /// This method panics if the given module was not loaded in the target process.
-    pub fn eject(&self, module: BorrowedProcessModule<'_>) -> Result<(), EjectError> {
+    pub fn eject<'a>(
+        &self,
+        module: impl Into<BorrowedProcessModule<'a>>,
+    ) -> Result<(), EjectError> {
+        self.eject_with_mode(module, EjectMode::FreeLibrary)
+    }
+
+    /// Ejects a module from the target process using the given mode.
+    ///
+    /// # Panics
+    /// This method panics if the given module was not loaded in the target process.
+    pub fn eject_with_mode<'a>(
+        &self,
+        module: impl Into<BorrowedProcessModule<'a>>,
+        mode: EjectMode,
+    ) -> Result<(), EjectError> {
+        let module = module.into();
+        trace_span!(DEBUG, "eject", pid = ?self.process().pid().ok(), module = ?module.handle(), mode = ?mode);
         assert!(
             module.process() == &self.process(),
             "trying to eject a module from a different process"
         );
+        if self.event_listeners.is_empty() {
+            return self.eject_inner(module, mode);
+        }
+
+        self.event_listeners
+            .emit(&SyringeEvent::BeforeEject { module });
+        let start = Instant::now();
+        let result = self.eject_inner(module, mode);
+        self.event_listeners.emit(&SyringeEvent::AfterEject {
+            module,
+            result: result.as_ref().copied(),
+            elapsed: start.elapsed(),
+        });
+        result
+    }
+
+    /// Ejects the module loaded from the given path from the target process, releasing all of its references.
+    ///
+    /// The path is matched in the same way as by [`Process::find_module_by_path`].
+    /// As a module that was loaded multiple times is only unloaded once all of its references are released,
+    /// `FreeLibrary` is called until the module is unloaded.
+    /// Returns `false` if no module with the given path is loaded.
+    ///
+    /// # Errors
+    /// Returns [`EjectError::ModulePinned`] if the module is still loaded after releasing a large number of references.
+    pub fn eject_by_path(&self, module_path: impl AsRef<Path>) -> Result<bool, EjectError> {
+        let Some(module) = self.process().find_module_by_path(module_path)? else {
+            return Ok(false);
+        };
+        self.eject_all_references(module)?;
+        Ok(true)
+    }
+
+    /// Ejects all copies of the given payload from the target process, e.g. ones left behind by previous runs of the injector,
+    /// releasing all of their references. Returns the number of ejected modules.
+    ///
+    /// If the payload file embeds a payload marker (see `payload_marker!`), a module is considered a copy if it is loaded from
+    /// the given path or embeds a marker with the same id, regardless of its file name.
+    /// Otherwise, a module is considered a copy if it is loaded from the given path (matched in the same way as by
+    /// [`Process::find_module_by_path`]) or is a copy of it staged with a randomized name
+    /// (see [`InjectOptions::with_randomized_name`]). If only a file name is given, modules with the same file name,
+    /// ignoring case, are considered copies as well.
+    ///
+    /// # Note
+    /// Copies injected by this syringe are ejected as well.
+    ///
+    /// # Errors
+    /// Returns [`EjectError::ModulePinned`] if a copy is still loaded after releasing a large number of references.
+    pub fn eject_stale(&self, payload_name_or_path: impl AsRef<Path>) -> Result<usize, EjectError> {
+        let payload_path = payload_name_or_path.as_ref();
+        let Some(payload_name) = payload_path.file_name() else {
+            return Ok(0);
+        };
+        let payload_name = payload_name.to_string_lossy();
+        let is_bare_name = payload_path
+            .parent()
+            .is_none_or(|parent| parent.as_os_str().is_empty());
+        let marker_id = if is_bare_name {
+            None
+        } else {
+            match with_image_file(payload_path, read_marker_from_image) {
+                Ok(marker_id) => marker_id,
+                // a payload that no longer exists can still be matched by its path.
+                Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+                Err(err) => return Err(EjectError::Io(err)),
+            }
+        };
+        let path_matcher = ModulePathMatcher::new(payload_path);
+
+        self.eject_matching(|module| {
+            if path_matcher.matches(&module.path()?) {
+                return Ok(true);
+            }
+            if let Some(marker_id) = &marker_id {
+                return Ok(read_payload_marker(module)?.as_ref() == Some(marker_id));
+            }
+            let name = module.base_name()?;
+            let name = name.to_string_lossy();
+            Ok(is_staged_copy_name(&payload_name, &name)
+                || is_bare_name && name.eq_ignore_ascii_case(&payload_name))
+        })
+    }
 
-        let inject_data = self
-            .inject_help_data
-            .get_or_try_init(|| Self::load_inject_help_data_for_process(self.process()))?;
+    /// Ejects all modules embedding a payload marker with the given id (see `payload_marker!`) from the target process,
+    /// releasing all of their references. Returns the number of ejected modules.
+    ///
+    /// Unlike [`Syringe::eject_stale`], this finds copies of a payload regardless of their file name.
+    ///
+    /// # Note
+    /// Copies injected by this syringe are ejected as well.
+    ///
+    /// # Errors
+    /// Returns [`EjectError::ModulePinned`] if a copy is still loaded after releasing a large number of references.
+    pub fn eject_stale_by_marker(&self, marker_id: &str) -> Result<usize, EjectError> {
+        self.eject_matching(|module| {
+            Ok(read_payload_marker(module)?.is_some_and(|id| id == marker_id))
+        })
+    }
+
+    fn eject_matching(
+        &self,
+        mut is_match: impl FnMut(BorrowedProcessModule<'_>) -> Result<bool, io::Error>,
+    ) -> Result<usize, EjectError> {
+        let mut matches = Vec::new();
+        for module in self.process().module_iter()? {
+            match is_match(module) {
+                Ok(true) => matches.push(module.handle()),
+                Ok(false) => {}
+                // modules unloaded while iterating cannot be inspected and need no ejecting anyway.
+                Err(_) if !module.guess_is_loaded() => {}
+                Err(err) => {
+                    return Err(self
+                        .error_unless_exited(EjectError::Io(err), EjectError::ProcessInaccessible))
+                }
+            }
+        }
+
+        for &handle in &matches {
+            let module = unsafe { ProcessModule::new_unchecked(handle, self.process()) };
+            trace_event!(info, module = ?handle, "ejecting stale payload");
+            self.eject_all_references(module)?;
+        }
+        Ok(matches.len())
+    }
+
+    /// Calls `FreeLibrary` until the given module is unloaded.
+    fn eject_all_references(&self, module: BorrowedProcessModule<'_>) -> Result<(), EjectError> {
+        const MAX_RELEASED_REFERENCES: usize = 1024;
+
+        for _ in 0..MAX_RELEASED_REFERENCES {
+            self.eject(module)?;
+            if !module.guess_is_loaded() {
+                return Ok(());
+            }
+        }
+        Err(EjectError::ModulePinned)
+    }
+
+    /// Adopts a module that is already loaded in the target process, so it can be managed through the same API as an injected one.
+    /// This is useful to eject a payload left behind by a previous run of the injector or by another tool.
+    ///
+    /// # Panics
+    /// This method panics if the given module is not from the target process.
+    pub fn adopt_module(&self, module: BorrowedProcessModule<'_>) -> InjectedModule<'_> {
+        assert!(
+            module.process() == &self.process(),
+            "trying to adopt a module from a different process"
+        );
+        let module = unsafe { ProcessModule::new_unchecked(module.handle(), self.process()) };
+        InjectedModule::new(self, module)
+    }
+
+    fn eject_inner(
+        &self,
+        module: BorrowedProcessModule<'_>,
+        mode: EjectMode,
+    ) -> Result<(), EjectError> {
+        if self.has_target_exited() {
+            return Err(EjectError::ProcessInaccessible);
+        }
+        self.eject_module(module, mode)
+            .map_err(|err| self.error_unless_exited(err, EjectError::ProcessInaccessible))
+    }
+
+    fn eject_module(
+        &self,
+        module: BorrowedProcessModule<'_>,
+        mode: EjectMode,
+    ) -> Result<(), EjectError> {
+        if !self.process().is_current() {
+            self.require_access(REMOTE_CODE_ACCESS)?;
+        }
 
         if !module.guess_is_loaded() {
             if self.process().is_alive() {
@@ -205,37 +1318,73 @@ impl Syringe {
             }
         }
 
-        let exit_code = self.process().run_remote_thread(
-            unsafe { mem::transmute(inject_data.get_free_library_fn_ptr()) },
-            module.handle(),
-        )?;
-
-        let free_library_result = exit_code as BOOL;
-
-        if free_library_result == FALSE {
-            return Err(EjectError::RemoteIo(io::Error::new(
-                io::ErrorKind::Other,
-                "failed to eject module from process",
-            )));
-        }
-        if let Ok(exception) = ExceptionCode::try_from_primitive(exit_code) {
-            return Err(EjectError::RemoteException(exception));
+        let start = Instant::now();
+        match mode {
+            EjectMode::FreeLibrary => self.free_library(module),
+            EjectMode::SelfUnload => self.self_unload(module),
         }
+        .inspect_err(|e| {
+            self.emit_remote_call_failed(Operation::Eject, e, start);
+        })?;
 
-        debug_assert!(
-            !self
-                .remote_allocator
-                .process()
-                .module_handles()?
-                .any(|m| m == module.handle()),
-            "ejected module survived"
-        );
+        // a module injected multiple times is only unloaded once all references are released.
+        if !module.guess_is_loaded() {
+            #[cfg(feature = "rpc-core")]
+            self.procedure_cache.remove_module(module.handle());
+
+            let staged_path = self
+                .staged_payloads
+                .borrow_mut()
+                .remove(&(module.handle() as usize));
+            if let Some(staged_path) = staged_path {
+                remove_staged_payload(&staged_path)?;
+            }
+        }
 
         Ok(())
     }
 
+    pub(crate) fn inject_help_data(&self) -> Result<&InjectHelpData, LoadInjectHelpDataError> {
+        self.inject_help_data.get_or_try_init(|| {
+            Self::load_inject_help_data_for_process(
+                self.process(),
+                &self.module_retry_policy,
+                self.wine_compatibility,
+            )
+        })
+    }
+
+    /// Reuses the inject help data the given syringe has already loaded if its target has the same `kernel32.dll` mapped at the same address.
+    /// Returns whether the data was reused.
+    pub(crate) fn share_inject_help_data_from(&self, other: &Syringe) -> Result<bool, io::Error> {
+        let Some(data) = other.inject_help_data.get() else {
+            return Ok(false);
+        };
+        if self.inject_help_data.get().is_some()
+            || self.remote_allocator.is_x86()? != other.remote_allocator.is_x86()?
+        {
+            return Ok(false);
+        }
+        match self.process().find_module_by_name("kernel32.dll")? {
+            Some(kernel32) if kernel32.handle() == data.kernel32_module => {
+                Ok(self.inject_help_data.set(data.clone()).is_ok())
+            }
+            _ => Ok(false),
+        }
+    }
+
     pub(crate) fn load_inject_help_data_for_process(
         process: BorrowedProcess<'_>,
+        #[cfg_attr(
+            not(all(target_arch = "x86_64", feature = "into-x86-from-x64")),
+            allow(unused_variables)
+        )]
+        retry_policy: &RetryPolicy,
+        #[cfg_attr(
+            not(all(target_arch = "x86_64", feature = "into-x86-from-x64")),
+            allow(unused_variables)
+        )]
+        prefer_loaded_exports: bool,
     ) -> Result<InjectHelpData, LoadInjectHelpDataError> {
         let is_target_x64 = process.is_x64()?;
         let is_self_x64 = cfg!(target_arch = "x86_64");
@@ -243,53 +1392,41 @@ impl Syringe {
         match (is_target_x64, is_self_x64) {
             (true, true) | (false, false) => Self::load_inject_help_data_for_current_target(),
             #[cfg(all(target_arch = "x86_64", feature = "into-x86-from-x64"))]
-            (false, true) => Self::_load_inject_help_data_for_process(process),
+            (false, true) => Self::_load_inject_help_data_for_process(
+                process,
+                retry_policy,
+                prefer_loaded_exports,
+            ),
             _ => Err(LoadInjectHelpDataError::UnsupportedTarget),
         }
     }
 
-    #[allow(dead_code)]
-    pub(crate) fn remote_exit_code_to_exception(exit_code: u32) -> Result<u32, ExceptionCode> {
-        if exit_code == 0 {
-            return Ok(exit_code);
-        }
-
-        match ExceptionCode::try_from_primitive(exit_code) {
-            Ok(exception) => Err(exception),
-            Err(_) => Ok(exit_code),
-        }
-    }
-
-    pub(crate) fn remote_exit_code_to_error_or_exception(
-        exit_code: u32,
-    ) -> Result<(), ExceptionOrIoError> {
-        if exit_code == 0 {
-            return Ok(());
-        }
-
-        match ExceptionCode::try_from_primitive(exit_code) {
-            Ok(exception) => Err(ExceptionOrIoError::Exception(exception)),
-            Err(_) => Err(ExceptionOrIoError::Io(io::Error::from_raw_os_error(
-                exit_code as _,
-            ))),
-        }
-    }
-
     fn load_inject_help_data_for_current_target() -> Result<InjectHelpData, LoadInjectHelpDataError>
     {
         let kernel32_module =
             BorrowedProcessModule::find_local_by_name_or_abs_path_wstr(u16cstr!("kernel32.dll"))?
                 .unwrap();
 
-        let load_library_fn_ptr =
-            kernel32_module.get_local_procedure_address_cstr(cstr!("LoadLibraryW"))?;
-        let free_library_fn_ptr =
-            kernel32_module.get_local_procedure_address_cstr(cstr!("FreeLibrary"))?;
-        let get_last_error_fn_ptr =
-            kernel32_module.get_local_procedure_address_cstr(cstr!("GetLastError"))?;
+        let find_export = |name: &'static CStr| {
+            kernel32_module
+                .get_local_procedure_address_cstr(name)
+                .map_err(|err| {
+                    if err.raw_os_error() == Some(ERROR_PROC_NOT_FOUND as i32) {
+                        LoadInjectHelpDataError::MissingExport(MissingExportError::kernel32(
+                            name.to_str().unwrap_or_default(),
+                        ))
+                    } else {
+                        err.into()
+                    }
+                })
+        };
+        let load_library_fn_ptr = find_export(cstr!("LoadLibraryW"))?;
+        let free_library_fn_ptr = find_export(cstr!("FreeLibrary"))?;
+        let get_last_error_fn_ptr = find_export(cstr!("GetLastError"))?;
+        let wait_for_single_object_fn_ptr = find_export(cstr!("WaitForSingleObject"))?;
+        let set_event_fn_ptr = find_export(cstr!("SetEvent"))?;
         #[cfg(feature = "rpc-core")]
-        let get_proc_address_fn_ptr =
-            kernel32_module.get_local_procedure_address_cstr(cstr!("GetProcAddress"))?;
+        let get_proc_address_fn_ptr = find_export(cstr!("GetProcAddress"))?;
 
         Ok(InjectHelpData {
             kernel32_module: kernel32_module.handle(),
@@ -297,6 +1434,9 @@ impl Syringe {
             free_library_offset: free_library_fn_ptr as usize - kernel32_module.handle() as usize,
             get_last_error_offset: get_last_error_fn_ptr as usize
                 - kernel32_module.handle() as usize,
+            wait_for_single_object_offset: wait_for_single_object_fn_ptr as usize
+                - kernel32_module.handle() as usize,
+            set_event_offset: set_event_fn_ptr as usize - kernel32_module.handle() as usize,
             #[cfg(feature = "rpc-core")]
             get_proc_address_offset: get_proc_address_fn_ptr as usize
                 - kernel32_module.handle() as usize,
@@ -307,12 +1447,44 @@ impl Syringe {
     #[cfg(feature = "into-x86-from-x64")]
     fn _load_inject_help_data_for_process(
         process: BorrowedProcess<'_>,
+        retry_policy: &RetryPolicy,
+        prefer_loaded_exports: bool,
     ) -> Result<InjectHelpData, LoadInjectHelpDataError> {
         // get kernel32 handle of target process (may fail if target process is currently starting and has not loaded kernel32 yet)
-        let kernel32_module = process
-            .wait_for_module_by_name("kernel32.dll", Duration::from_secs(1))?
-            .unwrap();
+        let kernel32_module = retry_faillable_until_some_with_policy(
+            || process.find_module_by_name("kernel32.dll"),
+            retry_policy,
+        )?
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "kernel32.dll is not loaded in the target process",
+            )
+        })?;
 
+        if prefer_loaded_exports {
+            return match Self::load_inject_help_data_from_memory(kernel32_module) {
+                Ok(inject_data) => Ok(inject_data),
+                Err(memory_err) => Self::load_inject_help_data_from_file(process, kernel32_module)
+                    .map_err(|_| memory_err),
+            };
+        }
+
+        // the file on disk may differ from the loaded module (e.g. if it was patched or updated in the meantime),
+        // so fall back to the export table mapped into the target process.
+        match Self::load_inject_help_data_from_file(process, kernel32_module) {
+            Ok(inject_data) => Ok(inject_data),
+            Err(file_err) => {
+                Self::load_inject_help_data_from_memory(kernel32_module).map_err(|_| file_err)
+            }
+        }
+    }
+
+    #[cfg(all(target_arch = "x86_64", feature = "into-x86-from-x64"))]
+    fn load_inject_help_data_from_file(
+        process: BorrowedProcess<'_>,
+        kernel32_module: BorrowedProcessModule<'_>,
+    ) -> Result<InjectHelpData, LoadInjectHelpDataError> {
         // get path of kernel32 used in target process
         let kernel32_path = if process.is_x86()? {
             // We need to manually construct the path to the kernel32.dll used in WOW64 processes.
@@ -323,41 +1495,75 @@ impl Syringe {
             kernel32_module.path()?
         };
 
+        // the offsets only depend on the kernel32 file, so they are shared by all syringes in this process.
+        let cache_key = ModuleVersionInfo::read_from_file(&kernel32_path)
+            .ok()
+            .flatten()
+            .and_then(|info| info.file_version())
+            .map(|version| (kernel32_path.clone(), version));
+        if let Some(cache_key) = &cache_key {
+            // the cache only holds complete entries, so it is still usable if another thread panicked while holding the lock.
+            let cache = KERNEL32_OFFSET_CACHE
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if let Some(cached) = cache.as_ref().and_then(|cache| cache.get(cache_key)) {
+                return Ok(InjectHelpData {
+                    kernel32_module: kernel32_module.handle(),
+                    ..cached.clone()
+                });
+            }
+        }
+
         // load the dll as a pe and extract the fn offsets
-        let module_file_buffer = fs::read(kernel32_path)?;
+        let module_file_buffer = fs::read(&kernel32_path)?;
         let pe = PE::parse(&module_file_buffer)?;
-        let load_library_export = pe
-            .exports
-            .iter()
-            .find(|export| matches!(export.name, Some("LoadLibraryW")))
-            .unwrap();
-
-        let free_library_export = pe
-            .exports
-            .iter()
-            .find(|export| matches!(export.name, Some("FreeLibrary")))
-            .unwrap();
+        let inject_data = Self::inject_help_data_from_exports(kernel32_module.handle(), |name| {
+            Ok(pe
+                .exports
+                .iter()
+                .find(|export| export.name == Some(name))
+                .map(|export| export.rva))
+        })?;
 
-        let get_last_error_export = pe
-            .exports
-            .iter()
-            .find(|export| matches!(export.name, Some("GetLastError")))
-            .unwrap();
+        if let Some(cache_key) = cache_key {
+            KERNEL32_OFFSET_CACHE
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get_or_insert_with(HashMap::new)
+                .insert(cache_key, inject_data.clone());
+        }
+        Ok(inject_data)
+    }
 
-        #[cfg(feature = "rpc-core")]
-        let get_proc_address_export = pe
-            .exports
-            .iter()
-            .find(|export| matches!(export.name, Some("GetProcAddress")))
-            .unwrap();
+    #[cfg(all(target_arch = "x86_64", feature = "into-x86-from-x64"))]
+    fn load_inject_help_data_from_memory(
+        kernel32_module: BorrowedProcessModule<'_>,
+    ) -> Result<InjectHelpData, LoadInjectHelpDataError> {
+        let image = RemoteImage::new(kernel32_module)?;
+        Self::inject_help_data_from_exports(kernel32_module.handle(), |name| {
+            Ok(image.export_rva(name)?)
+        })
+    }
 
+    #[cfg(all(target_arch = "x86_64", feature = "into-x86-from-x64"))]
+    fn inject_help_data_from_exports(
+        kernel32_module: ModuleHandle,
+        mut export_rva: impl FnMut(&'static str) -> Result<Option<usize>, LoadInjectHelpDataError>,
+    ) -> Result<InjectHelpData, LoadInjectHelpDataError> {
+        let mut find_export = |name| {
+            export_rva(name)?.ok_or(LoadInjectHelpDataError::MissingExport(
+                MissingExportError::kernel32(name),
+            ))
+        };
         Ok(InjectHelpData {
-            kernel32_module: kernel32_module.handle(),
-            load_library_offset: load_library_export.rva,
-            free_library_offset: free_library_export.rva,
-            get_last_error_offset: get_last_error_export.rva,
+            kernel32_module,
+            load_library_offset: find_export("LoadLibraryW")?,
+            free_library_offset: find_export("FreeLibrary")?,
+            get_last_error_offset: find_export("GetLastError")?,
+            wait_for_single_object_offset: find_export("WaitForSingleObject")?,
+            set_event_offset: find_export("SetEvent")?,
             #[cfg(feature = "rpc-core")]
-            get_proc_address_offset: get_proc_address_export.rva,
+            get_proc_address_offset: find_export("GetProcAddress")?,
         })
     }
 
@@ -376,10 +1582,199 @@ impl Syringe {
     }
 }
 
+impl Drop for Syringe {
+    fn drop(&mut self) {
+        // the stubs of the syringe are freed along with it, only other allocations can outlive it.
+        drop(self.load_library_w_stub.take());
+        drop(self.context_thread_stub.take());
+        #[cfg(feature = "rpc-core")]
+        drop(self.get_proc_address_stub.take());
+        #[cfg(all(feature = "rpc-core", feature = "assembler"))]
+        drop(self.get_procs_stub.take());
+        if cfg!(debug_assertions) {
+            self.warn_about_remaining_resources();
+        }
+    }
+}
+
+/// Opens the process with the given id for injection, classifying the common reasons for failure.
+fn open_target_process(pid: u32) -> Result<OwnedProcess, OpenProcessError> {
+    // hardened processes may deny the rights required for injection while still allowing more limited access,
+    // which is enough for some operations of the syringe.
+    let mut result = OwnedProcess::from_pid(pid);
+    for access in [
+        PROCESS_MEMORY_ACCESS,
+        PROCESS_MEMORY_READ_ACCESS,
+        PROCESS_QUERY_ACCESS,
+    ] {
+        match result {
+            Err(ref err) if err.raw_os_error() == Some(ERROR_ACCESS_DENIED as i32) => {
+                result = OwnedProcess::from_pid_with_access(pid, access);
+            }
+            _ => break,
+        }
+    }
+    match result {
+        // the process object lives on while handles to it are open, so an exited process can still be opened.
+        Ok(process) if !process.is_alive() => Err(OpenProcessError::Exited { pid }),
+        Ok(process) => Ok(process),
+        Err(err) if err.raw_os_error() == Some(ERROR_INVALID_PARAMETER as i32) => {
+            Err(OpenProcessError::NotFound { pid })
+        }
+        Err(err) if err.raw_os_error() == Some(ERROR_ACCESS_DENIED as i32) => {
+            Err(OpenProcessError::AccessDenied {
+                pid,
+                requires_elevation: !BorrowedProcess::current().is_elevated().unwrap_or(true),
+            })
+        }
+        Err(err) => Err(OpenProcessError::Io(err)),
+    }
+}
+
+/// Checks whether the module with the given file name is a copy of the payload with the given file name staged with a
+/// randomized name, i.e. whether the module name is the payload name with a randomized suffix added while staging.
+fn is_staged_copy_name(payload_name: &str, module_name: &str) -> bool {
+    let (payload_stem, payload_ext) = split_file_name(payload_name);
+    let (module_stem, module_ext) = split_file_name(module_name);
+    if !module_ext.eq_ignore_ascii_case(payload_ext) {
+        return false;
+    }
+    let Some((stem, suffix)) = module_stem.rsplit_once('-') else {
+        return false;
+    };
+    stem.eq_ignore_ascii_case(payload_stem)
+        && suffix.len() == 16
+        && suffix.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Splits the given file name into its stem and extension, which is empty if there is none.
+fn split_file_name(file_name: &str) -> (&str, &str) {
+    match file_name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, ext),
+        _ => (file_name, ""),
+    }
+}
+
+/// Reads the id of the payload marker embedded into the given module, if any.
+fn read_payload_marker(module: BorrowedProcessModule<'_>) -> Result<Option<String>, io::Error> {
+    read_marker_from_image(RemoteImage::new(module)?)
+}
+
+/// Reads the id of the payload marker embedded into the given image, if any.
+fn read_marker_from_image(image: RemoteImage<'_>) -> Result<Option<String>, io::Error> {
+    let Some(rva) = image.export_rva(PAYLOAD_MARKER_EXPORT_NAME)? else {
+        return Ok(None);
+    };
+    let marker = unsafe {
+        image
+            .memory(rva, mem::size_of::<PayloadMarkerData>())
+            .read_struct::<PayloadMarkerData>(0)?
+    };
+    let len = (marker.len as usize).min(marker.id.len());
+    Ok(Some(
+        String::from_utf8_lossy(&marker.id[..len]).into_owned(),
+    ))
+}
+
 #[derive(Debug)]
-struct LoadLibraryWStub {
+pub(crate) struct LoadLibraryWStub {
     code: RemoteAllocation,
-    result: RemoteBox<ModuleHandle>,
+    result: RemoteResultBuf,
+}
+
+/// How long a rolled back injection waits for a `LoadLibraryW` call that timed out to return.
+const ROLLBACK_WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An injection of a module into a remote process, which releases the reference to the module again if the injection fails
+/// after the module was (partially) loaded, e.g. because waiting for the remote thread timed out or its result could not be read.
+/// Otherwise the payload would stay loaded without a handle being returned to the caller.
+///
+/// The remote allocations of the injection are freed when they are dropped, so they do not need to be tracked here.
+struct InjectTransaction<'a> {
+    syringe: &'a Syringe,
+    load_library_w: &'a LoadLibraryWStub,
+    remote_module_path: &'a Path,
+    // a module that was loaded before the injection is only referenced again, so it must not be unloaded.
+    was_loaded: bool,
+}
+
+impl<'a> InjectTransaction<'a> {
+    fn begin(
+        syringe: &'a Syringe,
+        load_library_w: &'a LoadLibraryWStub,
+        remote_module_path: &'a Path,
+    ) -> Result<Self, InjectError> {
+        let was_loaded = syringe
+            .process()
+            .find_module_by_path(remote_module_path)?
+            .is_some();
+        // a result left by an earlier injection must not be taken for one of this injection when rolling back.
+        load_library_w.result.reset()?;
+        Ok(Self {
+            syringe,
+            load_library_w,
+            remote_module_path,
+            was_loaded,
+        })
+    }
+
+    fn finish(
+        self,
+        result: Result<ModuleHandle, InjectError>,
+    ) -> Result<ModuleHandle, InjectError> {
+        if result.is_err() && self.syringe.process().is_alive() {
+            self.roll_back();
+        }
+        result
+    }
+
+    fn roll_back(&self) {
+        // the original error is more useful than a failure to roll back, so failures are only traced.
+        // a call on the worker thread that timed out may still be loading the module, which must not be unloaded meanwhile.
+        match self
+            .syringe
+            .remote_allocator
+            .wait_for_worker(Some(ROLLBACK_WAIT_TIMEOUT))
+        {
+            Ok(true) => {}
+            Ok(false) => {
+                trace_event!(warn, path = %self.remote_module_path.display(), "LoadLibraryW of failed injection did not return, not rolling back");
+                return;
+            }
+            Err(_err) => {
+                trace_event!(debug, error = %_err, "failed to wait for LoadLibraryW of failed injection");
+                return;
+            }
+        }
+
+        // a call that returned took exactly one reference to the module, which is released again even if it was loaded before.
+        let module = match self.load_library_w.loaded_module() {
+            Ok(Some(handle)) => unsafe {
+                ProcessModule::new_unchecked(handle, self.syringe.process())
+            },
+            // the call did not complete, so a half-loaded module can only be left behind if it was not loaded before.
+            _ if self.was_loaded => return,
+            _ => match self
+                .syringe
+                .process()
+                .find_module_by_path(self.remote_module_path)
+            {
+                Ok(Some(module)) => module,
+                Ok(None) => return,
+                Err(_err) => {
+                    trace_event!(debug, error = %_err, "failed to look up module of failed injection");
+                    return;
+                }
+            },
+        };
+        // the timeout that made the injection fail would most likely make releasing the module fail as well.
+        let allocator = &self.syringe.remote_allocator;
+        let timeout = allocator.worker_call_timeout();
+        allocator.set_worker_call_timeout(Some(ROLLBACK_WAIT_TIMEOUT));
+        let _result = self.syringe.eject_module(module, EjectMode::FreeLibrary);
+        allocator.set_worker_call_timeout(timeout);
+        trace_event!(info, module = ?module.handle(), result = ?_result, "released module of failed injection");
+    }
 }
 
 impl LoadLibraryWStub {
@@ -387,74 +1782,101 @@ impl LoadLibraryWStub {
         inject_data: &InjectHelpData,
         remote_allocator: &RemoteBoxAllocator,
     ) -> Result<Self, InjectError> {
-        let result = remote_allocator.alloc_uninit::<ModuleHandle>()?;
+        let result = RemoteResultBuf::for_word(remote_allocator)?;
 
-        let code = if remote_allocator.process().is_x86()? {
-            Self::build_code_x86(
-                inject_data.get_load_library_fn_ptr(),
-                result.as_raw_ptr().cast(),
-                inject_data.get_get_last_error(),
-            )
-            .unwrap()
+        let load_library_w = inject_data.get_load_library_fn_ptr() as usize;
+        let get_last_error = inject_data.get_get_last_error() as usize;
+        let result_address = result.as_raw_ptr() as usize;
+        let code = if remote_allocator.is_x86()? {
+            Self::build_code_x86(load_library_w, result_address, get_last_error).unwrap()
         } else {
-            Self::build_code_x64(
-                inject_data.get_load_library_fn_ptr(),
-                result.as_raw_ptr().cast(),
-                inject_data.get_get_last_error(),
-            )
-            .unwrap()
+            Self::build_code_x64(load_library_w, result_address, get_last_error).unwrap()
         };
-        let code = remote_allocator.alloc_and_copy_buf(code.as_slice())?;
+        trace_event!(trace, bytes = %crate::utils::HexBytes(code.as_slice()), "assembled LoadLibraryW stub");
+        let code = remote_allocator.alloc_and_copy_code(code.as_slice())?;
+        trace_event!(debug, address = ?code.as_ptr(), len = code.len(), "wrote LoadLibraryW stub");
 
         Ok(Self { code, result })
     }
 
-    fn call(&self, remote_wide_module_path: *mut u16) -> Result<ModuleHandle, InjectError> {
-        // creating a thread that will call LoadLibraryW with a pointer to payload_path as argument
-        let exit_code = self.code.process().run_remote_thread(
-            unsafe { mem::transmute(self.code.as_raw_ptr()) },
-            remote_wide_module_path,
-        )?;
+    fn call(
+        &self,
+        remote_wide_module_path: *mut u16,
+        thread_options: &RemoteThreadOptions,
+    ) -> Result<ModuleHandle, InjectError> {
+        // the result is shared between calls, so a stale handle must not survive a call that does not write it.
+        self.result.reset()?;
 
-        Syringe::remote_exit_code_to_error_or_exception(exit_code)?;
+        // creating a thread that will call LoadLibraryW with a pointer to payload_path as argument
+        let exit_code = self
+            .code
+            .allocator()
+            .run_remote_thread(
+                unsafe {
+                    mem::transmute::<*mut u8, extern "system" fn(*mut u16) -> u32>(
+                        self.code.as_raw_ptr(),
+                    )
+                },
+                remote_wide_module_path,
+                thread_options,
+            )?
+            .into_result()?;
+        if exit_code != 0 {
+            return Err(InjectError::RemoteIo(io::Error::from_raw_os_error(
+                exit_code as _,
+            )));
+        }
 
-        let injected_module_handle = self.result.read()?;
-        assert!(!injected_module_handle.is_null());
+        let injected_module_handle =
+            self.result
+                .read_word()?
+                .into_io_result("LoadLibraryW")
+                .map_err(InjectError::RemoteIo)? as usize as ModuleHandle;
 
         Ok(injected_module_handle)
     }
 
+    /// Returns the module loaded by the last call if it returned successfully, even if the call was not waited for.
+    fn loaded_module(&self) -> Result<Option<ModuleHandle>, io::Error> {
+        Ok(match self.result.read_word()? {
+            RemoteResult::Ok(handle) => Some(handle as usize as ModuleHandle),
+            RemoteResult::Err(_) | RemoteResult::Missing => None,
+        })
+    }
+
     #[allow(dead_code)]
     fn process(&self) -> BorrowedProcess<'_> {
         self.code.process()
     }
 
-    #[allow(clippy::fn_to_numeric_cast, clippy::fn_to_numeric_cast_with_truncation)]
-    fn build_code_x86(
-        load_library_w: LoadLibraryWFn,
-        return_buffer: *mut HMODULE,
-        get_last_error: GetLastErrorFn,
+    /// Builds the code of the stub for an x86 target from the addresses of the functions it calls and of its result buffer.
+    #[cfg(feature = "assembler")]
+    pub(crate) fn build_code_x86(
+        load_library_w: usize,
+        result: usize,
+        get_last_error: usize,
     ) -> Result<Vec<u8>, IcedError> {
-        assert!(!return_buffer.is_null());
-        assert_eq!(load_library_w as u32 as usize, load_library_w as usize);
-        assert_eq!(return_buffer as u32 as usize, return_buffer as usize);
-        assert_eq!(get_last_error as u32 as usize, get_last_error as usize);
+        assert_eq!(load_library_w as u32 as usize, load_library_w);
+        assert_eq!(get_last_error as u32 as usize, get_last_error);
 
         let mut asm = CodeAssembler::new(32)?;
+        let mut failed = asm.create_label();
+        let mut done = asm.create_label();
 
         asm.mov(eax, esp + 4)?; // CreateRemoteThread lpParameter
         asm.push(eax)?; // lpLibFileName
         asm.mov(eax, load_library_w as u32)?;
         asm.call(eax)?;
-        asm.mov(dword_ptr(return_buffer as u32), eax)?;
-        // asm.mov(eax, 0)?;
-        let mut label = asm.create_label();
         asm.test(eax, eax)?;
-        asm.mov(eax, 0)?;
-        asm.jnz(label)?;
+        asm.jz(failed)?;
+        RemoteResultBuf::emit_write_x86_to(&mut asm, result, RemoteResultStatus::Ok, 4)?;
+        asm.jmp(done)?;
+        asm.set_label(&mut failed)?;
         asm.mov(eax, get_last_error as u32)?;
-        asm.call(eax)?; // return 0
-        asm.set_label(&mut label)?;
+        asm.call(eax)?;
+        RemoteResultBuf::emit_write_x86_to(&mut asm, result, RemoteResultStatus::Error, 4)?;
+        asm.set_label(&mut done)?;
+        asm.mov(eax, 0)?; // return 0
         asm.ret_1(4)?; // Restore stack ptr. (Callee cleanup)
 
         let code = asm.assemble(0x1234_5678)?;
@@ -467,30 +1889,32 @@ impl LoadLibraryWStub {
         Ok(code)
     }
 
-    #[allow(clippy::fn_to_numeric_cast, clippy::fn_to_numeric_cast_with_truncation)]
-    fn build_code_x64(
-        load_library_w: LoadLibraryWFn,
-        return_buffer: *mut HMODULE,
-        get_last_error: GetLastErrorFn,
+    /// Builds the code of the stub for an x64 target from the addresses of the functions it calls and of its result buffer.
+    #[cfg(feature = "assembler")]
+    pub(crate) fn build_code_x64(
+        load_library_w: usize,
+        result: usize,
+        get_last_error: usize,
     ) -> Result<Vec<u8>, IcedError> {
-        assert!(!return_buffer.is_null());
-
         let mut asm = CodeAssembler::new(64)?;
+        let mut failed = asm.create_label();
+        let mut done = asm.create_label();
 
         asm.sub(rsp, 40)?; // Re-align stack to 16 byte boundary +32 shadow space
 
         // arg already in rcx
         asm.mov(rax, load_library_w as u64)?;
         asm.call(rax)?;
-        asm.mov(dword_ptr(return_buffer as u64), rax)?; // move result to buffer
-
-        let mut label = asm.create_label();
         asm.test(rax, rax)?;
-        asm.mov(rax, 0u64)?;
-        asm.jnz(label)?;
+        asm.jz(failed)?;
+        RemoteResultBuf::emit_write_x64_to(&mut asm, result, RemoteResultStatus::Ok, 8)?;
+        asm.jmp(done)?;
+        asm.set_label(&mut failed)?;
         asm.mov(rax, get_last_error as u64)?;
-        asm.call(rax)?; // return 0
-        asm.set_label(&mut label)?;
+        asm.call(rax)?;
+        RemoteResultBuf::emit_write_x64_to(&mut asm, result, RemoteResultStatus::Error, 4)?;
+        asm.set_label(&mut done)?;
+        asm.mov(rax, 0u64)?; // return 0
 
         asm.add(rsp, 40)?; // Re-align stack to 16 byte boundary + shadow space.
         asm.ret()?; // Restore stack ptr. (Callee cleanup)
@@ -504,4 +1928,136 @@ impl LoadLibraryWStub {
 
         Ok(code)
     }
+
+    #[cfg(not(feature = "assembler"))]
+    pub(crate) fn build_code_x86(
+        load_library_w: usize,
+        result: usize,
+        get_last_error: usize,
+    ) -> Result<Vec<u8>, Infallible> {
+        assert_eq!(load_library_w as u32 as usize, load_library_w);
+        assert_eq!(get_last_error as u32 as usize, get_last_error);
+        assert_eq!(result as u32 as usize, result);
+
+        Ok(stub_templates::load_library_w_x86(
+            load_library_w as u32,
+            result as u32,
+            get_last_error as u32,
+        ))
+    }
+
+    #[cfg(not(feature = "assembler"))]
+    pub(crate) fn build_code_x64(
+        load_library_w: usize,
+        result: usize,
+        get_last_error: usize,
+    ) -> Result<Vec<u8>, Infallible> {
+        Ok(stub_templates::load_library_w_x64(
+            load_library_w as u64,
+            result as u64,
+            get_last_error as u64,
+        ))
+    }
+}
+
+#[cfg(all(test, feature = "assembler"))]
+mod tests {
+    use super::*;
+    use crate::stub_templates;
+    use winapi::um::{errhandlingapi::GetLastError, libloaderapi::LoadLibraryW};
+
+    #[test]
+    #[allow(clippy::fn_to_numeric_cast, clippy::fn_to_numeric_cast_with_truncation)]
+    fn load_library_w_stub_template_matches_the_assembler() {
+        let allocator = RemoteBoxAllocator::new(OwnedProcess::current());
+        let result = RemoteResultBuf::for_word(&allocator).unwrap();
+        let load_library_w: LoadLibraryWFn = LoadLibraryW;
+        let get_last_error: GetLastErrorFn = GetLastError;
+
+        if cfg!(target_pointer_width = "32") {
+            assert_eq!(
+                LoadLibraryWStub::build_code_x86(
+                    load_library_w as usize,
+                    result.as_raw_ptr() as usize,
+                    get_last_error as usize,
+                )
+                .unwrap(),
+                stub_templates::load_library_w_x86(
+                    load_library_w as u32,
+                    result.as_raw_ptr() as u32,
+                    get_last_error as u32
+                )
+            );
+        } else {
+            assert_eq!(
+                LoadLibraryWStub::build_code_x64(
+                    load_library_w as usize,
+                    result.as_raw_ptr() as usize,
+                    get_last_error as usize,
+                )
+                .unwrap(),
+                stub_templates::load_library_w_x64(
+                    load_library_w as u64,
+                    result.as_raw_ptr() as u64,
+                    get_last_error as u64
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn staged_copy_names_match_randomized_suffix() {
+        assert!(is_staged_copy_name(
+            "payload.dll",
+            "payload-0123456789abcdef.dll"
+        ));
+        assert!(is_staged_copy_name(
+            "payload.dll",
+            "PAYLOAD-0123456789ABCDEF.DLL"
+        ));
+        assert!(is_staged_copy_name(
+            "my-payload",
+            "my-payload-0123456789abcdef"
+        ));
+
+        assert!(!is_staged_copy_name("payload.dll", "payload.dll"));
+        assert!(!is_staged_copy_name("payload.dll", "other.dll"));
+        assert!(!is_staged_copy_name("payload.dll", "payload-0123.dll"));
+        assert!(!is_staged_copy_name(
+            "payload.dll",
+            "payload-0123456789abcdef.exe"
+        ));
+        assert!(!is_staged_copy_name(
+            "payload.dll",
+            "payload-0123456789abcdeg.dll"
+        ));
+    }
+
+    #[test]
+    #[cfg(all(target_arch = "x86_64", feature = "into-x86-from-x64"))]
+    fn kernel32_offset_cache_survives_poisoning() {
+        let process = BorrowedProcess::current();
+        let kernel32_module = process
+            .find_module_by_name("kernel32.dll")
+            .unwrap()
+            .unwrap();
+        let uncached = Syringe::load_inject_help_data_from_file(process, kernel32_module).unwrap();
+
+        let _ = std::thread::spawn(|| {
+            let _cache = KERNEL32_OFFSET_CACHE.lock().unwrap();
+            panic!("poisoning the cache");
+        })
+        .join();
+        assert!(KERNEL32_OFFSET_CACHE.is_poisoned());
+
+        let cached = Syringe::load_inject_help_data_from_file(process, kernel32_module).unwrap();
+        assert_eq!(
+            cached.get_load_library_fn_ptr() as usize,
+            uncached.get_load_library_fn_ptr() as usize
+        );
+        assert_eq!(
+            cached.get_free_library_fn_ptr() as usize,
+            uncached.get_free_library_fn_ptr() as usize
+        );
+    }
 }
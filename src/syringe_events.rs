@@ -0,0 +1,88 @@
+use std::{error::Error, fmt, path::Path, time::Duration};
+
+use crate::{
+    error::{EjectError, InjectError, Operation},
+    process::{BorrowedProcess, BorrowedProcessModule},
+};
+
+/// An event in the lifecycle of an operation performed by a [`Syringe`](crate::Syringe).
+///
+/// Listeners for these events can be registered using [`Syringe::add_event_listener`](crate::Syringe::add_event_listener).
+#[derive(Debug)]
+#[non_exhaustive]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub enum SyringeEvent<'a> {
+    /// A payload is about to be injected.
+    BeforeInject {
+        /// The target process.
+        process: BorrowedProcess<'a>,
+        /// The payload path as passed to the syringe.
+        payload_path: &'a Path,
+    },
+    /// An injection attempt has finished.
+    AfterInject {
+        /// The target process.
+        process: BorrowedProcess<'a>,
+        /// The payload path as passed to the syringe.
+        payload_path: &'a Path,
+        /// The injected module or the error that caused the injection to fail.
+        result: Result<BorrowedProcessModule<'a>, &'a InjectError>,
+        /// The time taken by the whole injection.
+        elapsed: Duration,
+    },
+    /// A module is about to be ejected.
+    BeforeEject {
+        /// The module to eject.
+        module: BorrowedProcessModule<'a>,
+    },
+    /// An ejection attempt has finished.
+    AfterEject {
+        /// The module that was ejected.
+        module: BorrowedProcessModule<'a>,
+        /// The error that caused the ejection to fail, if any.
+        result: Result<(), &'a EjectError>,
+        /// The time taken by the whole ejection.
+        elapsed: Duration,
+    },
+    /// A call executed in the target process failed.
+    RemoteCallFailed {
+        /// The target process.
+        process: BorrowedProcess<'a>,
+        /// The operation the call was part of.
+        operation: Operation,
+        /// The error returned by the call.
+        error: &'a (dyn Error + 'static),
+        /// The time taken by the remote call.
+        elapsed: Duration,
+    },
+}
+
+type EventListener = Box<dyn Fn(&SyringeEvent<'_>) + Send>;
+
+/// The event listeners registered on a syringe.
+#[derive(Default)]
+pub(crate) struct EventListeners(Vec<EventListener>);
+
+impl EventListeners {
+    pub fn add(&mut self, listener: impl Fn(&SyringeEvent<'_>) + Send + 'static) {
+        self.0.push(Box::new(listener));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn emit(&self, event: &SyringeEvent<'_>) {
+        for listener in &self.0 {
+            listener(event);
+        }
+    }
+}
+
+impl fmt::Debug for EventListeners {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventListeners")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
@@ -0,0 +1,284 @@
+use std::{io, path::Path};
+
+use crate::{
+    error::{EjectError, InjectError},
+    process::{OwnedProcess, Process, ProcessIter, ProcessSelector},
+    InjectOptions, InjectedModule, Syringe,
+};
+
+#[cfg(feature = "rpc-payload")]
+use crate::{
+    error::{LoadProcedureError, SyringeOperationError},
+    rpc::{PayloadRpcError, PayloadRpcFunctionPtr, RemotePayloadProcedure},
+};
+
+/// The result of an operation performed on one of the targets of a [`SyringeSet`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub struct TargetResult<T> {
+    pid: u32,
+    value: T,
+}
+
+impl<T> TargetResult<T> {
+    /// Returns the id of the target process.
+    #[must_use]
+    pub const fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Returns the result of the operation for the target.
+    #[must_use]
+    pub const fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the result of the operation for the target, consuming this result.
+    #[must_use]
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
+
+/// A set of [`Syringe`]s for multiple target processes, e.g. all instances of a multi-instance application.
+///
+/// The syringes share the data located in `kernel32.dll` that is needed for injection, so it is only located once per architecture,
+/// and bulk operations return a [`TargetResult`] for every target in the order the targets were added.
+/// Operations are performed on the targets one after another on the current thread, see [`Syringe::inject_into_all`] for concurrent injection.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::{process::ProcessSelector, SyringeSet};
+///
+/// let targets = SyringeSet::from_selector(&ProcessSelector::glob("game*.exe"));
+/// for result in targets.inject_all("injection_payload.dll") {
+///     if let Err(err) = result.value() {
+///         eprintln!("failed to inject into {}: {err}", result.pid());
+///     }
+/// }
+/// ```
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "syringe")))]
+pub struct SyringeSet {
+    targets: Vec<(u32, Syringe)>,
+}
+
+impl SyringeSet {
+    /// Creates a new empty set.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new set containing every running process selected by the given selector that can be opened.
+    /// The current process is never selected.
+    ///
+    /// Like [`OwnedProcess::find_all_by_selector`], processes that can not be opened are skipped,
+    /// use [`insert_selected`](Self::insert_selected) to find out which processes failed to be added.
+    #[must_use]
+    pub fn from_selector(selector: &ProcessSelector) -> Self {
+        let mut set = Self::new();
+        // a failed snapshot leaves the set empty, just like finding no process.
+        let _ = set.insert_selected(selector);
+        set
+    }
+
+    /// Adds every running process selected by the given selector to the set.
+    /// The current process is never selected.
+    ///
+    /// Returns a result for every selected process, which is `false` if it is already part of the set
+    /// and an error if it could not be opened, e.g. because it exited since the snapshot was taken.
+    pub fn insert_selected(
+        &mut self,
+        selector: &ProcessSelector,
+    ) -> Result<Vec<TargetResult<Result<bool, io::Error>>>, io::Error> {
+        let current_pid = std::process::id();
+        let mut results = Vec::new();
+        for entry in ProcessIter::new()? {
+            if entry.pid() == current_pid || !selector.matches(&entry) {
+                continue;
+            }
+            results.push(TargetResult {
+                pid: entry.pid(),
+                value: entry.open().and_then(|process| self.insert(process)),
+            });
+        }
+        Ok(results)
+    }
+
+    /// Adds the given process to the set and returns whether it was added.
+    /// A process that is already part of the set is not added again.
+    pub fn insert(&mut self, process: OwnedProcess) -> Result<bool, io::Error> {
+        let pid = process.pid()?.get();
+        if self.contains(pid) {
+            return Ok(false);
+        }
+        self.targets.push((pid, Syringe::for_process(process)));
+        Ok(true)
+    }
+
+    /// Removes the process with the given id from the set and returns its syringe.
+    pub fn remove(&mut self, pid: u32) -> Option<Syringe> {
+        let index = self.targets.iter().position(|(id, _)| *id == pid)?;
+        Some(self.targets.remove(index).1)
+    }
+
+    /// Removes all targets that have exited and returns how many were removed.
+    pub fn remove_exited(&mut self) -> usize {
+        let len = self.targets.len();
+        self.targets
+            .retain(|(_, syringe)| !syringe.has_target_exited());
+        len - self.targets.len()
+    }
+
+    /// Returns whether the process with the given id is part of the set.
+    #[must_use]
+    pub fn contains(&self, pid: u32) -> bool {
+        self.get(pid).is_some()
+    }
+
+    /// Returns the syringe for the process with the given id.
+    #[must_use]
+    pub fn get(&self, pid: u32) -> Option<&Syringe> {
+        self.targets
+            .iter()
+            .find(|(id, _)| *id == pid)
+            .map(|(_, syringe)| syringe)
+    }
+
+    /// Returns the number of targets in the set.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Returns whether the set has no targets.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Returns an iterator over the syringes of the targets in the set.
+    pub fn iter(&self) -> impl Iterator<Item = &Syringe> {
+        self.targets.iter().map(|(_, syringe)| syringe)
+    }
+
+    /// Performs the given operation on the syringe of every target and collects the results.
+    pub fn for_each_target<T>(&self, mut f: impl FnMut(&Syringe) -> T) -> Vec<TargetResult<T>> {
+        self.targets
+            .iter()
+            .map(|(pid, syringe)| TargetResult {
+                pid: *pid,
+                value: f(syringe),
+            })
+            .collect()
+    }
+
+    /// Injects the module from the given path into every target, see [`Syringe::inject`].
+    pub fn inject_all(
+        &self,
+        payload_path: impl AsRef<Path>,
+    ) -> Vec<TargetResult<Result<InjectedModule<'_>, InjectError>>> {
+        self.inject_all_with_options(payload_path, &InjectOptions::new())
+    }
+
+    /// Injects the module from the given path into every target using the given options, see [`Syringe::inject_with_options`].
+    pub fn inject_all_with_options(
+        &self,
+        payload_path: impl AsRef<Path>,
+        options: &InjectOptions,
+    ) -> Vec<TargetResult<Result<InjectedModule<'_>, InjectError>>> {
+        let payload_path = payload_path.as_ref();
+        self.targets
+            .iter()
+            .enumerate()
+            .map(|(i, (pid, syringe))| {
+                self.share_inject_help_data(i);
+                TargetResult {
+                    pid: *pid,
+                    value: syringe.inject_with_options(payload_path, options),
+                }
+            })
+            .collect()
+    }
+
+    /// Ejects the module with the given path from every target it is loaded in, see [`Syringe::eject_by_path`].
+    /// The result is `false` for the targets the module is not loaded in.
+    pub fn eject_all_by_path(
+        &self,
+        module_path: impl AsRef<Path>,
+    ) -> Vec<TargetResult<Result<bool, EjectError>>> {
+        let module_path = module_path.as_ref();
+        self.for_each_target(|syringe| syringe.eject_by_path(module_path))
+    }
+
+    /// Loads the given procedure of the module with the given path from every target, see [`Syringe::get_payload_procedure`].
+    /// The result is [`None`] for the targets the module is not loaded in or that do not export the procedure.
+    ///
+    /// # Safety
+    /// See [`Syringe::get_payload_procedure`].
+    #[cfg(feature = "rpc-payload")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-payload")))]
+    pub unsafe fn get_payload_procedures<F: PayloadRpcFunctionPtr>(
+        &self,
+        module_path: impl AsRef<Path>,
+        name: &str,
+    ) -> Vec<TargetResult<Result<Option<RemotePayloadProcedure<F>>, LoadProcedureError>>> {
+        let module_path = module_path.as_ref();
+        self.for_each_target(|syringe| {
+            let Some(module) = syringe.process().find_module_by_path(module_path)? else {
+                return Ok(None);
+            };
+            unsafe { syringe.get_payload_procedure(module, name) }
+        })
+    }
+
+    /// Loads the given procedure of the module with the given path from every target and calls it using the given function,
+    /// which receives the procedure of each target in turn.
+    /// The result is [`None`] for the targets the module is not loaded in or that do not export the procedure.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use dll_syringe::{process::ProcessSelector, SyringeSet};
+    ///
+    /// let targets = SyringeSet::from_selector(&ProcessSelector::name("game.exe"));
+    /// targets.inject_all("injection_payload.dll");
+    /// let results = unsafe {
+    ///     targets.call_payload_procedure::<fn(f64, f64) -> f64, _>("injection_payload.dll", "add", |add| add.call(&2.0, &4.0))
+    /// };
+    /// ```
+    ///
+    /// # Safety
+    /// See [`Syringe::get_payload_procedure`].
+    #[cfg(feature = "rpc-payload")]
+    #[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "rpc-payload")))]
+    pub unsafe fn call_payload_procedure<F: PayloadRpcFunctionPtr, T>(
+        &self,
+        module_path: impl AsRef<Path>,
+        name: &str,
+        mut call: impl FnMut(&RemotePayloadProcedure<F>) -> Result<T, PayloadRpcError>,
+    ) -> Vec<TargetResult<Result<Option<T>, SyringeOperationError>>> {
+        unsafe { self.get_payload_procedures::<F>(module_path, name) }
+            .into_iter()
+            .map(|result| TargetResult {
+                pid: result.pid,
+                value: match result.value {
+                    Ok(Some(procedure)) => call(&procedure).map(Some).map_err(Into::into),
+                    Ok(None) => Ok(None),
+                    Err(err) => Err(err.into()),
+                },
+            })
+            .collect()
+    }
+
+    /// Reuses the injection data another target with the same `kernel32.dll` has already located for the target at the given index.
+    fn share_inject_help_data(&self, index: usize) {
+        let syringe = &self.targets[index].1;
+        for (_, other) in &self.targets {
+            // errors, e.g. of a target that exited, only mean that the data can not be shared with that target.
+            if let Ok(true) = syringe.share_inject_help_data_from(other) {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,359 @@
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::{self, Command, Stdio},
+    sync::Mutex,
+};
+
+use crate::process::{OwnedProcess, ProcessKillGuard};
+
+/// The manifest of the dummy target process.
+const DUMMY_TARGET_MANIFEST: &str =
+    "[package]\nname = \"test_target\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n";
+
+/// The source of the dummy target process, which only sleeps for a while, so that the tests can run.
+/// It does not wait indefinitely, so targets of aborted tests do not keep running.
+const DUMMY_TARGET_SOURCE: &str =
+    "fn main() {\n    std::thread::sleep(std::time::Duration::from_secs(120));\n}\n";
+
+// the dummy target crate is shared by all tests of this process, which run on multiple threads.
+static DUMMY_TARGET_LOCK: Mutex<()> = Mutex::new(());
+
+/// The architecture a test payload and target are built for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "test-support")))]
+pub enum TestArch {
+    /// 32-bit x86 (`i686`).
+    X86,
+    /// 64-bit x86 (`x86_64`).
+    X64,
+}
+
+impl TestArch {
+    /// Returns whether this build of `dll-syringe` can inject into processes of this architecture.
+    #[must_use]
+    pub const fn is_supported(self) -> bool {
+        match self {
+            Self::X86 => cfg!(any(
+                target_arch = "x86",
+                all(target_arch = "x86_64", feature = "into-x86-from-x64")
+            )),
+            Self::X64 => cfg!(target_arch = "x86_64"),
+        }
+    }
+
+    /// Returns the target triple of this architecture with the same vendor, os and environment as the current target,
+    /// e.g. `i686-pc-windows-msvc` for [`TestArch::X86`] on `x86_64-pc-windows-msvc`.
+    #[must_use]
+    pub fn target_triple(self) -> String {
+        let platform = current_platform::CURRENT_PLATFORM;
+        match self {
+            Self::X86 => platform.replace("x86_64", "i686"),
+            Self::X64 => platform.replace("i686", "x86_64"),
+        }
+    }
+}
+
+/// Builds the crate in the given directory for the given architecture and returns the path of its artifact with the given
+/// extension, e.g. `dll` for a payload or `exe` for a target.
+///
+/// The crate is built in debug mode into the `target` directory next to its manifest. If the `CROSS_SYSROOT` environment
+/// variable is set, i.e. when testing using `cross`, an artifact that was built beforehand is used as is,
+/// as the toolchain of the target is usually not available in the container.
+///
+/// # Note
+/// The name of the artifact is read from the `name` in the `[lib]` or `[package]` section of the manifest.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "test-support")))]
+pub fn build_crate(
+    crate_dir: impl AsRef<Path>,
+    arch: TestArch,
+    artifact_ext: &str,
+) -> Result<PathBuf, io::Error> {
+    let crate_dir = crate_dir.as_ref().canonicalize()?;
+    let manifest = fs::read_to_string(crate_dir.join("Cargo.toml"))?;
+    let artifact_name = artifact_name(&manifest).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} has no crate name", crate_dir.display()),
+        )
+    })?;
+    let target = arch.target_triple();
+    let target_dir = crate_dir.join("target");
+    let artifact_path = target_dir
+        .join(&target)
+        .join("debug")
+        .join(format!("{artifact_name}.{artifact_ext}"));
+
+    if !(is_cross() && artifact_path.exists()) {
+        let status = Command::new("cargo")
+            .arg("build")
+            .arg("--target")
+            .arg(&target)
+            .arg("--target-dir")
+            .arg(&target_dir)
+            .current_dir(&crate_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "failed to build crate {} for target {target}",
+                crate_dir.display()
+            )));
+        }
+    }
+
+    if !artifact_path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("artifact {} does not exist", artifact_path.display()),
+        ));
+    }
+    Ok(artifact_path)
+}
+
+/// Builds the dummy target process for the given architecture and returns the path of its executable.
+///
+/// The dummy is a tiny program that sleeps for two minutes. Its crate is written to the temporary directory, so it is only
+/// built once per architecture.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "test-support")))]
+pub fn build_dummy_target(arch: TestArch) -> Result<PathBuf, io::Error> {
+    // a poisoned lock only means that another test failed, the files are never left half-written.
+    let _lock = DUMMY_TARGET_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let crate_dir = env::temp_dir().join("dll-syringe-test-target");
+    fs::create_dir_all(crate_dir.join("src"))?;
+    write_if_changed(&crate_dir.join("Cargo.toml"), DUMMY_TARGET_MANIFEST)?;
+    write_if_changed(&crate_dir.join("src").join("main.rs"), DUMMY_TARGET_SOURCE)?;
+    build_crate(crate_dir, arch, "exe")
+}
+
+/// Spawns the executable at the given path without any standard streams and returns a guard that kills it when dropped.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "test-support")))]
+pub fn spawn_target(executable: impl AsRef<Path>) -> Result<ProcessKillGuard, io::Error> {
+    let process: OwnedProcess = Command::new(executable.as_ref())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?
+        .into();
+    Ok(process.kill_on_drop())
+}
+
+/// Builds the payload crate in the given directory and the dummy target for the given architecture, spawns the target and
+/// calls the given test with it and the path of the payload. The target is killed once the test returns or panics.
+///
+/// # Panics
+/// Panics if this build of `dll-syringe` cannot inject into processes of the given architecture, see [`TestArch::is_supported`],
+/// or if the payload or the target could not be built or the target could not be spawned.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "test-support")))]
+pub fn run_payload_test(
+    arch: TestArch,
+    payload_crate_dir: impl AsRef<Path>,
+    test: impl FnOnce(OwnedProcess, &Path),
+) {
+    assert_supported(arch);
+    let payload_path = build_crate(payload_crate_dir, arch, "dll").unwrap();
+    run_process_test(arch, |process| test(process, &payload_path));
+}
+
+/// Builds and spawns the dummy target for the given architecture and calls the given test with it.
+/// The target is killed once the test returns or panics.
+///
+/// # Panics
+/// Panics if this build of `dll-syringe` cannot inject into processes of the given architecture, see [`TestArch::is_supported`],
+/// or if the target could not be built or spawned.
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "test-support")))]
+pub fn run_process_test(arch: TestArch, test: impl FnOnce(OwnedProcess)) {
+    assert_supported(arch);
+    let target = spawn_target(build_dummy_target(arch).unwrap()).unwrap();
+    test(target.try_clone().unwrap());
+}
+
+/// Expands to the given items if this build of `dll-syringe` can inject into x86 processes, see [`TestArch::is_supported`].
+/// The features are checked here, as the `cfg` attributes in the expansion of an exported macro refer to the features of the
+/// crate using it.
+#[cfg(any(
+    target_arch = "x86",
+    all(target_arch = "x86_64", feature = "into-x86-from-x64")
+))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_x86_supported {
+    ($($item:item)*) => {
+        $($item)*
+    };
+}
+
+#[cfg(not(any(
+    target_arch = "x86",
+    all(target_arch = "x86_64", feature = "into-x86-from-x64")
+)))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __if_x86_supported {
+    ($($item:item)*) => {};
+}
+
+/// Defines a test module with one test per supported architecture that builds the payload crate in the given directory,
+/// spawns a dummy target process and runs the body with both, see [`run_payload_test`](crate::test_support::run_payload_test).
+/// Relative paths are resolved against the working directory of the test, which is the directory of the package by default.
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::{process::OwnedProcess, Syringe};
+/// use std::path::Path;
+///
+/// dll_syringe::syringe_test! {
+///     payload: "tests/my_payload",
+///     fn inject_succeeds(process: OwnedProcess, payload_path: &Path) {
+///         let syringe = Syringe::for_process(process);
+///         syringe.inject(payload_path).unwrap();
+///     }
+/// }
+/// # fn main() {}
+/// ```
+#[macro_export]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "test-support")))]
+macro_rules! syringe_test {
+    (payload: $payload_crate_dir:expr, fn $test_name:ident ($process:ident : OwnedProcess, $payload_path:ident : &Path $(,)?) $body:block) => {
+        mod $test_name {
+            use super::*;
+
+            $crate::__if_x86_supported! {
+                #[test]
+                fn x86() {
+                    $crate::test_support::run_payload_test(
+                        $crate::test_support::TestArch::X86,
+                        $payload_crate_dir,
+                        test,
+                    )
+                }
+            }
+
+            #[test]
+            #[cfg(target_arch = "x86_64")]
+            fn x86_64() {
+                $crate::test_support::run_payload_test(
+                    $crate::test_support::TestArch::X64,
+                    $payload_crate_dir,
+                    test,
+                )
+            }
+
+            fn test(
+                $process: $crate::process::OwnedProcess,
+                $payload_path: &::std::path::Path,
+            ) $body
+        }
+    };
+}
+
+/// Defines a test module with one test per supported architecture that spawns a dummy target process and runs the body with it,
+/// see [`run_process_test`](crate::test_support::run_process_test).
+///
+/// # Example
+/// ```no_run
+/// use dll_syringe::process::{OwnedProcess, Process};
+///
+/// dll_syringe::process_test! {
+///     fn target_is_alive(process: OwnedProcess) {
+///         assert!(process.is_alive());
+///     }
+/// }
+/// # fn main() {}
+/// ```
+#[macro_export]
+#[cfg_attr(feature = "doc-cfg", doc(cfg(feature = "test-support")))]
+macro_rules! process_test {
+    (fn $test_name:ident ($process:ident : OwnedProcess $(,)?) $body:block) => {
+        mod $test_name {
+            use super::*;
+
+            $crate::__if_x86_supported! {
+                #[test]
+                fn x86() {
+                    $crate::test_support::run_process_test($crate::test_support::TestArch::X86, test)
+                }
+            }
+
+            #[test]
+            #[cfg(target_arch = "x86_64")]
+            fn x86_64() {
+                $crate::test_support::run_process_test($crate::test_support::TestArch::X64, test)
+            }
+
+            fn test($process: $crate::process::OwnedProcess) $body
+        }
+    };
+}
+
+/// Returns the name of the artifact of the crate with the given manifest, which is the name of its library or package.
+fn artifact_name(manifest: &str) -> Option<String> {
+    let mut section = "";
+    let mut package_name = None;
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            section = line;
+            continue;
+        }
+        let Some(value) = line
+            .strip_prefix("name")
+            .and_then(|rest| rest.trim_start().strip_prefix('='))
+        else {
+            continue;
+        };
+        let name = value.trim().trim_matches('"').replace('-', "_");
+        match section {
+            "[lib]" => return Some(name),
+            "[package]" => package_name = Some(name),
+            _ => {}
+        }
+    }
+    package_name
+}
+
+/// Writes the given contents to the given file unless it already has them, so cargo does not rebuild the crate needlessly.
+///
+/// The contents are written to a temporary file that replaces the file afterwards, so other processes building the crate
+/// at the same time never see a partially written file.
+fn write_if_changed(path: &Path, contents: &str) -> Result<(), io::Error> {
+    if fs::read_to_string(path).is_ok_and(|existing| existing == contents) {
+        return Ok(());
+    }
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(format!(".{}.tmp", process::id()));
+    let temp_path = path.with_file_name(temp_name);
+    fs::write(&temp_path, contents)?;
+    fs::rename(&temp_path, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temp_path);
+    })
+}
+
+fn assert_supported(arch: TestArch) {
+    assert!(
+        arch.is_supported(),
+        "injecting into {arch:?} processes is not supported by this build of dll-syringe"
+    );
+}
+
+/// Detects whether the tests run using `cross`, in which case the crates are usually built beforehand by an external script.
+fn is_cross() -> bool {
+    env::var("CROSS_SYSROOT").is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn artifact_name_prefers_lib_name() {
+        let manifest = "[package]\nname = \"my-payload\"\nversion = \"0.1.0\"\n\n[lib]\nname = \"payload\"\ncrate-type = [\"cdylib\"]\n";
+        assert_eq!(artifact_name(manifest).as_deref(), Some("payload"));
+
+        let manifest = "[package]\nname = \"my-payload\"\n\n[dependencies]\nserde = \"1.0\"\n";
+        assert_eq!(artifact_name(manifest).as_deref(), Some("my_payload"));
+    }
+}
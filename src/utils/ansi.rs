@@ -0,0 +1,168 @@
+use std::{ffi::CString, io, ptr};
+
+use winapi::{
+    shared::minwindef::{BOOL, FALSE},
+    um::{
+        stringapiset::{MultiByteToWideChar, WideCharToMultiByte},
+        winnls::{GetACP, CP_ACP, CP_UTF8, MB_ERR_INVALID_CHARS, WC_NO_BEST_FIT_CHARS},
+    },
+};
+
+/// Converts the given string to a nul terminated string in the ANSI code page of the system,
+/// which is the encoding expected by the ANSI variants of the win32 apis, e.g. `GetProcAddress`.
+///
+/// Fails with [`io::ErrorKind::InvalidInput`] if the string contains a nul byte or a character that is not part of the code page.
+#[cfg_attr(not(feature = "rpc-core"), allow(dead_code))]
+pub fn to_ansi_cstring(s: &str) -> Result<CString, io::Error> {
+    let bytes = if s.is_ascii() || unsafe { GetACP() } == CP_UTF8 {
+        s.as_bytes().to_vec()
+    } else {
+        let wide = s.encode_utf16().collect::<Vec<_>>();
+        let wide_len = i32::try_from(wide.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "string is too long"))?;
+
+        let mut used_default_char: BOOL = FALSE;
+        let len = unsafe {
+            WideCharToMultiByte(
+                CP_ACP,
+                WC_NO_BEST_FIT_CHARS,
+                wide.as_ptr(),
+                wide_len,
+                ptr::null_mut(),
+                0,
+                ptr::null(),
+                &mut used_default_char,
+            )
+        };
+        if len == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut bytes = vec![0u8; len as usize];
+        let len = unsafe {
+            WideCharToMultiByte(
+                CP_ACP,
+                WC_NO_BEST_FIT_CHARS,
+                wide.as_ptr(),
+                wide_len,
+                bytes.as_mut_ptr().cast(),
+                len,
+                ptr::null(),
+                &mut used_default_char,
+            )
+        };
+        if len == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if used_default_char != FALSE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "string cannot be represented in the ANSI code page",
+            ));
+        }
+        bytes.truncate(len as usize);
+        bytes
+    };
+
+    CString::new(bytes)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "string contains a nul byte"))
+}
+
+/// Decodes the given bytes from the ANSI code page of the system, which is the encoding used by the ANSI variants of the win32 apis.
+///
+/// Fails with [`io::ErrorKind::InvalidData`] if the bytes are not valid in the code page.
+#[cfg_attr(not(feature = "process-memory"), allow(dead_code))]
+pub fn from_ansi_bytes(bytes: &[u8]) -> Result<String, io::Error> {
+    if bytes.is_ascii() {
+        return Ok(String::from_utf8(bytes.to_vec()).unwrap());
+    }
+
+    let bytes_len = i32::try_from(bytes.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "string is too long"))?;
+    let len = unsafe {
+        MultiByteToWideChar(
+            CP_ACP,
+            MB_ERR_INVALID_CHARS,
+            bytes.as_ptr().cast(),
+            bytes_len,
+            ptr::null_mut(),
+            0,
+        )
+    };
+    if len == 0 {
+        return Err(invalid_ansi_error());
+    }
+
+    let mut wide = vec![0u16; len as usize];
+    let len = unsafe {
+        MultiByteToWideChar(
+            CP_ACP,
+            MB_ERR_INVALID_CHARS,
+            bytes.as_ptr().cast(),
+            bytes_len,
+            wide.as_mut_ptr(),
+            len,
+        )
+    };
+    if len == 0 {
+        return Err(invalid_ansi_error());
+    }
+    wide.truncate(len as usize);
+    String::from_utf16(&wide).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn invalid_ansi_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "string is not valid in the ANSI code page: {}",
+            io::Error::last_os_error()
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_is_kept() {
+        assert_eq!(
+            to_ansi_cstring("GetProcAddress").unwrap().as_bytes(),
+            b"GetProcAddress"
+        );
+        assert_eq!(to_ansi_cstring("").unwrap().as_bytes(), b"");
+    }
+
+    #[test]
+    fn nul_is_rejected() {
+        let err = to_ansi_cstring("Get\0ProcAddress").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn unrepresentable_characters_are_rejected() {
+        // a supplementary character is not part of any ANSI code page, only of UTF-8.
+        match to_ansi_cstring("Proc\u{1F600}") {
+            Ok(name) => {
+                assert_eq!(unsafe { GetACP() }, CP_UTF8);
+                assert_eq!(name.as_bytes(), "Proc\u{1F600}".as_bytes());
+            }
+            Err(err) => assert_eq!(err.kind(), io::ErrorKind::InvalidInput),
+        }
+    }
+
+    #[test]
+    fn ansi_roundtrips() {
+        assert_eq!(
+            from_ansi_bytes(b"GetProcAddress").unwrap(),
+            "GetProcAddress"
+        );
+        assert_eq!(from_ansi_bytes(b"").unwrap(), "");
+
+        // 'é' is part of most ANSI code pages, but encoded differently than in UTF-8 unless that is the code page.
+        if let Ok(ansi) = to_ansi_cstring("caf\u{e9}") {
+            assert_eq!(from_ansi_bytes(ansi.as_bytes()).unwrap(), "caf\u{e9}");
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::{
+    ffi::CString,
+    sync::{Mutex, PoisonError},
+};
+
+use winapi::{
+    shared::minwindef::DWORD,
+    um::dbghelp::{UnDecorateSymbolName, UNDNAME_COMPLETE, UNDNAME_NAME_ONLY},
+};
+
+// the functions of dbghelp are not thread-safe.
+static DBGHELP_LOCK: Mutex<()> = Mutex::new(());
+
+/// Returns the complete undecorated form of the given MSVC decorated name or [`None`] if it is not decorated.
+pub(crate) fn demangle(name: &str) -> Option<String> {
+    undecorate(name, UNDNAME_COMPLETE)
+}
+
+/// Returns the undecorated name without its type information or [`None`] if it is not decorated.
+pub(crate) fn demangle_name_only(name: &str) -> Option<String> {
+    undecorate(name, UNDNAME_NAME_ONLY)
+}
+
+fn undecorate(name: &str, flags: DWORD) -> Option<String> {
+    // decorated C++ names always start with a question mark.
+    if !name.starts_with('?') {
+        return None;
+    }
+    let decorated = CString::new(name).ok()?;
+
+    let mut buf = [0u8; 1024];
+    let len = {
+        let _lock = DBGHELP_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        unsafe {
+            UnDecorateSymbolName(
+                decorated.as_ptr(),
+                buf.as_mut_ptr().cast(),
+                buf.len() as DWORD,
+                flags,
+            )
+        }
+    };
+    let undecorated = String::from_utf8(buf[..len as usize].to_vec()).ok()?;
+    // names that cannot be undecorated are returned unchanged.
+    if len == 0 || undecorated == name {
+        None
+    } else {
+        Some(undecorated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demangles_decorated_names() {
+        assert_eq!(
+            demangle("?Foo@@YAXXZ").as_deref(),
+            Some("void __cdecl Foo(void)")
+        );
+        assert_eq!(demangle_name_only("?Foo@@YAXXZ").as_deref(), Some("Foo"));
+        assert_eq!(demangle("LoadLibraryW"), None);
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    ffi::OsString,
+    io,
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+};
+
+use widestring::{u16str, U16CString};
+use winapi::{shared::minwindef::MAX_PATH, um::fileapi::GetShortPathNameW};
+
+use super::{win_fill_path_buf_helper, FillPathBufResult};
+
+const EXTENDED_LENGTH_PREFIX: &[u16] = u16str!(r"\\?\").as_slice();
+const EXTENDED_LENGTH_UNC_PREFIX: &[u16] = u16str!(r"\\?\UNC\").as_slice();
+const DEVICE_PREFIX: &[u16] = u16str!(r"\\.\").as_slice();
+const UNC_PREFIX: &[u16] = u16str!(r"\\").as_slice();
+
+/// Returns whether the given path exceeds the `MAX_PATH` limit of the legacy win32 apis.
+pub fn is_long_path(path: &Path) -> bool {
+    // MAX_PATH includes the nul terminator.
+    path.as_os_str().encode_wide().count() >= MAX_PATH
+}
+
+/// Converts an absolute path into its extended-length (`\\?\`) form, which bypasses the `MAX_PATH` limit.
+///
+/// Extended-length paths are not normalized by the system, so the given path needs to be absolute and normalized.
+pub fn to_extended_length_path(path: &Path) -> PathBuf {
+    let path = path
+        .as_os_str()
+        .encode_wide()
+        .map(|c| {
+            if c == u16::from(b'/') {
+                u16::from(b'\\')
+            } else {
+                c
+            }
+        })
+        .collect::<Vec<_>>();
+    if path.starts_with(EXTENDED_LENGTH_PREFIX) || path.starts_with(DEVICE_PREFIX) {
+        PathBuf::from(OsString::from_wide(&path))
+    } else if let Some(unc_path) = path.strip_prefix(UNC_PREFIX) {
+        PathBuf::from(OsString::from_wide(
+            &[EXTENDED_LENGTH_UNC_PREFIX, unc_path].concat(),
+        ))
+    } else {
+        PathBuf::from(OsString::from_wide(
+            &[EXTENDED_LENGTH_PREFIX, &path].concat(),
+        ))
+    }
+}
+
+/// Returns the short (8.3) form of the given existing path.
+///
+/// # Note
+/// Short names may be disabled on the volume, in which case the returned path may still be long.
+pub fn short_path_name(path: &Path) -> Result<PathBuf, io::Error> {
+    let long_path = U16CString::from_os_str(to_extended_length_path(path))
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    let short_path = win_fill_path_buf_helper(|buf_ptr, buf_size| {
+        let result = unsafe { GetShortPathNameW(long_path.as_ptr(), buf_ptr, buf_size as u32) };
+        if result == 0 {
+            FillPathBufResult::Error(io::Error::last_os_error())
+        } else if result as usize >= buf_size {
+            // the returned size includes the nul terminator if the buffer is too small.
+            FillPathBufResult::BufTooSmall {
+                size_hint: Some(result as usize),
+            }
+        } else {
+            FillPathBufResult::Success {
+                actual_len: result as usize,
+            }
+        }
+    })?;
+
+    // strip the extended-length prefix again so the path is usable by apis that do not support it.
+    let short_path = short_path.as_os_str().encode_wide().collect::<Vec<_>>();
+    Ok(PathBuf::from(
+        if let Some(unc_path) = short_path.strip_prefix(EXTENDED_LENGTH_UNC_PREFIX) {
+            OsString::from_wide(&[UNC_PREFIX, unc_path].concat())
+        } else {
+            OsString::from_wide(
+                short_path
+                    .strip_prefix(EXTENDED_LENGTH_PREFIX)
+                    .unwrap_or(&short_path),
+            )
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extended_length_path_forms() {
+        assert_eq!(
+            to_extended_length_path(Path::new(r"C:\payloads\payload.dll")),
+            Path::new(r"\\?\C:\payloads\payload.dll")
+        );
+        assert_eq!(
+            to_extended_length_path(Path::new(r"\\server\share\payload.dll")),
+            Path::new(r"\\?\UNC\server\share\payload.dll")
+        );
+        assert_eq!(
+            to_extended_length_path(Path::new(r"\\?\C:\payload.dll")),
+            Path::new(r"\\?\C:\payload.dll")
+        );
+        assert_eq!(
+            to_extended_length_path(Path::new("C:/payloads/payload.dll")),
+            Path::new(r"\\?\C:\payloads\payload.dll")
+        );
+    }
+
+    #[test]
+    fn extended_length_path_preserves_unpaired_surrogates() {
+        let path = OsString::from_wide(&[
+            u16::from(b'C'),
+            u16::from(b':'),
+            u16::from(b'\\'),
+            0xD800,
+            u16::from(b'/'),
+            u16::from(b'a'),
+        ]);
+        let extended = to_extended_length_path(Path::new(&path));
+        assert_eq!(
+            extended.as_os_str().encode_wide().collect::<Vec<_>>(),
+            [
+                EXTENDED_LENGTH_PREFIX,
+                &[0x43, 0x3A, 0x5C, 0xD800, 0x5C, 0x61]
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn long_path_detection() {
+        assert!(!is_long_path(Path::new(r"C:\payload.dll")));
+        let long_path = format!(r"C:\{}\payload.dll", "a".repeat(MAX_PATH));
+        assert!(is_long_path(Path::new(&long_path)));
+    }
+}
@@ -16,3 +16,24 @@ pub(crate) use win_path_buf_utils::*;
 
 mod range;
 pub(crate) use range::*;
+
+mod wow64_redirection;
+pub(crate) use wow64_redirection::*;
+
+#[cfg(feature = "syringe")]
+mod long_path;
+#[cfg(feature = "syringe")]
+pub(crate) use long_path::*;
+
+#[cfg(any(feature = "rpc-core", feature = "process-memory"))]
+mod ansi;
+#[cfg(any(feature = "rpc-core", feature = "process-memory"))]
+pub(crate) use ansi::*;
+
+#[cfg(feature = "demangle")]
+mod demangle;
+#[cfg(feature = "demangle")]
+pub(crate) use demangle::*;
+
+mod trace;
+pub(crate) use trace::*;
@@ -1,26 +1,74 @@
 use std::ops::{Bound, Range, RangeBounds};
 
+/// Resolves the given bounds relative to a buffer of the given length starting at the given offset.
+///
+/// # Panics
+/// Panics if the bounds are out of range or the end is before the start.
 pub(crate) fn range_from_bounds(
     offset: usize,
     len: usize,
     range: &impl RangeBounds<usize>,
 ) -> Range<usize> {
+    let (rel_start, rel_end) = relative_bounds(len, range);
+    let rel_start = rel_start.expect("range start out of bounds");
+    let rel_end = rel_end.expect("range end out of bounds");
+
+    assert!(rel_start <= len, "range start out of bounds");
+    assert!(rel_end <= len, "range end out of bounds");
+    assert!(rel_end >= rel_start, "range end before start");
+
+    Range {
+        start: offset + rel_start,
+        end: offset + rel_end,
+    }
+}
+
+/// Resolves the given bounds relative to a buffer of the given length starting at the given offset.
+/// Returns [`None`] if the bounds are out of range or the end is before the start.
+pub(crate) fn checked_range_from_bounds(
+    offset: usize,
+    len: usize,
+    range: &impl RangeBounds<usize>,
+) -> Option<Range<usize>> {
+    let (rel_start, rel_end) = relative_bounds(len, range);
+    let (rel_start, rel_end) = (rel_start?, rel_end?);
+    (rel_start <= rel_end && rel_end <= len).then(|| Range {
+        start: offset + rel_start,
+        end: offset + rel_end,
+    })
+}
+
+fn relative_bounds(len: usize, range: &impl RangeBounds<usize>) -> (Option<usize>, Option<usize>) {
     let rel_start = match range.start_bound() {
-        Bound::Unbounded => 0,
-        Bound::Included(start) => *start,
-        Bound::Excluded(start) => start.saturating_add(1),
+        Bound::Unbounded => Some(0),
+        Bound::Included(start) => Some(*start),
+        Bound::Excluded(start) => start.checked_add(1),
     };
     let rel_end = match range.end_bound() {
-        Bound::Unbounded => len,
-        Bound::Included(end) => *end,
-        Bound::Excluded(end) => end.saturating_sub(1),
+        Bound::Unbounded => Some(len),
+        Bound::Included(end) => end.checked_add(1),
+        Bound::Excluded(end) => Some(*end),
     };
+    (rel_start, rel_end)
+}
 
-    assert!(rel_start <= len, "range start out of bounds");
-    assert!(rel_end <= len, "range end out of bounds");
-    assert!(rel_end >= rel_start, "range end before start");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_are_resolved_like_slice_indices() {
+        assert_eq!(range_from_bounds(10, 8, &(2..5)), 12..15);
+        assert_eq!(range_from_bounds(10, 8, &(2..=5)), 12..16);
+        assert_eq!(range_from_bounds(10, 8, &(..)), 10..18);
+        assert_eq!(range_from_bounds(10, 8, &(8..)), 18..18);
 
-    let start = offset + rel_start;
-    let end = offset + rel_end;
-    Range { start, end }
+        assert_eq!(checked_range_from_bounds(0, 8, &(..=8)), None);
+        assert_eq!(
+            checked_range_from_bounds(0, 8, &(Bound::Included(5), Bound::Excluded(2))),
+            None
+        );
+        assert_eq!(checked_range_from_bounds(0, 8, &(..=usize::MAX)), None);
+        assert_eq!(checked_range_from_bounds(0, 8, &(3..8)), Some(3..8));
+    }
 }
@@ -1,7 +1,9 @@
-use std::time::Duration;
+use std::{thread, time::Duration};
 
 use stopwatch2::Stopwatch;
 
+use crate::process::RetryPolicy;
+
 pub(crate) fn retry_with_timeout<R>(
     operation: impl Fn() -> Option<R>,
     timeout: Duration,
@@ -38,13 +40,40 @@ pub(crate) fn retry_faillable_until_some_with_timeout<R, E>(
     }
 }
 
+pub(crate) fn retry_faillable_until_some_with_policy<R, E>(
+    mut operation: impl FnMut() -> Result<Option<R>, E>,
+    policy: &RetryPolicy,
+) -> Result<Option<R>, E> {
+    let mut stopwatch = Stopwatch::default();
+    stopwatch.start();
+    let mut attempts = 0;
+    loop {
+        let result = operation();
+        attempts += 1;
+        let is_exhausted = policy.max_attempts().is_some_and(|max| attempts >= max)
+            || stopwatch.elapsed() >= policy.timeout();
+        match result {
+            Ok(Some(result)) => return Ok(Some(result)),
+            result if is_exhausted => return result,
+            _ => thread::sleep(
+                policy
+                    .interval()
+                    .min(policy.timeout().saturating_sub(stopwatch.elapsed())),
+            ),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{cell::Cell, time::Duration};
 
-    use retry::{retry_faillable_until_some_with_timeout, retry_faillable_with_timeout};
+    use retry::{
+        retry_faillable_until_some_with_policy, retry_faillable_until_some_with_timeout,
+        retry_faillable_with_timeout,
+    };
 
-    use crate::utils::retry;
+    use crate::{process::RetryPolicy, utils::retry};
 
     #[test]
     fn retry_with_zero_timeout_tries_once_and_returns() {
@@ -80,4 +109,35 @@ mod tests {
             retry_faillable_until_some_with_timeout(|| Ok(None), Duration::from_millis(25));
         assert_eq!(result, Ok(None));
     }
+
+    #[test]
+    fn retry_with_policy_stops_after_max_attempts() {
+        let tries = Cell::new(0);
+        let policy = RetryPolicy::new()
+            .with_max_attempts(Some(3))
+            .with_interval(Duration::ZERO);
+        let result: Result<Option<()>, ()> = retry_faillable_until_some_with_policy(
+            || {
+                tries.set(tries.get() + 1);
+                Ok(None)
+            },
+            &policy,
+        );
+        assert_eq!(result, Ok(None));
+        assert_eq!(tries.get(), 3);
+    }
+
+    #[test]
+    fn retry_with_no_retry_policy_tries_once() {
+        let tries = Cell::new(0);
+        let result: Result<Option<()>, ()> = retry_faillable_until_some_with_policy(
+            || {
+                tries.set(tries.get() + 1);
+                Err(())
+            },
+            &RetryPolicy::no_retry(),
+        );
+        assert_eq!(result, Err(()));
+        assert_eq!(tries.get(), 1);
+    }
 }
@@ -0,0 +1,35 @@
+/// Emits a `tracing` event at the given level if the `tracing` feature is enabled.
+/// The arguments are passed through to the corresponding `tracing` macro.
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        ::tracing::$level!($($arg)+);
+    };
+}
+pub(crate) use trace_event;
+
+/// Enters a `tracing` span at the given level for the rest of the current scope if the `tracing` feature is enabled.
+macro_rules! trace_span {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        let _span = ::tracing::span!(::tracing::Level::$level, $($arg)+).entered();
+    };
+}
+pub(crate) use trace_span;
+
+/// Formats a byte slice as space separated hex pairs, e.g. for dumping generated stubs.
+#[cfg(all(feature = "tracing", feature = "syringe"))]
+pub(crate) struct HexBytes<'a>(pub &'a [u8]);
+
+#[cfg(all(feature = "tracing", feature = "syringe"))]
+impl std::fmt::Display for HexBytes<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    ffi::OsStr,
+    path::{Component, Path, PathBuf},
+};
+
+/// Subdirectories of `System32` that are exempt from the WOW64 file system redirection.
+const NON_REDIRECTED_SUBDIRS: &[&[&str]] = &[
+    &["catroot"],
+    &["catroot2"],
+    &["driverstore"],
+    &["drivers", "etc"],
+    &["logfiles"],
+    &["spool"],
+];
+
+/// The system directory a path points into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SystemDir {
+    System32,
+    SysWow64,
+    Sysnative,
+}
+
+impl SystemDir {
+    fn name(self) -> &'static str {
+        match self {
+            Self::System32 => "System32",
+            Self::SysWow64 => "SysWOW64",
+            Self::Sysnative => "Sysnative",
+        }
+    }
+
+    fn from_name(name: &OsStr) -> Option<Self> {
+        [Self::System32, Self::SysWow64, Self::Sysnative]
+            .into_iter()
+            .find(|dir| name.eq_ignore_ascii_case(dir.name()))
+    }
+}
+
+/// Rewrites a path below the given windows directory so that a process with the target bitness resolves
+/// it to the same file as a process with the source bitness, taking the WOW64 file system redirection into account.
+///
+/// Returns [`None`] if the path does not need to be rewritten.
+pub fn redirect_system_path(
+    path: &Path,
+    windows_dir: &Path,
+    from_wow64: bool,
+    to_wow64: bool,
+) -> Option<PathBuf> {
+    let mut components = path.components();
+    for windows_dir_component in windows_dir.components() {
+        let component = components.next()?;
+        if !component
+            .as_os_str()
+            .eq_ignore_ascii_case(windows_dir_component.as_os_str())
+        {
+            return None;
+        }
+    }
+
+    let dir = match components.next()? {
+        Component::Normal(name) => SystemDir::from_name(name)?,
+        _ => return None,
+    };
+    let rest = components.as_path();
+
+    // the directory the source process actually resolves the path to.
+    let resolved = match dir {
+        SystemDir::System32 if from_wow64 && !is_non_redirected(rest) => SystemDir::SysWow64,
+        // Sysnative only exists for WOW64 processes, a native process most likely meant System32.
+        SystemDir::Sysnative => SystemDir::System32,
+        dir => dir,
+    };
+    // the directory the target process needs to be given to resolve to the same location.
+    let target = match resolved {
+        SystemDir::System32 if to_wow64 && !is_non_redirected(rest) => SystemDir::Sysnative,
+        dir => dir,
+    };
+
+    if target == dir {
+        return None;
+    }
+
+    let mut redirected = windows_dir.to_path_buf();
+    redirected.push(target.name());
+    redirected.push(rest);
+    Some(redirected)
+}
+
+fn is_non_redirected(path: &Path) -> bool {
+    NON_REDIRECTED_SUBDIRS.iter().any(|subdir| {
+        let mut components = path.components();
+        subdir.iter().all(|name| {
+            components
+                .next()
+                .is_some_and(|component| component.as_os_str().eq_ignore_ascii_case(name))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOWS_DIR: &str = r"C:\Windows";
+
+    fn redirect(path: &str, from_wow64: bool, to_wow64: bool) -> Option<PathBuf> {
+        redirect_system_path(
+            Path::new(path),
+            Path::new(WINDOWS_DIR),
+            from_wow64,
+            to_wow64,
+        )
+    }
+
+    #[test]
+    fn native_to_wow64_uses_sysnative() {
+        assert_eq!(
+            redirect(r"C:\Windows\System32\payload.dll", false, true),
+            Some(PathBuf::from(r"C:\Windows\Sysnative\payload.dll"))
+        );
+        assert_eq!(
+            redirect(r"c:\windows\system32\sub\payload.dll", false, true),
+            Some(PathBuf::from(r"C:\Windows\Sysnative\sub\payload.dll"))
+        );
+        assert_eq!(
+            redirect(r"C:\Windows\SysWOW64\payload.dll", false, true),
+            None
+        );
+        assert_eq!(
+            redirect(r"C:\Windows\System32\drivers\etc\payload.dll", false, true),
+            None
+        );
+    }
+
+    #[test]
+    fn wow64_to_native_uses_syswow64() {
+        assert_eq!(
+            redirect(r"C:\Windows\System32\payload.dll", true, false),
+            Some(PathBuf::from(r"C:\Windows\SysWOW64\payload.dll"))
+        );
+        assert_eq!(
+            redirect(r"C:\Windows\Sysnative\payload.dll", true, false),
+            Some(PathBuf::from(r"C:\Windows\System32\payload.dll"))
+        );
+        assert_eq!(
+            redirect(r"C:\Windows\System32\spool\payload.dll", true, false),
+            None
+        );
+    }
+
+    #[test]
+    fn same_bitness_keeps_paths() {
+        assert_eq!(
+            redirect(r"C:\Windows\System32\payload.dll", true, true),
+            None
+        );
+        assert_eq!(
+            redirect(r"C:\Windows\System32\payload.dll", false, false),
+            None
+        );
+        assert_eq!(
+            redirect(r"C:\Windows\Sysnative\payload.dll", true, true),
+            None
+        );
+        assert_eq!(
+            redirect(r"C:\Windows\Sysnative\payload.dll", false, false),
+            Some(PathBuf::from(r"C:\Windows\System32\payload.dll"))
+        );
+        assert_eq!(redirect(r"C:\payloads\payload.dll", false, true), None);
+        assert_eq!(redirect(r"C:\Windows\payload.dll", false, true), None);
+    }
+}
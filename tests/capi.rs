@@ -0,0 +1,51 @@
+#![cfg(feature = "capi")]
+
+use dll_syringe::capi::{
+    syringe_call_procedure, syringe_eject, syringe_free, syringe_inject,
+    syringe_last_error_message, syringe_open, SyringeStatus,
+};
+use dll_syringe::process::Process;
+use std::{ffi::c_void, os::windows::ffi::OsStrExt, ptr};
+
+#[allow(unused)]
+mod common;
+
+syringe_test! {
+    fn inject_call_and_eject_through_c_api(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let pid = process.pid().unwrap().get();
+        let mut syringe = ptr::null_mut();
+        assert_eq!(unsafe { syringe_open(pid, &mut syringe) }, SyringeStatus::Ok);
+
+        let wide_path = payload_path
+            .as_os_str()
+            .encode_wide()
+            .chain([0])
+            .collect::<Vec<_>>();
+        let mut module: *mut c_void = ptr::null_mut();
+        assert_eq!(
+            unsafe { syringe_inject(syringe, wide_path.as_ptr(), &mut module) },
+            SyringeStatus::Ok
+        );
+        assert!(!module.is_null());
+
+        let mut result = 0;
+        assert_eq!(
+            unsafe { syringe_call_procedure(syringe, module, c"double_word_raw".as_ptr(), 21, &mut result) },
+            SyringeStatus::Ok
+        );
+        assert_eq!(result, 42);
+
+        assert_eq!(
+            unsafe { syringe_call_procedure(syringe, module, c"no_such_procedure".as_ptr(), 0, ptr::null_mut()) },
+            SyringeStatus::NotFound
+        );
+        assert!(unsafe { syringe_last_error_message(ptr::null_mut(), 0) } > 1);
+
+        assert_eq!(unsafe { syringe_eject(syringe, module) }, SyringeStatus::Ok);
+        assert_eq!(unsafe { syringe_last_error_message(ptr::null_mut(), 0) }, 0);
+        unsafe { syringe_free(syringe) };
+    }
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "dotnet")]
+
+use dll_syringe::{error::ManagedInjectError, process::Process, ManagedEntryPoint, Syringe};
+
+#[allow(unused)]
+mod common;
+
+process_test! {
+    fn inject_managed_with_missing_assembly_fails_in_app_domain(
+        process: OwnedProcess,
+    ) {
+        let syringe = Syringe::for_process(process);
+        let entry_point = ManagedEntryPoint::new(r"C:\does\not\exist\Missing.dll", "Missing.EntryPoint", "Run");
+
+        let err = syringe.inject_managed(&entry_point).unwrap_err();
+        assert!(
+            matches!(err, ManagedInjectError::Hosting { call: "ICLRRuntimeHost::ExecuteInDefaultAppDomain", .. }),
+            "{err:?}"
+        );
+        assert!(syringe.process().find_module_by_name("mscoree.dll").unwrap().is_some());
+    }
+}
+
+process_test! {
+    fn inject_managed_with_unknown_runtime_version_fails(
+        process: OwnedProcess,
+    ) {
+        let syringe = Syringe::for_process(process);
+        let entry_point = ManagedEntryPoint::new(r"C:\does\not\exist\Missing.dll", "Missing.EntryPoint", "Run")
+            .with_runtime_version("v0.0.0");
+
+        let err = syringe.inject_managed(&entry_point).unwrap_err();
+        assert!(
+            matches!(err, ManagedInjectError::Hosting { call: "ICLRMetaHost::GetRuntime", .. }),
+            "{err:?}"
+        );
+    }
+}
@@ -1,6 +1,7 @@
 #![cfg(feature = "syringe")]
 
-use dll_syringe::{error::EjectError, process::Process, Syringe};
+use dll_syringe::{error::EjectError, process::Process, EjectMode, OwnedInjectedModule, Syringe};
+use std::rc::Rc;
 
 #[allow(unused)]
 mod common;
@@ -16,6 +17,31 @@ syringe_test! {
     }
 }
 
+syringe_test! {
+    fn eject_with_self_unload_mode_unloads_payload(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let syringe = Syringe::for_process(process);
+        let module = syringe.inject(payload_path).unwrap();
+        syringe.eject_with_mode(module, EjectMode::SelfUnload).unwrap();
+        assert!(syringe.process().find_module_by_path(payload_path).unwrap().is_none());
+    }
+}
+
+syringe_test! {
+    fn eject_with_self_unload_mode_fails_without_export(
+        process: OwnedProcess,
+        _payload_path: &Path,
+    ) {
+        let syringe = Syringe::for_process(process);
+        let kernel32 = syringe.process().find_module_by_name("kernel32.dll").unwrap().unwrap();
+        let err = syringe.eject_with_mode(kernel32, EjectMode::SelfUnload).unwrap_err();
+        assert!(matches!(err, EjectError::SelfUnloadUnsupported), "{err:?}");
+        assert!(kernel32.guess_is_loaded());
+    }
+}
+
 syringe_test! {
     fn eject_with_crashed_process_fails_with_process_inaccessible(
         process: OwnedProcess,
@@ -32,3 +58,49 @@ syringe_test! {
         assert!(matches!(err, EjectError::ProcessInaccessible), "{err:?}");
     }
 }
+
+syringe_test! {
+    fn eject_owned_injected_module(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let syringe = Rc::new(Syringe::for_process(process));
+        let module = OwnedInjectedModule::new(syringe.clone(), syringe.inject(payload_path).unwrap());
+        drop(syringe);
+
+        let process = module.syringe().process().try_to_owned().unwrap();
+        module.eject().unwrap();
+        assert!(process.find_module_by_path(payload_path).unwrap().is_none());
+    }
+}
+
+syringe_test! {
+    fn eject_adopted_module(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let previous_syringe = Syringe::for_process(process.try_clone().unwrap());
+        previous_syringe.inject(payload_path).unwrap();
+        drop(previous_syringe);
+
+        let syringe = Syringe::for_process(process);
+        let module = syringe.process().find_module_by_path(payload_path).unwrap().unwrap();
+        syringe.adopt_module(module).eject().unwrap();
+        assert!(syringe.process().find_module_by_path(payload_path).unwrap().is_none());
+    }
+}
+
+syringe_test! {
+    fn eject_by_path_releases_all_references(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let syringe = Syringe::for_process(process);
+        syringe.inject(payload_path).unwrap();
+        syringe.inject(payload_path).unwrap();
+
+        assert!(syringe.eject_by_path(payload_path).unwrap());
+        assert!(syringe.process().find_module_by_path(payload_path).unwrap().is_none());
+        assert!(!syringe.eject_by_path(payload_path).unwrap());
+    }
+}
@@ -9,6 +9,21 @@ extern "system" fn DllMain(
     1
 }
 
+dll_syringe::payload_config!(64);
+dll_syringe::payload_marker!("dll-syringe-test-payload");
+dll_syringe::payload_self_unload!();
+dll_syringe::payload_log_sink!();
+
+#[no_mangle]
+pub extern "system" fn config_len() -> u32 {
+    DLL_SYRINGE_CONFIG.read().len() as u32
+}
+
+#[no_mangle]
+pub extern "system" fn log_value(value: u32) -> u32 {
+    dll_syringe::payload_log!("value {value}") as u32
+}
+
 dll_syringe::payload_procedure! {
     fn add(a: u32, b: u32) -> u32 {
         a + b
@@ -42,6 +57,16 @@ pub extern "system" fn sub_raw(a: u32, b: u32) -> u32 {
     a - b
 }
 
+#[no_mangle]
+pub extern "system" fn mul_wide_raw(a: u32, b: u32) -> u64 {
+    a as u64 * b as u64
+}
+
+#[no_mangle]
+pub extern "system" fn double_word_raw(a: usize) -> usize {
+    a * 2
+}
+
 #[no_mangle]
 pub extern "system" fn add_smol_raw(a: u16, b: u8) -> u16 {
     a + b as u16
@@ -1,4 +1,16 @@
 fn main() {
+    // `--spawn-children=<depth>` spawns a chain of that many descendants, so the tests can follow child processes.
+    let depth = std::env::args().find_map(|arg| {
+        arg.strip_prefix("--spawn-children=")
+            .and_then(|depth| depth.parse::<u32>().ok())
+    });
+    if let Some(depth) = depth.filter(|&depth| depth > 0) {
+        std::process::Command::new(std::env::current_exe().unwrap())
+            .arg(format!("--spawn-children={}", depth - 1))
+            .spawn()
+            .unwrap();
+    }
+
     // this loop keeps the process alive for a while, so that the tests can run.
     // we dont want to wait indefinitely to avoid creating sleeping zombies.
     for _ in 0..120 {
@@ -1,6 +1,20 @@
 #![cfg(feature = "syringe")]
 
-use dll_syringe::{error::InjectError, process::Process, Syringe};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use winapi::um::winnt::{PROCESS_CREATE_THREAD, PROCESS_QUERY_INFORMATION};
+
+use dll_syringe::{
+    error::{EjectError, InjectError, MissingDependencyError, OpenProcessError},
+    process::{
+        AllocationPlacement, Process, ProcessSelector, RemoteAllocationBackend, RemoteThreadResult,
+        PROCESS_MEMORY_READ_ACCESS,
+    },
+    HotReloadEvent, HotReloader, InjectOptions, PayloadBundle, StubKind, Syringe, SyringeEvent,
+    SyringeSet,
+};
 
 #[allow(unused)]
 mod common;
@@ -15,6 +29,251 @@ syringe_test! {
     }
 }
 
+syringe_test! {
+    fn inject_with_copy_to_temp_loads_and_removes_copy(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let options = InjectOptions::new()
+            .with_copy_to_temp(true)
+            .with_randomized_name(true)
+            .with_temp_dir(temp_dir.path());
+        let syringe = Syringe::for_process(process);
+        let module = syringe.inject_with_options(payload_path, &options).unwrap();
+        let staged_path = module.path().unwrap();
+        assert!(staged_path.exists());
+        assert_ne!(staged_path.file_name(), payload_path.file_name());
+
+        syringe.eject(module).unwrap();
+        assert!(!staged_path.exists());
+    }
+}
+
+syringe_test! {
+    fn eject_stale_ejects_copies_from_previous_runs(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let options = InjectOptions::new()
+            .with_copy_to_temp(true)
+            .with_randomized_name(true)
+            .with_temp_dir(temp_dir.path());
+        let staged_path = {
+            let previous_run = Syringe::for_process(process.try_clone().unwrap());
+            let module = previous_run.inject_with_options(payload_path, &options).unwrap();
+            module.path().unwrap()
+        };
+
+        let syringe = Syringe::for_process(process);
+        assert_eq!(syringe.eject_stale(payload_path).unwrap(), 1);
+        assert!(syringe.process().find_module_by_path(&staged_path).unwrap().is_none());
+        assert_eq!(syringe.eject_stale(payload_path).unwrap(), 0);
+    }
+}
+
+syringe_test! {
+    fn eject_stale_ejects_renamed_copies_by_marker(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let renamed_path = temp_dir.path().join("renamed.dll");
+        std::fs::copy(payload_path, &renamed_path).unwrap();
+        let syringe = Syringe::for_process(process);
+        syringe.inject(&renamed_path).unwrap();
+
+        assert_eq!(syringe.eject_stale(payload_path).unwrap(), 1);
+        assert!(syringe.process().find_module_by_path(&renamed_path).unwrap().is_none());
+        assert_eq!(syringe.eject_stale(payload_path).unwrap(), 0);
+    }
+}
+
+syringe_test! {
+    fn eject_stale_by_marker_ejects_copies_with_marker(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let renamed_path = temp_dir.path().join("renamed.dll");
+        std::fs::copy(payload_path, &renamed_path).unwrap();
+        let syringe = Syringe::for_process(process);
+        syringe.inject(payload_path).unwrap();
+        syringe.inject(&renamed_path).unwrap();
+
+        assert_eq!(syringe.eject_stale_by_marker("other-payload").unwrap(), 0);
+        assert_eq!(syringe.eject_stale_by_marker("dll-syringe-test-payload").unwrap(), 2);
+        assert!(syringe.process().find_module_by_path(payload_path).unwrap().is_none());
+        assert!(syringe.process().find_module_by_path(&renamed_path).unwrap().is_none());
+        assert_eq!(syringe.eject_stale_by_marker("dll-syringe-test-payload").unwrap(), 0);
+    }
+}
+
+syringe_test! {
+    fn hot_reloader_reinjects_changed_payload(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let watched_path = temp_dir.path().join("payload.dll");
+        std::fs::copy(payload_path, &watched_path).unwrap();
+
+        let syringe = Syringe::for_process(process);
+        let mut reloader = HotReloader::new(&syringe, &watched_path)
+            .with_inject_options(
+                InjectOptions::new()
+                    .with_copy_to_temp(true)
+                    .with_randomized_name(true)
+                    .with_temp_dir(temp_dir.path()),
+            )
+            .with_settle_time(Duration::ZERO);
+        let reloads = Arc::new(Mutex::new(0));
+        let recorded = reloads.clone();
+        reloader.add_listener(move |event| {
+            if let HotReloadEvent::Reloaded { .. } = event {
+                *recorded.lock().unwrap() += 1;
+            }
+        });
+
+        let first = reloader.inject().unwrap().module().path().unwrap();
+        assert!(!reloader.poll().unwrap());
+
+        // a rebuild of the same size is detected by its modification time.
+        std::thread::sleep(Duration::from_millis(50));
+        std::fs::File::options()
+            .write(true)
+            .open(&watched_path)
+            .unwrap()
+            .set_modified(std::time::SystemTime::now())
+            .unwrap();
+        assert!(reloader.poll().unwrap());
+
+        let second = reloader.module().unwrap().module().path().unwrap();
+        assert_ne!(first, second);
+        assert!(!first.exists());
+        assert_eq!(*reloads.lock().unwrap(), 2);
+    }
+}
+
+syringe_test! {
+    fn hot_reloader_does_not_retry_failed_build(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let watched_path = temp_dir.path().join("payload.dll");
+        std::fs::copy(payload_path, &watched_path).unwrap();
+
+        let syringe = Syringe::for_process(process);
+        let mut reloader = HotReloader::new(&syringe, &watched_path)
+            .with_inject_options(
+                InjectOptions::new()
+                    .with_copy_to_temp(true)
+                    .with_randomized_name(true)
+                    .with_temp_dir(temp_dir.path()),
+            )
+            .with_settle_time(Duration::ZERO);
+        let failures = Arc::new(Mutex::new(0));
+        let recorded = failures.clone();
+        reloader.add_listener(move |event| {
+            if let HotReloadEvent::Failed { .. } = event {
+                *recorded.lock().unwrap() += 1;
+            }
+        });
+        reloader.inject().unwrap();
+
+        std::fs::write(&watched_path, b"not a dll").unwrap();
+        assert!(reloader.poll().is_err());
+        assert!(!reloader.poll().unwrap());
+        assert!(!reloader.poll().unwrap());
+        assert_eq!(*failures.lock().unwrap(), 1);
+
+        std::fs::copy(payload_path, &watched_path).unwrap();
+        assert!(reloader.poll().unwrap());
+        assert!(reloader.module().is_some());
+        assert_eq!(*failures.lock().unwrap(), 1);
+    }
+}
+
+syringe_test! {
+    fn inject_and_eject_emit_lifecycle_events(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut syringe = Syringe::for_process(process);
+        let recorded = events.clone();
+        syringe.add_event_listener(move |event| {
+            let name = match event {
+                SyringeEvent::BeforeInject { .. } => "before_inject",
+                SyringeEvent::AfterInject { result, .. } => {
+                    assert!(result.is_ok());
+                    "after_inject"
+                }
+                SyringeEvent::BeforeEject { .. } => "before_eject",
+                SyringeEvent::AfterEject { result, .. } => {
+                    assert!(result.is_ok());
+                    "after_eject"
+                }
+                _ => "other",
+            };
+            recorded.lock().unwrap().push(name);
+        });
+
+        let module = syringe.inject(payload_path).unwrap();
+        syringe.eject(module).unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            ["before_inject", "after_inject", "before_eject", "after_eject"]
+        );
+    }
+}
+
+process_test! {
+    fn inject_with_invalid_path_reports_failed_remote_call(
+        process: OwnedProcess,
+    ) {
+        let failed_calls = Arc::new(Mutex::new(0));
+        let mut syringe = Syringe::for_process(process);
+        let recorded = failed_calls.clone();
+        syringe.add_event_listener(move |event| {
+            if let SyringeEvent::RemoteCallFailed { .. } = event {
+                *recorded.lock().unwrap() += 1;
+            }
+        });
+
+        syringe.inject("invalid path").unwrap_err();
+        assert_eq!(*failed_calls.lock().unwrap(), 1);
+    }
+}
+
+syringe_test! {
+    fn inject_when_module_loaded_with_loaded_module_succeeds(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let syringe = Syringe::for_process(process);
+        syringe
+            .inject_when_module_loaded("kernel32.dll", payload_path, Duration::from_secs(1))
+            .unwrap();
+    }
+}
+
+syringe_test! {
+    fn inject_when_module_loaded_with_missing_module_times_out(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let syringe = Syringe::for_process(process);
+        let err = syringe
+            .inject_when_module_loaded("not_a_module.dll", payload_path, Duration::from_millis(50))
+            .unwrap_err();
+        assert!(matches!(err, InjectError::ModuleLoadTimedOut { .. }));
+    }
+}
+
 process_test! {
     fn inject_with_invalid_path_fails_with_remote_io(
         process: OwnedProcess,
@@ -32,6 +291,62 @@ process_test! {
     }
 }
 
+syringe_test! {
+    fn inject_with_missing_dependency_reports_dependency(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        // rename the kernel32 import of a copy of the payload to a module that does not exist.
+        let mut payload = std::fs::read(payload_path).unwrap();
+        let mut renamed = false;
+        for i in 0..payload.len() - 12 {
+            if payload[i..i + 12].eq_ignore_ascii_case(b"kernel32.dll") {
+                payload[i..i + 12].copy_from_slice(b"missing9.dll");
+                renamed = true;
+            }
+        }
+        assert!(renamed);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let broken_payload_path = temp_dir.path().join("broken_payload.dll");
+        std::fs::write(&broken_payload_path, payload).unwrap();
+
+        let syringe = Syringe::for_process(process);
+        let err = syringe.inject(&broken_payload_path).unwrap_err();
+        assert!(matches!(&err, InjectError::RemoteIo(io) if io.raw_os_error() == Some(126)), "{err:?}");
+
+        let options = InjectOptions::new().with_diagnose_missing_dependencies(true);
+        let err = syringe.inject_with_options(&broken_payload_path, &options).unwrap_err();
+        match err {
+            InjectError::MissingDependency(MissingDependencyError::NotFound { name, importer }) => {
+                assert_eq!(name, "missing9.dll");
+                assert_eq!(importer, broken_payload_path);
+            }
+            err => panic!("{err:?}"),
+        }
+    }
+}
+
+process_test! {
+    fn for_process_by_pid_reports_missing_and_exited_processes(
+        process: OwnedProcess,
+    ) {
+        let pid = process.pid().unwrap().get();
+        let syringe = Syringe::for_process_by_pid(pid).unwrap();
+        assert_eq!(syringe.process().pid().unwrap().get(), pid);
+
+        process.kill().unwrap();
+        process.wait_for_exit(Duration::from_secs(5)).unwrap().unwrap();
+        // the handle of the syringe keeps the process object alive.
+        let err = Syringe::for_process_by_pid(pid).unwrap_err();
+        assert!(matches!(err, OpenProcessError::Exited { pid: err_pid } if err_pid == pid), "{err:?}");
+        drop(syringe);
+        drop(process);
+
+        let err = Syringe::for_process_by_name("not_a_running_process").unwrap_err();
+        assert!(matches!(err, OpenProcessError::NoMatchingProcess { .. }), "{err:?}");
+    }
+}
+
 syringe_test! {
     fn inject_with_crashed_process_fails_with_process_inaccessible(
         process: OwnedProcess,
@@ -47,6 +362,379 @@ syringe_test! {
     }
 }
 
+syringe_test! {
+    fn inject_with_wine_compatibility_succeeds(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let mut syringe = Syringe::for_process(process);
+        assert_eq!(syringe.wine_compatibility(), dll_syringe::process::is_wine());
+        syringe.set_wine_compatibility(true);
+
+        let module = syringe.inject(payload_path).unwrap();
+        syringe.eject(module).unwrap();
+    }
+}
+
+syringe_test! {
+    fn syringe_caches_target_architecture(
+        process: OwnedProcess,
+        _payload_path: &Path,
+    ) {
+        let is_x86 = process.is_x86().unwrap();
+        let syringe = Syringe::for_process(process);
+        assert_eq!(syringe.is_target_x86().unwrap(), is_x86);
+        assert_eq!(syringe.is_target_x86().unwrap(), is_x86);
+    }
+}
+
+syringe_test! {
+    fn inject_and_eject_on_worker_thread(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let mut syringe = Syringe::for_process(process);
+        syringe.start_worker_thread().unwrap();
+        let worker_tid = syringe.worker_thread_id().unwrap();
+
+        let module = syringe.inject(payload_path).unwrap();
+        syringe.eject(module).unwrap();
+        assert_eq!(syringe.worker_thread_id(), Some(worker_tid));
+
+        assert!(syringe.stop_worker_thread().unwrap());
+        assert!(!syringe.stop_worker_thread().unwrap());
+        let module = syringe.inject(payload_path).unwrap();
+        syringe.eject(module).unwrap();
+    }
+}
+
+syringe_test! {
+    fn worker_thread_calls_time_out_and_return_full_words(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let mut syringe = Syringe::for_process(process);
+        syringe.start_worker_thread().unwrap();
+        let kernel32 = syringe.process().find_module_by_name("kernel32.dll").unwrap().unwrap();
+        let sleep = kernel32.get_procedure_address_from_exports("Sleep").unwrap().unwrap() as usize;
+        let get_module_handle_w = kernel32
+            .get_procedure_address_from_exports("GetModuleHandleW")
+            .unwrap()
+            .unwrap() as usize;
+
+        let exe = syringe
+            .process()
+            .find_module_by_path(syringe.process().path().unwrap())
+            .unwrap()
+            .unwrap();
+        let base = unsafe { syringe.call_on_worker_thread(get_module_handle_w, 0) }.unwrap();
+        assert_eq!(base, exe.handle() as u64);
+
+        syringe.set_worker_call_timeout(Some(Duration::from_millis(100)));
+        let err = unsafe { syringe.call_on_worker_thread(sleep, 2000) }.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+        let err = unsafe { syringe.call_on_worker_thread(sleep, 0) }.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+
+        // calls fall back to new threads while the worker is busy.
+        let module = syringe.inject(payload_path).unwrap();
+        syringe.eject(module).unwrap();
+
+        std::thread::sleep(Duration::from_millis(2500));
+        unsafe { syringe.call_on_worker_thread(sleep, 0) }.unwrap();
+    }
+}
+
+syringe_test! {
+    fn inject_timing_out_on_worker_thread_unloads_module(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let mut syringe = Syringe::for_process(process);
+        syringe.start_worker_thread().unwrap();
+        syringe.set_worker_call_timeout(Some(Duration::ZERO));
+
+        assert!(syringe.inject(payload_path).is_err());
+        assert!(syringe
+            .process()
+            .find_module_by_path(payload_path)
+            .unwrap()
+            .is_none());
+    }
+}
+
+syringe_test! {
+    fn inject_timing_out_on_worker_thread_releases_reference_to_loaded_module(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let syringe = Syringe::for_process(process.try_clone().unwrap());
+        let module = syringe.inject(payload_path).unwrap();
+
+        let mut timing_out_syringe = Syringe::for_process(process);
+        timing_out_syringe.start_worker_thread().unwrap();
+        timing_out_syringe.set_worker_call_timeout(Some(Duration::ZERO));
+        assert!(timing_out_syringe.inject(payload_path).is_err());
+        assert!(module.guess_is_loaded());
+
+        // the module is unloaded by releasing the reference of the first injection.
+        syringe.eject(module).unwrap();
+        assert!(!module.guess_is_loaded());
+    }
+}
+
+syringe_test! {
+    fn worker_thread_reports_status_like_return_values_as_returned(
+        process: OwnedProcess,
+        _payload_path: &Path,
+    ) {
+        let mut syringe = Syringe::for_process(process);
+        syringe.start_worker_thread().unwrap();
+
+        // mov eax, 0xC0000005; ret
+        let code: &[u8] = if syringe.is_target_x86().unwrap() {
+            &[0xB8, 0x05, 0x00, 0x00, 0xC0, 0xC2, 0x04, 0x00]
+        } else {
+            &[0xB8, 0x05, 0x00, 0x00, 0xC0, 0xC3]
+        };
+        let code = syringe.alloc_shellcode(code).unwrap();
+        let result = unsafe { code.run(std::ptr::null_mut(), syringe.remote_thread_options()) }.unwrap();
+        assert_eq!(result, RemoteThreadResult::Returned(0xC000_0005));
+    }
+}
+
+syringe_test! {
+    fn inject_with_heap_allocation_backend_succeeds(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let mut syringe = Syringe::for_process(process);
+        syringe.set_allocation_backend(RemoteAllocationBackend::ProcessHeap);
+
+        let module = syringe.inject(payload_path).unwrap();
+        syringe.eject(module).unwrap();
+    }
+}
+
+syringe_test! {
+    fn inject_with_allocation_placement_places_stubs(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let mut syringe = Syringe::for_process(process);
+        syringe.set_allocation_placement(AllocationPlacement::Below4Gb);
+
+        let module = syringe.inject(payload_path).unwrap();
+        let stubs = syringe.stubs().unwrap();
+        assert!(!stubs.is_empty());
+        assert!(stubs.iter().all(|stub| (stub.address() as u64) < 1 << 32));
+        syringe.eject(module).unwrap();
+    }
+}
+
+syringe_test! {
+    fn inject_beyond_allocation_budget_fails(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let mut syringe = Syringe::for_process(process);
+        syringe.set_allocation_budget(Some(0));
+        assert_eq!(syringe.allocation_budget(), Some(0));
+
+        let err = syringe.inject(payload_path).unwrap_err();
+        let InjectError::AllocationBudgetExceeded(err) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert_eq!(err.budget(), 0);
+        assert_eq!(syringe.committed_remote_bytes(), 0);
+
+        syringe.set_allocation_budget(None);
+        let module = syringe.inject(payload_path).unwrap();
+        assert!(syringe.committed_remote_bytes() > 0);
+        syringe.eject(module).unwrap();
+    }
+}
+
+syringe_test! {
+    fn resource_usage_reports_stubs_and_allocations(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let syringe = Syringe::for_process(process);
+        assert_eq!(syringe.resource_usage(), Default::default());
+
+        let module = syringe.inject(payload_path).unwrap();
+        let usage = syringe.resource_usage();
+        assert_eq!(usage.stubs(), 1);
+        assert_eq!(usage.thread_handles(), 0);
+        assert!(usage.live_allocations() > 0);
+        assert!(usage.live_allocation_bytes() <= usage.committed_bytes());
+        assert_eq!(usage.leaked_bytes(), 0);
+
+        syringe.eject(module).unwrap();
+        assert_eq!(syringe.resource_usage().live_allocations(), usage.live_allocations());
+    }
+}
+
+syringe_test! {
+    fn inject_into_all_injects_selected_processes(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let pid = process.pid().unwrap().get();
+        let results = Syringe::inject_into_all(&ProcessSelector::Pids(vec![pid]), payload_path);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].pid(), pid);
+        assert!(results[0].module().is_ok());
+
+        let syringe = results.into_iter().next().unwrap().into_syringe().unwrap();
+        assert!(syringe.eject_by_path(payload_path).unwrap());
+    }
+}
+
+syringe_test! {
+    fn syringe_set_reports_results_per_target(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let spawn = || -> OwnedProcess {
+            Command::new(process.path().unwrap())
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .unwrap()
+                .into()
+        };
+        let exited = spawn();
+        let other = spawn();
+        let _guard = other.try_clone().unwrap().kill_on_drop();
+        let pids = [&process, &exited, &other].map(|p| p.pid().unwrap().get());
+
+        let mut set = SyringeSet::new();
+        for target in [&process, &exited, &other] {
+            assert!(set.insert(target.try_clone().unwrap()).unwrap());
+        }
+        let name = process.base_name().unwrap().into_string().unwrap();
+        let inserted = set.insert_selected(&ProcessSelector::name(name)).unwrap();
+        assert!(inserted.iter().any(|result| result.pid() == pids[0] && matches!(result.value(), Ok(false))));
+        assert_eq!(set.len(), 3);
+
+        exited.kill().unwrap();
+        exited.wait_for_exit(Duration::from_secs(5)).unwrap().unwrap();
+
+        // a failing target does not keep the others from being injected into.
+        let results = set.inject_all(payload_path);
+        assert_eq!(results.iter().map(|result| result.pid()).collect::<Vec<_>>(), pids);
+        assert!(results[0].value().is_ok());
+        assert!(results[1].value().is_err());
+        assert!(results[2].value().is_ok());
+
+        let results = set.eject_all_by_path(payload_path);
+        assert!(matches!(results[0].value(), Ok(true)));
+        assert!(results[1].value().is_err());
+        assert!(matches!(results[2].value(), Ok(true)));
+    }
+}
+
+syringe_test! {
+    fn syringe_set_injects_and_ejects_all_targets(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let pid = process.pid().unwrap().get();
+        let mut set = SyringeSet::new();
+        assert!(set.insert(process.try_clone().unwrap()).unwrap());
+        assert!(!set.insert(process.try_clone().unwrap()).unwrap());
+        assert_eq!(set.len(), 1);
+
+        let results = set.inject_all(payload_path);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].pid(), pid);
+        assert!(results[0].value().is_ok());
+
+        let results = set.eject_all_by_path(payload_path);
+        assert!(matches!(results[0].value(), Ok(true)));
+
+        process.kill().unwrap();
+        process.wait_for_exit(Duration::from_secs(5)).unwrap().unwrap();
+        let start = std::time::Instant::now();
+        while set.remove_exited() == 0 {
+            assert!(start.elapsed() < Duration::from_secs(5), "exit was not reported");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(set.is_empty());
+    }
+}
+
+syringe_test! {
+    fn syringe_reports_target_exit(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let syringe = Syringe::for_process(process);
+        assert!(!syringe.has_target_exited());
+
+        syringe.process().kill().unwrap();
+        syringe.process().wait_for_exit(Duration::from_secs(5)).unwrap().unwrap();
+        let start = std::time::Instant::now();
+        while !syringe.has_target_exited() {
+            assert!(start.elapsed() < Duration::from_secs(5), "exit was not reported");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let err = syringe.inject(payload_path).unwrap_err();
+        assert!(matches!(err, InjectError::ProcessInaccessible), "{err:?}");
+    }
+}
+
+syringe_test! {
+    fn run_shellcode_returns_exit_code(
+        process: OwnedProcess,
+        _payload_path: &Path,
+    ) {
+        let is_x86 = process.is_x86().unwrap();
+        let syringe = Syringe::for_process(process);
+
+        // mov eax, 42; ret
+        let code: &[u8] = if is_x86 {
+            &[0xB8, 0x2A, 0x00, 0x00, 0x00, 0xC2, 0x04, 0x00]
+        } else {
+            &[0xB8, 0x2A, 0x00, 0x00, 0x00, 0xC3]
+        };
+        assert_eq!(
+            unsafe { syringe.run_shellcode(code, None) }.unwrap(),
+            RemoteThreadResult::Returned(42)
+        );
+
+        // mov eax, [parameter]; ret
+        let code: &[u8] = if is_x86 {
+            &[0x8B, 0x44, 0x24, 0x04, 0x8B, 0x00, 0xC2, 0x04, 0x00]
+        } else {
+            &[0x8B, 0x01, 0xC3]
+        };
+        let parameter = 1337u32.to_ne_bytes();
+        assert_eq!(
+            unsafe { syringe.run_shellcode(code, Some(&parameter)) }.unwrap(),
+            RemoteThreadResult::Returned(1337)
+        );
+    }
+}
+
+syringe_test! {
+    fn run_shellcode_in_exited_process_reports_os_error(
+        process: OwnedProcess,
+        _payload_path: &Path,
+    ) {
+        let syringe = Syringe::for_process(process);
+        syringe.process().kill().unwrap();
+        syringe.process().wait_for_exit(Duration::from_secs(5)).unwrap().unwrap();
+
+        let err = unsafe { syringe.run_shellcode(&[0xC3], None) }.unwrap_err();
+        assert!(err.raw_os_error().is_some(), "{err:?}");
+    }
+}
+
 mod inject_with_wrong_payload_fails_with_module_incompatible {
     use super::*;
     use dll_syringe::process::OwnedProcess;
@@ -96,3 +784,303 @@ mod inject_with_wrong_payload_fails_with_module_incompatible {
         assert!(matches!(err, InjectError::ArchitectureMismatch), "{err:?}");
     }
 }
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn debug_injector_spawn_injects_payload() {
+    use dll_syringe::{process::ProcessBuilder, DebugInjector};
+
+    let payload_path = common::build_test_payload_x64().unwrap();
+    let target_path = common::build_test_target_x64().unwrap();
+
+    let injection = DebugInjector::new(&payload_path)
+        .spawn(&ProcessBuilder::new(&target_path))
+        .unwrap();
+    let _guard = injection
+        .syringe()
+        .process()
+        .try_to_owned()
+        .unwrap()
+        .kill_on_drop();
+
+    assert!(injection.module().guess_is_loaded());
+    assert!(injection.restore_error().is_none());
+    assert!(injection.syringe().process().is_alive());
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn debug_injector_attach_injects_payload_into_running_process() {
+    use dll_syringe::{process::ProcessBuilder, DebugInjector};
+
+    let payload_path = common::build_test_payload_x64().unwrap();
+    let target_path = common::build_test_target_x64().unwrap();
+
+    let process = ProcessBuilder::new(&target_path)
+        .spawn()
+        .unwrap()
+        .into_process()
+        .kill_on_drop();
+    // let the target finish initializing, so the entry point is not patched.
+    std::thread::sleep(Duration::from_millis(500));
+
+    let injection = DebugInjector::new(&payload_path)
+        .attach(process.try_clone().unwrap())
+        .unwrap();
+
+    assert!(injection.module().guess_is_loaded());
+    assert!(injection.restore_error().is_none());
+    assert!(process.is_alive());
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn inject_into_current_process_loads_payload_locally() {
+    let payload_path = common::build_test_payload_x64().unwrap();
+    let syringe = Syringe::for_current_process();
+    assert!(syringe.process().is_current());
+
+    let module = syringe.inject(&payload_path).unwrap();
+    assert!(module.guess_is_loaded());
+    // the payload is loaded without a stub in the target process.
+    assert!(syringe.stubs().unwrap().is_empty());
+
+    syringe.eject(module).unwrap();
+    assert!(!module.guess_is_loaded());
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn inject_into_current_process_opened_by_pid_loads_payload_locally() {
+    let payload_path = common::build_test_payload_x64().unwrap();
+    let syringe = Syringe::for_process_by_pid(std::process::id()).unwrap();
+    assert!(syringe.process().is_current());
+
+    let module = syringe.inject(&payload_path).unwrap();
+    assert!(module.guess_is_loaded());
+    syringe.eject(module).unwrap();
+    assert!(!module.guess_is_loaded());
+    // neither injecting nor ejecting placed a stub in the current process.
+    assert!(syringe.stubs().unwrap().is_empty());
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn inject_into_current_process_classifies_load_errors() {
+    let payload_path = common::build_test_payload_x86().unwrap();
+    let syringe = Syringe::for_current_process();
+    let err = syringe.inject(&payload_path).unwrap_err();
+    assert!(matches!(err, InjectError::ArchitectureMismatch), "{err:?}");
+}
+
+syringe_test! {
+    fn stubs_are_listed_after_injection(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let syringe = Syringe::for_process(process);
+        assert!(syringe.stubs().unwrap().is_empty());
+        syringe.inject(payload_path).unwrap();
+
+        let stubs = syringe.stubs().unwrap();
+        let stub = stubs.iter().find(|stub| stub.kind() == StubKind::LoadLibraryW).unwrap();
+        assert!(!stub.bytes().is_empty());
+        assert!(stub.contains(stub.address()));
+        assert!(!stub.contains(stub.address() + stub.bytes().len()));
+
+        #[cfg(feature = "assembler")]
+        {
+            let disassembly = stub.disassembly();
+            assert!(disassembly.lines().last().unwrap().contains("ret"), "{disassembly}");
+            assert!(disassembly.contains(&format!("{:#010x}", stub.address())), "{disassembly}");
+        }
+    }
+}
+
+syringe_test! {
+    fn bundle_runtime_is_ejected_after_last_plugin(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let plugin_a = temp_dir.path().join("plugin_a.dll");
+        let plugin_b = temp_dir.path().join("plugin_b.dll");
+        std::fs::copy(payload_path, &plugin_a).unwrap();
+        std::fs::copy(payload_path, &plugin_b).unwrap();
+
+        let syringe = Syringe::for_process(process);
+        let bundle = PayloadBundle::new(payload_path)
+            .with_plugin(&plugin_a)
+            .with_plugin(&plugin_b);
+        let mut injected = bundle.inject(&syringe).unwrap();
+        assert_eq!(injected.plugin_count(), 2);
+        assert!(injected.runtime().is_some());
+
+        assert!(injected.eject_plugin(&plugin_a).unwrap());
+        assert!(!injected.eject_plugin(&plugin_a).unwrap());
+        assert!(syringe.process().find_module_by_path(&plugin_a).unwrap().is_none());
+        assert!(syringe.process().find_module_by_path(payload_path).unwrap().is_some());
+
+        assert!(injected.eject_plugin(&plugin_b).unwrap());
+        assert!(injected.runtime().is_none());
+        assert!(syringe.process().find_module_by_path(payload_path).unwrap().is_none());
+
+        injected.add_plugin(&plugin_a).unwrap();
+        assert!(syringe.process().find_module_by_path(payload_path).unwrap().is_some());
+        injected.eject().unwrap();
+        assert!(syringe.process().find_module_by_path(payload_path).unwrap().is_none());
+    }
+}
+
+syringe_test! {
+    fn bundle_runtime_keeps_its_name_with_randomized_names(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let plugin = temp_dir.path().join("plugin.dll");
+        std::fs::copy(payload_path, &plugin).unwrap();
+
+        let syringe = Syringe::for_process(process);
+        let options = InjectOptions::new()
+            .with_copy_to_temp(true)
+            .with_randomized_name(true)
+            .with_temp_dir(temp_dir.path().join("copies"));
+        let injected = PayloadBundle::new(payload_path)
+            .with_plugin(&plugin)
+            .with_inject_options(options)
+            .inject(&syringe)
+            .unwrap();
+
+        let runtime_path = injected.runtime().unwrap().module().path().unwrap();
+        assert_eq!(runtime_path.file_name(), payload_path.file_name());
+        let (_, plugin_module) = injected.plugins().next().unwrap();
+        assert_ne!(plugin_module.module().path().unwrap().file_name(), plugin.file_name());
+        injected.eject().unwrap();
+    }
+}
+
+syringe_test! {
+    fn syringe_with_limited_access_only_fails_operations_requiring_more(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        Syringe::for_process(process.try_clone().unwrap()).inject(payload_path).unwrap();
+
+        let pid = process.pid().unwrap().get();
+        let limited = OwnedProcess::from_pid_with_access(
+            pid,
+            PROCESS_MEMORY_READ_ACCESS | PROCESS_QUERY_INFORMATION,
+        )
+        .unwrap();
+        let syringe = Syringe::for_process(limited);
+        // the payload is already loaded, so finding it only requires access to the module list.
+        let module = syringe.find_or_inject(payload_path).unwrap();
+
+        let err = syringe.eject(module).unwrap_err();
+        let EjectError::MissingAccess(err) = err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert_ne!(err.missing() & PROCESS_CREATE_THREAD, 0);
+        assert!(matches!(
+            syringe.inject(payload_path),
+            Err(InjectError::MissingAccess(_))
+        ));
+    }
+}
+
+#[cfg(feature = "serde")]
+syringe_test! {
+    fn apply_profile_injects_payload_and_makes_post_inject_calls(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        use dll_syringe::{InjectionProfile, PostInjectCall, ProfileTarget};
+
+        let profile = InjectionProfile::new(ProfileTarget::Pids(vec![process.pid().unwrap().get()]))
+            .with_payload_x86(payload_path)
+            .with_payload_x64(payload_path)
+            .with_post_inject_call(PostInjectCall::new("double_word_raw", 21));
+        let syringe = Syringe::for_process(process);
+        let injection = syringe.apply_profile(&profile).unwrap();
+        assert_eq!(injection.call_results(), [42]);
+
+        // the injection does not keep the syringe borrowed mutably.
+        syringe.eject(injection.module()).unwrap();
+    }
+}
+
+#[cfg(feature = "serde")]
+syringe_test! {
+    fn apply_profile_fails_for_missing_post_inject_procedure(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        use dll_syringe::{error::ProfileError, InjectionProfile, PostInjectCall, ProfileTarget};
+
+        let profile = InjectionProfile::new(ProfileTarget::Pids(Vec::new()))
+            .with_payload_x86(payload_path)
+            .with_payload_x64(payload_path)
+            .with_post_inject_call(PostInjectCall::new("does_not_exist", 0));
+        let syringe = Syringe::for_process(process);
+        let result = syringe.apply_profile(&profile);
+        assert!(
+            matches!(result, Err(ProfileError::MissingProcedure { ref procedure }) if procedure == "does_not_exist"),
+            "{result:?}"
+        );
+
+        let profile = InjectionProfile::new(ProfileTarget::Pids(Vec::new()));
+        assert!(matches!(
+            syringe.apply_profile(&profile),
+            Err(ProfileError::NoPayloadForArchitecture { .. })
+        ));
+    }
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn child_injector_keeps_descendants_in_job() {
+    use dll_syringe::{
+        process::{JobObject, ProcessBuilder},
+        ChildInjector,
+    };
+
+    let payload_path = common::build_test_payload_x64().unwrap();
+    let target_path = common::build_test_target_x64().unwrap();
+
+    let job = JobObject::new().unwrap();
+    job.set_kill_on_close(true).unwrap();
+    // the root is started suspended, so it cannot spawn its child before it is assigned to the job.
+    let spawned = ProcessBuilder::new(&target_path)
+        .arg("--spawn-children=2")
+        .suspended(true)
+        .spawn()
+        .unwrap();
+    let root = spawned.process().try_clone().unwrap();
+    let _guard = root.try_clone().unwrap().kill_on_drop();
+
+    let mut injector = ChildInjector::new(root.try_clone().unwrap(), &payload_path).with_job(job.clone());
+    assert!(injector.poll().unwrap().is_empty());
+    assert!(job.contains(&root).unwrap());
+    spawned.resume().unwrap();
+
+    let mut injected = Vec::new();
+    for _ in 0..50 {
+        // a child that is still initializing is retried on the next poll.
+        if let Ok(ids) = injector.poll() {
+            injected.extend(ids);
+        }
+        if !injected.is_empty() && job.process_ids().unwrap().len() == 3 {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    assert_eq!(injected.len(), 1);
+    // the grandchild is not injected without following children recursively, but it is part of the job nonetheless.
+    assert_eq!(job.process_ids().unwrap().len(), 3);
+
+    drop(injector);
+    drop(job);
+    root.wait_for_exit(Duration::from_secs(5)).unwrap();
+    assert!(!root.is_alive());
+}
@@ -24,6 +24,39 @@ process_test! {
     }
 }
 
+process_test! {
+    fn owned_module_outlives_borrowed_process(
+        process: OwnedProcess
+    ) {
+        let path = process.path().unwrap();
+        let module = process.borrowed().wait_for_module_by_path(&path, Duration::from_secs(1)).unwrap().unwrap();
+        let owned = module.try_to_owned().unwrap();
+        drop(process);
+
+        let cloned = owned.try_clone().unwrap();
+        assert_eq!(owned.handle(), cloned.handle());
+        assert!(same_file::is_same_file(&path, cloned.borrowed().path().unwrap()).unwrap());
+        assert!(owned.into_process().is_alive());
+    }
+}
+
+process_test! {
+    fn static_addresses_translate_to_remote_addresses(
+        process: OwnedProcess
+    ) {
+        let kernel32 = process.borrowed().wait_for_module_by_name("kernel32.dll", Duration::from_secs(1)).unwrap().unwrap();
+        let preferred_base = kernel32.preferred_base().unwrap();
+        // the file of a WOW64 module has to be read from SysWOW64.
+        assert_eq!(process.is_x86().unwrap(), preferred_base <= u32::MAX as usize);
+
+        let base = kernel32.base() as usize;
+        assert_eq!(kernel32.relocation_delta().unwrap(), base.wrapping_sub(preferred_base) as isize);
+        assert_eq!(kernel32.rva_to_remote(0x1000), base + 0x1000);
+        assert_eq!(kernel32.static_to_remote(preferred_base + 0x1000).unwrap(), base + 0x1000);
+        assert!(kernel32.static_to_remote(preferred_base.wrapping_sub(1)).is_err());
+    }
+}
+
 #[cfg(feature = "syringe")]
 use dll_syringe::Syringe;
 
@@ -64,3 +97,25 @@ syringe_test! {
         assert!(!module.try_guess_is_loaded().unwrap());
     }
 }
+
+#[cfg(feature = "syringe")]
+syringe_test! {
+    fn find_module_exporting_finds_renamed_payload(
+        process: OwnedProcess,
+        payload_path: &Path,
+    ) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let renamed_path = temp_dir.path().join("renamed.dll");
+        std::fs::copy(payload_path, &renamed_path).unwrap();
+
+        let syringe = Syringe::for_process(process);
+        let module = syringe.inject(&renamed_path).unwrap();
+        let process = syringe.process();
+        let (found, address) = process.find_module_exporting("double_word_raw").unwrap().unwrap();
+        assert_eq!(found, module.module());
+        assert_eq!(address, module.get_procedure_address_from_exports("double_word_raw").unwrap().unwrap());
+        assert!(process.find_module_exporting("no_such_export").unwrap().is_none());
+
+        syringe.eject(module).unwrap();
+    }
+}
@@ -1,9 +1,17 @@
 use core::mem::zeroed;
-use dll_syringe::process::{BorrowedProcess, OwnedProcess, Process};
-use std::{ffi::CString, fs, mem, mem::size_of, time::Duration};
+use dll_syringe::{
+    error::TerminateError,
+    process::{
+        BorrowedProcess, CapabilityProbe, JobObject, ModuleListFilter, NameMatchOptions,
+        OwnedProcess, Process, ProcessSelector, ProcessWatcher,
+    },
+};
+use std::{env, ffi::CString, fs, mem, mem::size_of, path::PathBuf, time::Duration};
 use winapi::um::{
-    libloaderapi::{GetProcAddress, LoadLibraryA},
-    winnt::OSVERSIONINFOW,
+    libloaderapi::{FreeLibrary, GetProcAddress, LoadLibraryA},
+    winnt::{
+        OSVERSIONINFOW, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_SUSPEND_RESUME, SYNCHRONIZE,
+    },
 };
 
 #[allow(unused)]
@@ -17,6 +25,46 @@ process_test! {
     }
 }
 
+process_test! {
+    fn module_iter_matches_modules(
+        process: OwnedProcess
+    ) {
+        let modules = process.modules().unwrap();
+        let iter = process.module_iter().unwrap();
+        assert_eq!(iter.len(), modules.len());
+        for (module, expected) in iter.zip(&modules) {
+            assert_eq!(module.handle(), expected.handle());
+            assert_eq!(module.path().unwrap(), expected.path().unwrap());
+        }
+    }
+}
+
+process_test! {
+    fn capabilities_allow_injecting_into_test_target(
+        process: OwnedProcess
+    ) {
+        let capabilities = process.capabilities();
+        assert_eq!(capabilities.is_target_x64(), Some(process.is_x64().unwrap()));
+        assert_eq!(capabilities.is_host_x64(), cfg!(target_arch = "x86_64"));
+        assert!(!capabilities.requires_debug_privilege());
+        assert!(capabilities.protection_level().is_none());
+        assert!(capabilities.can_inject(), "{:?}", capabilities.blockers());
+    }
+}
+
+process_test! {
+    fn capabilities_report_failed_probes_as_unavailable(
+        process: OwnedProcess
+    ) {
+        // a handle without any access rights can not be queried for anything.
+        let process = process.duplicate_with_access(SYNCHRONIZE).unwrap();
+        let capabilities = process.capabilities();
+        assert_eq!(capabilities.is_target_x64(), None);
+        assert!(capabilities.unavailable().contains(&CapabilityProbe::Bitness), "{:?}", capabilities.unavailable());
+        assert!(capabilities.can_inject());
+    }
+}
+
 process_test! {
     fn list_module_handles_on_running_succeeds(
         process: OwnedProcess
@@ -121,6 +169,135 @@ process_test! {
     }
 }
 
+process_test! {
+    fn wait_for_by_name_finds_running(
+        process: OwnedProcess
+    ) {
+        let found = OwnedProcess::wait_for_by_name("test_target", Duration::from_secs(1)).unwrap();
+        assert_eq!(found.base_name().unwrap(), process.base_name().unwrap());
+        assert!(OwnedProcess::wait_for_by_name("does_not_exist.exe", Duration::from_millis(100)).is_none());
+    }
+}
+
+process_test! {
+    fn wait_for_exit_returns_exit_code(
+        process: OwnedProcess
+    ) {
+        assert_eq!(process.wait_for_exit(Duration::from_millis(10)).unwrap(), None);
+        process.kill_with_exit_code(42).unwrap();
+        assert_eq!(process.wait_for_exit(Duration::from_secs(5)).unwrap(), Some(42));
+    }
+}
+
+process_test! {
+    fn terminate_and_wait_returns_exit_code(
+        process: OwnedProcess
+    ) {
+        assert_eq!(process.terminate_and_wait(42, Duration::from_secs(5)).unwrap(), 42);
+        // terminating an exited process succeeds without changing its exit code.
+        assert_eq!(process.terminate_and_wait(7, Duration::from_secs(5)).unwrap(), 42);
+    }
+}
+
+process_test! {
+    fn terminate_without_access_fails_with_access_denied(
+        process: OwnedProcess
+    ) {
+        let limited = OwnedProcess::from_pid_with_access(process.pid().unwrap().get(), SYNCHRONIZE | PROCESS_QUERY_LIMITED_INFORMATION).unwrap();
+        let err = limited.terminate(1).unwrap_err();
+        assert!(matches!(err, TerminateError::AccessDenied), "{err:?}");
+        assert!(process.is_alive());
+    }
+}
+
+process_test! {
+    fn on_exit_calls_callback_after_exit(
+        process: OwnedProcess
+    ) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let watch = process.on_exit(move || sender.send(()).unwrap()).unwrap();
+        assert!(!watch.has_exited());
+        assert!(receiver.try_recv().is_err());
+
+        process.kill().unwrap();
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(watch.has_exited());
+    }
+}
+
+process_test! {
+    fn on_exit_watch_can_be_dropped_in_callback(
+        process: OwnedProcess
+    ) {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let slot = std::sync::Arc::new(std::sync::Mutex::new(None));
+        let callback_slot = slot.clone();
+        let watch = process.on_exit(move || {
+            drop(callback_slot.lock().unwrap().take());
+            sender.send(()).unwrap();
+        }).unwrap();
+        *slot.lock().unwrap() = Some(watch);
+
+        process.kill().unwrap();
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(slot.lock().unwrap().is_none());
+    }
+}
+
+process_test! {
+    fn on_exit_catches_panicking_callback(
+        process: OwnedProcess
+    ) {
+        let watch = process.on_exit(|| panic!("exit callback")).unwrap();
+
+        process.kill().unwrap();
+        while !watch.has_exited() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        drop(watch);
+    }
+}
+
+process_test! {
+    fn process_id_does_not_match_after_exit(
+        process: OwnedProcess
+    ) {
+        let id = process.id().unwrap();
+        assert_eq!(id.pid(), process.pid().unwrap().get());
+        assert!(id.matches(&process).unwrap());
+        assert!(id.open().unwrap().is_alive());
+        assert!(!id.matches(&BorrowedProcess::current()).unwrap());
+
+        process.kill().unwrap();
+        process.wait_for_exit(Duration::from_secs(5)).unwrap().unwrap();
+        // the handle keeps the exited process object around, so it still matches.
+        assert!(id.matches(&process).unwrap());
+        assert_eq!(id.open().unwrap_err().kind(), std::io::ErrorKind::NotFound);
+
+        drop(process);
+        assert_eq!(id.open().unwrap_err().kind(), std::io::ErrorKind::NotFound);
+    }
+}
+
+process_test! {
+    fn capture_thread_stack_reads_stack_of_remote_thread(
+        process: OwnedProcess
+    ) {
+        let thread = process.main_thread().unwrap().unwrap();
+        let stack = process.thread_stack_range(thread.tid()).unwrap();
+        assert!(!stack.is_empty());
+        assert_ne!(process.thread_teb(thread.tid()).unwrap(), 0);
+
+        let snapshot = process.capture_thread_stack(thread.tid(), 0x100).unwrap();
+        assert_eq!(snapshot.tid(), thread.tid());
+        assert_eq!(snapshot.range().end, stack.end);
+        assert_eq!(snapshot.bytes().len(), 0x100.min(stack.len()));
+
+        let current = unsafe { winapi::um::processthreadsapi::GetCurrentThreadId() };
+        assert!(process.thread_teb(current).is_err());
+    }
+}
+
 #[test]
 fn current_process_is_current() {
     let process = BorrowedProcess::current();
@@ -138,6 +315,141 @@ fn remote_process_is_not_current() {
     assert!(!process_a.is_current() || !process_b.is_current());
 }
 
+#[test]
+fn iter_contains_current_process() {
+    let current_pid = BorrowedProcess::current().pid().unwrap().get();
+    let entry = OwnedProcess::iter()
+        .unwrap()
+        .find(|entry| entry.pid() == current_pid)
+        .unwrap();
+    assert!(entry.thread_count() > 0);
+    assert!(entry.open().unwrap().is_current());
+}
+
+#[test]
+fn find_first_by_name_with_matches_options() {
+    let current = BorrowedProcess::current();
+    let path = current.path().unwrap();
+    let stem = path.file_stem().unwrap().to_str().unwrap().to_uppercase();
+
+    let options = NameMatchOptions::new()
+        .with_exact(true)
+        .with_case_sensitive(false)
+        .with_optional_extension(true);
+    let process = OwnedProcess::find_first_by_name_with(&stem, &options).unwrap();
+    assert!(process
+        .base_name()
+        .unwrap()
+        .eq_ignore_ascii_case(path.file_name().unwrap()));
+    assert!(
+        OwnedProcess::find_first_by_name_with(&stem, &options.with_case_sensitive(true)).is_none()
+    );
+
+    let options = NameMatchOptions::new()
+        .with_exact(true)
+        .with_full_path(true);
+    let processes = OwnedProcess::find_all_by_name_with(path.to_str().unwrap(), &options);
+    assert!(processes.iter().any(|process| process.is_current()));
+}
+
+#[test]
+fn find_by_selector_matches_patterns() {
+    let current = BorrowedProcess::current();
+    let name = current.base_name().unwrap().into_string().unwrap();
+    let (prefix, _) = name.split_at(3);
+
+    let selector = ProcessSelector::glob(format!("{}*.EXE", prefix.to_uppercase()));
+    let processes = OwnedProcess::find_all_by_selector(&selector);
+    assert!(processes.iter().any(|process| process.is_current()));
+    assert!(OwnedProcess::find_first_by_selector(&ProcessSelector::glob("?")).is_none());
+
+    let selector = ProcessSelector::Pids(vec![current.pid().unwrap().get()]);
+    let process = OwnedProcess::wait_for_by_selector(&selector, Duration::from_secs(1)).unwrap();
+    assert!(process.is_current());
+
+    #[cfg(feature = "regex")]
+    {
+        let selector = ProcessSelector::regex(&format!("(?i)^{}.*\\.exe$", regex::escape(prefix))).unwrap();
+        let processes = OwnedProcess::find_all_by_selector(&selector);
+        assert!(processes.iter().any(|process| process.is_current()));
+    }
+}
+
+process_test! {
+    fn process_watcher_reports_started_processes(
+        process: OwnedProcess
+    ) {
+        let name = process.base_name().unwrap().into_string().unwrap();
+        let mut watcher = ProcessWatcher::new(ProcessSelector::name(&name)).unwrap();
+        assert!(watcher.poll().unwrap().is_empty());
+
+        let started: OwnedProcess = Command::new(process.path().unwrap())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap()
+            .into();
+        let _guard = started.try_clone().unwrap().kill_on_drop();
+
+        let reported = watcher.wait_for_next(Duration::from_secs(5)).unwrap().unwrap();
+        assert_eq!(reported.pid().unwrap(), started.pid().unwrap());
+        assert!(watcher.poll().unwrap().is_empty());
+
+        let mut watcher = ProcessWatcher::including_running(ProcessSelector::name(name));
+        let reported = watcher.poll().unwrap();
+        assert!(reported.iter().any(|p| p.pid().unwrap() == process.pid().unwrap()));
+        assert!(reported.iter().any(|p| p.pid().unwrap() == started.pid().unwrap()));
+    }
+}
+
+process_test! {
+    fn process_watcher_assigns_started_processes_to_job(
+        process: OwnedProcess
+    ) {
+        let name = process.base_name().unwrap().into_string().unwrap();
+        let job = JobObject::new().unwrap();
+        job.set_kill_on_close(true).unwrap();
+        let mut watcher = ProcessWatcher::new(ProcessSelector::name(name)).unwrap().with_job(job.clone());
+
+        let started: OwnedProcess = Command::new(process.path().unwrap())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap()
+            .into();
+        let _guard = started.try_clone().unwrap().kill_on_drop();
+
+        let reported = watcher.wait_for_next(Duration::from_secs(5)).unwrap().unwrap();
+        assert_eq!(reported.pid().unwrap(), started.pid().unwrap());
+        assert!(job.contains(&started).unwrap());
+        assert!(!job.contains(&process).unwrap());
+
+        let started_pid = started.pid().unwrap().get();
+        let selector = ProcessSelector::filter(move |entry| entry.pid() == started_pid);
+        let other_job = JobObject::new().unwrap();
+        let waited = OwnedProcess::wait_for_by_selector_in_job(&selector, Duration::from_secs(1), &other_job).unwrap();
+        assert_eq!(waited.pid().unwrap(), started.pid().unwrap());
+        assert!(other_job.contains(&started).unwrap());
+
+        drop(watcher);
+        drop(job);
+        started.wait_for_exit(Duration::from_secs(5)).unwrap();
+        assert!(!started.is_alive());
+    }
+}
+
+#[test]
+fn find_first_where_matches_predicate() {
+    let current_pid = BorrowedProcess::current().pid().unwrap().get();
+    let process = OwnedProcess::find_first_where(|entry| entry.pid() == current_pid).unwrap();
+    assert!(process.is_current());
+
+    let children = OwnedProcess::find_all_where(|entry| entry.parent_pid() == current_pid);
+    assert!(children.iter().all(|child| !child.is_current()));
+}
+
 #[test]
 fn current_pseudo_process_eq_current_process() {
     let pseudo = BorrowedProcess::current();
@@ -149,6 +461,173 @@ fn current_pseudo_process_eq_current_process() {
     assert_eq!(pseudo, normal.try_clone().unwrap());
 }
 
+#[test]
+#[cfg(feature = "process-memory")]
+fn read_c_string_reads_until_terminator() {
+    let process = BorrowedProcess::current();
+    let ansi = b"kernel32\0ignored\0";
+    let address = ansi.as_ptr() as usize;
+    assert_eq!(process.read_c_string(address, 64).unwrap(), "kernel32");
+    assert_eq!(process.read_c_string_bytes(address, 64).unwrap(), b"kernel32");
+    assert!(process.read_c_string(address, 4).is_err());
+
+    let wide = "kernel32\0".encode_utf16().collect::<Vec<_>>();
+    assert_eq!(
+        process.read_wide_c_string(wide.as_ptr() as usize, 64).unwrap(),
+        "kernel32"
+    );
+}
+
+#[test]
+#[cfg(feature = "process-memory")]
+fn resolve_pointer_chain_follows_pointers() {
+    let value = 42u32;
+    let inner = [0usize, &value as *const u32 as usize];
+    let outer = inner.as_ptr() as usize;
+    let base = &outer as *const usize as usize;
+
+    let process = BorrowedProcess::current();
+    let resolved = process
+        .resolve_pointer_chain(base, &[size_of::<usize>(), 0])
+        .unwrap();
+    assert_eq!(resolved, &value as *const u32 as usize);
+
+    let err = process.resolve_pointer_chain(base, &[0, 0]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[cfg(feature = "process-memory")]
+process_test! {
+    fn memory_map_only_reports_address_space_usable_by_target(
+        process: OwnedProcess
+    ) {
+        let map = process.memory_map().unwrap();
+        let block = map.largest_free_block().unwrap();
+        assert!(map.can_fit(block.size()));
+        assert!(!map.can_fit(block.size() + 1));
+        if process.is_x86().unwrap() {
+            assert!(block.base() as u64 + block.size() as u64 <= 1 << 32);
+            assert!(map.free_bytes() as u64 <= 1 << 32);
+        }
+    }
+}
+
+#[cfg(feature = "process-memory")]
+process_test! {
+    fn chunked_read_write_reports_progress_in_remote(
+        process: OwnedProcess
+    ) {
+        use dll_syringe::process::memory::ProcessMemoryBuffer;
+
+        // spans multiple chunks of the remote read and write loops.
+        let len = 3 * 1024 * 1024 + 3;
+        let buffer = ProcessMemoryBuffer::allocate_data(process.borrowed(), len).unwrap();
+        let data = (0..len).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+
+        let mut written = Vec::new();
+        buffer.write_with_progress(0, &data, |done| written.push(done)).unwrap();
+        assert!(written.len() > 1);
+        assert!(written.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(written.last(), Some(&len));
+
+        let mut read_back = vec![0u8; len];
+        let mut read = Vec::new();
+        buffer.read_with_progress(0, &mut read_back, |done| read.push(done)).unwrap();
+        assert_eq!(read.last(), Some(&len));
+        assert_eq!(read_back, data);
+
+        assert_eq!(buffer.read_vec(len - 3, 3).unwrap(), &data[len - 3..]);
+    }
+}
+
+#[cfg(feature = "process-memory")]
+process_test! {
+    fn copy_to_copies_between_remote_processes(
+        process: OwnedProcess
+    ) {
+        use dll_syringe::process::memory::ProcessMemoryBuffer;
+
+        let other: OwnedProcess = Command::new(process.path().unwrap())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap()
+            .into();
+        let _guard = other.try_clone().unwrap().kill_on_drop();
+
+        // spans multiple chunks of the bounded local buffer.
+        let len = 2 * 1024 * 1024 + 5;
+        let data = (0..len).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let source = ProcessMemoryBuffer::allocate_data(process.borrowed(), len).unwrap();
+        source.write(0, &data).unwrap();
+        let mut target = ProcessMemoryBuffer::allocate_data(other.borrowed(), len).unwrap();
+
+        source.copy_to(&mut target).unwrap();
+        assert_eq!(target.read_vec(0, len).unwrap(), data);
+
+        // copying back into the current process works the same way.
+        let mut local = ProcessMemoryBuffer::allocate_data(BorrowedProcess::current(), len).unwrap();
+        target.copy_to(&mut local).unwrap();
+        assert_eq!(local.read_vec(0, len).unwrap(), data);
+    }
+}
+
+#[cfg(feature = "process-memory")]
+process_test! {
+    fn dump_all_writes_minidump(
+        process: OwnedProcess
+    ) {
+        let mut dump = Vec::new();
+        let len = process
+            .dump_all(dll_syringe::process::memory::DumpFormat::Minidump, &mut dump)
+            .unwrap();
+        assert_eq!(len, dump.len() as u64);
+        assert!(dump.starts_with(b"MDMP"));
+    }
+}
+
+#[cfg(feature = "process-memory")]
+process_test! {
+    fn write_watcher_reports_remote_writes(
+        process: OwnedProcess
+    ) {
+        use dll_syringe::{
+            function::RawFunctionPtr,
+            process::memory::{ProcessMemoryBuffer, WriteWatcher},
+        };
+
+        let buffer = ProcessMemoryBuffer::allocate_data_page(process.borrowed()).unwrap();
+        let kernel32 = process.find_module_by_name("kernel32.dll").unwrap().unwrap();
+        // GetSystemTimeAsFileTime takes a single pointer, so it can be used as a thread entry point that writes to it.
+        let write_fn = kernel32
+            .get_procedure_address_from_exports("GetSystemTimeAsFileTime")
+            .unwrap()
+            .unwrap();
+
+        let mut watcher = WriteWatcher::attach(process.borrowed()).unwrap();
+        let target = buffer.as_ptr() as usize;
+        watcher.watch(target..target + 8).unwrap();
+
+        let _thread = process
+            .start_remote_thread(
+                unsafe {
+                    mem::transmute::<RawFunctionPtr, unsafe extern "system" fn(*mut u8) -> u32>(
+                        write_fn,
+                    )
+                },
+                buffer.as_ptr(),
+            )
+            .unwrap();
+        let access = watcher
+            .wait_for_write(Some(Duration::from_secs(10)))
+            .unwrap()
+            .unwrap();
+        assert!((target..target + 8).contains(&access.address()));
+        drop(watcher);
+    }
+}
+
 fn is_running_under_wine() -> bool {
     unsafe {
         let ntdll = CString::new("ntdll.dll").unwrap();
@@ -177,3 +656,381 @@ fn is_older_than_windows_10() -> bool {
         os_info.dwMajorVersion < 10
     }
 }
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn spawn_suspended_can_be_resumed() {
+    let target = common::build_test_target_x64().unwrap();
+    let spawned = OwnedProcess::spawn(&target)
+        .suspended(true)
+        .spawn()
+        .unwrap();
+    assert!(spawned.process().is_alive());
+    spawned.resume().unwrap();
+    spawned.process().wait_for_module_by_name("kernel32.dll", Duration::from_secs(1)).unwrap();
+    spawned.process().kill().unwrap();
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn children_contains_spawned_child() {
+    let current = BorrowedProcess::current();
+    let target = common::build_test_target_x64().unwrap();
+    let child = OwnedProcess::from_child(std::process::Command::new(target).spawn().unwrap());
+    let child_id = child.id().unwrap();
+    let children = current.children().unwrap();
+    assert!(children.iter().any(|c| child_id.matches(c).unwrap()));
+    child.kill().unwrap();
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn command_line_contains_arguments() {
+    let target = common::build_test_target_x64().unwrap();
+    let process = OwnedProcess::spawn(&target)
+        .arg("--instance=42")
+        .spawn()
+        .unwrap()
+        .into_process();
+    process.wait_for_module_by_name("kernel32.dll", Duration::from_secs(1)).unwrap();
+    let command_line = process.command_line().unwrap();
+    assert!(command_line.to_string_lossy().ends_with("--instance=42"));
+    assert!(!process.environment().unwrap().is_empty());
+    process.kill().unwrap();
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn current_dir_is_read_from_target() {
+    let target = common::build_test_target_x64().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let process = OwnedProcess::spawn(&target)
+        .current_dir(dir.path())
+        .spawn()
+        .unwrap()
+        .into_process();
+    process
+        .wait_for_module_by_name("kernel32.dll", Duration::from_secs(1))
+        .unwrap();
+    let current_dir = process.current_dir().unwrap();
+    process.kill().unwrap();
+    assert!(same_file::is_same_file(current_dir, dir.path()).unwrap());
+}
+
+process_test! {
+    fn spawned_child_shares_security_context(
+        process: OwnedProcess
+    ) {
+        let current = BorrowedProcess::current();
+        assert_eq!(process.session_id().unwrap(), current.session_id().unwrap());
+        assert_eq!(process.integrity_level().unwrap(), current.integrity_level().unwrap());
+        assert_eq!(process.is_elevated().unwrap(), current.is_elevated().unwrap());
+    }
+}
+
+process_test! {
+    fn from_pid_with_query_access_allows_queries(
+        process: OwnedProcess
+    ) {
+        let pid = process.pid().unwrap().get();
+        let limited = OwnedProcess::from_pid_with_access(pid, dll_syringe::process::PROCESS_QUERY_ACCESS).unwrap();
+        assert!(limited.is_alive());
+        assert_eq!(limited.path().unwrap(), process.path().unwrap());
+        assert!(limited.modules().is_err());
+    }
+}
+
+process_test! {
+    fn process_round_trips_through_handles(
+        process: OwnedProcess
+    ) {
+        use std::os::windows::io::{AsHandle, OwnedHandle};
+
+        let borrowed = BorrowedProcess::try_from(process.as_handle()).unwrap();
+        assert_eq!(borrowed, process);
+
+        let pid = process.pid().unwrap();
+        let handle = OwnedHandle::from(process);
+        let process = OwnedProcess::try_from(handle).unwrap();
+        assert_eq!(process.pid().unwrap(), pid);
+    }
+}
+
+process_test! {
+    fn duplicate_with_access_limits_rights(
+        process: OwnedProcess
+    ) {
+        let limited = process.duplicate_with_access(dll_syringe::process::PROCESS_QUERY_ACCESS).unwrap();
+        assert_eq!(limited, process);
+        assert!(limited.is_alive());
+        assert!(limited.modules().is_err());
+    }
+}
+
+process_test! {
+    fn translate_path_resolves_to_same_file(
+        process: OwnedProcess
+    ) {
+        let kernel32 = BorrowedProcess::current().find_module_by_name("kernel32").unwrap().unwrap().path().unwrap();
+        let translated = process.translate_path(&kernel32).unwrap();
+        if process.is_x86().unwrap() == BorrowedProcess::current().is_x86().unwrap() {
+            assert_eq!(translated, kernel32);
+        } else {
+            assert!(translated.to_string_lossy().to_lowercase().contains("sysnative"));
+        }
+        assert_eq!(process.translate_path("payload.dll").unwrap(), std::path::Path::new("payload.dll"));
+    }
+}
+
+process_test! {
+    fn suspend_and_resume_succeed(
+        process: OwnedProcess
+    ) {
+        process.suspend().unwrap();
+        assert!(process.is_alive());
+        process.resume().unwrap();
+        assert!(process.is_alive());
+    }
+}
+
+process_test! {
+    fn suspend_reopens_handle_without_suspend_resume_access(
+        process: OwnedProcess
+    ) {
+        let opened = OwnedProcess::from_pid(process.pid().unwrap().get()).unwrap();
+        assert_eq!(opened.granted_access().unwrap() & PROCESS_SUSPEND_RESUME, 0);
+        opened.suspend().unwrap();
+        opened.resume().unwrap();
+        assert!(opened.is_alive());
+    }
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn main_thread_of_spawned_matches() {
+    let target = common::build_test_target_x64().unwrap();
+    let spawned = OwnedProcess::spawn(&target).suspended(true).spawn().unwrap();
+    let threads = spawned.process().threads().unwrap();
+    assert!(threads.iter().any(|t| t.tid() == spawned.main_thread_id()));
+    let main_thread = spawned.process().main_thread().unwrap().unwrap();
+    assert_eq!(main_thread.tid(), spawned.main_thread_id());
+    assert_ne!(main_thread.start_address().unwrap(), 0);
+    spawned.process().kill().unwrap();
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn spawned_in_kill_on_close_job_exits_with_job() {
+    let target = common::build_test_target_x64().unwrap();
+    let job = JobObject::new().unwrap();
+    job.set_kill_on_close(true).unwrap();
+    let spawned = OwnedProcess::spawn(&target).job(&job).spawn().unwrap();
+    let process = spawned.into_process();
+
+    assert!(process.is_in_job().unwrap());
+    assert!(job.contains(&process).unwrap());
+    assert_eq!(job.process_ids().unwrap(), [process.pid().unwrap().get()]);
+    assert!(!JobObject::new().unwrap().contains(&process).unwrap());
+
+    drop(job);
+    process.wait_for_exit(Duration::from_secs(5)).unwrap();
+    assert!(!process.is_alive());
+}
+
+#[test]
+fn remote_thread_with_options_reports_thread_id() {
+    use dll_syringe::process::{
+        RemoteThreadCreationMethod, RemoteThreadOptions, RemoteThreadResult,
+    };
+
+    extern "system" fn thread_fn(_: *mut ()) -> u32 {
+        7
+    }
+
+    let process = BorrowedProcess::current();
+    let options = RemoteThreadOptions::new()
+        .with_stack_size(1024 * 1024)
+        .with_suspended(true);
+    let thread = process
+        .start_remote_thread_with_options(thread_fn, std::ptr::null_mut(), &options)
+        .unwrap();
+    assert_ne!(thread.tid(), 0);
+    assert_eq!(
+        thread.creation_method(),
+        Some(RemoteThreadCreationMethod::CreateRemoteThread)
+    );
+    assert_eq!(thread.resume().unwrap(), 1);
+
+    let result = process
+        .run_remote_thread_with_options(thread_fn, std::ptr::null_mut(), &options)
+        .unwrap();
+    assert_eq!(result, RemoteThreadResult::Returned(7));
+}
+
+#[test]
+fn remote_thread_with_security_descriptor_runs() {
+    use dll_syringe::process::{RemoteThreadOptions, RemoteThreadResult, SecurityDescriptor};
+
+    extern "system" fn thread_fn(_: *mut ()) -> u32 {
+        7
+    }
+
+    assert!(SecurityDescriptor::from_sddl("not a descriptor").is_err());
+    let descriptor = SecurityDescriptor::from_sddl("D:(A;;GA;;;SY)(A;;GA;;;BA)(A;;GA;;;OW)").unwrap();
+    let options = RemoteThreadOptions::new().with_security_descriptor(Some(descriptor));
+    // the options own the descriptor, so they can be moved to another thread.
+    let result = std::thread::spawn(move || {
+        BorrowedProcess::current()
+            .run_remote_thread_with_options(thread_fn, std::ptr::null_mut(), &options)
+            .unwrap()
+    })
+    .join()
+    .unwrap();
+    assert_eq!(result, RemoteThreadResult::Returned(7));
+}
+
+#[test]
+fn remote_thread_runs_with_configured_priority_and_affinity() {
+    use dll_syringe::process::{RemoteThreadOptions, RemoteThreadResult, ThreadPriority};
+    use winapi::um::processthreadsapi::{
+        GetCurrentProcessorNumber, GetCurrentThread, GetThreadPriority,
+    };
+
+    extern "system" fn priority_fn(_: *mut ()) -> u32 {
+        unsafe { GetThreadPriority(GetCurrentThread()) as u32 }
+    }
+
+    extern "system" fn processor_fn(_: *mut ()) -> u32 {
+        unsafe { GetCurrentProcessorNumber() }
+    }
+
+    let process = BorrowedProcess::current();
+    let options = RemoteThreadOptions::new().with_priority(Some(ThreadPriority::BelowNormal));
+    let result = process
+        .run_remote_thread_with_options(priority_fn, std::ptr::null_mut(), &options)
+        .unwrap();
+    assert_eq!(
+        result,
+        RemoteThreadResult::Returned(ThreadPriority::BelowNormal.to_raw() as u32)
+    );
+
+    let options = RemoteThreadOptions::new().with_affinity_mask(Some(1));
+    let result = process
+        .run_remote_thread_with_options(processor_fn, std::ptr::null_mut(), &options)
+        .unwrap();
+    assert_eq!(result, RemoteThreadResult::Returned(0));
+}
+
+#[test]
+fn remote_thread_results_distinguish_how_threads_ended() {
+    use dll_syringe::{
+        error::ExceptionCode,
+        process::{RemoteThreadOptions, RemoteThreadResult},
+    };
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    static RELEASE: AtomicBool = AtomicBool::new(false);
+
+    extern "system" fn wait_fn(_: *mut ()) -> u32 {
+        while !RELEASE.load(Ordering::SeqCst) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        u32::MAX
+    }
+
+    let process = BorrowedProcess::current();
+    let options = RemoteThreadOptions::new();
+    let result = process
+        .run_remote_thread_with_timeout(
+            wait_fn,
+            std::ptr::null_mut(),
+            &options,
+            Some(Duration::from_millis(50)),
+        )
+        .unwrap();
+    assert_eq!(result, RemoteThreadResult::StillActive);
+    assert!(result.into_result().is_err());
+    RELEASE.store(true, Ordering::SeqCst);
+
+    // values that are not a known status are returned as is.
+    let result = process
+        .run_remote_thread_with_timeout(wait_fn, std::ptr::null_mut(), &options, None)
+        .unwrap();
+    assert_eq!(result, RemoteThreadResult::Returned(u32::MAX));
+
+    assert_eq!(
+        RemoteThreadResult::from_exit_code(ExceptionCode::AccessViolation.code()),
+        RemoteThreadResult::Exception(ExceptionCode::AccessViolation)
+    );
+    // STATUS_DLL_NOT_FOUND
+    let result = RemoteThreadResult::from_exit_code(0xC000_0135);
+    assert!(matches!(result, RemoteThreadResult::Terminated(_)));
+    assert!(result.into_result().is_err());
+}
+
+#[test]
+fn module_snapshot_diff_reports_loaded_module() {
+    let process = BorrowedProcess::current();
+    let before = process.module_snapshot().unwrap();
+    assert!(before.diff(&before).is_empty());
+
+    // load a copy under a unique name, so the module cannot have been loaded before.
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("module_snapshot_test.dll");
+    let system_dir = PathBuf::from(env::var_os("SystemRoot").unwrap()).join("System32");
+    fs::copy(system_dir.join("msimg32.dll"), &path).unwrap();
+    let name = CString::new(path.to_str().unwrap()).unwrap();
+    let module = unsafe { LoadLibraryA(name.as_ptr()) };
+    assert!(!module.is_null());
+
+    let after = process.module_snapshot().unwrap();
+    let diff = before.diff(&after);
+    assert!(diff.unloaded.iter().all(|m| m.handle() != module));
+    assert!(diff.loaded.iter().any(|m| m.handle() == module));
+    assert!(after.contains_path(after.modules()[0].path()));
+
+    unsafe { FreeLibrary(module) };
+}
+
+#[test]
+fn find_module_by_path_resolves_case_and_missing_extension() {
+    let process = BorrowedProcess::current();
+    let kernel32 = process
+        .find_module_by_name("kernel32.dll")
+        .unwrap()
+        .unwrap();
+    let path = kernel32.path().unwrap();
+
+    let uppercase = path.with_extension("").to_string_lossy().to_uppercase();
+    let found = process.find_module_by_path(&uppercase).unwrap().unwrap();
+    assert_eq!(found.handle(), kernel32.handle());
+
+    let snapshot = process.module_snapshot().unwrap();
+    let found = snapshot.find_by_path(&uppercase).unwrap();
+    assert_eq!(found.handle(), kernel32.handle());
+    assert!(snapshot.find_by_path("does_not_exist.dll").is_none());
+}
+
+#[test]
+fn module_handles_with_filter_lists_native_modules() {
+    let process = BorrowedProcess::current();
+    let kernel32 = process
+        .find_module_by_name("kernel32.dll")
+        .unwrap()
+        .unwrap();
+
+    let all = process.module_handles().unwrap().collect::<Vec<_>>();
+    assert!(all.contains(&kernel32.handle()));
+
+    let native_filter = if cfg!(target_pointer_width = "64") {
+        ModuleListFilter::X64
+    } else {
+        ModuleListFilter::X86
+    };
+    let native = process
+        .module_handles_with_filter(native_filter)
+        .unwrap()
+        .collect::<Vec<_>>();
+    assert!(native.contains(&kernel32.handle()));
+    assert!(native.len() <= all.len());
+}
@@ -1,6 +1,8 @@
 #![cfg(feature = "rpc-core")]
 
-use dll_syringe::{process::Process, Syringe};
+use dll_syringe::{
+    error::PayloadConfigError, process::Process, rpc::ProcedureLookupMethod, Syringe,
+};
 
 #[allow(unused)]
 mod common;
@@ -24,6 +26,42 @@ mod core {
         }
     }
 
+    process_test! {
+        fn get_procedure_address_by_ordinal(
+            process: OwnedProcess,
+        ) {
+            let remote_syringe = Syringe::for_process(process.try_clone().unwrap());
+
+            let kernel32 = remote_syringe.process().wait_for_module_by_name("kernel32.dll", Duration::from_secs(1)).unwrap().unwrap();
+            let ordinal = kernel32
+                .exports()
+                .unwrap()
+                .into_iter()
+                .find(|export| export.name() == Some("OpenProcess"))
+                .unwrap()
+                .ordinal();
+            let open_process = remote_syringe.get_procedure_address(kernel32, "OpenProcess").unwrap();
+            assert!(open_process.is_some());
+            assert_eq!(remote_syringe.get_procedure_address_by_ordinal(kernel32, ordinal).unwrap(), open_process);
+
+            let mut syringe = Syringe::for_process(process);
+            syringe.set_procedure_lookup_method(ProcedureLookupMethod::ExportTable);
+            let kernel32 = syringe.process().find_module_by_name("kernel32.dll").unwrap().unwrap();
+            assert_eq!(syringe.get_procedure_address_by_ordinal(kernel32, ordinal).unwrap(), open_process);
+        }
+    }
+
+    process_test! {
+        fn get_procedure_address_of_name_with_nul(
+            process: OwnedProcess,
+        ) {
+            let syringe = Syringe::for_process(process);
+
+            let kernel32 = syringe.process().wait_for_module_by_name("kernel32.dll", Duration::from_secs(1)).unwrap().unwrap();
+            assert!(syringe.get_procedure_address(kernel32, "Open\0Process").is_err());
+        }
+    }
+
     syringe_test! {
         fn get_procedure_address_of_dll_main(
             process: OwnedProcess,
@@ -47,6 +85,86 @@ mod core {
             assert!(invalid.is_none());
         }
     }
+
+    syringe_test! {
+        fn get_procedure_address_is_cached(
+            process: OwnedProcess,
+            payload_path: &Path,
+        ) {
+            let syringe = Syringe::for_process(process);
+            let module = syringe.inject(payload_path).unwrap();
+
+            let add = syringe.get_procedure_address(module, "add_raw").unwrap();
+            assert!(add.is_some());
+            assert_eq!(syringe.get_procedure_address(module, "add_raw").unwrap(), add);
+            assert_eq!(syringe.get_procedure_address(module, "ProcedureThatDoesNotExist").unwrap(), None);
+
+            syringe.clear_procedure_cache();
+            assert_eq!(syringe.get_procedure_address(module, "add_raw").unwrap(), add);
+        }
+    }
+
+    syringe_test! {
+        fn get_procedures_resolves_names_in_order(
+            process: OwnedProcess,
+            payload_path: &Path,
+        ) {
+            let syringe = Syringe::for_process(process);
+            let module = syringe.inject(payload_path).unwrap();
+
+            let add = syringe.get_procedure_address(module, "add_raw").unwrap();
+            syringe.clear_procedure_cache();
+            let dll_main = syringe.get_procedure_address(module, "DllMain").unwrap();
+            assert!(add.is_some() && dll_main.is_some());
+
+            let procedures = syringe
+                .get_procedures(module, &["add_raw", "ProcedureThatDoesNotExist", "DllMain"])
+                .unwrap();
+            assert_eq!(procedures, [add, None, dll_main]);
+            assert_eq!(syringe.get_procedures(module, &[] as &[&str]).unwrap(), []);
+        }
+    }
+
+    syringe_test! {
+        fn get_procedure_address_from_export_table(
+            process: OwnedProcess,
+            payload_path: &Path,
+        ) {
+            let remote_syringe = Syringe::for_process(process.try_clone().unwrap());
+            let module = remote_syringe.inject(payload_path).unwrap();
+            let kernel32 = remote_syringe.process().find_module_by_name("kernel32.dll").unwrap().unwrap();
+            let add = remote_syringe.get_procedure_address(module, "add_raw").unwrap();
+            let open_process = remote_syringe.get_procedure_address(kernel32, "OpenProcess").unwrap();
+
+            let mut syringe = Syringe::for_process(process);
+            syringe.set_procedure_lookup_method(ProcedureLookupMethod::ExportTable);
+            let module = syringe.process().find_module_by_path(payload_path).unwrap().unwrap();
+            let kernel32 = syringe.process().find_module_by_name("kernel32.dll").unwrap().unwrap();
+            assert_eq!(syringe.get_procedure_address(module, "add_raw").unwrap(), add);
+            assert_eq!(syringe.get_procedure_address(kernel32, "OpenProcess").unwrap(), open_process);
+            assert_eq!(syringe.get_procedure_address(module, "ProcedureThatDoesNotExist").unwrap(), None);
+        }
+    }
+
+    syringe_test! {
+        fn write_and_read_payload_config(
+            process: OwnedProcess,
+            payload_path: &Path,
+        ) {
+            let syringe = Syringe::for_process(process);
+            let module = syringe.inject(payload_path).unwrap();
+
+            assert_eq!(module.read_config().unwrap(), b"");
+            module.write_config(b"some config").unwrap();
+            assert_eq!(module.read_config().unwrap(), b"some config");
+
+            let result = module.write_config(&[0; 65]);
+            assert!(
+                matches!(result, Err(PayloadConfigError::TooLarge { len: 65, capacity: 64 })),
+                "{result:?}"
+            );
+        }
+    }
 }
 
 #[cfg(feature = "rpc-payload")]
@@ -68,6 +186,38 @@ mod payload {
         }
     }
 
+    syringe_test! {
+        fn call_simple_through_injected_module(
+            process: OwnedProcess,
+            payload_path: &Path,
+        ) {
+            let syringe = Syringe::for_process(process);
+            let module = syringe.inject(payload_path).unwrap();
+
+            let remote_add = unsafe { module.get_payload_procedure::<fn(u32, u32) -> u32>("add") }.unwrap().unwrap();
+            let add_result = remote_add.call(&42, &10).unwrap();
+            assert_eq!(add_result, 52);
+        }
+    }
+
+    syringe_test! {
+        fn call_simple_after_dropping_owned_module(
+            process: OwnedProcess,
+            payload_path: &Path,
+        ) {
+            let syringe = std::rc::Rc::new(Syringe::for_process(process));
+            let module = dll_syringe::OwnedInjectedModule::new(syringe.clone(), syringe.inject(payload_path).unwrap());
+            let remote_add = unsafe { module.get_payload_procedure::<fn(u32, u32) -> u32>("add") }.unwrap().unwrap();
+            let remote_value = syringe.alloc_box(&7u32).unwrap();
+            drop(module);
+            drop(syringe);
+
+            let add_result = remote_add.call(&42, &10).unwrap();
+            assert_eq!(add_result, 52);
+            assert_eq!(remote_value.read().unwrap(), 7);
+        }
+    }
+
     syringe_test! {
         fn call_complex(
             process: OwnedProcess,
@@ -82,6 +232,23 @@ mod payload {
         }
     }
 
+    syringe_test! {
+        fn call_with_staged_args(
+            process: OwnedProcess,
+            payload_path: &Path,
+        ) {
+            let syringe = Syringe::for_process(process);
+            let module = syringe.inject(payload_path).unwrap();
+
+            let remote_sum = unsafe { syringe.get_payload_procedure::<fn(Vec<u64>) -> u64>(module, "sum") }.unwrap().unwrap();
+            let nums = remote_sum.stage_args(&(1..=1000).collect()).unwrap();
+            assert!(!nums.is_empty());
+            // the result fits into the arguments, but must not be written over them.
+            assert_eq!(remote_sum.call_staged(&nums).unwrap(), 500_500);
+            assert_eq!(remote_sum.call_staged(&nums).unwrap(), 500_500);
+        }
+    }
+
     syringe_test! {
         fn call_panic(
             process: OwnedProcess,
@@ -94,12 +261,12 @@ mod payload {
             let result = remote_does_panic.call();
             assert!(result.is_err());
             let err = result.unwrap_err();
-            assert!(matches!(err, PayloadRpcError::RemoteProcedure(_)));
-            let err_message = match err {
-                PayloadRpcError::RemoteProcedure(e) => e,
-                _ => panic!("Expected RpcError::RemoteProcedure"),
+            let (message, location) = match err {
+                PayloadRpcError::RemoteProcedurePanicked { message, location } => (message, location),
+                _ => panic!("Expected RpcError::RemoteProcedurePanicked, got {err:?}"),
             };
-            assert_eq!(err_message, String::from("Some error message"));
+            assert_eq!(message, String::from("Some error message"));
+            assert!(location.unwrap().contains("lib.rs"));
         }
     }
 }
@@ -107,7 +274,7 @@ mod payload {
 #[cfg(feature = "rpc-raw")]
 mod raw {
     pub use super::*;
-    use dll_syringe::rpc::RawRpcError;
+    use dll_syringe::{process::RemoteAllocationBackend, rpc::RawRpcError};
 
     syringe_test! {
         fn call_simple(
@@ -123,6 +290,99 @@ mod raw {
         }
     }
 
+    syringe_test! {
+        fn call_without_args_or_result(
+            process: OwnedProcess,
+            _payload_path: &Path,
+        ) {
+            let pid = process.pid().unwrap().get();
+            let syringe = Syringe::for_process(process);
+            let kernel32 = syringe.process().find_module_by_name("kernel32.dll").unwrap().unwrap();
+
+            let flush_write_buffers = unsafe { syringe.get_raw_procedure::<extern "system" fn()>(kernel32, "FlushProcessWriteBuffers") }.unwrap().unwrap();
+            flush_write_buffers.call().unwrap();
+            flush_write_buffers.call().unwrap();
+
+            let get_current_process_id = unsafe { syringe.get_raw_procedure::<extern "system" fn() -> u32>(kernel32, "GetCurrentProcessId") }.unwrap().unwrap();
+            assert_eq!(get_current_process_id.call().unwrap(), pid);
+        }
+    }
+
+    syringe_test! {
+        fn call_symbol(
+            process: OwnedProcess,
+            _payload_path: &Path,
+        ) {
+            let pid = process.pid().unwrap().get();
+            let syringe = Syringe::for_process(process);
+            let kernel32 = syringe.process().find_module_by_name("kernel32.dll").unwrap().unwrap();
+
+            let get_current_process_id = unsafe { kernel32.get::<extern "system" fn() -> u32>("GetCurrentProcessId") }.unwrap().unwrap();
+            assert_eq!(
+                Some(get_current_process_id.as_raw_ptr()),
+                kernel32.get_procedure_address_from_exports("GetCurrentProcessId").unwrap()
+            );
+            assert_eq!(get_current_process_id.to_raw_procedure(&syringe).unwrap().call().unwrap(), pid);
+        }
+    }
+
+    syringe_test! {
+        fn call_symbol_of_other_process_fails(
+            process: OwnedProcess,
+            _payload_path: &Path,
+        ) {
+            let syringe = Syringe::for_current_process();
+            let kernel32 = process.find_module_by_name("kernel32.dll").unwrap().unwrap();
+
+            let get_current_process_id = unsafe { kernel32.get::<extern "system" fn() -> u32>("GetCurrentProcessId") }.unwrap().unwrap();
+            let err = get_current_process_id.to_raw_procedure(&syringe).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        }
+    }
+
+    syringe_test! {
+        fn call_on_worker_thread(
+            process: OwnedProcess,
+            _payload_path: &Path,
+        ) {
+            let mut syringe = Syringe::for_process(process);
+            syringe.start_worker_thread().unwrap();
+            let worker_tid = syringe.worker_thread_id().unwrap();
+            let kernel32 = syringe.process().find_module_by_name("kernel32.dll").unwrap().unwrap();
+
+            let get_current_thread_id = unsafe { syringe.get_raw_procedure::<extern "system" fn() -> u32>(kernel32, "GetCurrentThreadId") }.unwrap().unwrap();
+            assert_eq!(get_current_thread_id.call().unwrap(), worker_tid);
+            assert_eq!(get_current_thread_id.call().unwrap(), worker_tid);
+
+            assert!(syringe.stop_worker_thread().unwrap());
+            assert_eq!(syringe.worker_thread_id(), None);
+            assert_ne!(get_current_thread_id.call().unwrap(), worker_tid);
+        }
+    }
+
+    syringe_test! {
+        fn call_with_out_buf(
+            process: OwnedProcess,
+            _payload_path: &Path,
+        ) {
+            let syringe = Syringe::for_process(process);
+            let kernel32 = syringe.process().find_module_by_name("kernel32.dll").unwrap().unwrap();
+
+            let get_module_file_name = unsafe { syringe.get_raw_procedure::<extern "system" fn(u32, *mut u8, usize) -> usize>(kernel32, "GetModuleFileNameA") }.unwrap().unwrap();
+            let file_name = get_module_file_name.call_with_out_buf(0, 4096).unwrap();
+            assert!(!file_name.is_truncated());
+            assert_eq!(file_name.data().len(), file_name.len());
+            assert!(file_name.data().to_ascii_lowercase().ends_with(b".exe"));
+
+            // a too small buffer receives as many characters as fit followed by a terminator, and its size is returned.
+            let prefix = get_module_file_name.call_with_out_buf(0, 4).unwrap();
+            assert_eq!(prefix.len(), 4);
+            assert!(!prefix.is_truncated());
+            assert_eq!(&prefix.data()[..3], &file_name.data()[..3]);
+            assert_eq!(prefix.data()[3], 0);
+        }
+    }
+
     syringe_test! {
         fn call_correct_order(
             process: OwnedProcess,
@@ -151,6 +411,20 @@ mod raw {
         }
     }
 
+    syringe_test! {
+        fn call_wide_result(
+            process: OwnedProcess,
+            payload_path: &Path,
+        ) {
+            let syringe = Syringe::for_process(process);
+            let module = syringe.inject(payload_path).unwrap();
+
+            let remote_mul = unsafe { syringe.get_raw_procedure::<extern "system" fn(u32, u32) -> u64>(module, "mul_wide_raw") }.unwrap().unwrap();
+            let mul_result = remote_mul.call(u32::MAX, 3).unwrap();
+            assert_eq!(mul_result, u32::MAX as u64 * 3);
+        }
+    }
+
     syringe_test! {
         fn call_many_args(
             process: OwnedProcess,
@@ -193,6 +467,33 @@ mod raw {
         }
     }
 
+    syringe_test! {
+        fn call_procedures_prepared_in_batch(
+            process: OwnedProcess,
+            payload_path: &Path,
+        ) {
+            let syringe = Syringe::for_process(process);
+            let module = syringe.inject(payload_path).unwrap();
+
+            let (remote_add, remote_sub, remote_mul) = syringe.batch_stub_writes(|syringe| {
+                let remote_add = unsafe { syringe.get_raw_procedure::<extern "system" fn(u32, u32) -> u32>(module, "add_raw") }.unwrap().unwrap();
+                let remote_sub = unsafe { syringe.get_raw_procedure::<extern "system" fn(f32, f32) -> f32>(module, "sub_float_raw") }.unwrap().unwrap();
+                let remote_mul = unsafe { syringe.get_raw_procedure::<extern "system" fn(u32, u32) -> u64>(module, "mul_wide_raw") }.unwrap().unwrap();
+                remote_add.prepare().unwrap();
+                remote_sub.prepare().unwrap();
+                remote_mul.prepare().unwrap();
+                // a call inside the batch writes the pending stubs first.
+                assert_eq!(remote_add.call(42, 10).unwrap(), 52);
+                (remote_add, remote_sub, remote_mul)
+            }).unwrap();
+
+            assert_eq!(remote_add.call(1, 2).unwrap(), 3);
+            assert_eq!(remote_sub.call(1.2, 0.2).unwrap(), 1.0);
+            assert_eq!(remote_mul.call(u32::MAX, 2).unwrap(), u64::from(u32::MAX) * 2);
+            assert!(remote_mul.stub_info().unwrap().is_some());
+        }
+    }
+
     syringe_test! {
         fn call_simple_c_call(
             process: OwnedProcess,
@@ -207,6 +508,51 @@ mod raw {
         }
     }
 
+    syringe_test! {
+        fn payload_reads_written_config(
+            process: OwnedProcess,
+            payload_path: &Path,
+        ) {
+            let syringe = Syringe::for_process(process);
+            let module = syringe.inject(payload_path).unwrap();
+            module.write_config(b"some config").unwrap();
+
+            let config_len = unsafe { module.get_raw_procedure::<extern "system" fn() -> u32>("config_len") }.unwrap().unwrap();
+            assert_eq!(config_len.call().unwrap(), 11);
+        }
+    }
+
+    syringe_test! {
+        fn payload_writes_to_attached_log_reader(
+            process: OwnedProcess,
+            payload_path: &Path,
+        ) {
+            let syringe = Syringe::for_process(process);
+            let module = syringe.inject(payload_path).unwrap();
+            let mut log = module.attach_log_reader().unwrap();
+
+            let log_value = unsafe { module.get_raw_procedure::<extern "system" fn(u32) -> u32>("log_value") }.unwrap().unwrap();
+            assert_eq!(log_value.call(7).unwrap(), 1);
+            assert_eq!(log.read_line().unwrap().as_deref(), Some("value 7"));
+        }
+    }
+
+    syringe_test! {
+        fn payload_log_handler_is_attached_on_inject(
+            process: OwnedProcess,
+            payload_path: &Path,
+        ) {
+            let (sender, receiver) = std::sync::mpsc::channel();
+            let mut syringe = Syringe::for_process(process);
+            syringe.set_payload_log_handler(move |_, line| sender.send(line.to_string()).unwrap());
+            let module = syringe.inject(payload_path).unwrap();
+
+            let log_value = unsafe { module.get_raw_procedure::<extern "system" fn(u32) -> u32>("log_value") }.unwrap().unwrap();
+            assert_eq!(log_value.call(7).unwrap(), 1);
+            assert_eq!(receiver.recv_timeout(std::time::Duration::from_secs(5)).unwrap(), "value 7");
+        }
+    }
+
     syringe_test! {
         fn call_many_args2_c_call(
             process: OwnedProcess,
@@ -249,6 +595,27 @@ mod raw {
         }
     }
 
+    syringe_test! {
+        fn heap_backed_procedures_are_dropped_cleanly_after_kill(
+            process: OwnedProcess,
+            payload_path: &Path,
+        ) {
+            let mut syringe = Syringe::for_process(process);
+            syringe.set_allocation_backend(RemoteAllocationBackend::ProcessHeap);
+            let module = syringe.inject(payload_path).unwrap();
+            let remote_add = unsafe { syringe.get_raw_procedure::<extern "C" fn(u32, u32) -> u32>(module, "add_raw_c") }.unwrap().unwrap();
+            assert_eq!(remote_add.call(42, 10).unwrap(), 52);
+
+            syringe.process().kill().unwrap();
+            syringe.process().wait_for_exit(std::time::Duration::from_secs(5)).unwrap().unwrap();
+            let add_err = remote_add.call(42, 10).unwrap_err();
+            assert!(matches!(add_err, RawRpcError::ProcessInaccessible), "{add_err:?}");
+
+            drop(remote_add);
+            assert!(syringe.has_target_exited());
+        }
+    }
+
     syringe_test! {
         fn call_crash_fails_with_access_violation(
             process: OwnedProcess,